@@ -3,7 +3,7 @@
 import { useEffect, useMemo, useState } from 'react';
 import Link from 'next/link';
 import { useRouter } from 'next/navigation';
-import { createMmMtfSweepPreset, listRuns } from '@/lib/api';
+import { createMmMtfSweepPreset, listRuns, subscribeRunUpdates } from '@/lib/api';
 import type { RunRecord } from '@/lib/types';
 
 function statusClass(status: string): string {
@@ -20,8 +20,10 @@ export default function RunsPage() {
   const [symbol, setSymbol] = useState('ETHUSDT');
   const [start, setStart] = useState('2026-01-01');
   const [end, setEnd] = useState('2026-02-10');
+  const [notify, setNotify] = useState(false);
   const [statusFilter, setStatusFilter] = useState('all');
   const [kindFilter, setKindFilter] = useState('all');
+  const [projectFilter, setProjectFilter] = useState('all');
   const [query, setQuery] = useState('');
 
   async function refresh() {
@@ -38,14 +40,29 @@ export default function RunsPage() {
 
   useEffect(() => {
     refresh();
-    const t = setInterval(refresh, 5000);
-    return () => clearInterval(t);
+    const t = setInterval(refresh, 30000);
+    const unsubscribe = subscribeRunUpdates(null, (update) => {
+      if (!update || update.kind === 'status') {
+        refresh();
+      }
+    });
+    return () => {
+      clearInterval(t);
+      unsubscribe();
+    };
   }, []);
 
   async function onCreatePreset() {
     setSubmitting(true);
     try {
-      const run = await createMmMtfSweepPreset({ symbol, start, end, maker_fee_bps_list: '10' });
+      const run = await createMmMtfSweepPreset({
+        symbol,
+        start,
+        end,
+        maker_fee_bps_list: '10',
+        notify,
+        project: projectFilter === 'all' ? undefined : projectFilter
+      });
       await refresh();
       router.push(`/runs/${run.id}`);
     } catch (e) {
@@ -66,11 +83,18 @@ export default function RunsPage() {
     return Array.from(set).sort();
   }, [runs]);
 
+  const knownProjects = useMemo(() => {
+    const set = new Set<string>();
+    for (const r of runs) set.add(r.project ?? 'default');
+    return Array.from(set).sort();
+  }, [runs]);
+
   const filteredRuns = useMemo(() => {
     const q = query.trim().toLowerCase();
     return runs.filter((r) => {
       if (statusFilter !== 'all' && r.status !== statusFilter) return false;
       if (kindFilter !== 'all' && r.kind !== kindFilter) return false;
+      if (projectFilter !== 'all' && (r.project ?? 'default') !== projectFilter) return false;
       if (!q) return true;
       return (
         r.name.toLowerCase().includes(q) ||
@@ -78,7 +102,7 @@ export default function RunsPage() {
         r.kind.toLowerCase().includes(q)
       );
     });
-  }, [runs, statusFilter, kindFilter, query]);
+  }, [runs, statusFilter, kindFilter, projectFilter, query]);
 
   return (
     <section className="stack">
@@ -99,6 +123,10 @@ export default function RunsPage() {
             End
             <input value={end} onChange={(e) => setEnd(e.target.value)} />
           </label>
+          <label>
+            Notify when done
+            <input type="checkbox" checked={notify} onChange={(e) => setNotify(e.target.checked)} />
+          </label>
         </div>
 
         <div className="row gap">
@@ -120,6 +148,7 @@ export default function RunsPage() {
               <option value="running">running</option>
               <option value="completed">completed</option>
               <option value="failed">failed</option>
+              <option value="cancelled">cancelled</option>
             </select>
           </label>
           <label>
@@ -133,6 +162,17 @@ export default function RunsPage() {
               ))}
             </select>
           </label>
+          <label>
+            Project
+            <select value={projectFilter} onChange={(e) => setProjectFilter(e.target.value)}>
+              <option value="all">all</option>
+              {knownProjects.map((p) => (
+                <option key={p} value={p}>
+                  {p}
+                </option>
+              ))}
+            </select>
+          </label>
           <label>
             Search
             <input
@@ -153,6 +193,7 @@ export default function RunsPage() {
             <tr>
               <th>Created</th>
               <th>Name</th>
+              <th>Project</th>
               <th>Kind</th>
               <th>Status</th>
               <th>Action</th>
@@ -163,6 +204,7 @@ export default function RunsPage() {
               <tr key={run.id}>
                 <td className="tiny">{new Date(run.created_at).toLocaleString()}</td>
                 <td>{run.name}</td>
+                <td className="tiny">{run.project ?? 'default'}</td>
                 <td className="mono tiny">{run.kind}</td>
                 <td>
                   <span className={statusClass(run.status)}>{run.status}</span>
@@ -174,7 +216,7 @@ export default function RunsPage() {
             ))}
             {!filteredRuns.length ? (
               <tr>
-                <td colSpan={5} className="muted tiny">
+                <td colSpan={6} className="muted tiny">
                   No runs match the current filters
                 </td>
               </tr>
@@ -2,8 +2,16 @@
 
 import { useEffect, useMemo, useState } from 'react';
 import Link from 'next/link';
-import { getRun, getRunArtifacts, getRunEvents, getRunMetrics } from '@/lib/api';
+import {
+  artifactDownloadUrl,
+  getRun,
+  getRunArtifacts,
+  getRunEvents,
+  getRunMetrics,
+  subscribeRunUpdates
+} from '@/lib/api';
 import type {
+  CoreMetrics,
   EquityPoint,
   RunArtifact,
   RunEventRecord,
@@ -28,13 +36,34 @@ function normalizeTs(ts: number): number {
   return ts < 10_000_000_000 ? ts * 1000 : ts;
 }
 
-function extractMetric(metrics: RunMetricsResponse | null, key: string): number | null {
+function extractMetric(metrics: RunMetricsResponse | null, key: keyof CoreMetrics): number | null {
   if (!metrics) return null;
-  return toNumber(metrics.payload[key]);
+  return toNumber(metrics.payload?.core?.[key]);
+}
+
+function formatProgress(metrics: RunMetricsResponse | null): string {
+  const raw = metrics?.payload?.progress;
+  if (!raw || typeof raw !== 'object') return '-';
+  const p = raw as Record<string, unknown>;
+  const percent = toNumber(p.percent);
+  if (percent === null) return '-';
+  const eta = toNumber(p.eta_s);
+  const counts = `${toNumber(p.done) ?? '?'}/${toNumber(p.total) ?? '?'} ${p.unit ?? ''}`.trim();
+  return `${percent.toFixed(1)}% (${counts}${eta !== null && percent < 100 ? `, eta ${Math.round(eta)}s` : ''})`;
+}
+
+function formatArtifactMeta(a: RunArtifact): string {
+  const parts: string[] = [];
+  if (a.size_bytes !== null) {
+    parts.push(a.size_bytes >= 1024 * 1024 ? `${(a.size_bytes / 1024 / 1024).toFixed(1)} MB` : `${(a.size_bytes / 1024).toFixed(1)} KB`);
+  }
+  if (a.row_count !== null) parts.push(`${a.row_count} rows`);
+  if (a.checksum) parts.push(a.checksum.slice(0, 19));
+  return parts.join(' · ');
 }
 
 function parseEquityPoints(metrics: RunMetricsResponse | null): EquityPoint[] {
-  const raw = metrics?.payload?.chart_equity;
+  const raw = metrics?.payload?.charts?.equity;
   if (!Array.isArray(raw)) return [];
   const out: EquityPoint[] = [];
   for (const item of raw) {
@@ -49,7 +78,7 @@ function parseEquityPoints(metrics: RunMetricsResponse | null): EquityPoint[] {
 }
 
 function parseTradePoints(metrics: RunMetricsResponse | null): TradePoint[] {
-  const raw = metrics?.payload?.chart_trades;
+  const raw = metrics?.payload?.charts?.trades;
   if (!Array.isArray(raw)) return [];
   const out: TradePoint[] = [];
   for (const item of raw) {
@@ -131,8 +160,13 @@ export default function RunDetailsPage({ params }: { params: { id: string } }) {
 
   useEffect(() => {
     refresh();
-    const t = setInterval(refresh, isActive ? 1000 : 4000);
-    return () => clearInterval(t);
+    // обновления приходят по SSE; опрос — только подстраховка при обрыве соединения
+    const t = setInterval(refresh, isActive ? 10000 : 30000);
+    const unsubscribe = subscribeRunUpdates(runId, () => refresh());
+    return () => {
+      clearInterval(t);
+      unsubscribe();
+    };
   }, [runId, isActive]);
 
   const progressChart = useMemo(
@@ -163,16 +197,35 @@ export default function RunDetailsPage({ params }: { params: { id: string } }) {
           </div>
           <div>
             <div className="label">Status</div>
-            <div>{run?.status || '-'}</div>
+            <div>
+              {run?.status || '-'}
+              {run?.archived_at ? <span className="tiny muted"> (archived)</span> : null}
+            </div>
           </div>
           <div>
             <div className="label">Live Updates</div>
             <div>{isActive ? 'on (1s)' : 'idle (4s)'}</div>
           </div>
+          <div>
+            <div className="label">Progress</div>
+            <div>{formatProgress(metrics)}</div>
+          </div>
           <div>
             <div className="label">Kind</div>
             <div className="mono tiny">{run?.kind || '-'}</div>
           </div>
+          <div>
+            <div className="label">Depends On</div>
+            <div className="row gap mono tiny">
+              {run?.depends_on?.length
+                ? run.depends_on.map((id) => (
+                    <Link key={id} href={`/runs/${id}`}>
+                      {id.slice(0, 8)}
+                    </Link>
+                  ))
+                : '-'}
+            </div>
+          </div>
           <div>
             <div className="label">ROI %</div>
             <div>{extractMetric(metrics, 'roi')?.toFixed(2) ?? '-'}</div>
@@ -211,11 +264,22 @@ export default function RunDetailsPage({ params }: { params: { id: string } }) {
       <div className="card stack">
         <h2>Artifacts</h2>
         <ul>
-          {artifacts.map((a) => (
-            <li key={a.id}>
-              <span className="mono">{a.kind}</span> <span className="tiny muted">{a.path}</span>
-            </li>
-          ))}
+          {artifacts.map((a) => {
+            const href = artifactDownloadUrl(a);
+            return (
+              <li key={a.id}>
+                {href ? (
+                  <a className="mono" href={href} target="_blank" rel="noreferrer">
+                    {a.name}
+                  </a>
+                ) : (
+                  <span className="mono">{a.name}</span>
+                )}{' '}
+                <span className="tiny muted">[{a.kind}] {a.path}</span>{' '}
+                <span className="tiny muted">{formatArtifactMeta(a)}</span>
+              </li>
+            );
+          })}
           {!artifacts.length ? <li className="muted">No artifacts yet</li> : null}
         </ul>
       </div>
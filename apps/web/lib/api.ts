@@ -1,9 +1,11 @@
 import type {
+  MetricsSnapshotRecord,
   PresetRequest,
   RunArtifact,
   RunEventRecord,
   RunMetricsResponse,
-  RunRecord
+  RunRecord,
+  RunUpdate
 } from '@/lib/types';
 
 const API_BASE = process.env.NEXT_PUBLIC_API_BASE_URL;
@@ -53,6 +55,14 @@ export async function getRunMetrics(id: string): Promise<RunMetricsResponse | nu
   }
 }
 
+export async function getRunMetricsHistory(id: string): Promise<MetricsSnapshotRecord[]> {
+  try {
+    return await jsonFetch<MetricsSnapshotRecord[]>(`/runs/${id}/metrics/history?limit=1000`);
+  } catch {
+    return [];
+  }
+}
+
 export async function getRunArtifacts(id: string): Promise<RunArtifact[]> {
   try {
     return await jsonFetch<RunArtifact[]>(`/runs/${id}/artifacts`);
@@ -61,6 +71,36 @@ export async function getRunArtifacts(id: string): Promise<RunArtifact[]> {
   }
 }
 
+// Редирект API на свежий presigned URL — ссылка не протухает, пока открыта страница
+export function artifactDownloadUrl(a: RunArtifact): string | null {
+  return a.object_key ? `${requireApiBase()}/runs/${a.run_id}/artifacts/${a.id}/download` : null;
+}
+
+// SSE `GET /runs/stream`: status/metrics по мере записи worker'ом; `lagged` — часть пропущена,
+// onUpdate(null) — стоит перечитать всё. Возвращает отписку.
+export function subscribeRunUpdates(
+  runId: string | null,
+  onUpdate: (update: RunUpdate | null) => void
+): () => void {
+  const query = runId ? `?run_id=${encodeURIComponent(runId)}` : '';
+  const source = new EventSource(`${requireApiBase()}/runs/stream${query}`);
+  const onEvent = (e: MessageEvent<string>) => {
+    try {
+      onUpdate(JSON.parse(e.data) as RunUpdate);
+    } catch {
+      onUpdate(null);
+    }
+  };
+  source.addEventListener('status', onEvent);
+  source.addEventListener('metrics', onEvent);
+  source.addEventListener('lagged', () => onUpdate(null));
+  return () => source.close();
+}
+
+export function cancelRun(id: string): Promise<{ status: string }> {
+  return jsonFetch<{ status: string }>(`/runs/${id}/cancel`, { method: 'POST' });
+}
+
 export function createMmMtfSweepPreset(req: PresetRequest): Promise<RunRecord> {
   return jsonFetch<RunRecord>('/runs/presets/mm_mtf_sweep', {
     method: 'POST',
@@ -1,4 +1,4 @@
-export type RunStatus = 'queued' | 'running' | 'completed' | 'failed';
+export type RunStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
 
 export interface RunRecord {
   id: string;
@@ -10,20 +10,73 @@ export interface RunRecord {
   ended_at: string | null;
   exit_code: number | null;
   error: string | null;
+  depends_on?: string[];
+  experiment_id?: string | null;
+  archived_at?: string | null;
+  project?: string;
 }
 
+export interface ExperimentRecord {
+  id: string;
+  name: string;
+  description: string | null;
+  created_at: string;
+}
+
+export interface RunUpdate {
+  run_id: string;
+  kind: 'status' | 'metrics';
+  status?: RunStatus;
+}
+
+export type EventLevel = 'debug' | 'info' | 'warn' | 'error';
+
 export interface RunEventRecord {
   id: number;
   run_id: string;
   ts: string;
-  level: string;
+  level: EventLevel;
   message: string;
+  fields: Record<string, unknown>;
+}
+
+export interface CoreMetrics {
+  pnl: number | null;
+  roi: number | null;
+  max_drawdown: number | null;
+  sharpe: number | null;
+  sortino: number | null;
+  calmar: number | null;
+  profit_factor: number | null;
+  win_rate: number | null;
+  closed_trades: number | null;
+  final_equity: number | null;
+  exposure: number | null;
+  total_costs: number | null;
+}
+
+export interface MetricsPayload {
+  schema_version: number;
+  core: CoreMetrics;
+  charts: { equity: unknown[]; trades: unknown[] };
+  progress: Record<string, unknown> | null;
+  costs: Record<string, unknown> | null;
+  monthly: unknown[] | null;
+  extras: Record<string, unknown>;
 }
 
 export interface RunMetricsResponse {
   run_id: string;
   updated_at: string | null;
-  payload: Record<string, unknown>;
+  payload: MetricsPayload;
+}
+
+export interface MetricsSnapshotRecord {
+  id: number;
+  run_id: string;
+  ts: string;
+  is_final: boolean;
+  payload: MetricsPayload;
 }
 
 export interface EquityPoint {
@@ -40,11 +93,27 @@ export interface TradePoint {
   pnl?: number | null;
 }
 
+export type ArtifactKind =
+  | 'equity_csv'
+  | 'fills_csv'
+  | 'sweep_summary'
+  | 'report'
+  | 'log'
+  | 'capture'
+  | 'table'
+  | 'other';
+
 export interface RunArtifact {
   id: number;
   run_id: string;
-  kind: string;
+  kind: ArtifactKind;
+  name: string;
   path: string;
+  size_bytes: number | null;
+  row_count: number | null;
+  checksum: string | null;
+  object_key?: string | null;
+  download_url?: string | null;
   created_at: string;
 }
 
@@ -56,4 +125,6 @@ export interface PresetRequest {
   htf_interval?: string;
   ltf_interval?: string;
   top_n?: number;
+  notify?: boolean;
+  project?: string;
 }
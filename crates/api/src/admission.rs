@@ -0,0 +1,254 @@
+//! Лимиты очереди: сколько run'ов может ждать всего, каждого вида и от одного ключа.
+//! Постановка сверх лимита отклоняется с 429, чтобы скрипт в цикле не занял worker на дни.
+
+use std::collections::HashMap;
+use std::env;
+
+use anyhow::{Context, Result, bail};
+use axum::{Json, http::StatusCode};
+use serde_json::json;
+use sqlx::{Postgres, Transaction};
+
+use orchestrator_core::models::RunKind;
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+/// `None` — без лимита
+#[derive(Debug, Clone, Default)]
+pub struct AdmissionLimits {
+    /// `QUEUE_MAX_QUEUED`
+    pub max_queued: Option<i64>,
+    /// `QUEUE_MAX_QUEUED_PER_KIND`: `backtest_mm_mtf_sweep=5,data_download=20`
+    pub per_kind: HashMap<String, i64>,
+    /// `QUEUE_MAX_QUEUED_PER_KEY`; запросы без ключа считаются одним ключом
+    pub per_key: Option<i64>,
+}
+
+impl AdmissionLimits {
+    pub fn from_env() -> Result<Self> {
+        let limit = |name: &str| -> Result<Option<i64>> {
+            match env::var(name) {
+                Ok(v) if !v.trim().is_empty() => {
+                    let n: i64 = v
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("invalid {}", name))?;
+                    if n < 1 {
+                        bail!("{} must be positive", name);
+                    }
+                    Ok(Some(n))
+                }
+                _ => Ok(None),
+            }
+        };
+        let mut per_kind = HashMap::new();
+        if let Ok(raw) = env::var("QUEUE_MAX_QUEUED_PER_KIND") {
+            for pair in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let Some((kind, n)) = pair.split_once('=') else {
+                    bail!("QUEUE_MAX_QUEUED_PER_KIND entry '{}' is not kind=N", pair);
+                };
+                let kind = kind.trim();
+                serde_json::from_value::<RunKind>(json!(kind))
+                    .with_context(|| format!("QUEUE_MAX_QUEUED_PER_KIND: unknown kind {}", kind))?;
+                let n: i64 = n.trim().parse().ok().filter(|n| *n >= 1).with_context(|| {
+                    format!("QUEUE_MAX_QUEUED_PER_KIND: invalid limit for {}", kind)
+                })?;
+                per_kind.insert(kind.to_string(), n);
+            }
+        }
+        Ok(Self {
+            max_queued: limit("QUEUE_MAX_QUEUED")?,
+            per_kind,
+            per_key: limit("QUEUE_MAX_QUEUED_PER_KEY")?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_queued.is_none() && self.per_kind.is_empty() && self.per_key.is_none()
+    }
+
+    /// Проверка перед вставкой run'а в той же транзакции; advisory lock сериализует
+    /// параллельные постановки, чтобы они не проскочили лимит вместе
+    pub async fn check(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        kind: &str,
+        submitted_by: Option<&str>,
+    ) -> Result<(), ApiError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('mmbot_run_admission'))")
+            .execute(&mut **tx)
+            .await
+            .map_err(internal_err)?;
+        let (total, of_kind, of_key): (i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*),
+                COUNT(*) FILTER (WHERE kind = $1),
+                COUNT(*) FILTER (WHERE submitted_by IS NOT DISTINCT FROM $2)
+            FROM runs
+            WHERE status = 'queued'
+            "#,
+        )
+        .bind(kind)
+        .bind(submitted_by)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(internal_err)?;
+
+        self.admit(
+            QueuedCounts {
+                total,
+                of_kind,
+                of_key,
+            },
+            kind,
+            submitted_by,
+        )
+    }
+
+    /// Решение по счётчикам очереди: первый превышенный лимит — 429
+    fn admit(
+        &self,
+        counts: QueuedCounts,
+        kind: &str,
+        submitted_by: Option<&str>,
+    ) -> Result<(), ApiError> {
+        if let Some(limit) = self.max_queued.filter(|l| counts.total >= *l) {
+            return Err(rejected(
+                format!("{} runs queued in total", counts.total),
+                "QUEUE_MAX_QUEUED",
+                limit,
+            ));
+        }
+        if let Some(limit) = self
+            .per_kind
+            .get(kind)
+            .copied()
+            .filter(|l| counts.of_kind >= *l)
+        {
+            return Err(rejected(
+                format!("{} runs of kind {} queued", counts.of_kind, kind),
+                "QUEUE_MAX_QUEUED_PER_KIND",
+                limit,
+            ));
+        }
+        if let Some(limit) = self.per_key.filter(|l| counts.of_key >= *l) {
+            let who = submitted_by.unwrap_or("requests without api key");
+            return Err(rejected(
+                format!("{} runs queued by {}", counts.of_key, who),
+                "QUEUE_MAX_QUEUED_PER_KEY",
+                limit,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Ожидающие run'ы: всего, того же вида и от того же ключа
+#[derive(Debug, Clone, Copy)]
+struct QueuedCounts {
+    total: i64,
+    of_kind: i64,
+    of_key: i64,
+}
+
+fn rejected(reason: String, setting: &str, limit: i64) -> ApiError {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({
+            "error": format!("queue limit reached: {} (limit {})", reason, limit),
+            "limit": setting,
+        })),
+    )
+}
+
+fn internal_err(e: sqlx::Error) -> ApiError {
+    tracing::error!("admission check failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": "internal error"})),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> AdmissionLimits {
+        AdmissionLimits {
+            max_queued: Some(10),
+            per_kind: HashMap::from([("backtest_mm_mtf_sweep".to_string(), 2)]),
+            per_key: Some(3),
+        }
+    }
+
+    fn counts(total: i64, of_kind: i64, of_key: i64) -> QueuedCounts {
+        QueuedCounts {
+            total,
+            of_kind,
+            of_key,
+        }
+    }
+
+    fn rejected_by(res: Result<(), ApiError>) -> String {
+        let (status, Json(body)) = res.unwrap_err();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("queue limit reached")
+        );
+        body["limit"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn below_every_limit_is_admitted() {
+        let l = limits();
+        assert!(
+            l.admit(counts(9, 1, 2), "backtest_mm_mtf_sweep", Some("key:a"))
+                .is_ok()
+        );
+        // лимит вида не касается других видов
+        assert!(
+            l.admit(counts(9, 5, 2), "backtest_mm", Some("key:a"))
+                .is_ok()
+        );
+        assert!(
+            AdmissionLimits::default()
+                .admit(counts(1000, 1000, 1000), "backtest_mm", None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn global_limit_rejects_with_429() {
+        let res = limits().admit(counts(10, 0, 0), "backtest_mm", Some("key:a"));
+        assert_eq!(rejected_by(res), "QUEUE_MAX_QUEUED");
+    }
+
+    #[test]
+    fn per_kind_limit_rejects_only_its_kind() {
+        let res = limits().admit(counts(5, 2, 0), "backtest_mm_mtf_sweep", Some("key:a"));
+        assert_eq!(rejected_by(res), "QUEUE_MAX_QUEUED_PER_KIND");
+    }
+
+    #[test]
+    fn per_key_limit_counts_keyless_requests_as_one_key() {
+        let res = limits().admit(counts(5, 0, 3), "backtest_mm", Some("key:a"));
+        assert_eq!(rejected_by(res), "QUEUE_MAX_QUEUED_PER_KEY");
+
+        let (_, Json(body)) = limits()
+            .admit(counts(5, 0, 3), "backtest_mm", None)
+            .unwrap_err();
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("requests without api key")
+        );
+    }
+}
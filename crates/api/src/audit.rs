@@ -0,0 +1,286 @@
+//! Журнал изменяющих вызовов API (POST/PUT/PATCH/DELETE) в `audit_log`: актор, время,
+//! тело запроса и статус ответа. Ключ API в журнал не пишется — только его отпечаток,
+//! секретные поля тела заменяются на `***`.
+//! Слой стоит внутри `auth::authenticate`: актор — из проверенного `Scope`,
+//! отклонённые аутентификацией запросы в журнал не попадают.
+//!
+//! Журнал обязателен: запись создаётся до обработчика (статус `0` — ответа ещё нет),
+//! и если база её не приняла, изменение не выполняется — 503. Статус ответа
+//! дописывается после обработчика; сбой этой записи только логируется, запись
+//! остаётся со статусом `0`.
+
+use std::future::Future;
+
+use axum::{
+    Json,
+    body::{Body, to_bytes},
+    extract::{MatchedPath, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::auth::Scope;
+
+/// Запрос больше отклоняется до обработчика: API принимает только небольшой JSON
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Поля тела с такими подстроками в имени (без учёта регистра) в журнал не попадают
+const SECRET_FIELDS: &[&str] = &["secret", "password", "passphrase", "token", "api_key"];
+const REDACTED: &str = "***";
+
+/// Изменяющий вызов до ответа обработчика
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub actor: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub route: Option<String>,
+    pub body: Option<Value>,
+}
+
+/// Куда пишется журнал: `open` — до обработчика (id записи), `close` — статус ответа
+pub trait AuditSink: Clone + Send + Sync + 'static {
+    fn open(&self, entry: &AuditEntry) -> impl Future<Output = anyhow::Result<i64>> + Send;
+    fn close(&self, id: i64, status: u16) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// `audit_log` в Postgres
+#[derive(Clone)]
+pub struct PgAudit(pub PgPool);
+
+impl AuditSink for PgAudit {
+    async fn open(&self, entry: &AuditEntry) -> anyhow::Result<i64> {
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO audit_log (ts, actor, method, path, route, status, body)
+            VALUES (NOW(), $1, $2, $3, $4, 0, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(&entry.actor)
+        .bind(&entry.method)
+        .bind(&entry.path)
+        .bind(&entry.route)
+        .bind(&entry.body)
+        .fetch_one(&self.0)
+        .await?;
+        Ok(id)
+    }
+
+    async fn close(&self, id: i64, status: u16) -> anyhow::Result<()> {
+        sqlx::query("UPDATE audit_log SET status = $2 WHERE id = $1")
+            .bind(id)
+            .bind(status as i32)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+}
+
+pub async fn record_mutation<S: AuditSink>(
+    State(sink): State<S>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string());
+    let actor = req.extensions().get::<Scope>().and_then(|s| s.actor.clone());
+
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let body_json = if bytes.is_empty() {
+        None
+    } else {
+        let value = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| json!(String::from_utf8_lossy(&bytes)));
+        Some(redact(value))
+    };
+    let entry = AuditEntry {
+        actor,
+        method,
+        path,
+        route,
+        body: body_json,
+    };
+
+    let id = match sink.open(&entry).await {
+        Ok(id) => id,
+        Err(e) => {
+            error!("audit log insert failed, request rejected: {}", e);
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "error": "audit log unavailable" })),
+            )
+                .into_response();
+        }
+    };
+
+    let resp = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    if let Err(e) = sink.close(id, resp.status().as_u16()).await {
+        error!("audit log status update failed: id={} {}", id, e);
+    }
+    resp
+}
+
+/// Значения секретных полей (на любой глубине) — `***`
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let name = k.to_ascii_lowercase();
+                    if SECRET_FIELDS.iter().any(|s| name.contains(s)) {
+                        (k, json!(REDACTED))
+                    } else {
+                        (k, redact(v))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        Router,
+        middleware::{self, Next},
+        routing::post,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MemAudit {
+        rows: Arc<Mutex<Vec<(AuditEntry, u16)>>>,
+        fail: bool,
+    }
+
+    impl AuditSink for MemAudit {
+        async fn open(&self, entry: &AuditEntry) -> anyhow::Result<i64> {
+            if self.fail {
+                anyhow::bail!("db down");
+            }
+            let mut rows = self.rows.lock().unwrap();
+            rows.push((entry.clone(), 0));
+            Ok(rows.len() as i64 - 1)
+        }
+
+        async fn close(&self, id: i64, status: u16) -> anyhow::Result<()> {
+            self.rows.lock().unwrap()[id as usize].1 = status;
+            Ok(())
+        }
+    }
+
+    async fn with_scope(mut req: Request, next: Next) -> Response {
+        req.extensions_mut().insert(Scope {
+            project: None,
+            actor: Some("key:abc".into()),
+        });
+        next.run(req).await
+    }
+
+    fn app(sink: MemAudit, handled: Arc<Mutex<usize>>) -> Router {
+        let handler = move || async move {
+            *handled.lock().unwrap() += 1;
+            StatusCode::CREATED
+        };
+        Router::new()
+            .route("/runs", post(handler.clone()).get(handler))
+            .route_layer(middleware::from_fn_with_state(
+                sink,
+                record_mutation::<MemAudit>,
+            ))
+            .route_layer(middleware::from_fn(with_scope))
+    }
+
+    fn request(method: Method, body: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri("/runs")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn mutation_is_recorded_with_actor_status_and_redacted_body() {
+        let sink = MemAudit::default();
+        let handled = Arc::new(Mutex::new(0));
+        let body = r#"{"kind":"backtest","params":{"api_secret":"s3","symbol":"ETHUSDT"}}"#;
+
+        let resp =
+            block_on(app(sink.clone(), handled.clone()).oneshot(request(Method::POST, body)))
+                .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let rows = sink.rows.lock().unwrap();
+        assert_eq!(rows.len(), 1);
+        let (entry, status) = &rows[0];
+        assert_eq!(*status, 201);
+        assert_eq!(entry.actor.as_deref(), Some("key:abc"));
+        assert_eq!(
+            (entry.method.as_str(), entry.path.as_str()),
+            ("POST", "/runs")
+        );
+        assert_eq!(entry.route.as_deref(), Some("/runs"));
+        assert_eq!(
+            entry.body,
+            Some(json!({"kind": "backtest", "params": {"api_secret": "***", "symbol": "ETHUSDT"}}))
+        );
+    }
+
+    #[test]
+    fn get_is_not_recorded() {
+        let sink = MemAudit::default();
+        let handled = Arc::new(Mutex::new(0));
+
+        let resp =
+            block_on(app(sink.clone(), handled.clone()).oneshot(request(Method::GET, ""))).unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(*handled.lock().unwrap(), 1);
+        assert!(sink.rows.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn mutation_is_rejected_when_audit_write_fails() {
+        let sink = MemAudit {
+            fail: true,
+            ..MemAudit::default()
+        };
+        let handled = Arc::new(Mutex::new(0));
+
+        let resp =
+            block_on(app(sink, handled.clone()).oneshot(request(Method::POST, "{}"))).unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*handled.lock().unwrap(), 0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Ключи API и проекты. Ключ проекта видит и создаёт только run'ы и эксперименты своего
+//! проекта; ключ без проекта — администратор. Запрос без ключа — администратор, только
+//! пока в базе нет ни одного действующего ключа (чтобы создать первый) и не включён
+//! `API_REQUIRE_KEY`. В `api_keys` хранится только sha256 ключа.
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use orchestrator_core::models::DEFAULT_PROJECT;
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+#[derive(Clone)]
+pub struct Auth {
+    pub pg: PgPool,
+    /// Без ключа — 401 (кроме `/health` и `/metrics`), даже пока ключей ещё нет
+    pub require_key: bool,
+}
+
+/// Что доступно запросу; кладётся в extensions middleware `authenticate`
+#[derive(Debug, Clone)]
+pub struct Scope {
+    /// `None` — все проекты
+    pub project: Option<String>,
+    /// `key:<hex>` ключа запроса, `None` — без ключа
+    pub actor: Option<String>,
+}
+
+impl Scope {
+    pub fn is_admin(&self) -> bool {
+        self.project.is_none()
+    }
+
+    pub fn require_admin(&self) -> Result<(), ApiError> {
+        if self.is_admin() {
+            Ok(())
+        } else {
+            Err(forbidden("admin key required"))
+        }
+    }
+
+    /// Проект для нового run'а или эксперимента: ключ проекта создаёт только в своём
+    pub fn project_for_create(&self, requested: Option<&str>) -> Result<String, ApiError> {
+        match (&self.project, requested) {
+            (Some(own), Some(p)) if own != p => Err(forbidden("key is scoped to another project")),
+            (Some(own), _) => Ok(own.clone()),
+            (None, p) => Ok(p.unwrap_or(DEFAULT_PROJECT).to_string()),
+        }
+    }
+
+    /// Фильтр списка: ключ проекта видит только свой, администратор — запрошенный или все
+    pub fn list_filter(&self, requested: Option<&str>) -> Result<Option<String>, ApiError> {
+        match (&self.project, requested) {
+            (Some(own), Some(p)) if own != p => Err(forbidden("key is scoped to another project")),
+            (Some(own), _) => Ok(Some(own.clone())),
+            (None, p) => Ok(p.map(str::to_string)),
+        }
+    }
+
+    pub fn can_see(&self, project: &str) -> bool {
+        self.project.as_deref().is_none_or(|own| own == project)
+    }
+}
+
+pub async fn authenticate(State(auth): State<Auth>, mut req: Request, next: Next) -> Response {
+    if matches!(req.uri().path(), "/health" | "/metrics") {
+        return next.run(req).await;
+    }
+    let scope = match request_key(req.headers()) {
+        Some(key) => {
+            let hash = key_hash(key);
+            let found: sqlx::Result<Option<Option<String>>> = sqlx::query_scalar(
+                "SELECT project FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+            )
+            .bind(&hash)
+            .fetch_optional(&auth.pg)
+            .await;
+            match found {
+                Ok(Some(project)) => Scope {
+                    project,
+                    actor: Some(key_actor(&hash)),
+                },
+                Ok(None) => return unauthorized("unknown or revoked api key"),
+                Err(e) => return internal("api key lookup", e),
+            }
+        }
+        None if auth.require_key => return unauthorized("api key required"),
+        None => {
+            let exists: sqlx::Result<bool> = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM api_keys WHERE revoked_at IS NULL)",
+            )
+            .fetch_one(&auth.pg)
+            .await;
+            match exists {
+                Ok(false) => Scope {
+                    project: None,
+                    actor: None,
+                },
+                Ok(true) => return unauthorized("api key required"),
+                Err(e) => return internal("api key lookup", e),
+            }
+        }
+    };
+    req.extensions_mut().insert(scope);
+    next.run(req).await
+}
+
+/// Ключ из `X-Api-Key` или `Authorization: Bearer`
+pub fn request_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("x-api-key")
+        .or_else(|| headers.get(header::AUTHORIZATION))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("Bearer ").trim())
+        .filter(|v| !v.is_empty())
+}
+
+/// sha256 ключа в hex — так он хранится в `api_keys`
+pub fn key_hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `key:<sha256 первых 8 байт>` — актор в `audit_log`
+pub fn key_actor(key_hash: &str) -> String {
+    format!("key:{}", &key_hash[..16])
+}
+
+pub fn generate_key() -> String {
+    format!("mmk_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn forbidden(msg: &str) -> ApiError {
+    (StatusCode::FORBIDDEN, Json(json!({"error": msg})))
+}
+
+fn internal(what: &str, e: sqlx::Error) -> Response {
+    error!("{} failed: {}", what, e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": "internal error"})),
+    )
+        .into_response()
+}
+
+fn unauthorized(msg: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({"error": msg}))).into_response()
+}
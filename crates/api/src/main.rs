@@ -1,17 +1,33 @@
+mod admission;
+mod audit;
+mod auth;
+mod service_metrics;
+mod updates;
+
 use std::{env, net::SocketAddr};
 
 use anyhow::{Context, Result};
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
+    middleware,
+    response::{IntoResponse, Redirect},
+    routing::{delete, get, post},
 };
 use orchestrator_core::models::{
-    CreateRunRequest, RUN_QUEUE_KEY, RunEventRecord, RunKind, RunRecord, RunStatus,
+    ApiKeyRecord, ArtifactKind, ArtifactRecord, AuditRecord, CreateApiKeyRequest,
+    CreateExperimentRequest, CreateProjectRequest, CreateRunRequest, CreatedApiKey, EventLevel,
+    ExperimentRecord, MetricsSnapshotRecord, PendingRunRecord, ProjectRecord, QueueStreamRecord,
+    RUN_STREAM_FIELD, RUN_STREAM_GROUP, RUN_STREAM_KEYS, RunEventRecord, RunUpdate, RunKind,
+    RunRecord, RunStatus, is_valid_project_name, run_cancel_key,
 };
+use orchestrator_core::artifact_store::ArtifactStore;
+use orchestrator_core::metrics::MetricsPayload;
+use orchestrator_core::params::{MmMtfSweepParams, NOTIFY_PARAM, typed_cli_args};
+use orchestrator_core::telemetry;
 use redis::AsyncCommands;
+use redis::streams::{StreamPendingCountReply, StreamRangeReply};
 use serde::Deserialize;
 use serde_json::json;
 use sqlx::PgPool;
@@ -19,20 +35,24 @@ use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info};
 use uuid::Uuid;
 
+use admission::AdmissionLimits;
+use auth::Scope;
+use service_metrics::ServiceMetrics;
+
 #[derive(Clone)]
 struct AppState {
     pg: PgPool,
     redis: redis::Client,
+    metrics: ServiceMetrics,
+    updates: tokio::sync::broadcast::Sender<RunUpdate>,
+    /// `None` — артефакты без ссылок на скачивание, только пути на диске worker'а
+    artifacts: Option<ArtifactStore>,
+    admission: AdmissionLimits,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "api=info,axum=info".into()),
-        )
-        .init();
+    let _telemetry = telemetry::init("api", "api=info,axum=info");
 
     let database_url = env::var("DATABASE_URL").context("DATABASE_URL is required")?;
     let redis_url = env::var("REDIS_URL").context("REDIS_URL is required")?;
@@ -42,17 +62,58 @@ async fn main() -> Result<()> {
     sqlx::migrate!("../../migrations").run(&pg).await?;
     let redis = redis::Client::open(redis_url)?;
     let cors = build_cors_from_env();
+    let artifacts = ArtifactStore::from_env().context("artifact store config failed")?;
+    let admission = AdmissionLimits::from_env().context("queue limits config failed")?;
+    let auth = auth::Auth {
+        pg: pg.clone(),
+        require_key: env::var("API_REQUIRE_KEY").is_ok_and(|v| v == "true" || v == "1"),
+    };
 
-    let state = AppState { pg, redis };
+    let metrics = ServiceMetrics::new()?;
+    let updates = updates::spawn_listener(pg.clone());
+    let state = AppState {
+        pg: pg.clone(),
+        redis,
+        metrics: metrics.clone(),
+        updates,
+        artifacts,
+        admission,
+    };
 
     let app = Router::new()
         .route("/health", get(health))
+        .route("/metrics", get(service_metrics_handler))
         .route("/runs", post(create_run).get(list_runs))
         .route("/runs/presets/mm_mtf_sweep", post(create_run_preset_mm_mtf_sweep))
+        .route("/runs/stream", get(stream_run_updates))
         .route("/runs/{id}", get(get_run))
+        .route("/runs/{id}/cancel", post(cancel_run))
         .route("/runs/{id}/events", get(list_run_events))
         .route("/runs/{id}/metrics", get(get_run_metrics))
+        .route("/runs/{id}/metrics/history", get(list_run_metrics_history))
         .route("/runs/{id}/artifacts", get(get_run_artifacts))
+        .route(
+            "/runs/{id}/artifacts/{artifact_id}/download",
+            get(download_run_artifact),
+        )
+        .route("/experiments", post(create_experiment).get(list_experiments))
+        .route("/experiments/{id}", get(get_experiment))
+        .route("/experiments/{id}/runs", get(list_experiment_runs))
+        .route("/audit", get(list_audit))
+        .route("/queue", get(get_queue))
+        .route("/projects", post(create_project).get(list_projects))
+        .route("/api-keys", post(create_api_key).get(list_api_keys))
+        .route("/api-keys/{id}", delete(revoke_api_key))
+        // последний слой — внешний: audit видит только аутентифицированные запросы
+        .route_layer(middleware::from_fn_with_state(
+            audit::PgAudit(pg),
+            audit::record_mutation::<audit::PgAudit>,
+        ))
+        .route_layer(middleware::from_fn_with_state(auth, auth::authenticate))
+        .route_layer(middleware::from_fn_with_state(
+            metrics,
+            service_metrics::track_latency,
+        ))
         .layer(cors)
         .with_state(state);
 
@@ -109,11 +170,26 @@ async fn health() -> impl IntoResponse {
     Json(json!({"ok": true}))
 }
 
+async fn service_metrics_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let body = state
+        .metrics
+        .render(&state.pg, &state.redis)
+        .await
+        .map_err(internal_err)?;
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    ))
+}
+
 async fn create_run(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Json(req): Json<CreateRunRequest>,
 ) -> Result<(StatusCode, Json<RunRecord>), (StatusCode, Json<serde_json::Value>)> {
-    enqueue_run(&state, req).await
+    enqueue_run(&state, &scope, req).await
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,10 +202,21 @@ struct MmMtfSweepPresetRequest {
     maker_fee_bps_list: Option<String>,
     top_n: Option<usize>,
     summary_out: Option<String>,
+    /// Например, `data_download` run с теми же символом и диапазоном
+    #[serde(default)]
+    depends_on: Vec<Uuid>,
+    #[serde(default)]
+    experiment_id: Option<Uuid>,
+    /// Сообщение в Telegram/Slack, когда sweep закончится
+    #[serde(default)]
+    notify: bool,
+    #[serde(default)]
+    project: Option<String>,
 }
 
 async fn create_run_preset_mm_mtf_sweep(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Json(req): Json<MmMtfSweepPresetRequest>,
 ) -> Result<(StatusCode, Json<RunRecord>), (StatusCode, Json<serde_json::Value>)> {
     if req.symbol.trim().is_empty() || req.start.trim().is_empty() || req.end.trim().is_empty() {
@@ -139,10 +226,6 @@ async fn create_run_preset_mm_mtf_sweep(
         ));
     }
 
-    let htf_interval = req.htf_interval.unwrap_or_else(|| "5".to_string());
-    let ltf_interval = req.ltf_interval.unwrap_or_else(|| "1".to_string());
-    let maker_fee_bps_list = req.maker_fee_bps_list.unwrap_or_else(|| "10".to_string());
-    let top_n = req.top_n.unwrap_or(30).clamp(1, 200);
     let summary_out = req.summary_out.unwrap_or_else(|| {
         format!(
             "data/mm_mtf_sweep_{}_{}_{}.csv",
@@ -151,73 +234,91 @@ async fn create_run_preset_mm_mtf_sweep(
             req.end.replace('-', "")
         )
     });
+    let (Ok(start), Ok(end)) = (req.start.parse(), req.end.parse()) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "start, end must be YYYY-MM-DD"})),
+        ));
+    };
+    let htf_interval = req.htf_interval.as_deref().unwrap_or("5").parse().ok();
+    let ltf_interval = req.ltf_interval.as_deref().unwrap_or("1").parse().ok();
+    let maker_fee_bps_list = match req.maker_fee_bps_list.as_deref() {
+        Some(s) => s
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<f64>, _>>()
+            .ok(),
+        None => Some(vec![10.0]),
+    };
+    let (Some(htf_interval), Some(ltf_interval), Some(maker_fee_bps_list)) =
+        (htf_interval, ltf_interval, maker_fee_bps_list)
+    else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "intervals and maker_fee_bps_list must be numeric"})),
+        ));
+    };
 
+    let params = MmMtfSweepParams {
+        symbol: req.symbol.clone(),
+        htf_interval: Some(htf_interval),
+        ltf_interval: Some(ltf_interval),
+        start,
+        end,
+        initial_quote: Some(1000.0),
+        initial_base: Some(0.0),
+        initial_base_ratio: None,
+        market: None,
+        search: None,
+        samples: None,
+        seed: None,
+        levels_list: Some(vec![3, 5, 7]),
+        step_bps_list: Some(vec![6.0, 8.0, 10.0, 12.0]),
+        base_quote_per_order_list: Some(vec![20.0, 30.0, 40.0]),
+        max_size_mult_list: Some(vec![1.5, 2.0, 2.5]),
+        soft_min_list: Some(vec![0.35, 0.40]),
+        soft_max_list: Some(vec![0.55, 0.60]),
+        hard_min_list: Some(vec![0.30, 0.35]),
+        hard_max_list: Some(vec![0.65, 0.70]),
+        maker_fee_bps_list: Some(maker_fee_bps_list),
+        defensive_step_mult_list: Some(vec![1.2, 1.5, 1.8]),
+        defensive_size_mult_list: Some(vec![0.35, 0.5, 0.7]),
+        force_close_fee_bps: Some(10.0),
+        force_close_spread_bps: Some(8.0),
+        force_close_slippage_bps: Some(2.0),
+        force_close_at_end: true,
+        bootstrap_rebalance: true,
+        bootstrap_target_ratio: Some(0.50),
+        top_n: Some(req.top_n.unwrap_or(30).clamp(1, 200) as u32),
+        rank_by: None,
+        summary_out: Some(summary_out),
+    };
+
+    let mut params = serde_json::to_value(&params).map_err(internal_err)?;
+    if req.notify {
+        params[NOTIFY_PARAM] = json!(true);
+    }
     let run = CreateRunRequest {
         name: format!("mm_mtf_sweep {} {}..{}", req.symbol, req.start, req.end),
         kind: RunKind::BacktestMmMtfSweep,
-        cli_args: vec![
-            "--symbol".into(),
-            req.symbol,
-            "--htf-interval".into(),
-            htf_interval,
-            "--ltf-interval".into(),
-            ltf_interval,
-            "--start".into(),
-            req.start,
-            "--end".into(),
-            req.end,
-            "--htf-cache".into(),
-            "data/mm_mtf_htf_5m.csv".into(),
-            "--ltf-cache".into(),
-            "data/mm_mtf_ltf_1m.csv".into(),
-            "--levels-list".into(),
-            "3,5,7".into(),
-            "--step-bps-list".into(),
-            "6,8,10,12".into(),
-            "--base-quote-per-order-list".into(),
-            "20,30,40".into(),
-            "--max-size-mult-list".into(),
-            "1.5,2.0,2.5".into(),
-            "--soft-min-list".into(),
-            "0.35,0.40".into(),
-            "--soft-max-list".into(),
-            "0.55,0.60".into(),
-            "--hard-min-list".into(),
-            "0.30,0.35".into(),
-            "--hard-max-list".into(),
-            "0.65,0.70".into(),
-            "--maker-fee-bps-list".into(),
-            maker_fee_bps_list,
-            "--defensive-step-mult-list".into(),
-            "1.2,1.5,1.8".into(),
-            "--defensive-size-mult-list".into(),
-            "0.35,0.5,0.7".into(),
-            "--initial-quote".into(),
-            "1000".into(),
-            "--initial-base".into(),
-            "0".into(),
-            "--bootstrap-rebalance".into(),
-            "--bootstrap-target-ratio".into(),
-            "0.50".into(),
-            "--force-close-at-end".into(),
-            "--force-close-fee-bps".into(),
-            "10".into(),
-            "--force-close-spread-bps".into(),
-            "8".into(),
-            "--force-close-slippage-bps".into(),
-            "2".into(),
-            "--top-n".into(),
-            top_n.to_string(),
-            "--summary-out".into(),
-            summary_out,
-        ],
+        cli_args: Vec::new(),
+        params: Some(params),
+        depends_on: req.depends_on,
+        experiment_id: req.experiment_id,
+        project: req.project,
     };
 
-    enqueue_run(&state, run).await
+    enqueue_run(&state, &scope, run).await
 }
 
+#[tracing::instrument(
+    name = "enqueue",
+    skip_all,
+    fields(run_id = tracing::field::Empty, kind = ?req.kind)
+)]
 async fn enqueue_run(
     state: &AppState,
+    scope: &Scope,
     req: CreateRunRequest,
 ) -> Result<(StatusCode, Json<RunRecord>), (StatusCode, Json<serde_json::Value>)> {
     if req.name.trim().is_empty() {
@@ -226,17 +327,76 @@ async fn enqueue_run(
             Json(json!({"error": "name cannot be empty"})),
         ));
     }
+    let project = scope.project_for_create(req.project.as_deref())?;
+    ensure_project_exists(&state.pg, &project).await?;
+
+    // схема параметров проверяется до записи run'а; флаги из них собирает worker
+    if let Some(params) = &req.params {
+        if let Err(e) = typed_cli_args(req.kind, params) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("invalid params: {}", e)})),
+            ));
+        }
+    }
+
+    let mut depends_on = req.depends_on.clone();
+    depends_on.sort_unstable();
+    depends_on.dedup();
+    // зависимости и эксперимент — только из того же проекта
+    if !depends_on.is_empty() {
+        let known: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM runs WHERE id = ANY($1) AND project = $2",
+        )
+        .bind(&depends_on)
+        .bind(&project)
+        .fetch_one(&state.pg)
+        .await
+        .map_err(internal_err)?;
+        if known != depends_on.len() as i64 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "depends_on references unknown run"})),
+            ));
+        }
+    }
+
+    if let Some(experiment_id) = req.experiment_id {
+        let known: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM experiments WHERE id = $1 AND project = $2)",
+        )
+        .bind(experiment_id)
+        .bind(&project)
+        .fetch_one(&state.pg)
+        .await
+        .map_err(internal_err)?;
+        if !known {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "experiment_id references unknown experiment"})),
+            ));
+        }
+    }
 
     let run_id = Uuid::new_v4();
+    tracing::Span::current().record("run_id", tracing::field::display(run_id));
     let now = chrono::Utc::now();
     let run_kind = serde_json::to_string(&req.kind).map_err(internal_err)?;
     let run_kind = run_kind.trim_matches('"').to_string();
     let status = "queued";
 
+    // лимиты очереди и вставка — в одной транзакции
+    let mut tx = state.pg.begin().await.map_err(internal_err)?;
+    state
+        .admission
+        .check(&mut tx, &run_kind, scope.actor.as_deref())
+        .await?;
     sqlx::query(
         r#"
-        INSERT INTO runs (id, name, kind, status, created_at)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO runs (
+            id, name, kind, status, created_at, depends_on, experiment_id, project, submitted_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         "#,
     )
     .bind(run_id)
@@ -244,21 +404,26 @@ async fn enqueue_run(
     .bind(&run_kind)
     .bind(status)
     .bind(now)
-    .execute(&state.pg)
+    .bind(&depends_on)
+    .bind(req.experiment_id)
+    .bind(&project)
+    .bind(&scope.actor)
+    .execute(&mut *tx)
     .await
     .map_err(internal_err)?;
 
     let args_json = serde_json::to_value(&req.cli_args).map_err(internal_err)?;
     sqlx::query(
         r#"
-        INSERT INTO run_params (run_id, cli_args, created_at)
-        VALUES ($1, $2, $3)
+        INSERT INTO run_params (run_id, cli_args, params, created_at)
+        VALUES ($1, $2, $3, $4)
         "#,
     )
     .bind(run_id)
     .bind(args_json)
+    .bind(&req.params)
     .bind(now)
-    .execute(&state.pg)
+    .execute(&mut *tx)
     .await
     .map_err(internal_err)?;
 
@@ -271,18 +436,25 @@ async fn enqueue_run(
     .bind(run_id)
     .bind(now)
     .bind(format!("queued run {} ({})", req.name, run_kind))
-    .execute(&state.pg)
+    .execute(&mut *tx)
     .await
     .map_err(internal_err)?;
+    tx.commit().await.map_err(internal_err)?;
 
+    // XADD после коммита: если он не прошёл, run остаётся queued без записи в очереди,
+    // worker найдёт его сверкой (`queue::ORPHAN_AFTER`) и поставит сам
     let mut conn = state
         .redis
         .get_multiplexed_tokio_connection()
         .await
         .map_err(redis_err)?;
-    conn.lpush::<_, _, usize>(RUN_QUEUE_KEY, run_id.to_string())
-        .await
-        .map_err(redis_err)?;
+    conn.xadd::<_, _, _, _, String>(
+        req.kind.priority().stream_key(),
+        "*",
+        &[(RUN_STREAM_FIELD, run_id.to_string())],
+    )
+    .await
+    .map_err(redis_err)?;
 
     let out = RunRecord {
         id: run_id,
@@ -294,45 +466,109 @@ async fn enqueue_run(
         ended_at: None,
         exit_code: None,
         error: None,
+        depends_on,
+        experiment_id: req.experiment_id,
+        archived_at: None,
+        project,
     };
     Ok((StatusCode::ACCEPTED, Json(out)))
 }
 
+async fn ensure_project_exists(
+    pg: &PgPool,
+    project: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let known: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM projects WHERE name = $1)")
+        .bind(project)
+        .fetch_one(pg)
+        .await
+        .map_err(internal_err)?;
+    if !known {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("unknown project {}", project)})),
+        ));
+    }
+    Ok(())
+}
+
+/// Run чужого проекта для ключа проекта — как несуществующий
+async fn ensure_run_visible(
+    state: &AppState,
+    scope: &Scope,
+    id: Uuid,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if scope.is_admin() {
+        return Ok(());
+    }
+    let project: Option<String> = sqlx::query_scalar("SELECT project FROM runs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.pg)
+        .await
+        .map_err(internal_err)?;
+    match project {
+        Some(p) if !scope.can_see(&p) => {
+            Err((StatusCode::NOT_FOUND, Json(json!({"error": "run not found"}))))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ListRunsQuery {
     limit: Option<i64>,
+    experiment_id: Option<Uuid>,
+    project: Option<String>,
 }
 
 async fn list_runs(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Query(q): Query<ListRunsQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let limit = q.limit.unwrap_or(50).clamp(1, 500);
+    let project = scope.list_filter(q.project.as_deref())?;
+    let out = fetch_runs(&state.pg, q.experiment_id, project.as_deref(), limit)
+        .await
+        .map_err(internal_err)?;
+    Ok(Json(out))
+}
 
+async fn fetch_runs(
+    pg: &PgPool,
+    experiment_id: Option<Uuid>,
+    project: Option<&str>,
+    limit: i64,
+) -> sqlx::Result<Vec<RunRecord>> {
     let rows = sqlx::query_as::<_, DbRun>(
         r#"
-        SELECT id, name, kind, status, created_at, started_at, ended_at, exit_code, error
+        SELECT id, name, kind, status, created_at, started_at, ended_at, exit_code, error,
+            depends_on, experiment_id, archived_at, project
         FROM runs
+        WHERE ($1::uuid IS NULL OR experiment_id = $1)
+            AND ($3::text IS NULL OR project = $3)
         ORDER BY created_at DESC
-        LIMIT $1
+        LIMIT $2
         "#,
     )
+    .bind(experiment_id)
     .bind(limit)
-    .fetch_all(&state.pg)
-    .await
-    .map_err(internal_err)?;
+    .bind(project)
+    .fetch_all(pg)
+    .await?;
 
-    let out: Vec<RunRecord> = rows.into_iter().filter_map(|r| db_to_run_record(r).ok()).collect();
-    Ok(Json(out))
+    Ok(rows.into_iter().filter_map(|r| db_to_run_record(r).ok()).collect())
 }
 
 async fn get_run(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let row = sqlx::query_as::<_, DbRun>(
         r#"
-        SELECT id, name, kind, status, created_at, started_at, ended_at, exit_code, error
+        SELECT id, name, kind, status, created_at, started_at, ended_at, exit_code, error,
+            depends_on, experiment_id, archived_at, project
         FROM runs
         WHERE id = $1
         "#,
@@ -342,7 +578,7 @@ async fn get_run(
     .await
     .map_err(internal_err)?;
 
-    let Some(row) = row else {
+    let Some(row) = row.filter(|r| scope.can_see(&r.project)) else {
         return Err((StatusCode::NOT_FOUND, Json(json!({"error": "run not found"}))));
     };
 
@@ -350,28 +586,363 @@ async fn get_run(
     Ok(Json(out))
 }
 
+async fn create_experiment(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Json(req): Json<CreateExperimentRequest>,
+) -> Result<(StatusCode, Json<ExperimentRecord>), (StatusCode, Json<serde_json::Value>)> {
+    if req.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "name cannot be empty"})),
+        ));
+    }
+    let project = scope.project_for_create(req.project.as_deref())?;
+    ensure_project_exists(&state.pg, &project).await?;
+
+    let out = ExperimentRecord {
+        id: Uuid::new_v4(),
+        name: req.name,
+        description: req.description,
+        created_at: chrono::Utc::now(),
+        project,
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO experiments (id, name, description, created_at, project)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(out.id)
+    .bind(&out.name)
+    .bind(&out.description)
+    .bind(out.created_at)
+    .bind(&out.project)
+    .execute(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    Ok((StatusCode::CREATED, Json(out)))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListExperimentsQuery {
+    limit: Option<i64>,
+    project: Option<String>,
+}
+
+async fn list_experiments(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Query(q): Query<ListExperimentsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 500);
+    let project = scope.list_filter(q.project.as_deref())?;
+    let rows = sqlx::query_as::<_, DbExperiment>(
+        r#"
+        SELECT id, name, description, created_at, project
+        FROM experiments
+        WHERE $2::text IS NULL OR project = $2
+        ORDER BY created_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .bind(project)
+    .fetch_all(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let out: Vec<ExperimentRecord> = rows.into_iter().map(db_to_experiment_record).collect();
+    Ok(Json(out))
+}
+
+async fn get_experiment(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let row = sqlx::query_as::<_, DbExperiment>(
+        r#"
+        SELECT id, name, description, created_at, project
+        FROM experiments
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let Some(row) = row.filter(|r| scope.can_see(&r.project)) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "experiment not found"})),
+        ));
+    };
+    Ok(Json(db_to_experiment_record(row)))
+}
+
+async fn list_experiment_runs(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<ListRunsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let limit = q.limit.unwrap_or(500).clamp(1, 5000);
+    let out = fetch_runs(&state.pg, Some(id), scope.project.as_deref(), limit)
+        .await
+        .map_err(internal_err)?;
+    Ok(Json(out))
+}
+
+/// Streams очереди: сколько записей ждёт или взято и какие run'ы взяты, но не подтверждены
+async fn get_queue(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    let mut conn = state
+        .redis
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(redis_err)?;
+
+    let mut out = Vec::new();
+    for stream in RUN_STREAM_KEYS {
+        let length: u64 = conn.xlen(stream).await.map_err(redis_err)?;
+        // группы нет, пока worker ни разу не стартовал
+        let pending: StreamPendingCountReply = conn
+            .xpending_count(stream, RUN_STREAM_GROUP, "-", "+", 100)
+            .await
+            .unwrap_or_default();
+        let mut records = Vec::new();
+        for p in pending.ids {
+            let range: StreamRangeReply = conn
+                .xrange(stream, &p.id, &p.id)
+                .await
+                .map_err(redis_err)?;
+            let run_id = range
+                .ids
+                .first()
+                .and_then(|e| e.get::<String>(RUN_STREAM_FIELD))
+                .and_then(|s| s.parse().ok());
+            records.push(PendingRunRecord {
+                entry_id: p.id,
+                run_id,
+                consumer: p.consumer,
+                idle_ms: p.last_delivered_ms as u64,
+                deliveries: p.times_delivered as u64,
+            });
+        }
+        out.push(QueueStreamRecord {
+            stream: stream.to_string(),
+            length,
+            pending: records,
+        });
+    }
+    Ok(Json(out))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListAuditQuery {
+    limit: Option<i64>,
+    actor: Option<String>,
+}
+
+async fn list_audit(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Query(q): Query<ListAuditQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    let rows = sqlx::query_as::<_, DbAudit>(
+        r#"
+        SELECT id, ts, actor, method, path, route, status, body
+        FROM audit_log
+        WHERE $1::text IS NULL OR actor = $1
+        ORDER BY id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(&q.actor)
+    .bind(limit)
+    .fetch_all(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let out: Vec<AuditRecord> = rows
+        .into_iter()
+        .map(|r| AuditRecord {
+            id: r.id,
+            ts: r.ts,
+            actor: r.actor,
+            method: r.method,
+            path: r.path,
+            route: r.route,
+            status: r.status,
+            body: r.body,
+        })
+        .collect();
+    Ok(Json(out))
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamUpdatesQuery {
+    run_id: Option<Uuid>,
+}
+
+/// Ключу проекта — только обновления одного своего run'а
+async fn stream_run_updates(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Query(q): Query<StreamUpdatesQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    match q.run_id {
+        Some(id) => ensure_run_visible(&state, &scope, id).await?,
+        None if !scope.is_admin() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "run_id is required for project keys"})),
+            ));
+        }
+        None => {}
+    }
+    Ok(updates::sse(state.updates.subscribe(), q.run_id))
+}
+
+/// Queued — отменяется сразу; running — флаг в Redis, worker шлёт процессу SIGTERM
+/// (engine при этом штатно снимает ордера и сохраняет состояние).
+async fn cancel_run(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
+    let status: Option<String> = sqlx::query_scalar("SELECT status FROM runs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.pg)
+        .await
+        .map_err(internal_err)?;
+
+    let Some(status) = status else {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "run not found"}))));
+    };
+
+    match status.as_str() {
+        "queued" => {
+            sqlx::query(
+                r#"
+                UPDATE runs
+                SET status = 'cancelled', ended_at = NOW()
+                WHERE id = $1 AND status = 'queued'
+                "#,
+            )
+            .bind(id)
+            .execute(&state.pg)
+            .await
+            .map_err(internal_err)?;
+            let update = RunUpdate::status(id, RunStatus::Cancelled);
+            if let Err(e) = updates::notify(&state.pg, &update).await {
+                error!("notify for run {} failed: {}", id, e);
+            }
+            append_run_event(&state.pg, id, "cancelled before start")
+                .await
+                .map_err(internal_err)?;
+            Ok((StatusCode::OK, Json(json!({"status": "cancelled"}))))
+        }
+        "running" => {
+            let mut conn = state
+                .redis
+                .get_multiplexed_tokio_connection()
+                .await
+                .map_err(redis_err)?;
+            conn.set_ex::<_, _, ()>(run_cancel_key(id), 1, 24 * 60 * 60)
+                .await
+                .map_err(redis_err)?;
+            append_run_event(&state.pg, id, "cancel requested")
+                .await
+                .map_err(internal_err)?;
+            Ok((StatusCode::ACCEPTED, Json(json!({"status": "cancelling"}))))
+        }
+        _ => Err((
+            StatusCode::CONFLICT,
+            Json(json!({"error": format!("run already {}", status)})),
+        )),
+    }
+}
+
+async fn append_run_event(pg: &PgPool, run_id: Uuid, message: &str) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO run_events (run_id, ts, level, message, fields)
+        VALUES ($1, NOW(), 'info', $2, $3)
+        "#,
+    )
+    .bind(run_id)
+    .bind(message)
+    .bind(json!({"event": "cancel"}))
+    .execute(pg)
+    .await?;
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 struct ListEventsQuery {
     limit: Option<i64>,
+    /// Минимальный уровень: `warn` — только warn и error
+    level: Option<String>,
+    /// `fields.event`: `progress`, `artifacts`, `metrics`, `log`, `status`, ...
+    event: Option<String>,
+    /// Только более новые и по возрастанию `id` — для tail'а
+    after_id: Option<i64>,
 }
 
 async fn list_run_events(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Path(id): Path<Uuid>,
     Query(q): Query<ListEventsQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
     let limit = q.limit.unwrap_or(200).clamp(1, 2000);
+    let min_level = match q.level.as_deref() {
+        Some(s) => EventLevel::parse(s).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "level must be debug, info, warn or error"})),
+            )
+        })?,
+        None => EventLevel::Debug,
+    };
+    let levels: Vec<&str> = [
+        EventLevel::Debug,
+        EventLevel::Info,
+        EventLevel::Warn,
+        EventLevel::Error,
+    ]
+    .into_iter()
+    .filter(|l| *l >= min_level)
+    .map(EventLevel::as_str)
+    .collect();
     let rows = sqlx::query_as::<_, DbRunEvent>(
         r#"
-        SELECT id, run_id, ts, level, message
+        SELECT id, run_id, ts, level, message, fields
         FROM run_events
         WHERE run_id = $1
-        ORDER BY id DESC
+            AND level = ANY($3)
+            AND ($4::text IS NULL OR fields->>'event' = $4)
+            AND ($5::bigint IS NULL OR id > $5)
+        ORDER BY CASE WHEN $5::bigint IS NULL THEN -id ELSE id END
         LIMIT $2
         "#,
     )
     .bind(id)
     .bind(limit)
+    .bind(&levels)
+    .bind(&q.event)
+    .bind(q.after_id)
     .fetch_all(&state.pg)
     .await
     .map_err(internal_err)?;
@@ -382,8 +953,9 @@ async fn list_run_events(
             id: e.id,
             run_id: e.run_id,
             ts: e.ts,
-            level: e.level,
+            level: EventLevel::parse(&e.level).unwrap_or(EventLevel::Info),
             message: e.message,
+            fields: e.fields,
         })
         .collect();
     Ok(Json(out))
@@ -391,8 +963,10 @@ async fn list_run_events(
 
 async fn get_run_metrics(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
     let row = sqlx::query_as::<_, DbRunMetrics>(
         r#"
         SELECT run_id, payload, updated_at
@@ -405,29 +979,78 @@ async fn get_run_metrics(
     .await
     .map_err(internal_err)?;
 
+    // payload всегда в текущей схеме: записи до версионирования переводятся на лету
     let out = match row {
         Some(row) => json!({
             "run_id": row.run_id,
             "updated_at": row.updated_at,
-            "payload": row.payload
+            "payload": MetricsPayload::from_value(row.payload)
         }),
         None => json!({
             "run_id": id,
             "updated_at": serde_json::Value::Null,
-            "payload": serde_json::json!({})
+            "payload": MetricsPayload::from_value(serde_json::Value::Null)
         }),
     };
 
     Ok(Json(out))
 }
 
+#[derive(Debug, Deserialize)]
+struct MetricsHistoryQuery {
+    limit: Option<i64>,
+    /// Только снимки новее (для опроса идущего run'а)
+    after_id: Option<i64>,
+}
+
+/// Снимки по возрастанию времени: эволюция лучшего конфига sweep'а, equity live run'а
+async fn list_run_metrics_history(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<MetricsHistoryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
+    let limit = q.limit.unwrap_or(500).clamp(1, 5000);
+    let rows = sqlx::query_as::<_, DbRunMetricsSnapshot>(
+        r#"
+        SELECT id, run_id, ts, is_final, payload
+        FROM run_metrics_snapshots
+        WHERE run_id = $1 AND id > $2
+        ORDER BY id ASC
+        LIMIT $3
+        "#,
+    )
+    .bind(id)
+    .bind(q.after_id.unwrap_or(0))
+    .bind(limit)
+    .fetch_all(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let out: Vec<MetricsSnapshotRecord> = rows
+        .into_iter()
+        .map(|r| MetricsSnapshotRecord {
+            id: r.id,
+            run_id: r.run_id,
+            ts: r.ts,
+            is_final: r.is_final,
+            payload: MetricsPayload::from_value(r.payload),
+        })
+        .collect();
+    Ok(Json(out))
+}
+
 async fn get_run_artifacts(
     State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
     let rows = sqlx::query_as::<_, DbRunArtifact>(
         r#"
-        SELECT id, run_id, kind, path, created_at
+        SELECT id, run_id, kind, name, path, size_bytes, row_count, checksum, object_key,
+               created_at
         FROM run_artifacts
         WHERE run_id = $1
         ORDER BY id ASC
@@ -438,7 +1061,211 @@ async fn get_run_artifacts(
     .await
     .map_err(internal_err)?;
 
-    Ok(Json(rows))
+    let mut out: Vec<ArtifactRecord> = Vec::with_capacity(rows.len());
+    for r in rows {
+        let download_url = artifact_download_url(&state, r.object_key.as_deref()).await;
+        out.push(ArtifactRecord {
+            id: r.id,
+            run_id: r.run_id,
+            kind: ArtifactKind::parse(&r.kind).unwrap_or(ArtifactKind::Other),
+            name: r.name,
+            path: r.path,
+            size_bytes: r.size_bytes,
+            row_count: r.row_count,
+            checksum: r.checksum,
+            object_key: r.object_key,
+            download_url,
+            created_at: r.created_at,
+        });
+    }
+    Ok(Json(out))
+}
+
+/// Редирект на свежий presigned URL: ссылка в UI не протухает вместе с подписью
+async fn download_run_artifact(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path((id, artifact_id)): Path<(Uuid, i64)>,
+) -> Result<Redirect, (StatusCode, Json<serde_json::Value>)> {
+    ensure_run_visible(&state, &scope, id).await?;
+    let object_key: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT object_key FROM run_artifacts WHERE run_id = $1 AND id = $2",
+    )
+    .bind(id)
+    .bind(artifact_id)
+    .fetch_optional(&state.pg)
+    .await
+    .map_err(internal_err)?;
+    let Some(object_key) = object_key else {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "artifact not found"}))));
+    };
+    match artifact_download_url(&state, object_key.as_deref()).await {
+        Some(url) => Ok(Redirect::temporary(&url)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "artifact is not in object storage"})),
+        )),
+    }
+}
+
+async fn artifact_download_url(state: &AppState, object_key: Option<&str>) -> Option<String> {
+    let (store, key) = (state.artifacts.as_ref()?, object_key?);
+    match store.download_url(key).await {
+        Ok(url) => Some(url),
+        Err(e) => {
+            error!("presign of {} failed: {}", key, e);
+            None
+        }
+    }
+}
+
+async fn create_project(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Json(req): Json<CreateProjectRequest>,
+) -> Result<(StatusCode, Json<ProjectRecord>), (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    if !is_valid_project_name(&req.name) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "project name must be 1-64 chars of a-z, 0-9, _ and -"})),
+        ));
+    }
+    let row = sqlx::query_as::<_, DbProject>(
+        r#"
+        INSERT INTO projects (name, description)
+        VALUES ($1, $2)
+        ON CONFLICT (name) DO NOTHING
+        RETURNING name, description, created_at
+        "#,
+    )
+    .bind(&req.name)
+    .bind(&req.description)
+    .fetch_optional(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let Some(row) = row else {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({"error": "project already exists"})),
+        ));
+    };
+    Ok((
+        StatusCode::CREATED,
+        Json(ProjectRecord {
+            name: row.name,
+            description: row.description,
+            created_at: row.created_at,
+        }),
+    ))
+}
+
+/// Ключу проекта — только его проект
+async fn list_projects(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rows = sqlx::query_as::<_, DbProject>(
+        r#"
+        SELECT name, description, created_at
+        FROM projects
+        WHERE $1::text IS NULL OR name = $1
+        ORDER BY name ASC
+        "#,
+    )
+    .bind(&scope.project)
+    .fetch_all(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let out: Vec<ProjectRecord> = rows
+        .into_iter()
+        .map(|r| ProjectRecord {
+            name: r.name,
+            description: r.description,
+            created_at: r.created_at,
+        })
+        .collect();
+    Ok(Json(out))
+}
+
+async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    if let Some(project) = &req.project {
+        ensure_project_exists(&state.pg, project).await?;
+    }
+    let key = auth::generate_key();
+    let row = sqlx::query_as::<_, DbApiKey>(
+        r#"
+        INSERT INTO api_keys (key_hash, project, label)
+        VALUES ($1, $2, $3)
+        RETURNING id, key_hash, project, label, created_at, revoked_at
+        "#,
+    )
+    .bind(auth::key_hash(&key))
+    .bind(&req.project)
+    .bind(&req.label)
+    .fetch_one(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKey {
+            key,
+            record: db_to_api_key_record(row),
+        }),
+    ))
+}
+
+async fn list_api_keys(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    let rows = sqlx::query_as::<_, DbApiKey>(
+        r#"
+        SELECT id, key_hash, project, label, created_at, revoked_at
+        FROM api_keys
+        ORDER BY id ASC
+        "#,
+    )
+    .fetch_all(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let out: Vec<ApiKeyRecord> = rows.into_iter().map(db_to_api_key_record).collect();
+    Ok(Json(out))
+}
+
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(scope): Extension<Scope>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    scope.require_admin()?;
+    let row = sqlx::query_as::<_, DbApiKey>(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = COALESCE(revoked_at, NOW())
+        WHERE id = $1
+        RETURNING id, key_hash, project, label, created_at, revoked_at
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.pg)
+    .await
+    .map_err(internal_err)?;
+
+    let Some(row) = row else {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "api key not found"}))));
+    };
+    Ok(Json(db_to_api_key_record(row)))
 }
 
 #[derive(sqlx::FromRow)]
@@ -452,6 +1279,48 @@ struct DbRun {
     ended_at: Option<chrono::DateTime<chrono::Utc>>,
     exit_code: Option<i32>,
     error: Option<String>,
+    depends_on: Vec<Uuid>,
+    experiment_id: Option<Uuid>,
+    archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    project: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbAudit {
+    id: i64,
+    ts: chrono::DateTime<chrono::Utc>,
+    actor: Option<String>,
+    method: String,
+    path: String,
+    route: Option<String>,
+    status: i32,
+    body: Option<serde_json::Value>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbExperiment {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    project: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbProject {
+    name: String,
+    description: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbApiKey {
+    id: i64,
+    key_hash: String,
+    project: Option<String>,
+    label: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -461,6 +1330,7 @@ struct DbRunEvent {
     ts: chrono::DateTime<chrono::Utc>,
     level: String,
     message: String,
+    fields: serde_json::Value,
 }
 
 #[derive(sqlx::FromRow, serde::Serialize)]
@@ -470,12 +1340,26 @@ struct DbRunMetrics {
     updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(sqlx::FromRow, serde::Serialize)]
+#[derive(sqlx::FromRow)]
+struct DbRunMetricsSnapshot {
+    id: i64,
+    run_id: Uuid,
+    ts: chrono::DateTime<chrono::Utc>,
+    is_final: bool,
+    payload: serde_json::Value,
+}
+
+#[derive(sqlx::FromRow)]
 struct DbRunArtifact {
     id: i64,
     run_id: Uuid,
     kind: String,
+    name: String,
     path: String,
+    size_bytes: Option<i64>,
+    row_count: Option<i64>,
+    checksum: Option<String>,
+    object_key: Option<String>,
     created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -490,9 +1374,34 @@ fn db_to_run_record(r: DbRun) -> Result<RunRecord> {
         ended_at: r.ended_at,
         exit_code: r.exit_code,
         error: r.error,
+        depends_on: r.depends_on,
+        experiment_id: r.experiment_id,
+        archived_at: r.archived_at,
+        project: r.project,
     })
 }
 
+fn db_to_experiment_record(r: DbExperiment) -> ExperimentRecord {
+    ExperimentRecord {
+        id: r.id,
+        name: r.name,
+        description: r.description,
+        created_at: r.created_at,
+        project: r.project,
+    }
+}
+
+fn db_to_api_key_record(r: DbApiKey) -> ApiKeyRecord {
+    ApiKeyRecord {
+        id: r.id,
+        project: r.project,
+        label: r.label,
+        actor: auth::key_actor(&r.key_hash),
+        created_at: r.created_at,
+        revoked_at: r.revoked_at,
+    }
+}
+
 fn parse_run_kind(s: &str) -> Result<RunKind> {
     match s {
         "backtest_trend" => Ok(RunKind::BacktestTrend),
@@ -500,6 +1409,15 @@ fn parse_run_kind(s: &str) -> Result<RunKind> {
         "backtest_mm" => Ok(RunKind::BacktestMm),
         "backtest_mm_mtf" => Ok(RunKind::BacktestMmMtf),
         "backtest_mm_mtf_sweep" => Ok(RunKind::BacktestMmMtfSweep),
+        "backtest_mm_mtf_ga" => Ok(RunKind::BacktestMmMtfGa),
+        "backtest_mm_mtf_costs" => Ok(RunKind::BacktestMmMtfCosts),
+        "backtest_mm_mtf_stress" => Ok(RunKind::BacktestMmMtfStress),
+        "backtest_mm_portfolio" => Ok(RunKind::BacktestMmPortfolio),
+        "backtest_diff" => Ok(RunKind::BacktestDiff),
+        "data_download" => Ok(RunKind::DataDownload),
+        "report_generation" => Ok(RunKind::ReportGeneration),
+        "live" => Ok(RunKind::Live),
+        "paper" => Ok(RunKind::Paper),
         _ => anyhow::bail!("unknown run kind: {}", s),
     }
 }
@@ -508,6 +1426,7 @@ fn parse_run_status(s: &str) -> Result<RunStatus> {
     match s {
         "queued" => Ok(RunStatus::Queued),
         "running" => Ok(RunStatus::Running),
+        "cancelled" => Ok(RunStatus::Cancelled),
         "completed" => Ok(RunStatus::Completed),
         "failed" => Ok(RunStatus::Failed),
         _ => anyhow::bail!("unknown run status: {}", s),
@@ -0,0 +1,135 @@
+//! Prometheus метрики самого api: латентность запросов по маршрутам, глубина очередей,
+//! число run'ов по статусам и пул соединений Postgres. Gauge'и обновляются при scrape.
+
+use std::time::Instant;
+
+use anyhow::Result;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use orchestrator_core::models::{RUN_STREAM_GROUP, RUN_STREAM_KEYS};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use redis::AsyncCommands;
+use redis::streams::StreamPendingReply;
+use sqlx::PgPool;
+
+const RUN_STATUSES: [&str; 5] = ["queued", "running", "completed", "failed", "cancelled"];
+
+#[derive(Clone)]
+pub struct ServiceMetrics {
+    registry: Registry,
+    http_latency: HistogramVec,
+    queue_depth: IntGaugeVec,
+    queue_pending: IntGaugeVec,
+    runs: IntGaugeVec,
+    pool_size: IntGauge,
+    pool_idle: IntGauge,
+}
+
+impl ServiceMetrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("mmbot_api".to_string()), None)?;
+        let http_latency = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency").buckets(
+                vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0],
+            ),
+            &["method", "route", "status"],
+        )?;
+        let queue_depth = IntGaugeVec::new(
+            Opts::new(
+                "run_queue_depth",
+                "Runs waiting in Redis stream, not yet taken",
+            ),
+            &["queue"],
+        )?;
+        let queue_pending = IntGaugeVec::new(
+            Opts::new(
+                "run_queue_pending",
+                "Runs taken by workers and not yet acknowledged",
+            ),
+            &["queue"],
+        )?;
+        let runs = IntGaugeVec::new(Opts::new("runs", "Runs by status"), &["status"])?;
+        let pool_size = IntGauge::new("db_pool_connections", "Open Postgres connections")?;
+        let pool_idle = IntGauge::new("db_pool_idle_connections", "Idle Postgres connections")?;
+
+        registry.register(Box::new(http_latency.clone()))?;
+        registry.register(Box::new(queue_depth.clone()))?;
+        registry.register(Box::new(queue_pending.clone()))?;
+        registry.register(Box::new(runs.clone()))?;
+        registry.register(Box::new(pool_size.clone()))?;
+        registry.register(Box::new(pool_idle.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_latency,
+            queue_depth,
+            queue_pending,
+            runs,
+            pool_size,
+            pool_idle,
+        })
+    }
+
+    /// Очереди, статусы run'ов и пул — на момент scrape, затем текстовый формат Prometheus
+    pub async fn render(&self, pg: &PgPool, redis: &redis::Client) -> Result<String> {
+        let mut conn = redis.get_multiplexed_tokio_connection().await?;
+        for key in RUN_STREAM_KEYS {
+            // подтверждённые записи worker удаляет: в stream'е только ждущие и взятые
+            let len: i64 = conn.xlen(key).await?;
+            // группы нет, пока worker ни разу не стартовал
+            let pending = conn
+                .xpending::<_, _, StreamPendingReply>(key, RUN_STREAM_GROUP)
+                .await
+                .map_or(0, |r| r.count() as i64);
+            self.queue_depth
+                .with_label_values(&[key])
+                .set((len - pending).max(0));
+            self.queue_pending.with_label_values(&[key]).set(pending);
+        }
+
+        let counts: Vec<(String, i64)> =
+            sqlx::query_as("SELECT status, COUNT(*) FROM runs GROUP BY status")
+                .fetch_all(pg)
+                .await?;
+        for status in RUN_STATUSES {
+            let n = counts
+                .iter()
+                .find(|(s, _)| s == status)
+                .map_or(0, |(_, n)| *n);
+            self.runs.with_label_values(&[status]).set(n);
+        }
+
+        self.pool_size.set(pg.size() as i64);
+        self.pool_idle.set(pg.num_idle() as i64);
+
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+/// Латентность по шаблону маршрута (`/runs/{id}`), чтобы id не плодили серии
+pub async fn track_latency(
+    State(metrics): State<ServiceMetrics>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |p| p.as_str())
+        .to_string();
+    let method = req.method().to_string();
+    let started = Instant::now();
+    let resp = next.run(req).await;
+    metrics
+        .http_latency
+        .with_label_values(&[method.as_str(), route.as_str(), resp.status().as_str()])
+        .observe(started.elapsed().as_secs_f64());
+    resp
+}
@@ -0,0 +1,89 @@
+//! Обновления run'ов из Postgres LISTEN `RUN_UPDATES_CHANNEL` — в broadcast, оттуда
+//! SSE подписчикам: дашборду не нужно опрашивать API, пока run идёт.
+
+use std::{convert::Infallible, time::Duration};
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::{Stream, stream};
+use orchestrator_core::models::{RUN_UPDATES_CHANNEL, RunUpdate, RunUpdateKind};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Сколько обновлений может отстать медленный подписчик, прежде чем получит `lagged`
+const BROADCAST_CAPACITY: usize = 1024;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Слушает канал в фоне (с переподключением) и раздаёт обновления всем подписчикам
+pub fn spawn_listener(pg: PgPool) -> broadcast::Sender<RunUpdate> {
+    let (tx, _) = broadcast::channel(BROADCAST_CAPACITY);
+    let sender = tx.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&pg, &sender).await {
+                warn!("run updates listener failed: {}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+    tx
+}
+
+async fn listen(pg: &PgPool, tx: &broadcast::Sender<RunUpdate>) -> sqlx::Result<()> {
+    let mut listener = PgListener::connect_with(pg).await?;
+    listener.listen(RUN_UPDATES_CHANNEL).await?;
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str::<RunUpdate>(notification.payload()) {
+            // нет подписчиков — не ошибка
+            Ok(update) => {
+                let _ = tx.send(update);
+            }
+            Err(e) => warn!("invalid run update '{}': {}", notification.payload(), e),
+        }
+    }
+}
+
+/// NOTIFY из API (отмена queued run'а идёт мимо worker'а)
+pub async fn notify(pg: &PgPool, update: &RunUpdate) -> anyhow::Result<()> {
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(RUN_UPDATES_CHANNEL)
+        .bind(serde_json::to_string(update)?)
+        .execute(pg)
+        .await?;
+    Ok(())
+}
+
+/// SSE: `event: status` / `event: metrics` с `RunUpdate` в data; `run_id` — только этот run.
+/// `event: lagged` — подписчик отстал и часть обновлений пропущена, стоит перечитать состояние
+pub fn sse(
+    rx: broadcast::Receiver<RunUpdate>,
+    run_id: Option<Uuid>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream::unfold(rx, move |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(update) if run_id.is_none_or(|id| id == update.run_id) => {
+                    let name = match update.kind {
+                        RunUpdateKind::Status => "status",
+                        RunUpdateKind::Metrics => "metrics",
+                    };
+                    let event = Event::default()
+                        .event(name)
+                        .json_data(&update)
+                        .unwrap_or_else(|_| Event::default().event(name));
+                    return Some((Ok(event), rx));
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    let event = Event::default().event("lagged").data(n.to_string());
+                    return Some((Ok(event), rx));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
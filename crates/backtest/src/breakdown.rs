@@ -0,0 +1,123 @@
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+/// PnL/ROI/число сделок за календарный период (UTC)
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodRow {
+    pub period: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub start_equity: f64,
+    pub end_equity: f64,
+    pub pnl: f64,
+    pub roi_pct: f64,
+    pub trades: usize,
+}
+
+fn month_of(ts: i64) -> String {
+    Utc.timestamp_millis_opt(ts)
+        .single()
+        .map(|t| t.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+/// Помесячная разбивка equity-кривой `(ts, equity)`.
+/// Период начинается с equity на конце предыдущего (первый — с `initial_equity`),
+/// последний закрывается `final_equity` (после force close) — сумма PnL равна итоговому.
+/// `trade_ts` — моменты сделок/fill'ов для счётчика.
+pub fn monthly_breakdown(
+    initial_equity: f64,
+    points: &[(i64, f64)],
+    final_equity: f64,
+    trade_ts: &[i64],
+) -> Vec<PeriodRow> {
+    let mut out: Vec<PeriodRow> = Vec::new();
+    for &(ts, equity) in points {
+        let period = month_of(ts);
+        match out.last_mut() {
+            Some(row) if row.period == period => {
+                row.end_ts = ts;
+                row.end_equity = equity;
+            }
+            last => {
+                let start_equity = last.map_or(initial_equity, |r| r.end_equity);
+                out.push(PeriodRow {
+                    period,
+                    start_ts: ts,
+                    end_ts: ts,
+                    start_equity,
+                    end_equity: equity,
+                    pnl: 0.0,
+                    roi_pct: 0.0,
+                    trades: 0,
+                });
+            }
+        }
+    }
+    if let Some(last) = out.last_mut() {
+        last.end_equity = final_equity;
+    }
+    for &ts in trade_ts {
+        let period = month_of(ts);
+        if let Some(row) = out.iter_mut().find(|r| r.period == period) {
+            row.trades += 1;
+        }
+    }
+    for row in out.iter_mut() {
+        row.pnl = row.end_equity - row.start_equity;
+        row.roi_pct = if row.start_equity > 0.0 {
+            100.0 * row.pnl / row.start_equity
+        } else {
+            0.0
+        };
+    }
+    out
+}
+
+/// Сводка разбивки для stdout: лучший/худший период и доля прибыльных
+pub fn breakdown_summary(rows: &[PeriodRow]) -> String {
+    let best = rows.iter().max_by(|a, b| a.pnl.total_cmp(&b.pnl));
+    let worst = rows.iter().min_by(|a, b| a.pnl.total_cmp(&b.pnl));
+    let (Some(best), Some(worst)) = (best, worst) else {
+        return "periods=0".to_string();
+    };
+    let total: f64 = rows.iter().map(|r| r.pnl).sum();
+    let best_share = if total > 0.0 {
+        100.0 * best.pnl / total
+    } else {
+        0.0
+    };
+    format!(
+        "periods={} profitable_periods={} best_period={} best_period_pnl={:.4} best_period_share={:.2}% worst_period={} worst_period_pnl={:.4}",
+        rows.len(),
+        rows.iter().filter(|r| r.pnl > 0.0).count(),
+        best.period,
+        best.pnl,
+        best_share,
+        worst.period,
+        worst.pnl
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_equity_by_month() {
+        // 2026-01-31 и 2026-02-01/02 UTC
+        let jan = 1_769_817_600_000;
+        let feb = 1_769_904_000_000;
+        let points = [(jan, 105.0), (feb, 102.0), (feb + 86_400_000, 110.0)];
+        let rows = monthly_breakdown(100.0, &points, 108.0, &[jan, feb, feb + 1]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].period, "2026-01");
+        assert_eq!((rows[0].pnl, rows[0].trades), (5.0, 1));
+        assert_eq!(rows[1].period, "2026-02");
+        assert_eq!((rows[1].start_equity, rows[1].end_equity), (105.0, 108.0));
+        assert_eq!(rows[1].trades, 2);
+        let total: f64 = rows.iter().map(|r| r.pnl).sum();
+        assert_eq!(total, 8.0);
+        assert!(breakdown_summary(&rows).starts_with("periods=2 profitable_periods=2"));
+    }
+}
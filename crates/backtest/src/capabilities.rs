@@ -0,0 +1,96 @@
+//! `--capabilities`: бинарь печатает JSON с версией протокола (строки `progress:`,
+//! `artifacts:`, `key=value` в stdout, `--report-out`), версией стратегий и своими флагами
+//! и выходит. Worker сверяет его с аргументами run'а до запуска.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use clap::Command;
+use serde::Serialize;
+
+pub const CAPABILITIES_FLAG: &str = "--capabilities";
+/// Меняется при несовместимой смене вывода бинарей, который разбирает worker
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Версия crate'ов со стратегиями (общая для workspace)
+pub const STRATEGY_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub binary: String,
+    pub protocol_version: u32,
+    pub strategy_version: String,
+    /// Длинные флаги без `--`
+    pub flags: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn of(cmd: &Command, binary: &str) -> Self {
+        let mut flags: Vec<String> = cmd
+            .get_arguments()
+            .filter_map(|a| a.get_long())
+            .map(str::to_string)
+            .collect();
+        let own = &CAPABILITIES_FLAG[2..];
+        if !flags.iter().any(|f| f == own) {
+            flags.push(own.to_string());
+        }
+        flags.sort();
+        Self {
+            binary: binary.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            strategy_version: STRATEGY_VERSION.to_string(),
+            flags,
+        }
+    }
+}
+
+/// Имя бинаря из `argv[0]`
+pub fn binary_name(argv: &[OsString]) -> String {
+    argv.first()
+        .and_then(|a| Path::new(a).file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// С `--capabilities` в аргументах печатает JSON и завершает процесс; до разбора флагов,
+/// поэтому обязательные флаги не нужны
+pub fn exit_if_requested(cmd: &Command, argv: &[OsString]) {
+    if !argv.iter().skip(1).any(|a| a == CAPABILITIES_FLAG) {
+        return;
+    }
+    let caps = Capabilities::of(cmd, &binary_name(argv));
+    match serde_json::to_string(&caps) {
+        Ok(json) => {
+            println!("{}", json);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("capabilities serialization failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Parser, Debug)]
+    struct Args {
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = false)]
+        quiet: bool,
+    }
+
+    #[test]
+    fn lists_long_flags_and_versions() {
+        let caps = Capabilities::of(&Args::command(), "backtest_mm");
+        assert_eq!(caps.flags, vec!["capabilities", "quiet", "symbol"]);
+        assert_eq!(caps.protocol_version, PROTOCOL_VERSION);
+        let json = serde_json::to_value(&caps).unwrap();
+        assert_eq!(json["binary"], "backtest_mm");
+        assert_eq!(json["strategy_version"], STRATEGY_VERSION);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Первая строка: для какого прогона (данные, диапазон, издержки) посчитаны конфиги
+#[derive(Serialize, Deserialize)]
+struct Header {
+    run: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<R> {
+    key: String,
+    report: R,
+}
+
+/// Чекпоинт sweep'а: JSONL, строка на посчитанный конфиг.
+///
+/// С `resume` уже посчитанные конфиги берутся из файла; оборванная последняя
+/// строка (падение посреди записи) пропускается.
+pub struct Checkpoint<R> {
+    done: HashMap<String, R>,
+    writer: BufWriter<File>,
+    flush_every: usize,
+    pending: usize,
+}
+
+impl<R: Serialize + DeserializeOwned> Checkpoint<R> {
+    pub fn open(path: &str, run: &str, resume: bool, flush_every: usize) -> Result<Self> {
+        let mut done = HashMap::new();
+        let mut append = false;
+        let mut torn_tail = false;
+        if resume && Path::new(path).exists() {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("read checkpoint {} failed", path))?;
+            torn_tail = !content.is_empty() && !content.ends_with('\n');
+            let mut lines = content.lines();
+            if let Some(first) = lines.next() {
+                let header: Header =
+                    serde_json::from_str(first).context("bad checkpoint header")?;
+                if header.run != run {
+                    anyhow::bail!(
+                        "checkpoint {} was written for a different run: {}",
+                        path,
+                        header.run
+                    );
+                }
+                append = true;
+            }
+            for line in lines {
+                let Ok(entry) = serde_json::from_str::<Entry<R>>(line) else {
+                    continue;
+                };
+                done.insert(entry.key, entry.report);
+            }
+        }
+
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .with_context(|| format!("open checkpoint {} failed", path))?;
+        let mut writer = BufWriter::new(file);
+        if torn_tail {
+            writer.write_all(b"\n")?;
+        }
+        if !append {
+            serde_json::to_writer(
+                &mut writer,
+                &Header {
+                    run: run.to_string(),
+                },
+            )?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+
+        Ok(Self {
+            done,
+            writer,
+            flush_every: flush_every.max(1),
+            pending: 0,
+        })
+    }
+
+    /// Сколько конфигов взято из файла
+    pub fn resumed(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&R> {
+        self.done.get(key)
+    }
+
+    /// Дописывает результат; на диск — раз в `flush_every` конфигов
+    pub fn record(&mut self, key: String, report: &R) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &Entry { key, report })?;
+        self.writer.write_all(b"\n")?;
+        self.pending += 1;
+        if self.pending >= self.flush_every {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Результат из чекпоинта, иначе `run()` с записью в файл.
+    /// Посчитанное остаётся доступным через [`Checkpoint::get`].
+    pub fn get_or_run(&mut self, key: String, run: impl FnOnce() -> R) -> Result<R>
+    where
+        R: Clone,
+    {
+        if let Some(r) = self.done.get(&key) {
+            return Ok(r.clone());
+        }
+        let r = run();
+        self.insert(key, r.clone())?;
+        Ok(r)
+    }
+
+    /// Результат, посчитанный вне чекпоинта (параллельные прогоны): запись и доступ через `get`
+    pub fn insert(&mut self, key: String, report: R) -> Result<()> {
+        self.record(key.clone(), &report)?;
+        self.done.insert(key, report);
+        Ok(())
+    }
+
+    /// Убирает результат из памяти (в файле он остаётся)
+    pub fn forget(&mut self, key: &str) {
+        self.done.remove(key);
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_skips_done_and_rejects_other_run() {
+        let dir = std::env::temp_dir().join(format!("ckpt_test_{}", std::process::id()));
+        let path = dir.join("sweep.jsonl");
+        let path = path.to_str().unwrap();
+
+        let mut ck = Checkpoint::<f64>::open(path, "run-a", false, 10).unwrap();
+        ck.record("a".to_string(), &1.5).unwrap();
+        ck.record("b".to_string(), &2.5).unwrap();
+        drop(ck);
+        // оборванная запись в конце
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(b"{\"key\":\"c\",\"rep")
+            .unwrap();
+
+        let mut ck = Checkpoint::<f64>::open(path, "run-a", true, 10).unwrap();
+        assert_eq!(ck.resumed(), 2);
+        assert_eq!(ck.get("b"), Some(&2.5));
+        assert_eq!(ck.get("c"), None);
+        ck.record("c".to_string(), &3.5).unwrap();
+        drop(ck);
+
+        let ck = Checkpoint::<f64>::open(path, "run-a", true, 10).unwrap();
+        assert_eq!(ck.get("c"), Some(&3.5));
+        drop(ck);
+
+        assert!(Checkpoint::<f64>::open(path, "run-b", true, 10).is_err());
+        let ck = Checkpoint::<f64>::open(path, "run-b", false, 10).unwrap();
+        assert_eq!(ck.resumed(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::ffi::OsString;
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command, Parser};
+
+use crate::capabilities;
+
+/// Аргументы backtest бинаря с учётом `--config backtest.toml` (или JSON-артефакта
+/// `*_config.json` прошлого прогона). Ключи файла — имена флагов (`maker_fee_bps = 10`,
+/// списки — массивом или строкой) — подставляются перед флагами CLI, поэтому CLI их
+/// перекрывает. Таблица `[<имя бинаря>]` перекрывает ключи верхнего уровня,
+/// остальные таблицы пропускаются: один файл на несколько бинарей.
+/// С `--capabilities` печатает возможности бинаря и завершает процесс.
+pub fn parse_args<T: Parser>() -> Result<T> {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let bin = capabilities::binary_name(&argv);
+    let cmd = command(T::command());
+    capabilities::exit_if_requested(&cmd, &argv);
+
+    let file_args = match config_path(&argv) {
+        Some(path) => {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("read config {} failed", path))?;
+            config_args(&cmd, &bin, &path, &text)
+                .with_context(|| format!("config {} is invalid", path))?
+        }
+        None => Vec::new(),
+    };
+    let mut full: Vec<OsString> = Vec::with_capacity(argv.len() + file_args.len());
+    full.extend(argv.first().cloned());
+    full.extend(file_args.into_iter().map(OsString::from));
+    full.extend(argv.into_iter().skip(1));
+
+    let matches = cmd.get_matches_from(full);
+    Ok(T::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
+}
+
+/// Повтор флага перекрывает прежнее значение; значения enum без учёта регистра
+/// (в JSON-артефакте они могут быть записаны иначе, чем в CLI)
+fn command(cmd: Command) -> Command {
+    cmd.args_override_self(true)
+        .mut_args(|a| {
+            if a.get_action().takes_values() {
+                a.ignore_case(true)
+            } else {
+                a
+            }
+        })
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Конфиг прогона (TOML или JSON); флаги CLI перекрывают его значения"),
+        )
+        .arg(
+            Arg::new("capabilities")
+                .long("capabilities")
+                .action(ArgAction::SetTrue)
+                .help("Флаги, версия протокола и стратегий в JSON для worker'а"),
+        )
+}
+
+fn config_path(argv: &[OsString]) -> Option<String> {
+    let mut it = argv.iter().skip(1).map(|a| a.to_string_lossy());
+    while let Some(a) = it.next() {
+        if a == "--config" {
+            return it.next().map(|p| p.into_owned());
+        }
+        if let Some(p) = a.strip_prefix("--config=") {
+            return Some(p.to_string());
+        }
+    }
+    None
+}
+
+/// Флаги `--key=value` из файла конфига для команды `cmd`
+fn config_args(cmd: &Command, bin: &str, path: &str, text: &str) -> Result<Vec<String>> {
+    let table: toml::Table = if path.ends_with(".json") {
+        let json: serde_json::Map<String, serde_json::Value> = serde_json::from_str(text)?;
+        // null — незаданный Option, флаг не передаётся
+        let json: serde_json::Map<_, _> = json.into_iter().filter(|(_, v)| !v.is_null()).collect();
+        match toml::Value::try_from(json)? {
+            toml::Value::Table(t) => t,
+            _ => anyhow::bail!("config must be an object"),
+        }
+    } else {
+        text.parse()?
+    };
+
+    let mut out = Vec::new();
+    let mut section = None;
+    for (key, value) in &table {
+        match value {
+            toml::Value::Table(t) if key == bin => section = Some(t),
+            toml::Value::Table(_) => {}
+            _ => out.extend(flag(cmd, key, value)?),
+        }
+    }
+    for (key, value) in section.into_iter().flatten() {
+        out.extend(flag(cmd, key, value)?);
+    }
+    Ok(out)
+}
+
+fn flag(cmd: &Command, key: &str, value: &toml::Value) -> Result<Option<String>> {
+    let long = key.replace('_', "-");
+    let Some(arg) = cmd
+        .get_arguments()
+        .find(|a| a.get_long() == Some(long.as_str()))
+    else {
+        anyhow::bail!("unknown key: {}", key);
+    };
+    if matches!(arg.get_action(), ArgAction::SetTrue) {
+        return match value {
+            toml::Value::Boolean(true) => Ok(Some(format!("--{}", long))),
+            toml::Value::Boolean(false) => Ok(None),
+            _ => anyhow::bail!("{} must be a bool", key),
+        };
+    }
+    let value = match value {
+        toml::Value::Array(items) => items
+            .iter()
+            .map(scalar)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("{} must be a list of scalars", key))?
+            .join(","),
+        v => scalar(v).with_context(|| format!("{} must be a scalar or a list", key))?,
+    };
+    Ok(Some(format!("--{}={}", long, value)))
+}
+
+fn scalar(value: &toml::Value) -> Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        toml::Value::Datetime(d) => Ok(d.to_string()),
+        _ => anyhow::bail!("nested value"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    #[derive(Parser, Debug)]
+    struct Args {
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = 10.0)]
+        maker_fee_bps: f64,
+        #[arg(long, default_value = "5")]
+        levels_list: String,
+        #[arg(long, default_value_t = false)]
+        refresh: bool,
+    }
+
+    fn parse(cli: &[&str], path: &str, text: &str) -> Result<Args> {
+        let cmd = command(Args::command());
+        let mut argv = vec!["backtest_mm".to_string()];
+        argv.extend(config_args(&cmd, "backtest_mm", path, text)?);
+        argv.extend(cli.iter().map(|s| s.to_string()));
+        Ok(Args::from_arg_matches(&cmd.try_get_matches_from(argv)?)?)
+    }
+
+    #[test]
+    fn cli_flags_override_config_file() {
+        let toml = r#"
+            symbol = "BTCUSDT"
+            maker_fee_bps = 5
+            levels_list = [3, 5, 7]
+            refresh = true
+
+            [backtest_mm]
+            maker_fee_bps = 2.5
+
+            [backtest_trend]
+            ema_fast = 10
+        "#;
+        let a = parse(&[], "b.toml", toml).unwrap();
+        assert_eq!(a.symbol, "BTCUSDT");
+        assert_eq!(a.maker_fee_bps, 2.5);
+        assert_eq!(a.levels_list, "3,5,7");
+        assert!(a.refresh);
+
+        let a = parse(
+            &["--maker-fee-bps", "7", "--symbol", "ETHUSDT"],
+            "b.toml",
+            toml,
+        )
+        .unwrap();
+        assert_eq!((a.symbol.as_str(), a.maker_fee_bps), ("ETHUSDT", 7.0));
+
+        // артефакт прошлого прогона
+        let json =
+            r#"{"symbol": "SOLUSDT", "maker_fee_bps": -1.5, "levels_list": "4", "refresh": false}"#;
+        let a = parse(&[], "run_config.json", json).unwrap();
+        assert_eq!((a.symbol.as_str(), a.maker_fee_bps), ("SOLUSDT", -1.5));
+        assert!(!a.refresh);
+
+        assert!(parse(&[], "b.toml", "makr_fee_bps = 1\nsymbol = \"X\"").is_err());
+    }
+}
@@ -0,0 +1,833 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, TimeZone, Utc};
+use clap::ValueEnum;
+
+use ::data::store::{CandleStore as LocalStore, SeriesKey};
+use bybit::rest::{BybitRest, download_range_resumable};
+use core::types::{Price, Qty, TimestampMs};
+use exchange::binance::BinanceExchange;
+use exchange::traits::Exchange;
+use storage::gaps::{GapRepair, backfill_windows, find_gaps, subtract_known};
+use storage::postgres::CandleStore;
+use storage::series::CandleSeries;
+use structure::candle::Candle;
+
+use crate::quality::{QualityGate, QualityMode, check_alignment, check_candles, describe};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Биржа исторических свечей (`--exchange`); формат кэша у всех одинаковый
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Venue {
+    #[default]
+    Bybit,
+    /// Binance spot, только публичные свечи
+    Binance,
+}
+
+impl Venue {
+    pub fn name(self) -> &'static str {
+        match self {
+            Venue::Bybit => "bybit",
+            Venue::Binance => "binance",
+        }
+    }
+}
+
+/// Строка кэша свечей (CSV/Parquet)
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct CandleRow {
+    pub ts: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+pub fn date_to_ms(date: &str) -> Result<i64> {
+    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("bad date: {}", date))?;
+    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
+    Ok(dt.timestamp_millis())
+}
+
+/// `[start 00:00, end 23:59:59.999]` в мс (обе даты включительно)
+pub fn date_range_ms(start: &str, end: &str) -> Result<(i64, i64)> {
+    Ok((date_to_ms(start)?, date_to_ms(end)? + DAY_MS - 1))
+}
+
+pub fn parse_interval_ms(interval: &str) -> Result<i64> {
+    let mins: i64 = interval
+        .parse()
+        .with_context(|| format!("interval must be numeric minutes, got {}", interval))?;
+    if mins <= 0 {
+        anyhow::bail!("interval must be > 0");
+    }
+    Ok(mins * 60 * 1000)
+}
+
+/// Символы через запятую: `"BTCUSDT,ETHUSDT"`
+pub fn parse_symbols(s: &str) -> Result<Vec<String>> {
+    let symbols: Vec<String> = s
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect();
+    if symbols.is_empty() {
+        anyhow::bail!("symbol cannot be empty");
+    }
+    Ok(symbols)
+}
+
+/// Кэш символа при нескольких символах: `data/x.csv` → `data/x_ETHUSDT.csv`
+pub fn symbol_cache_path(cache: &str, symbol: &str) -> String {
+    let path = Path::new(cache);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path
+            .with_file_name(format!(
+                "{}_{}.{}",
+                stem.to_string_lossy(),
+                symbol,
+                ext.to_string_lossy()
+            ))
+            .to_string_lossy()
+            .into_owned(),
+        _ => format!("{}_{}", cache, symbol),
+    }
+}
+
+/// Список значений для sweep'а: `"3,5,7"`
+pub fn parse_num_list<T>(s: &str, name: &str) -> Result<Vec<T>>
+where
+    T: std::str::FromStr,
+    <T as std::str::FromStr>::Err: std::fmt::Display,
+{
+    let mut out = Vec::new();
+    for raw in s.split(',') {
+        let v = raw.trim();
+        if v.is_empty() {
+            continue;
+        }
+        let parsed = v
+            .parse::<T>()
+            .map_err(|e| anyhow::anyhow!("bad value in {}: '{}' ({})", name, v, e))?;
+        out.push(parsed);
+    }
+    if out.is_empty() {
+        anyhow::bail!("{} cannot be empty", name);
+    }
+    Ok(out)
+}
+
+/// Начало out-of-sample: последняя доля `oos` времени свечей (`None` при `oos == 0`)
+pub fn oos_start_ms(candles: &[Candle], oos: f64) -> Result<Option<i64>> {
+    if !(0.0..1.0).contains(&oos) {
+        anyhow::bail!("oos_split must be in [0, 1)");
+    }
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return Ok(None);
+    };
+    if oos == 0.0 {
+        return Ok(None);
+    }
+    let span = (last.ts.0 - first.ts.0) as f64;
+    Ok(Some(last.ts.0 - (span * oos).round() as i64))
+}
+
+/// Свечи до `ts` и начиная с него
+pub fn split_at_ms(candles: &[Candle], ts: i64) -> (&[Candle], &[Candle]) {
+    candles.split_at(candles.partition_point(|c| c.ts.0 < ts))
+}
+
+/// Кэш свечей в CSV или Parquet (формат — по содержимому файла)
+pub fn read_cache(path: &str) -> Result<Vec<Candle>> {
+    Ok(crate::table::read_candle_rows(path)?
+        .into_iter()
+        .map(|row| Candle {
+            ts: TimestampMs(row.ts),
+            open: Price(row.open),
+            high: Price(row.high),
+            low: Price(row.low),
+            close: Price(row.close),
+            volume: Qty(row.volume),
+        })
+        .collect())
+}
+
+/// `.parquet` — Parquet (feature `parquet`), иначе CSV
+pub fn write_cache(path: &str, candles: &[Candle]) -> Result<()> {
+    crate::table::write_table(
+        path,
+        candles.iter().map(|c| CandleRow {
+            ts: c.ts.0,
+            open: c.open.0,
+            high: c.high.0,
+            low: c.low.0,
+            close: c.close.0,
+            volume: c.volume.0,
+        }),
+    )
+}
+
+/// Что лежит в кэше: пишется рядом с ним (`<cache>.meta.json`) при загрузке
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CacheMeta {
+    pub symbol: String,
+    /// Интервал свечей в минутах; `trades` — кэш сделок
+    pub interval: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Биржа данных; в meta до появления `--exchange` его нет — это Bybit
+    #[serde(default)]
+    pub exchange: Venue,
+    /// Пропуски свечей, которых нет и у биржи: докачка их больше не пробует
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known_gaps: Vec<(i64, i64)>,
+}
+
+impl CacheMeta {
+    fn path(cache: &str) -> String {
+        format!("{}.meta.json", cache)
+    }
+
+    pub fn read(cache: &str) -> Option<Self> {
+        let raw = std::fs::read_to_string(Self::path(cache)).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    pub fn write(&self, cache: &str) -> Result<()> {
+        crate::report::write_json(&Self::path(cache), self)
+    }
+
+    /// Тот же символ и интервал, диапазон не уже запрошенного
+    pub fn covers(&self, symbol: &str, interval: &str, (start_ms, end_ms): (i64, i64)) -> bool {
+        self.symbol.eq_ignore_ascii_case(symbol)
+            && self.interval == interval
+            && self.start_ms <= start_ms
+            && self.end_ms >= end_ms
+    }
+}
+
+fn compact_date(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .map(|t| t.format("%Y%m%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Кэш по умолчанию: `data/cache/ETHUSDT_funding_20240101_20240301.csv`
+/// (`kind` — `trades`, `funding` или интервал в минутах)
+pub fn default_cache_path(symbol: &str, kind: &str, (start_ms, end_ms): (i64, i64)) -> String {
+    let kind = if kind.parse::<i64>().is_ok() {
+        format!("{}m", kind)
+    } else {
+        kind.to_string()
+    };
+    format!(
+        "data/cache/{}_{}_{}_{}.csv",
+        symbol.to_ascii_uppercase(),
+        kind,
+        compact_date(start_ms),
+        compact_date(end_ms)
+    )
+}
+
+/// Явно заданный кэш (при нескольких символах — `<cache>_<SYMBOL>`) или путь по умолчанию
+pub fn cache_path_for(
+    cache: Option<&str>,
+    multi: bool,
+    symbol: &str,
+    kind: &str,
+    range: (i64, i64),
+) -> String {
+    match cache {
+        Some(c) if multi => symbol_cache_path(c, symbol),
+        Some(c) => c.to_string(),
+        None => default_cache_path(symbol, kind, range),
+    }
+}
+
+/// Явно заданный файл-кэш свечей символа (при нескольких символах — `<cache>_<SYMBOL>`);
+/// `None` — свечи из хранилища
+pub fn explicit_cache(cache: Option<&str>, multi: bool, symbol: &str) -> Option<String> {
+    match cache {
+        Some(c) if multi => Some(symbol_cache_path(c, symbol)),
+        other => other.map(str::to_string),
+    }
+}
+
+/// Покрывают ли свечи кэша без meta запрошенный диапазон с этим интервалом
+/// (допуск — одна свеча с каждого края)
+fn candles_cover(candles: &[Candle], interval_ms: i64, (start_ms, end_ms): (i64, i64)) -> bool {
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return false;
+    };
+    let step = candles
+        .windows(2)
+        .map(|w| w[1].ts.0 - w[0].ts.0)
+        .filter(|d| *d > 0)
+        .min();
+    step.is_none_or(|d| d == interval_ms)
+        && first.ts.0 <= start_ms + interval_ms
+        && last.ts.0 >= end_ms - 2 * interval_ms
+}
+
+/// Свечи `[start, end]` после проверки качества (пропуски, дубли, нулевые цены):
+/// `strict` — ошибка с отчётом, `repair` — одна попытка перекачать серию, `off` — без проверки.
+/// Источник — локальный кэш, общее хранилище или биржа `venue`.
+pub async fn load_candles(
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    cache: Option<&str>,
+    refresh: bool,
+    gate: QualityGate,
+) -> Result<Vec<Candle>> {
+    let candles = fetch_candles(venue, symbol, interval, range, cache, refresh).await?;
+    if gate.mode == QualityMode::Off {
+        return Ok(candles);
+    }
+    let interval_ms = parse_interval_ms(interval)?;
+    // незакрытые и будущие свечи не считаются пропусками
+    let checked = (
+        range.0,
+        range.1.min(Utc::now().timestamp_millis() - interval_ms),
+    );
+    let mut report = check_candles(&candles, interval_ms, checked);
+    if gate.passes(&report) {
+        if report.missing > 0 {
+            println!(
+                "data_quality: symbol={} interval={}m {}",
+                symbol,
+                interval,
+                report.summary()
+            );
+        }
+        return Ok(candles);
+    }
+    if gate.mode == QualityMode::Repair && !refresh {
+        println!(
+            "data_quality: symbol={} interval={}m {} — re-downloading",
+            symbol,
+            interval,
+            report.summary()
+        );
+        let candles = fetch_candles(venue, symbol, interval, range, cache, true).await?;
+        report = check_candles(&candles, interval_ms, checked);
+        if gate.passes(&report) {
+            return Ok(candles);
+        }
+    }
+    anyhow::bail!(
+        "data quality check failed for {} {}m {}..{} (max_missing_pct={}):\n{}\n\
+         use --data-quality repair to re-download or --data-quality off to skip the check",
+        symbol,
+        interval,
+        compact_date(range.0),
+        compact_date(range.1),
+        gate.max_missing_pct,
+        describe(&report, 10)
+    )
+}
+
+/// Проверка MTF данных: каждое HTF окно `[start, end]` покрыто LTF свечами.
+/// Окно без LTF свечей — ошибка (в нём сетка молча не исполняется), частично
+/// покрытые окна (пропуски в пределах `max_missing_pct`) печатаются строкой `ltf_coverage:`.
+pub fn ensure_ltf_coverage(
+    symbol: &str,
+    htf: &[Candle],
+    ltf: &[Candle],
+    (htf_ms, ltf_ms): (i64, i64),
+    range: (i64, i64),
+    gate: QualityGate,
+) -> Result<()> {
+    if gate.mode == QualityMode::Off {
+        return Ok(());
+    }
+    let checked = (range.0, range.1.min(Utc::now().timestamp_millis() - ltf_ms));
+    let report = check_alignment(htf, ltf, (htf_ms, ltf_ms), checked);
+    if report.empty_windows() > 0 {
+        anyhow::bail!(
+            "ltf coverage check failed for {} {}m/{}m:\n{}\n\
+             use --data-quality off to skip the check",
+            symbol,
+            htf_ms / 60_000,
+            ltf_ms / 60_000,
+            report.describe(10)
+        );
+    }
+    if !report.uncovered.is_empty() {
+        println!("ltf_coverage: symbol={} {}", symbol, report.summary());
+    }
+    Ok(())
+}
+
+/// Свечи `[start, end]`: с явным `cache` — из файла, если он покрывает
+/// биржу/символ/интервал/диапазон, иначе (или при `refresh`) — с биржи с перезаписью файла
+/// (прерванная загрузка с Bybit продолжается со страницы из `<cache>.partial.json`).
+/// Без `cache` — из общего хранилища `CANDLE_STORE_URL`, если задано (только Bybit),
+/// или из локального хранилища `CANDLE_DB` с докачкой недостающих кусков.
+async fn fetch_candles(
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    cache: Option<&str>,
+    refresh: bool,
+) -> Result<Vec<Candle>> {
+    if let Some(path) = cache {
+        return fetch_cache_file(venue, symbol, interval, range, path, refresh).await;
+    }
+    let load = match (venue, std::env::var(CANDLE_STORE_ENV)) {
+        (Venue::Bybit, Ok(url)) if !url.is_empty() => {
+            let store = CandleStore::connect(&url).await?;
+            let load = load_from_store(&store, symbol, interval, range, refresh).await?;
+            println!(
+                "candle_store: symbol={} interval={}m downloaded_ranges={} candles={}",
+                symbol,
+                interval,
+                load.downloaded_ranges,
+                load.candles.len()
+            );
+            load
+        }
+        _ => {
+            let store = open_local_store().await?;
+            let load = load_from_local(&store, venue, symbol, interval, range, refresh).await?;
+            if load.downloaded_ranges > 0 {
+                println!(
+                    "candle_db: exchange={} symbol={} interval={}m downloaded_ranges={} candles={}",
+                    venue.name(),
+                    symbol,
+                    interval,
+                    load.downloaded_ranges,
+                    load.candles.len()
+                );
+            }
+            load
+        }
+    };
+    Ok(load.candles)
+}
+
+/// Свечи из явно заданного файла-кэша (CSV/Parquet) с докачкой при непокрытии
+async fn fetch_cache_file(
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    path: &str,
+    refresh: bool,
+) -> Result<Vec<Candle>> {
+    let (start_ms, end_ms) = range;
+    let mut cached = None;
+    if !refresh && Path::new(path).exists() {
+        let candles = read_cache(path).with_context(|| format!("read cache {} failed", path))?;
+        let meta = CacheMeta::read(path);
+        let valid = match &meta {
+            Some(meta) => meta.exchange == venue && meta.covers(symbol, interval, range),
+            None => candles_cover(&candles, parse_interval_ms(interval)?, range),
+        };
+        if valid {
+            cached = Some((candles, meta));
+        } else {
+            println!(
+                "cache: {} does not cover {} {} {}m {}..{}, refreshing",
+                path,
+                venue.name(),
+                symbol,
+                interval,
+                compact_date(start_ms),
+                compact_date(end_ms)
+            );
+        }
+    }
+
+    let new_meta = || CacheMeta {
+        symbol: symbol.to_string(),
+        interval: interval.to_string(),
+        start_ms,
+        end_ms,
+        exchange: venue,
+        known_gaps: Vec::new(),
+    };
+    let (candles, mut meta, downloaded) = match cached {
+        Some((candles, meta)) => (candles, meta.unwrap_or_else(new_meta), false),
+        None => {
+            let checkpoint = format!("{}.partial.json", path);
+            let checkpoint = Some(Path::new(&checkpoint));
+            let data = download_candles(venue, symbol, interval, range, checkpoint).await?;
+            (data, new_meta(), true)
+        }
+    };
+    let fill = backfill_gaps(venue, symbol, interval, candles, range, &meta.known_gaps).await?;
+    print_repair(venue, symbol, interval, &fill.repair);
+    if downloaded || !fill.windows.is_empty() {
+        write_cache(path, &fill.candles).with_context(|| format!("write cache {} failed", path))?;
+        meta.known_gaps.extend(fill.repair.unrepaired);
+        meta.write(path)
+            .with_context(|| format!("write cache meta for {} failed", path))?;
+    }
+    Ok(in_range(fill.candles, range))
+}
+
+/// Свечей в окне докачки пропусков — одна страница биржи
+const BACKFILL_WINDOW_CANDLES: i64 = 1000;
+
+/// Серия после докачки пропусков
+struct Backfill {
+    /// Все свечи серии по возрастанию ts, включая докачанные
+    candles: Vec<Candle>,
+    /// Скачанные окна и их свечи
+    windows: Vec<((i64, i64), Vec<Candle>)>,
+    repair: GapRepair,
+}
+
+/// Докачка пропусков серии в закрытой части `range`, кроме известных пропусков
+/// биржи `known`: с биржи качаются только окна вокруг пропусков. Что не закрылось
+/// и после докачки — пропуски самой биржи (`repair.unrepaired`).
+async fn backfill_gaps(
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    candles: Vec<Candle>,
+    range: (i64, i64),
+    known: &[(i64, i64)],
+) -> Result<Backfill> {
+    let interval_ms = parse_interval_ms(interval)?;
+    let closed = (
+        range.0,
+        closed_end(range.1, interval_ms, Utc::now().timestamp_millis()),
+    );
+    let detected = subtract_known(&find_gaps(&candles, interval_ms, closed), known);
+    if detected.is_empty() {
+        return Ok(Backfill {
+            candles,
+            windows: Vec::new(),
+            repair: GapRepair::default(),
+        });
+    }
+
+    let mut merged = candles;
+    let mut windows = Vec::new();
+    for window in backfill_windows(&detected, interval_ms, BACKFILL_WINDOW_CANDLES) {
+        let mut data = download_candles(venue, symbol, interval, window, None).await?;
+        data.retain(|c| c.ts.0 >= window.0 && c.ts.0 <= window.1);
+        merged.extend_from_slice(&data);
+        windows.push((window, data));
+    }
+    merged.sort_by_key(|c| c.ts.0);
+    merged.dedup_by_key(|c| c.ts.0);
+
+    let overlaps = |(a, b): (i64, i64), (c, d): (i64, i64)| a <= d && c <= b;
+    let unrepaired: Vec<(i64, i64)> = find_gaps(&merged, interval_ms, closed)
+        .into_iter()
+        .filter(|g| detected.iter().any(|d| overlaps(*g, *d)))
+        .collect();
+    let repaired = detected
+        .iter()
+        .copied()
+        .filter(|d| !unrepaired.iter().any(|g| overlaps(*g, *d)))
+        .collect();
+    Ok(Backfill {
+        candles: merged,
+        windows,
+        repair: GapRepair {
+            detected,
+            repaired,
+            unrepaired,
+        },
+    })
+}
+
+/// Строки `backfill:` с итогом и докачанными диапазонами (первые 10)
+fn print_repair(venue: Venue, symbol: &str, interval: &str, repair: &GapRepair) {
+    if repair.detected.is_empty() {
+        return;
+    }
+    println!(
+        "backfill: exchange={} symbol={} interval={}m {}",
+        venue.name(),
+        symbol,
+        interval,
+        repair.summary()
+    );
+    for (from_ts, to_ts) in repair.repaired.iter().take(10) {
+        println!(
+            "backfill_repaired: symbol={} interval={}m from_ts={} to_ts={}",
+            symbol, interval, from_ts, to_ts
+        );
+    }
+    for (from_ts, to_ts) in repair.unrepaired.iter().take(10) {
+        println!(
+            "backfill_exchange_gap: symbol={} interval={}m from_ts={} to_ts={}",
+            symbol, interval, from_ts, to_ts
+        );
+    }
+}
+
+/// Свечи `[start, end]` с биржи; чекпоинт докачки — только у Bybit
+async fn download_candles(
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    (start_ms, end_ms): (i64, i64),
+    checkpoint: Option<&Path>,
+) -> Result<Vec<Candle>> {
+    let data = match venue {
+        Venue::Bybit => {
+            let api = BybitRest::new();
+            download_range_resumable(&api, symbol, interval, start_ms, end_ms, checkpoint).await
+        }
+        Venue::Binance => {
+            BinanceExchange::new()
+                .candles(symbol, interval, start_ms, end_ms)
+                .await
+        }
+    };
+    data.with_context(|| format!("download {} {} {}m failed", venue.name(), symbol, interval))
+}
+
+/// Файл локального хранилища свечей (SQLite), по умолчанию `data/candles.sqlite`
+pub const LOCAL_STORE_ENV: &str = "CANDLE_DB";
+const DEFAULT_LOCAL_STORE: &str = "data/candles.sqlite";
+/// Кусок докачки в хранилище свечей
+const STORE_CHUNK_MS: i64 = 30 * DAY_MS;
+
+pub async fn open_local_store() -> Result<LocalStore> {
+    let path = std::env::var(LOCAL_STORE_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| DEFAULT_LOCAL_STORE.to_string());
+    LocalStore::open(&path).await
+}
+
+/// Свечи из локального хранилища, с биржи `venue` (см. `load_series`)
+pub async fn load_from_local(
+    store: &LocalStore,
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    refresh: bool,
+) -> Result<StoreLoad> {
+    let mut series = store.series(SeriesKey::new(venue.name(), symbol, interval));
+    load_series(&mut series, venue, symbol, interval, range, refresh).await
+}
+
+/// Свечи серии хранилища: с биржи `venue` докачиваются только недостающие куски
+/// (при `refresh` — весь диапазон), по месяцу за раз, затем — пропуски внутри
+/// скачанного. Незакрытые свечи не сохраняются — хвост докачается при следующем запуске.
+async fn load_series<S: CandleSeries>(
+    series: &mut S,
+    venue: Venue,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    refresh: bool,
+) -> Result<StoreLoad> {
+    let interval_ms = parse_interval_ms(interval)?;
+    let closed_end = closed_end(range.1, interval_ms, Utc::now().timestamp_millis());
+    let gaps = if refresh {
+        vec![range]
+    } else {
+        series.missing(range).await?
+    };
+    let mut downloaded_ranges = 0;
+    for (start, end) in download_chunks(&gaps, closed_end) {
+        let mut data = download_candles(venue, symbol, interval, (start, end), None).await?;
+        data.retain(|c| c.ts.0 <= closed_end);
+        series.save((start, end), &data).await?;
+        downloaded_ranges += 1;
+    }
+
+    let candles = series.load(range).await?;
+    let known = series.known_gaps(range).await?;
+    let fill = backfill_gaps(venue, symbol, interval, candles, range, &known).await?;
+    for (window, data) in &fill.windows {
+        series.save(*window, data).await?;
+    }
+    series.add_known_gaps(&fill.repair.unrepaired).await?;
+    print_repair(venue, symbol, interval, &fill.repair);
+    Ok(StoreLoad {
+        candles: in_range(fill.candles, range),
+        downloaded_ranges,
+        repair: fill.repair,
+    })
+}
+
+/// Конец закрытой части диапазона: свеча, открытая в `now_ms`, ещё меняется
+fn closed_end(end_ms: i64, interval_ms: i64, now_ms: i64) -> i64 {
+    end_ms.min(now_ms - interval_ms)
+}
+
+/// Куски докачки недостающих диапазонов, не длиннее `STORE_CHUNK_MS` и не дальше
+/// `closed_end`: после сбоя скачанные куски не качаются заново
+fn download_chunks(gaps: &[(i64, i64)], closed_end: i64) -> Vec<(i64, i64)> {
+    let mut out = Vec::new();
+    for &(gap_start, gap_end) in gaps {
+        let mut start = gap_start;
+        while start <= gap_end.min(closed_end) {
+            let end = (start + STORE_CHUNK_MS - 1).min(gap_end).min(closed_end);
+            out.push((start, end));
+            start = end + 1;
+        }
+    }
+    out
+}
+
+/// Общий Postgres-кэш свечей (worker выставляет его из `DATABASE_URL`)
+pub const CANDLE_STORE_ENV: &str = "CANDLE_STORE_URL";
+
+/// Результат чтения из общего или локального хранилища
+#[derive(Debug)]
+pub struct StoreLoad {
+    pub candles: Vec<Candle>,
+    /// Сколько недостающих кусков пришлось скачать с биржи
+    pub downloaded_ranges: usize,
+    /// Докачка пропусков внутри уже скачанных кусков
+    pub repair: GapRepair,
+}
+
+/// Свечи из общего хранилища под блокировкой символа/интервала: докачка с Bybit
+/// та же, что у локального (см. `load_series`)
+pub async fn load_from_store(
+    store: &CandleStore,
+    symbol: &str,
+    interval: &str,
+    range: (i64, i64),
+    refresh: bool,
+) -> Result<StoreLoad> {
+    let interval_min: i32 = interval
+        .parse()
+        .with_context(|| format!("interval must be numeric minutes, got {}", interval))?;
+    let mut session = store.begin(symbol, interval_min).await?;
+    let load = load_series(&mut session, Venue::Bybit, symbol, interval, range, refresh).await?;
+    session.commit().await?;
+    Ok(load)
+}
+
+fn in_range(mut candles: Vec<Candle>, (start_ms, end_ms): (i64, i64)) -> Vec<Candle> {
+    candles.retain(|c| c.ts.0 >= start_ms && c.ts.0 <= end_ms);
+    candles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_chunks_stop_at_the_last_closed_candle() {
+        let end = closed_end(10 * DAY_MS, 60_000, 5 * DAY_MS);
+        assert_eq!(end, 5 * DAY_MS - 60_000);
+
+        let gaps = [(0, STORE_CHUNK_MS + 10), (40 * DAY_MS, 41 * DAY_MS)];
+        assert_eq!(
+            download_chunks(&gaps, STORE_CHUNK_MS + 5),
+            vec![
+                (0, STORE_CHUNK_MS - 1),
+                (STORE_CHUNK_MS, STORE_CHUNK_MS + 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_round_trip_and_list_parsing() {
+        let path = std::env::temp_dir().join(format!("bt-cache-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let c = Candle {
+            ts: TimestampMs(60_000),
+            open: Price(1.0),
+            high: Price(2.0),
+            low: Price(0.5),
+            close: Price(1.5),
+            volume: Qty(10.0),
+        };
+        write_cache(path, &[c]).unwrap();
+        let back = read_cache(path).unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].close, Price(1.5));
+        std::fs::remove_file(path).unwrap();
+
+        let candles: Vec<Candle> = (0..11)
+            .map(|i| Candle {
+                ts: TimestampMs(i * 60_000),
+                ..c
+            })
+            .collect();
+        assert_eq!(oos_start_ms(&candles, 0.0).unwrap(), None);
+        assert!(oos_start_ms(&candles, 1.0).is_err());
+        let ts = oos_start_ms(&candles, 0.3).unwrap().unwrap();
+        let (is, oos) = split_at_ms(&candles, ts);
+        assert_eq!((is.len(), oos.len()), (7, 4));
+
+        assert_eq!(
+            parse_num_list::<usize>("3, 5,,7", "x").unwrap(),
+            vec![3, 5, 7]
+        );
+        assert!(parse_num_list::<f64>("", "x").is_err());
+        assert_eq!(
+            parse_symbols("BTCUSDT, ETHUSDT").unwrap(),
+            vec!["BTCUSDT", "ETHUSDT"]
+        );
+        assert_eq!(
+            symbol_cache_path("data/eth_5m.csv", "SOLUSDT"),
+            "data/eth_5m_SOLUSDT.csv"
+        );
+        let (s, e) = date_range_ms("2026-01-01", "2026-01-01").unwrap();
+        assert_eq!(e - s, DAY_MS - 1);
+    }
+
+    #[test]
+    fn cache_keys_and_coverage() {
+        let range = date_range_ms("2024-01-01", "2024-03-01").unwrap();
+        assert_eq!(
+            default_cache_path("ethusdt", "5", range),
+            "data/cache/ETHUSDT_5m_20240101_20240301.csv"
+        );
+        assert_eq!(
+            cache_path_for(Some("data/x.csv"), true, "SOLUSDT", "5", range),
+            "data/x_SOLUSDT.csv"
+        );
+        assert_eq!(
+            explicit_cache(Some("data/x.csv"), true, "SOLUSDT").as_deref(),
+            Some("data/x_SOLUSDT.csv")
+        );
+        assert_eq!(explicit_cache(None, true, "SOLUSDT"), None);
+
+        let meta = CacheMeta {
+            symbol: "ETHUSDT".to_string(),
+            interval: "5".to_string(),
+            start_ms: range.0,
+            end_ms: range.1,
+            exchange: Venue::Bybit,
+            known_gaps: Vec::new(),
+        };
+        assert!(meta.covers("ETHUSDT", "5", (range.0 + DAY_MS, range.1)));
+        assert!(!meta.covers("BTCUSDT", "5", range));
+        assert!(!meta.covers("ETHUSDT", "1", range));
+        assert!(!meta.covers("ETHUSDT", "5", (range.0, range.1 + DAY_MS)));
+
+        let candle = |ts: i64| Candle {
+            ts: TimestampMs(ts),
+            open: Price(1.0),
+            high: Price(1.0),
+            low: Price(1.0),
+            close: Price(1.0),
+            volume: Qty(1.0),
+        };
+        let m5 = 5 * 60_000;
+        let day: Vec<Candle> = (0..288).map(|i| candle(i * m5)).collect();
+        assert!(candles_cover(&day, m5, (0, DAY_MS - 1)));
+        assert!(!candles_cover(&day, 60_000, (0, DAY_MS - 1)));
+        assert!(!candles_cover(&day, m5, (0, 2 * DAY_MS - 1)));
+    }
+}
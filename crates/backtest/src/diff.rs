@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Исполнение из CSV-артефакта прогона: fills MM (`mode`, `price`)
+/// или сделки trend (`reason`, `fill_price`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunFill {
+    pub ts: i64,
+    pub side: String,
+    pub kind: String,
+    pub qty: f64,
+    pub price: f64,
+}
+
+fn column(headers: &csv::StringRecord, names: &[&str], path: &str) -> Result<usize> {
+    names
+        .iter()
+        .find_map(|n| headers.iter().position(|h| h == *n))
+        .with_context(|| format!("{}: no column {}", path, names.join("/")))
+}
+
+fn field<T: std::str::FromStr>(rec: &csv::StringRecord, idx: usize, path: &str) -> Result<T> {
+    let raw = rec.get(idx).unwrap_or_default();
+    raw.parse().map_err(|_| {
+        anyhow::anyhow!(
+            "{}: bad value {:?} in line {}",
+            path,
+            raw,
+            rec.position().map_or(0, |p| p.line())
+        )
+    })
+}
+
+pub fn read_fills(path: &str) -> Result<Vec<RunFill>> {
+    let mut rdr = csv::Reader::from_path(path).with_context(|| format!("open {} failed", path))?;
+    let headers = rdr.headers()?.clone();
+    let ts = column(&headers, &["ts"], path)?;
+    let side = column(&headers, &["side"], path)?;
+    let kind = column(&headers, &["mode", "reason"], path)?;
+    let qty = column(&headers, &["qty"], path)?;
+    let price = column(&headers, &["price", "fill_price"], path)?;
+    let mut out = Vec::new();
+    for rec in rdr.records() {
+        let rec = rec?;
+        out.push(RunFill {
+            ts: field(&rec, ts, path)?,
+            side: rec.get(side).unwrap_or_default().to_string(),
+            kind: rec.get(kind).unwrap_or_default().to_string(),
+            qty: field(&rec, qty, path)?,
+            price: field(&rec, price, path)?,
+        });
+    }
+    Ok(out)
+}
+
+/// Точки `(ts, equity)` equity-артефакта (MM и trend)
+pub fn read_equity(path: &str) -> Result<Vec<(i64, f64)>> {
+    let mut rdr = csv::Reader::from_path(path).with_context(|| format!("open {} failed", path))?;
+    let headers = rdr.headers()?.clone();
+    let ts = column(&headers, &["ts"], path)?;
+    let equity = column(&headers, &["equity"], path)?;
+    let mut out = Vec::new();
+    for rec in rdr.records() {
+        let rec = rec?;
+        out.push((field(&rec, ts, path)?, field(&rec, equity, path)?));
+    }
+    Ok(out)
+}
+
+/// Исполнение, которого нет во втором прогоне
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmatchedFillRow {
+    /// `a_only` или `b_only`
+    pub run: &'static str,
+    pub ts: i64,
+    pub side: String,
+    pub kind: String,
+    pub qty: f64,
+    pub price: f64,
+}
+
+fn close(x: f64, y: f64, rel_tol: f64) -> bool {
+    (x - y).abs() <= rel_tol * x.abs().max(y.abs()).max(1.0)
+}
+
+/// Исполнения без пары в другом прогоне: пара — тот же ts, сторона и тип,
+/// qty и цена в пределах `rel_tol`
+pub fn unmatched_fills(a: &[RunFill], b: &[RunFill], rel_tol: f64) -> Vec<UnmatchedFillRow> {
+    let mut by_ts: BTreeMap<i64, (Vec<&RunFill>, Vec<&RunFill>)> = BTreeMap::new();
+    for f in a {
+        by_ts.entry(f.ts).or_default().0.push(f);
+    }
+    for f in b {
+        by_ts.entry(f.ts).or_default().1.push(f);
+    }
+    let row = |run, f: &RunFill| UnmatchedFillRow {
+        run,
+        ts: f.ts,
+        side: f.side.clone(),
+        kind: f.kind.clone(),
+        qty: f.qty,
+        price: f.price,
+    };
+
+    let mut out = Vec::new();
+    for (fa, mut fb) in by_ts.into_values() {
+        for f in fa {
+            let pair = fb.iter().position(|g| {
+                g.side == f.side
+                    && g.kind == f.kind
+                    && close(g.qty, f.qty, rel_tol)
+                    && close(g.price, f.price, rel_tol)
+            });
+            match pair {
+                Some(i) => {
+                    fb.remove(i);
+                }
+                None => out.push(row("a_only", f)),
+            }
+        }
+        out.extend(fb.into_iter().map(|f| row("b_only", f)));
+    }
+    out
+}
+
+/// Equity двух прогонов в общих точках: PnL от первой общей точки и расхождение
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EquityDiffRow {
+    pub ts: i64,
+    pub equity_a: f64,
+    pub equity_b: f64,
+    pub pnl_a: f64,
+    pub pnl_b: f64,
+    /// `equity_b - equity_a`
+    pub diff: f64,
+}
+
+pub fn equity_diff(a: &[(i64, f64)], b: &[(i64, f64)]) -> Vec<EquityDiffRow> {
+    let b: BTreeMap<i64, f64> = b.iter().copied().collect();
+    let mut start = None;
+    a.iter()
+        .filter_map(|&(ts, ea)| b.get(&ts).map(|&eb| (ts, ea, eb)))
+        .map(|(ts, ea, eb)| {
+            let (sa, sb) = *start.get_or_insert((ea, eb));
+            EquityDiffRow {
+                ts,
+                equity_a: ea,
+                equity_b: eb,
+                pnl_a: ea - sa,
+                pnl_b: eb - sb,
+                diff: eb - ea,
+            }
+        })
+        .collect()
+}
+
+/// Первая точка, где equity разошлись больше чем на `tol` (quote)
+pub fn first_divergence(rows: &[EquityDiffRow], tol: f64) -> Option<&EquityDiffRow> {
+    rows.iter().find(|r| r.diff.abs() > tol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(ts: i64, side: &str, qty: f64, price: f64) -> RunFill {
+        RunFill {
+            ts,
+            side: side.to_string(),
+            kind: "Normal".to_string(),
+            qty,
+            price,
+        }
+    }
+
+    #[test]
+    fn diff_reports_unmatched_fills_and_first_equity_split() {
+        let a = [
+            fill(1, "BUY", 0.1, 100.0),
+            fill(1, "BUY", 0.1, 99.0),
+            fill(2, "SELL", 0.1, 101.0),
+        ];
+        let b = [
+            fill(1, "BUY", 0.1, 99.0),
+            fill(1, "BUY", 0.1, 100.0 + 1e-12),
+            fill(3, "SELL", 0.1, 101.0),
+        ];
+        let rows = unmatched_fills(&a, &b, 1e-9);
+        let got: Vec<(&str, i64)> = rows.iter().map(|r| (r.run, r.ts)).collect();
+        assert_eq!(got, [("a_only", 2), ("b_only", 3)]);
+
+        let ea = [(1, 1000.0), (2, 1001.0), (3, 1002.0), (4, 1003.0)];
+        let eb = [(1, 1000.0), (2, 1001.0), (4, 1000.5)];
+        let diff = equity_diff(&ea, &eb);
+        assert_eq!(diff.len(), 3);
+        assert_eq!(diff[2].pnl_a, 3.0);
+        assert_eq!(diff[2].pnl_b, 0.5);
+        assert_eq!(first_divergence(&diff, 1e-6).map(|r| r.ts), Some(4));
+        assert!(first_divergence(&diff[..2], 1e-6).is_none());
+    }
+}
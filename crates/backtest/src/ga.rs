@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::search::{Dim, normal};
+
+/// Настройки генетического оптимизатора
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct GaParams {
+    pub population: usize,
+    /// Включая начальное случайное поколение
+    pub generations: usize,
+    /// Сколько лучших переходит в следующее поколение без изменений
+    pub elite: usize,
+    /// Вероятность мутации каждого гена
+    pub mutation_rate: f64,
+    /// Сигма гауссовой мутации в долях диапазона измерения
+    pub mutation_scale: f64,
+    /// Размер турнира при выборе родителя
+    pub tournament: usize,
+    pub seed: u64,
+}
+
+impl GaParams {
+    pub fn validate(&self) -> Result<()> {
+        if self.population < 2 || self.generations == 0 || self.tournament == 0 {
+            anyhow::bail!("population must be >= 2, generations and tournament >= 1");
+        }
+        if self.elite >= self.population {
+            anyhow::bail!("elite must be < population");
+        }
+        if !(0.0..=1.0).contains(&self.mutation_rate) || self.mutation_scale < 0.0 {
+            anyhow::bail!("mutation_rate must be in [0, 1], mutation_scale >= 0");
+        }
+        Ok(())
+    }
+}
+
+/// Особь: гены в [0,1]^d и индекс её прогона
+type Individual = (Vec<f64>, usize);
+
+/// Генетический поиск в единичном кубе; измерения отображаются через [`Dim::at`].
+///
+/// Турнирная селекция, равномерный кроссовер, гауссова мутация, элитизм.
+/// Одинаковые конфиги считаются один раз.
+pub struct Ga {
+    params: GaParams,
+    dims: Vec<Dim>,
+    rng: StdRng,
+}
+
+impl Ga {
+    pub fn new(params: GaParams, dims: Vec<Dim>) -> Self {
+        Self {
+            params,
+            dims,
+            rng: StdRng::seed_from_u64(params.seed),
+        }
+    }
+
+    /// `build` — конфиг из значений измерений (`None` — невалидный),
+    /// `better` — порядок прогонов (`Less` — лучше),
+    /// `on_generation` — поколение, отсортированное от лучшей особи.
+    /// Возвращает все посчитанные конфиги.
+    pub fn run<C: Debug, R>(
+        mut self,
+        mut build: impl FnMut(&[f64]) -> Option<C>,
+        mut eval: impl FnMut(&C) -> Result<R>,
+        better: impl Fn(&R, &R) -> Ordering,
+        mut on_generation: impl FnMut(usize, &[&(C, R)]),
+    ) -> Result<Vec<(C, R)>> {
+        let mut evals = Evals {
+            all: Vec::new(),
+            seen: HashMap::new(),
+        };
+        let max_attempts = self.params.population.saturating_mul(20);
+
+        let mut pop: Vec<Individual> = Vec::with_capacity(self.params.population);
+        for _ in 0..max_attempts {
+            if pop.len() >= self.params.population {
+                break;
+            }
+            let genes: Vec<f64> = (0..self.dims.len()).map(|_| self.rng.r#gen()).collect();
+            if let Some(idx) = evals.get(&self.dims, &genes, &mut build, &mut eval)? {
+                pop.push((genes, idx));
+            }
+        }
+        if pop.is_empty() {
+            anyhow::bail!("no valid configurations in the search space");
+        }
+
+        for generation in 0..self.params.generations {
+            pop.sort_by(|a, b| better(&evals.all[a.1].1, &evals.all[b.1].1));
+            let ranked: Vec<&(C, R)> = pop.iter().map(|(_, idx)| &evals.all[*idx]).collect();
+            on_generation(generation, &ranked);
+            if generation + 1 == self.params.generations {
+                break;
+            }
+
+            let mut next: Vec<Individual> = pop.iter().take(self.params.elite).cloned().collect();
+            for _ in 0..max_attempts {
+                if next.len() >= self.params.population {
+                    break;
+                }
+                let a = &pop[self.tournament(pop.len())].0;
+                let b = &pop[self.tournament(pop.len())].0;
+                let child = self.offspring(a, b);
+                if let Some(idx) = evals.get(&self.dims, &child, &mut build, &mut eval)? {
+                    next.push((child, idx));
+                }
+            }
+            pop = next;
+        }
+        Ok(evals.all)
+    }
+
+    /// Популяция отсортирована от лучшей — выигрывает наименьший индекс
+    fn tournament(&mut self, len: usize) -> usize {
+        (0..self.params.tournament)
+            .map(|_| self.rng.gen_range(0..len))
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn offspring(&mut self, a: &[f64], b: &[f64]) -> Vec<f64> {
+        a.iter()
+            .zip(b)
+            .map(|(&x, &y)| {
+                let mut g = if self.rng.gen_bool(0.5) { x } else { y };
+                if self.rng.r#gen::<f64>() < self.params.mutation_rate {
+                    g += self.params.mutation_scale * normal(&mut self.rng);
+                }
+                g.clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+}
+
+struct Evals<C, R> {
+    all: Vec<(C, R)>,
+    seen: HashMap<String, usize>,
+}
+
+impl<C: Debug, R> Evals<C, R> {
+    /// Индекс прогона конфига из генов; `None` — конфиг невалиден
+    fn get(
+        &mut self,
+        dims: &[Dim],
+        genes: &[f64],
+        build: &mut impl FnMut(&[f64]) -> Option<C>,
+        eval: &mut impl FnMut(&C) -> Result<R>,
+    ) -> Result<Option<usize>> {
+        let values: Vec<f64> = dims.iter().zip(genes).map(|(d, &u)| d.at(u)).collect();
+        let Some(cfg) = build(&values) else {
+            return Ok(None);
+        };
+        let key = format!("{:?}", cfg);
+        if let Some(&idx) = self.seen.get(&key) {
+            return Ok(Some(idx));
+        }
+        let report = eval(&cfg)?;
+        self.all.push((cfg, report));
+        self.seen.insert(key, self.all.len() - 1);
+        Ok(Some(self.all.len() - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(seed: u64) -> GaParams {
+        GaParams {
+            population: 16,
+            generations: 12,
+            elite: 2,
+            mutation_rate: 0.3,
+            mutation_scale: 0.1,
+            tournament: 3,
+            seed,
+        }
+    }
+
+    #[test]
+    fn evolves_towards_peak_and_keeps_elite() {
+        let dims = vec![
+            Dim::parse("0..1", "x", false).unwrap(),
+            Dim::parse("0..1", "y", false).unwrap(),
+        ];
+        let mut best_per_gen = Vec::new();
+        let all = Ga::new(params(3), dims)
+            .run(
+                |p| (p[0] <= 0.95).then(|| (p[0], p[1])),
+                |&(x, y)| Ok(-((x - 0.8).powi(2) + (y - 0.3).powi(2))),
+                |a: &f64, b: &f64| b.total_cmp(a),
+                |_, ranked| best_per_gen.push(ranked[0].1),
+            )
+            .unwrap();
+
+        assert_eq!(best_per_gen.len(), 12);
+        // элитизм: лучшая особь не теряется между поколениями
+        assert!(best_per_gen.windows(2).all(|w| w[1] >= w[0]));
+        assert!(best_per_gen[11] > -0.005);
+        assert!(all.iter().all(|((x, _), _)| *x <= 0.95));
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        assert!(params(1).validate().is_ok());
+        let mut p = params(1);
+        p.elite = p.population;
+        assert!(p.validate().is_err());
+        p = params(1);
+        p.mutation_rate = 1.5;
+        assert!(p.validate().is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Точек на графике не больше: длинные прогоны прореживаются
+const MAX_CHART_POINTS: usize = 2000;
+/// Строк в таблице сделок не больше, полный список — в CSV
+const MAX_TABLE_ROWS: usize = 5000;
+const CHART_W: f64 = 1000.0;
+const CHART_H: f64 = 220.0;
+
+/// Ряды прогона для HTML отчёта: equity-кривая и сделки
+#[derive(Debug, Clone, Default)]
+pub struct HtmlData {
+    /// `(ts, equity, drawdown_pct)`
+    pub equity: Vec<(i64, f64, f64)>,
+    /// Строки таблицы сделок: fills MM или trades trend
+    pub trades: Vec<Value>,
+}
+
+impl HtmlData {
+    pub fn new<T: Serialize>(
+        equity: impl IntoIterator<Item = (i64, f64, f64)>,
+        trades: impl IntoIterator<Item = T>,
+    ) -> Result<Self> {
+        Ok(Self {
+            equity: equity.into_iter().collect(),
+            trades: trades
+                .into_iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Из CSV-артефактов прогона; без колонки `drawdown_pct` просадка считается от пика equity
+    pub fn from_csv(equity_path: &str, trades_path: Option<&str>) -> Result<Self> {
+        let mut peak = f64::NEG_INFINITY;
+        let mut equity = Vec::new();
+        for row in read_rows(equity_path)? {
+            let num = |k: &str| row.get(k).and_then(Value::as_f64);
+            let (Some(ts), Some(eq)) = (row.get("ts").and_then(Value::as_i64), num("equity"))
+            else {
+                anyhow::bail!("{}: ts and equity columns are required", equity_path);
+            };
+            peak = peak.max(eq);
+            let dd = num("drawdown_pct").unwrap_or_else(|| {
+                if peak > 0.0 {
+                    (peak - eq) / peak * 100.0
+                } else {
+                    0.0
+                }
+            });
+            equity.push((ts, eq, dd));
+        }
+        let trades = match trades_path {
+            Some(path) => read_rows(path)?,
+            None => Vec::new(),
+        };
+        Ok(Self { equity, trades })
+    }
+}
+
+/// Строки CSV как объекты: числа — числами, пустые ячейки — null
+pub fn read_rows(path: &str) -> Result<Vec<Value>> {
+    let mut rdr = csv::Reader::from_path(path).with_context(|| format!("open {} failed", path))?;
+    let headers = rdr.headers()?.clone();
+    let mut out = Vec::new();
+    for rec in rdr.records() {
+        let rec = rec?;
+        let row: serde_json::Map<String, Value> = headers
+            .iter()
+            .zip(rec.iter())
+            .map(|(h, v)| {
+                let v = if v.is_empty() {
+                    Value::Null
+                } else if let Ok(i) = v.parse::<i64>() {
+                    Value::from(i)
+                } else {
+                    v.parse::<f64>()
+                        .ok()
+                        .filter(|f| f.is_finite())
+                        .map_or_else(|| Value::from(v), Value::from)
+                };
+                (h.to_string(), v)
+            })
+            .collect();
+        out.push(Value::Object(row));
+    }
+    Ok(out)
+}
+
+/// Секции отчёта, уже сериализованные `JsonReport`
+pub struct HtmlSections<'a> {
+    pub kind: &'a str,
+    pub config: &'a Value,
+    pub metrics: &'a Value,
+    pub costs: &'a Value,
+    pub monthly: &'a Value,
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn cell(v: &Value) -> String {
+    match v {
+        Value::Null => "-".to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() => format!("{:.6}", f)
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string(),
+            _ => n.to_string(),
+        },
+        Value::String(s) => escape(s),
+        v => escape(&v.to_string()),
+    }
+}
+
+fn date(ts: i64) -> String {
+    DateTime::from_timestamp_millis(ts)
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Скаляры вложенных объектов как `a.b = v`; массивы пропускаются (monthly — отдельной таблицей)
+fn flatten(prefix: &str, v: &Value, out: &mut Vec<(String, String)>) {
+    match v {
+        Value::Object(m) => {
+            for (k, v) in m {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten(&key, v, out);
+            }
+        }
+        Value::Array(_) => {}
+        v => out.push((prefix.to_string(), cell(v))),
+    }
+}
+
+/// Линия графика: точки, прореженные до `MAX_CHART_POINTS`, с последней точкой
+fn svg_chart(points: &[(i64, f64)], color: &str, fill: bool) -> String {
+    if points.len() < 2 {
+        return "<p>нет данных</p>".to_string();
+    }
+    let step = points.len().div_ceil(MAX_CHART_POINTS);
+    let mut pts: Vec<(i64, f64)> = points.iter().step_by(step).copied().collect();
+    if pts.last() != points.last() {
+        pts.extend(points.last().copied());
+    }
+    let (t0, t1) = (pts[0].0, pts[pts.len() - 1].0);
+    let lo = pts.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let hi = pts.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    let span_t = (t1 - t0).max(1) as f64;
+    let span_v = if hi > lo { hi - lo } else { 1.0 };
+    let xy = |(t, v): (i64, f64)| {
+        (
+            (t - t0) as f64 / span_t * CHART_W,
+            CHART_H - (v - lo) / span_v * CHART_H,
+        )
+    };
+
+    let mut path = String::new();
+    for (i, p) in pts.iter().enumerate() {
+        let (x, y) = xy(*p);
+        let _ = write!(path, "{}{:.1},{:.1}", if i == 0 { "M" } else { " L" }, x, y);
+    }
+    let mut svg = format!(
+        "<svg viewBox=\"-60 -10 {} {}\" width=\"100%\">",
+        CHART_W + 70.0,
+        CHART_H + 35.0
+    );
+    let _ = write!(
+        svg,
+        "<line x1=\"0\" y1=\"{h}\" x2=\"{w}\" y2=\"{h}\" class=\"axis\"/>\
+         <line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"{h}\" class=\"axis\"/>\
+         <text x=\"-5\" y=\"5\" text-anchor=\"end\">{hi:.2}</text>\
+         <text x=\"-5\" y=\"{h}\" text-anchor=\"end\">{lo:.2}</text>\
+         <text x=\"0\" y=\"{ty}\">{d0}</text>\
+         <text x=\"{w}\" y=\"{ty}\" text-anchor=\"end\">{d1}</text>",
+        w = CHART_W,
+        h = CHART_H,
+        ty = CHART_H + 20.0,
+        d0 = date(t0),
+        d1 = date(t1),
+    );
+    if fill {
+        let _ = write!(
+            svg,
+            "<path d=\"{} L{:.1},0 L0,0 Z\" fill=\"{}\" fill-opacity=\"0.3\"/>",
+            path, CHART_W, color
+        );
+    }
+    let _ = write!(
+        svg,
+        "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/></svg>",
+        path, color
+    );
+    svg
+}
+
+fn table(headers: &[String], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut out = String::from("<table><tr>");
+    for h in headers {
+        let _ = write!(out, "<th>{}</th>", escape(h));
+    }
+    out.push_str("</tr>");
+    for row in rows {
+        out.push_str("<tr>");
+        for c in row {
+            let _ = write!(out, "<td>{}</td>", c);
+        }
+        out.push_str("</tr>");
+    }
+    out.push_str("</table>");
+    out
+}
+
+/// Таблица строк-объектов: колонки — ключи первой строки
+fn rows_table(rows: &[Value], limit: usize) -> String {
+    let Some(Value::Object(first)) = rows.first() else {
+        return "<p>нет строк</p>".to_string();
+    };
+    let headers: Vec<String> = first.keys().cloned().collect();
+    let body = rows.iter().take(limit).map(|r| {
+        headers
+            .iter()
+            .map(|h| {
+                let v = r.get(h).unwrap_or(&Value::Null);
+                if h == "ts" || h.ends_with("_ts") {
+                    v.as_i64().map_or_else(|| cell(v), date)
+                } else {
+                    cell(v)
+                }
+            })
+            .collect()
+    });
+    let mut out = table(&headers, body);
+    if rows.len() > limit {
+        let _ = write!(
+            out,
+            "<p>показаны первые {} из {} строк, полный список — в CSV</p>",
+            limit,
+            rows.len()
+        );
+    }
+    out
+}
+
+/// Самодостаточный HTML отчёт прогона: без внешних скриптов и стилей,
+/// графики — inline SVG
+pub fn render(s: &HtmlSections, data: &HtmlData) -> String {
+    let mut metrics = Vec::new();
+    flatten("", s.metrics, &mut metrics);
+    flatten("costs", s.costs, &mut metrics);
+    let metrics_table = table(
+        &["metric".to_string(), "value".to_string()],
+        metrics.into_iter().map(|(k, v)| vec![escape(&k), v]),
+    );
+    let monthly = match s.monthly {
+        Value::Array(rows) => rows_table(rows, MAX_TABLE_ROWS),
+        _ => "<p>нет данных</p>".to_string(),
+    };
+    let equity: Vec<(i64, f64)> = data.equity.iter().map(|e| (e.0, e.1)).collect();
+    let drawdown: Vec<(i64, f64)> = data.equity.iter().map(|e| (e.0, -e.2)).collect();
+    let config = serde_json::to_string_pretty(s.config).unwrap_or_default();
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{kind}</title><style>\
+         body{{font-family:sans-serif;margin:24px;color:#222}}\
+         table{{border-collapse:collapse;font-size:12px}}\
+         td,th{{border:1px solid #ccc;padding:2px 6px;text-align:right}}\
+         th{{background:#f0f0f0}}\
+         pre{{background:#f6f6f6;padding:8px;font-size:12px}}\
+         svg text{{font-size:11px;fill:#555}}\
+         .axis{{stroke:#999}}\
+         .scroll{{max-height:480px;overflow:auto}}\
+         </style></head><body>\n\
+         <h1>{kind}</h1>\n\
+         <h2>Метрики</h2>\n{metrics_table}\n\
+         <h2>Equity</h2>\n{equity}\n\
+         <h2>Drawdown, %</h2>\n{drawdown}\n\
+         <h2>По месяцам</h2>\n{monthly}\n\
+         <h2>Сделки ({trades})</h2>\n<div class=\"scroll\">{trade_table}</div>\n\
+         <h2>Конфиг</h2>\n<pre>{config}</pre>\n\
+         </body></html>\n",
+        kind = escape(s.kind),
+        equity = svg_chart(&equity, "#1f77b4", false),
+        drawdown = svg_chart(&drawdown, "#d62728", true),
+        trades = data.trades.len(),
+        trade_table = rows_table(&data.trades, MAX_TABLE_ROWS),
+        config = escape(&config),
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn report_is_self_contained_and_escaped() {
+        let equity: Vec<(i64, f64, f64)> = (0..5000)
+            .map(|i| (i * 300_000, 1000.0 + (i % 100) as f64, (i % 7) as f64))
+            .collect();
+        let trades = [json!({"ts": 0, "side": "BUY", "mode": "<Normal>", "price": 2000.5})];
+        let data = HtmlData::new(equity, trades).unwrap();
+        let html = render(
+            &HtmlSections {
+                kind: "backtest_mm",
+                config: &json!({"symbol": "ETHUSDT", "levels": 5}),
+                metrics: &json!({"report": {"final_equity": 1012.25, "perf": {"sharpe": 1.5}}}),
+                costs: &json!({"total": 3.0}),
+                monthly: &Value::Null,
+            },
+            &data,
+        );
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("<script") && !html.contains("http"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        assert!(html.contains("<td>report.perf.sharpe</td><td>1.5</td>"));
+        assert!(html.contains("<td>costs.total</td>"));
+        assert!(html.contains("&lt;Normal&gt;") && !html.contains("<Normal>"));
+        assert!(html.contains("<td>1970-01-01 00:00</td>"));
+        assert!(html.contains("&quot;symbol&quot;: &quot;ETHUSDT&quot;"));
+        // график прорежен
+        let points: Vec<(i64, f64)> = data.equity.iter().map(|e| (e.0, e.1)).collect();
+        let n = svg_chart(&points, "#000", false).matches(" L").count();
+        assert!(n < MAX_CHART_POINTS && n > MAX_CHART_POINTS / 2);
+    }
+}
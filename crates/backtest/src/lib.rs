@@ -0,0 +1,30 @@
+//! Общее ядро backtest-бинарей: кэш свечей, учёт издержек и fill'ов,
+//! просадка, метрики и CSV/Parquet-артефакты.
+
+pub mod breakdown;
+pub mod capabilities;
+pub mod checkpoint;
+pub mod config;
+pub mod data;
+pub mod diff;
+pub mod ga;
+pub mod html;
+pub mod mm;
+pub mod montecarlo;
+pub mod parallel;
+pub mod perp;
+pub mod portfolio;
+pub mod progress;
+pub mod quality;
+pub mod report;
+pub mod search;
+pub mod sensitivity;
+pub mod stability;
+pub mod stats;
+pub mod stress;
+pub mod synthetic;
+pub mod table;
+pub mod topn;
+pub mod trades;
+pub mod trend;
+pub mod walkforward;
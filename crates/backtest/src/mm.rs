@@ -0,0 +1,1751 @@
+use bybit::rest::FundingRate;
+use core::types::{Bps, Money, Price, Qty, Ratio, Trade};
+use execution::accounting::Ledger;
+use execution::sim::ExecutionModel;
+use mm::grid::{
+    DesiredOrder, GridParams, Inventory, LotFilters, Side, base_ratio, build_grid,
+};
+use policy::mm_policy::{
+    MmDecisionReason, MmMode, MmPolicyDecision, MmPolicyParams, mm_policy_decision,
+};
+use structure::atr::atr;
+use structure::bos::{BosParams, BosState, BosTracker};
+use structure::candle::Candle;
+use structure::pullback::{PullbackParams, PullbackTracker};
+use structure::structure::{StructureParams, detect_structure};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::perp::{PerpParams, funding_payment};
+use crate::report::{EquityRow, FillRow};
+use crate::stats::{
+    CostBreakdown, Drawdown, Performance, ReturnStats, TradeStats, mean_count, mean_of,
+};
+
+/// Сколько HTF свечей держим для ATR и структуры
+const HTF_WINDOW: usize = 240;
+
+/// Параметры сигнала: структура (пивоты), подтверждение BOS, pullback
+#[derive(Debug, Copy, Clone)]
+pub struct SignalParams {
+    pub structure: StructureParams,
+    pub bos: BosParams,
+    pub pullback: PullbackParams,
+}
+
+impl Default for SignalParams {
+    fn default() -> Self {
+        Self {
+            structure: StructureParams {
+                pivot_k: 1,
+                min_atr_frac: 0.1,
+            },
+            bos: BosParams {
+                confirm_candles: 2,
+                epsilon_frac: 0.1,
+            },
+            pullback: PullbackParams {
+                epsilon_frac: 0.1,
+                retrace_frac: 0.4,
+            },
+        }
+    }
+}
+
+impl SignalParams {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.structure.pivot_k == 0 {
+            anyhow::bail!("pivot_k must be >= 1");
+        }
+        if self.bos.confirm_candles == 0 {
+            anyhow::bail!("bos_confirm_candles must be >= 1");
+        }
+        if self.structure.min_atr_frac < 0.0
+            || self.bos.epsilon_frac < 0.0
+            || self.pullback.epsilon_frac < 0.0
+        {
+            anyhow::bail!("min_atr_frac and epsilon fractions must be non-negative");
+        }
+        if !(self.pullback.retrace_frac > 0.0 && self.pullback.retrace_frac <= 1.0) {
+            anyhow::bail!("pullback_retrace_frac must be in (0, 1]");
+        }
+        Ok(())
+    }
+}
+
+/// Параметры стратегии MM (то, что перебирают sweep'ы)
+#[derive(Debug, Copy, Clone)]
+pub struct MmParams {
+    pub levels: usize,
+    pub step_bps: f64,
+    pub base_quote_per_order: f64,
+    pub max_size_mult: f64,
+    pub min_base_qty: f64,
+    pub soft_min: f64,
+    pub soft_max: f64,
+    pub hard_min: f64,
+    pub hard_max: f64,
+    pub maker_fee_bps: f64,
+    /// Defensive: шаг сетки ×step_mult, размер ордера ×size_mult
+    pub defensive_step_mult: f64,
+    pub defensive_size_mult: f64,
+    pub signal: SignalParams,
+}
+
+impl MmParams {
+    pub fn policy(&self) -> MmPolicyParams {
+        MmPolicyParams {
+            soft_min: Ratio(self.soft_min),
+            soft_max: Ratio(self.soft_max),
+            hard_min: Ratio(self.hard_min),
+            hard_max: Ratio(self.hard_max),
+        }
+    }
+
+    pub fn grid(&self) -> GridParams {
+        GridParams {
+            levels: self.levels,
+            step: Bps(self.step_bps),
+            base_quote_per_order: Money(self.base_quote_per_order),
+            max_size_mult: self.max_size_mult,
+            soft_min: Ratio(self.soft_min),
+            soft_max: Ratio(self.soft_max),
+            hard_min: Ratio(self.hard_min),
+            hard_max: Ratio(self.hard_max),
+            min_base_qty: Qty(self.min_base_qty),
+            filters: LotFilters::default(),
+        }
+    }
+
+    /// Сетка для режима: в Defensive шире и мельче
+    pub fn grid_for(&self, mode: MmMode) -> GridParams {
+        let grid = self.grid();
+        match mode {
+            MmMode::Defensive => GridParams {
+                step: Bps(grid.step.0 * self.defensive_step_mult.max(1.0)),
+                base_quote_per_order: Money(
+                    grid.base_quote_per_order.0 * self.defensive_size_mult.clamp(0.05, 1.0),
+                ),
+                ..grid
+            },
+            _ => grid,
+        }
+    }
+
+    pub fn validate_bands(&self) -> anyhow::Result<()> {
+        if !(0.0 <= self.hard_min
+            && self.hard_min <= self.soft_min
+            && self.soft_min <= self.soft_max
+            && self.soft_max <= self.hard_max
+            && self.hard_max <= 1.0)
+        {
+            anyhow::bail!("invalid bands: expected hard_min <= soft_min <= soft_max <= hard_max");
+        }
+        Ok(())
+    }
+}
+
+/// Когда maker лимитка считается исполненной
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FillMode {
+    /// Цена дошла до уровня (low <= buy, high >= sell)
+    #[default]
+    Touch,
+    /// Цена прошла сквозь уровень (low < buy) на `penetration`
+    Strict,
+}
+
+/// Правило maker fill'а: касание или проход сквозь уровень
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize)]
+pub struct FillRule {
+    pub mode: FillMode,
+    /// Strict: насколько цена должна уйти за уровень (тики × шаг цены)
+    pub penetration: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи; остаток уровня
+    /// переносится на следующие свечи (при исполнении по свечам)
+    pub volume_frac: Option<f64>,
+}
+
+impl FillRule {
+    pub fn new(mode: FillMode, penetration_ticks: u32, tick_size: f64) -> anyhow::Result<Self> {
+        if !(tick_size > 0.0 && tick_size.is_finite()) {
+            anyhow::bail!("tick_size must be positive");
+        }
+        if penetration_ticks > 0 && mode != FillMode::Strict {
+            anyhow::bail!("fill_penetration_ticks requires --fill-mode strict");
+        }
+        Ok(Self {
+            mode,
+            penetration: penetration_ticks as f64 * tick_size,
+            volume_frac: None,
+        })
+    }
+
+    pub fn with_volume_frac(mut self, frac: Option<f64>) -> anyhow::Result<Self> {
+        if frac.is_some_and(|f| !(f > 0.0 && f <= 1.0)) {
+            anyhow::bail!("fill_volume_frac must be in (0, 1]");
+        }
+        self.volume_frac = frac;
+        Ok(self)
+    }
+
+    /// Цена, которую нужно достичь, чтобы лимитка `side` на `level` исполнилась
+    fn trigger(&self, side: Side, level: f64) -> f64 {
+        match side {
+            Side::Buy => level - self.penetration,
+            Side::Sell => level + self.penetration,
+        }
+    }
+
+    /// Исполняет ли цена `p` лимитку `side` на `level`
+    pub fn fills(&self, side: Side, p: f64, level: f64) -> bool {
+        let trigger = self.trigger(side, level);
+        match (self.mode, side) {
+            (FillMode::Touch, Side::Buy) => p <= trigger,
+            (FillMode::Touch, Side::Sell) => p >= trigger,
+            (FillMode::Strict, Side::Buy) => p < trigger,
+            (FillMode::Strict, Side::Sell) => p > trigger,
+        }
+    }
+}
+
+/// Порядок проверки уровней сетки внутри свечи
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntrabarPath {
+    /// Сначала покупки, потом продажи, ближние к цене раньше
+    #[default]
+    Sorted,
+    /// Open → high → low → close
+    Ohlc,
+    /// Open → low → high → close
+    Olhc,
+    /// Из ohlc/olhc на каждой свече — путь с меньшей equity на close
+    WorstCase,
+    /// Из ohlc/olhc на каждой свече — путь с большей equity на close
+    BestCase,
+}
+
+/// Как закрывается остаток base в конце прогона
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForceCloseMode {
+    /// Один taker fill по последнему close со спредом и проскальзыванием
+    #[default]
+    Last,
+    /// TWAP: равные доли на последних N LTF свечах по их VWAP, только комиссия
+    Vwap,
+}
+
+impl ForceCloseMode {
+    /// `MmRunConfig::force_close_twap` для режима: `None` у `last`
+    pub fn twap_candles(self, candles: usize) -> anyhow::Result<Option<usize>> {
+        match self {
+            Self::Last => Ok(None),
+            Self::Vwap if candles == 0 => anyhow::bail!("force_close_candles must be >= 1"),
+            Self::Vwap => Ok(Some(candles)),
+        }
+    }
+}
+
+/// VWAP свечи без сделок — typical price `(high + low + close) / 3`
+fn candle_vwap(c: &Candle) -> Price {
+    Price((c.high.0 + c.low.0 + c.close.0) / 3.0)
+}
+
+/// VWAP сделок окна, `None` без объёма
+fn trades_vwap(trades: &[Trade]) -> Option<Price> {
+    let (notional, qty) = trades.iter().fold((0.0, 0.0), |(n, q), t| {
+        (n + t.price.0 * t.qty.0, q + t.qty.0)
+    });
+    (qty > 0.0).then(|| Price(notional / qty))
+}
+
+/// Условия прогона, общие для всех конфигов sweep'а
+#[derive(Debug, Copy, Clone)]
+pub struct MmRunConfig {
+    pub initial_quote: f64,
+    pub initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи: equity
+    /// `initial_quote + initial_base·price` делится заново, без комиссий
+    pub initial_base_ratio: Option<f64>,
+    /// Линейный perp вместо spot: покупки в плечо, funding и ликвидация
+    pub perp: Option<PerpParams>,
+    /// Taker-исполнение для bootstrap и force close
+    pub taker: ExecutionModel,
+    pub force_close_at_end: bool,
+    /// Закрывать base равными долями на последних N LTF свечах по их VWAP
+    /// вместо одного fill'а по последнему close (`None` — один fill)
+    pub force_close_twap: Option<usize>,
+    /// Рыночно довести inventory до `bootstrap_target_ratio`, когда
+    /// policy запрещает MM только из-за hard band
+    pub bootstrap_rebalance: bool,
+    pub bootstrap_target_ratio: f64,
+    /// Порядок fill'ов внутри свечи (исполнение по high/low)
+    pub intrabar: IntrabarPath,
+    /// Касание уровня или проход сквозь него, лимит по объёму свечи
+    pub fill: FillRule,
+    /// Копить equity/fill строки (sweep'ам не нужны)
+    pub record: bool,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct MmReport {
+    pub buy_fills: usize,
+    pub sell_fills: usize,
+    pub bootstrap_trades: usize,
+    /// Свечей, на которых policy выключала MM (single-TF)
+    pub disabled_bars: usize,
+    pub final_quote: f64,
+    pub final_base: f64,
+    pub final_equity: f64,
+    /// Funding за прогон (perp): уплачено минус получено
+    #[serde(default)]
+    pub funding_paid: f64,
+    /// Ликвидаций позиции (perp)
+    #[serde(default)]
+    pub liquidations: usize,
+    /// TWAP выход по VWAP минус тот же объём одним fill'ом по последнему close
+    #[serde(default)]
+    pub force_close_vs_last: f64,
+    /// Доля свечей, закрытых с долей base вне hard band, %
+    #[serde(default)]
+    pub outside_hard_band_pct: f64,
+    #[serde(flatten)]
+    pub perf: Performance,
+    pub costs: CostBreakdown,
+}
+
+impl MmReport {
+    /// Среднее по прогонам одного конфига (символам)
+    pub fn mean(items: &[Self]) -> Self {
+        let perf: Vec<Performance> = items.iter().map(|r| r.perf).collect();
+        let costs: Vec<CostBreakdown> = items.iter().map(|r| r.costs).collect();
+        Self {
+            buy_fills: mean_count(items, |r| r.buy_fills),
+            sell_fills: mean_count(items, |r| r.sell_fills),
+            bootstrap_trades: mean_count(items, |r| r.bootstrap_trades),
+            disabled_bars: mean_count(items, |r| r.disabled_bars),
+            final_quote: mean_of(items, |r| r.final_quote),
+            final_base: mean_of(items, |r| r.final_base),
+            final_equity: mean_of(items, |r| r.final_equity),
+            funding_paid: mean_of(items, |r| r.funding_paid),
+            liquidations: mean_count(items, |r| r.liquidations),
+            force_close_vs_last: mean_of(items, |r| r.force_close_vs_last),
+            outside_hard_band_pct: mean_of(items, |r| r.outside_hard_band_pct),
+            perf: Performance::mean(&perf),
+            costs: CostBreakdown::mean(&costs),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MmRun {
+    pub report: MmReport,
+    pub equity_rows: Vec<EquityRow>,
+    pub fill_rows: Vec<FillRow>,
+}
+
+/// HTF структура: BOS + pullback + policy на закрытии свечи
+struct Structure {
+    params: SignalParams,
+    candles: Vec<Candle>,
+    bos: BosTracker,
+    pullback: PullbackTracker,
+}
+
+impl Structure {
+    fn new(params: SignalParams) -> Self {
+        Self {
+            params,
+            candles: Vec::with_capacity(HTF_WINDOW + 8),
+            bos: BosTracker::new(),
+            pullback: PullbackTracker::new(),
+        }
+    }
+
+    /// Обновляет трекеры, возвращает mid (close) — `None`, пока нет ATR
+    fn on_close(&mut self, c: Candle) -> Option<Price> {
+        self.candles.push(c);
+        if self.candles.len() > HTF_WINDOW {
+            let excess = self.candles.len() - HTF_WINDOW;
+            self.candles.drain(0..excess);
+        }
+        let atr = atr(&self.candles)?;
+
+        let ms = detect_structure(&self.candles, self.params.structure);
+        self.bos.on_candle_close(&c, &ms, atr, self.params.bos);
+        if self.bos.state == BosState::Confirmed {
+            self.pullback
+                .on_candle_close(&c, &self.bos, atr, self.params.pullback);
+        } else {
+            self.pullback.reset();
+        }
+        Some(c.close)
+    }
+
+    fn decide(
+        &self,
+        inventory: Inventory,
+        mid: Price,
+        policy: MmPolicyParams,
+    ) -> Option<MmPolicyDecision> {
+        let ratio = base_ratio(inventory, mid)?;
+        Some(mm_policy_decision(
+            self.bos.state,
+            &self.pullback,
+            ratio,
+            policy,
+        ))
+    }
+}
+
+/// Свободный quote на покупку по `price`: на spot — баланс, на perp — остаток плеча
+fn buy_budget(ledger: &Ledger, perp: Option<PerpParams>, price: f64) -> f64 {
+    match perp {
+        Some(p) => p.buying_power(ledger, price),
+        None => ledger.quote,
+    }
+}
+
+/// Порядок исполнения внутри свечи: ближние к цене лимитки раньше
+fn sort_for_fill(orders: &mut [DesiredOrder]) {
+    orders.sort_by(|a, b| match (a.side, b.side) {
+        (Side::Buy, Side::Buy) => b
+            .price
+            .0
+            .partial_cmp(&a.price.0)
+            .unwrap_or(std::cmp::Ordering::Equal),
+        (Side::Sell, Side::Sell) => a
+            .price
+            .0
+            .partial_cmp(&b.price.0)
+            .unwrap_or(std::cmp::Ordering::Equal),
+        (Side::Buy, Side::Sell) => std::cmp::Ordering::Less,
+        (Side::Sell, Side::Buy) => std::cmp::Ordering::Greater,
+    });
+}
+
+/// Лимитки, которых коснулась цена на пути через точки свечи, в порядке касания.
+/// Каждая исполняется один раз — в первой точке касания.
+fn along_path(orders: Vec<DesiredOrder>, path: [Price; 4], rule: FillRule) -> Vec<DesiredOrder> {
+    let mut touched: Vec<(f64, DesiredOrder)> = orders
+        .into_iter()
+        .filter_map(|o| touch_time(&path, rule, o.side, o.price.0).map(|t| (t, o)))
+        .collect();
+    touched.sort_by(|a, b| a.0.total_cmp(&b.0));
+    touched.into_iter().map(|(_, o)| o).collect()
+}
+
+/// Время первого исполнения уровня на ломаной `path` (номер отрезка + доля)
+fn touch_time(path: &[Price; 4], rule: FillRule, side: Side, level: f64) -> Option<f64> {
+    let reached = |p: f64| rule.fills(side, p, level);
+    if reached(path[0].0) {
+        return Some(0.0);
+    }
+    let trigger = rule.trigger(side, level);
+    for (k, w) in path.windows(2).enumerate() {
+        let (a, b) = (w[0].0, w[1].0);
+        if reached(b) {
+            return Some(k as f64 + (trigger - a) / (b - a));
+        }
+    }
+    None
+}
+
+/// Исполненная maker сделка
+struct MakerFill {
+    qty: f64,
+    fee: f64,
+    realized: Option<f64>,
+}
+
+/// Maker сделка по счёту: покупка целиком на свободный quote (perp — в пределах плеча),
+/// продажа — не больше base
+fn maker_trade(
+    ledger: &mut Ledger,
+    perp: Option<PerpParams>,
+    side: Side,
+    qty: f64,
+    price: f64,
+    fee_ratio: f64,
+) -> Option<MakerFill> {
+    match side {
+        Side::Buy => {
+            let gross = qty * price;
+            let fee = gross * fee_ratio;
+            if gross + fee > buy_budget(ledger, perp, price) || qty <= 0.0 {
+                return None;
+            }
+            ledger.buy(qty, price, fee);
+            Some(MakerFill {
+                qty,
+                fee,
+                realized: None,
+            })
+        }
+        Side::Sell => {
+            let qty = qty.min(ledger.base);
+            if ledger.base <= 0.0 || qty <= 0.0 {
+                return None;
+            }
+            let fee = qty * price * fee_ratio;
+            let realized = ledger.sell(qty, price, fee);
+            Some(MakerFill {
+                qty,
+                fee,
+                realized: Some(realized),
+            })
+        }
+    }
+}
+
+/// Общий симулятор MM для всех режимов (один TF, MTF, сделки, портфель):
+/// счёт, fill'ы, funding, ликвидация, force close, метрики и артефакты прогона
+pub(crate) struct MmSimulator {
+    cfg: MmRunConfig,
+    pub(crate) ledger: Ledger,
+    trades: TradeStats,
+    drawdown: Drawdown,
+    returns: ReturnStats,
+    costs: CostBreakdown,
+    /// ∫base·dt (base·мс) и проданный base — средний срок удержания по закону Литтла
+    base_ms: f64,
+    sold_qty: f64,
+    /// Notional всех fill'ов — для оборота
+    traded: f64,
+    last_mark: Option<(i64, f64)>,
+    buy_fills: usize,
+    bootstrap_trades: usize,
+    disabled_bars: usize,
+    /// Ставки funding по возрастанию ts и первая ещё не начисленная
+    funding: Vec<FundingRate>,
+    funding_idx: usize,
+    funding_paid: f64,
+    liquidations: usize,
+    /// Неисполненные остатки уровней сетки под `fill_volume_frac`:
+    /// (сторона, номер уровня от ближнего, qty)
+    carry: Vec<(Side, usize, f64)>,
+    grid_posted: bool,
+    /// Продано TWAP выходом: base и выручка
+    twap_qty: f64,
+    twap_proceeds: f64,
+    /// Hard band policy и свечи (всего / вне полосы)
+    hard_band: (f64, f64),
+    bars: usize,
+    outside_band_bars: usize,
+    equity_rows: Vec<EquityRow>,
+    fill_rows: Vec<FillRow>,
+}
+
+impl MmRunConfig {
+    /// Начальные (quote, base) с учётом `initial_base_ratio`
+    pub fn initial_balances(&self, first_close: f64) -> (f64, f64) {
+        match self.initial_base_ratio {
+            Some(ratio) if first_close > 0.0 => {
+                let equity = self.initial_quote + self.initial_base * first_close;
+                (equity * (1.0 - ratio), equity * ratio / first_close)
+            }
+            _ => (self.initial_quote, self.initial_base),
+        }
+    }
+}
+
+impl MmSimulator {
+    fn new(mut cfg: MmRunConfig, first_close: Price) -> Self {
+        (cfg.initial_quote, cfg.initial_base) = cfg.initial_balances(first_close.0);
+        cfg.initial_base_ratio = None;
+        let ledger = Ledger::new(cfg.initial_quote, cfg.initial_base, first_close.0);
+        Self {
+            cfg,
+            ledger,
+            trades: TradeStats::default(),
+            drawdown: Drawdown::new(ledger.equity(first_close.0)),
+            returns: ReturnStats::default(),
+            costs: CostBreakdown::default(),
+            base_ms: 0.0,
+            sold_qty: 0.0,
+            traded: 0.0,
+            last_mark: None,
+            buy_fills: 0,
+            bootstrap_trades: 0,
+            disabled_bars: 0,
+            funding: Vec::new(),
+            funding_idx: 0,
+            funding_paid: 0.0,
+            liquidations: 0,
+            carry: Vec::new(),
+            grid_posted: false,
+            twap_qty: 0.0,
+            twap_proceeds: 0.0,
+            hard_band: (0.0, 1.0),
+            bars: 0,
+            outside_band_bars: 0,
+            equity_rows: Vec::new(),
+            fill_rows: Vec::new(),
+        }
+    }
+
+    /// Funding на perp начиная с `from_ts` (старт прогона); на spot ставки игнорируются
+    fn with_funding(mut self, funding: &[FundingRate], from_ts: i64) -> Self {
+        if self.cfg.perp.is_some() {
+            self.funding = funding
+                .iter()
+                .copied()
+                .filter(|f| f.ts.0 >= from_ts)
+                .collect();
+        }
+        self
+    }
+
+    fn with_hard_band(mut self, params: &MmParams) -> Self {
+        self.hard_band = (params.hard_min, params.hard_max);
+        self
+    }
+
+    /// Inventory для сетки и policy: на perp quote — остаток плеча,
+    /// доля base считается от максимального notional
+    fn inventory(&self, mid: Price) -> Inventory {
+        Inventory {
+            base: Qty(self.ledger.base),
+            quote: Money(buy_budget(&self.ledger, self.cfg.perp, mid.0)),
+        }
+    }
+
+    /// Начисляет funding с ts не позже открытия свечи по её open
+    fn settle_funding(&mut self, c: &Candle) {
+        while let Some(f) = self
+            .funding
+            .get(self.funding_idx)
+            .filter(|f| f.ts.0 <= c.ts.0)
+        {
+            let payment = funding_payment(self.ledger.base, c.open.0, f.rate);
+            self.ledger.quote -= payment;
+            self.funding_paid += payment;
+            self.funding_idx += 1;
+        }
+    }
+
+    /// Ликвидация perp позиции, если low свечи дошёл до цены ликвидации:
+    /// весь base продаётся по ней (или по open при гэпе) с taker комиссией
+    fn check_liquidation(&mut self, c: &Candle) {
+        let Some(liq) = self
+            .cfg
+            .perp
+            .and_then(|p| p.liquidation_price(&self.ledger))
+            .filter(|liq| c.low.0 <= *liq)
+        else {
+            return;
+        };
+        let qty = self.ledger.base;
+        let price = liq.min(c.open.0);
+        let fee = qty * price * self.cfg.taker.fee_bps.max(0.0) / 10_000.0;
+        let realized = self.ledger.sell(qty, price, fee);
+        self.costs.on_taker(qty, price, price, fee);
+        self.liquidations += 1;
+        self.record_fill(
+            c.ts.0,
+            Side::Sell,
+            "Liquidation",
+            qty,
+            price,
+            fee,
+            qty * price - fee,
+            Some(realized),
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_fill(
+        &mut self,
+        ts: i64,
+        side: Side,
+        mode: &str,
+        qty: f64,
+        price: f64,
+        fee: f64,
+        quote_delta: f64,
+        realized: Option<f64>,
+    ) {
+        self.traded += qty * price;
+        match realized {
+            Some(pnl) => {
+                self.trades.on_close(pnl);
+                self.sold_qty += qty;
+            }
+            None => self.buy_fills += 1,
+        }
+        if self.cfg.record {
+            self.fill_rows.push(FillRow {
+                ts,
+                side: match side {
+                    Side::Buy => "BUY",
+                    Side::Sell => "SELL",
+                }
+                .to_string(),
+                mode: mode.to_string(),
+                qty,
+                price,
+                fee_quote: fee,
+                quote_delta,
+                realized_pnl: realized,
+            });
+        }
+    }
+
+    /// Исполняет лимитки сетки, которые задел диапазон свечи (maker),
+    /// в порядке `cfg.intrabar`
+    fn fill_grid(
+        &mut self,
+        c: &Candle,
+        mut orders: Vec<DesiredOrder>,
+        fee_ratio: f64,
+        mode: MmMode,
+    ) {
+        sort_for_fill(&mut orders);
+        let mode = format!("{:?}", mode);
+        let levels = self.cap_by_volume(c, &mut orders);
+
+        let rule = self.cfg.fill;
+        let path = match self.cfg.intrabar {
+            IntrabarPath::Sorted => {
+                orders.retain(|o| match o.side {
+                    Side::Buy => rule.fills(o.side, c.low.0, o.price.0),
+                    Side::Sell => rule.fills(o.side, c.high.0, o.price.0),
+                });
+                orders
+            }
+            IntrabarPath::Ohlc => along_path(orders, [c.open, c.high, c.low, c.close], rule),
+            IntrabarPath::Olhc => along_path(orders, [c.open, c.low, c.high, c.close], rule),
+            IntrabarPath::WorstCase | IntrabarPath::BestCase => {
+                let up = along_path(orders.clone(), [c.open, c.high, c.low, c.close], rule);
+                let down = along_path(orders, [c.open, c.low, c.high, c.close], rule);
+                let (eq_up, eq_down) = (
+                    self.dry_run(&up, c.close, fee_ratio),
+                    self.dry_run(&down, c.close, fee_ratio),
+                );
+                let worst_is_up = eq_up <= eq_down;
+                if worst_is_up == (self.cfg.intrabar == IntrabarPath::WorstCase) {
+                    up
+                } else {
+                    down
+                }
+            }
+        };
+        let mut filled = Vec::with_capacity(path.len());
+        for o in path {
+            let qty = self.fill_maker(c.ts.0, o.side, &mode, o.qty.0, o.price.0, fee_ratio);
+            filled.push((o.side, o.price.0, qty));
+        }
+        if let Some(levels) = levels {
+            self.carry = levels
+                .into_iter()
+                .filter_map(|(side, level, price, posted)| {
+                    let done: f64 = filled
+                        .iter()
+                        .filter(|f| f.0 == side && f.1 == price)
+                        .map(|f| f.2)
+                        .sum();
+                    let left = posted - done;
+                    (left > 1e-12).then_some((side, level, left))
+                })
+                .collect();
+        }
+    }
+
+    /// Лимит `fill.volume_frac` на уровень: qty уровня — остаток с прошлых свечей
+    /// (или размер сетки), к исполнению — не больше доли объёма свечи.
+    /// Возвращает (сторона, уровень, цена, qty уровня) для переноса остатков.
+    fn cap_by_volume(
+        &mut self,
+        c: &Candle,
+        orders: &mut [DesiredOrder],
+    ) -> Option<Vec<(Side, usize, f64, f64)>> {
+        let frac = self.cfg.fill.volume_frac?;
+        self.grid_posted = true;
+        let cap = frac * c.volume.0.max(0.0);
+        let (mut buys, mut sells) = (0, 0);
+        let mut levels = Vec::with_capacity(orders.len());
+        for o in orders.iter_mut() {
+            let counter = match o.side {
+                Side::Buy => &mut buys,
+                Side::Sell => &mut sells,
+            };
+            let level = *counter;
+            *counter += 1;
+            let posted = self
+                .carry
+                .iter()
+                .find(|(side, l, _)| *side == o.side && *l == level)
+                .map_or(o.qty.0, |(_, _, left)| *left);
+            levels.push((o.side, level, o.price.0, posted));
+            o.qty = Qty(posted.min(cap));
+        }
+        Some(levels)
+    }
+
+    /// Equity на `close` после fill'ов `orders` — без изменения счёта
+    fn dry_run(&self, orders: &[DesiredOrder], close: Price, fee_ratio: f64) -> f64 {
+        let mut ledger = self.ledger;
+        for o in orders {
+            maker_trade(
+                &mut ledger,
+                self.cfg.perp,
+                o.side,
+                o.qty.0,
+                o.price.0,
+                fee_ratio,
+            );
+        }
+        ledger.equity(close.0)
+    }
+
+    /// Исполняет лимитки сетки сделками окна: fill, только если сделка прошла
+    /// по цене лимитки, и не больше её объёма (очередь перед нами не учитывается)
+    fn fill_trades(
+        &mut self,
+        trades: &[Trade],
+        mut orders: Vec<DesiredOrder>,
+        fee_ratio: f64,
+        mode: MmMode,
+    ) {
+        sort_for_fill(&mut orders);
+        let mode = format!("{:?}", mode);
+        let mut left: Vec<f64> = orders.iter().map(|o| o.qty.0).collect();
+
+        for t in trades {
+            let mut volume = t.qty.0;
+            for (o, left) in orders.iter().zip(left.iter_mut()) {
+                if volume <= 0.0 {
+                    break;
+                }
+                let crossed = self.cfg.fill.fills(o.side, t.price.0, o.price.0);
+                if !crossed || *left <= 0.0 {
+                    continue;
+                }
+                let filled = self.fill_maker(
+                    t.ts.0,
+                    o.side,
+                    &mode,
+                    left.min(volume),
+                    o.price.0,
+                    fee_ratio,
+                );
+                *left -= filled;
+                volume -= filled;
+            }
+        }
+    }
+
+    /// Maker fill по цене лимитки; возвращает исполненный qty
+    /// (0 — не хватило quote на покупку или нет base на продажу)
+    fn fill_maker(
+        &mut self,
+        ts: i64,
+        side: Side,
+        mode: &str,
+        qty: f64,
+        price: f64,
+        fee_ratio: f64,
+    ) -> f64 {
+        let Some(fill) = maker_trade(&mut self.ledger, self.cfg.perp, side, qty, price, fee_ratio)
+        else {
+            return 0.0;
+        };
+        self.costs.on_maker(fill.fee);
+        let gross = fill.qty * price;
+        let quote_delta = match side {
+            Side::Buy => -(gross + fill.fee),
+            Side::Sell => gross - fill.fee,
+        };
+        self.record_fill(
+            ts,
+            side,
+            mode,
+            fill.qty,
+            price,
+            fill.fee,
+            quote_delta,
+            fill.realized,
+        );
+        fill.qty
+    }
+
+    /// Рыночная сделка к целевой доле base (taker)
+    fn bootstrap(&mut self, ts: i64, mid: Price) {
+        let taker = self.cfg.taker;
+        let budget = buy_budget(&self.ledger, self.cfg.perp, mid.0);
+        let capacity = self.ledger.base * mid.0 + budget;
+        let target = self.cfg.bootstrap_target_ratio.clamp(0.0, 1.0);
+        let delta_value = target * capacity - self.ledger.base * mid.0;
+
+        if delta_value > 0.0 && budget > 0.0 {
+            let qty = taker.buy_qty_for_quote(delta_value.min(budget), mid);
+            if qty.0 <= 0.0 {
+                return;
+            }
+            let cost = taker.buy_cost(qty, mid);
+            if cost > budget {
+                return;
+            }
+            let price = taker.buy_fill_price(mid).0;
+            let fee = cost - qty.0 * price;
+            self.ledger.buy(qty.0, price, fee);
+            self.costs.on_taker(qty.0, mid.0, price, fee);
+            self.bootstrap_trades += 1;
+            self.record_fill(ts, Side::Buy, "Bootstrap", qty.0, price, fee, -cost, None);
+        } else if delta_value < 0.0 && self.ledger.base > 0.0 {
+            let qty = ((-delta_value) / mid.0).min(self.ledger.base);
+            if qty <= 0.0 {
+                return;
+            }
+            let proceeds = taker.sell_proceeds(Qty(qty), mid);
+            let price = taker.sell_fill_price(mid).0;
+            let fee = qty * price - proceeds;
+            let realized = self.ledger.sell(qty, price, fee);
+            self.costs.on_taker(qty, mid.0, price, fee);
+            self.bootstrap_trades += 1;
+            self.record_fill(
+                ts,
+                Side::Sell,
+                "Bootstrap",
+                qty,
+                price,
+                fee,
+                proceeds,
+                Some(realized),
+            );
+        }
+    }
+
+    fn mark(&mut self, c: &Candle, mode: MmMode) {
+        self.check_liquidation(c);
+        // сетку сняли — остатки уровней отменяются
+        if !std::mem::take(&mut self.grid_posted) {
+            self.carry.clear();
+        }
+        let equity = self.ledger.equity(c.close.0);
+        let base = self.ledger.base;
+        self.bars += 1;
+        let (hard_min, hard_max) = self.hard_band;
+        if base_ratio(self.inventory(c.close), c.close)
+            .is_some_and(|r| !(hard_min..=hard_max).contains(&r.0))
+        {
+            self.outside_band_bars += 1;
+        }
+        self.returns.on_bar(c.ts.0, equity, base > 0.0);
+        if let Some((ts, prev_base)) = self.last_mark {
+            self.base_ms += prev_base * (c.ts.0 - ts) as f64;
+        }
+        self.last_mark = Some((c.ts.0, base));
+
+        let Some(dd) = self.drawdown.update(c.ts.0, equity) else {
+            return;
+        };
+        if self.cfg.record {
+            self.equity_rows.push(EquityRow {
+                ts: c.ts.0,
+                close: c.close.0,
+                mode: format!("{:?}", mode),
+                quote: self.ledger.quote,
+                base: self.ledger.base,
+                cost_basis_quote: self.ledger.cost_basis_quote,
+                equity,
+                drawdown_pct: dd * 100.0,
+            });
+        }
+    }
+
+    /// Закрытие остатка base по рынку в конце прогона
+    fn force_close(&mut self, ts: i64, mark: Price) {
+        let qty = self.ledger.base;
+        if !self.cfg.force_close_at_end || qty <= 0.0 {
+            return;
+        }
+        let proceeds = self.cfg.taker.sell_proceeds(Qty(qty), mark);
+        // спред и проскальзывание учитываются как комиссия к mark
+        let fee = qty * mark.0 - proceeds;
+        let realized = self.ledger.sell(qty, mark.0, fee);
+        let fill = self.cfg.taker.sell_fill_price(mark).0;
+        self.costs
+            .on_taker(qty, mark.0, fill, qty * fill - proceeds);
+        self.record_fill(
+            ts,
+            Side::Sell,
+            "ForceClose",
+            qty,
+            mark.0,
+            fee.max(0.0),
+            proceeds,
+            Some(realized),
+        );
+    }
+
+    /// Первая свеча TWAP выхода из `len` свечей прогона
+    fn twap_from(&self, len: usize) -> usize {
+        match self.cfg.force_close_twap {
+            Some(n) if self.cfg.force_close_at_end => len.saturating_sub(n),
+            _ => usize::MAX,
+        }
+    }
+
+    /// Доля TWAP выхода по `vwap` свечи: остаток base делится на `slices`
+    /// оставшихся свечей; спред и проскальзывание уже в VWAP
+    fn twap_slice(&mut self, ts: i64, vwap: Price, slices: usize) {
+        let qty = self.ledger.base / slices.max(1) as f64;
+        if qty <= 0.0 || vwap.0 <= 0.0 {
+            return;
+        }
+        let fee = self.cfg.taker.fee_quote(qty * vwap.0);
+        let realized = self.ledger.sell(qty, vwap.0, fee);
+        self.costs.on_taker(qty, vwap.0, vwap.0, fee);
+        self.twap_qty += qty;
+        self.twap_proceeds += qty * vwap.0 - fee;
+        self.record_fill(
+            ts,
+            Side::Sell,
+            "ForceCloseTwap",
+            qty,
+            vwap.0,
+            fee,
+            qty * vwap.0 - fee,
+            Some(realized),
+        );
+    }
+
+    fn finish(self, final_mark: Price) -> MmRun {
+        let force_close_vs_last = if self.twap_qty > 0.0 {
+            self.twap_proceeds - self.cfg.taker.sell_proceeds(Qty(self.twap_qty), final_mark)
+        } else {
+            0.0
+        };
+        let final_equity = self.ledger.equity(final_mark.0);
+        let initial_equity = self.cfg.initial_quote + self.cfg.initial_base * final_mark.0;
+        let avg_hold_ms = if self.sold_qty > 0.0 {
+            self.base_ms / self.sold_qty
+        } else {
+            0.0
+        };
+        MmRun {
+            report: MmReport {
+                buy_fills: self.buy_fills,
+                sell_fills: self.trades.closed,
+                bootstrap_trades: self.bootstrap_trades,
+                disabled_bars: self.disabled_bars,
+                final_quote: self.ledger.quote,
+                final_base: self.ledger.base,
+                final_equity,
+                funding_paid: self.funding_paid,
+                liquidations: self.liquidations,
+                force_close_vs_last,
+                outside_hard_band_pct: if self.bars > 0 {
+                    self.outside_band_bars as f64 * 100.0 / self.bars as f64
+                } else {
+                    0.0
+                },
+                perf: Performance::new(&self.trades, &self.drawdown, initial_equity, final_equity)
+                    .with_risk(&self.returns, avg_hold_ms)
+                    .with_turnover(self.traded, initial_equity),
+                costs: self.costs,
+            },
+            equity_rows: self.equity_rows,
+            fill_rows: self.fill_rows,
+        }
+    }
+}
+
+/// MM на одном таймфрейме: решение и сетка по закрытию свечи,
+/// исполнение — по диапазону этой же свечи.
+pub fn run_mm(candles: &[Candle], params: &MmParams, cfg: MmRunConfig) -> MmRun {
+    run_mm_with_funding(candles, &[], params, cfg)
+}
+
+/// Как [`run_mm`], с начислением `funding` при `cfg.perp`
+pub fn run_mm_with_funding(
+    candles: &[Candle],
+    funding: &[FundingRate],
+    params: &MmParams,
+    cfg: MmRunConfig,
+) -> MmRun {
+    let Some(first) = candles.first() else {
+        return MmSimulator::new(cfg, Price(0.0)).finish(Price(0.0));
+    };
+    let mut sim = MmSimulator::new(cfg, first.close)
+        .with_funding(funding, first.ts.0)
+        .with_hard_band(params);
+    let mut structure = Structure::new(params.signal);
+    let policy = params.policy();
+    let fee_ratio = params.maker_fee_bps.max(0.0) / 10_000.0;
+    let mut last_ts = first.ts.0;
+    let twap_from = sim.twap_from(candles.len());
+
+    for (i, c) in candles.iter().copied().enumerate() {
+        last_ts = c.ts.0;
+        sim.settle_funding(&c);
+        if i >= twap_from {
+            structure.on_close(c);
+            sim.twap_slice(c.ts.0, candle_vwap(&c), candles.len() - i);
+            sim.mark(&c, MmMode::Disabled);
+            continue;
+        }
+        let Some(mid) = structure.on_close(c) else {
+            continue;
+        };
+        let Some(decision) = structure.decide(sim.inventory(mid), mid, policy) else {
+            continue;
+        };
+        if decision.mode == MmMode::Disabled {
+            sim.disabled_bars += 1;
+        }
+
+        if matches!(decision.mode, MmMode::Normal | MmMode::Defensive) {
+            let grid = build_grid(mid, mid, sim.inventory(mid), params.grid_for(decision.mode));
+            if let Some(orders) = grid {
+                sim.fill_grid(&c, orders, fee_ratio, decision.mode);
+            }
+        }
+        sim.mark(&c, decision.mode);
+    }
+
+    let final_mark = structure
+        .candles
+        .last()
+        .map(|c| c.close)
+        .unwrap_or(Price(0.0));
+    sim.force_close(last_ts, final_mark);
+    sim.finish(final_mark)
+}
+
+/// MM на двух таймфреймах: режим решается на закрытии HTF свечи,
+/// сетка строится и исполняется на LTF свечах следующего HTF окна.
+pub fn run_mm_mtf(
+    htf: &[Candle],
+    ltf: &[Candle],
+    htf_ms: i64,
+    params: &MmParams,
+    cfg: MmRunConfig,
+) -> MmRun {
+    run_mm_mtf_with_funding(htf, ltf, &[], htf_ms, params, cfg)
+}
+
+/// Как [`run_mm_mtf`], с начислением `funding` при `cfg.perp`
+pub fn run_mm_mtf_with_funding(
+    htf: &[Candle],
+    ltf: &[Candle],
+    funding: &[FundingRate],
+    htf_ms: i64,
+    params: &MmParams,
+    cfg: MmRunConfig,
+) -> MmRun {
+    let Some(first) = htf.first() else {
+        return MmSimulator::new(cfg, Price(0.0)).finish(Price(0.0));
+    };
+    let mut leg = MtfLeg::new(ltf, htf_ms, params, cfg, first).with_funding(funding);
+    for h in htf.iter().copied() {
+        leg.step(h);
+    }
+    leg.finish()
+}
+
+/// Как [`run_mm_mtf`], но лимитки исполняются историческими сделками:
+/// сетка на LTF свече строится от close предыдущей (без заглядывания вперёд)
+/// и стоит, пока не придёт следующая. `ltf` — свечи из этих же сделок.
+pub fn run_mm_mtf_trades(
+    htf: &[Candle],
+    ltf: &[Candle],
+    trades: &[Trade],
+    (htf_ms, ltf_ms): (i64, i64),
+    params: &MmParams,
+    cfg: MmRunConfig,
+) -> MmRun {
+    let Some(first) = htf.first() else {
+        return MmSimulator::new(cfg, Price(0.0)).finish(Price(0.0));
+    };
+    let mut leg = MtfLeg::new(ltf, htf_ms, params, cfg, first).with_trades(trades, ltf_ms);
+    for h in htf.iter().copied() {
+        leg.step(h);
+    }
+    leg.finish()
+}
+
+/// Сделки для исполнения сетки по LTF окнам
+struct TradeFeed<'a> {
+    trades: &'a [Trade],
+    idx: usize,
+    ltf_ms: i64,
+    prev_close: Option<Price>,
+}
+
+impl<'a> TradeFeed<'a> {
+    /// Сделки в `[start, end)`; более ранние пропускаются
+    fn window(&mut self, start: i64, end: i64) -> &'a [Trade] {
+        let trades = self.trades;
+        while self.idx < trades.len() && trades[self.idx].ts.0 < start {
+            self.idx += 1;
+        }
+        let from = self.idx;
+        while self.idx < trades.len() && trades[self.idx].ts.0 < end {
+            self.idx += 1;
+        }
+        &trades[from..self.idx]
+    }
+}
+
+/// Пошаговый MTF прогон одного символа: `run_mm_mtf` и портфель
+pub(crate) struct MtfLeg<'a> {
+    pub(crate) sim: MmSimulator,
+    ltf: &'a [Candle],
+    htf_ms: i64,
+    params: &'a MmParams,
+    structure: Structure,
+    policy: MmPolicyParams,
+    fee_ratio: f64,
+    active_mode: MmMode,
+    ltf_idx: usize,
+    last_ts: i64,
+    /// Первая LTF свеча TWAP выхода
+    twap_from: usize,
+    trades: Option<TradeFeed<'a>>,
+}
+
+impl<'a> MtfLeg<'a> {
+    pub(crate) fn new(
+        ltf: &'a [Candle],
+        htf_ms: i64,
+        params: &'a MmParams,
+        cfg: MmRunConfig,
+        first: &Candle,
+    ) -> Self {
+        let sim = MmSimulator::new(cfg, first.close).with_hard_band(params);
+        Self {
+            twap_from: sim.twap_from(ltf.len()),
+            sim,
+            ltf,
+            htf_ms,
+            params,
+            structure: Structure::new(params.signal),
+            policy: params.policy(),
+            fee_ratio: params.maker_fee_bps.max(0.0) / 10_000.0,
+            active_mode: MmMode::Disabled,
+            ltf_idx: 0,
+            last_ts: first.ts.0,
+            trades: None,
+        }
+    }
+
+    pub(crate) fn with_funding(mut self, funding: &[FundingRate]) -> Self {
+        // до первого step `last_ts` — ts первой HTF свечи
+        self.sim = self.sim.with_funding(funding, self.last_ts);
+        self
+    }
+
+    /// Исполнение по сделкам вместо касания high/low LTF свечи
+    pub(crate) fn with_trades(mut self, trades: &'a [Trade], ltf_ms: i64) -> Self {
+        self.trades = Some(TradeFeed {
+            trades,
+            idx: 0,
+            ltf_ms,
+            prev_close: None,
+        });
+        self
+    }
+
+    /// LTF свечи окна HTF свечи `h`, затем решение на её закрытии
+    pub(crate) fn step(&mut self, h: Candle) {
+        let window_start = h.ts.0;
+        let window_end = window_start + self.htf_ms;
+        let ltf = self.ltf;
+
+        while self.ltf_idx < ltf.len() && ltf[self.ltf_idx].ts.0 < window_start {
+            self.ltf_idx += 1;
+        }
+        while self.ltf_idx < ltf.len() && ltf[self.ltf_idx].ts.0 < window_end {
+            let lc = ltf[self.ltf_idx];
+            self.last_ts = lc.ts.0;
+            self.sim.settle_funding(&lc);
+            if self.ltf_idx >= self.twap_from {
+                self.twap_slice(lc);
+                continue;
+            }
+            let active = matches!(self.active_mode, MmMode::Normal | MmMode::Defensive);
+            let grid = self.params.grid_for(self.active_mode);
+            match self.trades.as_mut() {
+                None if active => {
+                    let orders = build_grid(lc.close, lc.close, self.sim.inventory(lc.close), grid);
+                    if let Some(orders) = orders {
+                        self.sim
+                            .fill_grid(&lc, orders, self.fee_ratio, self.active_mode);
+                    }
+                }
+                None => {}
+                Some(feed) => {
+                    let window = feed.window(lc.ts.0, lc.ts.0 + feed.ltf_ms);
+                    let orders = feed
+                        .prev_close
+                        .filter(|_| active)
+                        .and_then(|mid| build_grid(mid, mid, self.sim.inventory(mid), grid));
+                    if let Some(orders) = orders {
+                        self.sim
+                            .fill_trades(window, orders, self.fee_ratio, self.active_mode);
+                    }
+                    feed.prev_close = Some(lc.close);
+                }
+            }
+            self.sim.mark(&lc, self.active_mode);
+            self.ltf_idx += 1;
+        }
+        // идёт TWAP выход: ни сетки, ни bootstrap
+        if self.ltf_idx > self.twap_from {
+            self.active_mode = MmMode::Disabled;
+            return;
+        }
+
+        let Some(mid) = self.structure.on_close(h) else {
+            self.active_mode = MmMode::Disabled;
+            return;
+        };
+        let Some(mut decision) = self
+            .structure
+            .decide(self.sim.inventory(mid), mid, self.policy)
+        else {
+            self.active_mode = MmMode::Disabled;
+            return;
+        };
+
+        if self.sim.cfg.bootstrap_rebalance
+            && matches!(decision.reason, MmDecisionReason::InventoryOutsideHardBand)
+            && self.structure.bos.state == BosState::Confirmed
+            && self.structure.pullback.triggered
+        {
+            self.sim.bootstrap(h.ts.0, mid);
+            if let Some(d) = self
+                .structure
+                .decide(self.sim.inventory(mid), mid, self.policy)
+            {
+                decision = d;
+            }
+        }
+        self.active_mode = decision.mode;
+    }
+
+    /// Доля TWAP выхода на LTF свече: VWAP её сделок или typical price
+    fn twap_slice(&mut self, lc: Candle) {
+        let vwap = match self.trades.as_mut() {
+            Some(feed) => {
+                feed.prev_close = Some(lc.close);
+                trades_vwap(feed.window(lc.ts.0, lc.ts.0 + feed.ltf_ms))
+            }
+            None => None,
+        };
+        let slices = self.ltf.len() - self.ltf_idx;
+        self.sim
+            .twap_slice(lc.ts.0, vwap.unwrap_or_else(|| candle_vwap(&lc)), slices);
+        self.sim.mark(&lc, MmMode::Disabled);
+        self.ltf_idx += 1;
+    }
+
+    /// Close последней пройденной LTF свечи
+    pub(crate) fn last_close(&self) -> Option<Price> {
+        self.ltf_idx.checked_sub(1).map(|i| self.ltf[i].close)
+    }
+
+    pub(crate) fn finish(mut self) -> MmRun {
+        let final_mark = self.ltf.last().map(|c| c.close).unwrap_or(Price(0.0));
+        self.sim.force_close(self.last_ts, final_mark);
+        self.sim.finish(final_mark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{TimestampMs, TradeSide};
+
+    fn candle(ts: i64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(close),
+            high: Price(close * 1.003),
+            low: Price(close * 0.997),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    fn params() -> MmParams {
+        MmParams {
+            levels: 3,
+            step_bps: 10.0,
+            base_quote_per_order: 25.0,
+            max_size_mult: 2.0,
+            min_base_qty: 0.0001,
+            soft_min: 0.40,
+            soft_max: 0.60,
+            hard_min: 0.35,
+            hard_max: 0.65,
+            maker_fee_bps: 10.0,
+            defensive_step_mult: 1.5,
+            defensive_size_mult: 0.5,
+            signal: SignalParams::default(),
+        }
+    }
+
+    fn cfg() -> MmRunConfig {
+        MmRunConfig {
+            initial_quote: 1000.0,
+            initial_base: 0.0,
+            initial_base_ratio: None,
+            perp: None,
+            taker: ExecutionModel {
+                fee_bps: 10.0,
+                spread_bps: 8.0,
+                slippage_bps: 2.0,
+            },
+            force_close_at_end: true,
+            force_close_twap: None,
+            bootstrap_rebalance: true,
+            bootstrap_target_ratio: 0.5,
+            intrabar: IntrabarPath::Sorted,
+            fill: FillRule::default(),
+            record: true,
+        }
+    }
+
+    #[test]
+    fn runs_are_consistent_and_flat_at_end() {
+        // флэт с пивотом 102, пробой вверх, откат и боковик выше уровня
+        let mut closes: Vec<f64> = (0..20).map(|i| 100.0 + 2.0 * (i % 2) as f64).collect();
+        closes.extend([104.0, 106.0, 108.0, 110.0, 106.0]);
+        closes.extend((0..200).map(|i| 106.0 + 2.0 * (i % 2) as f64));
+        let htf: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| candle(i as i64 * 300_000, *c))
+            .collect();
+        let ltf: Vec<Candle> = htf
+            .iter()
+            .flat_map(|h| (0..5).map(move |k| candle(h.ts.0 + k * 60_000, h.close.0)))
+            .collect();
+
+        let balanced = MmRunConfig {
+            initial_base: 10.0,
+            ..cfg()
+        };
+        for (run, cfg) in [
+            (run_mm(&htf, &params(), balanced), balanced),
+            (run_mm_mtf(&htf, &ltf, 300_000, &params(), cfg()), cfg()),
+        ] {
+            let r = run.report;
+            assert!(r.buy_fills > 0 && r.sell_fills > 1);
+            assert_eq!(r.final_base, 0.0);
+            assert_eq!(r.sell_fills, r.perf.closed_trades);
+            assert_eq!(
+                run.fill_rows.len(),
+                r.buy_fills + r.sell_fills,
+                "every fill is recorded once"
+            );
+            let flows: f64 = run.fill_rows.iter().map(|f| f.quote_delta).sum();
+            assert!((r.final_quote - cfg.initial_quote - flows).abs() < 1e-6);
+            let initial_equity = cfg.initial_quote + cfg.initial_base * 108.0;
+            assert!((r.perf.pnl - (r.final_equity - initial_equity)).abs() < 1e-9);
+        }
+        assert!(
+            run_mm_mtf(&htf, &ltf, 300_000, &params(), cfg())
+                .report
+                .bootstrap_trades
+                > 0
+        );
+    }
+
+    #[test]
+    fn trades_fill_only_through_price_and_within_volume() {
+        let mut closes: Vec<f64> = (0..20).map(|i| 100.0 + 2.0 * (i % 2) as f64).collect();
+        closes.extend([104.0, 106.0, 108.0, 110.0, 106.0]);
+        closes.extend((0..200).map(|i| 106.0 + 2.0 * (i % 2) as f64));
+        let htf: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| candle(i as i64 * 300_000, *c))
+            .collect();
+        // три сделки по 0.05 в минуту: close −0.3%, close, close +0.3%
+        let wide: Vec<Trade> = htf
+            .iter()
+            .flat_map(|h| {
+                (0..15).map(move |k| Trade {
+                    ts: TimestampMs(h.ts.0 + k * 20_000),
+                    price: Price(h.close.0 * (1.0 + 0.003 * [-1.0, 0.0, 1.0][k as usize % 3])),
+                    qty: Qty(0.05),
+                    side: TradeSide::Buy,
+                })
+            })
+            .collect();
+        let ltf = crate::trades::candles_from_trades(&wide, 60_000);
+        let r = run_mm_mtf_trades(&htf, &ltf, &wide, (300_000, 60_000), &params(), cfg());
+        assert!(r.report.buy_fills > 0 && r.report.sell_fills > 0);
+        let flows: f64 = r.fill_rows.iter().map(|f| f.quote_delta).sum();
+        assert!((r.report.final_quote - cfg().initial_quote - flows).abs() < 1e-6);
+        assert!(
+            r.fill_rows
+                .iter()
+                .filter(|f| f.mode == "Normal" || f.mode == "Defensive")
+                .all(|f| f.qty <= 0.05 + 1e-12),
+            "capped by trade size"
+        );
+
+        // каждый fill сетки — по сделке, прошедшей через цену лимитки
+        for f in r.fill_rows.iter().filter(|f| f.mode == "Normal") {
+            assert!(wide.iter().any(|t| t.ts.0 == f.ts
+                && if f.side == "BUY" {
+                    t.price.0 <= f.price
+                } else {
+                    t.price.0 >= f.price
+                }));
+        }
+    }
+
+    #[test]
+    fn intrabar_path_orders_fills_within_candle() {
+        let order = |side, price| DesiredOrder {
+            side,
+            price: Price(price),
+            qty: Qty(1.0),
+        };
+        let orders = vec![
+            order(Side::Buy, 99.0),
+            order(Side::Sell, 101.0),
+            order(Side::Buy, 95.0),
+        ];
+        let c = Candle {
+            high: Price(102.0),
+            low: Price(98.0),
+            ..candle(0, 100.0)
+        };
+        let prices = |path| {
+            along_path(orders.clone(), path, FillRule::default())
+                .iter()
+                .map(|o| o.price.0)
+                .collect::<Vec<_>>()
+        };
+        // 95 не задет; вверх сначала — продажа раньше покупки
+        assert_eq!(prices([c.open, c.high, c.low, c.close]), [101.0, 99.0]);
+        assert_eq!(prices([c.open, c.low, c.high, c.close]), [99.0, 101.0]);
+
+        // без base: продажа возможна, только если покупка на свече была раньше
+        let fill = |intrabar| {
+            let cfg = MmRunConfig {
+                initial_quote: 100.0,
+                intrabar,
+                ..cfg()
+            };
+            let mut sim = MmSimulator::new(cfg, c.close);
+            sim.fill_grid(&c, orders.clone(), 0.0, MmMode::Normal);
+            (sim.ledger.base, sim.ledger.equity(c.close.0))
+        };
+        assert_eq!(fill(IntrabarPath::Ohlc), (1.0, 101.0));
+        assert_eq!(fill(IntrabarPath::Olhc), (0.0, 102.0));
+        assert_eq!(fill(IntrabarPath::Sorted), (0.0, 102.0));
+        assert_eq!(fill(IntrabarPath::WorstCase), fill(IntrabarPath::Ohlc));
+        assert_eq!(fill(IntrabarPath::BestCase), fill(IntrabarPath::Olhc));
+    }
+
+    #[test]
+    fn strict_fill_mode_requires_trading_through_level() {
+        let strict = FillRule::new(FillMode::Strict, 0, 0.01).unwrap();
+        let deep = FillRule::new(FillMode::Strict, 2, 0.5).unwrap();
+        assert!(FillRule::default().fills(Side::Buy, 99.0, 99.0));
+        assert!(!strict.fills(Side::Buy, 99.0, 99.0));
+        assert!(strict.fills(Side::Buy, 98.99, 99.0));
+        assert!(!deep.fills(Side::Buy, 98.0, 99.0));
+        assert!(deep.fills(Side::Buy, 97.9, 99.0));
+        assert!(deep.fills(Side::Sell, 100.1, 99.0));
+        assert!(FillRule::new(FillMode::Touch, 1, 0.01).is_err());
+
+        // low ровно на уровне покупки, high прошёл продажу
+        let c = Candle {
+            high: Price(101.5),
+            low: Price(99.0),
+            ..candle(0, 100.0)
+        };
+        let orders = vec![
+            DesiredOrder {
+                side: Side::Buy,
+                price: Price(99.0),
+                qty: Qty(1.0),
+            },
+            DesiredOrder {
+                side: Side::Sell,
+                price: Price(101.0),
+                qty: Qty(1.0),
+            },
+        ];
+        for intrabar in [IntrabarPath::Sorted, IntrabarPath::Olhc] {
+            let fills = |fill| {
+                let cfg = MmRunConfig {
+                    initial_base: 1.0,
+                    intrabar,
+                    fill,
+                    ..cfg()
+                };
+                let mut sim = MmSimulator::new(cfg, c.close);
+                sim.fill_grid(&c, orders.clone(), 0.0, MmMode::Normal);
+                (sim.buy_fills, sim.trades.closed)
+            };
+            assert_eq!(fills(FillRule::default()), (1, 1));
+            assert_eq!(fills(strict), (0, 1));
+            assert_eq!(fills(deep), (0, 0));
+        }
+    }
+
+    #[test]
+    fn volume_cap_carries_level_remainder_to_next_candles() {
+        let cfg = MmRunConfig {
+            fill: FillRule::default().with_volume_frac(Some(0.3)).unwrap(),
+            ..cfg()
+        };
+        let buy = |price| {
+            vec![DesiredOrder {
+                side: Side::Buy,
+                price: Price(price),
+                qty: Qty(1.0),
+            }]
+        };
+        // объём каждой свечи — 1.0, за свечу уровень берёт не больше 0.3
+        let mut sim = MmSimulator::new(cfg, Price(100.0));
+        sim.fill_grid(&candle(0, 99.0), buy(99.0), 0.0, MmMode::Normal);
+        sim.mark(&candle(0, 99.0), MmMode::Normal);
+        assert!((sim.ledger.base - 0.3).abs() < 1e-12);
+        assert_eq!(sim.carry.len(), 1);
+
+        // остаток уровня переезжает на новую цену сетки
+        sim.fill_grid(&candle(60_000, 98.0), buy(98.0), 0.0, MmMode::Normal);
+        sim.mark(&candle(60_000, 98.0), MmMode::Normal);
+        assert!((sim.ledger.base - 0.6).abs() < 1e-12);
+        assert!((sim.carry[0].2 - 0.4).abs() < 1e-12);
+
+        // свеча без сетки отменяет остатки
+        sim.mark(&candle(120_000, 98.0), MmMode::Disabled);
+        assert!(sim.carry.is_empty());
+        assert!(FillRule::default().with_volume_frac(Some(1.5)).is_err());
+    }
+
+    #[test]
+    fn signal_params_drive_bos_confirmation() {
+        let mut closes: Vec<f64> = (0..20).map(|i| 100.0 + 2.0 * (i % 2) as f64).collect();
+        closes.extend([104.0, 106.0, 108.0, 110.0, 106.0]);
+        closes.extend((0..50).map(|i| 106.0 + 2.0 * (i % 2) as f64));
+        let htf: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| candle(i as i64 * 300_000, *c))
+            .collect();
+        let cfg = MmRunConfig {
+            initial_base: 10.0,
+            ..cfg()
+        };
+
+        let mut strict = params();
+        strict.signal.bos.confirm_candles = 500;
+        assert_eq!(run_mm(&htf, &strict, cfg).report.buy_fills, 0);
+        assert!(run_mm(&htf, &params(), cfg).report.buy_fills > 0);
+
+        strict.signal.bos.confirm_candles = 0;
+        assert!(strict.signal.validate().is_err());
+        assert!(SignalParams::default().validate().is_ok());
+    }
+
+    #[test]
+    fn perp_pays_funding_and_liquidates_levered_position() {
+        let perp = MmRunConfig {
+            perp: Some(PerpParams::new(5.0, 0.01).unwrap()),
+            ..cfg()
+        };
+        let rate = |ts, rate| FundingRate {
+            ts: TimestampMs(ts),
+            rate,
+        };
+        // ставка до старта прогона не начисляется
+        let funding = [rate(0, 0.01), rate(60_000, 0.001)];
+        let mut sim = MmSimulator::new(perp, Price(100.0)).with_funding(&funding, 30_000);
+        assert_eq!(sim.inventory(Price(100.0)).quote.0, 5000.0);
+
+        sim.ledger.buy(30.0, 100.0, 0.0);
+        sim.settle_funding(&candle(60_000, 100.0));
+        assert!((sim.funding_paid - 3.0).abs() < 1e-9);
+        sim.mark(&candle(120_000, 100.0), MmMode::Normal);
+        assert_eq!(sim.liquidations, 0);
+
+        let crash = Candle {
+            low: Price(60.0),
+            ..candle(180_000, 90.0)
+        };
+        sim.mark(&crash, MmMode::Normal);
+        assert_eq!((sim.liquidations, sim.ledger.base), (1, 0.0));
+        let r = sim.finish(Price(90.0)).report;
+        assert_eq!(r.liquidations, 1);
+        assert!((r.funding_paid - 3.0).abs() < 1e-9);
+        // остаётся поддерживающая маржа минус taker комиссия
+        assert!(r.final_equity > 0.0 && r.final_equity < 0.01 * 30.0 * 70.0);
+
+        // на spot ставки игнорируются
+        let spot = MmSimulator::new(cfg(), Price(100.0)).with_funding(&funding, 0);
+        assert!(spot.funding.is_empty());
+    }
+
+    #[test]
+    fn initial_base_ratio_splits_equity_at_first_close() {
+        let ratio = MmRunConfig {
+            initial_quote: 1000.0,
+            initial_base: 5.0,
+            initial_base_ratio: Some(0.5),
+            ..cfg()
+        };
+        assert_eq!(ratio.initial_balances(200.0), (1000.0, 5.0));
+        assert_eq!(ratio.initial_balances(100.0), (750.0, 7.5));
+        assert_eq!(cfg().initial_balances(100.0), (1000.0, 0.0));
+
+        // то же самое, что заданные вручную балансы
+        let htf: Vec<Candle> = (0..60)
+            .map(|i| candle(i * 300_000, 100.0 + 2.0 * (i % 2) as f64))
+            .collect();
+        let explicit = MmRunConfig {
+            initial_quote: 750.0,
+            initial_base: 7.5,
+            ..cfg()
+        };
+        let a = run_mm(&htf, &params(), ratio).report;
+        let b = run_mm(&htf, &params(), explicit).report;
+        assert_eq!(a.final_equity, b.final_equity);
+        assert_eq!(a.perf.pnl, b.perf.pnl);
+    }
+
+    #[test]
+    fn vwap_force_close_sells_in_slices_over_last_ltf_candles() {
+        let htf: Vec<Candle> = (0..4).map(|i| candle(i * 900_000, 100.0)).collect();
+        let ltf: Vec<Candle> = (0..12).map(|i| candle(i * 300_000, 100.0)).collect();
+        let twap = MmRunConfig {
+            initial_base: 2.0,
+            force_close_twap: ForceCloseMode::Vwap.twap_candles(3).unwrap(),
+            ..cfg()
+        };
+        let run = run_mm_mtf(&htf, &ltf, 900_000, &params(), twap);
+        let slices: Vec<&FillRow> = run
+            .fill_rows
+            .iter()
+            .filter(|f| f.mode == "ForceCloseTwap")
+            .collect();
+        assert_eq!(slices.len(), 3);
+        assert_eq!(slices[0].ts, 9 * 300_000);
+        assert!(slices.iter().all(|f| (f.qty - 2.0 / 3.0).abs() < 1e-9));
+        assert!(run.fill_rows.iter().all(|f| f.mode != "ForceClose"));
+        assert_eq!(run.report.final_base, 0.0);
+
+        // typical price = 100: без спреда и проскальзывания одного fill'а
+        let last = 2.0 * 100.0 * (1.0 - 0.0006) * (1.0 - 0.001);
+        let expected = 2.0 * 100.0 * (1.0 - 0.001) - last;
+        assert!((run.report.force_close_vs_last - expected).abs() < 1e-9);
+        assert!(ForceCloseMode::Vwap.twap_candles(0).is_err());
+        assert_eq!(ForceCloseMode::Last.twap_candles(3).unwrap(), None);
+    }
+}
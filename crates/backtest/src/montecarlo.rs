@@ -0,0 +1,250 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::report::{FillRow, TrendRoundTripRow};
+
+/// Как пересобирается последовательность сделок
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McMode {
+    /// Сделки выбираются с возвращением: меняются и итог, и путь equity
+    #[default]
+    Bootstrap,
+    /// Те же сделки в случайном порядке: итог тот же, меняется просадка
+    Shuffle,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct McParams {
+    pub runs: usize,
+    pub mode: McMode,
+    /// Доп. проскальзывание на сделку: равномерно от 0 до `slippage_bps` её оборота
+    pub slippage_bps: f64,
+    pub seed: u64,
+}
+
+impl McParams {
+    pub fn validate(&self) -> Result<()> {
+        if self.runs == 0 {
+            anyhow::bail!("mc_runs must be > 0");
+        }
+        if self.slippage_bps < 0.0 {
+            anyhow::bail!("mc_slippage_bps must be >= 0");
+        }
+        Ok(())
+    }
+}
+
+/// Закрытая сделка: PnL и оборот, на который ложится случайное проскальзывание
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct McTrade {
+    pub pnl: f64,
+    pub notional: f64,
+}
+
+impl McTrade {
+    /// Сделки trend backtest'а: оборот — вход и выход
+    pub fn from_round_trips(rows: &[TrendRoundTripRow]) -> Vec<Self> {
+        rows.iter()
+            .map(|t| Self {
+                pnl: t.pnl,
+                notional: t.qty * (t.entry_price + t.exit_price),
+            })
+            .collect()
+    }
+
+    /// Сделки MM: fill'ы с реализованным PnL; оборот — вход и выход по цене закрытия
+    pub fn from_fills(rows: &[FillRow]) -> Vec<Self> {
+        rows.iter()
+            .filter_map(|f| {
+                f.realized_pnl.map(|pnl| Self {
+                    pnl,
+                    notional: 2.0 * f.qty * f.price,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Метрики одного прогона на equity по сделкам
+#[derive(Debug, Clone, Serialize)]
+pub struct McRow {
+    pub run: usize,
+    pub roi_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub profit_factor: f64,
+}
+
+/// `runs` пересобранных последовательностей сделок от `initial_equity`
+pub fn simulate(trades: &[McTrade], initial_equity: f64, params: &McParams) -> Vec<McRow> {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let mut order: Vec<usize> = (0..trades.len()).collect();
+    let mut out = Vec::with_capacity(params.runs);
+    for run in 1..=params.runs {
+        match params.mode {
+            McMode::Bootstrap => {
+                for i in order.iter_mut() {
+                    *i = rng.gen_range(0..trades.len());
+                }
+            }
+            McMode::Shuffle => {
+                // Fisher–Yates
+                for i in (1..order.len()).rev() {
+                    order.swap(i, rng.gen_range(0..=i));
+                }
+            }
+        }
+        let mut equity = initial_equity;
+        let mut peak = equity;
+        let mut max_dd = 0.0_f64;
+        let (mut gross_profit, mut gross_loss) = (0.0, 0.0);
+        for &i in &order {
+            let t = trades[i];
+            let slip = t.notional * params.slippage_bps * rng.r#gen::<f64>() / 10_000.0;
+            let pnl = t.pnl - slip;
+            if pnl >= 0.0 {
+                gross_profit += pnl;
+            } else {
+                gross_loss -= pnl;
+            }
+            equity += pnl;
+            peak = peak.max(equity);
+            if peak > 0.0 {
+                max_dd = max_dd.max((peak - equity) / peak);
+            }
+        }
+        out.push(McRow {
+            run,
+            roi_pct: if initial_equity > 0.0 {
+                100.0 * (equity - initial_equity) / initial_equity
+            } else {
+                0.0
+            },
+            max_drawdown_pct: max_dd * 100.0,
+            profit_factor: if gross_loss > 0.0 {
+                gross_profit / gross_loss
+            } else {
+                f64::INFINITY
+            },
+        });
+    }
+    out
+}
+
+/// Квантили метрики по прогонам: 5/50/95 — 90% интервал и медиана
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct McInterval {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl McInterval {
+    fn new(mut xs: Vec<f64>) -> Self {
+        xs.sort_by(f64::total_cmp);
+        let q = |p: f64| {
+            let idx = ((xs.len() as f64 - 1.0) * p).round() as usize;
+            xs.get(idx).copied().unwrap_or(0.0)
+        };
+        Self {
+            p5: q(0.05),
+            p50: q(0.5),
+            p95: q(0.95),
+        }
+    }
+}
+
+/// Итог Monte Carlo для stdout и JSON отчёта
+#[derive(Debug, Clone, Serialize)]
+pub struct McSummary {
+    pub runs: usize,
+    pub trades: usize,
+    pub roi_pct: McInterval,
+    pub max_drawdown_pct: McInterval,
+    /// inf (прогон без убыточных сделок) уходит в JSON как null
+    pub profit_factor: McInterval,
+    /// Доля прогонов с отрицательным ROI, %
+    pub loss_prob_pct: f64,
+}
+
+impl McSummary {
+    pub fn new(rows: &[McRow], trades: usize) -> Self {
+        let losses = rows.iter().filter(|r| r.roi_pct < 0.0).count();
+        Self {
+            runs: rows.len(),
+            trades,
+            roi_pct: McInterval::new(rows.iter().map(|r| r.roi_pct).collect()),
+            max_drawdown_pct: McInterval::new(rows.iter().map(|r| r.max_drawdown_pct).collect()),
+            profit_factor: McInterval::new(rows.iter().map(|r| r.profit_factor).collect()),
+            loss_prob_pct: 100.0 * losses as f64 / rows.len().max(1) as f64,
+        }
+    }
+
+    /// Строка `mc:` для stdout
+    pub fn label(&self) -> String {
+        format!(
+            "runs={} trades={} roi_p5={:.2}% roi_p50={:.2}% roi_p95={:.2}% dd_p50={:.2}% dd_p95={:.2}% pf_p5={:.4} pf_p50={:.4} loss_prob={:.1}%",
+            self.runs,
+            self.trades,
+            self.roi_pct.p5,
+            self.roi_pct.p50,
+            self.roi_pct.p95,
+            self.max_drawdown_pct.p50,
+            self.max_drawdown_pct.p95,
+            self.profit_factor.p5,
+            self.profit_factor.p50,
+            self.loss_prob_pct
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(pnl: f64) -> McTrade {
+        McTrade {
+            pnl,
+            notional: 1000.0,
+        }
+    }
+
+    #[test]
+    fn shuffle_keeps_total_and_bootstrap_spreads_it() {
+        let trades: Vec<McTrade> = [50.0, -20.0, 30.0, -40.0, 10.0, 25.0]
+            .into_iter()
+            .map(trade)
+            .collect();
+        let mut params = McParams {
+            runs: 200,
+            mode: McMode::Shuffle,
+            slippage_bps: 0.0,
+            seed: 7,
+        };
+        assert!(McParams { runs: 0, ..params }.validate().is_err());
+
+        let rows = simulate(&trades, 1000.0, &params);
+        assert_eq!(rows.len(), 200);
+        assert!(rows.iter().all(|r| (r.roi_pct - 5.5).abs() < 1e-9));
+        let dd: Vec<f64> = rows.iter().map(|r| r.max_drawdown_pct).collect();
+        assert!(dd.iter().any(|&d| d != dd[0]));
+        // тот же seed — те же прогоны
+        assert_eq!(
+            simulate(&trades, 1000.0, &params)[17].max_drawdown_pct,
+            rows[17].max_drawdown_pct
+        );
+
+        params.mode = McMode::Bootstrap;
+        params.slippage_bps = 10.0;
+        let rows = simulate(&trades, 1000.0, &params);
+        let s = McSummary::new(&rows, trades.len());
+        assert!(s.roi_pct.p5 < s.roi_pct.p50 && s.roi_pct.p50 < s.roi_pct.p95);
+        assert!(s.max_drawdown_pct.p5 <= s.max_drawdown_pct.p95);
+        assert!(s.loss_prob_pct > 0.0 && s.loss_prob_pct < 100.0);
+        // проскальзывание только ухудшает: лучший прогон не выше всех прибыльных сделок
+        assert!(rows.iter().all(|r| r.roi_pct < 30.0));
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+use rayon::ThreadPool;
+use rayon::prelude::*;
+
+/// Пул прогонов sweep'а; `jobs = 0` — по числу ядер
+pub fn pool(jobs: usize) -> Result<ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .context("build sweep thread pool failed")
+}
+
+/// `run` по всем `items` на пуле; результаты — в порядке `items`.
+/// `on_done(n)` — число готовых, после каждых `every` и на последнем.
+pub fn par_map<T: Sync, R: Send>(
+    pool: &ThreadPool,
+    items: &[T],
+    every: usize,
+    run: impl Fn(&T) -> R + Sync,
+    on_done: impl FnMut(usize) + Send,
+) -> Vec<R> {
+    let every = every.max(1);
+    let done = AtomicUsize::new(0);
+    let on_done = Mutex::new(on_done);
+    pool.install(|| {
+        items
+            .par_iter()
+            .map(|item| {
+                let r = run(item);
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if n % every == 0 || n == items.len() {
+                    if let Ok(mut f) = on_done.lock() {
+                        // под блокировкой — последнее значение, счётчик не идёт назад
+                        f(done.load(Ordering::Relaxed));
+                    }
+                }
+                r
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_order_and_reports_progress() {
+        let pool = pool(4).unwrap();
+        let items: Vec<u64> = (0..100).collect();
+        let mut reported = Vec::new();
+        let out = par_map(&pool, &items, 10, |x| x * x, |n| reported.push(n));
+        assert_eq!(out, items.iter().map(|x| x * x).collect::<Vec<_>>());
+        assert_eq!(reported.len(), 10);
+        assert_eq!(reported.last(), Some(&100));
+
+        assert!(par_map(&pool, &[] as &[u64], 10, |x| *x, |_| {}).is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use bybit::rest::{BybitRest, FundingRate, download_funding};
+use core::types::TimestampMs;
+use execution::accounting::Ledger;
+
+use crate::data::{CacheMeta, Venue, cache_path_for};
+
+/// Рынок, на котором считается MM
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketKind {
+    #[default]
+    Spot,
+    /// Линейный perp: плечо, funding и ликвидация
+    Perp,
+}
+
+/// Линейный perp, только long (как и spot MM): позиция — `base`, заём под плечо —
+/// отрицательный `quote`, equity та же `quote + base·mark`
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct PerpParams {
+    pub leverage: f64,
+    /// Поддерживающая маржа, доля от notional позиции
+    pub maintenance_margin_rate: f64,
+}
+
+impl PerpParams {
+    pub fn new(leverage: f64, maintenance_margin_rate: f64) -> Result<Self> {
+        if !(1.0..=100.0).contains(&leverage) {
+            anyhow::bail!("leverage must be in [1, 100]");
+        }
+        if !(0.0..1.0 / leverage).contains(&maintenance_margin_rate) {
+            anyhow::bail!("maintenance_margin_rate must be in [0, 1/leverage)");
+        }
+        Ok(Self {
+            leverage,
+            maintenance_margin_rate,
+        })
+    }
+
+    /// Параметры perp для `--market perp`, `None` на spot
+    pub fn for_market(
+        market: MarketKind,
+        leverage: f64,
+        maintenance_margin_rate: f64,
+    ) -> Result<Option<Self>> {
+        match market {
+            MarketKind::Spot => Ok(None),
+            MarketKind::Perp => Self::new(leverage, maintenance_margin_rate).map(Some),
+        }
+    }
+
+    /// Сколько quote ещё можно вложить в позицию: notional до `equity·leverage`
+    pub fn buying_power(&self, ledger: &Ledger, mark: f64) -> f64 {
+        (ledger.equity(mark) * self.leverage - ledger.base * mark).max(0.0)
+    }
+
+    /// Mark, на котором equity падает до поддерживающей маржи; без займа ликвидации нет
+    pub fn liquidation_price(&self, ledger: &Ledger) -> Option<f64> {
+        if ledger.base <= 0.0 || ledger.quote >= 0.0 {
+            return None;
+        }
+        // quote + base·p = mmr·base·p
+        Some(-ledger.quote / (ledger.base * (1.0 - self.maintenance_margin_rate)))
+    }
+}
+
+/// Платёж funding позиции `base` по `mark`: long платит при положительной ставке
+pub fn funding_payment(base: f64, mark: f64, rate: f64) -> f64 {
+    base * mark * rate
+}
+
+/// Строка CSV-кэша funding
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct FundingRow {
+    ts: i64,
+    rate: f64,
+}
+
+fn read_funding_cache(path: &str) -> Result<Vec<FundingRate>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut out = Vec::new();
+    for r in rdr.deserialize::<FundingRow>() {
+        let row = r?;
+        out.push(FundingRate {
+            ts: TimestampMs(row.ts),
+            rate: row.rate,
+        });
+    }
+    Ok(out)
+}
+
+/// Funding `[start, end]` из CSV-кэша, если он покрывает символ и диапазон,
+/// иначе (или при `refresh`) — из истории Bybit (linear).
+/// `cache = None` — путь по умолчанию (`data/cache/ETHUSDT_funding_...csv`).
+pub async fn load_funding(
+    symbol: &str,
+    range: (i64, i64),
+    cache: Option<&str>,
+    refresh: bool,
+) -> Result<Vec<FundingRate>> {
+    let (start_ms, end_ms) = range;
+    let path = cache_path_for(cache, false, symbol, "funding", range);
+    if !refresh && Path::new(&path).exists() {
+        let valid = CacheMeta::read(&path).is_none_or(|m| m.covers(symbol, "funding", range));
+        if valid {
+            let mut funding =
+                read_funding_cache(&path).with_context(|| format!("read cache {} failed", path))?;
+            funding.retain(|f| f.ts.0 >= start_ms && f.ts.0 <= end_ms);
+            return Ok(funding);
+        }
+        println!(
+            "cache: {} does not cover {} funding, refreshing",
+            path, symbol
+        );
+    }
+
+    let funding = download_funding(&BybitRest::new(), symbol, start_ms, end_ms)
+        .await
+        .with_context(|| format!("download {} funding failed", symbol))?;
+    crate::report::write_csv(
+        &path,
+        funding.iter().map(|f| FundingRow {
+            ts: f.ts.0,
+            rate: f.rate,
+        }),
+    )
+    .with_context(|| format!("write cache {} failed", path))?;
+    CacheMeta {
+        symbol: symbol.to_string(),
+        interval: "funding".to_string(),
+        start_ms,
+        end_ms,
+        exchange: Venue::Bybit,
+        known_gaps: Vec::new(),
+    }
+    .write(&path)
+    .with_context(|| format!("write cache meta for {} failed", path))?;
+    Ok(funding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leverage_extends_buying_power_until_liquidation() {
+        let p = PerpParams::new(5.0, 0.01).unwrap();
+        let mut l = Ledger::new(1000.0, 0.0, 100.0);
+        assert_eq!(p.buying_power(&l, 100.0), 5000.0);
+        assert_eq!(p.liquidation_price(&l), None);
+
+        l.buy(30.0, 100.0, 0.0);
+        assert_eq!(l.quote, -2000.0);
+        assert_eq!(p.buying_power(&l, 100.0), 2000.0);
+        let liq = p.liquidation_price(&l).unwrap();
+        let equity = l.equity(liq);
+        assert!((equity - 0.01 * 30.0 * liq).abs() < 1e-9);
+        assert!(liq > 2000.0 / 30.0 && liq < 100.0);
+
+        assert!((funding_payment(30.0, 100.0, 0.0001) - 0.3).abs() < 1e-12);
+        assert!(PerpParams::new(0.5, 0.01).is_err());
+        assert!(PerpParams::new(20.0, 0.05).is_err());
+    }
+}
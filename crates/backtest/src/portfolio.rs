@@ -0,0 +1,540 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::Serialize;
+use structure::candle::Candle;
+
+use crate::mm::{MmParams, MmRunConfig, MtfLeg};
+use crate::report::PortfolioFillRow;
+use crate::stats::{
+    CostBreakdown, Drawdown, Performance, ReturnStats, TradeStats, correlation, std_dev,
+};
+
+/// Правило распределения капитала между символами
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Allocation {
+    Equal,
+    /// Веса обратно пропорциональны волатильности HTF доходностей за `vol_window` свечей
+    InverseVol,
+    /// Фиксированные веса в порядке символов (нормируются)
+    Weights(Vec<f64>),
+}
+
+impl Allocation {
+    /// `equal`, `inverse-vol` или веса через запятую
+    pub fn parse(s: &str, symbols: usize) -> Result<Self> {
+        match s.trim() {
+            "equal" => Ok(Self::Equal),
+            "inverse-vol" => Ok(Self::InverseVol),
+            list => {
+                let weights = list
+                    .split(',')
+                    .map(|w| w.trim().parse::<f64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| {
+                        anyhow::anyhow!(
+                            "allocation must be equal, inverse-vol or comma-separated weights"
+                        )
+                    })?;
+                if weights.len() != symbols {
+                    anyhow::bail!("expected {} weights, got {}", symbols, weights.len());
+                }
+                if weights.iter().any(|w| w.is_nan() || *w < 0.0)
+                    || weights.iter().sum::<f64>() <= 0.0
+                {
+                    anyhow::bail!("weights must be non-negative with a positive sum");
+                }
+                Ok(Self::Weights(weights))
+            }
+        }
+    }
+}
+
+/// Символ портфеля: HTF для решений, LTF для исполнения
+pub struct PortfolioMarket<'a> {
+    pub symbol: String,
+    pub htf: &'a [Candle],
+    pub ltf: &'a [Candle],
+}
+
+/// Строка equity-кривой портфеля (на закрытии HTF шага)
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioEquityRow {
+    pub ts: i64,
+    pub quote: f64,
+    pub positions_value: f64,
+    pub equity: f64,
+    pub drawdown_pct: f64,
+}
+
+/// Итоги одного символа в портфеле
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioSymbolRow {
+    pub symbol: String,
+    /// Целевой вес на последнем шаге
+    pub weight: f64,
+    pub buy_fills: usize,
+    pub sell_fills: usize,
+    pub bootstrap_trades: usize,
+    pub win_rate_pct: f64,
+    pub pnl: f64,
+    /// Доля в PnL портфеля, %
+    pub pnl_share_pct: f64,
+    pub total_costs: f64,
+}
+
+/// Корреляция приращений PnL двух символов по HTF шагам
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrelationRow {
+    pub symbol_a: String,
+    pub symbol_b: String,
+    pub correlation: f64,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct PortfolioReport {
+    pub symbols: usize,
+    pub buy_fills: usize,
+    pub sell_fills: usize,
+    pub bootstrap_trades: usize,
+    pub final_quote: f64,
+    pub final_equity: f64,
+    /// Средняя попарная корреляция PnL символов
+    pub mean_correlation: f64,
+    #[serde(flatten)]
+    pub perf: Performance,
+    pub costs: CostBreakdown,
+}
+
+#[derive(Debug, Clone)]
+pub struct PortfolioRun {
+    pub report: PortfolioReport,
+    pub symbol_rows: Vec<PortfolioSymbolRow>,
+    pub correlations: Vec<CorrelationRow>,
+    pub equity_rows: Vec<PortfolioEquityRow>,
+    pub fill_rows: Vec<PortfolioFillRow>,
+}
+
+/// Состояние символа в портфеле
+struct Slot<'a> {
+    leg: MtfLeg<'a>,
+    htf: &'a [Candle],
+    htf_idx: usize,
+    /// Чистый поток quote символа: его PnL = flows + base·mark
+    flows: f64,
+    mark: f64,
+    pnl_prev: f64,
+    pnl_deltas: Vec<f64>,
+    closes: Vec<f64>,
+    weight: f64,
+}
+
+impl Slot<'_> {
+    fn pnl(&self) -> f64 {
+        self.flows + self.leg.sim.ledger.base * self.mark
+    }
+}
+
+/// Общий quote портфеля: выдаёт символам бюджет на шаг, принимает их поток quote
+/// и отмечает equity, просадку и доходности на закрытии HTF шага
+struct PortfolioLedger {
+    quote: f64,
+    drawdown: Drawdown,
+    returns: ReturnStats,
+    record: bool,
+    equity_rows: Vec<PortfolioEquityRow>,
+}
+
+impl PortfolioLedger {
+    fn new(initial_quote: f64, record: bool) -> Self {
+        Self {
+            quote: initial_quote,
+            drawdown: Drawdown::new(initial_quote),
+            returns: ReturnStats::default(),
+            record,
+            equity_rows: Vec::new(),
+        }
+    }
+
+    /// Quote, который видит символ: его бюджет, но не больше свободного общего quote
+    fn lend(&self, budget: f64) -> f64 {
+        budget.clamp(0.0, self.quote.max(0.0))
+    }
+
+    /// Символ вернул `left` из выданных `lent`; возвращает его поток quote
+    fn settle(&mut self, lent: f64, left: f64) -> f64 {
+        let delta = left - lent;
+        self.quote += delta;
+        delta
+    }
+
+    fn equity(&self, positions_value: f64) -> f64 {
+        self.quote + positions_value
+    }
+
+    /// Отметка equity на закрытии шага `ts`
+    fn mark(&mut self, ts: i64, positions_value: f64) {
+        let equity = self.equity(positions_value);
+        self.returns.on_bar(ts, equity, positions_value > 0.0);
+        let Some(dd) = self.drawdown.update(ts, equity) else {
+            return;
+        };
+        if self.record {
+            self.equity_rows.push(PortfolioEquityRow {
+                ts,
+                quote: self.quote,
+                positions_value,
+                equity,
+                drawdown_pct: dd * 100.0,
+            });
+        }
+    }
+}
+
+/// MM на нескольких символах с общим quote балансом.
+///
+/// На каждом HTF шаге капитал делится по `allocation`: символ видит как свой
+/// quote бюджет `weight·equity − base·mark` (не больше свободного общего quote),
+/// сетка, policy и bootstrap считают inventory от него. Символы стартуют без base,
+/// equity портфеля отмечается на закрытии HTF шага.
+pub fn run_portfolio(
+    markets: &[PortfolioMarket],
+    htf_ms: i64,
+    params: &MmParams,
+    allocation: &Allocation,
+    vol_window: usize,
+    cfg: MmRunConfig,
+) -> Result<PortfolioRun> {
+    if markets.is_empty() {
+        anyhow::bail!("portfolio needs at least one symbol");
+    }
+    let leg_cfg = MmRunConfig {
+        initial_quote: 0.0,
+        initial_base: 0.0,
+        initial_base_ratio: None,
+        perp: None,
+        record: true,
+        ..cfg
+    };
+    let mut slots = Vec::with_capacity(markets.len());
+    for m in markets {
+        let Some(first) = m.htf.first() else {
+            anyhow::bail!("no HTF candles for {}", m.symbol);
+        };
+        slots.push(Slot {
+            leg: MtfLeg::new(m.ltf, htf_ms, params, leg_cfg, first),
+            htf: m.htf,
+            htf_idx: 0,
+            flows: 0.0,
+            mark: first.close.0,
+            pnl_prev: 0.0,
+            pnl_deltas: Vec::new(),
+            closes: Vec::new(),
+            weight: 0.0,
+        });
+    }
+
+    let steps: BTreeSet<i64> = markets
+        .iter()
+        .flat_map(|m| m.htf.iter().map(|c| c.ts.0))
+        .collect();
+    let last_ts = steps.last().copied().unwrap_or(0);
+    let mut ledger = PortfolioLedger::new(cfg.initial_quote, cfg.record);
+
+    for ts in steps {
+        let equity = ledger.equity(
+            slots
+                .iter()
+                .map(|s| s.leg.sim.ledger.base * s.mark)
+                .sum::<f64>(),
+        );
+        assign_weights(&mut slots, allocation, vol_window);
+
+        for slot in slots.iter_mut() {
+            let Some(h) = slot.htf.get(slot.htf_idx).copied().filter(|h| h.ts.0 == ts) else {
+                continue;
+            };
+            slot.htf_idx += 1;
+
+            let budget = slot.weight * equity - slot.leg.sim.ledger.base * slot.mark;
+            let visible = ledger.lend(budget);
+            slot.leg.sim.ledger.quote = visible;
+            slot.leg.step(h);
+            slot.flows += ledger.settle(visible, slot.leg.sim.ledger.quote);
+
+            if let Some(close) = slot.leg.last_close() {
+                slot.mark = close.0;
+            }
+            slot.closes.push(h.close.0);
+        }
+
+        let positions_value: f64 = slots.iter().map(|s| s.leg.sim.ledger.base * s.mark).sum();
+        ledger.mark(ts, positions_value);
+        for slot in slots.iter_mut() {
+            let pnl = slot.pnl();
+            slot.pnl_deltas.push(pnl - slot.pnl_prev);
+            slot.pnl_prev = pnl;
+        }
+    }
+
+    let mut correlations = Vec::new();
+    for i in 0..slots.len() {
+        for j in i + 1..slots.len() {
+            correlations.push(CorrelationRow {
+                symbol_a: markets[i].symbol.clone(),
+                symbol_b: markets[j].symbol.clone(),
+                correlation: correlation(&slots[i].pnl_deltas, &slots[j].pnl_deltas),
+            });
+        }
+    }
+    let mean_correlation = if correlations.is_empty() {
+        0.0
+    } else {
+        correlations.iter().map(|c| c.correlation).sum::<f64>() / correlations.len() as f64
+    };
+
+    let mut trades = TradeStats::default();
+    let mut costs = Vec::with_capacity(slots.len());
+    let mut symbol_rows = Vec::with_capacity(slots.len());
+    let mut fill_rows = Vec::new();
+    let mut hold_ms = 0.0;
+    let mut traded = 0.0;
+    let (mut buy_fills, mut sell_fills, mut bootstrap_trades) = (0, 0, 0);
+    let mut positions_value = 0.0;
+
+    for (mut slot, m) in slots.into_iter().zip(markets) {
+        // force close в конце: выручка уходит в общий quote
+        slot.leg.sim.ledger.quote = 0.0;
+        let run = slot.leg.finish();
+        let r = run.report;
+        slot.flows += ledger.settle(0.0, r.final_quote);
+        let mark = m.ltf.last().map_or(slot.mark, |c| c.close.0);
+        positions_value += r.final_base * mark;
+
+        for f in &run.fill_rows {
+            traded += f.qty * f.price;
+            if let Some(pnl) = f.realized_pnl {
+                trades.on_close(pnl);
+            }
+        }
+        hold_ms += r.perf.avg_trade_duration_h * 3_600_000.0 * r.sell_fills as f64;
+        buy_fills += r.buy_fills;
+        sell_fills += r.sell_fills;
+        bootstrap_trades += r.bootstrap_trades;
+        costs.push(r.costs);
+        symbol_rows.push(PortfolioSymbolRow {
+            symbol: m.symbol.clone(),
+            weight: slot.weight,
+            buy_fills: r.buy_fills,
+            sell_fills: r.sell_fills,
+            bootstrap_trades: r.bootstrap_trades,
+            win_rate_pct: r.perf.win_rate_pct,
+            pnl: slot.flows + r.final_base * mark,
+            pnl_share_pct: 0.0,
+            total_costs: r.costs.total,
+        });
+        if cfg.record {
+            fill_rows.extend(
+                run.fill_rows
+                    .into_iter()
+                    .map(|f| PortfolioFillRow::new(&m.symbol, f)),
+            );
+        }
+    }
+    fill_rows.sort_by_key(|f| f.ts);
+
+    let final_equity = ledger.equity(positions_value);
+    let total_pnl: f64 = symbol_rows.iter().map(|r| r.pnl).sum();
+    if total_pnl != 0.0 {
+        for row in symbol_rows.iter_mut() {
+            row.pnl_share_pct = 100.0 * row.pnl / total_pnl;
+        }
+    }
+
+    ledger.drawdown.update(last_ts, final_equity);
+    let avg_hold_ms = if sell_fills > 0 {
+        hold_ms / sell_fills as f64
+    } else {
+        0.0
+    };
+    let costs = CostBreakdown::sum(&costs);
+    Ok(PortfolioRun {
+        report: PortfolioReport {
+            symbols: markets.len(),
+            buy_fills,
+            sell_fills,
+            bootstrap_trades,
+            final_quote: ledger.quote,
+            final_equity,
+            mean_correlation,
+            perf: Performance::new(&trades, &ledger.drawdown, cfg.initial_quote, final_equity)
+                .with_risk(&ledger.returns, avg_hold_ms)
+                .with_turnover(traded, cfg.initial_quote),
+            costs,
+        },
+        symbol_rows,
+        correlations,
+        equity_rows: ledger.equity_rows,
+        fill_rows,
+    })
+}
+
+/// Целевые веса символов на текущем шаге
+fn assign_weights(slots: &mut [Slot], allocation: &Allocation, vol_window: usize) {
+    let raw: Vec<f64> = match allocation {
+        Allocation::Equal => vec![1.0; slots.len()],
+        Allocation::Weights(w) => w.clone(),
+        Allocation::InverseVol => {
+            let vols: Vec<Option<f64>> = slots
+                .iter()
+                .map(|s| {
+                    let closes = &s.closes[s.closes.len().saturating_sub(vol_window + 1)..];
+                    if closes.len() < vol_window.max(2) + 1 {
+                        return None;
+                    }
+                    let rets: Vec<f64> = closes.windows(2).map(|w| w[1] / w[0] - 1.0).collect();
+                    Some(std_dev(&rets)).filter(|v| *v > 0.0)
+                })
+                .collect();
+            // пока у кого-то нет истории — поровну
+            if vols.iter().all(Option::is_some) {
+                vols.iter().map(|v| 1.0 / v.unwrap_or(1.0)).collect()
+            } else {
+                vec![1.0; slots.len()]
+            }
+        }
+    };
+    let sum: f64 = raw.iter().sum();
+    for (slot, w) in slots.iter_mut().zip(raw) {
+        slot.weight = if sum > 0.0 { w / sum } else { 0.0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm::{FillRule, IntrabarPath, SignalParams};
+    use core::types::{Price, Qty, TimestampMs};
+    use execution::sim::ExecutionModel;
+
+    fn candle(ts: i64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(close),
+            high: Price(close * 1.003),
+            low: Price(close * 0.997),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    fn series(scale: f64) -> (Vec<Candle>, Vec<Candle>) {
+        let mut closes: Vec<f64> = (0..20).map(|i| 100.0 + 2.0 * (i % 2) as f64).collect();
+        closes.extend([104.0, 106.0, 108.0, 110.0, 106.0]);
+        closes.extend((0..200).map(|i| 106.0 + 2.0 * (i % 2) as f64));
+        let htf: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| candle(i as i64 * 300_000, c * scale))
+            .collect();
+        let ltf = htf
+            .iter()
+            .flat_map(|h| (0..5).map(move |k| candle(h.ts.0 + k * 60_000, h.close.0)))
+            .collect();
+        (htf, ltf)
+    }
+
+    fn params() -> MmParams {
+        MmParams {
+            levels: 3,
+            step_bps: 10.0,
+            base_quote_per_order: 25.0,
+            max_size_mult: 2.0,
+            min_base_qty: 0.0001,
+            soft_min: 0.40,
+            soft_max: 0.60,
+            hard_min: 0.35,
+            hard_max: 0.65,
+            maker_fee_bps: 10.0,
+            defensive_step_mult: 1.5,
+            defensive_size_mult: 0.5,
+            signal: SignalParams::default(),
+        }
+    }
+
+    #[test]
+    fn shared_quote_accounts_for_every_symbol() {
+        let (htf_a, ltf_a) = series(1.0);
+        let (htf_b, ltf_b) = series(20.0);
+        let markets = [
+            PortfolioMarket {
+                symbol: "A".into(),
+                htf: &htf_a,
+                ltf: &ltf_a,
+            },
+            PortfolioMarket {
+                symbol: "B".into(),
+                htf: &htf_b,
+                ltf: &ltf_b,
+            },
+        ];
+        let cfg = MmRunConfig {
+            initial_quote: 2000.0,
+            initial_base: 0.0,
+            initial_base_ratio: None,
+            perp: None,
+            taker: ExecutionModel {
+                fee_bps: 10.0,
+                spread_bps: 8.0,
+                slippage_bps: 2.0,
+            },
+            force_close_at_end: true,
+            force_close_twap: None,
+            bootstrap_rebalance: true,
+            bootstrap_target_ratio: 0.5,
+            intrabar: IntrabarPath::Ohlc,
+            fill: FillRule::default(),
+            record: true,
+        };
+        let allocation = Allocation::parse("3,1", 2).unwrap();
+        let run = run_portfolio(&markets, 300_000, &params(), &allocation, 20, cfg).unwrap();
+        let r = run.report;
+
+        assert!(r.buy_fills > 0 && r.sell_fills > 0 && r.bootstrap_trades > 0);
+        assert_eq!(run.fill_rows.len(), r.buy_fills + r.sell_fills);
+        let traded: f64 = run.fill_rows.iter().map(|f| f.qty * f.price).sum();
+        assert!((r.perf.turnover - traded / 2000.0).abs() < 1e-9);
+        assert_eq!(
+            r.final_quote, r.final_equity,
+            "everything is closed at the end"
+        );
+        let pnl: f64 = run.symbol_rows.iter().map(|s| s.pnl).sum();
+        assert!((r.perf.pnl - pnl).abs() < 1e-6);
+        assert!(run.equity_rows.iter().all(|e| e.quote > -1e-9));
+        assert_eq!(run.symbol_rows[0].weight, 0.75);
+        // одинаковая форма цены — PnL символов движутся вместе
+        assert!(run.correlations[0].correlation > 0.9);
+
+        assert!(Allocation::parse("1", 2).is_err());
+        assert!(Allocation::parse("equal", 2).is_ok());
+    }
+
+    #[test]
+    fn ledger_lends_only_free_quote() {
+        let mut ledger = PortfolioLedger::new(1000.0, true);
+        assert_eq!(ledger.lend(1500.0), 1000.0);
+        assert_eq!(ledger.lend(-50.0), 0.0);
+
+        // символ купил на 400 из выданных 600
+        assert_eq!(ledger.settle(600.0, 200.0), -400.0);
+        assert_eq!(ledger.quote, 600.0);
+        assert_eq!(ledger.lend(700.0), 600.0);
+        ledger.mark(1, 400.0);
+        // позиция подешевела на 100
+        ledger.mark(2, 300.0);
+        assert_eq!(ledger.equity(300.0), 900.0);
+        assert_eq!(ledger.equity_rows.len(), 2);
+        assert!((ledger.equity_rows[1].drawdown_pct - 10.0).abs() < 1e-9);
+    }
+}
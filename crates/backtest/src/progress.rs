@@ -0,0 +1,155 @@
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::stats::{Performance, RankBy};
+
+/// Как часто печатать прогресс: worker сохраняет метрики не чаще раза в 2с
+const EMIT_INTERVAL: Duration = Duration::from_secs(2);
+
+const BAR_TEMPLATE: &str = "{prefix} [{bar:30}] {pos}/{len} elapsed {elapsed} eta {eta} {wide_msg}";
+
+/// Прогресс долгого прогона.
+/// Интерактивно (stderr — терминал, без `--quiet`) — progress bar с ETA и лучшим конфигом;
+/// иначе — строки stdout протокола worker'а:
+/// `progress: stage=.. unit=.. done=.. total=.. percent=.. elapsed_s=.. eta_s=..`
+#[derive(Debug)]
+pub struct Progress {
+    stage: &'static str,
+    unit: &'static str,
+    done: usize,
+    total: usize,
+    started: Instant,
+    last_emit: Option<Instant>,
+    bar: Option<ProgressBar>,
+    best: Option<Performance>,
+}
+
+impl Progress {
+    /// В режиме протокола сразу печатает нулевую строку,
+    /// чтобы прогон был виден до первого результата
+    pub fn new(stage: &'static str, unit: &'static str, total: usize, quiet: bool) -> Self {
+        let bar = if quiet {
+            None
+        } else {
+            let bar = ProgressBar::new(total as u64);
+            if let Ok(style) = ProgressStyle::with_template(BAR_TEMPLATE) {
+                bar.set_style(style.progress_chars("=> "));
+            }
+            bar.set_prefix(stage);
+            (!bar.is_hidden()).then_some(bar)
+        };
+        let mut p = Self {
+            stage,
+            unit,
+            done: 0,
+            total,
+            started: Instant::now(),
+            last_emit: None,
+            bar,
+            best: None,
+        };
+        p.emit();
+        p
+    }
+
+    pub fn tick(&mut self) {
+        self.set(self.done + 1);
+    }
+
+    /// Абсолютное число готовых единиц; печать — не чаще [`EMIT_INTERVAL`] и на последней
+    pub fn set(&mut self, done: usize) {
+        self.done = done.min(self.total);
+        if let Some(bar) = &self.bar {
+            bar.set_position(self.done as u64);
+            if self.done == self.total {
+                bar.finish_and_clear();
+            }
+            return;
+        }
+        let due = self.last_emit.is_none_or(|t| t.elapsed() >= EMIT_INTERVAL);
+        if due || self.done == self.total {
+            self.emit();
+        }
+    }
+
+    /// Кандидат в лучший конфиг для progress bar; `label` строится, только если он лучше
+    pub fn offer_best(&mut self, perf: &Performance, by: RankBy, label: impl FnOnce() -> String) {
+        let Some(bar) = &self.bar else {
+            return;
+        };
+        if self
+            .best
+            .is_some_and(|best| perf.rank_cmp(&best, by).is_ge())
+        {
+            return;
+        }
+        self.best = Some(*perf);
+        bar.set_message(format!(
+            "best {}={:.3} roi={:.2}% dd={:.2}% {}",
+            format!("{:?}", by).to_lowercase(),
+            perf.metric(by),
+            perf.roi_pct,
+            perf.max_drawdown_pct,
+            label()
+        ));
+    }
+
+    /// Последнее состояние, если цель не была достигнута (например, ранняя остановка поиска)
+    pub fn finish(&mut self) {
+        if self.done < self.total {
+            self.total = self.done;
+            if let Some(bar) = &self.bar {
+                bar.set_length(self.total as u64);
+            }
+            self.set(self.done);
+        }
+    }
+
+    fn emit(&mut self) {
+        if self.bar.is_none() {
+            println!("{}", self.line(self.started.elapsed()));
+        }
+        self.last_emit = Some(Instant::now());
+    }
+
+    fn line(&self, elapsed: Duration) -> String {
+        let elapsed_s = elapsed.as_secs_f64();
+        let percent = if self.total == 0 {
+            100.0
+        } else {
+            self.done as f64 / self.total as f64 * 100.0
+        };
+        let eta = if self.done == 0 {
+            "na".to_string()
+        } else {
+            let per_unit = elapsed_s / self.done as f64;
+            format!("{:.1}", per_unit * (self.total - self.done) as f64)
+        };
+        format!(
+            "progress: stage={} unit={} done={} total={} percent={:.2} elapsed_s={:.1} eta_s={}",
+            self.stage, self.unit, self.done, self.total, percent, elapsed_s, eta
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_percent_and_eta() {
+        let mut p = Progress::new("sweep", "configs", 8, true);
+        assert!(
+            p.line(Duration::ZERO)
+                .ends_with("done=0 total=8 percent=0.00 elapsed_s=0.0 eta_s=na")
+        );
+        p.done = 2;
+        assert_eq!(
+            p.line(Duration::from_secs(10)),
+            "progress: stage=sweep unit=configs done=2 total=8 percent=25.00 elapsed_s=10.0 eta_s=30.0"
+        );
+        p.set(20);
+        assert_eq!(p.done, 8);
+    }
+}
@@ -0,0 +1,404 @@
+use chrono::{TimeZone, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use structure::candle::Candle;
+
+/// Непрерывный кусок пропущенных свечей: первая и последняя отсутствующие ts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CandleGap {
+    pub from_ts: i64,
+    pub to_ts: i64,
+    pub missing: usize,
+}
+
+/// Проверка серии свечей на диапазоне: пропуски, дубли, порядок и цены
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QualityReport {
+    pub candles: usize,
+    /// Слотов интервала в `[start, end]`
+    pub expected: usize,
+    pub missing: usize,
+    pub gaps: Vec<CandleGap>,
+    pub duplicates: usize,
+    pub unsorted: usize,
+    /// Нулевые, отрицательные или не конечные OHLC
+    pub zero_prices: usize,
+    /// low/high не охватывают open/close или отрицательный объём
+    pub invalid_ohlc: usize,
+}
+
+impl QualityReport {
+    pub fn missing_pct(&self) -> f64 {
+        if self.expected == 0 {
+            0.0
+        } else {
+            100.0 * self.missing as f64 / self.expected as f64
+        }
+    }
+
+    /// Ошибки, которые не объясняются пропусками биржи
+    pub fn has_errors(&self) -> bool {
+        self.duplicates + self.unsorted + self.zero_prices + self.invalid_ohlc > 0
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "candles={} expected={} missing={} missing_pct={:.3}% gaps={} duplicates={} unsorted={} zero_prices={} invalid_ohlc={}",
+            self.candles,
+            self.expected,
+            self.missing,
+            self.missing_pct(),
+            self.gaps.len(),
+            self.duplicates,
+            self.unsorted,
+            self.zero_prices,
+            self.invalid_ohlc
+        )
+    }
+}
+
+/// Что делать со свечами, не прошедшими проверку перед backtest'ом
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QualityMode {
+    /// Падать с отчётом
+    #[default]
+    Strict,
+    /// Перекачать серию и проверить ещё раз
+    Repair,
+    Off,
+}
+
+/// Проверка серии перед backtest'ом: ошибки или пропуски сверх `max_missing_pct` не пропускаются
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct QualityGate {
+    pub mode: QualityMode,
+    pub max_missing_pct: f64,
+}
+
+impl QualityGate {
+    pub fn new(mode: QualityMode, max_missing_pct: f64) -> anyhow::Result<Self> {
+        if !(0.0..=100.0).contains(&max_missing_pct) {
+            anyhow::bail!("max_missing_pct must be in [0, 100]");
+        }
+        Ok(Self {
+            mode,
+            max_missing_pct,
+        })
+    }
+
+    pub fn passes(&self, r: &QualityReport) -> bool {
+        !r.has_errors() && r.missing_pct() <= self.max_missing_pct
+    }
+}
+
+impl Default for QualityGate {
+    fn default() -> Self {
+        Self {
+            mode: QualityMode::Strict,
+            max_missing_pct: 0.5,
+        }
+    }
+}
+
+fn fmt_ts(ts: i64) -> String {
+    Utc.timestamp_millis_opt(ts)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Подробный отчёт для ошибки: сводка и первые `max_gaps` пропусков в UTC
+pub fn describe(r: &QualityReport, max_gaps: usize) -> String {
+    let mut out = r.summary();
+    for g in r.gaps.iter().take(max_gaps) {
+        out.push_str(&format!(
+            "\n  gap {} .. {} ({} candles)",
+            fmt_ts(g.from_ts),
+            fmt_ts(g.to_ts),
+            g.missing
+        ));
+    }
+    if r.gaps.len() > max_gaps {
+        out.push_str(&format!("\n  ... {} more gaps", r.gaps.len() - max_gaps));
+    }
+    out
+}
+
+fn bad_price(v: f64) -> bool {
+    !v.is_finite() || v <= 0.0
+}
+
+/// Свечи ожидаются на ts, кратных `interval_ms`, во всём `[start, end]`
+pub fn check_candles(
+    candles: &[Candle],
+    interval_ms: i64,
+    (start_ms, end_ms): (i64, i64),
+) -> QualityReport {
+    let mut first_slot = start_ms.div_euclid(interval_ms) * interval_ms;
+    if first_slot < start_ms {
+        first_slot += interval_ms;
+    }
+    let last_slot = end_ms.div_euclid(interval_ms) * interval_ms;
+    let mut r = QualityReport {
+        candles: candles.len(),
+        expected: if last_slot >= first_slot {
+            ((last_slot - first_slot) / interval_ms + 1) as usize
+        } else {
+            0
+        },
+        ..QualityReport::default()
+    };
+
+    let push_gap = |from_ts: i64, to_ts: i64, r: &mut QualityReport| {
+        if to_ts >= from_ts {
+            let missing = ((to_ts - from_ts) / interval_ms + 1) as usize;
+            r.missing += missing;
+            r.gaps.push(CandleGap {
+                from_ts,
+                to_ts,
+                missing,
+            });
+        }
+    };
+
+    // следующий ожидаемый слот
+    let mut next = first_slot;
+    let mut prev: Option<i64> = None;
+    for c in candles {
+        let (o, h, l, cl) = (c.open.0, c.high.0, c.low.0, c.close.0);
+        if [o, h, l, cl].into_iter().any(bad_price) {
+            r.zero_prices += 1;
+        } else if l > o.min(cl) || h < o.max(cl) || !c.volume.0.is_finite() || c.volume.0 < 0.0 {
+            r.invalid_ohlc += 1;
+        }
+
+        let ts = c.ts.0;
+        match prev {
+            Some(p) if ts == p => {
+                r.duplicates += 1;
+                continue;
+            }
+            Some(p) if ts < p => {
+                r.unsorted += 1;
+                continue;
+            }
+            _ => {}
+        }
+        prev = Some(ts);
+        if ts < first_slot || ts > last_slot {
+            continue;
+        }
+        push_gap(next, ts - interval_ms, &mut r);
+        next = ts + interval_ms;
+    }
+    push_gap(next, last_slot, &mut r);
+    r
+}
+
+/// HTF окно `[ts, ts + htf)`, в котором LTF свечей меньше, чем слотов
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LtfWindow {
+    pub htf_ts: i64,
+    pub expected: usize,
+    pub got: usize,
+}
+
+/// Покрытие HTF окон LTF свечами: в непокрытом окне MTF сетка не исполняется
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AlignmentReport {
+    pub windows: usize,
+    /// Окна с неполным покрытием, по возрастанию ts
+    pub uncovered: Vec<LtfWindow>,
+}
+
+impl AlignmentReport {
+    /// Окна совсем без LTF свечей
+    pub fn empty_windows(&self) -> usize {
+        self.uncovered.iter().filter(|w| w.got == 0).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "htf_windows={} partial_windows={} empty_windows={}",
+            self.windows,
+            self.uncovered.len() - self.empty_windows(),
+            self.empty_windows()
+        )
+    }
+
+    /// Сводка и первые `max_windows` непокрытых окон в UTC
+    pub fn describe(&self, max_windows: usize) -> String {
+        let mut out = self.summary();
+        for w in self.uncovered.iter().take(max_windows) {
+            out.push_str(&format!(
+                "\n  window {}: {} of {} ltf candles",
+                fmt_ts(w.htf_ts),
+                w.got,
+                w.expected
+            ));
+        }
+        if self.uncovered.len() > max_windows {
+            out.push_str(&format!(
+                "\n  ... {} more windows",
+                self.uncovered.len() - max_windows
+            ));
+        }
+        out
+    }
+}
+
+/// LTF должен быть мельче HTF и делить его нацело, иначе окна не совпадают со свечами
+pub fn check_intervals(htf_ms: i64, ltf_ms: i64) -> anyhow::Result<()> {
+    if ltf_ms <= 0 || ltf_ms >= htf_ms || htf_ms % ltf_ms != 0 {
+        anyhow::bail!(
+            "ltf interval ({}m) must be smaller than and divide the htf interval ({}m)",
+            ltf_ms / 60_000,
+            htf_ms / 60_000
+        );
+    }
+    Ok(())
+}
+
+/// Сколько LTF свечей приходится на каждое HTF окно; окна обрезаются по `end`
+/// (включительно), HTF свечи вне `[start, end]` не проверяются
+pub fn check_alignment(
+    htf: &[Candle],
+    ltf: &[Candle],
+    (htf_ms, ltf_ms): (i64, i64),
+    (start_ms, end_ms): (i64, i64),
+) -> AlignmentReport {
+    let mut r = AlignmentReport::default();
+    let mut idx = 0;
+    for h in htf
+        .iter()
+        .filter(|h| h.ts.0 >= start_ms && h.ts.0 <= end_ms)
+    {
+        let from = h.ts.0;
+        let to = (from + htf_ms).min(end_ms + 1);
+        while idx < ltf.len() && ltf[idx].ts.0 < from {
+            idx += 1;
+        }
+        let mut got = 0;
+        while idx < ltf.len() && ltf[idx].ts.0 < to {
+            got += 1;
+            idx += 1;
+        }
+        let expected = ((to - from + ltf_ms - 1) / ltf_ms) as usize;
+        r.windows += 1;
+        if got < expected {
+            r.uncovered.push(LtfWindow {
+                htf_ts: from,
+                expected,
+                got,
+            });
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Price, Qty, TimestampMs};
+
+    fn candle(ts: i64, price: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(price),
+            high: Price(price + 1.0),
+            low: Price(price - 1.0),
+            close: Price(price),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn reports_gaps_duplicates_and_bad_prices() {
+        let m = 60_000;
+        let clean: Vec<Candle> = (0..10).map(|i| candle(i * m, 100.0)).collect();
+        let r = check_candles(&clean, m, (0, 10 * m - 1));
+        assert_eq!((r.expected, r.missing), (10, 0));
+        assert!(!r.has_errors());
+
+        let mut holed = clean.clone();
+        holed.remove(9);
+        holed.drain(3..5);
+        holed.insert(1, candle(0, 100.0));
+        holed[2].low = Price(0.0);
+        holed[3].high = Price(50.0);
+        let r = check_candles(&holed, m, (0, 10 * m - 1));
+        assert_eq!(
+            r.gaps,
+            vec![
+                CandleGap {
+                    from_ts: 3 * m,
+                    to_ts: 4 * m,
+                    missing: 2
+                },
+                CandleGap {
+                    from_ts: 9 * m,
+                    to_ts: 9 * m,
+                    missing: 1
+                },
+            ]
+        );
+        assert_eq!(r.missing, 3);
+        assert_eq!((r.duplicates, r.zero_prices, r.invalid_ohlc), (1, 1, 1));
+        assert!(r.has_errors());
+        assert!(r.summary().starts_with("candles=8 expected=10 missing=3"));
+        assert!(describe(&r, 1).contains("gap 1970-01-01 00:03 .. 1970-01-01 00:04 (2 candles)"));
+        assert!(describe(&r, 1).ends_with("... 1 more gaps"));
+
+        let gate = QualityGate::default();
+        assert!(gate.passes(&check_candles(&clean, m, (0, 10 * m - 1))));
+        assert!(!gate.passes(&r));
+        assert!(QualityGate::new(QualityMode::Off, 120.0).is_err());
+    }
+
+    #[test]
+    fn reports_htf_windows_not_covered_by_ltf() {
+        let (m, h) = (60_000, 300_000);
+        assert!(check_intervals(h, m).is_ok());
+        assert!(check_intervals(h, 2 * m).is_err());
+        assert!(check_intervals(h, h).is_err());
+
+        let htf: Vec<Candle> = (0..4).map(|i| candle(i * h, 100.0)).collect();
+        let mut ltf: Vec<Candle> = (0..20).map(|i| candle(i * m, 100.0)).collect();
+        let r = check_alignment(&htf, &ltf, (h, m), (0, 20 * m - 1));
+        assert_eq!((r.windows, r.uncovered.len()), (4, 0));
+
+        ltf.drain(5..10);
+        ltf.remove(12);
+        let r = check_alignment(&htf, &ltf, (h, m), (0, 20 * m - 1));
+        assert_eq!(
+            r.uncovered,
+            vec![
+                LtfWindow {
+                    htf_ts: h,
+                    expected: 5,
+                    got: 0
+                },
+                LtfWindow {
+                    htf_ts: 3 * h,
+                    expected: 5,
+                    got: 4
+                },
+            ]
+        );
+        assert_eq!(
+            r.summary(),
+            "htf_windows=4 partial_windows=1 empty_windows=1"
+        );
+        assert!(
+            r.describe(1)
+                .contains("window 1970-01-01 00:05: 0 of 5 ltf candles")
+        );
+
+        // последнее окно обрезается концом диапазона: 2 свечи из 2
+        let r = check_alignment(&htf, &ltf[..8], (h, m), (0, 11 * m));
+        assert_eq!((r.windows, r.uncovered.len()), (3, 1));
+        assert_eq!(r.uncovered[0].htf_ts, h);
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::breakdown::PeriodRow;
+use crate::html::{HtmlData, HtmlSections, render};
+use crate::stats::{CostBreakdown, Performance};
+
+/// Метрики конфига sweep'а на одном символе
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolRow {
+    pub rank: usize,
+    pub symbol: String,
+    pub closed_trades: usize,
+    pub roi_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub sharpe: f64,
+    pub profit_factor: f64,
+}
+
+impl SymbolRow {
+    pub fn new(rank: usize, symbol: &str, perf: &Performance) -> Self {
+        Self {
+            rank,
+            symbol: symbol.to_string(),
+            closed_trades: perf.closed_trades,
+            roi_pct: perf.roi_pct,
+            max_drawdown_pct: perf.max_drawdown_pct,
+            sharpe: perf.sharpe,
+            profit_factor: perf.profit_factor,
+        }
+    }
+}
+
+/// Строка equity-кривой backtest'а (MM и trend)
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityRow {
+    pub ts: i64,
+    pub close: f64,
+    /// Режим MM или состояние trend позиции
+    pub mode: String,
+    pub quote: f64,
+    pub base: f64,
+    pub cost_basis_quote: f64,
+    pub equity: f64,
+    pub drawdown_pct: f64,
+}
+
+/// Исполнение в MM backtest'е (лимитка сетки, bootstrap или force close)
+#[derive(Debug, Clone, Serialize)]
+pub struct FillRow {
+    pub ts: i64,
+    pub side: String,
+    pub mode: String,
+    pub qty: f64,
+    pub price: f64,
+    pub fee_quote: f64,
+    pub quote_delta: f64,
+    pub realized_pnl: Option<f64>,
+}
+
+/// Исполнение в портфельном MM backtest'е
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioFillRow {
+    pub ts: i64,
+    pub symbol: String,
+    pub side: String,
+    pub mode: String,
+    pub qty: f64,
+    pub price: f64,
+    pub fee_quote: f64,
+    pub quote_delta: f64,
+    pub realized_pnl: Option<f64>,
+}
+
+impl PortfolioFillRow {
+    pub fn new(symbol: &str, f: FillRow) -> Self {
+        Self {
+            ts: f.ts,
+            symbol: symbol.to_string(),
+            side: f.side,
+            mode: f.mode,
+            qty: f.qty,
+            price: f.price,
+            fee_quote: f.fee_quote,
+            quote_delta: f.quote_delta,
+            realized_pnl: f.realized_pnl,
+        }
+    }
+}
+
+/// Точка underwater-кривой: глубина просадки и сколько equity уже ниже пика
+#[derive(Debug, Clone, Serialize)]
+pub struct UnderwaterRow {
+    pub ts: i64,
+    pub drawdown_pct: f64,
+    pub underwater_h: f64,
+}
+
+/// Underwater-кривая по точкам `(ts, drawdown_pct)` equity-кривой
+pub fn underwater_rows(points: impl IntoIterator<Item = (i64, f64)>) -> Vec<UnderwaterRow> {
+    let mut peak_ts = None;
+    points
+        .into_iter()
+        .map(|(ts, drawdown_pct)| {
+            if drawdown_pct <= 0.0 || peak_ts.is_none() {
+                peak_ts = Some(ts);
+            }
+            UnderwaterRow {
+                ts,
+                drawdown_pct,
+                underwater_h: (ts - peak_ts.unwrap_or(ts)) as f64 / 3_600_000.0,
+            }
+        })
+        .collect()
+}
+
+/// Вход/выход trend backtest'а по рынку
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendTradeRow {
+    pub ts: i64,
+    pub side: String,
+    pub reason: String,
+    pub qty: f64,
+    pub mid_price: f64,
+    pub fill_price: f64,
+    pub quote_delta: f64,
+    pub trade_pnl: Option<f64>,
+}
+
+/// Закрытая сделка trend backtest'а: вход и выход одной позиции.
+/// MAE/MFE — худшее и лучшее движение цены (low/high свечей) от цены входа, %
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendRoundTripRow {
+    pub entry_ts: i64,
+    pub entry_price: f64,
+    pub exit_ts: i64,
+    pub exit_price: f64,
+    pub exit_reason: String,
+    pub qty: f64,
+    pub pnl: f64,
+    pub bars_held: usize,
+    pub mae_pct: f64,
+    pub mfe_pct: f64,
+}
+
+/// Машиночитаемый отчёт прогона (`--report-out`): worker берёт метрики
+/// отсюда, а не из stdout
+#[derive(Debug, Serialize)]
+pub struct JsonReport<C, M> {
+    pub kind: &'static str,
+    pub config: C,
+    pub metrics: M,
+    pub costs: Option<CostBreakdown>,
+    /// PnL/ROI/сделки по месяцам
+    pub monthly: Option<Vec<PeriodRow>>,
+    /// Зерно ГСЧ прогона: с ним стохастические части воспроизводятся
+    pub seed: Option<u64>,
+    /// kind -> путь, как в строке `artifacts:`
+    pub artifacts: BTreeMap<String, String>,
+    /// Ряды для HTML отчёта (`with_html`)
+    #[serde(skip)]
+    pub html: Option<HtmlData>,
+}
+
+impl<C: Serialize, M: Serialize> JsonReport<C, M> {
+    pub fn new(kind: &'static str, config: C, metrics: M) -> Self {
+        Self {
+            kind,
+            config,
+            metrics,
+            costs: None,
+            monthly: None,
+            seed: None,
+            artifacts: BTreeMap::new(),
+            html: None,
+        }
+    }
+
+    pub fn with_costs(mut self, costs: CostBreakdown) -> Self {
+        self.costs = Some(costs);
+        self
+    }
+
+    pub fn with_monthly(mut self, rows: Vec<PeriodRow>) -> Self {
+        self.monthly = Some(rows);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Вместе с отчётом пишется самодостаточный HTML: метрики, графики equity и
+    /// просадки, таблица сделок и конфиг
+    pub fn with_html(mut self, data: HtmlData) -> Self {
+        self.html = Some(data);
+        self
+    }
+
+    pub fn artifact(mut self, kind: &str, path: &str) -> Self {
+        self.artifacts.insert(kind.to_string(), path.to_string());
+        self
+    }
+
+    /// Путь рядом с отчётом (`<report><suffix>`) или `data/<kind><suffix>`
+    fn sibling(&self, report_out: Option<&str>, suffix: &str) -> String {
+        match report_out {
+            Some(path) => {
+                let p = Path::new(path);
+                let stem = p
+                    .file_stem()
+                    .map(|s| s.to_string_lossy())
+                    .unwrap_or_default();
+                p.with_file_name(format!("{}{}", stem, suffix))
+                    .to_string_lossy()
+                    .into_owned()
+            }
+            None => format!("data/{}{}", self.kind, suffix),
+        }
+    }
+
+    /// Пишет отчёт (если задан путь) и эффективный конфиг, печатает строку `artifacts:`.
+    /// Конфиг — рядом с отчётом (`<report>_config.json`) или `data/<kind>_config.json`;
+    /// прогон повторяется с ним через `--config`. HTML — `<report>.html`
+    /// или `data/<kind>_report.html`
+    pub fn finish(mut self, report_out: Option<&str>) -> Result<()> {
+        let config_out = self.sibling(report_out, "_config.json");
+        write_json(&config_out, &self.config)?;
+        self.artifacts.insert("config_json".to_string(), config_out);
+        if let Some(data) = self.html.take() {
+            let suffix = if report_out.is_some() {
+                ".html"
+            } else {
+                "_report.html"
+            };
+            let html_out = self.sibling(report_out, suffix);
+            let html = render(
+                &HtmlSections {
+                    kind: self.kind,
+                    config: &serde_json::to_value(&self.config)?,
+                    metrics: &serde_json::to_value(&self.metrics)?,
+                    costs: &serde_json::to_value(self.costs)?,
+                    monthly: &serde_json::to_value(&self.monthly)?,
+                },
+                &data,
+            );
+            if let Some(parent) = Path::new(&html_out).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&html_out, html)?;
+            self.artifacts.insert("report_html".to_string(), html_out);
+        }
+        if let Some(path) = report_out {
+            write_json(path, &self)?;
+            self.artifacts
+                .insert("report_json".to_string(), path.to_string());
+        }
+        if !self.artifacts.is_empty() {
+            let items: Vec<String> = self
+                .artifacts
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect();
+            println!("artifacts: {}", items.join(" "));
+        }
+        Ok(())
+    }
+}
+
+/// Пишет значение в JSON, создавая каталог
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(path)?;
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), value)?;
+    Ok(())
+}
+
+/// Пишет строки в CSV, создавая каталог
+/// CSV, строки которого дописываются по ходу прогона (без буфера всех строк в памяти)
+pub struct CsvStream {
+    wtr: csv::Writer<std::fs::File>,
+}
+
+impl CsvStream {
+    pub fn create(path: &str) -> Result<Self> {
+        if crate::table::is_parquet_path(path) {
+            anyhow::bail!("streamed results are CSV only: {}", path);
+        }
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let wtr =
+            csv::Writer::from_path(path).with_context(|| format!("create {} failed", path))?;
+        Ok(Self { wtr })
+    }
+
+    pub fn write<T: Serialize>(&mut self, row: &T) -> Result<()> {
+        self.wtr.serialize(row)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.wtr.flush()?;
+        Ok(())
+    }
+}
+
+pub fn write_csv<T: Serialize>(path: &str, rows: impl IntoIterator<Item = T>) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut wtr = csv::Writer::from_path(path)?;
+    for r in rows {
+        wtr.serialize(r)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
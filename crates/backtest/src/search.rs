@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+/// Как sweep перебирает конфиги
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// Декартово произведение списков
+    #[default]
+    Grid,
+    /// Равномерные случайные точки
+    Random,
+    /// Tree-structured Parzen Estimator: после разогрева случайными точками
+    /// семплирует там, где хорошие конфиги плотнее плохих
+    Tpe,
+}
+
+/// Одно измерение поиска: список `"3,5,7"` или диапазон `"8..16"`
+#[derive(Debug, Clone)]
+pub enum Dim {
+    Values(Vec<f64>),
+    Range { lo: f64, hi: f64, integer: bool },
+}
+
+impl Dim {
+    /// `integer` — значения округляются (levels, pivot_k и т.п.)
+    pub fn parse(s: &str, name: &str, integer: bool) -> Result<Self> {
+        let bad = |v: &str| anyhow::anyhow!("bad value in {}: '{}'", name, v);
+        if let Some((lo, hi)) = s.split_once("..") {
+            let lo: f64 = lo.trim().parse().map_err(|_| bad(s))?;
+            let hi: f64 = hi.trim().parse().map_err(|_| bad(s))?;
+            if lo.is_nan() || hi.is_nan() || lo > hi {
+                anyhow::bail!("bad range in {}: '{}' (expected lo..hi)", name, s);
+            }
+            return Ok(Self::Range { lo, hi, integer });
+        }
+        let mut values = Vec::new();
+        for raw in s.split(',') {
+            let v = raw.trim();
+            if v.is_empty() {
+                continue;
+            }
+            values.push(v.parse::<f64>().map_err(|_| bad(v))?);
+        }
+        if values.is_empty() {
+            anyhow::bail!("{} cannot be empty", name);
+        }
+        Ok(Self::Values(values))
+    }
+
+    /// Значение в точке `u` из [0, 1]
+    pub fn at(&self, u: f64) -> f64 {
+        let u = u.clamp(0.0, 1.0);
+        match self {
+            Self::Values(v) => v[((u * v.len() as f64) as usize).min(v.len() - 1)],
+            Self::Range { lo, hi, integer } if *integer => {
+                (lo + (u * (hi - lo + 1.0)).floor()).min(*hi)
+            }
+            Self::Range { lo, hi, .. } => lo + u * (hi - lo),
+        }
+    }
+
+    /// Значение на шаг вверх (`up`) или вниз от `value`: соседний элемент списка,
+    /// для диапазона — 1/10 ширины (целые — 1). `None` — шагать некуда.
+    pub fn step(&self, value: f64, up: bool) -> Option<f64> {
+        match self {
+            Self::Values(v) => {
+                let idx = v
+                    .iter()
+                    .enumerate()
+                    .min_by(|a, b| (a.1 - value).abs().total_cmp(&(b.1 - value).abs()))?
+                    .0;
+                let next = if up {
+                    idx.checked_add(1)
+                } else {
+                    idx.checked_sub(1)
+                }?;
+                v.get(next).copied()
+            }
+            Self::Range { lo, hi, integer } => {
+                let step = if *integer { 1.0 } else { (hi - lo) / 10.0 };
+                let next = if up { value + step } else { value - step }.clamp(*lo, *hi);
+                (step > 0.0 && next != value).then_some(next)
+            }
+        }
+    }
+}
+
+/// Сколько случайных точек до того, как TPE начнёт строить модель
+const TPE_STARTUP: usize = 20;
+/// Доля лучших наблюдений, задающая "хорошую" плотность
+const TPE_GAMMA: f64 = 0.25;
+const TPE_CANDIDATES: usize = 24;
+
+/// Random/TPE поиск в единичном кубе; измерения отображаются через [`Dim::at`]
+pub struct Search {
+    mode: SearchMode,
+    dims: Vec<Dim>,
+    rng: StdRng,
+    history: Vec<(Vec<f64>, f64)>,
+}
+
+impl Search {
+    pub fn new(mode: SearchMode, dims: Vec<Dim>, seed: u64) -> Self {
+        Self {
+            mode,
+            dims,
+            rng: StdRng::seed_from_u64(seed),
+            history: Vec::new(),
+        }
+    }
+
+    /// Следующая точка: (координаты в [0,1]^d, значения параметров)
+    pub fn suggest(&mut self) -> (Vec<f64>, Vec<f64>) {
+        let u = match self.mode {
+            SearchMode::Tpe if self.history.len() >= TPE_STARTUP => self.tpe_point(),
+            _ => self.random_point(),
+        };
+        let values = self.dims.iter().zip(&u).map(|(d, &x)| d.at(x)).collect();
+        (u, values)
+    }
+
+    /// Результат точки; больше — лучше (невалидным конфигам — `f64::NEG_INFINITY`)
+    pub fn observe(&mut self, u: Vec<f64>, score: f64) {
+        self.history.push((u, score));
+    }
+
+    /// Random/TPE sweep до `samples` уникальных валидных конфигов.
+    /// `build` — конфиг из значений измерений (`None` — невалидный),
+    /// `eval` — прогон и его оценка (больше — лучше).
+    pub fn run<C: Debug, R>(
+        mut self,
+        samples: usize,
+        mut build: impl FnMut(&[f64]) -> Option<C>,
+        mut eval: impl FnMut(&C) -> Result<(R, f64)>,
+    ) -> Result<Vec<(C, R)>> {
+        let mut out = Vec::with_capacity(samples);
+        let mut seen: HashMap<String, f64> = HashMap::new();
+        // мелкое дискретное пространство может кончиться раньше `samples`
+        let max_attempts = samples.saturating_mul(20).max(100);
+        for _ in 0..max_attempts {
+            if out.len() >= samples {
+                break;
+            }
+            let (u, values) = self.suggest();
+            let Some(cfg) = build(&values) else {
+                self.observe(u, f64::NEG_INFINITY);
+                continue;
+            };
+            let key = format!("{:?}", cfg);
+            if let Some(&score) = seen.get(&key) {
+                self.observe(u, score);
+                continue;
+            }
+            let (report, score) = eval(&cfg)?;
+            let score = if score.is_nan() {
+                f64::NEG_INFINITY
+            } else {
+                score
+            };
+            seen.insert(key, score);
+            self.observe(u, score);
+            out.push((cfg, report));
+        }
+        Ok(out)
+    }
+
+    fn random_point(&mut self) -> Vec<f64> {
+        (0..self.dims.len())
+            .map(|_| self.rng.r#gen::<f64>())
+            .collect()
+    }
+
+    /// Кандидаты — шум вокруг хороших точек; берётся максимум l(x) / g(x)
+    fn tpe_point(&mut self) -> Vec<f64> {
+        let mut sorted = self.history.clone();
+        sorted.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let n_good = ((sorted.len() as f64 * TPE_GAMMA).ceil() as usize).max(1);
+        let (good, bad) = sorted.split_at(n_good);
+        let good: Vec<&[f64]> = good.iter().map(|(u, _)| u.as_slice()).collect();
+        let bad: Vec<&[f64]> = bad.iter().map(|(u, _)| u.as_slice()).collect();
+        if bad.is_empty() {
+            return self.random_point();
+        }
+        let bw = (0.3 * (good.len() as f64).powf(-0.2)).max(0.02);
+
+        let mut best: Option<(f64, Vec<f64>)> = None;
+        for _ in 0..TPE_CANDIDATES {
+            let center = good[self.rng.gen_range(0..good.len())];
+            let cand: Vec<f64> = center
+                .iter()
+                .map(|&x| (x + bw * self.normal()).clamp(0.0, 1.0))
+                .collect();
+            let score = log_density(&cand, &good, bw) - log_density(&cand, &bad, bw);
+            if best.as_ref().is_none_or(|(s, _)| score > *s) {
+                best = Some((score, cand));
+            }
+        }
+        best.map(|(_, u)| u).unwrap_or_else(|| self.random_point())
+    }
+
+    fn normal(&mut self) -> f64 {
+        normal(&mut self.rng)
+    }
+}
+
+/// Стандартная нормальная величина (Box-Muller)
+pub(crate) fn normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = rng.r#gen::<f64>().max(f64::MIN_POSITIVE);
+    let u2: f64 = rng.r#gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+/// log средней гауссовой плотности (без нормировки — она сокращается в l/g)
+fn log_density(x: &[f64], points: &[&[f64]], bw: f64) -> f64 {
+    let sum: f64 = points
+        .iter()
+        .map(|p| {
+            let d2: f64 = p.iter().zip(x).map(|(a, b)| (a - b) * (a - b)).sum();
+            (-0.5 * d2 / (bw * bw)).exp()
+        })
+        .sum();
+    (sum / points.len() as f64 + 1e-300).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dims_map_unit_interval() {
+        let v = Dim::parse("3,5,7", "levels", true).unwrap();
+        assert_eq!(v.at(0.0), 3.0);
+        assert_eq!(v.at(0.5), 5.0);
+        assert_eq!(v.at(1.0), 7.0);
+
+        let r = Dim::parse("2..4", "pivot_k", true).unwrap();
+        assert_eq!(r.at(0.0), 2.0);
+        assert_eq!(r.at(0.5), 3.0);
+        assert_eq!(r.at(1.0), 4.0);
+
+        let f = Dim::parse("8..16", "step_bps", false).unwrap();
+        assert_eq!(f.at(0.25), 10.0);
+        assert_eq!(v.step(5.0, true), Some(7.0));
+        assert_eq!(v.step(3.0, false), None);
+        assert_eq!(r.step(4.0, true), None);
+        assert_eq!(r.step(4.0, false), Some(3.0));
+        assert!((f.step(10.0, true).unwrap() - 10.8).abs() < 1e-12);
+        assert_eq!(f.step(16.0, true), None);
+        assert!(Dim::parse("5..1", "x", false).is_err());
+    }
+
+    #[test]
+    fn tpe_beats_random_on_a_peak() {
+        let best_of = |mode| {
+            let dims = vec![
+                Dim::parse("0..1", "x", false).unwrap(),
+                Dim::parse("0..1", "y", false).unwrap(),
+            ];
+            let mut s = Search::new(mode, dims, 7);
+            let mut best = f64::NEG_INFINITY;
+            for _ in 0..80 {
+                let (u, p) = s.suggest();
+                let score = -((p[0] - 0.8).powi(2) + (p[1] - 0.3).powi(2));
+                best = best.max(score);
+                s.observe(u, score);
+            }
+            best
+        };
+        assert!(best_of(SearchMode::Tpe) > best_of(SearchMode::Random));
+    }
+
+    #[test]
+    fn run_skips_invalid_and_duplicate_configs() {
+        let dims = vec![Dim::parse("1,2,3", "a", true).unwrap()];
+        let search = Search::new(SearchMode::Random, dims, 1);
+        let out = search
+            .run(
+                10,
+                |p| (p[0] != 2.0).then_some(p[0] as usize),
+                |&a| Ok((a * 10, a as f64)),
+            )
+            .unwrap();
+        let mut got: Vec<usize> = out.iter().map(|(a, _)| *a).collect();
+        got.sort();
+        assert_eq!(got, vec![1, 3]);
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+use crate::stats::{CostBreakdown, Performance};
+
+/// Прогон фиксированного конфига при одной модели издержек
+#[derive(Debug, Clone, Serialize)]
+pub struct SensitivityRow {
+    pub maker_fee_bps: f64,
+    pub taker_fee_bps: f64,
+    pub spread_bps: f64,
+    pub slippage_bps: f64,
+    pub closed_trades: usize,
+    pub pnl: f64,
+    pub roi_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub sharpe: f64,
+    pub profit_factor: f64,
+    pub total_costs: f64,
+    pub profitable: bool,
+}
+
+impl SensitivityRow {
+    pub fn new(
+        (maker_fee_bps, taker_fee_bps, spread_bps, slippage_bps): (f64, f64, f64, f64),
+        perf: &Performance,
+        costs: &CostBreakdown,
+    ) -> Self {
+        Self {
+            maker_fee_bps,
+            taker_fee_bps,
+            spread_bps,
+            slippage_bps,
+            closed_trades: perf.closed_trades,
+            pnl: perf.pnl,
+            roi_pct: perf.roi_pct,
+            max_drawdown_pct: perf.max_drawdown_pct,
+            sharpe: perf.sharpe,
+            profit_factor: perf.profit_factor,
+            total_costs: costs.total,
+            profitable: perf.pnl > 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakevenStatus {
+    /// PnL переходит через ноль внутри перебранных значений
+    Crosses,
+    AlwaysProfitable,
+    NeverProfitable,
+}
+
+/// Комиссия, при которой конфиг перестаёт быть прибыльным
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Breakeven {
+    pub status: BreakevenStatus,
+    /// Линейная интерполяция PnL между соседними значениями
+    pub fee_bps: Option<f64>,
+}
+
+/// Точка безубыточности по `(fee_bps, pnl)`, отсортированным по возрастанию fee
+pub fn breakeven(points: &[(f64, f64)]) -> Breakeven {
+    let Some(first) = points.first() else {
+        return Breakeven {
+            status: BreakevenStatus::NeverProfitable,
+            fee_bps: None,
+        };
+    };
+    if first.1 <= 0.0 {
+        return Breakeven {
+            status: BreakevenStatus::NeverProfitable,
+            fee_bps: None,
+        };
+    }
+    for w in points.windows(2) {
+        let ((x0, p0), (x1, p1)) = (w[0], w[1]);
+        if p1 <= 0.0 {
+            return Breakeven {
+                status: BreakevenStatus::Crosses,
+                fee_bps: Some(x0 + (x1 - x0) * p0 / (p0 - p1)),
+            };
+        }
+    }
+    Breakeven {
+        status: BreakevenStatus::AlwaysProfitable,
+        fee_bps: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_fee_where_pnl_crosses_zero() {
+        let b = breakeven(&[(0.0, 30.0), (5.0, 10.0), (10.0, -10.0), (15.0, -30.0)]);
+        assert_eq!(b.status, BreakevenStatus::Crosses);
+        assert_eq!(b.fee_bps, Some(7.5));
+
+        assert_eq!(
+            breakeven(&[(0.0, 1.0), (5.0, 0.5)]).status,
+            BreakevenStatus::AlwaysProfitable
+        );
+        assert_eq!(
+            breakeven(&[(0.0, -1.0), (5.0, -2.0)]).status,
+            BreakevenStatus::NeverProfitable
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::search::Dim;
+use crate::stats::Performance;
+
+/// Прогон соседа: параметр `param` сдвинут на шаг от лучшего конфига
+#[derive(Debug, Clone, Serialize)]
+pub struct NeighborRow {
+    pub rank: usize,
+    pub param: &'static str,
+    pub base_value: f64,
+    pub value: f64,
+    pub roi_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub sharpe: f64,
+    pub roi_delta_pct: f64,
+}
+
+/// Чувствительность одного конфига к соседям
+#[derive(Debug, Clone, Serialize)]
+pub struct StabilitySummary {
+    pub rank: usize,
+    pub base_roi_pct: f64,
+    pub neighbors: usize,
+    pub mean_roi_pct: f64,
+    pub worst_roi_pct: f64,
+    pub worst_param: Option<&'static str>,
+    /// Худший сосед теряет больше `fragile_roi_drop` от ROI конфига
+    pub fragile: bool,
+}
+
+/// Окрестность ± один шаг по каждому параметру (остальные на месте).
+///
+/// `run` возвращает метрики конфига из значений измерений, `None` — конфиг
+/// невалиден. Хрупкость: `base_roi - worst_roi > fragile_roi_drop * max(|base_roi|, 1)`.
+pub fn neighborhood(
+    rank: usize,
+    names: &[&'static str],
+    dims: &[Dim],
+    values: &[f64],
+    base: &Performance,
+    fragile_roi_drop: f64,
+    mut run: impl FnMut(&[f64]) -> Result<Option<Performance>>,
+) -> Result<(Vec<NeighborRow>, StabilitySummary)> {
+    let mut rows = Vec::new();
+    for (i, dim) in dims.iter().enumerate() {
+        for up in [false, true] {
+            let Some(value) = dim.step(values[i], up) else {
+                continue;
+            };
+            let mut point = values.to_vec();
+            point[i] = value;
+            let Some(perf) = run(&point)? else {
+                continue;
+            };
+            rows.push(NeighborRow {
+                rank,
+                param: names[i],
+                base_value: values[i],
+                value,
+                roi_pct: perf.roi_pct,
+                max_drawdown_pct: perf.max_drawdown_pct,
+                sharpe: perf.sharpe,
+                roi_delta_pct: perf.roi_pct - base.roi_pct,
+            });
+        }
+    }
+
+    let worst = rows.iter().min_by(|a, b| a.roi_pct.total_cmp(&b.roi_pct));
+    let worst_roi_pct = worst.map_or(base.roi_pct, |r| r.roi_pct);
+    let mean_roi_pct = if rows.is_empty() {
+        base.roi_pct
+    } else {
+        rows.iter().map(|r| r.roi_pct).sum::<f64>() / rows.len() as f64
+    };
+    let summary = StabilitySummary {
+        rank,
+        base_roi_pct: base.roi_pct,
+        neighbors: rows.len(),
+        mean_roi_pct,
+        worst_roi_pct,
+        worst_param: worst.map(|r| r.param),
+        fragile: base.roi_pct - worst_roi_pct > fragile_roi_drop * base.roi_pct.abs().max(1.0),
+    };
+    Ok((rows, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_config_on_a_cliff() {
+        let dims = vec![
+            Dim::parse("1,2,3", "a", true).unwrap(),
+            Dim::parse("0..10", "b", false).unwrap(),
+        ];
+        let perf = |roi_pct| Performance {
+            roi_pct,
+            ..Default::default()
+        };
+        // ROI обваливается при a=3; от b=0 вниз шагать некуда
+        let roi = |p: &[f64]| if p[0] == 3.0 { -5.0 } else { 10.0 - p[1] };
+        let base = perf(roi(&[2.0, 0.0]));
+
+        let (rows, summary) = neighborhood(1, &["a", "b"], &dims, &[2.0, 0.0], &base, 0.5, |p| {
+            Ok(Some(perf(roi(p))))
+        })
+        .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(summary.worst_param, Some("a"));
+        assert_eq!(summary.worst_roi_pct, -5.0);
+        assert!(summary.fragile);
+
+        let (_, summary) = neighborhood(1, &["a", "b"], &dims, &[2.0, 0.0], &base, 0.5, |p| {
+            Ok((p[0] != 3.0).then(|| perf(roi(p))))
+        })
+        .unwrap();
+        assert_eq!(summary.neighbors, 2);
+        assert!(!summary.fragile);
+    }
+}
@@ -0,0 +1,583 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize};
+
+const YEAR_MS: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Закрытые сделки: выигрыши, проигрыши, валовые прибыль/убыток
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TradeStats {
+    pub closed: usize,
+    pub winning: usize,
+    pub losing: usize,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+}
+
+impl TradeStats {
+    pub fn on_close(&mut self, pnl: f64) {
+        self.closed += 1;
+        if pnl > 0.0 {
+            self.winning += 1;
+            self.gross_profit += pnl;
+        } else if pnl < 0.0 {
+            self.losing += 1;
+            self.gross_loss += -pnl;
+        }
+    }
+
+    pub fn win_rate_pct(&self) -> f64 {
+        if self.closed > 0 {
+            100.0 * (self.winning as f64) / (self.closed as f64)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn avg_win(&self) -> f64 {
+        if self.winning > 0 {
+            self.gross_profit / (self.winning as f64)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn avg_loss(&self) -> f64 {
+        if self.losing > 0 {
+            self.gross_loss / (self.losing as f64)
+        } else {
+            0.0
+        }
+    }
+
+    /// Без убыточных сделок — INF (если была прибыль) или 0
+    pub fn profit_factor(&self) -> f64 {
+        if self.gross_loss > 0.0 {
+            self.gross_profit / self.gross_loss
+        } else if self.gross_profit > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Максимальная просадка equity от пика (доля) и её длительность
+#[derive(Debug, Copy, Clone)]
+pub struct Drawdown {
+    pub max_equity: f64,
+    pub max_drawdown: f64,
+    peak_ts: Option<i64>,
+    /// Пик, от которого идёт текущая просадка
+    underwater_since: Option<i64>,
+    last_ts: i64,
+    max_duration_ms: i64,
+    /// Дно максимальной просадки, пока equity не вернулась к пику
+    open_trough_ts: Option<i64>,
+    recovery_ms: Option<i64>,
+}
+
+impl Drawdown {
+    pub fn new(initial_equity: f64) -> Self {
+        Self {
+            max_equity: initial_equity,
+            max_drawdown: 0.0,
+            peak_ts: None,
+            underwater_since: None,
+            last_ts: 0,
+            max_duration_ms: 0,
+            open_trough_ts: None,
+            recovery_ms: Some(0),
+        }
+    }
+
+    /// Текущая просадка; `None`, пока пик equity не положительный
+    pub fn update(&mut self, ts: i64, equity: f64) -> Option<f64> {
+        let peak_ts = *self.peak_ts.get_or_insert(ts);
+        self.last_ts = ts;
+        if equity >= self.max_equity {
+            self.max_equity = equity;
+            self.peak_ts = Some(ts);
+            if let Some(start) = self.underwater_since.take() {
+                self.max_duration_ms = self.max_duration_ms.max(ts - start);
+            }
+            if let Some(trough) = self.open_trough_ts.take() {
+                self.recovery_ms = Some(ts - trough);
+            }
+        } else if self.underwater_since.is_none() {
+            self.underwater_since = Some(peak_ts);
+        }
+        if self.max_equity <= 0.0 {
+            return None;
+        }
+        let dd = (self.max_equity - equity) / self.max_equity;
+        if dd > self.max_drawdown {
+            self.max_drawdown = dd;
+            self.open_trough_ts = Some(ts);
+            self.recovery_ms = None;
+        }
+        Some(dd)
+    }
+
+    /// Самый долгий период под водой (от пика до возврата к нему), включая незакрытый
+    pub fn max_duration_ms(&self) -> i64 {
+        let open = self
+            .underwater_since
+            .map_or(0, |start| self.last_ts - start);
+        self.max_duration_ms.max(open)
+    }
+
+    /// От дна максимальной просадки до нового пика; `None` — equity так и не восстановилась
+    pub fn recovery_ms(&self) -> Option<i64> {
+        self.recovery_ms
+    }
+}
+
+/// Доходности equity бар-к-бару: Sharpe, Sortino, доля времени в позиции.
+///
+/// Годовой масштаб берётся из наблюдаемой частоты баров, интервал свечей знать не нужно.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ReturnStats {
+    prev_equity: Option<f64>,
+    first_ts: Option<i64>,
+    last_ts: i64,
+    returns: usize,
+    sum: f64,
+    sum_sq: f64,
+    downside_sq: f64,
+    bars: usize,
+    exposed_bars: usize,
+}
+
+impl ReturnStats {
+    /// Equity на закрытии бара; `exposed` — есть открытая позиция
+    pub fn on_bar(&mut self, ts: i64, equity: f64, exposed: bool) {
+        self.first_ts.get_or_insert(ts);
+        self.last_ts = ts;
+        self.bars += 1;
+        if exposed {
+            self.exposed_bars += 1;
+        }
+        if let Some(prev) = self.prev_equity.filter(|p| *p > 0.0) {
+            let r = equity / prev - 1.0;
+            self.returns += 1;
+            self.sum += r;
+            self.sum_sq += r * r;
+            if r < 0.0 {
+                self.downside_sq += r * r;
+            }
+        }
+        self.prev_equity = Some(equity);
+    }
+
+    /// Длина прогона в годах
+    pub fn years(&self) -> f64 {
+        let span = self.last_ts - self.first_ts.unwrap_or(self.last_ts);
+        span as f64 / YEAR_MS
+    }
+
+    fn annualize(&self, mean: f64, dev: f64) -> f64 {
+        let years = self.years();
+        if self.returns < 2 || years <= 0.0 || dev <= 0.0 {
+            return 0.0;
+        }
+        let periods_per_year = self.returns as f64 / years;
+        mean / dev * periods_per_year.sqrt()
+    }
+
+    /// Годовой Sharpe (без безрисковой ставки); 0, если не определён
+    pub fn sharpe(&self) -> f64 {
+        if self.returns == 0 {
+            return 0.0;
+        }
+        let n = self.returns as f64;
+        let mean = self.sum / n;
+        let var = (self.sum_sq / n - mean * mean).max(0.0);
+        self.annualize(mean, var.sqrt())
+    }
+
+    /// Годовой Sortino: в знаменателе только отрицательные доходности
+    pub fn sortino(&self) -> f64 {
+        if self.returns == 0 {
+            return 0.0;
+        }
+        let n = self.returns as f64;
+        self.annualize(self.sum / n, (self.downside_sq / n).sqrt())
+    }
+
+    pub fn exposure_pct(&self) -> f64 {
+        if self.bars > 0 {
+            100.0 * self.exposed_bars as f64 / self.bars as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Издержки прогона в quote: maker комиссии сетки, taker комиссии
+/// (bootstrap, force close, рыночные входы/выходы) и спред+проскальзывание к mid
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub maker_fees: f64,
+    pub taker_fees: f64,
+    pub spread_slippage: f64,
+    pub total: f64,
+}
+
+impl CostBreakdown {
+    pub fn on_maker(&mut self, fee: f64) {
+        self.maker_fees += fee;
+        self.total += fee;
+    }
+
+    /// Taker сделка: `fill` — цена исполнения, `mid` — цена без спреда
+    pub fn on_taker(&mut self, qty: f64, mid: f64, fill: f64, fee: f64) {
+        let spread = qty * (fill - mid).abs();
+        self.taker_fees += fee;
+        self.spread_slippage += spread;
+        self.total += fee + spread;
+    }
+
+    pub fn mean(items: &[Self]) -> Self {
+        Self {
+            maker_fees: mean_of(items, |c| c.maker_fees),
+            taker_fees: mean_of(items, |c| c.taker_fees),
+            spread_slippage: mean_of(items, |c| c.spread_slippage),
+            total: mean_of(items, |c| c.total),
+        }
+    }
+
+    /// Издержки нескольких прогонов вместе (символы портфеля)
+    pub fn sum(items: &[Self]) -> Self {
+        items.iter().fold(Self::default(), |acc, c| Self {
+            maker_fees: acc.maker_fees + c.maker_fees,
+            taker_fees: acc.taker_fees + c.taker_fees,
+            spread_slippage: acc.spread_slippage + c.spread_slippage,
+            total: acc.total + c.total,
+        })
+    }
+}
+
+/// Среднее поля по прогонам (например, одного конфига на разных символах)
+pub fn mean_of<T>(items: &[T], f: impl Fn(&T) -> f64) -> f64 {
+    if items.is_empty() {
+        return 0.0;
+    }
+    items.iter().map(f).sum::<f64>() / items.len() as f64
+}
+
+/// Среднее счётчика, округлённое
+pub fn mean_count<T>(items: &[T], f: impl Fn(&T) -> usize) -> usize {
+    mean_of(items, |x| f(x) as f64).round() as usize
+}
+
+/// Стандартное отклонение (по генеральной совокупности)
+pub fn std_dev(xs: &[f64]) -> f64 {
+    let mean = mean_of(xs, |x| *x);
+    mean_of(xs, |x| (x - mean).powi(2)).sqrt()
+}
+
+/// Корреляция Пирсона рядов одинаковой длины; 0, если у ряда нет разброса
+pub fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    let (a, b) = (&a[..n], &b[..n]);
+    let (sa, sb) = (std_dev(a), std_dev(b));
+    if n < 2 || sa <= 0.0 || sb <= 0.0 {
+        return 0.0;
+    }
+    let (ma, mb) = (mean_of(a, |x| *x), mean_of(b, |x| *x));
+    let cov = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (x - ma) * (y - mb))
+        .sum::<f64>()
+        / n as f64;
+    (cov / (sa * sb)).clamp(-1.0, 1.0)
+}
+
+/// Критерий ранжирования sweep'ов
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankBy {
+    #[default]
+    Roi,
+    Sharpe,
+    Sortino,
+    Calmar,
+}
+
+/// Итоговые метрики прогона, общие для всех backtest'ов.
+/// В JSON ключи совпадают с теми, что печатаются в stdout.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct Performance {
+    pub closed_trades: usize,
+    #[serde(rename = "win_rate")]
+    pub win_rate_pct: f64,
+    pub avg_win: f64,
+    pub avg_loss: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    /// inf (нет убыточных сделок) уходит в JSON как null
+    #[serde(deserialize_with = "inf_from_null")]
+    pub profit_factor: f64,
+    #[serde(rename = "max_drawdown")]
+    pub max_drawdown_pct: f64,
+    pub pnl: f64,
+    #[serde(rename = "roi")]
+    pub roi_pct: f64,
+    pub sharpe: f64,
+    pub sortino: f64,
+    /// Годовая доходность (CAGR) / max drawdown
+    #[serde(deserialize_with = "inf_from_null")]
+    pub calmar: f64,
+    #[serde(rename = "exposure")]
+    pub exposure_pct: f64,
+    #[serde(rename = "avg_trade_duration")]
+    pub avg_trade_duration_h: f64,
+    /// Оборот: notional всех сделок / начальная equity, раз
+    #[serde(default)]
+    pub turnover: f64,
+    /// Самый долгий период под водой, ч
+    #[serde(rename = "max_drawdown_duration", default)]
+    pub max_drawdown_duration_h: f64,
+    /// От дна максимальной просадки до нового пика, ч; null — не восстановилась
+    #[serde(rename = "time_to_recovery", default)]
+    pub time_to_recovery_h: Option<f64>,
+}
+
+/// serde_json пишет inf как null — читаем обратно как inf
+fn inf_from_null<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(d)?.unwrap_or(f64::INFINITY))
+}
+
+impl Performance {
+    pub fn new(
+        trades: &TradeStats,
+        drawdown: &Drawdown,
+        initial_equity: f64,
+        final_equity: f64,
+    ) -> Self {
+        let pnl = final_equity - initial_equity;
+        Self {
+            closed_trades: trades.closed,
+            win_rate_pct: trades.win_rate_pct(),
+            avg_win: trades.avg_win(),
+            avg_loss: trades.avg_loss(),
+            gross_profit: trades.gross_profit,
+            gross_loss: trades.gross_loss,
+            profit_factor: trades.profit_factor(),
+            max_drawdown_pct: drawdown.max_drawdown * 100.0,
+            max_drawdown_duration_h: drawdown.max_duration_ms() as f64 / 3_600_000.0,
+            time_to_recovery_h: drawdown.recovery_ms().map(|ms| ms as f64 / 3_600_000.0),
+            pnl,
+            roi_pct: if initial_equity > 0.0 {
+                100.0 * pnl / initial_equity
+            } else {
+                0.0
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Риск-метрики по ряду доходностей; `avg_trade_ms` — средняя длительность сделки
+    pub fn with_risk(mut self, returns: &ReturnStats, avg_trade_ms: f64) -> Self {
+        self.sharpe = returns.sharpe();
+        self.sortino = returns.sortino();
+        self.exposure_pct = returns.exposure_pct();
+        self.avg_trade_duration_h = avg_trade_ms / 3_600_000.0;
+
+        let growth = 1.0 + self.roi_pct / 100.0;
+        let years = returns.years();
+        if growth > 0.0 && years > 0.0 && self.max_drawdown_pct > 0.0 {
+            let cagr_pct = (growth.powf(1.0 / years) - 1.0) * 100.0;
+            self.calmar = cagr_pct / self.max_drawdown_pct;
+        }
+        self
+    }
+
+    /// Оборот по суммарному notional сделок `traded` от начальной equity
+    pub fn with_turnover(mut self, traded: f64, initial_equity: f64) -> Self {
+        self.turnover = if initial_equity > 0.0 {
+            traded / initial_equity
+        } else {
+            0.0
+        };
+        self
+    }
+
+    /// Метрики, усреднённые по прогонам: конфиг ранжируется по среднему
+    pub fn mean(items: &[Self]) -> Self {
+        Self {
+            closed_trades: mean_count(items, |p| p.closed_trades),
+            win_rate_pct: mean_of(items, |p| p.win_rate_pct),
+            avg_win: mean_of(items, |p| p.avg_win),
+            avg_loss: mean_of(items, |p| p.avg_loss),
+            gross_profit: mean_of(items, |p| p.gross_profit),
+            gross_loss: mean_of(items, |p| p.gross_loss),
+            profit_factor: mean_of(items, |p| p.profit_factor),
+            max_drawdown_pct: mean_of(items, |p| p.max_drawdown_pct),
+            pnl: mean_of(items, |p| p.pnl),
+            roi_pct: mean_of(items, |p| p.roi_pct),
+            sharpe: mean_of(items, |p| p.sharpe),
+            sortino: mean_of(items, |p| p.sortino),
+            calmar: mean_of(items, |p| p.calmar),
+            exposure_pct: mean_of(items, |p| p.exposure_pct),
+            avg_trade_duration_h: mean_of(items, |p| p.avg_trade_duration_h),
+            turnover: mean_of(items, |p| p.turnover),
+            max_drawdown_duration_h: mean_of(items, |p| p.max_drawdown_duration_h),
+            // хотя бы один невосстановившийся прогон — конфиг не восстановился
+            time_to_recovery_h: items
+                .iter()
+                .map(|p| p.time_to_recovery_h)
+                .collect::<Option<Vec<f64>>>()
+                .map(|v| v.iter().sum::<f64>() / v.len().max(1) as f64),
+        }
+    }
+
+    /// Порядок sweep'ов: ROI по убыванию, затем меньшая просадка, затем больший PF
+    pub fn best_first(&self, other: &Self) -> Ordering {
+        other
+            .roi_pct
+            .partial_cmp(&self.roi_pct)
+            .unwrap_or(Ordering::Equal)
+            .then(
+                self.max_drawdown_pct
+                    .partial_cmp(&other.max_drawdown_pct)
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(
+                other
+                    .profit_factor
+                    .partial_cmp(&self.profit_factor)
+                    .unwrap_or(Ordering::Equal),
+            )
+    }
+
+    pub fn metric(&self, by: RankBy) -> f64 {
+        match by {
+            RankBy::Roi => self.roi_pct,
+            RankBy::Sharpe => self.sharpe,
+            RankBy::Sortino => self.sortino,
+            RankBy::Calmar => self.calmar,
+        }
+    }
+
+    /// Порядок sweep'ов по выбранной метрике (по убыванию), ничьи — как `best_first`
+    pub fn rank_cmp(&self, other: &Self, by: RankBy) -> Ordering {
+        other
+            .metric(by)
+            .partial_cmp(&self.metric(by))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.best_first(other))
+    }
+
+    /// Время восстановления для вывода: не восстановилась — `none`
+    pub fn recovery_label(&self) -> String {
+        match self.time_to_recovery_h {
+            Some(h) => format!("{:.2}h", h),
+            None => "none".to_string(),
+        }
+    }
+
+    /// PF для вывода: без убыточных сделок — `INF`
+    pub fn profit_factor_label(&self) -> String {
+        if self.gross_loss > 0.0 {
+            format!("{:.4}", self.profit_factor)
+        } else {
+            "INF".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn performance_from_trades_and_drawdown() {
+        let mut t = TradeStats::default();
+        for pnl in [10.0, -5.0, 5.0, 0.0] {
+            t.on_close(pnl);
+        }
+        let mut dd = Drawdown::new(100.0);
+        for (ts, e) in [(0, 110.0), (1, 99.0), (2, 120.0)] {
+            dd.update(ts, e);
+        }
+
+        let p = Performance::new(&t, &dd, 100.0, 120.0);
+        assert_eq!(p.closed_trades, 4);
+        assert_eq!(p.win_rate_pct, 50.0);
+        assert_eq!(p.avg_win, 7.5);
+        assert_eq!(p.profit_factor, 3.0);
+        assert!((p.max_drawdown_pct - 10.0).abs() < 1e-9);
+        assert_eq!(p.roi_pct, 20.0);
+
+        let worse = Performance { roi_pct: 5.0, ..p };
+        assert_eq!(p.best_first(&worse), Ordering::Less);
+    }
+
+    #[test]
+    fn drawdown_duration_and_recovery() {
+        const H: i64 = 3_600_000;
+        let mut dd = Drawdown::new(100.0);
+        // пик в 1ч, дно в 3ч, восстановление в 6ч; затем новая мелкая просадка до конца
+        for (h, e) in [(0, 100.0), (1, 110.0), (2, 100.0), (3, 90.0), (6, 111.0)] {
+            dd.update(h * H, e);
+        }
+        assert_eq!(dd.max_duration_ms(), 5 * H);
+        assert_eq!(dd.recovery_ms(), Some(3 * H));
+        for (h, e) in [(7, 108.0), (20, 109.0)] {
+            dd.update(h * H, e);
+        }
+        assert_eq!(dd.max_duration_ms(), 14 * H);
+        assert_eq!(dd.recovery_ms(), Some(3 * H));
+
+        dd.update(21 * H, 50.0);
+        assert_eq!(dd.recovery_ms(), None);
+        let p = Performance::new(&TradeStats::default(), &dd, 100.0, 50.0);
+        assert_eq!(p.max_drawdown_duration_h, 15.0);
+        assert_eq!(p.time_to_recovery_h, None);
+    }
+
+    #[test]
+    fn risk_metrics_from_equity_series() {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        let mut r = ReturnStats::default();
+        let mut dd = Drawdown::new(100.0);
+        for (i, e) in [100.0, 101.0, 100.5, 102.0, 103.0].into_iter().enumerate() {
+            r.on_bar(i as i64 * DAY, e, i % 2 == 0);
+            dd.update(i as i64 * DAY, e);
+        }
+        assert_eq!(r.exposure_pct(), 60.0);
+        assert!((r.years() - 4.0 / 365.0).abs() < 1e-12);
+        assert!(r.sharpe() > 0.0);
+        assert!(r.sortino() > r.sharpe());
+
+        let p = Performance::new(&TradeStats::default(), &dd, 100.0, 103.0)
+            .with_risk(&r, 2.0 * 3_600_000.0)
+            .with_turnover(250.0, 100.0);
+        assert_eq!(p.avg_trade_duration_h, 2.0);
+        assert_eq!(p.turnover, 2.5);
+        assert!(p.calmar > 0.0);
+
+        let steadier = Performance {
+            roi_pct: p.roi_pct - 1.0,
+            sharpe: p.sharpe * 2.0,
+            ..p
+        };
+        assert_eq!(p.rank_cmp(&steadier, RankBy::Roi), Ordering::Less);
+        assert_eq!(p.rank_cmp(&steadier, RankBy::Sharpe), Ordering::Greater);
+    }
+
+    #[test]
+    fn correlation_of_series() {
+        let a = [1.0, -2.0, 3.0, 0.5];
+        let b: Vec<f64> = a.iter().map(|x| 2.0 * x + 1.0).collect();
+        let c: Vec<f64> = a.iter().map(|x| -x).collect();
+        assert!((correlation(&a, &b) - 1.0).abs() < 1e-12);
+        assert!((correlation(&a, &c) + 1.0).abs() < 1e-12);
+        assert_eq!(correlation(&a, &[1.0; 4]), 0.0);
+        assert_eq!(std_dev(&[2.0, 4.0]), 1.0);
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use core::types::{Price, Qty};
+use structure::candle::Candle;
+
+/// Нижняя граница цены после шока, доля исходной: цена не уходит в ноль и ниже
+const MIN_PRICE_FRAC: f64 = 0.01;
+
+/// Синтетический шок для stress-теста MM
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StressKind {
+    /// Мгновенный гэп вниз на `gap_pct`, уровень после него не восстанавливается
+    Gap,
+    /// Отклонения цены от уровня до шока умножаются на `vol_mult`
+    Volatility,
+    /// Объём свечей умножается на `liquidity_mult`
+    Liquidity,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct StressParams {
+    pub gap_pct: f64,
+    pub vol_mult: f64,
+    pub liquidity_mult: f64,
+}
+
+impl StressParams {
+    pub fn validate(&self) -> Result<()> {
+        if self.gap_pct <= 0.0 || self.gap_pct >= 100.0 {
+            anyhow::bail!("stress gap_pct must be in (0, 100)");
+        }
+        if self.vol_mult <= 0.0 {
+            anyhow::bail!("stress vol_mult must be > 0");
+        }
+        if self.liquidity_mult <= 0.0 || self.liquidity_mult > 1.0 {
+            anyhow::bail!("stress liquidity_mult must be in (0, 1]");
+        }
+        Ok(())
+    }
+}
+
+/// Сценарии через запятую: `gap,volatility,liquidity`
+pub fn parse_kinds(s: &str) -> Result<Vec<StressKind>> {
+    let mut kinds = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let kind = StressKind::from_str(part, true)
+            .map_err(|_| anyhow::anyhow!("unknown stress kind: {}", part))?;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    if kinds.is_empty() {
+        anyhow::bail!("stress_kinds must not be empty");
+    }
+    Ok(kinds)
+}
+
+/// Начало шока на доле `frac` периода `candles`, по границе `align_ms` (HTF свеча)
+pub fn shock_start(candles: &[Candle], frac: f64, align_ms: i64) -> Option<i64> {
+    let (first, last) = (candles.first()?.ts.0, candles.last()?.ts.0);
+    let ts = first + ((last - first) as f64 * frac.clamp(0.0, 1.0)) as i64;
+    Some(ts - ts.rem_euclid(align_ms.max(1)))
+}
+
+/// Шок на окне `[start, end)`: монотонное преобразование цены и объёма по ts,
+/// поэтому HTF и LTF свечи одного периода меняются согласованно
+/// (окно выровнено по HTF свечам)
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Shock {
+    pub kind: StressKind,
+    pub start: i64,
+    pub end: i64,
+    /// Гэп: множитель цены; волатильность: множитель отклонений; ликвидность: объёма
+    mult: f64,
+    /// Волатильность: цена до шока и множитель цены после окна (уровень не прыгает назад)
+    anchor: f64,
+    after: f64,
+}
+
+impl Shock {
+    /// Уровни волатильности берутся из `reference` — самых мелких свечей прогона
+    pub fn new(
+        kind: StressKind,
+        (start, end): (i64, i64),
+        params: StressParams,
+        reference: &[Candle],
+    ) -> Self {
+        let mut shock = Self {
+            kind,
+            start,
+            end,
+            mult: match kind {
+                StressKind::Gap => 1.0 - params.gap_pct / 100.0,
+                StressKind::Volatility => params.vol_mult,
+                StressKind::Liquidity => params.liquidity_mult,
+            },
+            anchor: 0.0,
+            after: 1.0,
+        };
+        if kind == StressKind::Volatility {
+            shock.anchor = reference
+                .iter()
+                .rev()
+                .find(|c| c.ts.0 < start)
+                .map(|c| c.close.0)
+                .or(reference.first().map(|c| c.open.0))
+                .unwrap_or(0.0);
+            if let Some(last) = reference.iter().rev().find(|c| c.ts.0 < end) {
+                shock.after = shock.window_price(last.close.0) / last.close.0;
+            }
+        }
+        shock
+    }
+
+    fn window_price(&self, p: f64) -> f64 {
+        (self.anchor + self.mult * (p - self.anchor)).max(p * MIN_PRICE_FRAC)
+    }
+
+    fn price(&self, ts: i64, p: f64) -> f64 {
+        match self.kind {
+            _ if ts < self.start => p,
+            StressKind::Gap => p * self.mult,
+            StressKind::Volatility if ts < self.end => self.window_price(p),
+            StressKind::Volatility => p * self.after,
+            StressKind::Liquidity => p,
+        }
+    }
+
+    fn volume(&self, ts: i64, v: f64) -> f64 {
+        if self.kind == StressKind::Liquidity && ts >= self.start && ts < self.end {
+            v * self.mult
+        } else {
+            v
+        }
+    }
+
+    /// Свечи с применённым шоком
+    pub fn apply(&self, candles: &[Candle]) -> Vec<Candle> {
+        candles
+            .iter()
+            .map(|c| {
+                let ts = c.ts.0;
+                Candle {
+                    ts: c.ts,
+                    open: Price(self.price(ts, c.open.0)),
+                    high: Price(self.price(ts, c.high.0)),
+                    low: Price(self.price(ts, c.low.0)),
+                    close: Price(self.price(ts, c.close.0)),
+                    volume: Qty(self.volume(ts, c.volume.0)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::TimestampMs;
+
+    fn candle(ts: i64, open: f64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(open),
+            high: Price(open.max(close) + 1.0),
+            low: Price(open.min(close) - 1.0),
+            close: Price(close),
+            volume: Qty(10.0),
+        }
+    }
+
+    #[test]
+    fn shocks_keep_series_consistent() {
+        let params = StressParams {
+            gap_pct: 15.0,
+            vol_mult: 2.0,
+            liquidity_mult: 0.5,
+        };
+        params.validate().unwrap();
+        assert_eq!(
+            parse_kinds("gap, liquidity,gap").unwrap(),
+            [StressKind::Gap, StressKind::Liquidity]
+        );
+        assert!(parse_kinds("crash").is_err());
+        let candles: Vec<Candle> = (0..10)
+            .map(|i| candle(i * 60_000, 100.0 + i as f64, 101.0 + i as f64))
+            .collect();
+        assert_eq!(shock_start(&candles, 0.5, 120_000), Some(240_000));
+
+        let gap = Shock::new(StressKind::Gap, (240_000, 360_000), params, &candles).apply(&candles);
+        assert_eq!(gap[3].close.0, 104.0);
+        assert!((gap[4].open.0 - 104.0 * 0.85).abs() < 1e-9);
+        assert!((gap[9].close.0 - 110.0 * 0.85).abs() < 1e-9);
+
+        // до шока уровень 104: отклонения удваиваются, после окна цена не прыгает
+        let vol = Shock::new(StressKind::Volatility, (240_000, 360_000), params, &candles)
+            .apply(&candles);
+        assert_eq!(vol[4].open.0, 104.0);
+        assert_eq!(vol[5].close.0, 108.0);
+        assert!((vol[6].open.0 - 108.0).abs() < 1e-9);
+        assert!(
+            vol.iter()
+                .all(|c| c.low.0 <= c.open.0 && c.high.0 >= c.close.0)
+        );
+
+        let liq =
+            Shock::new(StressKind::Liquidity, (240_000, 360_000), params, &candles).apply(&candles);
+        let volumes: Vec<f64> = liq.iter().map(|c| c.volume.0).collect();
+        assert_eq!(volumes[3..7], [10.0, 5.0, 5.0, 10.0]);
+        assert_eq!(liq[5].close, candles[5].close);
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use core::types::{Price, Qty, TimestampMs};
+use structure::candle::Candle;
+
+use crate::search::normal;
+
+const YEAR_MS: f64 = 365.0 * 24.0 * 3600.0 * 1000.0;
+/// Шагов цены внутри свечи: из них берутся high/low
+const SUBSTEPS: usize = 16;
+/// Средний объём свечи, base
+const BASE_VOLUME: f64 = 100.0;
+
+/// Модель синтетической цены
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntheticModel {
+    /// Геометрическое броуновское движение: постоянные drift и волатильность
+    Gbm,
+    /// Стохастическая дисперсия с возвратом к `vol²` (Heston, full truncation)
+    Heston,
+    /// Спокойный и турбулентный режимы (марковское переключение) и скачки цены
+    Regime,
+}
+
+/// Параметры генератора; годовые величины, как в `vol`/`drift`
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct SyntheticParams {
+    pub model: SyntheticModel,
+    pub start_price: f64,
+    /// Годовой drift лог-цены
+    pub drift: f64,
+    /// Годовая волатильность (у heston — долгосрочная, у regime — спокойного режима)
+    pub vol: f64,
+    /// Heston: скорость возврата дисперсии, волатильность дисперсии, корреляция с ценой
+    pub kappa: f64,
+    pub vol_of_vol: f64,
+    pub rho: f64,
+    /// Regime: множитель волатильности турбулентного режима и переключений в год
+    pub turbulent_vol_mult: f64,
+    pub switches_per_year: f64,
+    /// Regime: скачков в год и их средний размер/разброс (доля лог-цены)
+    pub jumps_per_year: f64,
+    pub jump_mean: f64,
+    pub jump_std: f64,
+}
+
+impl SyntheticParams {
+    pub fn new(model: SyntheticModel, start_price: f64, drift: f64, vol: f64) -> Result<Self> {
+        if start_price <= 0.0 {
+            anyhow::bail!("synthetic start price must be > 0");
+        }
+        if vol <= 0.0 || vol > 10.0 {
+            anyhow::bail!("synthetic vol must be in (0, 10]");
+        }
+        Ok(Self {
+            model,
+            start_price,
+            drift,
+            vol,
+            kappa: 5.0,
+            vol_of_vol: 1.0,
+            rho: -0.5,
+            turbulent_vol_mult: 3.0,
+            switches_per_year: 50.0,
+            jumps_per_year: 20.0,
+            jump_mean: -0.02,
+            jump_std: 0.03,
+        })
+    }
+
+    /// Параметры для `--synthetic`; `None` — биржевые свечи
+    pub fn for_model(
+        model: Option<SyntheticModel>,
+        start_price: f64,
+        drift: f64,
+        vol: f64,
+    ) -> Result<Option<Self>> {
+        model
+            .map(|m| Self::new(m, start_price, drift, vol))
+            .transpose()
+    }
+}
+
+/// Серия и её заданные свойства — ground truth для проверки стратегии
+#[derive(Debug, Clone)]
+pub struct SyntheticSeries {
+    pub candles: Vec<Candle>,
+    /// Реализованная годовая волатильность log-доходностей close
+    pub realized_vol: f64,
+    pub jumps: usize,
+    pub regime_switches: usize,
+}
+
+impl SyntheticSeries {
+    pub fn summary(&self) -> String {
+        format!(
+            "candles={} realized_vol={:.4} jumps={} regime_switches={}",
+            self.candles.len(),
+            self.realized_vol,
+            self.jumps,
+            self.regime_switches
+        )
+    }
+}
+
+/// Свечи `interval_ms` на `[start, end]` (ts от `start`, выровненного по интервалу);
+/// одинаковые `params` и `seed` дают одну и ту же серию
+pub fn generate(
+    params: &SyntheticParams,
+    interval_ms: i64,
+    (start, end): (i64, i64),
+    seed: u64,
+) -> SyntheticSeries {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dt = interval_ms as f64 / YEAR_MS / SUBSTEPS as f64;
+    let base_var = params.vol * params.vol;
+    let mut var = base_var;
+    let mut turbulent = false;
+    let mut log_price = params.start_price.ln();
+    let mut out = SyntheticSeries {
+        candles: Vec::new(),
+        realized_vol: 0.0,
+        jumps: 0,
+        regime_switches: 0,
+    };
+
+    let mut ts = start + (interval_ms - start.rem_euclid(interval_ms)) % interval_ms;
+    while ts <= end {
+        let open = log_price.exp();
+        let (mut high, mut low) = (open, open);
+        let mut abs_move = 0.0;
+        for _ in 0..SUBSTEPS {
+            let z = normal(&mut rng);
+            let step_var = match params.model {
+                SyntheticModel::Gbm => base_var,
+                SyntheticModel::Heston => {
+                    let z2 =
+                        params.rho * z + (1.0 - params.rho * params.rho).sqrt() * normal(&mut rng);
+                    let v = var.max(0.0);
+                    var += params.kappa * (base_var - v) * dt
+                        + params.vol_of_vol * v.sqrt() * dt.sqrt() * z2;
+                    v
+                }
+                SyntheticModel::Regime => {
+                    if rng.r#gen::<f64>() < params.switches_per_year * dt {
+                        turbulent = !turbulent;
+                        out.regime_switches += 1;
+                    }
+                    let mult = if turbulent {
+                        params.turbulent_vol_mult
+                    } else {
+                        1.0
+                    };
+                    base_var * mult * mult
+                }
+            };
+            let mut ret = (params.drift - 0.5 * step_var) * dt + (step_var * dt).sqrt() * z;
+            if params.model == SyntheticModel::Regime
+                && rng.r#gen::<f64>() < params.jumps_per_year * dt
+            {
+                ret += params.jump_mean + params.jump_std * normal(&mut rng);
+                out.jumps += 1;
+            }
+            log_price += ret;
+            abs_move += ret.abs();
+            let p = log_price.exp();
+            high = high.max(p);
+            low = low.min(p);
+        }
+        // объём растёт с размахом движения внутри свечи
+        let typical_move = params.vol * (dt * SUBSTEPS as f64).sqrt();
+        let volume = BASE_VOLUME * (0.5 + rng.r#gen::<f64>()) * (0.5 + abs_move / typical_move);
+        out.candles.push(Candle {
+            ts: TimestampMs(ts),
+            open: Price(open),
+            high: Price(high),
+            low: Price(low),
+            close: Price(log_price.exp()),
+            volume: Qty(volume),
+        });
+        ts += interval_ms;
+    }
+
+    let returns: Vec<f64> = out
+        .candles
+        .windows(2)
+        .map(|w| (w[1].close.0 / w[0].close.0).ln())
+        .collect();
+    if returns.len() > 1 {
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let var =
+            returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        out.realized_vol = (var * YEAR_MS / interval_ms as f64).sqrt();
+    }
+    out
+}
+
+/// Синтетические свечи для backtest'а вместо `load_candles`, свойства серии — в stdout
+pub fn synthetic_candles(
+    params: &SyntheticParams,
+    interval_ms: i64,
+    range: (i64, i64),
+    seed: u64,
+) -> Vec<Candle> {
+    let series = generate(params, interval_ms, range, seed);
+    println!(
+        "synthetic: model={:?} seed={} drift={:.4} vol={:.4} {}",
+        params.model,
+        seed,
+        params.drift,
+        params.vol,
+        series.summary()
+    );
+    series.candles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structure::candle::resample_ms;
+
+    #[test]
+    fn generated_series_match_their_parameters() {
+        let day = 24 * 3600 * 1000;
+        let range = (0, 60 * day - 1);
+        let gbm = SyntheticParams::new(SyntheticModel::Gbm, 2000.0, 0.0, 0.6).unwrap();
+        let a = generate(&gbm, 300_000, range, 7);
+        assert_eq!(a.candles.len(), 60 * 288);
+        assert!((a.realized_vol - 0.6).abs() < 0.03, "{}", a.summary());
+        assert!(a.candles.iter().all(|c| {
+            c.low.0 <= c.open.0.min(c.close.0) && c.high.0 >= c.open.0.max(c.close.0)
+        }));
+        let again = generate(&gbm, 300_000, range, 7);
+        assert_eq!(
+            a.candles.last().unwrap().close,
+            again.candles.last().unwrap().close
+        );
+
+        let regime = SyntheticParams::new(SyntheticModel::Regime, 2000.0, 0.0, 0.6).unwrap();
+        let r = generate(&regime, 300_000, range, 7);
+        assert!(r.jumps > 0 && r.regime_switches > 0);
+        assert!(r.realized_vol > a.realized_vol);
+
+        let htf = resample_ms(&a.candles, 900_000);
+        assert_eq!(htf.len(), a.candles.len() / 3);
+        assert_eq!(htf[1].open, a.candles[3].open);
+        assert_eq!(htf[1].close, a.candles[5].close);
+        assert!(SyntheticParams::new(SyntheticModel::Heston, 0.0, 0.0, 0.6).is_err());
+    }
+}
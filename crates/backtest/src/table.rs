@@ -0,0 +1,199 @@
+//! Табличные артефакты и кэши: CSV или Parquet (feature `parquet`).
+//! Формат записи — по расширению `.parquet`, чтения — по содержимому файла.
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::data::CandleRow;
+
+/// Первые байты Parquet файла
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+pub fn is_parquet_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"))
+}
+
+/// Parquet ли файл (по magic bytes, расширение не важно)
+pub fn is_parquet_file(path: &str) -> Result<bool> {
+    let mut head = [0u8; 4];
+    let mut f = std::fs::File::open(path)?;
+    let n = f.read(&mut head)?;
+    Ok(n == head.len() && &head == PARQUET_MAGIC)
+}
+
+/// Строки в CSV или, для `.parquet`, в Parquet с теми же колонками
+pub fn write_table<T: Serialize>(path: &str, rows: impl IntoIterator<Item = T>) -> Result<()> {
+    if !is_parquet_path(path) {
+        return crate::report::write_csv(path, rows);
+    }
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    parquet_io::write_rows(path, rows)
+}
+
+/// Свечи кэша; формат определяется по содержимому
+pub fn read_candle_rows(path: &str) -> Result<Vec<CandleRow>> {
+    if is_parquet_file(path)? {
+        return parquet_io::read_candle_rows(path);
+    }
+    let mut rdr = csv::Reader::from_path(path)?;
+    rdr.deserialize()
+        .collect::<Result<Vec<CandleRow>, _>>()
+        .context("parse csv failed")
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_io {
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use anyhow::{Context, Result};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type};
+    use arrow_array::{Array, RecordBatch};
+    use arrow_schema::{DataType, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use serde::Serialize;
+
+    use crate::data::CandleRow;
+
+    /// Строки сериализуются как в CSV, типы колонок выводятся по значениям
+    pub fn write_rows<T: Serialize>(path: &str, rows: impl IntoIterator<Item = T>) -> Result<()> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        for r in rows {
+            wtr.serialize(r)?;
+        }
+        let buf = wtr.into_inner().context("serialize rows failed")?;
+
+        let schema = if buf.is_empty() {
+            Schema::empty()
+        } else {
+            arrow_csv::reader::Format::default()
+                .with_header(true)
+                .infer_schema(Cursor::new(&buf), None)?
+                .0
+        };
+        let schema = Arc::new(schema);
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let file = std::fs::File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+        if !buf.is_empty() {
+            let reader = arrow_csv::ReaderBuilder::new(schema)
+                .with_header(true)
+                .build(Cursor::new(&buf))?;
+            for batch in reader {
+                writer.write(&batch?)?;
+            }
+        }
+        writer.close()?;
+        Ok(())
+    }
+
+    fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Arc<dyn Array>> {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("parquet cache has no column {}", name))
+    }
+
+    fn f64_column(batch: &RecordBatch, name: &str) -> Result<Vec<f64>> {
+        let col = arrow_cast::cast(column(batch, name)?, &DataType::Float64)?;
+        Ok(col.as_primitive::<Float64Type>().values().to_vec())
+    }
+
+    pub fn read_candle_rows(path: &str) -> Result<Vec<CandleRow>> {
+        let file = std::fs::File::open(path)?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+        let mut out = Vec::new();
+        for batch in reader {
+            let batch = batch?;
+            let ts = arrow_cast::cast(column(&batch, "ts")?, &DataType::Int64)?;
+            let ts = ts.as_primitive::<Int64Type>();
+            let open = f64_column(&batch, "open")?;
+            let high = f64_column(&batch, "high")?;
+            let low = f64_column(&batch, "low")?;
+            let close = f64_column(&batch, "close")?;
+            let volume = f64_column(&batch, "volume")?;
+            for i in 0..batch.num_rows() {
+                out.push(CandleRow {
+                    ts: ts.value(i),
+                    open: open[i],
+                    high: high[i],
+                    low: low[i],
+                    close: close[i],
+                    volume: volume[i],
+                });
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+mod parquet_io {
+    use anyhow::Result;
+    use serde::Serialize;
+
+    use crate::data::CandleRow;
+
+    const DISABLED: &str = "parquet support is not compiled in (build with --features parquet)";
+
+    pub fn write_rows<T: Serialize>(path: &str, _rows: impl IntoIterator<Item = T>) -> Result<()> {
+        anyhow::bail!("{}: {}", DISABLED, path)
+    }
+
+    pub fn read_candle_rows(path: &str) -> Result<Vec<CandleRow>> {
+        anyhow::bail!("{}: {}", DISABLED, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_format_by_extension_and_content() {
+        assert!(is_parquet_path("data/cache/ETHUSDT_1m.parquet"));
+        assert!(is_parquet_path("x.PARQUET"));
+        assert!(!is_parquet_path("data/cache/ETHUSDT_1m.csv"));
+
+        let dir = std::env::temp_dir();
+        let rows = [CandleRow {
+            ts: 60_000,
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 10.0,
+        }];
+        let csv = dir.join(format!("bt-table-{}.csv", std::process::id()));
+        let csv = csv.to_str().unwrap();
+        write_table(csv, rows).unwrap();
+        assert!(!is_parquet_file(csv).unwrap());
+        assert_eq!(read_candle_rows(csv).unwrap()[0].close, 1.5);
+        std::fs::remove_file(csv).unwrap();
+
+        let pq = dir.join(format!("bt-table-{}.parquet", std::process::id()));
+        let pq = pq.to_str().unwrap();
+        let written = write_table(pq, rows);
+        if cfg!(feature = "parquet") {
+            written.unwrap();
+            assert!(is_parquet_file(pq).unwrap());
+            let back = read_candle_rows(pq).unwrap();
+            assert_eq!((back[0].ts, back[0].volume), (60_000, 10.0));
+            std::fs::remove_file(pq).unwrap();
+        } else {
+            assert!(written.is_err());
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::cmp::Ordering;
+
+/// Лучшие `cap` элементов потока (меньше по `cmp` — лучше), остальное отбрасывается.
+/// Элементы копятся до `2 * cap`, затем сортируются и обрезаются — амортизированно
+/// O(log cap) на элемент; `usize::MAX` — хранить всё.
+#[derive(Debug, Clone)]
+pub struct TopN<T> {
+    cap: usize,
+    items: Vec<T>,
+}
+
+impl<T> TopN<T> {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap: cap.max(1),
+            items: Vec::new(),
+        }
+    }
+
+    /// Добавляет элемент; возвращает вытесненные при обрезке (чтобы освободить их данные)
+    pub fn push(&mut self, item: T, cmp: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+        self.items.push(item);
+        if self.items.len() < self.cap.saturating_mul(2) {
+            return Vec::new();
+        }
+        self.items.sort_by(&cmp);
+        self.items.split_off(self.cap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len().min(self.cap)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Лучшие `cap` по возрастанию `cmp`
+    pub fn into_sorted(mut self, cmp: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+        self.items.sort_by(cmp);
+        self.items.truncate(self.cap);
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_best_items_within_bound() {
+        let desc = |a: &i32, b: &i32| b.cmp(a);
+        let mut top = TopN::new(3);
+        let mut evicted = Vec::new();
+        for x in [5, 1, 9, 3, 7, 2, 8, 6, 4, 0] {
+            evicted.extend(top.push(x, desc));
+            assert!(top.items.len() < 6);
+        }
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.into_sorted(desc), vec![9, 8, 7]);
+        evicted.sort();
+        assert_eq!(evicted, vec![1, 2, 3, 4, 5, 6]);
+
+        let mut all = TopN::new(usize::MAX);
+        for x in 0..100 {
+            assert!(all.push(x, desc).is_empty());
+        }
+        assert_eq!(all.into_sorted(desc).len(), 100);
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use bybit::rest::{BybitRest, download_trades};
+use core::types::{Price, Qty, TimestampMs, Trade, TradeSide};
+use structure::candle::Candle;
+
+use crate::data::{CacheMeta, Venue, cache_path_for};
+
+/// Источник LTF данных для исполнения сетки
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    /// Касание high/low LTF свечи — полный fill
+    #[default]
+    Candles,
+    /// Исторические сделки: fill, только если сделка прошла по цене лимитки, в пределах её объёма
+    Trades,
+}
+
+/// Строка CSV-кэша сделок
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct TradeRow {
+    ts: i64,
+    price: f64,
+    qty: f64,
+    side: SideCol,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SideCol {
+    Buy,
+    Sell,
+}
+
+pub fn read_trades_cache(path: &str) -> Result<Vec<Trade>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut out = Vec::new();
+    for r in rdr.deserialize::<TradeRow>() {
+        let row = r?;
+        out.push(Trade {
+            ts: TimestampMs(row.ts),
+            price: Price(row.price),
+            qty: Qty(row.qty),
+            side: match row.side {
+                SideCol::Buy => TradeSide::Buy,
+                SideCol::Sell => TradeSide::Sell,
+            },
+        });
+    }
+    Ok(out)
+}
+
+pub fn write_trades_cache(path: &str, trades: &[Trade]) -> Result<()> {
+    crate::report::write_csv(
+        path,
+        trades.iter().map(|t| TradeRow {
+            ts: t.ts.0,
+            price: t.price.0,
+            qty: t.qty.0,
+            side: match t.side {
+                TradeSide::Buy => SideCol::Buy,
+                TradeSide::Sell => SideCol::Sell,
+            },
+        }),
+    )
+}
+
+/// Кэши, записанные до стороны сделки, без колонки `side` — такие перекачиваются
+fn cache_has_sides(path: &str) -> bool {
+    csv::Reader::from_path(path)
+        .and_then(|mut r| r.headers().map(|h| h.iter().any(|c| c == "side")))
+        .unwrap_or(false)
+}
+
+/// Сделки `[start, end]` из CSV-кэша, если он покрывает символ и диапазон,
+/// иначе (или при `refresh`) — из дневных выгрузок Bybit.
+/// `cache = None` — путь по умолчанию (`data/cache/ETHUSDT_trades_...csv`).
+pub async fn load_trades(
+    symbol: &str,
+    range: (i64, i64),
+    cache: Option<&str>,
+    refresh: bool,
+) -> Result<Vec<Trade>> {
+    let (start_ms, end_ms) = range;
+    let path = cache_path_for(cache, false, symbol, "trades", range);
+    if !refresh && Path::new(&path).exists() {
+        // без meta (кэш записан вручную) сделкам нечем проверить покрытие — доверяем
+        let valid = CacheMeta::read(&path).is_none_or(|m| m.covers(symbol, "trades", range))
+            && cache_has_sides(&path);
+        if valid {
+            let mut trades =
+                read_trades_cache(&path).with_context(|| format!("read cache {} failed", path))?;
+            trades.retain(|t| t.ts.0 >= start_ms && t.ts.0 <= end_ms);
+            return Ok(trades);
+        }
+        println!(
+            "cache: {} does not cover {} trades, refreshing",
+            path, symbol
+        );
+    }
+
+    let out = download_trades(&BybitRest::new(), symbol, start_ms, end_ms).await?;
+    write_trades_cache(&path, &out).with_context(|| format!("write cache {} failed", path))?;
+    CacheMeta {
+        symbol: symbol.to_string(),
+        interval: "trades".to_string(),
+        start_ms,
+        end_ms,
+        exchange: Venue::Bybit,
+        known_gaps: Vec::new(),
+    }
+    .write(&path)
+    .with_context(|| format!("write cache meta for {} failed", path))?;
+    Ok(out)
+}
+
+/// Свечи интервала `interval_ms` из сделок (пустые интервалы пропускаются)
+pub fn candles_from_trades(trades: &[Trade], interval_ms: i64) -> Vec<Candle> {
+    let mut out: Vec<Candle> = Vec::new();
+    for t in trades {
+        let ts = t.ts.0 - t.ts.0.rem_euclid(interval_ms);
+        match out.last_mut() {
+            Some(c) if c.ts.0 == ts => {
+                c.high = Price(c.high.0.max(t.price.0));
+                c.low = Price(c.low.0.min(t.price.0));
+                c.close = t.price;
+                c.volume = Qty(c.volume.0 + t.qty.0);
+            }
+            _ => out.push(Candle {
+                ts: TimestampMs(ts),
+                open: t.price,
+                high: t.price,
+                low: t.price,
+                close: t.price,
+                volume: t.qty,
+            }),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(ts: i64, price: f64, qty: f64) -> Trade {
+        Trade {
+            ts: TimestampMs(ts),
+            price: Price(price),
+            qty: Qty(qty),
+            side: if ts % 2 == 0 {
+                TradeSide::Buy
+            } else {
+                TradeSide::Sell
+            },
+        }
+    }
+
+    #[test]
+    fn aggregates_trades_and_round_trips_cache() {
+        let trades = [
+            trade(1_000, 10.0, 1.0),
+            trade(30_000, 12.0, 0.5),
+            trade(59_999, 11.0, 2.0),
+            trade(180_000, 9.0, 1.0),
+        ];
+        let candles = candles_from_trades(&trades, 60_000);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].ts, TimestampMs(0));
+        assert_eq!(
+            (
+                candles[0].open,
+                candles[0].high,
+                candles[0].low,
+                candles[0].close
+            ),
+            (Price(10.0), Price(12.0), Price(10.0), Price(11.0))
+        );
+        assert_eq!(candles[0].volume, Qty(3.5));
+        assert_eq!(candles[1].ts, TimestampMs(180_000));
+
+        let path = std::env::temp_dir().join(format!("bt-trades-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_trades_cache(path, &trades).unwrap();
+        assert_eq!(read_trades_cache(path).unwrap(), trades);
+        std::fs::remove_file(path).unwrap();
+    }
+}
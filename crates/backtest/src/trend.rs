@@ -0,0 +1,551 @@
+use clap::ValueEnum;
+use core::types::{Money, Price, Qty};
+use execution::sim::ExecutionModel;
+use policy::trend_policy::{
+    TrendAction, TrendDecisionReason, TrendMode, TrendPolicyDecision, TrendPolicyInput,
+    TrendPolicyParams, trend_policy_decision,
+};
+use state_machine::trend_cause::TrendCause;
+use state_machine::trend_state::TrendState;
+use state_machine::trend_transition::trend_transition;
+use structure::atr::atr;
+use structure::bos::{BosState, BosTracker};
+use structure::candle::Candle;
+use structure::pullback::PullbackTracker;
+use structure::structure::detect_structure;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mm::SignalParams;
+use crate::report::{EquityRow, TrendRoundTripRow, TrendTradeRow};
+use crate::stats::{
+    CostBreakdown, Drawdown, Performance, ReturnStats, TradeStats, mean_count, mean_of,
+};
+
+/// Какие сигналы структуры нужны для входа поверх EMA-тренда
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryGate {
+    Trend,
+    TrendBos,
+    TrendBosPullback,
+}
+
+/// Список gate'ов через запятую: `trend,trend-bos,trend-bos-pullback`
+pub fn parse_gate_list(s: &str) -> anyhow::Result<Vec<EntryGate>> {
+    let mut out = Vec::new();
+    for raw in s.split(',') {
+        let v = raw.trim().to_ascii_lowercase();
+        let gate = match v.as_str() {
+            "trend" => EntryGate::Trend,
+            "trend-bos" => EntryGate::TrendBos,
+            "trend-bos-pullback" => EntryGate::TrendBosPullback,
+            _ => anyhow::bail!("bad entry gate: {}", raw.trim()),
+        };
+        out.push(gate);
+    }
+    if out.is_empty() {
+        anyhow::bail!("entry_gate_list cannot be empty");
+    }
+    Ok(out)
+}
+
+/// Параметры trend-стратегии (то, что перебирают sweep'ы)
+#[derive(Debug, Copy, Clone)]
+pub struct TrendParams {
+    pub ema_fast: usize,
+    pub ema_slow: usize,
+    pub atr_stop_mult: f64,
+    pub entry_gate: EntryGate,
+    pub min_trend_gap_bps: f64,
+    pub cooldown_bars: usize,
+    pub max_atr_pct: f64,
+}
+
+/// Условия прогона, общие для всех конфигов sweep'а
+#[derive(Debug, Copy, Clone)]
+pub struct TrendRunConfig {
+    pub initial_quote: f64,
+    pub exec: ExecutionModel,
+    pub force_close_at_end: bool,
+    /// Копить equity/trade строки (sweep'ам не нужны)
+    pub record: bool,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct TrendReport {
+    /// Исполнения: входы и выходы
+    pub trades: usize,
+    pub stop_exits: usize,
+    #[serde(skip, default = "flat")]
+    pub final_state: TrendState,
+    pub final_quote: f64,
+    pub final_base: f64,
+    pub final_equity: f64,
+    #[serde(flatten)]
+    pub perf: Performance,
+    pub costs: CostBreakdown,
+}
+
+fn flat() -> TrendState {
+    TrendState::Flat
+}
+
+impl TrendReport {
+    /// Среднее по прогонам одного конфига (символам); состояние — первого прогона
+    pub fn mean(items: &[Self]) -> Self {
+        let perf: Vec<Performance> = items.iter().map(|r| r.perf).collect();
+        let costs: Vec<CostBreakdown> = items.iter().map(|r| r.costs).collect();
+        Self {
+            trades: mean_count(items, |r| r.trades),
+            stop_exits: mean_count(items, |r| r.stop_exits),
+            final_state: items.first().map_or(TrendState::Flat, |r| r.final_state),
+            final_quote: mean_of(items, |r| r.final_quote),
+            final_base: mean_of(items, |r| r.final_base),
+            final_equity: mean_of(items, |r| r.final_equity),
+            perf: Performance::mean(&perf),
+            costs: CostBreakdown::mean(&costs),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrendRun {
+    pub report: TrendReport,
+    pub equity_rows: Vec<EquityRow>,
+    pub trade_rows: Vec<TrendTradeRow>,
+    pub round_trips: Vec<TrendRoundTripRow>,
+}
+
+/// Открытая позиция: вход и экстремумы цены с момента входа
+struct OpenTrade {
+    ts: i64,
+    price: f64,
+    qty: f64,
+    bars: usize,
+    low: f64,
+    high: f64,
+}
+
+impl OpenTrade {
+    fn on_bar(&mut self, c: &Candle) {
+        self.bars += 1;
+        self.low = self.low.min(c.low.0);
+        self.high = self.high.max(c.high.0);
+    }
+
+    fn close(self, ts: i64, price: f64, reason: String, pnl: f64) -> TrendRoundTripRow {
+        let pct = |p: f64| {
+            if self.price > 0.0 {
+                (p / self.price - 1.0) * 100.0
+            } else {
+                0.0
+            }
+        };
+        TrendRoundTripRow {
+            entry_ts: self.ts,
+            entry_price: self.price,
+            exit_ts: ts,
+            exit_price: price,
+            exit_reason: reason,
+            qty: self.qty,
+            pnl,
+            bars_held: self.bars,
+            mae_pct: pct(self.low).min(0.0),
+            mfe_pct: pct(self.high).max(0.0),
+        }
+    }
+}
+
+struct EmaCalc {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl EmaCalc {
+    fn new(period: usize) -> Self {
+        let p = period.max(1) as f64;
+        Self {
+            alpha: 2.0 / (p + 1.0),
+            value: None,
+        }
+    }
+
+    fn update(&mut self, x: f64) -> f64 {
+        match self.value {
+            Some(v) => {
+                let next = self.alpha * x + (1.0 - self.alpha) * v;
+                self.value = Some(next);
+                next
+            }
+            None => {
+                self.value = Some(x);
+                x
+            }
+        }
+    }
+}
+
+fn trend_mode_from_state(state: TrendState) -> TrendMode {
+    match state {
+        TrendState::Flat => TrendMode::Flat,
+        TrendState::Long => TrendMode::Long,
+    }
+}
+
+/// Long-only trend: вход по EMA-тренду (+ gate'ы структуры), выход по
+/// сигналу или ATR-стопу; исполнение по close свечи с taker-издержками.
+pub fn run_trend(candles: &[Candle], params: &TrendParams, cfg: TrendRunConfig) -> TrendRun {
+    let signal = SignalParams::default();
+    let exec = cfg.exec;
+    let window = params.ema_slow * 5;
+    let mut feed: Vec<Candle> = Vec::with_capacity(window + 8);
+    let mut ema_fast = EmaCalc::new(params.ema_fast);
+    let mut ema_slow = EmaCalc::new(params.ema_slow);
+
+    let mut trend_state = TrendState::Flat;
+    let mut quote = Money(cfg.initial_quote);
+    let mut base = Qty(0.0);
+    let mut entry_price: Option<Price> = None;
+    let mut entry_cost_quote: Option<f64> = None;
+    let mut bos = BosTracker::new();
+    let mut pullback = PullbackTracker::new();
+
+    let mut trades = 0usize;
+    let mut stop_exits = 0usize;
+    let mut stats = TradeStats::default();
+    let mut costs = CostBreakdown::default();
+    let mut drawdown = Drawdown::new(quote.0);
+    let mut returns = ReturnStats::default();
+    let mut entry_ts: Option<i64> = None;
+    let mut held_ms = 0_i64;
+    let mut traded = 0.0;
+    let mut bars_since_exit: usize = usize::MAX / 2;
+    let mut equity_rows = Vec::new();
+    let mut trade_rows = Vec::new();
+    let mut round_trips = Vec::new();
+    let mut open: Option<OpenTrade> = None;
+
+    for c in candles.iter().copied() {
+        bars_since_exit = bars_since_exit.saturating_add(1);
+        if let Some(t) = open.as_mut() {
+            t.on_bar(&c);
+        }
+        feed.push(c);
+        if feed.len() > window {
+            let excess = feed.len() - window;
+            feed.drain(0..excess);
+        }
+        let fast = ema_fast.update(c.close.0);
+        let slow = ema_slow.update(c.close.0);
+
+        let Some(atr) = atr(&feed) else {
+            continue;
+        };
+
+        let ms = detect_structure(&feed, signal.structure);
+        bos.on_candle_close(&c, &ms, atr, signal.bos);
+        if bos.state == BosState::Confirmed {
+            pullback.on_candle_close(&c, &bos, atr, signal.pullback);
+        } else {
+            pullback.reset();
+        }
+
+        let mut decision = trend_policy_decision(
+            trend_mode_from_state(trend_state),
+            TrendPolicyInput {
+                close: c.close,
+                atr,
+                ema_fast: Price(fast),
+                ema_slow: Price(slow),
+                position_qty: base,
+                entry_price,
+            },
+            TrendPolicyParams {
+                atr_stop_mult: params.atr_stop_mult,
+            },
+        );
+
+        if decision.action == TrendAction::EnterLong {
+            let bos_gate_ok = match params.entry_gate {
+                EntryGate::Trend => true,
+                EntryGate::TrendBos => bos.state == BosState::Confirmed,
+                EntryGate::TrendBosPullback => {
+                    bos.state == BosState::Confirmed && pullback.triggered
+                }
+            };
+            let trend_gap_bps = if c.close.0 > 0.0 {
+                ((fast - slow) / c.close.0) * 10_000.0
+            } else {
+                0.0
+            };
+            let trend_gap_ok = trend_gap_bps >= params.min_trend_gap_bps.max(0.0);
+            let cooldown_ok = bars_since_exit >= params.cooldown_bars;
+            let atr_pct = if c.close.0 > 0.0 {
+                100.0 * atr.0 / c.close.0
+            } else {
+                0.0
+            };
+            let atr_ok = atr_pct <= params.max_atr_pct.max(0.0);
+            let gate_ok = bos_gate_ok && trend_gap_ok && cooldown_ok && atr_ok;
+
+            if !gate_ok {
+                let next_mode = trend_mode_from_state(trend_state);
+                decision = TrendPolicyDecision {
+                    next_mode,
+                    action: match next_mode {
+                        TrendMode::Flat => TrendAction::HoldFlat,
+                        TrendMode::Long => TrendAction::HoldLong,
+                    },
+                    reason: TrendDecisionReason::NoSignal,
+                };
+            }
+        }
+
+        match decision.action {
+            TrendAction::EnterLong => {
+                if quote.0 > 0.0 {
+                    let qty = exec.buy_qty_for_quote(quote.0, c.close);
+                    if qty.0 > 0.0 {
+                        let fill = exec.buy_fill_price(c.close).0;
+                        let cost = exec.buy_cost(qty, c.close);
+                        costs.on_taker(qty.0, c.close.0, fill, cost - qty.0 * fill);
+                        traded += qty.0 * fill;
+                        quote = Money((quote.0 - cost).max(0.0));
+                        base = Qty(base.0 + qty.0);
+                        entry_price = Some(c.close);
+                        entry_cost_quote = Some(cost);
+                        entry_ts = Some(c.ts.0);
+                        trades += 1;
+                        if cfg.record {
+                            trade_rows.push(TrendTradeRow {
+                                ts: c.ts.0,
+                                side: "BUY".to_string(),
+                                reason: format!("{:?}", decision.reason),
+                                qty: qty.0,
+                                mid_price: c.close.0,
+                                fill_price: fill,
+                                quote_delta: -cost,
+                                trade_pnl: None,
+                            });
+                            open = Some(OpenTrade {
+                                ts: c.ts.0,
+                                price: fill,
+                                qty: qty.0,
+                                bars: 0,
+                                low: fill,
+                                high: fill,
+                            });
+                        }
+                    }
+                }
+                if let Ok(next) = trend_transition(trend_state, TrendCause::EntrySignal) {
+                    trend_state = next;
+                }
+            }
+            TrendAction::ExitLong => {
+                if base.0 > 0.0 {
+                    let fill = exec.sell_fill_price(c.close).0;
+                    let proceeds = exec.sell_proceeds(base, c.close);
+                    costs.on_taker(base.0, c.close.0, fill, base.0 * fill - proceeds);
+                    traded += base.0 * fill;
+                    let trade_pnl = entry_cost_quote.map(|cost| proceeds - cost);
+                    if let Some(pnl) = trade_pnl {
+                        stats.on_close(pnl);
+                    }
+                    if let Some(ts) = entry_ts.take() {
+                        held_ms += c.ts.0 - ts;
+                    }
+                    if cfg.record {
+                        trade_rows.push(TrendTradeRow {
+                            ts: c.ts.0,
+                            side: "SELL".to_string(),
+                            reason: format!("{:?}", decision.reason),
+                            qty: base.0,
+                            mid_price: c.close.0,
+                            fill_price: fill,
+                            quote_delta: proceeds,
+                            trade_pnl,
+                        });
+                        if let Some(t) = open.take() {
+                            round_trips.push(t.close(
+                                c.ts.0,
+                                fill,
+                                format!("{:?}", decision.reason),
+                                trade_pnl.unwrap_or(0.0),
+                            ));
+                        }
+                    }
+
+                    quote = Money(quote.0 + proceeds);
+                    base = Qty(0.0);
+                    entry_price = None;
+                    entry_cost_quote = None;
+                    bars_since_exit = 0;
+                    trades += 1;
+                }
+                let cause = match decision.reason {
+                    TrendDecisionReason::AtrStopHit => {
+                        stop_exits += 1;
+                        TrendCause::StopLossHit
+                    }
+                    TrendDecisionReason::InvalidLongOnlyInvariant => TrendCause::ForceFlat,
+                    _ => TrendCause::ExitSignal,
+                };
+                if let Ok(next) = trend_transition(trend_state, cause) {
+                    trend_state = next;
+                }
+            }
+            TrendAction::HoldFlat | TrendAction::HoldLong => {}
+        }
+
+        let equity = quote.0 + base.0 * c.close.0;
+        returns.on_bar(c.ts.0, equity, base.0 > 0.0);
+        if let Some(dd) = drawdown.update(c.ts.0, equity) {
+            if cfg.record {
+                equity_rows.push(EquityRow {
+                    ts: c.ts.0,
+                    close: c.close.0,
+                    mode: format!("{:?}", trend_state),
+                    quote: quote.0,
+                    base: base.0,
+                    cost_basis_quote: entry_cost_quote.unwrap_or(0.0),
+                    equity,
+                    drawdown_pct: dd * 100.0,
+                });
+            }
+        }
+    }
+
+    let final_mark = feed.last().map(|c| c.close).unwrap_or(Price(0.0));
+    let final_ts = candles.last().map(|c| c.ts.0).unwrap_or(0);
+    if cfg.force_close_at_end && base.0 > 0.0 {
+        let fill = exec.sell_fill_price(final_mark).0;
+        let proceeds = exec.sell_proceeds(base, final_mark);
+        costs.on_taker(base.0, final_mark.0, fill, base.0 * fill - proceeds);
+        traded += base.0 * fill;
+        let trade_pnl = entry_cost_quote.map(|cost| proceeds - cost);
+        if let Some(pnl) = trade_pnl {
+            stats.on_close(pnl);
+        }
+        if let Some(ts) = entry_ts {
+            held_ms += final_ts - ts;
+        }
+        if cfg.record {
+            trade_rows.push(TrendTradeRow {
+                ts: final_ts,
+                side: "SELL".to_string(),
+                reason: "ForceCloseAtEnd".to_string(),
+                qty: base.0,
+                mid_price: final_mark.0,
+                fill_price: fill,
+                quote_delta: proceeds,
+                trade_pnl,
+            });
+            if let Some(t) = open.take() {
+                round_trips.push(t.close(
+                    final_ts,
+                    fill,
+                    "ForceCloseAtEnd".to_string(),
+                    trade_pnl.unwrap_or(0.0),
+                ));
+            }
+        }
+        quote = Money(quote.0 + proceeds);
+        base = Qty(0.0);
+        trades += 1;
+        if let Ok(next) = trend_transition(trend_state, TrendCause::ForceFlat) {
+            trend_state = next;
+        }
+    }
+
+    let final_equity = quote.0 + base.0 * final_mark.0;
+    let avg_trade_ms = if stats.closed > 0 {
+        held_ms as f64 / stats.closed as f64
+    } else {
+        0.0
+    };
+
+    TrendRun {
+        report: TrendReport {
+            trades,
+            stop_exits,
+            final_state: trend_state,
+            final_quote: quote.0,
+            final_base: base.0,
+            final_equity,
+            perf: Performance::new(&stats, &drawdown, cfg.initial_quote, final_equity)
+                .with_risk(&returns, avg_trade_ms)
+                .with_turnover(traded, cfg.initial_quote),
+            costs,
+        },
+        equity_rows,
+        trade_rows,
+        round_trips,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::TimestampMs;
+
+    fn candle(i: i64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(i * 60_000),
+            open: Price(close),
+            high: Price(close * 1.001),
+            low: Price(close * 0.999),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn uptrend_then_crash_closes_one_trade() {
+        let mut candles = Vec::new();
+        for i in 0..60 {
+            candles.push(candle(i, 100.0 + i as f64));
+        }
+        for i in 60..80 {
+            candles.push(candle(i, 160.0 - 5.0 * (i - 60) as f64));
+        }
+        let params = TrendParams {
+            ema_fast: 3,
+            ema_slow: 10,
+            atr_stop_mult: 2.5,
+            entry_gate: EntryGate::Trend,
+            min_trend_gap_bps: 0.0,
+            cooldown_bars: 0,
+            max_atr_pct: 100.0,
+        };
+        let cfg = TrendRunConfig {
+            initial_quote: 1000.0,
+            exec: ExecutionModel {
+                fee_bps: 10.0,
+                spread_bps: 8.0,
+                slippage_bps: 2.0,
+            },
+            force_close_at_end: true,
+            record: true,
+        };
+        let run = run_trend(&candles, &params, cfg);
+        let r = run.report;
+
+        assert!(r.trades >= 2);
+        assert_eq!(r.final_base, 0.0);
+        assert_eq!(r.final_state, TrendState::Flat);
+        assert_eq!(run.trade_rows.len(), r.trades);
+        assert_eq!(run.round_trips.len(), r.perf.closed_trades);
+        for t in &run.round_trips {
+            assert!(t.exit_ts > t.entry_ts && t.bars_held > 0);
+            assert!(t.mae_pct <= 0.0 && t.mfe_pct >= 0.0);
+            assert!(t.exit_price >= t.entry_price * (1.0 + t.mae_pct / 100.0) - 1e-9);
+        }
+        let total: f64 = run.round_trips.iter().map(|t| t.pnl).sum();
+        assert!((total - (r.perf.gross_profit - r.perf.gross_loss)).abs() < 1e-9);
+        assert!(r.perf.closed_trades >= 1);
+        assert!(r.costs.taker_fees > 0.0 && r.costs.spread_slippage > 0.0);
+        assert!((r.perf.pnl - (r.final_equity - 1000.0)).abs() < 1e-9);
+    }
+}
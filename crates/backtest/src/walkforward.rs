@@ -0,0 +1,302 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use structure::candle::Candle;
+
+use crate::stats::{Performance, RankBy};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Окно walk-forward: обучение `train`, проверка `test` сразу после него (мс, включительно)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WfWindow {
+    pub index: usize,
+    pub train: (i64, i64),
+    pub test: (i64, i64),
+}
+
+/// Нарезка периода на окна: обучение `train`, проверка `test`, сдвиг `step`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkForward {
+    train_ms: i64,
+    test_ms: i64,
+    step_ms: i64,
+}
+
+impl WalkForward {
+    /// Из `--wf-*-days`: без `train` режим выключен; шаг по умолчанию — длина test окна
+    pub fn from_days(
+        train_days: Option<u32>,
+        test_days: Option<u32>,
+        step_days: Option<u32>,
+    ) -> Result<Option<Self>> {
+        let Some(train_days) = train_days else {
+            if test_days.is_some() || step_days.is_some() {
+                anyhow::bail!("walk-forward needs --wf-train-days");
+            }
+            return Ok(None);
+        };
+        let Some(test_days) = test_days else {
+            anyhow::bail!("walk-forward needs --wf-test-days");
+        };
+        let step_days = step_days.unwrap_or(test_days);
+        if train_days == 0 || test_days == 0 || step_days == 0 {
+            anyhow::bail!("walk-forward train/test/step days must be > 0");
+        }
+        Ok(Some(Self {
+            train_ms: i64::from(train_days) * DAY_MS,
+            test_ms: i64::from(test_days) * DAY_MS,
+            step_ms: i64::from(step_days) * DAY_MS,
+        }))
+    }
+
+    /// Окна внутри `[start, end]`; неполное последнее test окно отбрасывается
+    pub fn windows(&self, (start_ms, end_ms): (i64, i64)) -> Result<Vec<WfWindow>> {
+        let mut out = Vec::new();
+        let mut from = start_ms;
+        while from + self.train_ms + self.test_ms - 1 <= end_ms {
+            let test_from = from + self.train_ms;
+            out.push(WfWindow {
+                index: out.len() + 1,
+                train: (from, test_from - 1),
+                test: (test_from, test_from + self.test_ms - 1),
+            });
+            from += self.step_ms;
+        }
+        if out.is_empty() {
+            anyhow::bail!("period is shorter than one walk-forward window (train + test)");
+        }
+        Ok(out)
+    }
+}
+
+/// Свечи с ts в `[from, to]`
+pub fn slice_ms(candles: &[Candle], (from, to): (i64, i64)) -> &[Candle] {
+    let lo = candles.partition_point(|c| c.ts.0 < from);
+    let hi = candles.partition_point(|c| c.ts.0 <= to);
+    &candles[lo..hi.max(lo)]
+}
+
+/// Out-of-sample итог по test окнам
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct OosSummary {
+    pub windows: usize,
+    pub positive_windows: usize,
+    pub mean_roi_pct: f64,
+    /// ROI test окон, сложенный подряд (капитал переходит из окна в окно)
+    pub compound_roi_pct: f64,
+    /// Худшая просадка внутри test окна
+    pub worst_drawdown_pct: f64,
+    pub mean_sharpe: f64,
+}
+
+impl OosSummary {
+    pub fn new<'a>(perfs: impl IntoIterator<Item = &'a Performance>) -> Self {
+        let mut out = Self::default();
+        let mut growth = 1.0;
+        for p in perfs {
+            out.windows += 1;
+            out.positive_windows += usize::from(p.roi_pct > 0.0);
+            out.mean_roi_pct += p.roi_pct;
+            out.mean_sharpe += p.sharpe;
+            out.worst_drawdown_pct = out.worst_drawdown_pct.max(p.max_drawdown_pct);
+            growth *= 1.0 + p.roi_pct / 100.0;
+        }
+        if out.windows > 0 {
+            out.mean_roi_pct /= out.windows as f64;
+            out.mean_sharpe /= out.windows as f64;
+        }
+        out.compound_roi_pct = (growth - 1.0) * 100.0;
+        out
+    }
+}
+
+/// Строка по окну: какой конфиг выбран на обучении и как он прошёл проверку
+#[derive(Debug, Clone, Serialize)]
+pub struct WfWindowRow {
+    pub window: usize,
+    pub train_start_ms: i64,
+    pub train_end_ms: i64,
+    pub test_start_ms: i64,
+    pub test_end_ms: i64,
+    pub config: String,
+    pub train_roi_pct: f64,
+    pub test_roi_pct: f64,
+    pub test_max_drawdown_pct: f64,
+    pub test_sharpe: f64,
+}
+
+/// Строка по конфигу: out-of-sample метрики на всех test окнах
+#[derive(Debug, Clone, Serialize)]
+pub struct WfConfigRow {
+    pub rank: usize,
+    pub config: String,
+    /// В скольких окнах конфиг был лучшим на обучении
+    pub selected: usize,
+    pub windows: usize,
+    pub positive_windows: usize,
+    pub mean_oos_roi_pct: f64,
+    pub compound_oos_roi_pct: f64,
+    pub worst_oos_drawdown_pct: f64,
+    pub mean_oos_sharpe: f64,
+}
+
+impl WfConfigRow {
+    fn new(config: &str, selected: usize, oos: OosSummary) -> Self {
+        Self {
+            rank: 0,
+            config: config.to_string(),
+            selected,
+            windows: oos.windows,
+            positive_windows: oos.positive_windows,
+            mean_oos_roi_pct: oos.mean_roi_pct,
+            compound_oos_roi_pct: oos.compound_roi_pct,
+            worst_oos_drawdown_pct: oos.worst_drawdown_pct,
+            mean_oos_sharpe: oos.mean_sharpe,
+        }
+    }
+}
+
+/// Итог walk-forward: в каждом окне на обучении выбирается лучший конфиг
+#[derive(Debug, Clone)]
+pub struct WfResult {
+    /// Индекс выбранного конфига по окнам
+    pub selected: Vec<usize>,
+    /// Выбранные конфиги на своих test окнах подряд — метрики стратегии с переобучением
+    pub chained: OosSummary,
+    pub window_rows: Vec<WfWindowRow>,
+    /// По убыванию сложенного OOS ROI
+    pub config_rows: Vec<WfConfigRow>,
+}
+
+impl WfResult {
+    /// `train[w][c]` / `test[w][c]` — метрики конфига `c` в окне `w`, `labels[c]` — его описание
+    pub fn new(
+        windows: &[WfWindow],
+        labels: &[String],
+        train: &[Vec<Performance>],
+        test: &[Vec<Performance>],
+        by: RankBy,
+    ) -> Self {
+        let selected: Vec<usize> = train
+            .iter()
+            .map(|perfs| {
+                (0..perfs.len())
+                    .min_by(|&a, &b| perfs[a].rank_cmp(&perfs[b], by))
+                    .unwrap_or(0)
+            })
+            .collect();
+        let chained = OosSummary::new(selected.iter().enumerate().map(|(w, &c)| &test[w][c]));
+        let window_rows = windows
+            .iter()
+            .zip(&selected)
+            .enumerate()
+            .map(|(w, (win, &c))| WfWindowRow {
+                window: win.index,
+                train_start_ms: win.train.0,
+                train_end_ms: win.train.1,
+                test_start_ms: win.test.0,
+                test_end_ms: win.test.1,
+                config: labels[c].clone(),
+                train_roi_pct: train[w][c].roi_pct,
+                test_roi_pct: test[w][c].roi_pct,
+                test_max_drawdown_pct: test[w][c].max_drawdown_pct,
+                test_sharpe: test[w][c].sharpe,
+            })
+            .collect();
+        let mut config_rows: Vec<WfConfigRow> = labels
+            .iter()
+            .enumerate()
+            .map(|(c, label)| {
+                WfConfigRow::new(
+                    label,
+                    selected.iter().filter(|&&s| s == c).count(),
+                    OosSummary::new(test.iter().map(|perfs| &perfs[c])),
+                )
+            })
+            .collect();
+        config_rows.sort_by(|a, b| b.compound_oos_roi_pct.total_cmp(&a.compound_oos_roi_pct));
+        for (idx, row) in config_rows.iter_mut().enumerate() {
+            row.rank = idx + 1;
+        }
+        Self {
+            selected,
+            chained,
+            window_rows,
+            config_rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Price, Qty, TimestampMs};
+
+    fn perf(roi_pct: f64, max_drawdown_pct: f64) -> Performance {
+        Performance {
+            roi_pct,
+            max_drawdown_pct,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reselects_config_per_window() {
+        assert_eq!(WalkForward::from_days(None, None, None).unwrap(), None);
+        assert!(WalkForward::from_days(None, Some(5), None).is_err());
+        assert!(WalkForward::from_days(Some(10), None, None).is_err());
+
+        // 30 дней, обучение 10, проверка 5, шаг по умолчанию 5 → окна с 0, 5, 10, 15 дня
+        let wf = WalkForward::from_days(Some(10), Some(5), None)
+            .unwrap()
+            .unwrap();
+        let windows = wf.windows((0, 30 * DAY_MS - 1)).unwrap();
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0].train, (0, 10 * DAY_MS - 1));
+        assert_eq!(windows[0].test, (10 * DAY_MS, 15 * DAY_MS - 1));
+        assert_eq!(windows[3].test.1, 30 * DAY_MS - 1);
+        assert!(wf.windows((0, 14 * DAY_MS)).is_err());
+
+        let candles: Vec<Candle> = (0..10)
+            .map(|i| Candle {
+                ts: TimestampMs(i * 100),
+                open: Price(1.0),
+                high: Price(1.0),
+                low: Price(1.0),
+                close: Price(1.0),
+                volume: Qty(1.0),
+            })
+            .collect();
+        assert_eq!(slice_ms(&candles, (150, 400)).len(), 3);
+        assert!(slice_ms(&candles, (2000, 3000)).is_empty());
+
+        // конфиг 0 лучший на обучении в первом окне, конфиг 1 — во втором
+        let windows = &windows[..2];
+        let labels = vec!["a".to_string(), "b".to_string()];
+        let train = vec![
+            vec![perf(5.0, 1.0), perf(1.0, 1.0)],
+            vec![perf(1.0, 1.0), perf(8.0, 1.0)],
+        ];
+        let test = vec![
+            vec![perf(10.0, 4.0), perf(-5.0, 9.0)],
+            vec![perf(2.0, 1.0), perf(-10.0, 12.0)],
+        ];
+        let result = WfResult::new(windows, &labels, &train, &test, RankBy::Roi);
+        assert_eq!(result.selected, vec![0, 1]);
+        assert_eq!(result.chained.windows, 2);
+        assert_eq!(result.chained.positive_windows, 1);
+        assert!((result.chained.compound_roi_pct - (1.1 * 0.9 - 1.0) * 100.0).abs() < 1e-9);
+        assert_eq!(result.chained.worst_drawdown_pct, 12.0);
+        assert_eq!(result.window_rows[1].config, "b");
+        assert_eq!(result.window_rows[1].test_roi_pct, -10.0);
+
+        let best = &result.config_rows[0];
+        assert_eq!(
+            (best.rank, best.config.as_str(), best.selected),
+            (1, "a", 1)
+        );
+        assert_eq!(best.mean_oos_roi_pct, 6.0);
+        assert_eq!(best.worst_oos_drawdown_pct, 4.0);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod orderbook;
+pub mod private;
 pub mod rest;
 pub mod ws;
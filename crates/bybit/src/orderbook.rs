@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use core::types::{Price, Qty};
+
+/// Глубина топика `orderbook.<depth>.<symbol>`
+pub const BOOK_DEPTH: u32 = 50;
+
+/// Сообщение топика книги: `snapshot` заменяет книгу, `delta` правит уровни
+#[derive(Debug, Deserialize)]
+pub(crate) struct BookMessage {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub data: BookData,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BookData {
+    #[serde(rename = "b", default)]
+    pub bids: Vec<[String; 2]>,
+    #[serde(rename = "a", default)]
+    pub asks: Vec<[String; 2]>,
+    #[serde(rename = "u")]
+    pub update_id: u64,
+}
+
+/// Локальная L2-книга одного символа.
+/// Уровни хранятся по битам цены: для положительных f64 их порядок совпадает с порядком чисел.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<u64, f64>,
+    asks: BTreeMap<u64, f64>,
+    /// `u` последнего применённого сообщения, 0 — snapshot ещё не было
+    pub update_id: u64,
+}
+
+impl OrderBook {
+    /// Применяет сообщение топика; `false` — delta без snapshot'а, книга не тронута.
+    /// `u == 1` биржа шлёт после рестарта сервиса — это тоже snapshot.
+    pub(crate) fn apply(&mut self, msg: &BookMessage) -> bool {
+        let snapshot = msg.kind == "snapshot" || msg.data.update_id == 1;
+        if snapshot {
+            self.bids.clear();
+            self.asks.clear();
+        } else if self.update_id == 0 {
+            return false;
+        }
+        update_levels(&mut self.bids, &msg.data.bids);
+        update_levels(&mut self.asks, &msg.data.asks);
+        self.update_id = msg.data.update_id;
+        true
+    }
+
+    pub fn best_bid(&self) -> Option<(Price, Qty)> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(p, q)| (Price(f64::from_bits(*p)), Qty(*q)))
+    }
+
+    pub fn best_ask(&self) -> Option<(Price, Qty)> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(p, q)| (Price(f64::from_bits(*p)), Qty(*q)))
+    }
+
+    /// Лучшие bid/ask; `None`, пока одна из сторон пуста или книга пересечена
+    pub fn top(&self) -> Option<(Price, Price)> {
+        let (bid, _) = self.best_bid()?;
+        let (ask, _) = self.best_ask()?;
+        (bid.0 < ask.0).then_some((bid, ask))
+    }
+}
+
+/// Объём 0 удаляет уровень
+fn update_levels(side: &mut BTreeMap<u64, f64>, levels: &[[String; 2]]) {
+    for [price, qty] in levels {
+        let (Ok(price), Ok(qty)) = (price.parse::<f64>(), qty.parse::<f64>()) else {
+            continue;
+        };
+        if price <= 0.0 {
+            continue;
+        }
+        if qty > 0.0 {
+            side.insert(price.to_bits(), qty);
+        } else {
+            side.remove(&price.to_bits());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(text: &str) -> BookMessage {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn applies_snapshot_and_deltas() {
+        let mut book = OrderBook::default();
+
+        // delta до snapshot'а игнорируется
+        assert!(!book.apply(&msg(
+            r#"{"type":"delta","data":{"b":[["99","1"]],"a":[],"u":5}}"#
+        )));
+        assert_eq!(book.top(), None);
+
+        assert!(book.apply(&msg(
+            r#"{"type":"snapshot","data":{"s":"ETHUSDT","b":[["100.5","2"],["100","1"]],"a":[["101","3"],["101.5","1"]],"u":10}}"#
+        )));
+        assert_eq!(book.top(), Some((Price(100.5), Price(101.0))));
+
+        // лучший bid снят, ask улучшен
+        assert!(book.apply(&msg(
+            r#"{"type":"delta","data":{"b":[["100.5","0"]],"a":[["100.8","0.5"]],"u":11}}"#
+        )));
+        assert_eq!(book.top(), Some((Price(100.0), Price(100.8))));
+        assert_eq!(book.best_ask(), Some((Price(100.8), Qty(0.5))));
+        assert_eq!(book.update_id, 11);
+
+        // u == 1 — новый snapshot после рестарта биржи
+        assert!(book.apply(&msg(
+            r#"{"type":"delta","data":{"b":[["50","1"]],"a":[["51","1"]],"u":1}}"#
+        )));
+        assert_eq!(book.top(), Some((Price(50.0), Price(51.0))));
+    }
+}
@@ -0,0 +1,698 @@
+//! Signed REST Bybit v5 (spot): ордера, исполнения, баланс unified-аккаунта
+//! и правила инструмента одного символа.
+//!
+//! Подпись — HMAC-SHA256 секретом от `timestamp + api_key + recv_window + payload`,
+//! где payload — query string для GET и JSON-тело для POST.
+
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow, bail};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use sha2::Sha256;
+
+use core::types::{Money, Price, Qty, TimestampMs};
+use execution::orders::{Fill, LimitStatus, LiveOrder, NewOrder};
+use execution::traits::OrderGateway;
+use mm::grid::Side;
+
+use crate::rest::{InstrumentInfo, RawInstrument, num, num_or_zero};
+
+pub const MAINNET_URL: &str = "https://api.bybit.com";
+pub const TESTNET_URL: &str = "https://api-testnet.bybit.com";
+pub const API_KEY_ENV: &str = "BYBIT_API_KEY";
+pub const API_SECRET_ENV: &str = "BYBIT_API_SECRET";
+/// Spot: не больше 10 ордеров в одном batch-запросе
+pub const BATCH_LIMIT: usize = 10;
+const BATCH_PLACE_PATH: &str = "/v5/order/create-batch";
+const BATCH_CANCEL_PATH: &str = "/v5/order/cancel-batch";
+
+/// Окно, в котором биржа принимает запрос по `X-BAPI-TIMESTAMP`
+const RECV_WINDOW_MS: u64 = 5_000;
+/// Страница исполнений (максимум Bybit); дальше — по `nextPageCursor`
+const EXECUTIONS_LIMIT: u16 = 100;
+/// Страница открытых ордеров (максимум Bybit)
+const OPEN_ORDERS_LIMIT: u16 = 50;
+/// Страниц одного списка не больше: защита от курсора, который не кончается
+const MAX_PAGES: usize = 100;
+const ACCOUNT_TYPE: &str = "UNIFIED";
+
+/// Балансы пары в unified-аккаунте (с учётом заблокированного в ордерах)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletBalance {
+    /// Эквити всего аккаунта в USD
+    pub total_equity: Money,
+    pub base: Qty,
+    pub base_locked: Qty,
+    pub quote: Money,
+    pub quote_locked: Money,
+}
+
+#[derive(Clone)]
+pub struct BybitPrivate {
+    client: reqwest::Client,
+    base: String,
+    api_key: String,
+    secret: String,
+    symbol: String,
+    /// Лимит из заголовков последнего ответа (общий у клонов клиента)
+    limit: Arc<Mutex<Option<LimitStatus>>>,
+}
+
+impl BybitPrivate {
+    pub fn new(base: &str, api_key: &str, secret: &str, symbol: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base: base.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            secret: secret.to_string(),
+            symbol: symbol.to_string(),
+            limit: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Ключи из `BYBIT_API_KEY` / `BYBIT_API_SECRET`
+    pub fn from_env(base: &str, symbol: &str) -> Result<Self> {
+        let key =
+            std::env::var(API_KEY_ENV).with_context(|| format!("{} is not set", API_KEY_ENV))?;
+        let secret = std::env::var(API_SECRET_ENV)
+            .with_context(|| format!("{} is not set", API_SECRET_ENV))?;
+        Ok(Self::new(base, &key, &secret, symbol))
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Балансы `base_coin` / `quote_coin`
+    pub async fn wallet_balance(&self, base_coin: &str, quote_coin: &str) -> Result<WalletBalance> {
+        let resp: ListResult<RawWallet> = self
+            .get(
+                "/v5/account/wallet-balance",
+                &[("accountType", ACCOUNT_TYPE.into())],
+            )
+            .await?;
+        resp.list
+            .into_iter()
+            .find(|w| w.account_type == ACCOUNT_TYPE)
+            .with_context(|| format!("no {} wallet", ACCOUNT_TYPE))?
+            .balance(base_coin, quote_coin)
+    }
+
+    /// Тик цены, шаг и минимумы объёма символа
+    pub async fn instrument_info(&self) -> Result<InstrumentInfo> {
+        let resp: ListResult<RawInstrument> = self
+            .get(
+                "/v5/market/instruments-info",
+                &[("category", "spot".into()), ("symbol", self.symbol.clone())],
+            )
+            .await?;
+        resp.list
+            .into_iter()
+            .next()
+            .with_context(|| format!("unknown symbol {}", self.symbol))?
+            .into_info()
+    }
+
+    /// Сообщение авторизации private WS: подпись `GET/realtime{expires}`
+    pub(crate) fn ws_auth(&self, expires_ms: i64) -> Value {
+        json!({
+            "op": "auth",
+            "args": [
+                self.api_key,
+                expires_ms,
+                self.hmac_hex(&format!("GET/realtime{}", expires_ms)),
+            ],
+        })
+    }
+
+    fn sign(&self, ts: i64, payload: &str) -> String {
+        self.hmac_hex(&format!(
+            "{}{}{}{}",
+            ts, self.api_key, RECV_WINDOW_MS, payload
+        ))
+    }
+
+    fn hmac_hex(&self, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("hmac accepts any key length");
+        mac.update(payload.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let qs = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+        let req = self.client.get(format!("{}{}?{}", self.base, path, qs));
+        self.send(path, req, &qs).await
+    }
+
+    /// Все страницы списка по `cursor`
+    async fn get_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Vec<T>> {
+        collect_pages(path, |cursor| {
+            let mut query = query.to_vec();
+            if let Some(cursor) = cursor {
+                query.push(("cursor", cursor));
+            }
+            async move { self.get(path, &query).await }
+        })
+        .await
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: Value) -> Result<T> {
+        let resp = self.post_envelope(path, body).await?;
+        serde_json::from_value(resp.result).with_context(|| format!("bybit {}: bad result", path))
+    }
+
+    async fn post_envelope(&self, path: &str, body: Value) -> Result<Envelope> {
+        let body = body.to_string();
+        let req = self
+            .client
+            .post(format!("{}{}", self.base, path))
+            .header("Content-Type", "application/json")
+            .body(body.clone());
+        self.send_envelope(path, req, &body).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        req: reqwest::RequestBuilder,
+        payload: &str,
+    ) -> Result<T> {
+        let resp = self.send_envelope(path, req, payload).await?;
+        serde_json::from_value(resp.result).with_context(|| format!("bybit {}: bad result", path))
+    }
+
+    async fn send_envelope(
+        &self,
+        path: &str,
+        req: reqwest::RequestBuilder,
+        payload: &str,
+    ) -> Result<Envelope> {
+        let ts = now_ms();
+        let resp = req
+            .header("X-BAPI-API-KEY", &self.api_key)
+            .header("X-BAPI-TIMESTAMP", ts.to_string())
+            .header("X-BAPI-RECV-WINDOW", RECV_WINDOW_MS.to_string())
+            .header("X-BAPI-SIGN", self.sign(ts, payload))
+            .send()
+            .await?;
+        // заголовки лимита есть и в ответе с отказом
+        if let Some(status) = limit_status(resp.headers()) {
+            *self.limit.lock().unwrap_or_else(|e| e.into_inner()) = Some(status);
+        }
+        let resp: Envelope = resp.error_for_status()?.json().await?;
+        if resp.ret_code != 0 {
+            bail!("bybit {}: {} {}", path, resp.ret_code, resp.ret_msg);
+        }
+        Ok(resp)
+    }
+
+    /// Post-only лимитка символа — элемент `request` batch'а и тело одиночного запроса
+    fn order_body(&self, order: &NewOrder) -> Value {
+        json!({
+            "symbol": self.symbol,
+            "side": side_name(order.side),
+            "orderType": "Limit",
+            "timeInForce": "PostOnly",
+            "price": order.price.0.to_string(),
+            "qty": order.qty.0.to_string(),
+            "orderLinkId": order.link_id,
+        })
+    }
+}
+
+impl OrderGateway for BybitPrivate {
+    async fn open_orders(&self) -> Result<Vec<LiveOrder>> {
+        let orders: Vec<RawOrder> = self
+            .get_all(
+                "/v5/order/realtime",
+                &[
+                    ("category", "spot".into()),
+                    ("symbol", self.symbol.clone()),
+                    ("limit", OPEN_ORDERS_LIMIT.to_string()),
+                ],
+            )
+            .await?;
+        orders.into_iter().map(RawOrder::into_order).collect()
+    }
+
+    async fn place(&self, order: &NewOrder) -> Result<String> {
+        let mut body = self.order_body(order);
+        body["category"] = "spot".into();
+        let resp: OrderId = self.post("/v5/order/create", body).await?;
+        Ok(resp.order_id)
+    }
+
+    async fn market_sell(&self, link_id: &str, qty: Qty) -> Result<String> {
+        let resp: OrderId = self
+            .post(
+                "/v5/order/create",
+                json!({
+                    "category": "spot",
+                    "symbol": self.symbol,
+                    "side": "Sell",
+                    "orderType": "Market",
+                    "marketUnit": "baseCoin",
+                    "qty": qty.0.to_string(),
+                    "orderLinkId": link_id,
+                }),
+            )
+            .await?;
+        Ok(resp.order_id)
+    }
+
+    async fn cancel(&self, order_id: &str) -> Result<()> {
+        let _: OrderId = self
+            .post(
+                "/v5/order/cancel",
+                json!({"category": "spot", "symbol": self.symbol, "orderId": order_id}),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn place_batch(&self, orders: &[NewOrder]) -> Result<Vec<Result<String>>> {
+        let request: Vec<Value> = orders.iter().map(|o| self.order_body(o)).collect();
+        let resp = self
+            .post_envelope(
+                BATCH_PLACE_PATH,
+                json!({"category": "spot", "request": request}),
+            )
+            .await?;
+        let placed: ListResult<OrderId> = serde_json::from_value(resp.result)
+            .with_context(|| format!("bybit {}: bad result", BATCH_PLACE_PATH))?;
+        Ok(batch_items(
+            BATCH_PLACE_PATH,
+            &resp.ret_ext_info,
+            orders.len(),
+            |i| {
+                placed
+                    .list
+                    .get(i)
+                    .map(|o| o.order_id.clone())
+                    .filter(|id| !id.is_empty())
+            },
+        ))
+    }
+
+    async fn cancel_batch(&self, order_ids: &[String]) -> Result<Vec<Result<()>>> {
+        let request: Vec<Value> = order_ids
+            .iter()
+            .map(|id| json!({"symbol": self.symbol, "orderId": id}))
+            .collect();
+        let resp = self
+            .post_envelope(
+                BATCH_CANCEL_PATH,
+                json!({"category": "spot", "request": request}),
+            )
+            .await?;
+        Ok(batch_items(
+            BATCH_CANCEL_PATH,
+            &resp.ret_ext_info,
+            order_ids.len(),
+            |_| Some(()),
+        ))
+    }
+
+    fn limit_status(&self) -> Option<LimitStatus> {
+        *self.limit.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn fills_since(&self, since: TimestampMs) -> Result<Vec<Fill>> {
+        let executions: Vec<RawExecution> = self
+            .get_all(
+                "/v5/execution/list",
+                &[
+                    ("category", "spot".into()),
+                    ("symbol", self.symbol.clone()),
+                    ("startTime", since.0.to_string()),
+                    ("limit", EXECUTIONS_LIMIT.to_string()),
+                ],
+            )
+            .await?;
+        let mut out = executions
+            .into_iter()
+            .map(|e| e.into_fill(&self.symbol))
+            .collect::<Result<Vec<_>>>()?;
+        out.sort_by_key(|f| f.ts);
+        Ok(out)
+    }
+}
+
+/// Страницы списка подряд, пока `nextPageCursor` не пуст; `fetch` получает курсор
+/// (`None` — первая страница)
+async fn collect_pages<T, F, Fut>(path: &str, mut fetch: F) -> Result<Vec<T>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<ListResult<T>>>,
+{
+    let mut out = Vec::new();
+    let mut cursor = None;
+    for _ in 0..MAX_PAGES {
+        let page = fetch(cursor.take()).await?;
+        out.extend(page.list);
+        if page.next_page_cursor.is_empty() {
+            return Ok(out);
+        }
+        cursor = Some(page.next_page_cursor);
+    }
+    bail!("bybit {}: more than {} pages", path, MAX_PAGES)
+}
+
+/// Итог batch-запроса по элементам: ненулевой `code` из `retExtInfo.list` — отказ,
+/// иначе `ok(i)`
+fn batch_items<T>(
+    path: &str,
+    ext: &Value,
+    n: usize,
+    ok: impl Fn(usize) -> Option<T>,
+) -> Vec<Result<T>> {
+    (0..n)
+        .map(|i| {
+            let item = &ext["list"][i];
+            let code = item["code"].as_i64().unwrap_or(0);
+            if code != 0 {
+                return Err(anyhow!(
+                    "bybit {}: {} {}",
+                    path,
+                    code,
+                    item["msg"].as_str().unwrap_or_default()
+                ));
+            }
+            ok(i).with_context(|| format!("bybit {}: no result for item {}", path, i))
+        })
+        .collect()
+}
+
+/// `X-Bapi-Limit-Status` (осталось запросов в окне) и `X-Bapi-Limit-Reset-Timestamp`
+fn limit_status(headers: &reqwest::header::HeaderMap) -> Option<LimitStatus> {
+    let get = |name: &str| headers.get(name)?.to_str().ok()?.parse::<i64>().ok();
+    Some(LimitStatus {
+        remaining: u32::try_from(get("x-bapi-limit-status")?).ok()?,
+        reset_at: TimestampMs(get("x-bapi-limit-reset-timestamp")?),
+    })
+}
+
+pub(crate) fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn side(s: &str) -> Result<Side> {
+    match s {
+        "Buy" => Ok(Side::Buy),
+        "Sell" => Ok(Side::Sell),
+        other => bail!("unknown side {:?}", other),
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "Buy",
+        Side::Sell => "Sell",
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    ret_code: i64,
+    ret_msg: String,
+    #[serde(default)]
+    result: Value,
+    /// У batch-запросов — коды по каждому элементу
+    #[serde(default)]
+    ret_ext_info: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListResult<T> {
+    list: Vec<T>,
+    /// Пусто — последняя страница
+    #[serde(default)]
+    next_page_cursor: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderId {
+    order_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawOrder {
+    #[serde(default)]
+    pub(crate) category: String,
+    #[serde(default)]
+    pub(crate) symbol: String,
+    #[serde(default)]
+    pub(crate) order_status: String,
+    order_id: String,
+    #[serde(default)]
+    order_link_id: String,
+    side: String,
+    price: String,
+    qty: String,
+    cum_exec_qty: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawExecution {
+    #[serde(default)]
+    pub(crate) category: String,
+    #[serde(default)]
+    pub(crate) symbol: String,
+    exec_id: String,
+    order_id: String,
+    side: String,
+    exec_price: String,
+    exec_qty: String,
+    exec_fee: String,
+    #[serde(default)]
+    fee_currency: String,
+    exec_time: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawWallet {
+    #[serde(default)]
+    pub(crate) account_type: String,
+    #[serde(default)]
+    total_equity: String,
+    #[serde(default)]
+    coin: Vec<RawCoin>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawCoin {
+    coin: String,
+    #[serde(default)]
+    wallet_balance: String,
+    #[serde(default)]
+    locked: String,
+}
+
+impl RawOrder {
+    pub(crate) fn into_order(self) -> Result<LiveOrder> {
+        Ok(LiveOrder {
+            side: side(&self.side)?,
+            price: Price(num(&self.price)?),
+            qty: Qty(num(&self.qty)?),
+            filled: Qty(num_or_zero(&self.cum_exec_qty)?),
+            order_id: self.order_id,
+            link_id: self.order_link_id,
+        })
+    }
+}
+
+impl RawExecution {
+    pub(crate) fn into_fill(self, symbol: &str) -> Result<Fill> {
+        let price = num(&self.exec_price)?;
+        let fee = num_or_zero(&self.exec_fee)?;
+        // комиссия покупки на spot удерживается из купленного base: в `fee` — по цене fill'а
+        let base_fee = !self.fee_currency.is_empty() && symbol.starts_with(&self.fee_currency);
+        Ok(Fill {
+            ts: TimestampMs(self.exec_time.parse()?),
+            side: side(&self.side)?,
+            price: Price(price),
+            qty: Qty(num(&self.exec_qty)?),
+            fee: Money(if base_fee { fee * price } else { fee }),
+            base_fee: Qty(if base_fee { fee } else { 0.0 }),
+            exec_id: self.exec_id,
+            order_id: self.order_id,
+        })
+    }
+}
+
+impl RawWallet {
+    /// Монет без баланса в ответе нет — это ноль
+    pub(crate) fn balance(&self, base_coin: &str, quote_coin: &str) -> Result<WalletBalance> {
+        let coin = |name: &str| -> Result<(f64, f64)> {
+            match self.coin.iter().find(|c| c.coin == name) {
+                Some(c) => Ok((num_or_zero(&c.wallet_balance)?, num_or_zero(&c.locked)?)),
+                None => Ok((0.0, 0.0)),
+            }
+        };
+        let (base, base_locked) = coin(base_coin)?;
+        let (quote, quote_locked) = coin(quote_coin)?;
+        Ok(WalletBalance {
+            total_equity: Money(num_or_zero(&self.total_equity)?),
+            base: Qty(base),
+            base_locked: Qty(base_locked),
+            quote: Money(quote),
+            quote_locked: Money(quote_locked),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm::grid::DesiredOrder;
+
+    #[test]
+    fn signs_payload_and_normalizes_orders_to_instrument_rules() {
+        let c = BybitPrivate::new(TESTNET_URL, "key", "secret", "ETHUSDT");
+        assert_eq!(
+            c.sign(1_700_000_000_000, "category=spot&symbol=ETHUSDT"),
+            "8da223d83a652a7ff072bf3fd416aafde79b8564e799ca6d3f398f46503385a1"
+        );
+
+        let eth = InstrumentInfo {
+            symbol: "ETHUSDT".into(),
+            base_coin: "ETH".into(),
+            quote_coin: "USDT".into(),
+            tick_size: Price(0.01),
+            qty_step: Qty(0.00001),
+            min_qty: Qty(0.0001),
+            min_notional: Money(5.0),
+        };
+        let order = |side, price, qty| DesiredOrder {
+            side,
+            price: Price(price),
+            qty: Qty(qty),
+        };
+        let buy = eth
+            .normalize(&order(Side::Buy, 2345.678, 0.0123456))
+            .unwrap();
+        assert_eq!((buy.price.0, buy.qty.0), (2345.67, 0.01234));
+        let sell = eth.normalize(&order(Side::Sell, 2345.671, 0.01)).unwrap();
+        assert_eq!(sell.price.0, 2345.68);
+        // уже на тике — без сдвига
+        let sell = eth.normalize(&order(Side::Sell, 2345.6, 0.01)).unwrap();
+        assert_eq!(sell.price.0, 2345.6);
+        // 0.001 ETH * 2345 < 5 USDT
+        assert!(eth.normalize(&order(Side::Buy, 2345.0, 0.001)).is_none());
+    }
+
+    #[test]
+    fn follows_page_cursor_until_empty() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let pages = [
+                r#"{"list": [{"orderId": "1"}, {"orderId": "2"}], "nextPageCursor": "page%3A2"}"#,
+                r#"{"list": [{"orderId": "3"}], "nextPageCursor": "page%3A3"}"#,
+                r#"{"list": [], "nextPageCursor": ""}"#,
+            ];
+            let mut cursors = Vec::new();
+            let ids: Vec<OrderId> = collect_pages("/p", |cursor| {
+                let page = pages[cursors.len()];
+                cursors.push(cursor);
+                async move { Ok(serde_json::from_str::<ListResult<OrderId>>(page)?) }
+            })
+            .await
+            .unwrap();
+            let ids: Vec<_> = ids.into_iter().map(|o| o.order_id).collect();
+            assert_eq!(ids, ["1", "2", "3"]);
+            assert_eq!(
+                cursors,
+                [
+                    None,
+                    Some("page%3A2".to_string()),
+                    Some("page%3A3".to_string())
+                ]
+            );
+
+            // курсор по кругу — ошибка, а не вечный цикл
+            let looped = collect_pages("/p", |_| async {
+                Ok(serde_json::from_str::<ListResult<OrderId>>(
+                    r#"{"list": [], "nextPageCursor": "same"}"#,
+                )?)
+            })
+            .await;
+            assert!(looped.is_err());
+        });
+    }
+
+    #[test]
+    fn base_fee_of_spot_buy_is_withheld_from_base() {
+        let exec: RawExecution = serde_json::from_value(json!({
+            "execId": "e1",
+            "orderId": "o1",
+            "side": "Buy",
+            "execPrice": "2000",
+            "execQty": "0.5",
+            "execFee": "0.0005",
+            "feeCurrency": "ETH",
+            "execTime": "1700000000000",
+        }))
+        .unwrap();
+        let fill = exec.into_fill("ETHUSDT").unwrap();
+        assert_eq!(fill.qty, Qty(0.5));
+        assert_eq!(fill.base_fee, Qty(0.0005));
+        assert_eq!(fill.fee, Money(1.0));
+        assert_eq!(fill.net_qty(), Qty(0.4995));
+    }
+
+    #[test]
+    fn batch_items_and_limit_headers() {
+        let ext = json!({"list": [{"code": 0, "msg": "OK"}, {"code": 170213, "msg": "Order does not exist."}]});
+        let ids = ["1".to_string(), String::new()];
+        let items = batch_items("/p", &ext, 2, |i| Some(ids[i].clone()));
+        assert_eq!(items[0].as_ref().unwrap(), "1");
+        assert!(format!("{:#}", items[1].as_ref().unwrap_err()).contains("170213"));
+        // без retExtInfo — по результату
+        let items = batch_items("/p", &Value::Null, 1, |_| None::<()>);
+        assert!(items[0].is_err());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(limit_status(&headers), None);
+        headers.insert("X-Bapi-Limit-Status", "0".parse().unwrap());
+        headers.insert(
+            "X-Bapi-Limit-Reset-Timestamp",
+            "1700000001000".parse().unwrap(),
+        );
+        assert_eq!(
+            limit_status(&headers),
+            Some(LimitStatus {
+                remaining: 0,
+                reset_at: TimestampMs(1_700_000_001_000),
+            })
+        );
+    }
+}
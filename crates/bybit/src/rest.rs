@@ -1,18 +1,210 @@
-use core::types::{Price, Qty, TimestampMs};
-use serde::Deserialize;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, bail};
+use chrono::{TimeZone, Utc};
+use core::types::{Money, Price, Qty, TimestampMs, Trade, TradeSide};
+use mm::grid::{DesiredOrder, LotFilters};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use structure::candle::Candle;
+use tokio::sync::Mutex;
+
+/// retCode Bybit «слишком много запросов» (приходит с HTTP 200)
+const RATE_LIMIT_RET_CODE: i64 = 10006;
+/// Заголовок с моментом (мс) сброса лимита после 429
+const LIMIT_RESET_HEADER: &str = "X-Bapi-Limit-Reset-Timestamp";
+/// Чекпоинт докачки пишется раз в столько страниц
+const CHECKPOINT_EVERY_PAGES: usize = 10;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
 
 #[derive(Clone)]
 pub struct BybitRest {
     client: reqwest::Client,
     base: String,
+    /// Дневные выгрузки публичных сделок
+    public_base: String,
+    limit: RateLimit,
+    /// Момент последнего запроса — общий для клонов клиента
+    last_request: Arc<Mutex<Option<Instant>>>,
+}
+
+/// Темп REST-запросов и повторы после временных ошибок
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RateLimit {
+    /// Минимальная пауза между запросами (`BYBIT_REST_MIN_INTERVAL_MS`)
+    pub min_interval: Duration,
+    /// Повторов на 429 / 5xx / сетевую ошибку (`BYBIT_REST_MAX_RETRIES`)
+    pub max_retries: u32,
+    /// Пауза перед первым повтором, дальше удваивается до `max_backoff`
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(120),
+            max_retries: 5,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RateLimit {
+    /// Умолчания, переопределённые env; нечисловые значения игнорируются
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let mut limit = Self::default();
+        if let Some(ms) = env("BYBIT_REST_MIN_INTERVAL_MS") {
+            limit.min_interval = Duration::from_millis(ms);
+        }
+        if let Some(n) = env("BYBIT_REST_MAX_RETRIES") {
+            limit.max_retries = n.min(u32::MAX as u64) as u32;
+        }
+        limit
+    }
+
+    /// Пауза перед повтором номер `attempt` (с 0)
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_backoff)
+    }
+}
+
+/// Ставка funding линейного perp на момент расчёта (`rate` — доля за период)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FundingRate {
+    pub ts: TimestampMs,
+    pub rate: f64,
+}
+
+/// Открытый интерес линейного perp на момент `ts`, в контрактах (= base)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OpenInterest {
+    pub ts: TimestampMs,
+    pub value: Qty,
+}
+
+/// Правила spot-инструмента для лимиток
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentInfo {
+    pub symbol: String,
+    pub base_coin: String,
+    pub quote_coin: String,
+    pub tick_size: Price,
+    pub qty_step: Qty,
+    pub min_qty: Qty,
+    pub min_notional: Money,
+}
+
+impl InstrumentInfo {
+    /// Правила для `GridParams`
+    pub fn filters(&self) -> LotFilters {
+        LotFilters {
+            tick_size: self.tick_size,
+            qty_step: self.qty_step,
+            min_qty: self.min_qty,
+            min_notional: self.min_notional,
+        }
+    }
+
+    /// Ордер по правилам биржи, см. [`LotFilters::normalize`]
+    pub fn normalize(&self, order: &DesiredOrder) -> Option<DesiredOrder> {
+        self.filters().normalize(order)
+    }
+}
+
+impl Default for BybitRest {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BybitRest {
     pub fn new() -> Self {
+        Self::with_rate_limit(RateLimit::from_env())
+    }
+
+    pub fn with_rate_limit(limit: RateLimit) -> Self {
         Self {
             client: reqwest::Client::new(),
             base: "https://api.bybit.com".to_string(),
+            public_base: "https://public.bybit.com".to_string(),
+            limit,
+            last_request: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Выдерживает `min_interval` с прошлого запроса
+    async fn pace(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(t) = *last {
+            tokio::time::sleep_until((t + self.limit.min_interval).into()).await;
+        }
+        *last = Some(Instant::now());
+    }
+
+    /// Запрос с темпом и повторами: 429, 5xx и сетевые ошибки повторяются
+    /// с backoff'ом (после 429 — до сброса лимита, если биржа его сообщила)
+    async fn send(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> anyhow::Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            self.pace().await;
+            let (error, wait) = match request().send().await {
+                Ok(resp) if resp.status() == 429 || resp.status().is_server_error() => {
+                    let wait = limit_reset_wait(&resp).map(|w| w.min(self.limit.max_backoff));
+                    (format!("HTTP {}", resp.status()), wait)
+                }
+                Ok(resp) => return Ok(resp.error_for_status()?),
+                Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+                    (e.to_string(), None)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if attempt >= self.limit.max_retries {
+                bail!("{} (after {} retries)", error, attempt);
+            }
+            tokio::time::sleep(wait.unwrap_or_else(|| self.limit.backoff_for(attempt))).await;
+            attempt += 1;
+        }
+    }
+
+    /// GET `/v5/...`: `result` ответа; retCode 10006 повторяется как 429
+    async fn get_result<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let url = format!("{}{}", self.base, path);
+        let mut attempt = 0;
+        loop {
+            let env: Envelope = self
+                .send(|| self.client.get(&url).query(query))
+                .await?
+                .json()
+                .await?;
+            if env.ret_code == RATE_LIMIT_RET_CODE && attempt < self.limit.max_retries {
+                tokio::time::sleep(self.limit.backoff_for(attempt)).await;
+                attempt += 1;
+                continue;
+            }
+            if env.ret_code != 0 {
+                bail!("{} failed: {} {}", path, env.ret_code, env.ret_msg);
+            }
+            return serde_json::from_value(env.result)
+                .with_context(|| format!("{}: unexpected result", path));
         }
     }
 
@@ -24,27 +216,22 @@ impl BybitRest {
         end_ms: i64,
         limit: u16, // 1..=1000
     ) -> anyhow::Result<Vec<Candle>> {
-        let url = format!("{}/v5/market/kline", self.base);
-
-        let resp: KlineResp = self
-            .client
-            .get(url)
-            .query(&[
-                ("category", "spot"),
-                ("symbol", symbol),
-                ("interval", interval),
-                ("start", &start_ms.to_string()),
-                ("end", &end_ms.to_string()),
-                ("limit", &limit.to_string()),
-            ])
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
+        let result: KlineResult = self
+            .get_result(
+                "/v5/market/kline",
+                &[
+                    ("category", "spot".to_string()),
+                    ("symbol", symbol.to_string()),
+                    ("interval", interval.to_string()),
+                    ("start", start_ms.to_string()),
+                    ("end", end_ms.to_string()),
+                    ("limit", limit.to_string()),
+                ],
+            )
             .await?;
 
         let mut out = Vec::new();
-        let list = resp.result.list;
+        let list = result.list;
 
         // Bybit возвращает reverse sort by startTime, поэтому разворачиваем
         for row in list.into_iter().rev() {
@@ -67,17 +254,226 @@ impl BybitRest {
 
         Ok(out)
     }
+
+    /// История funding линейного perp, по возрастанию времени
+    pub async fn get_funding_history_linear(
+        &self,
+        symbol: &str,
+        start_ms: i64,
+        end_ms: i64,
+        limit: u16, // 1..=200
+    ) -> anyhow::Result<Vec<FundingRate>> {
+        let result: FundingResult = self
+            .get_result(
+                "/v5/market/funding/history",
+                &[
+                    ("category", "linear".to_string()),
+                    ("symbol", symbol.to_string()),
+                    ("startTime", start_ms.to_string()),
+                    ("endTime", end_ms.to_string()),
+                    ("limit", limit.to_string()),
+                ],
+            )
+            .await?;
+
+        let mut out = Vec::new();
+        for row in result.list.into_iter().rev() {
+            out.push(FundingRate {
+                ts: TimestampMs(row.funding_rate_timestamp.parse()?),
+                rate: row.funding_rate.parse()?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Страница open interest линейного perp (`interval` — `5min`, `15min`, `30min`,
+    /// `1h`, `4h`, `1d`; `limit` 1..=200) по возрастанию времени и курсор следующей
+    /// (более ранней) страницы
+    pub async fn get_open_interest_linear(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+        limit: u16,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<(Vec<OpenInterest>, Option<String>)> {
+        let mut query = vec![
+            ("category", "linear".to_string()),
+            ("symbol", symbol.to_string()),
+            ("intervalTime", interval.to_string()),
+            ("startTime", start_ms.to_string()),
+            ("endTime", end_ms.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(c) = cursor {
+            query.push(("cursor", c.to_string()));
+        }
+        let result: OpenInterestResult =
+            self.get_result("/v5/market/open-interest", &query).await?;
+        let next = result.next_page_cursor.filter(|c| !c.is_empty());
+        Ok((open_interest_rows(result.list)?, next))
+    }
+
+    /// Тик цены, шаг и минимумы объёма spot-символа
+    pub async fn get_instrument_info(&self, symbol: &str) -> anyhow::Result<InstrumentInfo> {
+        let result: InstrumentsResult = self
+            .get_result(
+                "/v5/market/instruments-info",
+                &[
+                    ("category", "spot".to_string()),
+                    ("symbol", symbol.to_string()),
+                ],
+            )
+            .await?;
+        result
+            .list
+            .into_iter()
+            .next()
+            .with_context(|| format!("unknown symbol {}", symbol))?
+            .into_info()
+    }
+
+    /// Лучшие bid/ask spot-символа; `None` — одна из сторон книги пуста
+    pub async fn get_orderbook_top(&self, symbol: &str) -> anyhow::Result<Option<(Price, Price)>> {
+        let result: OrderbookResult = self
+            .get_result(
+                "/v5/market/orderbook",
+                &[
+                    ("category", "spot".to_string()),
+                    ("symbol", symbol.to_string()),
+                    ("limit", "1".to_string()),
+                ],
+            )
+            .await?;
+        let (Some([bid, _]), Some([ask, _])) = (result.bids.first(), result.asks.first()) else {
+            return Ok(None);
+        };
+        Ok(Some((Price(num(bid)?), Price(num(ask)?))))
+    }
+
+    /// Последние spot сделки (`limit` 1..=60), по возрастанию времени
+    pub async fn get_recent_trades(&self, symbol: &str, limit: u16) -> anyhow::Result<Vec<Trade>> {
+        let result: RecentTradesResult = self
+            .get_result(
+                "/v5/market/recent-trade",
+                &[
+                    ("category", "spot".to_string()),
+                    ("symbol", symbol.to_string()),
+                    ("limit", limit.to_string()),
+                ],
+            )
+            .await?;
+
+        let mut out = Vec::new();
+        for row in result.list {
+            out.push(Trade {
+                ts: TimestampMs(row.time.parse()?),
+                price: Price(row.price.parse()?),
+                qty: Qty(row.size.parse()?),
+                side: parse_side(&row.side)?,
+            });
+        }
+        out.sort_by_key(|t| t.ts.0);
+        Ok(out)
+    }
+
+    /// Spot сделки за день (`date` — `YYYY-MM-DD`) из gzip CSV выгрузки,
+    /// по возрастанию времени
+    pub async fn get_trades_day_spot(
+        &self,
+        symbol: &str,
+        date: &str,
+    ) -> anyhow::Result<Vec<Trade>> {
+        let url = format!(
+            "{}/spot/{}/{}_{}.csv.gz",
+            self.public_base, symbol, symbol, date
+        );
+        let gz = self.send(|| self.client.get(&url)).await?.bytes().await?;
+
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(&gz[..]).read_to_string(&mut text)?;
+        let mut out = parse_trades_csv(&text)?;
+        out.sort_by_key(|t| t.ts.0);
+        Ok(out)
+    }
+}
+
+/// `Buy`/`buy` → `TradeSide::Buy`
+fn parse_side(s: &str) -> anyhow::Result<TradeSide> {
+    match s.trim() {
+        v if v.eq_ignore_ascii_case("buy") => Ok(TradeSide::Buy),
+        v if v.eq_ignore_ascii_case("sell") => Ok(TradeSide::Sell),
+        v => bail!("unknown trade side: {}", v),
+    }
+}
+
+/// CSV выгрузки: колонки ищем по заголовку (`timestamp`, `price`, `volume`/`size`, `side`).
+/// Время в секундах (выгрузки деривативов) переводим в мс.
+fn parse_trades_csv(text: &str) -> anyhow::Result<Vec<Trade>> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty trades file"))?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let col = |names: &[&str]| {
+        header
+            .iter()
+            .position(|h| names.contains(h))
+            .ok_or_else(|| anyhow::anyhow!("trades file has no {} column", names[0]))
+    };
+    let (ts_col, price_col, qty_col, side_col) = (
+        col(&["timestamp"])?,
+        col(&["price"])?,
+        col(&["volume", "size"])?,
+        col(&["side"])?,
+    );
+
+    let mut out = Vec::new();
+    for line in lines.filter(|l| !l.trim().is_empty()) {
+        let row: Vec<&str> = line.split(',').collect();
+        let field = |i: usize| {
+            row.get(i)
+                .ok_or_else(|| anyhow::anyhow!("short trades row: {}", line))
+        };
+        let ts: f64 = field(ts_col)?.trim().parse()?;
+        let ts = if ts < 1e11 { ts * 1000.0 } else { ts };
+        out.push(Trade {
+            ts: TimestampMs(ts.round() as i64),
+            price: Price(field(price_col)?.trim().parse()?),
+            qty: Qty(field(qty_col)?.trim().parse()?),
+            side: parse_side(field(side_col)?)?,
+        });
+    }
+    Ok(out)
+}
+
+/// Пауза до сброса лимита из заголовка ответа 429
+fn limit_reset_wait(resp: &reqwest::Response) -> Option<Duration> {
+    let reset_ms: i64 = resp
+        .headers()
+        .get(LIMIT_RESET_HEADER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some(Duration::from_millis((reset_ms - now_ms).max(0) as u64))
 }
 
 #[derive(Debug, Deserialize)]
-struct KlineResp {
-    #[allow(dead_code)]
+struct Envelope {
     #[serde(rename = "retCode")]
     ret_code: i64,
-    #[allow(dead_code)]
-    #[serde(rename = "retMsg")]
+    #[serde(rename = "retMsg", default)]
     ret_msg: String,
-    result: KlineResult,
+    #[serde(default)]
+    result: serde_json::Value,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,41 +481,335 @@ struct KlineResult {
     list: Vec<Vec<String>>,
 }
 
-pub async fn download_range(
+#[derive(Debug, Deserialize)]
+struct OrderbookResult {
+    #[serde(rename = "b", default)]
+    bids: Vec<[String; 2]>,
+    #[serde(rename = "a", default)]
+    asks: Vec<[String; 2]>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstrumentsResult {
+    list: Vec<RawInstrument>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawInstrument {
+    symbol: String,
+    base_coin: String,
+    quote_coin: String,
+    lot_size_filter: RawLotSize,
+    price_filter: RawPriceFilter,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLotSize {
+    base_precision: String,
+    min_order_qty: String,
+    #[serde(default)]
+    min_order_amt: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPriceFilter {
+    tick_size: String,
+}
+
+impl RawInstrument {
+    pub(crate) fn into_info(self) -> anyhow::Result<InstrumentInfo> {
+        Ok(InstrumentInfo {
+            tick_size: Price(num(&self.price_filter.tick_size)?),
+            qty_step: Qty(num(&self.lot_size_filter.base_precision)?),
+            min_qty: Qty(num(&self.lot_size_filter.min_order_qty)?),
+            min_notional: Money(num_or_zero(&self.lot_size_filter.min_order_amt)?),
+            symbol: self.symbol,
+            base_coin: self.base_coin,
+            quote_coin: self.quote_coin,
+        })
+    }
+}
+
+pub(crate) fn num(s: &str) -> anyhow::Result<f64> {
+    s.parse().with_context(|| format!("bad number {:?}", s))
+}
+
+/// Пустая строка в ответе — ноль
+pub(crate) fn num_or_zero(s: &str) -> anyhow::Result<f64> {
+    if s.is_empty() { Ok(0.0) } else { num(s) }
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentTradesResult {
+    list: Vec<RecentTradeRow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentTradeRow {
+    price: String,
+    size: String,
+    side: String,
+    time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FundingResult {
+    list: Vec<FundingRow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FundingRow {
+    funding_rate: String,
+    funding_rate_timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenInterestResult {
+    list: Vec<OpenInterestRow>,
+    #[serde(default)]
+    next_page_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenInterestRow {
+    open_interest: String,
+    timestamp: String,
+}
+
+/// Строки ответа идут от новых к старым — разворачиваем
+fn open_interest_rows(list: Vec<OpenInterestRow>) -> anyhow::Result<Vec<OpenInterest>> {
+    let mut out = Vec::with_capacity(list.len());
+    for row in list.into_iter().rev() {
+        out.push(OpenInterest {
+            ts: TimestampMs(row.timestamp.parse()?),
+            value: Qty(row.open_interest.parse()?),
+        });
+    }
+    Ok(out)
+}
+
+/// Open interest `[start, end]` постранично по курсору биржи
+pub async fn download_open_interest(
     api: &BybitRest,
     symbol: &str,
     interval: &str,
     start_ms: i64,
     end_ms: i64,
-) -> anyhow::Result<Vec<Candle>> {
-    let mut all: Vec<Candle> = Vec::new();
-    let mut cursor_end = end_ms;
-
-    // 1000 — максимум на страницу
-    let limit = 1000u16;
-
+) -> anyhow::Result<Vec<OpenInterest>> {
+    let mut all: Vec<OpenInterest> = Vec::new();
+    let mut cursor: Option<String> = None;
     loop {
-        if cursor_end <= start_ms {
-            break;
+        let (page, next) = api
+            .get_open_interest_linear(symbol, interval, start_ms, end_ms, 200, cursor.as_deref())
+            .await?;
+        let empty = page.is_empty();
+        all.extend(page);
+        match next {
+            Some(c) if !empty && cursor.as_deref() != Some(c.as_str()) => cursor = Some(c),
+            _ => break,
         }
+    }
+    all.sort_by_key(|o| o.ts.0);
+    all.dedup_by_key(|o| o.ts.0);
+    all.retain(|o| o.ts.0 >= start_ms && o.ts.0 <= end_ms);
+    Ok(all)
+}
 
+/// Funding `[start, end]` постранично, как [`download_range`]
+pub async fn download_funding(
+    api: &BybitRest,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> anyhow::Result<Vec<FundingRate>> {
+    let mut all: Vec<FundingRate> = Vec::new();
+    let mut cursor_end = end_ms;
+    while cursor_end > start_ms {
         let page = api
-            .get_klines_spot(symbol, interval, start_ms, cursor_end, limit)
+            .get_funding_history_linear(symbol, start_ms, cursor_end, 200)
             .await?;
-        if page.is_empty() {
+        let Some(first) = page.first() else {
             break;
+        };
+        cursor_end = first.ts.0 - 1;
+        all.extend(page);
+    }
+    all.sort_by_key(|f| f.ts.0);
+    all.dedup_by_key(|f| f.ts.0);
+    all.retain(|f| f.ts.0 >= start_ms && f.ts.0 <= end_ms);
+    Ok(all)
+}
+
+/// Spot сделки `[start, end]` постранично по дневным выгрузкам (UTC);
+/// выгрузка за день появляется на следующие сутки
+pub async fn download_trades(
+    api: &BybitRest,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> anyhow::Result<Vec<Trade>> {
+    let mut out = Vec::new();
+    let mut day = start_ms - start_ms.rem_euclid(DAY_MS);
+    while day <= end_ms {
+        let date = Utc
+            .timestamp_millis_opt(day)
+            .single()
+            .context("bad trades day")?
+            .format("%Y-%m-%d")
+            .to_string();
+        let trades = api
+            .get_trades_day_spot(symbol, &date)
+            .await
+            .with_context(|| format!("download {} trades for {} failed", symbol, date))?;
+        out.extend(
+            trades
+                .into_iter()
+                .filter(|t| t.ts.0 >= start_ms && t.ts.0 <= end_ms),
+        );
+        day += DAY_MS;
+    }
+    Ok(out)
+}
+
+/// Прогресс докачки свечей: страницы идут от конца диапазона к началу,
+/// `cursor_end` — конец следующей страницы
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeCheckpoint {
+    pub symbol: String,
+    pub interval: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub cursor_end: i64,
+    /// `(ts, open, high, low, close, volume)`
+    pub candles: Vec<(i64, f64, f64, f64, f64, f64)>,
+}
+
+impl RangeCheckpoint {
+    /// Чекпоинт того же символа, интервала и диапазона; чужой или битый файл — `None`
+    pub fn load(path: &Path, symbol: &str, interval: &str, range: (i64, i64)) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        let cp: Self = serde_json::from_str(&text).ok()?;
+        (cp.symbol == symbol && cp.interval == interval && (cp.start_ms, cp.end_ms) == range)
+            .then_some(cp)
+    }
+
+    /// Через временный файл, чтобы обрыв записи не портил прошлый чекпоинт
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn to_candles(&self) -> Vec<Candle> {
+        self.candles
+            .iter()
+            .map(|&(ts, open, high, low, close, volume)| Candle {
+                ts: TimestampMs(ts),
+                open: Price(open),
+                high: Price(high),
+                low: Price(low),
+                close: Price(close),
+                volume: Qty(volume),
+            })
+            .collect()
+    }
+}
+
+pub async fn download_range(
+    api: &BybitRest,
+    symbol: &str,
+    interval: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> anyhow::Result<Vec<Candle>> {
+    download_range_resumable(api, symbol, interval, start_ms, end_ms, None).await
+}
+
+/// Как [`download_range`], но с чекпоинтом в `checkpoint`: после сбоя повторный вызов
+/// с тем же диапазоном продолжает с последней сохранённой страницы.
+/// Файл удаляется после успешной докачки.
+pub async fn download_range_resumable(
+    api: &BybitRest,
+    symbol: &str,
+    interval: &str,
+    start_ms: i64,
+    end_ms: i64,
+    checkpoint: Option<&Path>,
+) -> anyhow::Result<Vec<Candle>> {
+    let resumed =
+        checkpoint.and_then(|p| RangeCheckpoint::load(p, symbol, interval, (start_ms, end_ms)));
+    let (mut all, mut cursor_end) = match &resumed {
+        Some(cp) => {
+            eprintln!(
+                "resuming {} {}m download from checkpoint: {} candles",
+                symbol,
+                interval,
+                cp.candles.len()
+            );
+            (cp.to_candles(), cp.cursor_end)
+        }
+        None => (Vec::new(), end_ms),
+    };
+    // чекпоинт — best effort: без него загрузка просто не продолжится после сбоя
+    let save = |all: &[Candle], cursor_end: i64| {
+        let Some(path) = checkpoint else {
+            return;
+        };
+        let cp = RangeCheckpoint {
+            symbol: symbol.to_string(),
+            interval: interval.to_string(),
+            start_ms,
+            end_ms,
+            cursor_end,
+            candles: all
+                .iter()
+                .map(|c| (c.ts.0, c.open.0, c.high.0, c.low.0, c.close.0, c.volume.0))
+                .collect(),
+        };
+        if let Err(e) = cp.save(path) {
+            eprintln!("save checkpoint {} failed: {:#}", path.display(), e);
         }
+    };
 
+    // 1000 — максимум на страницу
+    let limit = 1000u16;
+    let mut pages = 0usize;
+
+    while cursor_end > start_ms {
+        let page = match api
+            .get_klines_spot(symbol, interval, start_ms, cursor_end, limit)
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                save(&all, cursor_end);
+                return Err(e);
+            }
+        };
         // page уже в возрастающем порядке (мы rev сделали)
-        let first_ts = page.first().unwrap().ts.0;
-        all.extend(page);
+        let Some(first) = page.first() else {
+            break;
+        };
 
         // дальше “идём назад” по времени
         // чтобы не зациклиться на той же первой свече:
-        cursor_end = first_ts - 1;
+        cursor_end = first.ts.0 - 1;
+        all.extend(page);
 
-        // лёгкий троттлинг (можно сделать умнее)
-        tokio::time::sleep(std::time::Duration::from_millis(120)).await;
+        pages += 1;
+        if pages % CHECKPOINT_EVERY_PAGES == 0 {
+            save(&all, cursor_end);
+        }
     }
 
     // all будет “кусочками” от конца к началу — отсортируем и удалим дубликаты
@@ -129,5 +819,89 @@ pub async fn download_range(
     // обрежем точно по диапазону
     all.retain(|c| c.ts.0 >= start_ms && c.ts.0 <= end_ms);
 
+    if let Some(path) = checkpoint {
+        let _ = std::fs::remove_file(path);
+    }
     Ok(all)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_cap_and_checkpoint_matches_range() {
+        let limit = RateLimit::default();
+        assert_eq!(limit.backoff_for(0), Duration::from_millis(500));
+        assert_eq!(limit.backoff_for(2), Duration::from_secs(2));
+        assert_eq!(limit.backoff_for(10), limit.max_backoff);
+        assert_eq!(limit.backoff_for(u32::MAX), limit.max_backoff);
+
+        let path = std::env::temp_dir().join(format!("bybit-cp-{}.json", std::process::id()));
+        let cp = RangeCheckpoint {
+            symbol: "ETHUSDT".into(),
+            interval: "5".into(),
+            start_ms: 0,
+            end_ms: 600_000,
+            cursor_end: 299_999,
+            candles: vec![(300_000, 1.0, 2.0, 0.5, 1.5, 10.0)],
+        };
+        cp.save(&path).unwrap();
+
+        let back = RangeCheckpoint::load(&path, "ETHUSDT", "5", (0, 600_000)).unwrap();
+        assert_eq!(back, cp);
+        assert_eq!(back.to_candles()[0].close, Price(1.5));
+        // другой диапазон или интервал — качаем заново
+        assert!(RangeCheckpoint::load(&path, "ETHUSDT", "5", (0, 900_000)).is_none());
+        assert!(RangeCheckpoint::load(&path, "ETHUSDT", "1", (0, 600_000)).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parses_spot_and_derivatives_trade_dumps() {
+        let spot = "id,timestamp,price,volume,side\n1,1700000000123,2000.5,0.1,buy\n";
+        let trades = parse_trades_csv(spot).unwrap();
+        assert_eq!(
+            trades,
+            vec![Trade {
+                ts: TimestampMs(1_700_000_000_123),
+                price: Price(2000.5),
+                qty: Qty(0.1),
+                side: TradeSide::Buy,
+            }]
+        );
+
+        // деривативы: время в секундах, `size` вместо `volume`
+        let linear = "timestamp,symbol,side,size,price\n1700000000.5,ETHUSDT,Sell,2,1999\n";
+        let trades = parse_trades_csv(linear).unwrap();
+        assert_eq!(trades[0].ts, TimestampMs(1_700_000_000_500));
+        assert_eq!(trades[0].side, TradeSide::Sell);
+        assert!(parse_trades_csv("timestamp,price,volume\n1,1,1\n").is_err());
+    }
+
+    #[test]
+    fn open_interest_page_is_parsed_oldest_first() {
+        let result: OpenInterestResult = serde_json::from_str(
+            r#"{"symbol":"ETHUSDT","category":"linear","list":[
+                {"openInterest":"120.5","timestamp":"1700000300000"},
+                {"openInterest":"118","timestamp":"1700000000000"}
+            ],"nextPageCursor":"abc"}"#,
+        )
+        .unwrap();
+        assert_eq!(result.next_page_cursor.as_deref(), Some("abc"));
+        let rows = open_interest_rows(result.list).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                OpenInterest {
+                    ts: TimestampMs(1_700_000_000_000),
+                    value: Qty(118.0),
+                },
+                OpenInterest {
+                    ts: TimestampMs(1_700_000_300_000),
+                    value: Qty(120.5),
+                },
+            ]
+        );
+    }
+}
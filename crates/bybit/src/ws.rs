@@ -1,16 +1,110 @@
+use std::time::Duration;
+
+use anyhow::bail;
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
 use tokio::sync::mpsc::Sender;
 use tokio_tungstenite::tungstenite::Message;
 
 use core::types::{Price, Qty, TimestampMs};
+use execution::orders::{Fill, LiveOrder};
 use structure::candle::Candle;
 
+use crate::orderbook::{BOOK_DEPTH, BookMessage, OrderBook};
+use crate::private::{BybitPrivate, RawExecution, RawOrder, RawWallet, WalletBalance, now_ms};
+use crate::rest::InstrumentInfo;
+
+pub const PRIVATE_WS_MAINNET: &str = "wss://stream.bybit.com/v5/private";
+pub const PRIVATE_WS_TESTNET: &str = "wss://stream-testnet.bybit.com/v5/private";
+/// Private-соединение без ping'а биржа закрывает; рекомендованный интервал — 20с
+const PRIVATE_PING_INTERVAL: Duration = Duration::from_secs(20);
+/// Срок действия подписи авторизации
+const PRIVATE_AUTH_TTL_MS: i64 = 10_000;
+/// Ордер ещё стоит в книге
+const OPEN_ORDER_STATUSES: [&str; 3] = ["New", "PartiallyFilled", "Untriggered"];
+
 /// События market data
 #[derive(Debug, Clone)]
 pub enum MarketEvent {
-    Candle5m(Candle),
-    Ticker { mid: Price },
+    /// Закрытая свеча; `interval` — в формате Bybit ("1", "5", "60"...)
+    Candle {
+        interval: String,
+        candle: Candle,
+    },
+    Ticker {
+        mid: Price,
+    },
+    /// Лучшие цены локальной L2-книги; шлётся, когда одна из них изменилась
+    BookTop {
+        bid: Price,
+        ask: Price,
+    },
+    /// Публичная сделка (для локальной сборки свечей)
+    Trade {
+        ts: TimestampMs,
+        price: Price,
+        qty: Qty,
+    },
+    /// Соединение оборвалось и восстановлено; `attempt` — номер переподключения
+    Reconnected {
+        attempt: u64,
+    },
+}
+
+/// События аккаунта из private WS (только spot и символ клиента)
+#[derive(Debug, Clone)]
+pub enum AccountEvent {
+    /// Ордер выставлен, изменён, исполнен или снят; `open` — ещё стоит в книге
+    Order {
+        order: LiveOrder,
+        open: bool,
+    },
+    Fill(Fill),
+    /// Балансы пары после изменения
+    Wallet(WalletBalance),
+    /// Соединение оборвалось и восстановлено: push'и за время обрыва потеряны
+    Reconnected {
+        attempt: u64,
+    },
+}
+
+/// Публичные топики символа помимо kline
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WsTopic {
+    /// `tickers.<symbol>` → `MarketEvent::Ticker`
+    Ticker,
+    /// `publicTrade.<symbol>` → `MarketEvent::Trade`
+    Trades,
+    /// `orderbook.50.<symbol>` → `MarketEvent::BookTop`
+    OrderBook,
+}
+
+/// Подписка публичного WS: символ, интервалы свечей и прочие топики
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    pub symbol: String,
+    /// Интервалы kline в формате Bybit ("1", "5", "60"...)
+    pub kline_intervals: Vec<String>,
+    pub topics: Vec<WsTopic>,
+}
+
+impl WsConfig {
+    fn topic_names(&self) -> Vec<String> {
+        let mut out: Vec<String> = self
+            .kline_intervals
+            .iter()
+            .map(|i| format!("kline.{}.{}", i, self.symbol))
+            .collect();
+        for t in &self.topics {
+            out.push(match t {
+                WsTopic::Ticker => format!("tickers.{}", self.symbol),
+                WsTopic::Trades => format!("publicTrade.{}", self.symbol),
+                WsTopic::OrderBook => format!("orderbook.{}.{}", BOOK_DEPTH, self.symbol),
+            });
+        }
+        out
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +112,13 @@ struct WsEnvelope<T> {
     data: T,
 }
 
+/// Только topic — чтобы выбрать, как разбирать `data`
+#[derive(Debug, Deserialize)]
+struct WsTopicOnly {
+    #[serde(default)]
+    topic: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct KlineData {
     start: i64,
@@ -29,56 +130,103 @@ struct KlineData {
     confirm: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct TradeData {
+    #[serde(rename = "T")]
+    ts: i64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "v")]
+    qty: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct TickerData {
     #[serde(rename = "lastPrice")]
     last_price: String,
 }
 
-fn subscribe_messages() -> Vec<Message> {
-    vec![
-        Message::Text(
-            serde_json::json!({
-                "op": "subscribe",
-                "args": ["kline.5.ETHUSDT"]
-            })
-            .to_string(),
-        ),
-        Message::Text(
-            serde_json::json!({
-                "op": "subscribe",
-                "args": ["tickers.ETHUSDT"]
-            })
-            .to_string(),
-        ),
-    ]
-}
-
-pub async fn run_ws(tx: Sender<MarketEvent>) {
+fn subscribe_messages(config: &WsConfig) -> Vec<Message> {
+    config
+        .topic_names()
+        .into_iter()
+        .map(|topic| {
+            Message::Text(
+                serde_json::json!({
+                    "op": "subscribe",
+                    "args": [topic]
+                })
+                .to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Пауза перед переподключением: 1с, 2с, 4с... до 30с
+fn reconnect_backoff(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.min(5)).min(Duration::from_secs(30))
+}
+
+/// Держит WS-подписку, переподключаясь после обрыва.
+/// Завершается, только когда получатель `tx` закрыт.
+pub async fn run_ws(config: WsConfig, tx: Sender<MarketEvent>) {
     // Spot public WS endpoint
     let url = "wss://stream.bybit.com/v5/public/spot";
 
-    let (ws, _) = tokio_tungstenite::connect_async(url)
-        .await
-        .expect("WS connect failed");
+    let mut reconnects = 0u64;
+    let mut failures = 0u32;
+    loop {
+        match session(url, &config, &tx).await {
+            Ok(()) => failures = 0,
+            Err(e) => {
+                eprintln!("WS session failed: {:#}", e);
+                failures += 1;
+            }
+        }
+        if tx.is_closed() {
+            return;
+        }
+
+        tokio::time::sleep(reconnect_backoff(failures)).await;
+        reconnects += 1;
+        let ev = MarketEvent::Reconnected {
+            attempt: reconnects,
+        };
+        if tx.send(ev).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Одно соединение: подписка и чтение до обрыва.
+/// Книга живёт в пределах соединения: после переподключения биржа шлёт новый snapshot.
+async fn session(url: &str, config: &WsConfig, tx: &Sender<MarketEvent>) -> anyhow::Result<()> {
+    let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+    let mut book = OrderBook::default();
+    let mut last_top = None;
 
     let (mut write, mut read) = ws.split();
 
     // подписка
-    for msg in subscribe_messages() {
-        write.send(msg).await.expect("subscribe failed");
+    for msg in subscribe_messages(config) {
+        write.send(msg).await?;
     }
 
     while let Some(msg) = read.next().await {
-        let msg = match msg {
-            Ok(m) => m,
-            Err(_) => break,
-        };
+        let msg = msg?;
 
         let Message::Text(text) = msg else { continue };
 
-        // kline
-        if text.contains("kline.5.") {
+        // ответы на subscribe/ping без topic
+        let Ok(WsTopicOnly { topic }) = serde_json::from_str::<WsTopicOnly>(&text) else {
+            continue;
+        };
+
+        // kline.<interval>.<symbol>
+        if let Some(interval) = topic
+            .strip_prefix("kline.")
+            .and_then(|rest| rest.split('.').next())
+        {
             if let Ok(env) = serde_json::from_str::<WsEnvelope<Vec<KlineData>>>(&text) {
                 for k in env.data {
                     if !k.confirm {
@@ -94,14 +242,52 @@ pub async fn run_ws(tx: Sender<MarketEvent>) {
                         volume: Qty(k.volume.parse().unwrap_or(0.0)),
                     };
 
-                    let _ = tx.send(MarketEvent::Candle5m(candle)).await;
+                    let ev = MarketEvent::Candle {
+                        interval: interval.to_string(),
+                        candle,
+                    };
+                    let _ = tx.send(ev).await;
+                }
+            }
+            continue;
+        }
+
+        // trades
+        if topic.starts_with("publicTrade.") {
+            if let Ok(env) = serde_json::from_str::<WsEnvelope<Vec<TradeData>>>(&text) {
+                for t in env.data {
+                    let (Ok(p), Ok(q)) = (t.price.parse::<f64>(), t.qty.parse::<f64>()) else {
+                        continue;
+                    };
+                    let ev = MarketEvent::Trade {
+                        ts: TimestampMs(t.ts),
+                        price: Price(p),
+                        qty: Qty(q),
+                    };
+                    let _ = tx.send(ev).await;
+                }
+            }
+            continue;
+        }
+
+        // orderbook.<depth>.<symbol>
+        if topic.starts_with("orderbook.") {
+            if let Ok(msg) = serde_json::from_str::<BookMessage>(&text) {
+                if book.apply(&msg) {
+                    let top = book.top();
+                    if top != last_top {
+                        last_top = top;
+                        if let Some((bid, ask)) = top {
+                            let _ = tx.send(MarketEvent::BookTop { bid, ask }).await;
+                        }
+                    }
                 }
             }
             continue;
         }
 
         // ticker
-        if text.contains("tickers.") {
+        if topic.starts_with("tickers.") {
             if let Ok(env) = serde_json::from_str::<WsEnvelope<Vec<TickerData>>>(&text) {
                 if let Some(t) = env.data.first() {
                     if let Ok(p) = t.last_price.parse::<f64>() {
@@ -111,4 +297,282 @@ pub async fn run_ws(tx: Sender<MarketEvent>) {
             }
         }
     }
+    Ok(())
+}
+
+/// Private WS одного символа: авторизация ключом `BybitPrivate`,
+/// подписка на `order`, `execution` и `wallet`
+pub struct AccountStream {
+    pub url: String,
+    pub client: BybitPrivate,
+    pub base_coin: String,
+    pub quote_coin: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrivateMessage {
+    #[serde(default)]
+    op: String,
+    #[serde(default)]
+    success: Option<bool>,
+    #[serde(default)]
+    ret_msg: String,
+    #[serde(default)]
+    topic: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+impl AccountStream {
+    pub fn new(url: &str, client: BybitPrivate, instrument: &InstrumentInfo) -> Self {
+        Self {
+            url: url.to_string(),
+            client,
+            base_coin: instrument.base_coin.clone(),
+            quote_coin: instrument.quote_coin.clone(),
+        }
+    }
+
+    /// Держит подписку, переподключаясь после обрыва (как `run_ws`).
+    /// Завершается, только когда получатель `tx` закрыт.
+    pub async fn run(self, tx: Sender<AccountEvent>) {
+        let mut reconnects = 0u64;
+        let mut failures = 0u32;
+        loop {
+            match self.session(&tx).await {
+                Ok(()) => failures = 0,
+                Err(e) => {
+                    eprintln!("private WS session failed: {:#}", e);
+                    failures += 1;
+                }
+            }
+            if tx.is_closed() {
+                return;
+            }
+
+            tokio::time::sleep(reconnect_backoff(failures)).await;
+            reconnects += 1;
+            let ev = AccountEvent::Reconnected {
+                attempt: reconnects,
+            };
+            if tx.send(ev).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn session(&self, tx: &Sender<AccountEvent>) -> anyhow::Result<()> {
+        let (ws, _) = tokio_tungstenite::connect_async(self.url.as_str()).await?;
+        let (mut write, mut read) = ws.split();
+
+        let auth = self.client.ws_auth(now_ms() + PRIVATE_AUTH_TTL_MS);
+        write.send(Message::Text(auth.to_string())).await?;
+        let subscribe = serde_json::json!({
+            "op": "subscribe",
+            "args": ["order", "execution", "wallet"]
+        });
+        write.send(Message::Text(subscribe.to_string())).await?;
+
+        let mut ping = tokio::time::interval(PRIVATE_PING_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    let msg = serde_json::json!({"op": "ping"}).to_string();
+                    write.send(Message::Text(msg)).await?;
+                }
+                msg = read.next() => {
+                    let Some(msg) = msg else { return Ok(()) };
+                    let Message::Text(text) = msg? else { continue };
+                    for ev in self.parse(&text)? {
+                        if tx.send(ev).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// События из сообщения; `Err` — отказ в авторизации или подписке
+    fn parse(&self, text: &str) -> anyhow::Result<Vec<AccountEvent>> {
+        let msg: PrivateMessage = serde_json::from_str(text)?;
+        if msg.success == Some(false) {
+            bail!("{} rejected: {}", msg.op, msg.ret_msg);
+        }
+        let ours =
+            |category: &str, symbol: &str| category == "spot" && symbol == self.client.symbol();
+        let mut out = Vec::new();
+        match msg.topic.as_str() {
+            "order" => {
+                for o in rows::<RawOrder>(msg.data) {
+                    if !ours(&o.category, &o.symbol) {
+                        continue;
+                    }
+                    let open = OPEN_ORDER_STATUSES.contains(&o.order_status.as_str());
+                    match o.into_order() {
+                        Ok(order) => out.push(AccountEvent::Order { order, open }),
+                        Err(e) => eprintln!("private WS: bad order: {:#}", e),
+                    }
+                }
+            }
+            "execution" => {
+                for e in rows::<RawExecution>(msg.data) {
+                    if !ours(&e.category, &e.symbol) {
+                        continue;
+                    }
+                    match e.into_fill(self.client.symbol()) {
+                        Ok(f) => out.push(AccountEvent::Fill(f)),
+                        Err(e) => eprintln!("private WS: bad execution: {:#}", e),
+                    }
+                }
+            }
+            "wallet" => {
+                for w in rows::<RawWallet>(msg.data) {
+                    match w.balance(&self.base_coin, &self.quote_coin) {
+                        Ok(b) => out.push(AccountEvent::Wallet(b)),
+                        Err(e) => eprintln!("private WS: bad wallet: {:#}", e),
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(out)
+    }
+}
+
+/// Строки `data` по одной: битая строка пропускается, остальные доходят
+fn rows<T: DeserializeOwned>(data: serde_json::Value) -> Vec<T> {
+    match data {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .filter_map(|v| serde_json::from_value(v).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mm::grid::Side;
+
+    use super::*;
+    use crate::private::TESTNET_URL;
+
+    fn stream() -> AccountStream {
+        AccountStream {
+            url: PRIVATE_WS_TESTNET.into(),
+            client: BybitPrivate::new(TESTNET_URL, "key", "secret", "ETHUSDT"),
+            base_coin: "ETH".into(),
+            quote_coin: "USDT".into(),
+        }
+    }
+
+    #[test]
+    fn order_push_keeps_only_own_spot_orders() {
+        let msg = r#"{"topic":"order","creationTime":1700000000000,"data":[
+            {"category":"spot","symbol":"ETHUSDT","orderId":"o1","orderLinkId":"mm-1",
+             "side":"Buy","price":"2000.5","qty":"0.1","cumExecQty":"0.04","orderStatus":"PartiallyFilled"},
+            {"category":"spot","symbol":"ETHUSDT","orderId":"o2","orderLinkId":"",
+             "side":"Sell","price":"2010","qty":"0.1","cumExecQty":"0.1","orderStatus":"Filled"},
+            {"category":"linear","symbol":"ETHUSDT","orderId":"o3","orderLinkId":"",
+             "side":"Sell","price":"2010","qty":"1","cumExecQty":"0","orderStatus":"New"},
+            {"category":"spot","symbol":"BTCUSDT","orderId":"o4","orderLinkId":"",
+             "side":"Sell","price":"60000","qty":"1","cumExecQty":"0","orderStatus":"New"}
+        ]}"#;
+        let events = stream().parse(msg).unwrap();
+        assert_eq!(events.len(), 2);
+        let AccountEvent::Order { order, open } = &events[0] else {
+            panic!("expected order, got {:?}", events[0]);
+        };
+        assert!(*open);
+        assert_eq!(
+            (order.order_id.as_str(), order.link_id.as_str()),
+            ("o1", "mm-1")
+        );
+        assert_eq!(order.side, Side::Buy);
+        assert_eq!(
+            (order.price, order.qty, order.filled),
+            (Price(2000.5), Qty(0.1), Qty(0.04))
+        );
+        assert!(matches!(
+            &events[1],
+            AccountEvent::Order { open: false, .. }
+        ));
+    }
+
+    #[test]
+    fn execution_push_becomes_fill_with_base_fee() {
+        let msg = r#"{"topic":"execution","data":[
+            {"category":"spot","symbol":"ETHUSDT","execId":"e1","orderId":"o1","side":"Buy",
+             "execPrice":"2000","execQty":"0.05","execFee":"0.00005","feeCurrency":"ETH",
+             "execTime":"1700000000123"}
+        ]}"#;
+        let events = stream().parse(msg).unwrap();
+        let [AccountEvent::Fill(f)] = events.as_slice() else {
+            panic!("expected one fill, got {:?}", events);
+        };
+        assert_eq!((f.exec_id.as_str(), f.order_id.as_str()), ("e1", "o1"));
+        assert_eq!(f.ts, TimestampMs(1_700_000_000_123));
+        assert_eq!(
+            (f.side, f.price, f.qty),
+            (Side::Buy, Price(2000.0), Qty(0.05))
+        );
+        assert_eq!(f.base_fee, Qty(0.00005));
+        assert!((f.fee.0 - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wallet_push_reports_pair_balances() {
+        let msg = r#"{"topic":"wallet","data":[
+            {"accountType":"UNIFIED","totalEquity":"3100.5","coin":[
+                {"coin":"ETH","walletBalance":"1.5","locked":"0.2"},
+                {"coin":"USDT","walletBalance":"100","locked":"25"},
+                {"coin":"BTC","walletBalance":"0.01","locked":"0"}
+            ]}
+        ]}"#;
+        let events = stream().parse(msg).unwrap();
+        let [AccountEvent::Wallet(w)] = events.as_slice() else {
+            panic!("expected one wallet, got {:?}", events);
+        };
+        assert_eq!(w.total_equity.0, 3100.5);
+        assert_eq!((w.base, w.base_locked), (Qty(1.5), Qty(0.2)));
+        assert_eq!((w.quote.0, w.quote_locked.0), (100.0, 25.0));
+    }
+
+    #[test]
+    fn service_and_unknown_topic_messages_are_ignored() {
+        let s = stream();
+        for msg in [
+            r#"{"op":"auth","success":true,"ret_msg":""}"#,
+            r#"{"op":"pong","args":["1700000000000"]}"#,
+            r#"{"topic":"position","data":[{"symbol":"ETHUSDT"}]}"#,
+            r#"{"topic":"order","data":{"not":"an array"}}"#,
+        ] {
+            assert!(s.parse(msg).unwrap().is_empty(), "{}", msg);
+        }
+    }
+
+    #[test]
+    fn malformed_messages_fail_and_bad_rows_are_skipped() {
+        let s = stream();
+        assert!(s.parse("not json").is_err());
+        let err = s
+            .parse(r#"{"op":"auth","success":false,"ret_msg":"invalid sign"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid sign"));
+
+        // строка без обязательных полей и строка с нечисловой ценой не мешают остальным
+        let msg = r#"{"topic":"order","data":[
+            {"category":"spot","symbol":"ETHUSDT","orderId":"o1"},
+            {"category":"spot","symbol":"ETHUSDT","orderId":"o2","orderLinkId":"",
+             "side":"Buy","price":"abc","qty":"0.1","cumExecQty":"0","orderStatus":"New"},
+            {"category":"spot","symbol":"ETHUSDT","orderId":"o3","orderLinkId":"",
+             "side":"Buy","price":"1999","qty":"0.1","cumExecQty":"0","orderStatus":"New"}
+        ]}"#;
+        let events = s.parse(msg).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(
+            matches!(&events[0], AccountEvent::Order { order, open: true } if order.order_id == "o3")
+        );
+    }
 }
@@ -44,6 +44,22 @@ impl Equity {
     }
 }
 
+/// Сторона агрессора публичной сделки
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// Публичная сделка на бирже
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Trade {
+    pub ts: TimestampMs,
+    pub price: Price,
+    pub qty: Qty,
+    pub side: TradeSide,
+}
+
 //
 // --- Conversions & helpers --------------------------------------------------
 //
@@ -0,0 +1,5 @@
+//! Локальное хранилище свечей (SQLite): backtest'ы читают и докачивают свечи
+//! через него вместо CSV-кэшей на каждый диапазон, пропуски серий докачиваются
+//! до старта прогона. Диапазоны и пропуски — общие с Postgres (`storage`).
+
+pub mod store;
@@ -0,0 +1,334 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use sqlx::{QueryBuilder, Sqlite};
+
+use core::types::{Price, Qty, TimestampMs};
+use storage::series::CandleSeries;
+use structure::candle::Candle;
+
+/// Сколько ждать блокировку файла, пока другой процесс дописывает свечи
+const BUSY_TIMEOUT: Duration = Duration::from_secs(120);
+/// Строк на один INSERT: 9 параметров на строку, лимит SQLite — 32766
+const INSERT_BATCH: usize = 2000;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS candles (
+    exchange TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    ts INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume REAL NOT NULL,
+    PRIMARY KEY (exchange, symbol, interval, ts)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS candle_ranges (
+    exchange TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS candle_ranges_series
+    ON candle_ranges (exchange, symbol, interval);
+CREATE TABLE IF NOT EXISTS candle_gaps (
+    exchange TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL
+);
+"#;
+
+/// Серия свечей: биржа, символ и интервал в минутах ("5", "60", "D")
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SeriesKey {
+    pub exchange: String,
+    pub symbol: String,
+    pub interval: String,
+}
+
+impl SeriesKey {
+    pub fn new(exchange: &str, symbol: &str, interval: &str) -> Self {
+        Self {
+            exchange: exchange.to_ascii_lowercase(),
+            symbol: symbol.to_ascii_uppercase(),
+            interval: interval.to_string(),
+        }
+    }
+}
+
+/// Свечи в одном SQLite-файле: `candles` по (exchange, symbol, interval, ts),
+/// `candle_ranges` — какие диапазоны серии уже скачаны, `candle_gaps` — пропуски,
+/// которых нет и у биржи (повторная докачка их не закрыла).
+/// Несколько процессов могут писать в один файл: WAL и ожидание блокировки.
+#[derive(Clone)]
+pub struct CandleStore {
+    pool: SqlitePool,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbCandle {
+    ts: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+impl CandleStore {
+    /// Открывает (или создаёт вместе с каталогом) файл хранилища
+    pub async fn open(path: &str) -> Result<Self> {
+        if let Some(dir) = Path::new(path).parent() {
+            if !dir.as_os_str().is_empty() {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("create dir {} failed", dir.display()))?;
+            }
+        }
+        let options = SqliteConnectOptions::from_str(path)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(BUSY_TIMEOUT);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .with_context(|| format!("open candle store {} failed", path))?;
+        Self::init(pool).await
+    }
+
+    /// Хранилище в памяти (тесты, разовые прогоны)
+    pub async fn in_memory() -> Result<Self> {
+        // у каждого соединения :memory: своя база — держим одно
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::init(pool).await
+    }
+
+    async fn init(pool: SqlitePool) -> Result<Self> {
+        sqlx::raw_sql(SCHEMA)
+            .execute(&pool)
+            .await
+            .context("candle store schema failed")?;
+        Ok(Self { pool })
+    }
+
+    /// Серия `key` этого хранилища
+    pub fn series(&self, key: SeriesKey) -> LocalSeries {
+        LocalSeries {
+            pool: self.pool.clone(),
+            key,
+        }
+    }
+}
+
+/// Серия свечей в локальном хранилище
+pub struct LocalSeries {
+    pool: SqlitePool,
+    key: SeriesKey,
+}
+
+impl CandleSeries for LocalSeries {
+    async fn ranges(&mut self, range: (i64, i64)) -> Result<Vec<(i64, i64)>> {
+        let key = &self.key;
+        sqlx::query_as(
+            r#"
+            SELECT start_ms, end_ms
+            FROM candle_ranges
+            WHERE exchange = ? AND symbol = ? AND interval = ? AND end_ms >= ? AND start_ms <= ?
+            "#,
+        )
+        .bind(&key.exchange)
+        .bind(&key.symbol)
+        .bind(&key.interval)
+        .bind(range.0)
+        .bind(range.1)
+        .fetch_all(&self.pool)
+        .await
+        .context("candle ranges query failed")
+    }
+
+    /// Upsert свечей и отметка `range` как скачанного одной транзакцией
+    async fn save(&mut self, range: (i64, i64), candles: &[Candle]) -> Result<()> {
+        let key = &self.key;
+        let mut tx = self.pool.begin().await?;
+        for chunk in candles.chunks(INSERT_BATCH) {
+            let mut q: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR REPLACE INTO candles \
+                 (exchange, symbol, interval, ts, open, high, low, close, volume) ",
+            );
+            q.push_values(chunk, |mut row, c| {
+                row.push_bind(&key.exchange)
+                    .push_bind(&key.symbol)
+                    .push_bind(&key.interval)
+                    .push_bind(c.ts.0)
+                    .push_bind(c.open.0)
+                    .push_bind(c.high.0)
+                    .push_bind(c.low.0)
+                    .push_bind(c.close.0)
+                    .push_bind(c.volume.0);
+            });
+            q.build()
+                .execute(&mut *tx)
+                .await
+                .context("candles upsert failed")?;
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO candle_ranges (exchange, symbol, interval, start_ms, end_ms)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&key.exchange)
+        .bind(&key.symbol)
+        .bind(&key.interval)
+        .bind(range.0)
+        .bind(range.1)
+        .execute(&mut *tx)
+        .await
+        .context("candle range insert failed")?;
+        tx.commit().await.context("candle store commit failed")
+    }
+
+    async fn known_gaps(&mut self, range: (i64, i64)) -> Result<Vec<(i64, i64)>> {
+        let key = &self.key;
+        sqlx::query_as(
+            r#"
+            SELECT start_ms, end_ms
+            FROM candle_gaps
+            WHERE exchange = ? AND symbol = ? AND interval = ? AND end_ms >= ? AND start_ms <= ?
+            "#,
+        )
+        .bind(&key.exchange)
+        .bind(&key.symbol)
+        .bind(&key.interval)
+        .bind(range.0)
+        .bind(range.1)
+        .fetch_all(&self.pool)
+        .await
+        .context("candle gaps query failed")
+    }
+
+    async fn add_known_gaps(&mut self, gaps: &[(i64, i64)]) -> Result<()> {
+        let key = &self.key;
+        let mut tx = self.pool.begin().await?;
+        for &(start_ms, end_ms) in gaps {
+            sqlx::query(
+                r#"
+                INSERT INTO candle_gaps (exchange, symbol, interval, start_ms, end_ms)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&key.exchange)
+            .bind(&key.symbol)
+            .bind(&key.interval)
+            .bind(start_ms)
+            .bind(end_ms)
+            .execute(&mut *tx)
+            .await
+            .context("candle gap insert failed")?;
+        }
+        tx.commit().await.context("candle store commit failed")
+    }
+
+    async fn load(&mut self, (start_ms, end_ms): (i64, i64)) -> Result<Vec<Candle>> {
+        let key = &self.key;
+        let rows: Vec<DbCandle> = sqlx::query_as(
+            r#"
+            SELECT ts, open, high, low, close, volume
+            FROM candles
+            WHERE exchange = ? AND symbol = ? AND interval = ? AND ts BETWEEN ? AND ?
+            ORDER BY ts
+            "#,
+        )
+        .bind(&key.exchange)
+        .bind(&key.symbol)
+        .bind(&key.interval)
+        .bind(start_ms)
+        .bind(end_ms)
+        .fetch_all(&self.pool)
+        .await
+        .context("candles query failed")?;
+        Ok(rows
+            .into_iter()
+            .map(|r| Candle {
+                ts: TimestampMs(r.ts),
+                open: Price(r.open),
+                high: Price(r.high),
+                low: Price(r.low),
+                close: Price(r.close),
+                volume: Qty(r.volume),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(ts: i64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(close),
+            high: Price(close),
+            low: Price(close),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn appends_loads_and_finds_missing_ranges() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let store = CandleStore::in_memory().await.unwrap();
+            let key = SeriesKey::new("Bybit", "ethusdt", "1");
+            assert_eq!(key.symbol, "ETHUSDT");
+            let mut series = store.series(key);
+            let mut other = store.series(SeriesKey::new("binance", "ETHUSDT", "1"));
+
+            assert_eq!(
+                series.missing((0, 599_999)).await.unwrap(),
+                vec![(0, 599_999)]
+            );
+            let first: Vec<Candle> = (0..5).map(|i| candle(i * 60_000, 1.0)).collect();
+            series.save((0, 299_999), &first).await.unwrap();
+            assert_eq!(
+                series.missing((0, 599_999)).await.unwrap(),
+                vec![(300_000, 599_999)]
+            );
+            // другая биржа — отдельная серия
+            assert_eq!(other.missing((0, 299_999)).await.unwrap().len(), 1);
+            assert!(other.load((0, 599_999)).await.unwrap().is_empty());
+
+            // дозапись перекрывающегося куска заменяет свечу
+            let second: Vec<Candle> = (4..10).map(|i| candle(i * 60_000, 2.0)).collect();
+            series.save((240_000, 599_999), &second).await.unwrap();
+            assert!(series.missing((0, 599_999)).await.unwrap().is_empty());
+
+            let all = series.load((60_000, 599_999)).await.unwrap();
+            assert_eq!(all.len(), 9);
+            assert_eq!(all[0].ts, TimestampMs(60_000));
+            assert_eq!(all[3].close, Price(2.0));
+
+            series.add_known_gaps(&[(120_000, 179_999)]).await.unwrap();
+            assert_eq!(
+                series.known_gaps((0, 599_999)).await.unwrap(),
+                vec![(120_000, 179_999)]
+            );
+            assert!(other.known_gaps((0, 599_999)).await.unwrap().is_empty());
+        });
+    }
+}
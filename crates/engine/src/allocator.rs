@@ -0,0 +1,69 @@
+use clap::ValueEnum;
+
+/// Стратегии, которые engine умеет крутить одновременно
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum StrategyKind {
+    Mm,
+    Trend,
+}
+
+/// Стартовый под-счёт стратегии
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Allocation {
+    pub strategy: StrategyKind,
+    pub quote: f64,
+    pub base: f64,
+}
+
+/// Делит стартовый капитал между стратегиями.
+///
+/// Trend получает `trend_share` quote и стартует flat (long-only, без
+/// цены входа чужой base ему не нужен). MM — остальной quote и весь base.
+/// Если стратегия одна — ей весь капитал.
+pub fn allocate(
+    strategies: &[StrategyKind],
+    quote: f64,
+    base: f64,
+    trend_share: f64,
+) -> Vec<Allocation> {
+    let has_mm = strategies.contains(&StrategyKind::Mm);
+    let has_trend = strategies.contains(&StrategyKind::Trend);
+    let share = match (has_mm, has_trend) {
+        (true, true) => trend_share.clamp(0.0, 1.0),
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+
+    let mut out = Vec::new();
+    if has_mm {
+        out.push(Allocation {
+            strategy: StrategyKind::Mm,
+            quote: quote * (1.0 - share),
+            base,
+        });
+    }
+    if has_trend {
+        out.push(Allocation {
+            strategy: StrategyKind::Trend,
+            quote: quote * share,
+            base: if has_mm { 0.0 } else { base },
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quote_and_keeps_base_with_mm() {
+        let a = allocate(&[StrategyKind::Mm, StrategyKind::Trend], 1000.0, 0.5, 0.3);
+        assert_eq!(a.len(), 2);
+        assert_eq!((a[0].quote, a[0].base), (700.0, 0.5));
+        assert_eq!((a[1].quote, a[1].base), (300.0, 0.0));
+
+        let a = allocate(&[StrategyKind::Trend], 1000.0, 0.0, 0.3);
+        assert_eq!(a[0].quote, 1000.0);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+use clap::ValueEnum;
+use core::types::Price;
+use structure::bos::{BosState, BosTracker};
+use structure::candle::Candle;
+
+/// Вокруг чего строится сетка
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum)]
+pub enum AnchorMode {
+    /// Текущий mid (книги, без неё — close последней HTF свечи)
+    #[default]
+    Mid,
+    /// Уровень подтверждённого BOS
+    BosLevel,
+    /// Цена последнего исполнения
+    LastFill,
+    /// VWAP последних HTF свечей
+    Vwap,
+}
+
+/// Якорь сетки, живёт в `EngineCtx` между тиками.
+///
+/// Пока у выбранного режима нет данных (BOS ещё не подтверждён,
+/// не было fill'ов, пустое окно VWAP) — якорь = mid.
+#[derive(Debug, Clone)]
+pub struct AnchorState {
+    pub mode: AnchorMode,
+    /// Якорь последнего тика
+    pub current: Option<Price>,
+    last_fill: Option<Price>,
+    vwap_window: usize,
+    /// (typical_price * volume, volume) по свечам окна
+    vwap_parts: VecDeque<(f64, f64)>,
+}
+
+impl Default for AnchorState {
+    fn default() -> Self {
+        Self::new(AnchorMode::Mid, 20)
+    }
+}
+
+impl AnchorState {
+    pub fn new(mode: AnchorMode, vwap_window: usize) -> Self {
+        Self {
+            mode,
+            current: None,
+            last_fill: None,
+            vwap_window: vwap_window.max(1),
+            vwap_parts: VecDeque::with_capacity(vwap_window.max(1) + 1),
+        }
+    }
+
+    pub fn on_candle_close(&mut self, c: &Candle) {
+        let typical = (c.high.0 + c.low.0 + c.close.0) / 3.0;
+        self.vwap_parts
+            .push_back((typical * c.volume.0, c.volume.0));
+        while self.vwap_parts.len() > self.vwap_window {
+            self.vwap_parts.pop_front();
+        }
+    }
+
+    pub fn on_fill(&mut self, price: Price) {
+        self.last_fill = Some(price);
+    }
+
+    /// Забыть накопленные данные режима: до новых данных якорь = mid
+    pub fn recenter(&mut self) {
+        self.current = None;
+        self.last_fill = None;
+        self.vwap_parts.clear();
+    }
+
+    pub fn vwap(&self) -> Option<Price> {
+        let (pv, v) = self
+            .vwap_parts
+            .iter()
+            .fold((0.0, 0.0), |(pv, v), (x, y)| (pv + x, v + y));
+        if v > 0.0 { Some(Price(pv / v)) } else { None }
+    }
+
+    /// Пересчитывает якорь на тике
+    pub fn update(&mut self, mid: Price, bos: &BosTracker) -> Price {
+        let anchor = match self.mode {
+            AnchorMode::Mid => None,
+            AnchorMode::BosLevel => match bos.state {
+                BosState::Confirmed => bos.level,
+                _ => None,
+            },
+            AnchorMode::LastFill => self.last_fill,
+            AnchorMode::Vwap => self.vwap(),
+        }
+        .unwrap_or(mid);
+
+        self.current = Some(anchor);
+        anchor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Qty, TimestampMs};
+
+    fn candle(price: f64, volume: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(0),
+            open: Price(price),
+            high: Price(price),
+            low: Price(price),
+            close: Price(price),
+            volume: Qty(volume),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_mid_until_mode_has_data() {
+        let bos = BosTracker::new();
+
+        let mut a = AnchorState::new(AnchorMode::LastFill, 10);
+        assert_eq!(a.update(Price(100.0), &bos), Price(100.0));
+        a.on_fill(Price(95.0));
+        assert_eq!(a.update(Price(100.0), &bos), Price(95.0));
+
+        let mut a = AnchorState::new(AnchorMode::BosLevel, 10);
+        let mut bos = BosTracker::new();
+        bos.level = Some(Price(90.0));
+        assert_eq!(a.update(Price(100.0), &bos), Price(100.0));
+        bos.state = BosState::Confirmed;
+        assert_eq!(a.update(Price(100.0), &bos), Price(90.0));
+        assert_eq!(a.current, Some(Price(90.0)));
+    }
+
+    #[test]
+    fn vwap_uses_last_window_candles() {
+        let mut a = AnchorState::new(AnchorMode::Vwap, 2);
+        a.on_candle_close(&candle(1000.0, 100.0));
+        a.on_candle_close(&candle(100.0, 1.0));
+        a.on_candle_close(&candle(200.0, 3.0));
+
+        // первая свеча вышла из окна: (100*1 + 200*3) / 4
+        assert_eq!(a.vwap(), Some(Price(175.0)));
+        assert_eq!(a.update(Price(1.0), &BosTracker::new()), Price(175.0));
+    }
+}
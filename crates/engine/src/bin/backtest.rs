@@ -1,20 +1,25 @@
-use anyhow::{Context, Result};
-use chrono::{NaiveDate, TimeZone, Utc};
+use anyhow::Result;
 use clap::Parser;
-
-use bybit::rest::{BybitRest, download_range};
-use core::types::{Bps, Money, Price, Qty, Ratio};
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::data::{Venue, date_range_ms, load_candles, parse_interval_ms};
+use backtest::quality::{QualityGate, QualityMode};
+use backtest::report::JsonReport;
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use core::types::{Bps, Money, Qty, Ratio};
 use engine::feed::CandleFeed;
-use engine::sink;
+use engine::sink::{EventSink, TextSink};
 use engine::tick::{EngineCtx, TickInput, tick};
-use mm::grid::{GridParams, Inventory};
-use policy::mm_policy::MmPolicyParams;
+use mm::grid::{GridParams, Inventory, LotFilters};
+use mm::rebalance::RebalanceParams;
+use policy::mm_policy::{DataQuality, MmPolicyParams};
 use state_machine::state::BotState;
 use structure::bos::BosParams;
 use structure::pullback::PullbackParams;
 use structure::structure::{StructureParams, detect_structure};
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 struct Args {
     #[arg(long)]
     symbol: String,
@@ -24,84 +29,74 @@ struct Args {
     start: String,
     #[arg(long)]
     end: String,
-    #[arg(long, default_value = "data/backtest.csv")]
-    cache: String,
+    /// Кэш свечей, CSV или `.parquet` (feature `parquet`);
+    /// по умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    cache: Option<String>,
     #[arg(long, default_value_t = false)]
     refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг и счётчики прогона
+    #[arg(long)]
+    report_out: Option<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct CandleRow {
-    ts: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-fn date_to_ms(date: &str) -> Result<i64> {
-    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .with_context(|| format!("bad date: {}", date))?;
-    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
-    Ok(dt.timestamp_millis())
-}
-
-fn read_cache(path: &str) -> Result<Vec<structure::candle::Candle>> {
-    let mut rdr = csv::Reader::from_path(path)?;
-    let mut out = Vec::new();
-
-    for r in rdr.deserialize::<CandleRow>() {
-        let row = r?;
-        out.push(structure::candle::Candle {
-            ts: core::types::TimestampMs(row.ts),
-            open: Price(row.open),
-            high: Price(row.high),
-            low: Price(row.low),
-            close: Price(row.close),
-            volume: Qty(row.volume),
-        });
-    }
-
-    Ok(out)
-}
-
-fn write_cache(path: &str, candles: &[structure::candle::Candle]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let mut wtr = csv::Writer::from_path(path)?;
-    for c in candles {
-        wtr.serialize(CandleRow {
-            ts: c.ts.0,
-            open: c.open.0,
-            high: c.high.0,
-            low: c.low.0,
-            close: c.close.0,
-            volume: c.volume.0,
-        })?;
-    }
-    wtr.flush()?;
-    Ok(())
+#[derive(Serialize)]
+struct TickMetrics {
+    candles: usize,
+    ticks: usize,
+    final_state: String,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
-
-    let start_ms = date_to_ms(&args.start)?;
-    let end_ms = date_to_ms(&args.end)? + 24 * 60 * 60 * 1000 - 1;
-
-    let candles = if !args.refresh && std::path::Path::new(&args.cache).exists() {
-        read_cache(&args.cache).context("read cache failed")?
-    } else {
-        let api = BybitRest::new();
-        let data = download_range(&api, &args.symbol, &args.interval, start_ms, end_ms)
-            .await
-            .context("download range failed")?;
-        write_cache(&args.cache, &data).context("write cache failed")?;
-        data
+    let args: Args = parse_args()?;
+
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    let candles = match synthetic {
+        Some(p) => synthetic_candles(&p, parse_interval_ms(&args.interval)?, range, args.seed),
+        None => {
+            load_candles(
+                args.exchange,
+                &args.symbol,
+                &args.interval,
+                range,
+                args.cache.as_deref(),
+                args.refresh,
+                gate,
+            )
+            .await?
+        }
     };
 
     if candles.len() < 10 {
@@ -127,6 +122,14 @@ async fn main() -> Result<()> {
         hard_min: Ratio(0.35),
         hard_max: Ratio(0.65),
         min_base_qty: Qty(0.0001),
+        filters: LotFilters::default(),
+    };
+
+    let rebalance = RebalanceParams {
+        target_base_ratio: Ratio(0.50),
+        tolerance: Ratio(0.02),
+        fee_rate: Ratio(0.001),
+        min_quote_trade: Money(5.0),
     };
 
     let bos_params = BosParams {
@@ -143,6 +146,7 @@ async fn main() -> Result<()> {
         BotState::IdleUSDT,
         mm_policy,
         grid,
+        rebalance,
         bos_params,
         pullback_params,
     );
@@ -159,10 +163,13 @@ async fn main() -> Result<()> {
         quote: Money(1000.0),
     };
 
+    let mut sink = TextSink;
     let mut n_ticks = 0usize;
+    let n_candles = candles.len();
 
     for c in candles {
         feed.push(c);
+        ctx.anchor.on_candle_close(&c);
 
         let (Some(atr), Some(mid)) = (feed.atr(), feed.mid()) else {
             continue;
@@ -176,19 +183,29 @@ async fn main() -> Result<()> {
             .on_candle_close(last, &ctx.bos, atr, ctx.pullback_params);
 
         let input = TickInput {
+            ts: last.ts,
             mid,
             atr,
             inv,
             ltf_broken_down: false,
             ltf_recovered: false,
+            data_quality: DataQuality::Good,
         };
 
         let events = tick(&mut ctx, input);
-        sink::consume(events);
+        sink.consume(&events)?;
 
         n_ticks += 1;
     }
 
     println!("Backtest ticks processed: {}", n_ticks);
+    let metrics = TickMetrics {
+        candles: n_candles,
+        ticks: n_ticks,
+        final_state: format!("{:?}", ctx.state),
+    };
+    JsonReport::new("backtest", &args, metrics)
+        .with_seed(args.seed)
+        .finish(args.report_out.as_deref())?;
     Ok(())
 }
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::diff::{equity_diff, first_divergence, read_equity, read_fills, unmatched_fills};
+use backtest::report::{JsonReport, write_csv};
+
+/// Сравнение двух прогонов по их артефактам (fills/trades и equity CSV):
+/// исполнения только в одном из прогонов, расхождение PnL во времени
+/// и первая точка, где разошлись equity. Для проверки рефакторингов ядра backtest.
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    /// Fills (MM) или trades (trend) CSV прогона A
+    #[arg(long)]
+    a_fills: Option<String>,
+    #[arg(long)]
+    b_fills: Option<String>,
+    /// Equity CSV прогона A
+    #[arg(long)]
+    a_equity: Option<String>,
+    #[arg(long)]
+    b_equity: Option<String>,
+    /// Допуск qty и цены при сопоставлении исполнений, относительный
+    #[arg(long, default_value_t = 1e-9)]
+    fill_tolerance: f64,
+    /// Equity считаются разошедшимися при разнице больше допуска, quote
+    #[arg(long, default_value_t = 1e-6)]
+    equity_tolerance: f64,
+
+    /// Исполнения без пары в другом прогоне
+    #[arg(long, default_value = "data/backtest_diff_fills.csv")]
+    fills_out: String,
+    /// Equity и PnL обоих прогонов в общих точках и их разница
+    #[arg(long, default_value = "data/backtest_diff_equity.csv")]
+    equity_out: String,
+    /// JSON отчёт: пути, расхождения, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Metrics {
+    fills_a: usize,
+    fills_b: usize,
+    a_only_fills: usize,
+    b_only_fills: usize,
+    first_fill_divergence_ts: Option<i64>,
+    equity_points: usize,
+    first_equity_divergence_ts: Option<i64>,
+    max_abs_equity_diff: f64,
+    final_equity_diff: f64,
+    identical: bool,
+}
+
+/// Пара путей A/B: обе или ни одной
+fn pair(a: &Option<String>, b: &Option<String>, name: &str) -> Result<Option<(String, String)>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Ok(Some((a.clone(), b.clone()))),
+        (None, None) => Ok(None),
+        _ => anyhow::bail!("--a-{name} and --b-{name} must be set together"),
+    }
+}
+
+fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.fill_tolerance < 0.0 || args.equity_tolerance < 0.0 {
+        anyhow::bail!("tolerances must be non-negative");
+    }
+    let fills = pair(&args.a_fills, &args.b_fills, "fills")?;
+    let equity = pair(&args.a_equity, &args.b_equity, "equity")?;
+    if fills.is_none() && equity.is_none() {
+        anyhow::bail!("nothing to compare: set --a-fills/--b-fills and/or --a-equity/--b-equity");
+    }
+
+    let mut metrics = Metrics::default();
+    if let Some((a, b)) = &fills {
+        let (fa, fb) = (read_fills(a)?, read_fills(b)?);
+        let rows = unmatched_fills(&fa, &fb, args.fill_tolerance);
+        write_csv(&args.fills_out, &rows).context("write fills diff failed")?;
+        metrics.fills_a = fa.len();
+        metrics.fills_b = fb.len();
+        metrics.a_only_fills = rows.iter().filter(|r| r.run == "a_only").count();
+        metrics.b_only_fills = rows.len() - metrics.a_only_fills;
+        metrics.first_fill_divergence_ts = rows.first().map(|r| r.ts);
+    }
+    if let Some((a, b)) = &equity {
+        let rows = equity_diff(&read_equity(a)?, &read_equity(b)?);
+        write_csv(&args.equity_out, &rows).context("write equity diff failed")?;
+        metrics.equity_points = rows.len();
+        metrics.first_equity_divergence_ts =
+            first_divergence(&rows, args.equity_tolerance).map(|r| r.ts);
+        metrics.max_abs_equity_diff = rows.iter().map(|r| r.diff.abs()).fold(0.0, f64::max);
+        metrics.final_equity_diff = rows.last().map_or(0.0, |r| r.diff);
+    }
+    metrics.identical = metrics.a_only_fills + metrics.b_only_fills == 0
+        && metrics.first_equity_divergence_ts.is_none();
+
+    println!("run diff finished");
+    if fills.is_some() {
+        println!(
+            "fills: a={} b={} a_only={} b_only={} first_divergence_ts={}",
+            metrics.fills_a,
+            metrics.fills_b,
+            metrics.a_only_fills,
+            metrics.b_only_fills,
+            metrics
+                .first_fill_divergence_ts
+                .map_or("none".to_string(), |ts| ts.to_string())
+        );
+    }
+    if equity.is_some() {
+        println!(
+            "equity: points={} first_divergence_ts={} max_abs_diff={:.6} final_diff={:.6}",
+            metrics.equity_points,
+            metrics
+                .first_equity_divergence_ts
+                .map_or("none".to_string(), |ts| ts.to_string()),
+            metrics.max_abs_equity_diff,
+            metrics.final_equity_diff
+        );
+    }
+    println!("identical={}", metrics.identical);
+
+    let mut report = JsonReport::new("backtest_diff", &args, &metrics);
+    if fills.is_some() {
+        report = report.artifact("fills_diff_csv", &args.fills_out);
+    }
+    if equity.is_some() {
+        report = report.artifact("equity_diff_csv", &args.equity_out);
+    }
+    report.finish(args.report_out.as_deref())?;
+    Ok(())
+}
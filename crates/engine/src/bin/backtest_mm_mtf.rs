@@ -1,18 +1,29 @@
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, TimeZone, Utc};
 use clap::Parser;
-
-use bybit::rest::{BybitRest, download_range};
-use core::types::{Bps, Money, Price, Qty, Ratio};
-use engine::feed::CandleFeed;
+use serde::Serialize;
+
+use backtest::breakdown::{breakdown_summary, monthly_breakdown};
+use backtest::config::parse_args;
+use backtest::data::{Venue, date_range_ms, ensure_ltf_coverage, load_candles, parse_interval_ms};
+use backtest::html::HtmlData;
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmReport, MmRunConfig,
+    SignalParams, run_mm_mtf_trades, run_mm_mtf_with_funding,
+};
+use backtest::montecarlo::{McMode, McParams, McSummary, McTrade, simulate};
+use backtest::perp::{MarketKind, PerpParams, load_funding};
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{JsonReport, underwater_rows, write_csv};
+use backtest::stats::Performance;
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use backtest::trades::{DataSource, candles_from_trades, load_trades};
 use execution::sim::ExecutionModel;
-use mm::grid::{GridParams, Inventory, Side, build_grid};
-use policy::mm_policy::{MmDecisionReason, MmMode, MmPolicyParams, mm_policy_decision};
-use structure::bos::{BosParams, BosState, BosTracker};
-use structure::pullback::{PullbackParams, PullbackTracker};
-use structure::structure::{StructureParams, detect_structure};
+use structure::bos::BosParams;
+use structure::candle::resample_ms;
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 struct Args {
     #[arg(long)]
     symbol: String,
@@ -24,17 +35,73 @@ struct Args {
     start: String,
     #[arg(long)]
     end: String,
-    #[arg(long, default_value = "data/backtest_mm_mtf_htf.csv")]
-    htf_cache: String,
-    #[arg(long, default_value = "data/backtest_mm_mtf_ltf.csv")]
-    ltf_cache: String,
+    #[arg(long, default_value_t = 1)]
+    pivot_k: usize,
+    #[arg(long, default_value_t = 0.1)]
+    min_atr_frac: f64,
+    #[arg(long, default_value_t = 2)]
+    bos_confirm_candles: usize,
+    #[arg(long, default_value_t = 0.1)]
+    bos_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.1)]
+    pullback_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.4)]
+    pullback_retrace_frac: f64,
+
+    /// Кэш LTF свечей, CSV или `.parquet` (feature `parquet`); HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
+    /// Исполнение сетки: касание LTF свечи или исторические сделки
+    #[arg(long, value_enum, default_value_t = DataSource::Candles)]
+    data: DataSource,
+    /// Кэш сделок для `--data trades` (LTF свечи строятся из них)
+    #[arg(long)]
+    trades_cache: Option<String>,
     #[arg(long, default_value_t = false)]
     refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
 
     #[arg(long, default_value_t = 1000.0)]
     initial_quote: f64,
     #[arg(long, default_value_t = 0.0)]
     initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи (0.5 — поровну);
+    /// делает прогоны сравнимыми между символами и периодами
+    #[arg(long)]
+    initial_base_ratio: Option<f64>,
+    /// spot или perp (линейный контракт: плечо, funding, ликвидация)
+    #[arg(long, value_enum, default_value_t = MarketKind::Spot)]
+    market: MarketKind,
+    /// Плечо perp: notional позиции до `equity·leverage`
+    #[arg(long, default_value_t = 1.0)]
+    leverage: f64,
+    /// Поддерживающая маржа perp, доля notional позиции
+    #[arg(long, default_value_t = 0.005)]
+    maintenance_margin_rate: f64,
+    /// Кэш funding (CSV); по умолчанию `data/cache/<SYMBOL>_funding_<start>_<end>.csv`
+    #[arg(long)]
+    funding_cache: Option<String>,
 
     #[arg(long, default_value_t = 5)]
     levels: usize,
@@ -66,6 +133,12 @@ struct Args {
     force_close_slippage_bps: f64,
     #[arg(long, default_value_t = true)]
     force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
     #[arg(long, default_value_t = 1.5)]
     defensive_step_mult: f64,
     #[arg(long, default_value_t = 0.5)]
@@ -74,555 +147,299 @@ struct Args {
     bootstrap_rebalance: bool,
     #[arg(long, default_value_t = 0.50)]
     bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
 
     #[arg(long, default_value = "data/backtest_mm_mtf_equity.csv")]
     equity_out: String,
+    /// Underwater-кривая: глубина просадки и время под пиком equity
+    #[arg(long, default_value = "data/backtest_mm_mtf_underwater.csv")]
+    underwater_out: String,
+    /// PnL, ROI и число сделок по месяцам (UTC)
+    #[arg(long, default_value = "data/backtest_mm_mtf_monthly.csv")]
+    monthly_out: String,
     #[arg(long, default_value = "data/backtest_mm_mtf_fills.csv")]
     fills_out: String,
+    /// Monte Carlo по закрытым сделкам: прогонов пересборки (0 — выключено)
+    #[arg(long, default_value_t = 0)]
+    mc_runs: usize,
+    /// Monte Carlo: bootstrap — сделки с возвращением, shuffle — только их порядок
+    #[arg(long, value_enum, default_value_t = McMode::Bootstrap)]
+    mc_mode: McMode,
+    /// Monte Carlo: случайное доп. проскальзывание сделки, от 0 до N bps её оборота
+    #[arg(long, default_value_t = 0.0)]
+    mc_slippage_bps: f64,
+    /// Monte Carlo: ROI, просадка и PF каждого прогона
+    #[arg(long, default_value = "data/backtest_mm_mtf_mc.csv")]
+    mc_out: String,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг, метрики, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct CandleRow {
-    ts: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-#[derive(serde::Serialize)]
-struct EquityRow {
-    ts: i64,
-    close: f64,
-    mode: String,
-    quote: f64,
-    base: f64,
-    cost_basis_quote: f64,
-    equity: f64,
-    drawdown_pct: f64,
-}
-
-#[derive(serde::Serialize)]
-struct FillRow {
-    ts: i64,
-    side: String,
-    mode: String,
-    qty: f64,
-    price: f64,
-    fee_quote: f64,
-    quote_delta: f64,
-    realized_pnl: Option<f64>,
-}
-
-fn parse_interval_ms(interval: &str) -> Result<i64> {
-    let mins: i64 = interval
-        .parse()
-        .with_context(|| format!("interval must be numeric minutes, got {}", interval))?;
-    if mins <= 0 {
-        anyhow::bail!("interval must be > 0");
-    }
-    Ok(mins * 60 * 1000)
-}
-
-fn date_to_ms(date: &str) -> Result<i64> {
-    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .with_context(|| format!("bad date: {}", date))?;
-    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
-    Ok(dt.timestamp_millis())
-}
-
-fn read_cache(path: &str) -> Result<Vec<structure::candle::Candle>> {
-    let mut rdr = csv::Reader::from_path(path)?;
-    let mut out = Vec::new();
-    for r in rdr.deserialize::<CandleRow>() {
-        let row = r?;
-        out.push(structure::candle::Candle {
-            ts: core::types::TimestampMs(row.ts),
-            open: Price(row.open),
-            high: Price(row.high),
-            low: Price(row.low),
-            close: Price(row.close),
-            volume: Qty(row.volume),
-        });
-    }
-    Ok(out)
-}
-
-fn write_cache(path: &str, candles: &[structure::candle::Candle]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for c in candles {
-        wtr.serialize(CandleRow {
-            ts: c.ts.0,
-            open: c.open.0,
-            high: c.high.0,
-            low: c.low.0,
-            close: c.close.0,
-            volume: c.volume.0,
-        })?;
-    }
-    wtr.flush()?;
-    Ok(())
-}
-
-fn write_equity_csv(path: &str, rows: &[EquityRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
-    }
-    wtr.flush()?;
-    Ok(())
-}
-
-fn write_fills_csv(path: &str, rows: &[FillRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
-    }
-    wtr.flush()?;
-    Ok(())
+/// Метрики прогона и граница оптимизма fill'ов внутри свечи
+#[derive(Debug, Serialize)]
+struct Metrics<'a> {
+    #[serde(flatten)]
+    report: &'a MmReport,
+    /// Тот же прогон с `--intrabar-path worst-case`
+    worst_case: Option<Performance>,
+    /// Интервалы ROI/просадки/PF по Monte Carlo (`--mc-runs`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mc: Option<McSummary>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let args: Args = parse_args()?;
     if args.initial_quote < 0.0 || args.initial_base < 0.0 {
         anyhow::bail!("initial balances must be non-negative");
     }
-    if !(0.0 <= args.hard_min
-        && args.hard_min <= args.soft_min
-        && args.soft_min <= args.soft_max
-        && args.soft_max <= args.hard_max
-        && args.hard_max <= 1.0)
+    if args
+        .initial_base_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
     {
-        anyhow::bail!("invalid bands: expected hard_min <= soft_min <= soft_max <= hard_max");
+        anyhow::bail!("initial_base_ratio must be in [0, 1]");
     }
+    let params = MmParams {
+        levels: args.levels,
+        step_bps: args.step_bps,
+        base_quote_per_order: args.base_quote_per_order,
+        max_size_mult: args.max_size_mult,
+        min_base_qty: args.min_base_qty,
+        soft_min: args.soft_min,
+        soft_max: args.soft_max,
+        hard_min: args.hard_min,
+        hard_max: args.hard_max,
+        maker_fee_bps: args.maker_fee_bps,
+        defensive_step_mult: args.defensive_step_mult,
+        defensive_size_mult: args.defensive_size_mult,
+        signal: SignalParams {
+            structure: StructureParams {
+                pivot_k: args.pivot_k,
+                min_atr_frac: args.min_atr_frac,
+            },
+            bos: BosParams {
+                confirm_candles: args.bos_confirm_candles,
+                epsilon_frac: args.bos_epsilon_frac,
+            },
+            pullback: PullbackParams {
+                epsilon_frac: args.pullback_epsilon_frac,
+                retrace_frac: args.pullback_retrace_frac,
+            },
+        },
+    };
+    params.validate_bands()?;
+    params.signal.validate()?;
 
     let htf_ms = parse_interval_ms(&args.htf_interval)?;
-
-    let start_ms = date_to_ms(&args.start)?;
-    let end_ms = date_to_ms(&args.end)? + 24 * 60 * 60 * 1000 - 1;
-
-    let api = BybitRest::new();
-    let htf = if !args.refresh && std::path::Path::new(&args.htf_cache).exists() {
-        read_cache(&args.htf_cache).context("read htf cache failed")?
-    } else {
-        let data = download_range(&api, &args.symbol, &args.htf_interval, start_ms, end_ms)
-            .await
-            .context("download htf failed")?;
-        write_cache(&args.htf_cache, &data).context("write htf cache failed")?;
-        data
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let mc_params = McParams {
+        runs: args.mc_runs,
+        mode: args.mc_mode,
+        slippage_bps: args.mc_slippage_bps,
+        seed: args.seed,
     };
-    let ltf = if !args.refresh && std::path::Path::new(&args.ltf_cache).exists() {
-        read_cache(&args.ltf_cache).context("read ltf cache failed")?
-    } else {
-        let data = download_range(&api, &args.symbol, &args.ltf_interval, start_ms, end_ms)
-            .await
-            .context("download ltf failed")?;
-        write_cache(&args.ltf_cache, &data).context("write ltf cache failed")?;
-        data
+    if args.mc_runs > 0 {
+        mc_params.validate()?;
+    }
+    let range = date_range_ms(&args.start, &args.end)?;
+
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+    let perp = PerpParams::for_market(args.market, args.leverage, args.maintenance_margin_rate)?;
+    if perp.is_some() && args.data == DataSource::Trades {
+        anyhow::bail!("--market perp supports --data candles only");
+    }
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    if synthetic.is_some() && args.data == DataSource::Trades {
+        anyhow::bail!("--synthetic supports --data candles only");
+    }
+    let synthetic_ltf = synthetic.map(|p| synthetic_candles(&p, ltf_ms, range, args.seed));
+    let trades = match args.data {
+        DataSource::Candles => Vec::new(),
+        DataSource::Trades => {
+            load_trades(
+                &args.symbol,
+                range,
+                args.trades_cache.as_deref(),
+                args.refresh,
+            )
+            .await?
+        }
     };
+    let ltf = match (args.data, synthetic_ltf) {
+        (_, Some(ltf)) => ltf,
+        (DataSource::Candles, None) => {
+            load_candles(
+                args.exchange,
+                &args.symbol,
+                &args.ltf_interval,
+                range,
+                args.ltf_cache.as_deref(),
+                args.refresh,
+                gate,
+            )
+            .await?
+        }
+        (DataSource::Trades, None) => candles_from_trades(&trades, ltf_ms),
+    };
+    // качается только LTF серия, HTF собирается из неё
+    let htf = resample_ms(&ltf, htf_ms);
 
     if htf.len() < 20 || ltf.len() < 20 {
         anyhow::bail!("not enough candles: htf={} ltf={}", htf.len(), ltf.len());
     }
-
-    let mut feed = CandleFeed::new(240);
-    let mut bos = BosTracker::new();
-    let mut pullback = PullbackTracker::new();
-
-    let bos_params = BosParams {
-        confirm_candles: 2,
-        epsilon_frac: 0.1,
-    };
-    let pullback_params = PullbackParams {
-        epsilon_frac: 0.1,
-        retrace_frac: 0.4,
-    };
-    let structure_params = StructureParams {
-        pivot_k: 1,
-        min_atr_frac: 0.1,
-    };
-    let mm_policy = MmPolicyParams {
-        soft_min: Ratio(args.soft_min),
-        soft_max: Ratio(args.soft_max),
-        hard_min: Ratio(args.hard_min),
-        hard_max: Ratio(args.hard_max),
-    };
-    let grid_params = GridParams {
-        levels: args.levels,
-        step: Bps(args.step_bps),
-        base_quote_per_order: Money(args.base_quote_per_order),
-        max_size_mult: args.max_size_mult,
-        soft_min: Ratio(args.soft_min),
-        soft_max: Ratio(args.soft_max),
-        hard_min: Ratio(args.hard_min),
-        hard_max: Ratio(args.hard_max),
-        min_base_qty: Qty(args.min_base_qty),
-    };
-    let force_close_exec = ExecutionModel {
-        fee_bps: args.force_close_fee_bps,
-        spread_bps: args.force_close_spread_bps,
-        slippage_bps: args.force_close_slippage_bps,
+    ensure_ltf_coverage(&args.symbol, &htf, &ltf, (htf_ms, ltf_ms), range, gate)?;
+    // у синтетики нет funding: perp считается с нулевыми ставками
+    let funding = match perp {
+        Some(_) if synthetic.is_none() => {
+            load_funding(
+                &args.symbol,
+                range,
+                args.funding_cache.as_deref(),
+                args.refresh,
+            )
+            .await?
+        }
+        _ => Vec::new(),
     };
-    let maker_fee_ratio = args.maker_fee_bps.max(0.0) / 10_000.0;
-
-    let mut quote = args.initial_quote;
-    let mut base = args.initial_base;
-    let mut cost_basis_quote = if base > 0.0 { base * htf[0].close.0 } else { 0.0 };
-
-    let mut fill_rows = Vec::new();
-    let mut equity_rows = Vec::new();
-
-    let mut buy_fills = 0usize;
-    let mut sell_fills = 0usize;
-    let mut bootstrap_trades = 0usize;
-    let mut winning_sells = 0usize;
-    let mut losing_sells = 0usize;
-    let mut gross_profit = 0.0_f64;
-    let mut gross_loss = 0.0_f64;
-    let mut max_equity = quote + base * htf[0].close.0;
-    let mut max_drawdown = 0.0_f64;
 
-    let mut active_mode = MmMode::Disabled;
-    let mut ltf_idx = 0usize;
-    let mut last_ts = htf[0].ts.0;
-
-    for h in htf {
-        let window_start = h.ts.0;
-        let window_end = window_start + htf_ms;
-
-        while ltf_idx < ltf.len() && ltf[ltf_idx].ts.0 < window_start {
-            ltf_idx += 1;
+    let cfg = MmRunConfig {
+        initial_quote: args.initial_quote,
+        initial_base: args.initial_base,
+        initial_base_ratio: args.initial_base_ratio,
+        perp,
+        taker: ExecutionModel {
+            fee_bps: args.force_close_fee_bps,
+            spread_bps: args.force_close_spread_bps,
+            slippage_bps: args.force_close_slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        force_close_twap,
+        bootstrap_rebalance: args.bootstrap_rebalance,
+        bootstrap_target_ratio: args.bootstrap_target_ratio,
+        intrabar: args.intrabar_path,
+        fill,
+        record: true,
+    };
+    let run = match args.data {
+        DataSource::Candles => run_mm_mtf_with_funding(&htf, &ltf, &funding, htf_ms, &params, cfg),
+        DataSource::Trades => {
+            run_mm_mtf_trades(&htf, &ltf, &trades, (htf_ms, ltf_ms), &params, cfg)
         }
-
-        while ltf_idx < ltf.len() && ltf[ltf_idx].ts.0 < window_end {
-            let lc = ltf[ltf_idx];
-            last_ts = lc.ts.0;
-            let inv = Inventory {
-                base: Qty(base),
-                quote: Money(quote),
+    };
+    // по сделкам порядок внутри свечи не используется
+    let worst_case = (args.data == DataSource::Candles && cfg.intrabar != IntrabarPath::WorstCase)
+        .then(|| {
+            let worst = MmRunConfig {
+                intrabar: IntrabarPath::WorstCase,
+                record: false,
+                ..cfg
             };
-            if matches!(active_mode, MmMode::Normal | MmMode::Defensive) {
-                let mode_grid_params = match active_mode {
-                    MmMode::Defensive => GridParams {
-                        step: Bps(grid_params.step.0 * args.defensive_step_mult.max(1.0)),
-                        base_quote_per_order: Money(
-                            grid_params.base_quote_per_order.0
-                                * args.defensive_size_mult.clamp(0.05, 1.0),
-                        ),
-                        ..grid_params
-                    },
-                    _ => grid_params,
-                };
-                if let Some(mut orders) = build_grid(lc.close, lc.close, inv, mode_grid_params) {
-                    orders.sort_by(|a, b| match (a.side, b.side) {
-                        (Side::Buy, Side::Buy) => b
-                            .price
-                            .0
-                            .partial_cmp(&a.price.0)
-                            .unwrap_or(std::cmp::Ordering::Equal),
-                        (Side::Sell, Side::Sell) => a
-                            .price
-                            .0
-                            .partial_cmp(&b.price.0)
-                            .unwrap_or(std::cmp::Ordering::Equal),
-                        (Side::Buy, Side::Sell) => std::cmp::Ordering::Less,
-                        (Side::Sell, Side::Buy) => std::cmp::Ordering::Greater,
-                    });
-
-                    for o in orders {
-                        match o.side {
-                            Side::Buy => {
-                                if lc.low.0 > o.price.0 {
-                                    continue;
-                                }
-                                let gross = o.qty.0 * o.price.0;
-                                let fee = gross * maker_fee_ratio;
-                                let total_cost = gross + fee;
-                                if total_cost > quote || o.qty.0 <= 0.0 {
-                                    continue;
-                                }
-                                quote -= total_cost;
-                                base += o.qty.0;
-                                cost_basis_quote += total_cost;
-                                buy_fills += 1;
-                                fill_rows.push(FillRow {
-                                    ts: lc.ts.0,
-                                    side: "BUY".to_string(),
-                                    mode: format!("{:?}", active_mode),
-                                    qty: o.qty.0,
-                                    price: o.price.0,
-                                    fee_quote: fee,
-                                    quote_delta: -total_cost,
-                                    realized_pnl: None,
-                                });
-                            }
-                            Side::Sell => {
-                                if lc.high.0 < o.price.0 || base <= 0.0 {
-                                    continue;
-                                }
-                                let qty = o.qty.0.min(base);
-                                if qty <= 0.0 {
-                                    continue;
-                                }
-                                let base_before = base;
-                                let avg_cost = if base_before > 0.0 {
-                                    cost_basis_quote / base_before
-                                } else {
-                                    0.0
-                                };
-                                let gross = qty * o.price.0;
-                                let fee = gross * maker_fee_ratio;
-                                let proceeds = gross - fee;
-                                let removed_cost = avg_cost * qty;
-                                let realized = proceeds - removed_cost;
-
-                                quote += proceeds;
-                                base -= qty;
-                                cost_basis_quote = (cost_basis_quote - removed_cost).max(0.0);
-                                if base <= 1e-12 {
-                                    base = 0.0;
-                                    cost_basis_quote = 0.0;
-                                }
-
-                                sell_fills += 1;
-                                if realized > 0.0 {
-                                    winning_sells += 1;
-                                    gross_profit += realized;
-                                } else if realized < 0.0 {
-                                    losing_sells += 1;
-                                    gross_loss += -realized;
-                                }
-                                fill_rows.push(FillRow {
-                                    ts: lc.ts.0,
-                                    side: "SELL".to_string(),
-                                    mode: format!("{:?}", active_mode),
-                                    qty,
-                                    price: o.price.0,
-                                    fee_quote: fee,
-                                    quote_delta: proceeds,
-                                    realized_pnl: Some(realized),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-
-            let equity = quote + base * lc.close.0;
-            max_equity = max_equity.max(equity);
-            if max_equity > 0.0 {
-                let dd = (max_equity - equity) / max_equity;
-                max_drawdown = max_drawdown.max(dd);
-                equity_rows.push(EquityRow {
-                    ts: lc.ts.0,
-                    close: lc.close.0,
-                    mode: format!("{:?}", active_mode),
-                    quote,
-                    base,
-                    cost_basis_quote,
-                    equity,
-                    drawdown_pct: dd * 100.0,
-                });
-            }
-
-            ltf_idx += 1;
-        }
-
-        feed.push(h);
-        let (Some(atr), Some(mid)) = (feed.atr(), feed.mid()) else {
-            active_mode = MmMode::Disabled;
-            continue;
-        };
-        let ms = detect_structure(&feed.candles, structure_params);
-        bos.on_candle_close(&h, &ms, atr, bos_params);
-        if bos.state == BosState::Confirmed {
-            pullback.on_candle_close(&h, &bos, atr, pullback_params);
-        } else {
-            pullback.reset();
-        }
-
-        let inv = Inventory {
-            base: Qty(base),
-            quote: Money(quote),
-        };
-        if let Some(ratio) = mm::grid::base_ratio(inv, mid) {
-            let mut decision = mm_policy_decision(bos.state, &pullback, ratio, mm_policy);
-
-            if args.bootstrap_rebalance
-                && matches!(
-                    decision.reason,
-                    MmDecisionReason::InventoryOutsideHardBand
-                )
-                && bos.state == BosState::Confirmed
-                && pullback.triggered
-            {
-                let equity = quote + base * mid.0;
-                let target = args.bootstrap_target_ratio.clamp(0.0, 1.0);
-                let target_base_value = target * equity;
-                let current_base_value = base * mid.0;
-                let delta_value = target_base_value - current_base_value;
-
-                if delta_value > 0.0 && quote > 0.0 {
-                    let qty = force_close_exec.buy_qty_for_quote(delta_value.min(quote), mid);
-                    if qty.0 > 0.0 {
-                        let cost = force_close_exec.buy_cost(qty, mid);
-                        if cost <= quote {
-                            quote -= cost;
-                            base += qty.0;
-                            cost_basis_quote += cost;
-                            buy_fills += 1;
-                            bootstrap_trades += 1;
-                            fill_rows.push(FillRow {
-                                ts: h.ts.0,
-                                side: "BUY".to_string(),
-                                mode: "Bootstrap".to_string(),
-                                qty: qty.0,
-                                price: force_close_exec.buy_fill_price(mid).0,
-                                fee_quote: cost - (qty.0 * force_close_exec.buy_fill_price(mid).0),
-                                quote_delta: -cost,
-                                realized_pnl: None,
-                            });
-                        }
-                    }
-                } else if delta_value < 0.0 && base > 0.0 {
-                    let qty = ((-delta_value) / mid.0).min(base);
-                    if qty > 0.0 {
-                        let proceeds = force_close_exec.sell_proceeds(Qty(qty), mid);
-                        let base_before = base;
-                        let avg_cost = if base_before > 0.0 {
-                            cost_basis_quote / base_before
-                        } else {
-                            0.0
-                        };
-                        let removed_cost = avg_cost * qty;
-                        let realized = proceeds - removed_cost;
-                        quote += proceeds;
-                        base -= qty;
-                        cost_basis_quote = (cost_basis_quote - removed_cost).max(0.0);
-                        if base <= 1e-12 {
-                            base = 0.0;
-                            cost_basis_quote = 0.0;
-                        }
-                        sell_fills += 1;
-                        bootstrap_trades += 1;
-                        if realized > 0.0 {
-                            winning_sells += 1;
-                            gross_profit += realized;
-                        } else if realized < 0.0 {
-                            losing_sells += 1;
-                            gross_loss += -realized;
-                        }
-                        fill_rows.push(FillRow {
-                            ts: h.ts.0,
-                            side: "SELL".to_string(),
-                            mode: "Bootstrap".to_string(),
-                            qty,
-                            price: force_close_exec.sell_fill_price(mid).0,
-                            fee_quote: (qty * force_close_exec.sell_fill_price(mid).0) - proceeds,
-                            quote_delta: proceeds,
-                            realized_pnl: Some(realized),
-                        });
-                    }
-                }
-
-                let inv2 = Inventory {
-                    base: Qty(base),
-                    quote: Money(quote),
-                };
-                if let Some(r2) = mm::grid::base_ratio(inv2, mid) {
-                    decision = mm_policy_decision(bos.state, &pullback, r2, mm_policy);
-                }
-            }
-
-            active_mode = decision.mode;
-        } else {
-            active_mode = MmMode::Disabled;
-        }
-    }
-
-    if args.force_close_at_end && base > 0.0 {
-        let final_mark = ltf.last().map(|c| c.close).unwrap_or(Price(0.0));
-        let exit_qty = base;
-        let proceeds = force_close_exec.sell_proceeds(Qty(exit_qty), final_mark);
-        let avg_cost = if exit_qty > 0.0 {
-            cost_basis_quote / exit_qty
-        } else {
-            0.0
-        };
-        let removed_cost = avg_cost * exit_qty;
-        let realized = proceeds - removed_cost;
-        let gross = exit_qty * final_mark.0;
-        let fee = gross - proceeds;
-        quote += proceeds;
-        base = 0.0;
-        sell_fills += 1;
-        if realized > 0.0 {
-            winning_sells += 1;
-            gross_profit += realized;
-        } else if realized < 0.0 {
-            losing_sells += 1;
-            gross_loss += -realized;
-        }
-        fill_rows.push(FillRow {
-            ts: last_ts,
-            side: "SELL".to_string(),
-            mode: "ForceClose".to_string(),
-            qty: exit_qty,
-            price: final_mark.0,
-            fee_quote: fee.max(0.0),
-            quote_delta: proceeds,
-            realized_pnl: Some(realized),
+            run_mm_mtf_with_funding(&htf, &ltf, &funding, htf_ms, &params, worst)
+                .report
+                .perf
         });
-    }
-
-    let final_mark = ltf.last().map(|c| c.close).unwrap_or(Price(0.0));
-    let final_equity = quote + base * final_mark.0;
-    let initial_equity = args.initial_quote + args.initial_base * final_mark.0;
-    let pnl = final_equity - initial_equity;
-    let roi_pct = if initial_equity > 0.0 {
-        100.0 * pnl / initial_equity
-    } else {
-        0.0
-    };
-    let closed_trades = sell_fills;
-    let win_rate_pct = if closed_trades > 0 {
-        100.0 * (winning_sells as f64) / (closed_trades as f64)
-    } else {
-        0.0
-    };
-    let avg_win = if winning_sells > 0 {
-        gross_profit / (winning_sells as f64)
-    } else {
-        0.0
-    };
-    let avg_loss = if losing_sells > 0 {
-        gross_loss / (losing_sells as f64)
+    let r = run.report;
+    let p = r.perf;
+
+    write_csv(&args.equity_out, &run.equity_rows).context("write equity csv failed")?;
+    write_csv(
+        &args.underwater_out,
+        underwater_rows(run.equity_rows.iter().map(|e| (e.ts, e.drawdown_pct))),
+    )
+    .context("write underwater csv failed")?;
+    let monthly = monthly_breakdown(
+        r.final_equity - p.pnl,
+        &run.equity_rows
+            .iter()
+            .map(|e| (e.ts, e.equity))
+            .collect::<Vec<_>>(),
+        r.final_equity,
+        &run.fill_rows.iter().map(|f| f.ts).collect::<Vec<_>>(),
+    );
+    write_csv(&args.monthly_out, &monthly).context("write monthly csv failed")?;
+    write_csv(&args.fills_out, &run.fill_rows).context("write fills csv failed")?;
+    let mc_trades = McTrade::from_fills(&run.fill_rows);
+    let mc = if args.mc_runs > 0 && !mc_trades.is_empty() {
+        let rows = simulate(&mc_trades, r.final_equity - p.pnl, &mc_params);
+        write_csv(&args.mc_out, &rows).context("write monte carlo csv failed")?;
+        Some(McSummary::new(&rows, mc_trades.len()))
     } else {
-        0.0
+        None
     };
 
-    write_equity_csv(&args.equity_out, &equity_rows).context("write equity csv failed")?;
-    write_fills_csv(&args.fills_out, &fill_rows).context("write fills csv failed")?;
-
     println!("MM MTF backtest finished");
+    println!("tf: htf={}m ltf={}m", args.htf_interval, args.ltf_interval);
+    if args.data == DataSource::Trades {
+        println!("data: trades={} (fills against trade prints)", trades.len());
+    }
     println!(
-        "tf: htf={}m ltf={}m",
-        args.htf_interval, args.ltf_interval
+        "cost_model: maker_fee_bps={:.2} force_close_fee_bps={:.2} force_close_spread_bps={:.2} force_close_slippage_bps={:.2}",
+        args.maker_fee_bps,
+        args.force_close_fee_bps,
+        args.force_close_spread_bps,
+        args.force_close_slippage_bps
     );
+    let (initial_quote, initial_base) = cfg.initial_balances(htf[0].close.0);
     println!(
-        "cost_model: maker_fee_bps={:.2} force_close_fee_bps={:.2} force_close_spread_bps={:.2} force_close_slippage_bps={:.2}",
-        args.maker_fee_bps, args.force_close_fee_bps, args.force_close_spread_bps, args.force_close_slippage_bps
+        "initial: quote={:.4} base={:.8} base_ratio={}",
+        initial_quote,
+        initial_base,
+        args.initial_base_ratio
+            .map_or_else(|| "-".to_string(), |r| format!("{:.3}", r))
+    );
+    if let Some(p) = perp {
+        println!(
+            "perp: leverage={:.2} maintenance_margin_rate={:.4} funding_rates={} funding_paid={:.4} liquidations={}",
+            p.leverage,
+            p.maintenance_margin_rate,
+            funding.len(),
+            r.funding_paid,
+            r.liquidations
+        );
+    }
+    if let Some(n) = force_close_twap {
+        println!(
+            "force_close: vwap candles={} vs_last={:.4} (quote, >0 — TWAP выгоднее одного fill'а)",
+            n, r.force_close_vs_last
+        );
+    }
+    println!(
+        "signal: pivot_k={} min_atr_frac={:.3} bos_confirm_candles={} bos_epsilon_frac={:.3} pullback_epsilon_frac={:.3} pullback_retrace_frac={:.3}",
+        args.pivot_k,
+        args.min_atr_frac,
+        args.bos_confirm_candles,
+        args.bos_epsilon_frac,
+        args.pullback_epsilon_frac,
+        args.pullback_retrace_frac
     );
     println!(
         "defensive_profile: step_mult={:.2} size_mult={:.2}",
@@ -630,32 +447,76 @@ async fn main() -> Result<()> {
     );
     println!(
         "fills: buy={} sell={} bootstrap={}",
-        buy_fills, sell_fills, bootstrap_trades
+        r.buy_fills, r.sell_fills, r.bootstrap_trades
     );
     println!(
         "final_quote={:.4} final_base={:.8} final_equity={:.4}",
-        quote, base, final_equity
+        r.final_quote, r.final_base, r.final_equity
     );
-    println!("pnl={:.4} roi={:.2}% max_drawdown={:.2}%", pnl, roi_pct, max_drawdown * 100.0);
-    if gross_loss > 0.0 {
-        println!(
-            "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor={:.4}",
-            closed_trades,
-            win_rate_pct,
-            avg_win,
-            avg_loss,
-            gross_profit / gross_loss
-        );
-    } else {
+    println!(
+        "pnl={:.4} roi={:.2}% max_drawdown={:.2}%",
+        p.pnl, p.roi_pct, p.max_drawdown_pct
+    );
+    println!(
+        "drawdown: max_drawdown_duration={:.2}h time_to_recovery={}",
+        p.max_drawdown_duration_h,
+        p.recovery_label()
+    );
+    println!("monthly: {}", breakdown_summary(&monthly));
+    println!(
+        "risk: sharpe={:.3} sortino={:.3} calmar={:.3} exposure={:.2}% avg_trade_duration={:.2}h turnover={:.2}x",
+        p.sharpe, p.sortino, p.calmar, p.exposure_pct, p.avg_trade_duration_h, p.turnover
+    );
+    println!(
+        "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor={}",
+        p.closed_trades,
+        p.win_rate_pct,
+        p.avg_win,
+        p.avg_loss,
+        p.profit_factor_label()
+    );
+    if let Some(w) = &worst_case {
         println!(
-            "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor=INF",
-            closed_trades, win_rate_pct, avg_win, avg_loss
+            "worst_case: pnl={:.4} roi={:.2}% max_drawdown={:.2}% sharpe={:.3} (worst intrabar path per candle)",
+            w.pnl, w.roi_pct, w.max_drawdown_pct, w.sharpe
         );
     }
+    match &mc {
+        Some(s) => println!("mc: {}", s.label()),
+        None if args.mc_runs > 0 => println!("mc: skipped, no closed trades"),
+        None => {}
+    }
+    let c = r.costs;
     println!(
-        "artifacts: equity_csv={} fills_csv={}",
-        args.equity_out, args.fills_out
+        "costs: maker_fees={:.4} taker_fees={:.4} spread_slippage={:.4} total_costs={:.4}",
+        c.maker_fees, c.taker_fees, c.spread_slippage, c.total
     );
+    let has_mc = mc.is_some();
+    let mut report = JsonReport::new(
+        "backtest_mm_mtf",
+        &args,
+        Metrics {
+            report: &r,
+            worst_case,
+            mc,
+        },
+    )
+    .with_seed(args.seed)
+    .with_monthly(monthly)
+    .with_costs(c)
+    .with_html(HtmlData::new(
+        run.equity_rows
+            .iter()
+            .map(|e| (e.ts, e.equity, e.drawdown_pct)),
+        &run.fill_rows,
+    )?)
+    .artifact("equity_csv", &args.equity_out)
+    .artifact("underwater_csv", &args.underwater_out)
+    .artifact("fills_csv", &args.fills_out);
+    if has_mc {
+        report = report.artifact("mc_csv", &args.mc_out);
+    }
+    report.finish(args.report_out.as_deref())?;
 
     Ok(())
 }
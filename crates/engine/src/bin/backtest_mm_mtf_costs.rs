@@ -0,0 +1,373 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::data::{
+    Venue, date_range_ms, ensure_ltf_coverage, load_candles, parse_interval_ms, parse_num_list,
+};
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmRunConfig, SignalParams,
+    run_mm_mtf,
+};
+use backtest::progress::Progress;
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{JsonReport, write_csv};
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use backtest::sensitivity::{BreakevenStatus, SensitivityRow, breakeven};
+use execution::sim::ExecutionModel;
+use structure::bos::BosParams;
+use structure::candle::resample_ms;
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
+
+/// Чувствительность фиксированного MM MTF конфига к издержкам:
+/// перебор maker fee × taker fee × спред × проскальзывание.
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    #[arg(long)]
+    symbol: String,
+    #[arg(long, default_value = "5")]
+    htf_interval: String,
+    #[arg(long, default_value = "1")]
+    ltf_interval: String,
+    #[arg(long)]
+    start: String,
+    #[arg(long)]
+    end: String,
+    /// Кэш LTF свечей, CSV или `.parquet` (feature `parquet`); HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+
+    #[arg(long, default_value = "0,2,5,7.5,10,15,20")]
+    maker_fee_bps_list: String,
+    /// Taker комиссия bootstrap и force close
+    #[arg(long, default_value = "10")]
+    taker_fee_bps_list: String,
+    #[arg(long, default_value = "8")]
+    spread_bps_list: String,
+    #[arg(long, default_value = "2")]
+    slippage_bps_list: String,
+
+    #[arg(long, default_value_t = 1000.0)]
+    initial_quote: f64,
+    #[arg(long, default_value_t = 0.0)]
+    initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи (0.5 — поровну);
+    /// делает прогоны сравнимыми между символами и периодами
+    #[arg(long)]
+    initial_base_ratio: Option<f64>,
+
+    #[arg(long, default_value_t = 1)]
+    pivot_k: usize,
+    #[arg(long, default_value_t = 0.1)]
+    min_atr_frac: f64,
+    #[arg(long, default_value_t = 2)]
+    bos_confirm_candles: usize,
+    #[arg(long, default_value_t = 0.1)]
+    bos_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.1)]
+    pullback_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.4)]
+    pullback_retrace_frac: f64,
+
+    #[arg(long, default_value_t = 5)]
+    levels: usize,
+    #[arg(long, default_value_t = 12.0)]
+    step_bps: f64,
+    #[arg(long, default_value_t = 25.0)]
+    base_quote_per_order: f64,
+    #[arg(long, default_value_t = 2.0)]
+    max_size_mult: f64,
+    #[arg(long, default_value_t = 0.0001)]
+    min_base_qty: f64,
+
+    #[arg(long, default_value_t = 0.40)]
+    soft_min: f64,
+    #[arg(long, default_value_t = 0.60)]
+    soft_max: f64,
+    #[arg(long, default_value_t = 0.35)]
+    hard_min: f64,
+    #[arg(long, default_value_t = 0.65)]
+    hard_max: f64,
+
+    #[arg(long, default_value_t = true)]
+    force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
+    #[arg(long, default_value_t = 1.5)]
+    defensive_step_mult: f64,
+    #[arg(long, default_value_t = 0.5)]
+    defensive_size_mult: f64,
+    #[arg(long, default_value_t = true)]
+    bootstrap_rebalance: bool,
+    #[arg(long, default_value_t = 0.50)]
+    bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
+
+    /// Все прогоны: модель издержек и метрики
+    #[arg(long, default_value = "data/mm_mtf_costs_table.csv")]
+    table_out: String,
+    /// Безубыточная maker fee для каждой taker модели
+    #[arg(long, default_value = "data/mm_mtf_costs_breakeven.csv")]
+    breakeven_out: String,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг, точки безубыточности, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+    /// Без progress bar: прогресс строками `progress:` в stdout (так запускает worker)
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BreakevenRow {
+    taker_fee_bps: f64,
+    spread_bps: f64,
+    slippage_bps: f64,
+    status: BreakevenStatus,
+    breakeven_maker_fee_bps: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metrics<'a> {
+    runs: usize,
+    profitable_runs: usize,
+    breakeven: &'a [BreakevenRow],
+}
+
+fn sorted_list(s: &str, name: &str) -> Result<Vec<f64>> {
+    let mut values = parse_num_list::<f64>(s, name)?;
+    if values.iter().any(|v| v.is_nan() || *v < 0.0) {
+        anyhow::bail!("{} must be non-negative", name);
+    }
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    Ok(values)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote < 0.0 || args.initial_base < 0.0 {
+        anyhow::bail!("initial balances must be non-negative");
+    }
+    if args
+        .initial_base_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
+    {
+        anyhow::bail!("initial_base_ratio must be in [0, 1]");
+    }
+    let maker_fees = sorted_list(&args.maker_fee_bps_list, "maker_fee_bps_list")?;
+    let taker_fees = sorted_list(&args.taker_fee_bps_list, "taker_fee_bps_list")?;
+    let spreads = sorted_list(&args.spread_bps_list, "spread_bps_list")?;
+    let slippages = sorted_list(&args.slippage_bps_list, "slippage_bps_list")?;
+
+    let params = MmParams {
+        levels: args.levels,
+        step_bps: args.step_bps,
+        base_quote_per_order: args.base_quote_per_order,
+        max_size_mult: args.max_size_mult,
+        min_base_qty: args.min_base_qty,
+        soft_min: args.soft_min,
+        soft_max: args.soft_max,
+        hard_min: args.hard_min,
+        hard_max: args.hard_max,
+        maker_fee_bps: maker_fees[0],
+        defensive_step_mult: args.defensive_step_mult,
+        defensive_size_mult: args.defensive_size_mult,
+        signal: SignalParams {
+            structure: StructureParams {
+                pivot_k: args.pivot_k,
+                min_atr_frac: args.min_atr_frac,
+            },
+            bos: BosParams {
+                confirm_candles: args.bos_confirm_candles,
+                epsilon_frac: args.bos_epsilon_frac,
+            },
+            pullback: PullbackParams {
+                epsilon_frac: args.pullback_epsilon_frac,
+                retrace_frac: args.pullback_retrace_frac,
+            },
+        },
+    };
+    params.validate_bands()?;
+    params.signal.validate()?;
+
+    let htf_ms = parse_interval_ms(&args.htf_interval)?;
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    let ltf = match synthetic {
+        Some(p) => synthetic_candles(&p, ltf_ms, range, args.seed),
+        None => {
+            load_candles(
+                args.exchange,
+                &args.symbol,
+                &args.ltf_interval,
+                range,
+                args.ltf_cache.as_deref(),
+                args.refresh,
+                gate,
+            )
+            .await?
+        }
+    };
+    // качается только LTF серия, HTF собирается из неё
+    let htf = resample_ms(&ltf, htf_ms);
+    if htf.len() < 20 || ltf.len() < 20 {
+        anyhow::bail!("not enough candles: htf={} ltf={}", htf.len(), ltf.len());
+    }
+    ensure_ltf_coverage(&args.symbol, &htf, &ltf, (htf_ms, ltf_ms), range, gate)?;
+
+    let mut rows = Vec::new();
+    let mut breakevens = Vec::new();
+    let mut progress = Progress::new(
+        "costs",
+        "runs",
+        maker_fees.len() * taker_fees.len() * spreads.len() * slippages.len(),
+        args.quiet,
+    );
+    for &taker_fee_bps in &taker_fees {
+        for &spread_bps in &spreads {
+            for &slippage_bps in &slippages {
+                let cfg = MmRunConfig {
+                    initial_quote: args.initial_quote,
+                    initial_base: args.initial_base,
+                    initial_base_ratio: args.initial_base_ratio,
+                    perp: None,
+                    taker: ExecutionModel {
+                        fee_bps: taker_fee_bps,
+                        spread_bps,
+                        slippage_bps,
+                    },
+                    force_close_at_end: args.force_close_at_end,
+                    force_close_twap,
+                    bootstrap_rebalance: args.bootstrap_rebalance,
+                    bootstrap_target_ratio: args.bootstrap_target_ratio,
+                    intrabar: args.intrabar_path,
+                    fill,
+                    record: false,
+                };
+                let mut points = Vec::with_capacity(maker_fees.len());
+                for &maker_fee_bps in &maker_fees {
+                    let params = MmParams {
+                        maker_fee_bps,
+                        ..params
+                    };
+                    let r = run_mm_mtf(&htf, &ltf, htf_ms, &params, cfg).report;
+                    points.push((maker_fee_bps, r.perf.pnl));
+                    rows.push(SensitivityRow::new(
+                        (maker_fee_bps, taker_fee_bps, spread_bps, slippage_bps),
+                        &r.perf,
+                        &r.costs,
+                    ));
+                    progress.tick();
+                }
+                let b = breakeven(&points);
+                breakevens.push(BreakevenRow {
+                    taker_fee_bps,
+                    spread_bps,
+                    slippage_bps,
+                    status: b.status,
+                    breakeven_maker_fee_bps: b.fee_bps,
+                });
+            }
+        }
+    }
+
+    write_csv(&args.table_out, &rows).context("write sensitivity table failed")?;
+    write_csv(&args.breakeven_out, &breakevens).context("write breakeven csv failed")?;
+
+    let profitable_runs = rows.iter().filter(|r| r.profitable).count();
+    println!("MM MTF cost sensitivity finished");
+    println!(
+        "runs={} profitable={} maker_fee_bps={:?}",
+        rows.len(),
+        profitable_runs,
+        maker_fees
+    );
+    for b in &breakevens {
+        let verdict = match (b.status, b.breakeven_maker_fee_bps) {
+            (BreakevenStatus::Crosses, Some(fee)) => format!("breakeven_maker_fee_bps={:.2}", fee),
+            (BreakevenStatus::AlwaysProfitable, _) => "profitable at every maker fee".to_string(),
+            _ => "unprofitable at every maker fee".to_string(),
+        };
+        println!(
+            "taker_fee_bps={:.2} spread_bps={:.2} slippage_bps={:.2}: {}",
+            b.taker_fee_bps, b.spread_bps, b.slippage_bps, verdict
+        );
+    }
+
+    JsonReport::new(
+        "backtest_mm_mtf_costs",
+        &args,
+        Metrics {
+            runs: rows.len(),
+            profitable_runs,
+            breakeven: &breakevens,
+        },
+    )
+    .with_seed(args.seed)
+    .artifact("table_csv", &args.table_out)
+    .artifact("breakeven_csv", &args.breakeven_out)
+    .finish(args.report_out.as_deref())?;
+
+    Ok(())
+}
@@ -0,0 +1,488 @@
+use std::cell::Cell;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::data::{Venue, date_range_ms, ensure_ltf_coverage, load_candles, parse_interval_ms};
+use backtest::ga::{Ga, GaParams};
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmReport, MmRunConfig,
+    SignalParams, run_mm_mtf,
+};
+use backtest::progress::Progress;
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{JsonReport, write_csv};
+use backtest::search::Dim;
+use backtest::stats::RankBy;
+use execution::sim::ExecutionModel;
+use structure::bos::BosParams;
+use structure::candle::resample_ms;
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
+
+/// Генетический подбор параметров сетки, полос inventory и maker fee.
+/// Диапазоны — `lo..hi` или список `a,b,c`; сигнал фиксирован.
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    #[arg(long)]
+    symbol: String,
+    #[arg(long, default_value = "5")]
+    htf_interval: String,
+    #[arg(long, default_value = "1")]
+    ltf_interval: String,
+    #[arg(long)]
+    start: String,
+    #[arg(long)]
+    end: String,
+    /// Кэш LTF свечей, CSV или `.parquet` (feature `parquet`); HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+
+    #[arg(long, default_value_t = 1000.0)]
+    initial_quote: f64,
+    #[arg(long, default_value_t = 0.0)]
+    initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи (0.5 — поровну);
+    /// делает прогоны сравнимыми между символами и периодами
+    #[arg(long)]
+    initial_base_ratio: Option<f64>,
+
+    #[arg(long, default_value = "2..8")]
+    levels_range: String,
+    #[arg(long, default_value = "5..25")]
+    step_bps_range: String,
+    #[arg(long, default_value = "10..50")]
+    base_quote_per_order_range: String,
+    #[arg(long, default_value = "1.0..3.0")]
+    max_size_mult_range: String,
+    #[arg(long, default_value_t = 0.0001)]
+    min_base_qty: f64,
+
+    #[arg(long, default_value = "0.30..0.48")]
+    soft_min_range: String,
+    #[arg(long, default_value = "0.52..0.70")]
+    soft_max_range: String,
+    #[arg(long, default_value = "0.20..0.40")]
+    hard_min_range: String,
+    #[arg(long, default_value = "0.60..0.80")]
+    hard_max_range: String,
+    #[arg(long, default_value = "5,10")]
+    maker_fee_bps_range: String,
+
+    #[arg(long, default_value_t = 1.5)]
+    defensive_step_mult: f64,
+    #[arg(long, default_value_t = 0.5)]
+    defensive_size_mult: f64,
+    #[arg(long, default_value_t = 1)]
+    pivot_k: usize,
+    #[arg(long, default_value_t = 0.1)]
+    min_atr_frac: f64,
+    #[arg(long, default_value_t = 2)]
+    bos_confirm_candles: usize,
+    #[arg(long, default_value_t = 0.1)]
+    bos_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.1)]
+    pullback_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.4)]
+    pullback_retrace_frac: f64,
+    #[arg(long, default_value_t = 10.0)]
+    force_close_fee_bps: f64,
+    #[arg(long, default_value_t = 8.0)]
+    force_close_spread_bps: f64,
+    #[arg(long, default_value_t = 2.0)]
+    force_close_slippage_bps: f64,
+    #[arg(long, default_value_t = true)]
+    force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
+    #[arg(long, default_value_t = true)]
+    bootstrap_rebalance: bool,
+    #[arg(long, default_value_t = 0.50)]
+    bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
+
+    #[arg(long, default_value_t = 30)]
+    population: usize,
+    /// Включая начальное случайное поколение
+    #[arg(long, default_value_t = 15)]
+    generations: usize,
+    #[arg(long, default_value_t = 2)]
+    elite: usize,
+    #[arg(long, default_value_t = 0.2)]
+    mutation_rate: f64,
+    #[arg(long, default_value_t = 0.1)]
+    mutation_scale: f64,
+    #[arg(long, default_value_t = 3)]
+    tournament: usize,
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Фитнес: метрика по убыванию, ничьи — меньшая просадка
+    #[arg(long, value_enum, default_value_t = RankBy::Roi)]
+    rank_by: RankBy,
+
+    #[arg(long, default_value_t = 20)]
+    top_n: usize,
+    #[arg(long, default_value = "data/mm_mtf_ga_summary.csv")]
+    summary_out: String,
+    /// Лучшая особь и среднее по каждому поколению
+    #[arg(long, default_value = "data/mm_mtf_ga_generations.csv")]
+    generations_out: String,
+    #[arg(long, default_value = "data/mm_mtf_ga_best_equity.csv")]
+    equity_out: String,
+    #[arg(long, default_value = "data/mm_mtf_ga_best_fills.csv")]
+    fills_out: String,
+    /// JSON отчёт: конфиг, метрики лучшего прогона, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+    /// Без progress bar: прогресс строками `progress:` в stdout (так запускает worker)
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
+}
+
+#[derive(Serialize)]
+struct SummaryRow {
+    rank: usize,
+    levels: usize,
+    step_bps: f64,
+    base_quote_per_order: f64,
+    max_size_mult: f64,
+    soft_min: f64,
+    soft_max: f64,
+    hard_min: f64,
+    hard_max: f64,
+    maker_fee_bps: f64,
+    buy_fills: usize,
+    sell_fills: usize,
+    bootstrap_trades: usize,
+    win_rate_pct: f64,
+    profit_factor: f64,
+    max_drawdown_pct: f64,
+    pnl: f64,
+    roi_pct: f64,
+    sharpe: f64,
+    sortino: f64,
+    calmar: f64,
+    exposure_pct: f64,
+    avg_trade_duration_h: f64,
+    turnover: f64,
+}
+
+#[derive(Serialize)]
+struct GenerationRow {
+    generation: usize,
+    evaluated: usize,
+    best_roi_pct: f64,
+    best_max_drawdown_pct: f64,
+    best_sharpe: f64,
+    mean_roi_pct: f64,
+    mean_max_drawdown_pct: f64,
+}
+
+/// Метрики GA для JSON отчёта: метрики лучшего прогона на верхнем уровне
+#[derive(Serialize)]
+struct GaMetrics<'a> {
+    evaluated: usize,
+    generations: usize,
+    #[serde(flatten)]
+    best: Option<&'a MmReport>,
+    best_config: Option<&'a SummaryRow>,
+}
+
+/// Порядок измерений — как в [`params_at`]
+fn search_dims(args: &Args) -> Result<Vec<Dim>> {
+    Ok(vec![
+        Dim::parse(&args.levels_range, "levels_range", true)?,
+        Dim::parse(&args.step_bps_range, "step_bps_range", false)?,
+        Dim::parse(
+            &args.base_quote_per_order_range,
+            "base_quote_per_order_range",
+            false,
+        )?,
+        Dim::parse(&args.max_size_mult_range, "max_size_mult_range", false)?,
+        Dim::parse(&args.soft_min_range, "soft_min_range", false)?,
+        Dim::parse(&args.soft_max_range, "soft_max_range", false)?,
+        Dim::parse(&args.hard_min_range, "hard_min_range", false)?,
+        Dim::parse(&args.hard_max_range, "hard_max_range", false)?,
+        Dim::parse(&args.maker_fee_bps_range, "maker_fee_bps_range", false)?,
+    ])
+}
+
+fn params_at(args: &Args, signal: SignalParams, p: &[f64]) -> MmParams {
+    MmParams {
+        levels: p[0] as usize,
+        step_bps: p[1],
+        base_quote_per_order: p[2],
+        max_size_mult: p[3],
+        min_base_qty: args.min_base_qty,
+        soft_min: p[4],
+        soft_max: p[5],
+        hard_min: p[6],
+        hard_max: p[7],
+        maker_fee_bps: p[8],
+        defensive_step_mult: args.defensive_step_mult,
+        defensive_size_mult: args.defensive_size_mult,
+        signal,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote < 0.0 || args.initial_base < 0.0 {
+        anyhow::bail!("initial balances must be non-negative");
+    }
+    if args
+        .initial_base_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
+    {
+        anyhow::bail!("initial_base_ratio must be in [0, 1]");
+    }
+    let ga = GaParams {
+        population: args.population,
+        generations: args.generations,
+        elite: args.elite,
+        mutation_rate: args.mutation_rate,
+        mutation_scale: args.mutation_scale,
+        tournament: args.tournament,
+        seed: args.seed,
+    };
+    ga.validate()?;
+    let dims = search_dims(&args)?;
+    let signal = SignalParams {
+        structure: StructureParams {
+            pivot_k: args.pivot_k,
+            min_atr_frac: args.min_atr_frac,
+        },
+        bos: BosParams {
+            confirm_candles: args.bos_confirm_candles,
+            epsilon_frac: args.bos_epsilon_frac,
+        },
+        pullback: PullbackParams {
+            epsilon_frac: args.pullback_epsilon_frac,
+            retrace_frac: args.pullback_retrace_frac,
+        },
+    };
+    signal.validate()?;
+
+    let htf_ms = parse_interval_ms(&args.htf_interval)?;
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+    let ltf = load_candles(
+        args.exchange,
+        &args.symbol,
+        &args.ltf_interval,
+        range,
+        args.ltf_cache.as_deref(),
+        args.refresh,
+        gate,
+    )
+    .await?;
+    // качается только LTF серия, HTF собирается из неё
+    let htf = resample_ms(&ltf, htf_ms);
+    if htf.len() < 20 || ltf.len() < 20 {
+        anyhow::bail!("not enough candles: htf={} ltf={}", htf.len(), ltf.len());
+    }
+    ensure_ltf_coverage(&args.symbol, &htf, &ltf, (htf_ms, ltf_ms), range, gate)?;
+
+    let run_cfg = MmRunConfig {
+        initial_quote: args.initial_quote,
+        initial_base: args.initial_base,
+        initial_base_ratio: args.initial_base_ratio,
+        perp: None,
+        taker: ExecutionModel {
+            fee_bps: args.force_close_fee_bps,
+            spread_bps: args.force_close_spread_bps,
+            slippage_bps: args.force_close_slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        force_close_twap,
+        bootstrap_rebalance: args.bootstrap_rebalance,
+        bootstrap_target_ratio: args.bootstrap_target_ratio,
+        intrabar: args.intrabar_path,
+        fill,
+        record: false,
+    };
+
+    let mut generation_rows = Vec::with_capacity(args.generations);
+    let evaluated = Cell::new(0);
+    let mut progress = Progress::new("ga", "generations", args.generations, args.quiet);
+    let mut all = Ga::new(ga, dims).run(
+        |p| {
+            let cfg = params_at(&args, signal, p);
+            (cfg.levels > 0 && cfg.soft_min < cfg.soft_max && cfg.validate_bands().is_ok())
+                .then_some(cfg)
+        },
+        |cfg| {
+            evaluated.set(evaluated.get() + 1);
+            Ok(run_mm_mtf(&htf, &ltf, htf_ms, cfg, run_cfg).report)
+        },
+        |a: &MmReport, b: &MmReport| a.perf.rank_cmp(&b.perf, args.rank_by),
+        |generation, ranked| {
+            let best = &ranked[0].1.perf;
+            progress.offer_best(best, args.rank_by, || {
+                let cfg = &ranked[0].0;
+                format!(
+                    "levels={} step_bps={:.2} qpo={:.2} fee={:.2}",
+                    cfg.levels, cfg.step_bps, cfg.base_quote_per_order, cfg.maker_fee_bps
+                )
+            });
+            let n = ranked.len() as f64;
+            let row = GenerationRow {
+                generation,
+                evaluated: evaluated.get(),
+                best_roi_pct: best.roi_pct,
+                best_max_drawdown_pct: best.max_drawdown_pct,
+                best_sharpe: best.sharpe,
+                mean_roi_pct: ranked.iter().map(|(_, r)| r.perf.roi_pct).sum::<f64>() / n,
+                mean_max_drawdown_pct: ranked
+                    .iter()
+                    .map(|(_, r)| r.perf.max_drawdown_pct)
+                    .sum::<f64>()
+                    / n,
+            };
+            println!(
+                "generation {}: evaluated={} best_roi={:.2}% best_dd={:.2}% mean_roi={:.2}%",
+                row.generation,
+                row.evaluated,
+                row.best_roi_pct,
+                row.best_max_drawdown_pct,
+                row.mean_roi_pct
+            );
+            generation_rows.push(row);
+            progress.set(generation + 1);
+        },
+    )?;
+    all.sort_by(|a, b| a.1.perf.rank_cmp(&b.1.perf, args.rank_by));
+
+    let rows: Vec<SummaryRow> = all
+        .iter()
+        .take(args.top_n)
+        .enumerate()
+        .map(|(idx, (cfg, rep))| SummaryRow {
+            rank: idx + 1,
+            levels: cfg.levels,
+            step_bps: cfg.step_bps,
+            base_quote_per_order: cfg.base_quote_per_order,
+            max_size_mult: cfg.max_size_mult,
+            soft_min: cfg.soft_min,
+            soft_max: cfg.soft_max,
+            hard_min: cfg.hard_min,
+            hard_max: cfg.hard_max,
+            maker_fee_bps: cfg.maker_fee_bps,
+            buy_fills: rep.buy_fills,
+            sell_fills: rep.sell_fills,
+            bootstrap_trades: rep.bootstrap_trades,
+            win_rate_pct: rep.perf.win_rate_pct,
+            profit_factor: rep.perf.profit_factor,
+            max_drawdown_pct: rep.perf.max_drawdown_pct,
+            pnl: rep.perf.pnl,
+            roi_pct: rep.perf.roi_pct,
+            sharpe: rep.perf.sharpe,
+            sortino: rep.perf.sortino,
+            calmar: rep.perf.calmar,
+            exposure_pct: rep.perf.exposure_pct,
+            avg_trade_duration_h: rep.perf.avg_trade_duration_h,
+            turnover: rep.perf.turnover,
+        })
+        .collect();
+    write_csv(&args.summary_out, &rows).context("write summary failed")?;
+    write_csv(&args.generations_out, &generation_rows).context("write generations failed")?;
+
+    println!(
+        "MM MTF GA done: evaluated={} generations={} summary={} generations_csv={}",
+        all.len(),
+        generation_rows.len(),
+        args.summary_out,
+        args.generations_out
+    );
+    if let Some(best) = rows.first() {
+        println!(
+            "Best: levels={} step_bps={:.2} qpo={:.2} max_size_mult={:.2} bands=({:.2}-{:.2}|{:.2}-{:.2}) fee={:.2} roi={:.2}% sharpe={:.3} pf={:.4} dd={:.2}%",
+            best.levels,
+            best.step_bps,
+            best.base_quote_per_order,
+            best.max_size_mult,
+            best.hard_min,
+            best.soft_min,
+            best.soft_max,
+            best.hard_max,
+            best.maker_fee_bps,
+            best.roi_pct,
+            best.sharpe,
+            best.profit_factor,
+            best.max_drawdown_pct
+        );
+    }
+
+    let best = all.first().map(|(_, rep)| rep);
+    let metrics = GaMetrics {
+        evaluated: all.len(),
+        generations: generation_rows.len(),
+        best,
+        best_config: rows.first(),
+    };
+    let mut report = JsonReport::new("backtest_mm_mtf_ga", &args, metrics)
+        .with_seed(args.seed)
+        .artifact("summary_csv", &args.summary_out)
+        .artifact("generations_csv", &args.generations_out);
+    if let Some((cfg, rep)) = all.first() {
+        let run = run_mm_mtf(
+            &htf,
+            &ltf,
+            htf_ms,
+            cfg,
+            MmRunConfig {
+                record: true,
+                ..run_cfg
+            },
+        );
+        write_csv(&args.equity_out, &run.equity_rows).context("write equity csv failed")?;
+        write_csv(&args.fills_out, &run.fill_rows).context("write fills csv failed")?;
+        report = report
+            .with_costs(rep.costs)
+            .artifact("equity_csv", &args.equity_out)
+            .artifact("fills_csv", &args.fills_out);
+    }
+    report.finish(args.report_out.as_deref())?;
+
+    Ok(())
+}
@@ -0,0 +1,438 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::data::{
+    Venue, date_range_ms, ensure_ltf_coverage, load_candles, parse_interval_ms, parse_num_list,
+};
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmReport, MmRunConfig,
+    SignalParams, run_mm_mtf_with_funding,
+};
+use backtest::perp::{MarketKind, PerpParams, load_funding};
+use backtest::progress::Progress;
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{JsonReport, write_csv};
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use backtest::stress::{Shock, StressKind, StressParams, parse_kinds, shock_start};
+use execution::sim::ExecutionModel;
+use structure::bos::BosParams;
+use structure::candle::resample_ms;
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
+
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    #[arg(long)]
+    symbol: String,
+    #[arg(long, default_value = "5")]
+    htf_interval: String,
+    #[arg(long, default_value = "1")]
+    ltf_interval: String,
+    #[arg(long)]
+    start: String,
+    #[arg(long)]
+    end: String,
+    /// Кэш LTF свечей, CSV или `.parquet` (feature `parquet`); HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+
+    /// Сценарии шока через запятую: gap, volatility, liquidity
+    #[arg(long, default_value = "gap,volatility,liquidity")]
+    stress_kinds: String,
+    /// Где начинается шок: доли периода через запятую (по границе HTF свечи)
+    #[arg(long, default_value = "0.5")]
+    stress_at: String,
+    /// Длительность volatility/liquidity шока, HTF свечей (гэп остаётся до конца)
+    #[arg(long, default_value_t = 24)]
+    stress_duration_bars: usize,
+    /// Гэп вниз, %
+    #[arg(long, default_value_t = 15.0)]
+    stress_gap_pct: f64,
+    /// Множитель отклонений цены на окне шока
+    #[arg(long, default_value_t = 2.0)]
+    stress_vol_mult: f64,
+    /// Множитель объёма на окне шока (с `--fill-volume-frac` режет fill'ы)
+    #[arg(long, default_value_t = 0.5)]
+    stress_liquidity_mult: f64,
+
+    #[arg(long, default_value_t = 10.0)]
+    maker_fee_bps: f64,
+    #[arg(long, default_value_t = 10.0)]
+    force_close_fee_bps: f64,
+    #[arg(long, default_value_t = 8.0)]
+    force_close_spread_bps: f64,
+    #[arg(long, default_value_t = 2.0)]
+    force_close_slippage_bps: f64,
+
+    #[arg(long, default_value_t = 1000.0)]
+    initial_quote: f64,
+    #[arg(long, default_value_t = 0.0)]
+    initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи (0.5 — поровну);
+    /// делает прогоны сравнимыми между символами и периодами
+    #[arg(long)]
+    initial_base_ratio: Option<f64>,
+
+    /// spot или perp (линейный контракт: плечо, funding, ликвидация)
+    #[arg(long, value_enum, default_value_t = MarketKind::Spot)]
+    market: MarketKind,
+    /// Плечо perp: notional позиции до `equity·leverage`
+    #[arg(long, default_value_t = 1.0)]
+    leverage: f64,
+    /// Поддерживающая маржа perp, доля notional позиции
+    #[arg(long, default_value_t = 0.005)]
+    maintenance_margin_rate: f64,
+    /// Кэш funding (CSV); по умолчанию `data/cache/<SYMBOL>_funding_<start>_<end>.csv`
+    #[arg(long)]
+    funding_cache: Option<String>,
+
+    #[arg(long, default_value_t = 1)]
+    pivot_k: usize,
+    #[arg(long, default_value_t = 0.1)]
+    min_atr_frac: f64,
+    #[arg(long, default_value_t = 2)]
+    bos_confirm_candles: usize,
+    #[arg(long, default_value_t = 0.1)]
+    bos_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.1)]
+    pullback_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.4)]
+    pullback_retrace_frac: f64,
+
+    #[arg(long, default_value_t = 5)]
+    levels: usize,
+    #[arg(long, default_value_t = 12.0)]
+    step_bps: f64,
+    #[arg(long, default_value_t = 25.0)]
+    base_quote_per_order: f64,
+    #[arg(long, default_value_t = 2.0)]
+    max_size_mult: f64,
+    #[arg(long, default_value_t = 0.0001)]
+    min_base_qty: f64,
+
+    #[arg(long, default_value_t = 0.40)]
+    soft_min: f64,
+    #[arg(long, default_value_t = 0.60)]
+    soft_max: f64,
+    #[arg(long, default_value_t = 0.35)]
+    hard_min: f64,
+    #[arg(long, default_value_t = 0.65)]
+    hard_max: f64,
+
+    #[arg(long, default_value_t = true)]
+    force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
+    #[arg(long, default_value_t = 1.5)]
+    defensive_step_mult: f64,
+    #[arg(long, default_value_t = 0.5)]
+    defensive_size_mult: f64,
+    #[arg(long, default_value_t = true)]
+    bootstrap_rebalance: bool,
+    #[arg(long, default_value_t = 0.50)]
+    bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
+
+    /// Метрики базового прогона и каждого сценария
+    #[arg(long, default_value = "data/mm_mtf_stress.csv")]
+    table_out: String,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг, метрики сценариев, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+    /// Без progress bar: прогресс строками `progress:` в stdout (так запускает worker)
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ScenarioRow {
+    scenario: &'static str,
+    at_frac: Option<f64>,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+    pnl: f64,
+    roi_pct: f64,
+    max_drawdown_pct: f64,
+    max_drawdown_duration_h: f64,
+    /// Доля свечей с долей base вне hard band, %
+    outside_hard_band_pct: f64,
+    bootstrap_trades: usize,
+    liquidations: usize,
+    /// Вынужденные taker выходы: bootstrap и ликвидации
+    forced_exits: usize,
+    /// Просадка сценария минус просадка базового прогона, п.п.
+    drawdown_vs_baseline_pct: f64,
+}
+
+impl ScenarioRow {
+    fn new(
+        scenario: &'static str,
+        shock: Option<(f64, &Shock)>,
+        r: &MmReport,
+        baseline_dd: f64,
+    ) -> Self {
+        Self {
+            scenario,
+            at_frac: shock.map(|(at, _)| at),
+            start_ts: shock.map(|(_, s)| s.start),
+            end_ts: shock.map(|(_, s)| s.end),
+            pnl: r.perf.pnl,
+            roi_pct: r.perf.roi_pct,
+            max_drawdown_pct: r.perf.max_drawdown_pct,
+            max_drawdown_duration_h: r.perf.max_drawdown_duration_h,
+            outside_hard_band_pct: r.outside_hard_band_pct,
+            bootstrap_trades: r.bootstrap_trades,
+            liquidations: r.liquidations,
+            forced_exits: r.bootstrap_trades + r.liquidations,
+            drawdown_vs_baseline_pct: r.perf.max_drawdown_pct - baseline_dd,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Metrics<'a> {
+    scenarios: &'a [ScenarioRow],
+}
+
+fn scenario_name(kind: StressKind) -> &'static str {
+    match kind {
+        StressKind::Gap => "gap",
+        StressKind::Volatility => "volatility",
+        StressKind::Liquidity => "liquidity",
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote < 0.0 || args.initial_base < 0.0 {
+        anyhow::bail!("initial balances must be non-negative");
+    }
+    if args
+        .initial_base_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
+    {
+        anyhow::bail!("initial_base_ratio must be in [0, 1]");
+    }
+    let kinds = parse_kinds(&args.stress_kinds)?;
+    let points = parse_num_list::<f64>(&args.stress_at, "stress_at")?;
+    if points.iter().any(|p| !(0.0..=1.0).contains(p)) {
+        anyhow::bail!("stress_at must be in [0, 1]");
+    }
+    if args.stress_duration_bars == 0 {
+        anyhow::bail!("stress_duration_bars must be >= 1");
+    }
+    let stress = StressParams {
+        gap_pct: args.stress_gap_pct,
+        vol_mult: args.stress_vol_mult,
+        liquidity_mult: args.stress_liquidity_mult,
+    };
+    stress.validate()?;
+
+    let params = MmParams {
+        levels: args.levels,
+        step_bps: args.step_bps,
+        base_quote_per_order: args.base_quote_per_order,
+        max_size_mult: args.max_size_mult,
+        min_base_qty: args.min_base_qty,
+        soft_min: args.soft_min,
+        soft_max: args.soft_max,
+        hard_min: args.hard_min,
+        hard_max: args.hard_max,
+        maker_fee_bps: args.maker_fee_bps,
+        defensive_step_mult: args.defensive_step_mult,
+        defensive_size_mult: args.defensive_size_mult,
+        signal: SignalParams {
+            structure: StructureParams {
+                pivot_k: args.pivot_k,
+                min_atr_frac: args.min_atr_frac,
+            },
+            bos: BosParams {
+                confirm_candles: args.bos_confirm_candles,
+                epsilon_frac: args.bos_epsilon_frac,
+            },
+            pullback: PullbackParams {
+                epsilon_frac: args.pullback_epsilon_frac,
+                retrace_frac: args.pullback_retrace_frac,
+            },
+        },
+    };
+    params.validate_bands()?;
+    params.signal.validate()?;
+
+    let htf_ms = parse_interval_ms(&args.htf_interval)?;
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+    let perp = PerpParams::for_market(args.market, args.leverage, args.maintenance_margin_rate)?;
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    let ltf = match synthetic {
+        Some(p) => synthetic_candles(&p, ltf_ms, range, args.seed),
+        None => {
+            load_candles(
+                args.exchange,
+                &args.symbol,
+                &args.ltf_interval,
+                range,
+                args.ltf_cache.as_deref(),
+                args.refresh,
+                gate,
+            )
+            .await?
+        }
+    };
+    // качается только LTF серия, HTF собирается из неё
+    let htf = resample_ms(&ltf, htf_ms);
+    if htf.len() < 20 || ltf.len() < 20 {
+        anyhow::bail!("not enough candles: htf={} ltf={}", htf.len(), ltf.len());
+    }
+    ensure_ltf_coverage(&args.symbol, &htf, &ltf, (htf_ms, ltf_ms), range, gate)?;
+    // у синтетики нет funding: perp считается с нулевыми ставками
+    let funding = match perp {
+        Some(_) if synthetic.is_none() => {
+            load_funding(
+                &args.symbol,
+                range,
+                args.funding_cache.as_deref(),
+                args.refresh,
+            )
+            .await?
+        }
+        _ => Vec::new(),
+    };
+
+    let cfg = MmRunConfig {
+        initial_quote: args.initial_quote,
+        initial_base: args.initial_base,
+        initial_base_ratio: args.initial_base_ratio,
+        perp,
+        taker: ExecutionModel {
+            fee_bps: args.force_close_fee_bps,
+            spread_bps: args.force_close_spread_bps,
+            slippage_bps: args.force_close_slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        force_close_twap,
+        bootstrap_rebalance: args.bootstrap_rebalance,
+        bootstrap_target_ratio: args.bootstrap_target_ratio,
+        intrabar: args.intrabar_path,
+        fill,
+        record: false,
+    };
+
+    let mut progress = Progress::new("stress", "runs", 1 + kinds.len() * points.len(), args.quiet);
+    let baseline = run_mm_mtf_with_funding(&htf, &ltf, &funding, htf_ms, &params, cfg).report;
+    progress.tick();
+    let baseline_dd = baseline.perf.max_drawdown_pct;
+    let mut rows = vec![ScenarioRow::new("baseline", None, &baseline, baseline_dd)];
+    for &at in &points {
+        let Some(start) = shock_start(&htf, at, htf_ms) else {
+            continue;
+        };
+        let window = (start, start + args.stress_duration_bars as i64 * htf_ms);
+        for &kind in &kinds {
+            let shock = Shock::new(kind, window, stress, &ltf);
+            let (s_htf, s_ltf) = (shock.apply(&htf), shock.apply(&ltf));
+            let r = run_mm_mtf_with_funding(&s_htf, &s_ltf, &funding, htf_ms, &params, cfg).report;
+            rows.push(ScenarioRow::new(
+                scenario_name(kind),
+                Some((at, &shock)),
+                &r,
+                baseline_dd,
+            ));
+            progress.tick();
+        }
+    }
+
+    write_csv(&args.table_out, &rows).context("write stress table failed")?;
+
+    println!("MM MTF stress test finished");
+    println!(
+        "stress: gap_pct={:.2} vol_mult={:.2} liquidity_mult={:.2} duration_bars={}",
+        stress.gap_pct, stress.vol_mult, stress.liquidity_mult, args.stress_duration_bars
+    );
+    for r in &rows {
+        println!(
+            "scenario={} at={} pnl={:.4} roi={:.2}% max_dd={:.2}% (vs baseline {:+.2}) outside_hard_band={:.2}% bootstrap={} liquidations={} forced_exits={}",
+            r.scenario,
+            r.at_frac.map_or("-".to_string(), |a| format!("{:.2}", a)),
+            r.pnl,
+            r.roi_pct,
+            r.max_drawdown_pct,
+            r.drawdown_vs_baseline_pct,
+            r.outside_hard_band_pct,
+            r.bootstrap_trades,
+            r.liquidations,
+            r.forced_exits
+        );
+    }
+
+    JsonReport::new(
+        "backtest_mm_mtf_stress",
+        &args,
+        Metrics { scenarios: &rows },
+    )
+    .with_seed(args.seed)
+    .artifact("table_csv", &args.table_out)
+    .finish(args.report_out.as_deref())?;
+
+    Ok(())
+}
@@ -1,20 +1,40 @@
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, TimeZone, Utc};
 use clap::Parser;
+use serde::Serialize;
 
-use bybit::rest::{BybitRest, download_range};
-use core::types::{Bps, Money, Price, Qty, Ratio};
-use engine::feed::CandleFeed;
+use backtest::checkpoint::Checkpoint;
+use backtest::config::parse_args;
+use backtest::data::{
+    Venue, cache_path_for, date_range_ms, ensure_ltf_coverage, explicit_cache, load_candles,
+    oos_start_ms, parse_interval_ms, parse_num_list, parse_symbols, split_at_ms,
+};
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmReport, MmRunConfig,
+    SignalParams, run_mm_mtf_with_funding,
+};
+use backtest::parallel::{par_map, pool};
+use backtest::perp::{MarketKind, PerpParams, load_funding};
+use backtest::progress::Progress;
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{CsvStream, JsonReport, SymbolRow, write_csv};
+use backtest::search::{Dim, Search, SearchMode};
+use backtest::stability::{StabilitySummary, neighborhood};
+use backtest::stats::{Performance, RankBy};
+use backtest::table::write_table;
+use backtest::topn::TopN;
+use backtest::walkforward::{OosSummary, WalkForward, WfConfigRow, WfResult, WfWindow, slice_ms};
+use bybit::rest::FundingRate;
 use execution::sim::ExecutionModel;
-use mm::grid::{GridParams, Inventory, Side, build_grid};
-use policy::mm_policy::{MmDecisionReason, MmMode, MmPolicyParams, mm_policy_decision};
-use structure::bos::{BosParams, BosState, BosTracker};
-use structure::pullback::{PullbackParams, PullbackTracker};
-use structure::structure::{StructureParams, detect_structure};
+use structure::bos::BosParams;
+use structure::candle::{Candle, resample_ms};
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 struct Args {
-    #[arg(long)]
+    /// Символ или список (`--symbols BTCUSDT,ETHUSDT`): конфиг прогоняется на каждом,
+    /// ранжирование по средним метрикам; кэши — `<cache>_<SYMBOL>.csv`
+    #[arg(long, alias = "symbols")]
     symbol: String,
     #[arg(long, default_value = "5")]
     htf_interval: String,
@@ -24,17 +44,57 @@ struct Args {
     start: String,
     #[arg(long)]
     end: String,
-    #[arg(long, default_value = "data/backtest_mm_mtf_sweep_htf.csv")]
-    htf_cache: String,
-    #[arg(long, default_value = "data/backtest_mm_mtf_sweep_ltf.csv")]
-    ltf_cache: String,
+    /// Кэш LTF свечей, CSV или `.parquet` (feature `parquet`); HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
     #[arg(long, default_value_t = false)]
     refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
 
     #[arg(long, default_value_t = 1000.0)]
     initial_quote: f64,
     #[arg(long, default_value_t = 0.0)]
     initial_base: f64,
+    /// Доля base в начальной equity по close первой свечи (0.5 — поровну);
+    /// делает прогоны сравнимыми между символами и периодами
+    #[arg(long)]
+    initial_base_ratio: Option<f64>,
+    /// spot или perp (линейный контракт: плечо, funding, ликвидация)
+    #[arg(long, value_enum, default_value_t = MarketKind::Spot)]
+    market: MarketKind,
+    /// Плечо perp: notional позиции до `equity·leverage`
+    #[arg(long, default_value_t = 1.0)]
+    leverage: f64,
+    /// Поддерживающая маржа perp, доля notional позиции
+    #[arg(long, default_value_t = 0.005)]
+    maintenance_margin_rate: f64,
+    /// Кэш funding (CSV); по умолчанию `data/cache/<SYMBOL>_funding_<start>_<end>.csv`
+    #[arg(long)]
+    funding_cache: Option<String>,
+
+    /// grid — все комбинации списков; random/tpe — `--samples` точек,
+    /// числовые списки можно задавать диапазоном `lo..hi`
+    #[arg(long, value_enum, default_value_t = SearchMode::Grid)]
+    search: SearchMode,
+    #[arg(long, default_value_t = 200)]
+    samples: usize,
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Потоков для прогонов сетки и walk-forward, 0 — по числу ядер
+    #[arg(long, default_value_t = 0)]
+    jobs: usize,
+    /// Обновлять прогресс каждые N посчитанных конфигов
+    #[arg(long, default_value_t = 10)]
+    progress_every: usize,
 
     #[arg(long, default_value = "3,5,7")]
     levels_list: String,
@@ -62,6 +122,19 @@ struct Args {
     defensive_step_mult_list: String,
     #[arg(long, default_value = "0.5")]
     defensive_size_mult_list: String,
+
+    #[arg(long, default_value = "1")]
+    pivot_k_list: String,
+    #[arg(long, default_value = "0.1")]
+    min_atr_frac_list: String,
+    #[arg(long, default_value = "2")]
+    bos_confirm_candles_list: String,
+    #[arg(long, default_value = "0.1")]
+    bos_epsilon_frac_list: String,
+    #[arg(long, default_value = "0.1")]
+    pullback_epsilon_frac_list: String,
+    #[arg(long, default_value = "0.4")]
+    pullback_retrace_frac_list: String,
     #[arg(long, default_value_t = 10.0)]
     force_close_fee_bps: f64,
     #[arg(long, default_value_t = 8.0)]
@@ -70,28 +143,100 @@ struct Args {
     force_close_slippage_bps: f64,
     #[arg(long, default_value_t = true)]
     force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
     #[arg(long, default_value_t = true)]
     bootstrap_rebalance: bool,
     #[arg(long, default_value_t = 0.50)]
     bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
+
+    /// Доля конца периода под out-of-sample: ранжирование по началу,
+    /// OOS метрики top_n — в summary
+    #[arg(long, default_value_t = 0.0)]
+    oos_split: f64,
+    /// Walk-forward: дней обучения в окне; вместе с `--wf-test-days` лучший конфиг сетки
+    /// выбирается заново на каждом окне, итог — out-of-sample метрики (только `--search grid`)
+    #[arg(long)]
+    wf_train_days: Option<u32>,
+    /// Walk-forward: дней проверки сразу после обучения
+    #[arg(long)]
+    wf_test_days: Option<u32>,
+    /// Walk-forward: сдвиг окна, дней (по умолчанию — `--wf-test-days`)
+    #[arg(long)]
+    wf_step_days: Option<u32>,
+    /// Walk-forward: выбранный конфиг и его метрики по каждому окну
+    #[arg(long, default_value = "data/mm_mtf_sweep_wf_windows.csv")]
+    wf_windows_out: String,
+    /// Walk-forward: out-of-sample метрики каждого конфига по всем test окнам
+    #[arg(long, default_value = "data/mm_mtf_sweep_wf_configs.csv")]
+    wf_configs_out: String,
 
     #[arg(long, default_value_t = 20)]
     top_n: usize,
+    #[arg(long, value_enum, default_value_t = RankBy::Roi)]
+    rank_by: RankBy,
     #[arg(long, default_value = "data/mm_mtf_sweep_summary.csv")]
     summary_out: String,
+    /// Все протестированные конфигурации (не только top_n) — для анализа без перезапуска.
+    /// Здесь и в summary/per-symbol/stability: `.parquet` — Parquet (feature `parquet`)
+    #[arg(long, default_value = "data/mm_mtf_sweep_results.csv")]
+    results_out: String,
+    /// Писать каждый конфиг в `--results-out` (только CSV) по ходу sweep'а, в памяти держать
+    /// лишь лучшие (top_n/stability/artifacts) — для сеток на миллионы конфигов; rank там 0
+    #[arg(long, default_value_t = false)]
+    stream_results: bool,
+    /// Метрики top_n конфигов по каждому символу
+    #[arg(long, default_value = "data/mm_mtf_sweep_symbols.csv")]
+    per_symbol_out: String,
+    /// Посчитанные конфиги дописываются сюда по ходу sweep'а
+    #[arg(long, default_value = "data/mm_mtf_sweep_checkpoint.jsonl")]
+    checkpoint: String,
+    #[arg(long, default_value_t = 50)]
+    checkpoint_every: usize,
+    /// Не пересчитывать конфиги из `--checkpoint` (тот же прогон: данные, диапазон, издержки)
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+    /// Перезапустить top-K конфигов с записью equity/fills CSV (артефакты rankN_*)
+    #[arg(long, default_value_t = 3)]
+    top_artifacts: usize,
+    #[arg(long, default_value = "data/mm_mtf_sweep_top")]
+    top_artifacts_dir: String,
+    /// Для top-K конфигов прогнать соседей ± шаг по каждому параметру
+    #[arg(long, default_value_t = 3)]
+    stability_top: usize,
+    #[arg(long, default_value = "data/mm_mtf_sweep_stability.csv")]
+    stability_out: String,
+    /// Конфиг хрупкий, если худший сосед теряет больше этой доли ROI
+    #[arg(long, default_value_t = 0.5)]
+    fragile_roi_drop: f64,
+    /// JSON отчёт: конфиг, метрики лучшего прогона, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+    /// Без progress bar: прогресс строками `progress:` в stdout (так запускает worker)
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct CandleRow {
-    ts: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-#[derive(serde::Serialize)]
+#[derive(Serialize)]
 struct SummaryRow {
     rank: usize,
     levels: usize,
@@ -105,6 +250,12 @@ struct SummaryRow {
     maker_fee_bps: f64,
     defensive_step_mult: f64,
     defensive_size_mult: f64,
+    pivot_k: usize,
+    min_atr_frac: f64,
+    bos_confirm_candles: usize,
+    bos_epsilon_frac: f64,
+    pullback_epsilon_frac: f64,
+    pullback_retrace_frac: f64,
     buy_fills: usize,
     sell_fills: usize,
     bootstrap_trades: usize,
@@ -115,463 +266,403 @@ struct SummaryRow {
     max_drawdown_pct: f64,
     pnl: f64,
     roi_pct: f64,
+    sharpe: f64,
+    sortino: f64,
+    calmar: f64,
+    exposure_pct: f64,
+    avg_trade_duration_h: f64,
+    turnover: f64,
+    /// ROI на худшем символе
+    min_symbol_roi_pct: f64,
+    oos_roi_pct: Option<f64>,
+    oos_max_drawdown_pct: Option<f64>,
+    oos_sharpe: Option<f64>,
+    oos_profit_factor: Option<f64>,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct MmMtfConfig {
-    levels: usize,
-    step_bps: f64,
-    base_quote_per_order: f64,
-    max_size_mult: f64,
-    soft_min: f64,
-    soft_max: f64,
-    hard_min: f64,
-    hard_max: f64,
-    maker_fee_bps: f64,
-    defensive_step_mult: f64,
-    defensive_size_mult: f64,
+/// Метрики sweep'а для JSON отчёта: метрики лучшего прогона на верхнем уровне
+#[derive(Serialize)]
+struct SweepMetrics<'a> {
+    tested: usize,
+    top_saved: usize,
+    #[serde(flatten)]
+    best: Option<&'a MmReport>,
+    best_config: Option<&'a SummaryRow>,
+    per_symbol: &'a [SymbolRow],
+    stability: &'a [StabilitySummary],
+    /// Метрики лучшего конфига на out-of-sample
+    oos: Option<&'a Performance>,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct MmMtfReport {
-    buy_fills: usize,
-    sell_fills: usize,
-    bootstrap_trades: usize,
-    win_rate_pct: f64,
-    avg_win: f64,
-    avg_loss: f64,
-    profit_factor: f64,
-    max_drawdown_pct: f64,
-    pnl: f64,
-    roi_pct: f64,
+/// Метрики walk-forward для JSON отчёта: OOS выбранных конфигов подряд на верхнем уровне
+#[derive(Serialize)]
+struct WalkForwardMetrics<'a> {
+    configs: usize,
+    #[serde(flatten)]
+    oos: &'a OosSummary,
+    windows: &'a [WfWindow],
+    best_configs: &'a [WfConfigRow],
 }
 
-fn parse_interval_ms(interval: &str) -> Result<i64> {
-    let mins: i64 = interval
-        .parse()
-        .with_context(|| format!("interval must be numeric minutes, got {}", interval))?;
-    if mins <= 0 {
-        anyhow::bail!("interval must be > 0");
-    }
-    Ok(mins * 60 * 1000)
+/// Символ с in-sample и out-of-sample свечами обоих таймфреймов
+struct Market {
+    symbol: String,
+    htf: Vec<Candle>,
+    ltf: Vec<Candle>,
+    oos_htf: Vec<Candle>,
+    oos_ltf: Vec<Candle>,
+    /// Funding на весь диапазон (perp), прогон берёт ставки со своего старта
+    funding: Vec<FundingRate>,
 }
 
-fn parse_num_list<T>(s: &str, name: &str) -> Result<Vec<T>>
-where
-    T: std::str::FromStr,
-    <T as std::str::FromStr>::Err: std::fmt::Display,
-{
-    let mut out = Vec::new();
-    for raw in s.split(',') {
-        let v = raw.trim();
-        if v.is_empty() {
-            continue;
-        }
-        let parsed = v
-            .parse::<T>()
-            .map_err(|e| anyhow::anyhow!("bad value in {}: '{}' ({})", name, v, e))?;
-        out.push(parsed);
-    }
-    if out.is_empty() {
-        anyhow::bail!("{} cannot be empty", name);
-    }
-    Ok(out)
+fn symbol_key(symbol: &str, cfg: &MmParams) -> String {
+    format!("{} {:?}", symbol, cfg)
 }
 
-fn date_to_ms(date: &str) -> Result<i64> {
-    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .with_context(|| format!("bad date: {}", date))?;
-    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
-    Ok(dt.timestamp_millis())
+/// Конфиг на каждом символе (прогоны — через чекпоинт); отчёт — среднее
+/// Лучший конфиг в progress bar
+fn best_label(cfg: &MmParams) -> String {
+    format!(
+        "levels={} step_bps={:.2} qpo={:.2} bands=({:.2}-{:.2}|{:.2}-{:.2}) fee={:.2}",
+        cfg.levels,
+        cfg.step_bps,
+        cfg.base_quote_per_order,
+        cfg.hard_min,
+        cfg.soft_min,
+        cfg.soft_max,
+        cfg.hard_max,
+        cfg.maker_fee_bps
+    )
 }
 
-fn read_cache(path: &str) -> Result<Vec<structure::candle::Candle>> {
-    let mut rdr = csv::Reader::from_path(path)?;
-    let mut out = Vec::new();
-    for r in rdr.deserialize::<CandleRow>() {
-        let row = r?;
-        out.push(structure::candle::Candle {
-            ts: core::types::TimestampMs(row.ts),
-            open: Price(row.open),
-            high: Price(row.high),
-            low: Price(row.low),
-            close: Price(row.close),
-            volume: Qty(row.volume),
-        });
+/// Строка results/summary; `oos` — метрики конфига на out-of-sample
+fn summary_row(
+    rank: usize,
+    cfg: &MmParams,
+    rep: &MmReport,
+    min_symbol_roi_pct: f64,
+    oos: Option<&Performance>,
+) -> SummaryRow {
+    SummaryRow {
+        rank,
+        levels: cfg.levels,
+        step_bps: cfg.step_bps,
+        base_quote_per_order: cfg.base_quote_per_order,
+        max_size_mult: cfg.max_size_mult,
+        soft_min: cfg.soft_min,
+        soft_max: cfg.soft_max,
+        hard_min: cfg.hard_min,
+        hard_max: cfg.hard_max,
+        maker_fee_bps: cfg.maker_fee_bps,
+        defensive_step_mult: cfg.defensive_step_mult,
+        defensive_size_mult: cfg.defensive_size_mult,
+        pivot_k: cfg.signal.structure.pivot_k,
+        min_atr_frac: cfg.signal.structure.min_atr_frac,
+        bos_confirm_candles: cfg.signal.bos.confirm_candles,
+        bos_epsilon_frac: cfg.signal.bos.epsilon_frac,
+        pullback_epsilon_frac: cfg.signal.pullback.epsilon_frac,
+        pullback_retrace_frac: cfg.signal.pullback.retrace_frac,
+        buy_fills: rep.buy_fills,
+        sell_fills: rep.sell_fills,
+        bootstrap_trades: rep.bootstrap_trades,
+        win_rate_pct: rep.perf.win_rate_pct,
+        avg_win: rep.perf.avg_win,
+        avg_loss: rep.perf.avg_loss,
+        profit_factor: rep.perf.profit_factor,
+        max_drawdown_pct: rep.perf.max_drawdown_pct,
+        pnl: rep.perf.pnl,
+        roi_pct: rep.perf.roi_pct,
+        sharpe: rep.perf.sharpe,
+        sortino: rep.perf.sortino,
+        calmar: rep.perf.calmar,
+        exposure_pct: rep.perf.exposure_pct,
+        avg_trade_duration_h: rep.perf.avg_trade_duration_h,
+        turnover: rep.perf.turnover,
+        min_symbol_roi_pct,
+        oos_roi_pct: oos.map(|p| p.roi_pct),
+        oos_max_drawdown_pct: oos.map(|p| p.max_drawdown_pct),
+        oos_sharpe: oos.map(|p| p.sharpe),
+        oos_profit_factor: oos.map(|p| p.profit_factor),
     }
-    Ok(out)
 }
 
-fn write_cache(path: &str, candles: &[structure::candle::Candle]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for c in candles {
-        wtr.serialize(CandleRow {
-            ts: c.ts.0,
-            open: c.open.0,
-            high: c.high.0,
-            low: c.low.0,
-            close: c.close.0,
-            volume: c.volume.0,
-        })?;
-    }
-    wtr.flush()?;
-    Ok(())
+/// ROI конфига на худшем символе (прогоны — из чекпоинта)
+fn min_symbol_roi(checkpoint: &Checkpoint<MmReport>, markets: &[Market], cfg: &MmParams) -> f64 {
+    markets
+        .iter()
+        .filter_map(|m| checkpoint.get(&symbol_key(&m.symbol, cfg)))
+        .map(|r| r.perf.roi_pct)
+        .fold(f64::INFINITY, f64::min)
 }
 
-fn write_summary(path: &str, rows: &[SummaryRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
-    }
-    wtr.flush()?;
-    Ok(())
+/// Посчитанные конфиги: все в памяти или, с `--stream-results`, строки сразу в results CSV,
+/// а в памяти — только лучшие `keep`
+struct Collected {
+    top: TopN<(MmParams, MmReport)>,
+    stream: Option<CsvStream>,
+    rank_by: RankBy,
+    tested: usize,
 }
 
-fn run_mm_mtf(
-    htf: &[structure::candle::Candle],
-    ltf: &[structure::candle::Candle],
-    htf_ms: i64,
-    cfg: MmMtfConfig,
-    min_base_qty: f64,
-    initial_quote: f64,
-    initial_base: f64,
-    force_close_exec: ExecutionModel,
-    force_close_at_end: bool,
-    bootstrap_rebalance: bool,
-    bootstrap_target_ratio: f64,
-) -> MmMtfReport {
-    let mut feed = CandleFeed::new(240);
-    let mut bos = BosTracker::new();
-    let mut pullback = PullbackTracker::new();
-    let bos_params = BosParams {
-        confirm_candles: 2,
-        epsilon_frac: 0.1,
-    };
-    let pullback_params = PullbackParams {
-        epsilon_frac: 0.1,
-        retrace_frac: 0.4,
-    };
-    let structure_params = StructureParams {
-        pivot_k: 1,
-        min_atr_frac: 0.1,
-    };
-
-    let mm_policy = MmPolicyParams {
-        soft_min: Ratio(cfg.soft_min),
-        soft_max: Ratio(cfg.soft_max),
-        hard_min: Ratio(cfg.hard_min),
-        hard_max: Ratio(cfg.hard_max),
-    };
-    let grid_params = GridParams {
-        levels: cfg.levels,
-        step: Bps(cfg.step_bps),
-        base_quote_per_order: Money(cfg.base_quote_per_order),
-        max_size_mult: cfg.max_size_mult,
-        soft_min: Ratio(cfg.soft_min),
-        soft_max: Ratio(cfg.soft_max),
-        hard_min: Ratio(cfg.hard_min),
-        hard_max: Ratio(cfg.hard_max),
-        min_base_qty: Qty(min_base_qty),
-    };
+impl Collected {
+    fn new(args: &Args) -> Result<Self> {
+        let stream = if args.stream_results {
+            Some(CsvStream::create(&args.results_out)?)
+        } else {
+            None
+        };
+        let keep = match stream {
+            Some(_) => args.top_n.max(args.stability_top).max(args.top_artifacts),
+            None => usize::MAX,
+        };
+        Ok(Self {
+            top: TopN::new(keep),
+            stream,
+            rank_by: args.rank_by,
+            tested: 0,
+        })
+    }
 
-    let maker_fee_ratio = cfg.maker_fee_bps.max(0.0) / 10_000.0;
-    let mut quote = initial_quote;
-    let mut base = initial_base;
-    let mut cost_basis_quote = if base > 0.0 { base * htf[0].close.0 } else { 0.0 };
-
-    let mut buy_fills = 0usize;
-    let mut sell_fills = 0usize;
-    let mut bootstrap_trades = 0usize;
-    let mut winning_sells = 0usize;
-    let mut losing_sells = 0usize;
-    let mut gross_profit = 0.0_f64;
-    let mut gross_loss = 0.0_f64;
-    let mut max_equity = quote + base * htf[0].close.0;
-    let mut max_drawdown = 0.0_f64;
-
-    let mut active_mode = MmMode::Disabled;
-    let mut ltf_idx = 0usize;
-
-    for h in htf.iter().copied() {
-        let window_start = h.ts.0;
-        let window_end = window_start + htf_ms;
-
-        while ltf_idx < ltf.len() && ltf[ltf_idx].ts.0 < window_start {
-            ltf_idx += 1;
+    fn push(
+        &mut self,
+        checkpoint: &mut Checkpoint<MmReport>,
+        markets: &[Market],
+        cfg: MmParams,
+        rep: MmReport,
+    ) -> Result<()> {
+        self.tested += 1;
+        if let Some(stream) = &mut self.stream {
+            let min_roi = min_symbol_roi(checkpoint, markets, &cfg);
+            stream.write(&summary_row(0, &cfg, &rep, min_roi, None))?;
         }
-        while ltf_idx < ltf.len() && ltf[ltf_idx].ts.0 < window_end {
-            let lc = ltf[ltf_idx];
-            let inv = Inventory {
-                base: Qty(base),
-                quote: Money(quote),
-            };
-            if matches!(active_mode, MmMode::Normal | MmMode::Defensive) {
-                let mode_grid_params = match active_mode {
-                    MmMode::Defensive => GridParams {
-                        step: Bps(grid_params.step.0 * cfg.defensive_step_mult.max(1.0)),
-                        base_quote_per_order: Money(
-                            grid_params.base_quote_per_order.0
-                                * cfg.defensive_size_mult.clamp(0.05, 1.0),
-                        ),
-                        ..grid_params
-                    },
-                    _ => grid_params,
-                };
-                if let Some(mut orders) = build_grid(lc.close, lc.close, inv, mode_grid_params) {
-                    orders.sort_by(|a, b| match (a.side, b.side) {
-                        (Side::Buy, Side::Buy) => b
-                            .price
-                            .0
-                            .partial_cmp(&a.price.0)
-                            .unwrap_or(std::cmp::Ordering::Equal),
-                        (Side::Sell, Side::Sell) => a
-                            .price
-                            .0
-                            .partial_cmp(&b.price.0)
-                            .unwrap_or(std::cmp::Ordering::Equal),
-                        (Side::Buy, Side::Sell) => std::cmp::Ordering::Less,
-                        (Side::Sell, Side::Buy) => std::cmp::Ordering::Greater,
-                    });
-                    for o in orders {
-                        match o.side {
-                            Side::Buy => {
-                                if lc.low.0 > o.price.0 {
-                                    continue;
-                                }
-                                let gross = o.qty.0 * o.price.0;
-                                let fee = gross * maker_fee_ratio;
-                                let total_cost = gross + fee;
-                                if total_cost > quote || o.qty.0 <= 0.0 {
-                                    continue;
-                                }
-                                quote -= total_cost;
-                                base += o.qty.0;
-                                cost_basis_quote += total_cost;
-                                buy_fills += 1;
-                            }
-                            Side::Sell => {
-                                if lc.high.0 < o.price.0 || base <= 0.0 {
-                                    continue;
-                                }
-                                let qty = o.qty.0.min(base);
-                                if qty <= 0.0 {
-                                    continue;
-                                }
-                                let base_before = base;
-                                let avg_cost = if base_before > 0.0 {
-                                    cost_basis_quote / base_before
-                                } else {
-                                    0.0
-                                };
-                                let gross = qty * o.price.0;
-                                let fee = gross * maker_fee_ratio;
-                                let proceeds = gross - fee;
-                                let removed_cost = avg_cost * qty;
-                                let realized = proceeds - removed_cost;
-                                quote += proceeds;
-                                base -= qty;
-                                cost_basis_quote = (cost_basis_quote - removed_cost).max(0.0);
-                                if base <= 1e-12 {
-                                    base = 0.0;
-                                    cost_basis_quote = 0.0;
-                                }
-                                sell_fills += 1;
-                                if realized > 0.0 {
-                                    winning_sells += 1;
-                                    gross_profit += realized;
-                                } else if realized < 0.0 {
-                                    losing_sells += 1;
-                                    gross_loss += -realized;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            let equity = quote + base * lc.close.0;
-            max_equity = max_equity.max(equity);
-            if max_equity > 0.0 {
-                let dd = (max_equity - equity) / max_equity;
-                max_drawdown = max_drawdown.max(dd);
+        let by = self.rank_by;
+        let evicted = self
+            .top
+            .push((cfg, rep), |a, b| a.1.perf.rank_cmp(&b.1.perf, by));
+        // прогоны вытесненных по символам остаются только в файле чекпоинта
+        for (cfg, _) in evicted {
+            for m in markets {
+                checkpoint.forget(&symbol_key(&m.symbol, &cfg));
             }
-            ltf_idx += 1;
         }
+        Ok(())
+    }
 
-        feed.push(h);
-        let (Some(atr), Some(mid)) = (feed.atr(), feed.mid()) else {
-            active_mode = MmMode::Disabled;
-            continue;
-        };
-        let ms = detect_structure(&feed.candles, structure_params);
-        bos.on_candle_close(&h, &ms, atr, bos_params);
-        if bos.state == BosState::Confirmed {
-            pullback.on_candle_close(&h, &bos, atr, pullback_params);
-        } else {
-            pullback.reset();
+    /// Сколько конфигов посчитано и лучшие из них по убыванию
+    fn finish(mut self) -> Result<(usize, Vec<(MmParams, MmReport)>)> {
+        if let Some(stream) = &mut self.stream {
+            stream.flush()?;
         }
+        let by = self.rank_by;
+        let best = self
+            .top
+            .into_sorted(|a, b| a.1.perf.rank_cmp(&b.1.perf, by));
+        Ok((self.tested, best))
+    }
+}
 
-        let inv = Inventory {
-            base: Qty(base),
-            quote: Money(quote),
-        };
-        if let Some(ratio) = mm::grid::base_ratio(inv, mid) {
-            let mut decision = mm_policy_decision(bos.state, &pullback, ratio, mm_policy);
-            if bootstrap_rebalance
-                && matches!(
-                    decision.reason,
-                    MmDecisionReason::InventoryOutsideHardBand
-                )
-                && bos.state == BosState::Confirmed
-                && pullback.triggered
-            {
-                let equity = quote + base * mid.0;
-                let target = bootstrap_target_ratio.clamp(0.0, 1.0);
-                let target_base_value = target * equity;
-                let current_base_value = base * mid.0;
-                let delta_value = target_base_value - current_base_value;
-                if delta_value > 0.0 && quote > 0.0 {
-                    let qty = force_close_exec.buy_qty_for_quote(delta_value.min(quote), mid);
-                    if qty.0 > 0.0 {
-                        let cost = force_close_exec.buy_cost(qty, mid);
-                        if cost <= quote {
-                            quote -= cost;
-                            base += qty.0;
-                            cost_basis_quote += cost;
-                            buy_fills += 1;
-                            bootstrap_trades += 1;
-                        }
-                    }
-                } else if delta_value < 0.0 && base > 0.0 {
-                    let qty = ((-delta_value) / mid.0).min(base);
-                    if qty > 0.0 {
-                        let proceeds = force_close_exec.sell_proceeds(Qty(qty), mid);
-                        let base_before = base;
-                        let avg_cost = if base_before > 0.0 {
-                            cost_basis_quote / base_before
-                        } else {
-                            0.0
-                        };
-                        let removed_cost = avg_cost * qty;
-                        let realized = proceeds - removed_cost;
-                        quote += proceeds;
-                        base -= qty;
-                        cost_basis_quote = (cost_basis_quote - removed_cost).max(0.0);
-                        if base <= 1e-12 {
-                            base = 0.0;
-                            cost_basis_quote = 0.0;
-                        }
-                        sell_fills += 1;
-                        bootstrap_trades += 1;
-                        if realized > 0.0 {
-                            winning_sells += 1;
-                            gross_profit += realized;
-                        } else if realized < 0.0 {
-                            losing_sells += 1;
-                            gross_loss += -realized;
-                        }
-                    }
-                }
-                let inv2 = Inventory {
-                    base: Qty(base),
-                    quote: Money(quote),
-                };
-                if let Some(r2) = mm::grid::base_ratio(inv2, mid) {
-                    decision = mm_policy_decision(bos.state, &pullback, r2, mm_policy);
+/// Прогон конфига на одном символе: чистая функция от свечей, её гоняют потоки sweep'а
+fn run_symbol(m: &Market, htf_ms: i64, cfg: &MmParams, run_cfg: MmRunConfig) -> MmReport {
+    run_mm_mtf_with_funding(&m.htf, &m.ltf, &m.funding, htf_ms, cfg, run_cfg).report
+}
+
+fn evaluate(
+    checkpoint: &mut Checkpoint<MmReport>,
+    markets: &[Market],
+    htf_ms: i64,
+    cfg: &MmParams,
+    run_cfg: MmRunConfig,
+) -> Result<MmReport> {
+    let mut reports = Vec::with_capacity(markets.len());
+    for m in markets {
+        reports.push(checkpoint.get_or_run(symbol_key(&m.symbol, cfg), || {
+            run_symbol(m, htf_ms, cfg, run_cfg)
+        })?);
+    }
+    Ok(MmReport::mean(&reports))
+}
+
+/// Каждый конфиг сетки на обучении и проверке каждого окна (среднее по символам)
+fn walk_forward(
+    args: &Args,
+    wf: WalkForward,
+    range: (i64, i64),
+    markets: &[Market],
+    htf_ms: i64,
+    grid: &[MmParams],
+    run_cfg: MmRunConfig,
+) -> Result<()> {
+    let windows = wf.windows(range)?;
+    for w in &windows {
+        for m in markets {
+            for (part, period) in [("train", w.train), ("test", w.test)] {
+                let htf = slice_ms(&m.htf, period).len();
+                let ltf = slice_ms(&m.ltf, period).len();
+                if htf < 20 || ltf < 20 {
+                    anyhow::bail!(
+                        "not enough {} candles in walk-forward window {} for {}: htf={} ltf={}",
+                        part,
+                        w.index,
+                        m.symbol,
+                        htf,
+                        ltf
+                    );
                 }
             }
-            active_mode = decision.mode;
-        } else {
-            active_mode = MmMode::Disabled;
         }
     }
+    let run = |period: (i64, i64), cfg: &MmParams| {
+        let perf: Vec<Performance> = markets
+            .iter()
+            .map(|m| {
+                let (htf, ltf) = (slice_ms(&m.htf, period), slice_ms(&m.ltf, period));
+                run_mm_mtf_with_funding(htf, ltf, &m.funding, htf_ms, cfg, run_cfg)
+                    .report
+                    .perf
+            })
+            .collect();
+        Performance::mean(&perf)
+    };
 
-    if force_close_at_end && base > 0.0 {
-        let final_mark = ltf.last().map(|c| c.close).unwrap_or(Price(0.0));
-        let exit_qty = base;
-        let proceeds = force_close_exec.sell_proceeds(Qty(exit_qty), final_mark);
-        let avg_cost = if exit_qty > 0.0 {
-            cost_basis_quote / exit_qty
-        } else {
-            0.0
-        };
-        let removed_cost = avg_cost * exit_qty;
-        let realized = proceeds - removed_cost;
-        quote += proceeds;
-        base = 0.0;
-        sell_fills += 1;
-        if realized > 0.0 {
-            winning_sells += 1;
-            gross_profit += realized;
-        } else if realized < 0.0 {
-            losing_sells += 1;
-            gross_loss += -realized;
+    let mut progress = Progress::new(
+        "walk-forward",
+        "configs",
+        windows.len() * grid.len(),
+        args.quiet,
+    );
+    let pool = pool(args.jobs)?;
+    let mut train = Vec::with_capacity(windows.len());
+    let mut test = Vec::with_capacity(windows.len());
+    for (idx, w) in windows.iter().enumerate() {
+        let done = idx * grid.len();
+        let perfs = par_map(
+            &pool,
+            grid,
+            args.progress_every,
+            |cfg| (run(w.train, cfg), run(w.test, cfg)),
+            |n| progress.set(done + n),
+        );
+        for (cfg, (perf, _)) in grid.iter().zip(&perfs) {
+            progress.offer_best(perf, args.rank_by, || best_label(cfg));
         }
+        let (train_w, test_w): (Vec<_>, Vec<_>) = perfs.into_iter().unzip();
+        train.push(train_w);
+        test.push(test_w);
     }
+    progress.finish();
 
-    let final_mark = ltf.last().map(|c| c.close).unwrap_or(Price(0.0));
-    let final_equity = quote + base * final_mark.0;
-    let initial_equity = initial_quote + initial_base * final_mark.0;
-    let pnl = final_equity - initial_equity;
-    let roi_pct = if initial_equity > 0.0 {
-        100.0 * pnl / initial_equity
-    } else {
-        0.0
-    };
-    let win_rate_pct = if sell_fills > 0 {
-        100.0 * (winning_sells as f64) / (sell_fills as f64)
-    } else {
-        0.0
-    };
-    let avg_win = if winning_sells > 0 {
-        gross_profit / (winning_sells as f64)
-    } else {
-        0.0
-    };
-    let avg_loss = if losing_sells > 0 {
-        gross_loss / (losing_sells as f64)
-    } else {
-        0.0
-    };
-    let profit_factor = if gross_loss > 0.0 {
-        gross_profit / gross_loss
-    } else if gross_profit > 0.0 {
-        f64::INFINITY
-    } else {
-        0.0
-    };
-
-    MmMtfReport {
-        buy_fills,
-        sell_fills,
-        bootstrap_trades,
-        win_rate_pct,
-        avg_win,
-        avg_loss,
-        profit_factor,
-        max_drawdown_pct: max_drawdown * 100.0,
-        pnl,
-        roi_pct,
+    let labels: Vec<String> = grid.iter().map(best_label).collect();
+    let result = WfResult::new(&windows, &labels, &train, &test, args.rank_by);
+    write_table(&args.wf_windows_out, &result.window_rows)
+        .context("write walk-forward windows failed")?;
+    write_table(&args.wf_configs_out, &result.config_rows)
+        .context("write walk-forward configs failed")?;
+    println!(
+        "Walk-forward done: windows={} configs={} windows_csv={} configs_csv={}",
+        windows.len(),
+        grid.len(),
+        args.wf_windows_out,
+        args.wf_configs_out
+    );
+    for r in &result.window_rows {
+        println!(
+            "wf_window: {} test_from_ts={} train_roi={:.2}% oos_roi={:.2}% oos_dd={:.2}% {}",
+            r.window,
+            r.test_start_ms,
+            r.train_roi_pct,
+            r.test_roi_pct,
+            r.test_max_drawdown_pct,
+            r.config
+        );
+    }
+    let oos = &result.chained;
+    println!(
+        "wf_oos: roi={:.2}% mean_roi={:.2}% worst_dd={:.2}% positive={}/{}",
+        oos.compound_roi_pct,
+        oos.mean_roi_pct,
+        oos.worst_drawdown_pct,
+        oos.positive_windows,
+        oos.windows
+    );
+    if let Some(best) = result.config_rows.first() {
+        println!(
+            "wf_best_config: oos_roi={:.2}% worst_dd={:.2}% selected={}/{} {}",
+            best.compound_oos_roi_pct,
+            best.worst_oos_drawdown_pct,
+            best.selected,
+            best.windows,
+            best.config
+        );
     }
+
+    let take_n = args.top_n.min(result.config_rows.len());
+    let metrics = WalkForwardMetrics {
+        configs: grid.len(),
+        oos,
+        windows: &windows,
+        best_configs: &result.config_rows[..take_n],
+    };
+    JsonReport::new("backtest_mm_mtf_sweep", args, metrics)
+        .artifact("wf_windows_csv", &args.wf_windows_out)
+        .artifact("wf_configs_csv", &args.wf_configs_out)
+        .finish(args.report_out.as_deref())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    if args.initial_quote < 0.0 || args.initial_base < 0.0 {
-        anyhow::bail!("initial balances must be non-negative");
-    }
+/// Декартово произведение списков параметров сигнала (невалидные пропускаются)
+fn signal_grid(args: &Args) -> Result<Vec<SignalParams>> {
+    let pivot_k_list: Vec<usize> = parse_num_list(&args.pivot_k_list, "pivot_k_list")?;
+    let min_atr_frac_list: Vec<f64> = parse_num_list(&args.min_atr_frac_list, "min_atr_frac_list")?;
+    let confirm_list: Vec<usize> =
+        parse_num_list(&args.bos_confirm_candles_list, "bos_confirm_candles_list")?;
+    let bos_eps_list: Vec<f64> =
+        parse_num_list(&args.bos_epsilon_frac_list, "bos_epsilon_frac_list")?;
+    let pb_eps_list: Vec<f64> = parse_num_list(
+        &args.pullback_epsilon_frac_list,
+        "pullback_epsilon_frac_list",
+    )?;
+    let retrace_list: Vec<f64> = parse_num_list(
+        &args.pullback_retrace_frac_list,
+        "pullback_retrace_frac_list",
+    )?;
 
-    let htf_ms = parse_interval_ms(&args.htf_interval)?;
-    let start_ms = date_to_ms(&args.start)?;
-    let end_ms = date_to_ms(&args.end)? + 24 * 60 * 60 * 1000 - 1;
+    let mut out = Vec::new();
+    for &pivot_k in &pivot_k_list {
+        for &min_atr_frac in &min_atr_frac_list {
+            for &confirm_candles in &confirm_list {
+                for &bos_eps in &bos_eps_list {
+                    for &pb_eps in &pb_eps_list {
+                        for &retrace_frac in &retrace_list {
+                            let signal = SignalParams {
+                                structure: StructureParams {
+                                    pivot_k,
+                                    min_atr_frac,
+                                },
+                                bos: BosParams {
+                                    confirm_candles,
+                                    epsilon_frac: bos_eps,
+                                },
+                                pullback: PullbackParams {
+                                    epsilon_frac: pb_eps,
+                                    retrace_frac,
+                                },
+                            };
+                            if signal.validate().is_ok() {
+                                out.push(signal);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if out.is_empty() {
+        anyhow::bail!("no valid signal parameter combinations");
+    }
+    Ok(out)
+}
 
+/// Декартово произведение всех списков (невалидные полосы пропускаются)
+fn grid_configs(args: &Args) -> Result<Vec<MmParams>> {
     let levels_list: Vec<usize> = parse_num_list(&args.levels_list, "levels_list")?;
     let step_bps_list: Vec<f64> = parse_num_list(&args.step_bps_list, "step_bps_list")?;
     let base_quote_per_order_list: Vec<f64> =
@@ -588,37 +679,9 @@ async fn main() -> Result<()> {
         parse_num_list(&args.defensive_step_mult_list, "defensive_step_mult_list")?;
     let defensive_size_mult_list: Vec<f64> =
         parse_num_list(&args.defensive_size_mult_list, "defensive_size_mult_list")?;
+    let signals = signal_grid(args)?;
 
-    let api = BybitRest::new();
-    let htf = if !args.refresh && std::path::Path::new(&args.htf_cache).exists() {
-        read_cache(&args.htf_cache).context("read htf cache failed")?
-    } else {
-        let data = download_range(&api, &args.symbol, &args.htf_interval, start_ms, end_ms)
-            .await
-            .context("download htf failed")?;
-        write_cache(&args.htf_cache, &data).context("write htf cache failed")?;
-        data
-    };
-    let ltf = if !args.refresh && std::path::Path::new(&args.ltf_cache).exists() {
-        read_cache(&args.ltf_cache).context("read ltf cache failed")?
-    } else {
-        let data = download_range(&api, &args.symbol, &args.ltf_interval, start_ms, end_ms)
-            .await
-            .context("download ltf failed")?;
-        write_cache(&args.ltf_cache, &data).context("write ltf cache failed")?;
-        data
-    };
-    if htf.len() < 20 || ltf.len() < 20 {
-        anyhow::bail!("not enough candles: htf={} ltf={}", htf.len(), ltf.len());
-    }
-
-    let force_close_exec = ExecutionModel {
-        fee_bps: args.force_close_fee_bps,
-        spread_bps: args.force_close_spread_bps,
-        slippage_bps: args.force_close_slippage_bps,
-    };
-
-    let mut all: Vec<(MmMtfConfig, MmMtfReport)> = Vec::new();
+    let mut out = Vec::new();
     for &levels in &levels_list {
         for &step_bps in &step_bps_list {
             for &base_quote_per_order in &base_quote_per_order_list {
@@ -640,33 +703,24 @@ async fn main() -> Result<()> {
                                     for &maker_fee_bps in &maker_fee_bps_list {
                                         for &defensive_step_mult in &defensive_step_mult_list {
                                             for &defensive_size_mult in &defensive_size_mult_list {
-                                                let cfg = MmMtfConfig {
-                                                    levels,
-                                                    step_bps,
-                                                    base_quote_per_order,
-                                                    max_size_mult,
-                                                    soft_min,
-                                                    soft_max,
-                                                    hard_min,
-                                                    hard_max,
-                                                    maker_fee_bps,
-                                                    defensive_step_mult,
-                                                    defensive_size_mult,
-                                                };
-                                                let rep = run_mm_mtf(
-                                                    &htf,
-                                                    &ltf,
-                                                    htf_ms,
-                                                    cfg,
-                                                    args.min_base_qty,
-                                                    args.initial_quote,
-                                                    args.initial_base,
-                                                    force_close_exec,
-                                                    args.force_close_at_end,
-                                                    args.bootstrap_rebalance,
-                                                    args.bootstrap_target_ratio,
-                                                );
-                                                all.push((cfg, rep));
+                                                for &signal in &signals {
+                                                    let cfg = MmParams {
+                                                        levels,
+                                                        step_bps,
+                                                        base_quote_per_order,
+                                                        max_size_mult,
+                                                        min_base_qty: args.min_base_qty,
+                                                        soft_min,
+                                                        soft_max,
+                                                        hard_min,
+                                                        hard_max,
+                                                        maker_fee_bps,
+                                                        defensive_step_mult,
+                                                        defensive_size_mult,
+                                                        signal,
+                                                    };
+                                                    out.push(cfg);
+                                                }
                                             }
                                         }
                                     }
@@ -678,62 +732,418 @@ async fn main() -> Result<()> {
             }
         }
     }
+    Ok(out)
+}
 
-    all.sort_by(|a, b| {
-        b.1.roi_pct
-            .partial_cmp(&a.1.roi_pct)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then(
-                a.1.max_drawdown_pct
-                    .partial_cmp(&b.1.max_drawdown_pct)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-            )
-            .then(
-                b.1.profit_factor
-                    .partial_cmp(&a.1.profit_factor)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-            )
-    });
+/// Имена измерений [`search_dims`] для отчёта стабильности
+const DIM_NAMES: [&str; 17] = [
+    "levels",
+    "step_bps",
+    "base_quote_per_order",
+    "max_size_mult",
+    "soft_min",
+    "soft_max",
+    "hard_min",
+    "hard_max",
+    "maker_fee_bps",
+    "defensive_step_mult",
+    "defensive_size_mult",
+    "pivot_k",
+    "min_atr_frac",
+    "bos_confirm_candles",
+    "bos_epsilon_frac",
+    "pullback_epsilon_frac",
+    "pullback_retrace_frac",
+];
 
-    let take_n = args.top_n.min(all.len());
-    let mut rows = Vec::with_capacity(take_n);
-    for (idx, (cfg, rep)) in all.iter().take(take_n).enumerate() {
-        rows.push(SummaryRow {
-            rank: idx + 1,
-            levels: cfg.levels,
-            step_bps: cfg.step_bps,
-            base_quote_per_order: cfg.base_quote_per_order,
-            max_size_mult: cfg.max_size_mult,
-            soft_min: cfg.soft_min,
-            soft_max: cfg.soft_max,
-            hard_min: cfg.hard_min,
-            hard_max: cfg.hard_max,
-            maker_fee_bps: cfg.maker_fee_bps,
-            defensive_step_mult: cfg.defensive_step_mult,
-            defensive_size_mult: cfg.defensive_size_mult,
-            buy_fills: rep.buy_fills,
-            sell_fills: rep.sell_fills,
-            bootstrap_trades: rep.bootstrap_trades,
-            win_rate_pct: rep.win_rate_pct,
-            avg_win: rep.avg_win,
-            avg_loss: rep.avg_loss,
-            profit_factor: rep.profit_factor,
-            max_drawdown_pct: rep.max_drawdown_pct,
-            pnl: rep.pnl,
-            roi_pct: rep.roi_pct,
+/// Порядок измерений — как в [`params_at`]
+fn search_dims(args: &Args) -> Result<Vec<Dim>> {
+    Ok(vec![
+        Dim::parse(&args.levels_list, "levels_list", true)?,
+        Dim::parse(&args.step_bps_list, "step_bps_list", false)?,
+        Dim::parse(
+            &args.base_quote_per_order_list,
+            "base_quote_per_order_list",
+            false,
+        )?,
+        Dim::parse(&args.max_size_mult_list, "max_size_mult_list", false)?,
+        Dim::parse(&args.soft_min_list, "soft_min_list", false)?,
+        Dim::parse(&args.soft_max_list, "soft_max_list", false)?,
+        Dim::parse(&args.hard_min_list, "hard_min_list", false)?,
+        Dim::parse(&args.hard_max_list, "hard_max_list", false)?,
+        Dim::parse(&args.maker_fee_bps_list, "maker_fee_bps_list", false)?,
+        Dim::parse(
+            &args.defensive_step_mult_list,
+            "defensive_step_mult_list",
+            false,
+        )?,
+        Dim::parse(
+            &args.defensive_size_mult_list,
+            "defensive_size_mult_list",
+            false,
+        )?,
+        Dim::parse(&args.pivot_k_list, "pivot_k_list", true)?,
+        Dim::parse(&args.min_atr_frac_list, "min_atr_frac_list", false)?,
+        Dim::parse(
+            &args.bos_confirm_candles_list,
+            "bos_confirm_candles_list",
+            true,
+        )?,
+        Dim::parse(&args.bos_epsilon_frac_list, "bos_epsilon_frac_list", false)?,
+        Dim::parse(
+            &args.pullback_epsilon_frac_list,
+            "pullback_epsilon_frac_list",
+            false,
+        )?,
+        Dim::parse(
+            &args.pullback_retrace_frac_list,
+            "pullback_retrace_frac_list",
+            false,
+        )?,
+    ])
+}
+
+fn params_at(args: &Args, p: &[f64]) -> MmParams {
+    MmParams {
+        levels: p[0] as usize,
+        step_bps: p[1],
+        base_quote_per_order: p[2],
+        max_size_mult: p[3],
+        min_base_qty: args.min_base_qty,
+        soft_min: p[4],
+        soft_max: p[5],
+        hard_min: p[6],
+        hard_max: p[7],
+        maker_fee_bps: p[8],
+        defensive_step_mult: p[9],
+        defensive_size_mult: p[10],
+        signal: SignalParams {
+            structure: StructureParams {
+                pivot_k: p[11] as usize,
+                min_atr_frac: p[12],
+            },
+            bos: BosParams {
+                confirm_candles: p[13] as usize,
+                epsilon_frac: p[14],
+            },
+            pullback: PullbackParams {
+                epsilon_frac: p[15],
+                retrace_frac: p[16],
+            },
+        },
+    }
+}
+
+/// Обратное к [`params_at`]
+fn values_of(cfg: &MmParams) -> Vec<f64> {
+    vec![
+        cfg.levels as f64,
+        cfg.step_bps,
+        cfg.base_quote_per_order,
+        cfg.max_size_mult,
+        cfg.soft_min,
+        cfg.soft_max,
+        cfg.hard_min,
+        cfg.hard_max,
+        cfg.maker_fee_bps,
+        cfg.defensive_step_mult,
+        cfg.defensive_size_mult,
+        cfg.signal.structure.pivot_k as f64,
+        cfg.signal.structure.min_atr_frac,
+        cfg.signal.bos.confirm_candles as f64,
+        cfg.signal.bos.epsilon_frac,
+        cfg.signal.pullback.epsilon_frac,
+        cfg.signal.pullback.retrace_frac,
+    ]
+}
+
+/// Те же ограничения, что у сетки: полосы по порядку, валидный сигнал
+fn is_valid(cfg: &MmParams) -> bool {
+    cfg.soft_min < cfg.soft_max && cfg.validate_bands().is_ok() && cfg.signal.validate().is_ok()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote < 0.0 || args.initial_base < 0.0 {
+        anyhow::bail!("initial balances must be non-negative");
+    }
+    if args
+        .initial_base_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
+    {
+        anyhow::bail!("initial_base_ratio must be in [0, 1]");
+    }
+
+    let htf_ms = parse_interval_ms(&args.htf_interval)?;
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+    let perp = PerpParams::for_market(args.market, args.leverage, args.maintenance_margin_rate)?;
+
+    let grid = match args.search {
+        SearchMode::Grid => grid_configs(&args)?,
+        _ => Vec::new(),
+    };
+    let dims = search_dims(&args)?;
+    let wf = WalkForward::from_days(args.wf_train_days, args.wf_test_days, args.wf_step_days)?;
+    if wf.is_some() && (args.search != SearchMode::Grid || args.oos_split > 0.0) {
+        anyhow::bail!("walk-forward needs --search grid and no --oos-split");
+    }
+
+    let symbols = parse_symbols(&args.symbol)?;
+    let multi = symbols.len() > 1;
+    let mut markets = Vec::with_capacity(symbols.len());
+    let mut oos_starts = Vec::new();
+    for symbol in symbols {
+        let ltf_cache = explicit_cache(args.ltf_cache.as_deref(), multi, &symbol);
+        let all_ltf = load_candles(
+            args.exchange,
+            &symbol,
+            &args.ltf_interval,
+            range,
+            ltf_cache.as_deref(),
+            args.refresh,
+            gate,
+        )
+        .await?;
+        // качается только LTF серия, HTF собирается из неё
+        let all_htf = resample_ms(&all_ltf, htf_ms);
+        ensure_ltf_coverage(&symbol, &all_htf, &all_ltf, (htf_ms, ltf_ms), range, gate)?;
+        // граница по LTF, HTF режется там же
+        let oos_start = oos_start_ms(&all_ltf, args.oos_split)?;
+        let split = oos_start.unwrap_or(i64::MAX);
+        let (htf, oos_htf) = split_at_ms(&all_htf, split);
+        let (ltf, oos_ltf) = split_at_ms(&all_ltf, split);
+        if htf.len() < 20 || ltf.len() < 20 {
+            anyhow::bail!(
+                "not enough candles for {}: htf={} ltf={}",
+                symbol,
+                htf.len(),
+                ltf.len()
+            );
+        }
+        if oos_start.is_some() && (oos_htf.len() < 20 || oos_ltf.len() < 20) {
+            anyhow::bail!(
+                "not enough out-of-sample candles for {}: htf={} ltf={}",
+                symbol,
+                oos_htf.len(),
+                oos_ltf.len()
+            );
+        }
+        oos_starts.extend(oos_start);
+        let funding = match perp {
+            Some(_) => {
+                let cache = cache_path_for(
+                    args.funding_cache.as_deref(),
+                    multi,
+                    &symbol,
+                    "funding",
+                    range,
+                );
+                load_funding(&symbol, range, Some(&cache), args.refresh).await?
+            }
+            None => Vec::new(),
+        };
+        markets.push(Market {
+            symbol,
+            htf: htf.to_vec(),
+            ltf: ltf.to_vec(),
+            oos_htf: oos_htf.to_vec(),
+            oos_ltf: oos_ltf.to_vec(),
+            funding,
         });
     }
-    write_summary(&args.summary_out, &rows).context("write summary failed")?;
+    let with_oos = !oos_starts.is_empty();
+
+    let run_cfg = MmRunConfig {
+        initial_quote: args.initial_quote,
+        initial_base: args.initial_base,
+        initial_base_ratio: args.initial_base_ratio,
+        perp,
+        taker: ExecutionModel {
+            fee_bps: args.force_close_fee_bps,
+            spread_bps: args.force_close_spread_bps,
+            slippage_bps: args.force_close_slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        force_close_twap,
+        bootstrap_rebalance: args.bootstrap_rebalance,
+        bootstrap_target_ratio: args.bootstrap_target_ratio,
+        intrabar: args.intrabar_path,
+        fill,
+        record: false,
+    };
+    if let Some(wf) = wf {
+        return walk_forward(&args, wf, range, &markets, htf_ms, &grid, run_cfg);
+    }
+
+    let mut run_key = format!(
+        "{} htf={} ltf={} {}..{} {:?}",
+        args.symbol, args.htf_interval, args.ltf_interval, args.start, args.end, run_cfg
+    );
+    for ts in &oos_starts {
+        run_key.push_str(&format!(" is<{}", ts));
+    }
+    let mut checkpoint = Checkpoint::open(
+        &args.checkpoint,
+        &run_key,
+        args.resume,
+        args.checkpoint_every,
+    )?;
+    if checkpoint.resumed() > 0 {
+        println!(
+            "checkpoint: resumed={} path={}",
+            checkpoint.resumed(),
+            args.checkpoint
+        );
+    }
+
+    let mut collected = Collected::new(&args)?;
+    if args.search == SearchMode::Grid {
+        let pool = pool(args.jobs)?;
+        let mut progress = Progress::new("sweep", "configs", grid.len(), args.quiet);
+        // пачками: прогоны пачки параллельно, посчитанное сразу уходит в чекпоинт
+        let batch = args.checkpoint_every.max(pool.current_num_threads());
+        for batch in grid.chunks(batch) {
+            let done = collected.tested;
+            let fresh = par_map(
+                &pool,
+                batch,
+                args.progress_every,
+                |cfg| {
+                    markets
+                        .iter()
+                        .filter_map(|m| {
+                            let key = symbol_key(&m.symbol, cfg);
+                            let todo = checkpoint.get(&key).is_none();
+                            todo.then(|| (key, run_symbol(m, htf_ms, cfg, run_cfg)))
+                        })
+                        .collect::<Vec<_>>()
+                },
+                |n| progress.set(done + n),
+            );
+            for (key, rep) in fresh.into_iter().flatten() {
+                checkpoint.insert(key, rep)?;
+            }
+            for cfg in batch {
+                let rep = evaluate(&mut checkpoint, &markets, htf_ms, cfg, run_cfg)?;
+                progress.offer_best(&rep.perf, args.rank_by, || best_label(cfg));
+                collected.push(&mut checkpoint, &markets, *cfg, rep)?;
+            }
+        }
+    } else {
+        let mut progress = Progress::new("sweep", "configs", args.samples, args.quiet);
+        let search = Search::new(args.search, dims.clone(), args.seed);
+        let sampled = search.run(
+            args.samples,
+            |p| {
+                let cfg = params_at(&args, p);
+                is_valid(&cfg).then_some(cfg)
+            },
+            |cfg| {
+                let rep = evaluate(&mut checkpoint, &markets, htf_ms, cfg, run_cfg)?;
+                let score = rep.perf.metric(args.rank_by);
+                progress.offer_best(&rep.perf, args.rank_by, || best_label(cfg));
+                progress.tick();
+                Ok((rep, score))
+            },
+        )?;
+        progress.finish();
+        for (cfg, rep) in sampled {
+            collected.push(&mut checkpoint, &markets, cfg, rep)?;
+        }
+    }
+    let (tested, all) = collected.finish()?;
+
+    let mut stability_rows = Vec::new();
+    let mut stability = Vec::new();
+    for (idx, (cfg, rep)) in all.iter().take(args.stability_top).enumerate() {
+        let (rows, summary) = neighborhood(
+            idx + 1,
+            &DIM_NAMES,
+            &dims,
+            &values_of(cfg),
+            &rep.perf,
+            args.fragile_roi_drop,
+            |p| {
+                let cfg = params_at(&args, p);
+                if !is_valid(&cfg) {
+                    return Ok(None);
+                }
+                let rep = evaluate(&mut checkpoint, &markets, htf_ms, &cfg, run_cfg)?;
+                Ok(Some(rep.perf))
+            },
+        )?;
+        stability_rows.extend(rows);
+        stability.push(summary);
+    }
+    checkpoint.flush()?;
+
+    let take_n = args.top_n.min(all.len());
+    let oos: Vec<Performance> = if with_oos {
+        all.iter()
+            .take(take_n)
+            .map(|(cfg, _)| {
+                let perf: Vec<Performance> = markets
+                    .iter()
+                    .map(|m| {
+                        run_mm_mtf_with_funding(
+                            &m.oos_htf, &m.oos_ltf, &m.funding, htf_ms, cfg, run_cfg,
+                        )
+                        .report
+                        .perf
+                    })
+                    .collect();
+                Performance::mean(&perf)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut rows = Vec::with_capacity(all.len());
+    let mut symbol_rows = Vec::new();
+    for (idx, (cfg, rep)) in all.iter().enumerate() {
+        let min_symbol_roi_pct = min_symbol_roi(&checkpoint, &markets, cfg);
+        if idx < take_n {
+            for m in &markets {
+                if let Some(r) = checkpoint.get(&symbol_key(&m.symbol, cfg)) {
+                    symbol_rows.push(SymbolRow::new(idx + 1, &m.symbol, &r.perf));
+                }
+            }
+        }
+        rows.push(summary_row(
+            idx + 1,
+            cfg,
+            rep,
+            min_symbol_roi_pct,
+            oos.get(idx),
+        ));
+    }
+    if !args.stream_results {
+        write_table(&args.results_out, &rows).context("write results failed")?;
+    }
+    rows.truncate(take_n);
+    write_table(&args.summary_out, &rows).context("write summary failed")?;
+    write_table(&args.per_symbol_out, &symbol_rows).context("write per-symbol failed")?;
 
     println!(
-        "MM MTF sweep done: tested={} top_saved={} summary={}",
-        all.len(),
+        "MM MTF sweep done: tested={} top_saved={} symbols={} summary={} results={}",
+        tested,
         rows.len(),
-        args.summary_out
+        markets.len(),
+        args.summary_out,
+        args.results_out
     );
     if let Some(best) = rows.first() {
         println!(
-            "Best: levels={} step_bps={:.2} qpo={:.2} bands=({:.2}-{:.2}|{:.2}-{:.2}) fee={:.2} roi={:.2}% pf={:.4} dd={:.2}%",
+            "Best: levels={} step_bps={:.2} qpo={:.2} bands=({:.2}-{:.2}|{:.2}-{:.2}) fee={:.2} bos={}x{:.2} pullback={:.2} roi={:.2}% sharpe={:.3} pf={:.4} dd={:.2}%",
             best.levels,
             best.step_bps,
             best.base_quote_per_order,
@@ -742,11 +1152,106 @@ async fn main() -> Result<()> {
             best.soft_max,
             best.hard_max,
             best.maker_fee_bps,
+            best.bos_confirm_candles,
+            best.bos_epsilon_frac,
+            best.pullback_retrace_frac,
             best.roi_pct,
+            best.sharpe,
             best.profit_factor,
             best.max_drawdown_pct
         );
     }
+    let best_symbols: Vec<SymbolRow> = symbol_rows
+        .iter()
+        .filter(|r| r.rank == 1)
+        .cloned()
+        .collect();
+    if multi {
+        for r in &best_symbols {
+            println!(
+                "symbol: {} roi={:.2}% sharpe={:.3} dd={:.2}%",
+                r.symbol, r.roi_pct, r.sharpe, r.max_drawdown_pct
+            );
+        }
+    }
+    if let (Some(ts), Some(p)) = (oos_starts.first(), oos.first()) {
+        println!(
+            "oos: from_ts={} candles={} roi={:.2}% sharpe={:.3} pf={} dd={:.2}%",
+            ts,
+            markets[0].oos_ltf.len(),
+            p.roi_pct,
+            p.sharpe,
+            p.profit_factor_label(),
+            p.max_drawdown_pct
+        );
+    }
+    for st in &stability {
+        println!(
+            "stability: rank={} neighbors={} base_roi={:.2}% mean_roi={:.2}% worst_roi={:.2}% worst_param={} fragile={}",
+            st.rank,
+            st.neighbors,
+            st.base_roi_pct,
+            st.mean_roi_pct,
+            st.worst_roi_pct,
+            st.worst_param.unwrap_or("-"),
+            st.fragile
+        );
+    }
+
+    let best = all.first().map(|(_, rep)| rep);
+    let metrics = SweepMetrics {
+        tested,
+        top_saved: rows.len(),
+        best,
+        best_config: rows.first(),
+        per_symbol: &best_symbols,
+        stability: &stability,
+        oos: oos.first(),
+    };
+    let mut report = JsonReport::new("backtest_mm_mtf_sweep", &args, metrics)
+        .with_seed(args.seed)
+        .artifact("summary_csv", &args.summary_out)
+        .artifact("results_csv", &args.results_out)
+        .artifact("per_symbol_csv", &args.per_symbol_out);
+    if !stability.is_empty() {
+        write_table(&args.stability_out, &stability_rows).context("write stability failed")?;
+        report = report.artifact("stability_csv", &args.stability_out);
+    }
+
+    // при нескольких символах — `rankN_<SYMBOL>_*`
+    let dir = args.top_artifacts_dir.trim_end_matches('/');
+    for (idx, (cfg, _)) in all.iter().take(args.top_artifacts).enumerate() {
+        let rank = idx + 1;
+        for m in &markets {
+            let tag = if multi {
+                format!("rank{rank}_{}", m.symbol)
+            } else {
+                format!("rank{rank}")
+            };
+            let run = run_mm_mtf_with_funding(
+                &m.htf,
+                &m.ltf,
+                &m.funding,
+                htf_ms,
+                cfg,
+                MmRunConfig {
+                    record: true,
+                    ..run_cfg
+                },
+            );
+            let equity_out = format!("{dir}/{tag}_equity.csv");
+            let fills_out = format!("{dir}/{tag}_fills.csv");
+            write_csv(&equity_out, &run.equity_rows).context("write top equity csv failed")?;
+            write_csv(&fills_out, &run.fill_rows).context("write top fills csv failed")?;
+            report = report
+                .artifact(&format!("{tag}_equity_csv"), &equity_out)
+                .artifact(&format!("{tag}_fills_csv"), &fills_out);
+        }
+    }
+    if let Some(best) = best {
+        report = report.with_costs(best.costs);
+    }
+    report.finish(args.report_out.as_deref())?;
 
     Ok(())
 }
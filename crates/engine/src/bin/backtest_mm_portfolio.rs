@@ -0,0 +1,392 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::breakdown::{breakdown_summary, monthly_breakdown};
+use backtest::config::parse_args;
+use backtest::data::{
+    Venue, date_range_ms, ensure_ltf_coverage, explicit_cache, load_candles, parse_interval_ms,
+    parse_symbols,
+};
+use backtest::html::HtmlData;
+use backtest::mm::{
+    FillMode, FillRule, ForceCloseMode, IntrabarPath, MmParams, MmRunConfig, SignalParams,
+};
+use backtest::portfolio::{Allocation, PortfolioMarket, run_portfolio};
+use backtest::quality::{QualityGate, QualityMode, check_intervals};
+use backtest::report::{JsonReport, underwater_rows, write_csv};
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use execution::sim::ExecutionModel;
+use structure::bos::BosParams;
+use structure::candle::resample_ms;
+use structure::pullback::PullbackParams;
+use structure::structure::StructureParams;
+
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    /// Символы портфеля через запятую
+    #[arg(long, alias = "symbol")]
+    symbols: String,
+    #[arg(long, default_value = "5")]
+    htf_interval: String,
+    #[arg(long, default_value = "1")]
+    ltf_interval: String,
+    #[arg(long)]
+    start: String,
+    #[arg(long)]
+    end: String,
+    /// `equal`, `inverse-vol` или веса через запятую в порядке `--symbols`
+    #[arg(long, default_value = "equal")]
+    allocation: String,
+    /// Окно волатильности для `inverse-vol`, HTF свечей
+    #[arg(long, default_value_t = 96)]
+    vol_window: usize,
+    #[arg(long, default_value_t = 1)]
+    pivot_k: usize,
+    #[arg(long, default_value_t = 0.1)]
+    min_atr_frac: f64,
+    #[arg(long, default_value_t = 2)]
+    bos_confirm_candles: usize,
+    #[arg(long, default_value_t = 0.1)]
+    bos_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.1)]
+    pullback_epsilon_frac: f64,
+    #[arg(long, default_value_t = 0.4)]
+    pullback_retrace_frac: f64,
+
+    /// Кэши LTF свечей по символам: `<cache>_<SYMBOL>.csv`; HTF собирается из них.
+    /// По умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    ltf_cache: Option<String>,
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+
+    /// Общий quote баланс портфеля
+    #[arg(long, default_value_t = 3000.0)]
+    initial_quote: f64,
+
+    #[arg(long, default_value_t = 5)]
+    levels: usize,
+    #[arg(long, default_value_t = 12.0)]
+    step_bps: f64,
+    #[arg(long, default_value_t = 25.0)]
+    base_quote_per_order: f64,
+    #[arg(long, default_value_t = 2.0)]
+    max_size_mult: f64,
+    #[arg(long, default_value_t = 0.0001)]
+    min_base_qty: f64,
+
+    #[arg(long, default_value_t = 0.40)]
+    soft_min: f64,
+    #[arg(long, default_value_t = 0.60)]
+    soft_max: f64,
+    #[arg(long, default_value_t = 0.35)]
+    hard_min: f64,
+    #[arg(long, default_value_t = 0.65)]
+    hard_max: f64,
+
+    #[arg(long, default_value_t = 10.0)]
+    maker_fee_bps: f64,
+    #[arg(long, default_value_t = 10.0)]
+    force_close_fee_bps: f64,
+    #[arg(long, default_value_t = 8.0)]
+    force_close_spread_bps: f64,
+    #[arg(long, default_value_t = 2.0)]
+    force_close_slippage_bps: f64,
+    #[arg(long, default_value_t = true)]
+    force_close_at_end: bool,
+    /// Force close: last — один fill по последнему close, vwap — TWAP по VWAP последних LTF свечей
+    #[arg(long, value_enum, default_value_t = ForceCloseMode::Last)]
+    force_close_mode: ForceCloseMode,
+    /// vwap: на скольких последних LTF свечах распределить выход
+    #[arg(long, default_value_t = 12)]
+    force_close_candles: usize,
+    #[arg(long, default_value_t = 1.5)]
+    defensive_step_mult: f64,
+    #[arg(long, default_value_t = 0.5)]
+    defensive_size_mult: f64,
+    #[arg(long, default_value_t = true)]
+    bootstrap_rebalance: bool,
+    #[arg(long, default_value_t = 0.50)]
+    bootstrap_target_ratio: f64,
+    /// Порядок уровней сетки внутри свечи: sorted, ohlc, olhc, worst-case, best-case
+    #[arg(long, value_enum, default_value_t = IntrabarPath::Sorted)]
+    intrabar_path: IntrabarPath,
+    /// Maker fill: touch — касание уровня, strict — цена прошла сквозь уровень
+    #[arg(long, value_enum, default_value_t = FillMode::Touch)]
+    fill_mode: FillMode,
+    /// Strict: на сколько тиков цена должна уйти за уровень
+    #[arg(long, default_value_t = 0)]
+    fill_penetration_ticks: u32,
+    /// Шаг цены инструмента (для `--fill-penetration-ticks`)
+    #[arg(long, default_value_t = 0.01)]
+    tick_size: f64,
+    /// Лимит fill'а уровня сетки за свечу, доля объёма свечи (остаток — на следующие свечи)
+    #[arg(long)]
+    fill_volume_frac: Option<f64>,
+
+    #[arg(long, default_value = "data/backtest_mm_portfolio_equity.csv")]
+    equity_out: String,
+    /// Underwater-кривая: глубина просадки и время под пиком equity
+    #[arg(long, default_value = "data/backtest_mm_portfolio_underwater.csv")]
+    underwater_out: String,
+    /// PnL, ROI и число сделок по месяцам (UTC)
+    #[arg(long, default_value = "data/backtest_mm_portfolio_monthly.csv")]
+    monthly_out: String,
+    #[arg(long, default_value = "data/backtest_mm_portfolio_fills.csv")]
+    fills_out: String,
+    #[arg(long, default_value = "data/backtest_mm_portfolio_symbols.csv")]
+    symbols_out: String,
+    #[arg(long, default_value = "data/backtest_mm_portfolio_correlations.csv")]
+    correlations_out: String,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг, метрики, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote <= 0.0 {
+        anyhow::bail!("initial_quote must be positive");
+    }
+    let symbols = parse_symbols(&args.symbols)?;
+    let allocation = Allocation::parse(&args.allocation, symbols.len())?;
+    if args.vol_window < 2 {
+        anyhow::bail!("vol_window must be >= 2");
+    }
+    let params = MmParams {
+        levels: args.levels,
+        step_bps: args.step_bps,
+        base_quote_per_order: args.base_quote_per_order,
+        max_size_mult: args.max_size_mult,
+        min_base_qty: args.min_base_qty,
+        soft_min: args.soft_min,
+        soft_max: args.soft_max,
+        hard_min: args.hard_min,
+        hard_max: args.hard_max,
+        maker_fee_bps: args.maker_fee_bps,
+        defensive_step_mult: args.defensive_step_mult,
+        defensive_size_mult: args.defensive_size_mult,
+        signal: SignalParams {
+            structure: StructureParams {
+                pivot_k: args.pivot_k,
+                min_atr_frac: args.min_atr_frac,
+            },
+            bos: BosParams {
+                confirm_candles: args.bos_confirm_candles,
+                epsilon_frac: args.bos_epsilon_frac,
+            },
+            pullback: PullbackParams {
+                epsilon_frac: args.pullback_epsilon_frac,
+                retrace_frac: args.pullback_retrace_frac,
+            },
+        },
+    };
+    params.validate_bands()?;
+    params.signal.validate()?;
+
+    let htf_ms = parse_interval_ms(&args.htf_interval)?;
+    let ltf_ms = parse_interval_ms(&args.ltf_interval)?;
+    check_intervals(htf_ms, ltf_ms)?;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let fill = FillRule::new(args.fill_mode, args.fill_penetration_ticks, args.tick_size)?
+        .with_volume_frac(args.fill_volume_frac)?;
+    let force_close_twap = args
+        .force_close_mode
+        .twap_candles(args.force_close_candles)?;
+
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    let mut candles = Vec::with_capacity(symbols.len());
+    for (i, symbol) in symbols.iter().enumerate() {
+        // у каждого символа своя серия: зерно `--seed` + номер символа
+        let ltf = match synthetic {
+            Some(p) => synthetic_candles(&p, ltf_ms, range, args.seed.wrapping_add(i as u64)),
+            None => {
+                load_candles(
+                    args.exchange,
+                    symbol,
+                    &args.ltf_interval,
+                    range,
+                    explicit_cache(args.ltf_cache.as_deref(), true, symbol).as_deref(),
+                    args.refresh,
+                    gate,
+                )
+                .await?
+            }
+        };
+        // качается только LTF серия, HTF собирается из неё
+        let htf = resample_ms(&ltf, htf_ms);
+        if htf.len() < 20 || ltf.len() < 20 {
+            anyhow::bail!(
+                "not enough candles for {}: htf={} ltf={}",
+                symbol,
+                htf.len(),
+                ltf.len()
+            );
+        }
+        ensure_ltf_coverage(symbol, &htf, &ltf, (htf_ms, ltf_ms), range, gate)?;
+        candles.push((htf, ltf));
+    }
+    let markets: Vec<PortfolioMarket> = symbols
+        .iter()
+        .zip(&candles)
+        .map(|(symbol, (htf, ltf))| PortfolioMarket {
+            symbol: symbol.clone(),
+            htf,
+            ltf,
+        })
+        .collect();
+
+    let cfg = MmRunConfig {
+        initial_quote: args.initial_quote,
+        initial_base: 0.0,
+        initial_base_ratio: None,
+        perp: None,
+        taker: ExecutionModel {
+            fee_bps: args.force_close_fee_bps,
+            spread_bps: args.force_close_spread_bps,
+            slippage_bps: args.force_close_slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        force_close_twap,
+        bootstrap_rebalance: args.bootstrap_rebalance,
+        bootstrap_target_ratio: args.bootstrap_target_ratio,
+        intrabar: args.intrabar_path,
+        fill,
+        record: true,
+    };
+    let run = run_portfolio(&markets, htf_ms, &params, &allocation, args.vol_window, cfg)?;
+    let r = run.report;
+    let p = r.perf;
+
+    write_csv(&args.equity_out, &run.equity_rows).context("write equity csv failed")?;
+    write_csv(
+        &args.underwater_out,
+        underwater_rows(run.equity_rows.iter().map(|e| (e.ts, e.drawdown_pct))),
+    )
+    .context("write underwater csv failed")?;
+    let monthly = monthly_breakdown(
+        r.final_equity - p.pnl,
+        &run.equity_rows
+            .iter()
+            .map(|e| (e.ts, e.equity))
+            .collect::<Vec<_>>(),
+        r.final_equity,
+        &run.fill_rows.iter().map(|f| f.ts).collect::<Vec<_>>(),
+    );
+    write_csv(&args.monthly_out, &monthly).context("write monthly csv failed")?;
+    write_csv(&args.fills_out, &run.fill_rows).context("write fills csv failed")?;
+    write_csv(&args.symbols_out, &run.symbol_rows).context("write symbols csv failed")?;
+    write_csv(&args.correlations_out, &run.correlations)
+        .context("write correlations csv failed")?;
+
+    println!("MM portfolio backtest finished");
+    println!(
+        "portfolio: symbols={} allocation={} tf: htf={}m ltf={}m",
+        symbols.join(","),
+        args.allocation,
+        args.htf_interval,
+        args.ltf_interval
+    );
+    for s in &run.symbol_rows {
+        println!(
+            "symbol: {} weight={:.3} pnl={:.4} pnl_share={:.2}% fills: buy={} sell={} bootstrap={} win_rate={:.2}% costs={:.4}",
+            s.symbol,
+            s.weight,
+            s.pnl,
+            s.pnl_share_pct,
+            s.buy_fills,
+            s.sell_fills,
+            s.bootstrap_trades,
+            s.win_rate_pct,
+            s.total_costs
+        );
+    }
+    for c in &run.correlations {
+        println!(
+            "pnl_correlation: {}/{}={:.3}",
+            c.symbol_a, c.symbol_b, c.correlation
+        );
+    }
+    println!(
+        "final_quote={:.4} final_equity={:.4}",
+        r.final_quote, r.final_equity
+    );
+    println!(
+        "pnl={:.4} roi={:.2}% max_drawdown={:.2}%",
+        p.pnl, p.roi_pct, p.max_drawdown_pct
+    );
+    println!(
+        "drawdown: max_drawdown_duration={:.2}h time_to_recovery={}",
+        p.max_drawdown_duration_h,
+        p.recovery_label()
+    );
+    println!("monthly: {}", breakdown_summary(&monthly));
+    println!(
+        "risk: sharpe={:.3} sortino={:.3} calmar={:.3} exposure={:.2}% avg_trade_duration={:.2}h turnover={:.2}x",
+        p.sharpe, p.sortino, p.calmar, p.exposure_pct, p.avg_trade_duration_h, p.turnover
+    );
+    println!(
+        "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor={}",
+        p.closed_trades,
+        p.win_rate_pct,
+        p.avg_win,
+        p.avg_loss,
+        p.profit_factor_label()
+    );
+    let c = r.costs;
+    println!(
+        "costs: maker_fees={:.4} taker_fees={:.4} spread_slippage={:.4} total_costs={:.4}",
+        c.maker_fees, c.taker_fees, c.spread_slippage, c.total
+    );
+    JsonReport::new("backtest_mm_portfolio", &args, &r)
+        .with_seed(args.seed)
+        .with_monthly(monthly)
+        .with_costs(c)
+        .with_html(HtmlData::new(
+            run.equity_rows
+                .iter()
+                .map(|e| (e.ts, e.equity, e.drawdown_pct)),
+            &run.fill_rows,
+        )?)
+        .artifact("equity_csv", &args.equity_out)
+        .artifact("underwater_csv", &args.underwater_out)
+        .artifact("monthly_csv", &args.monthly_out)
+        .artifact("fills_csv", &args.fills_out)
+        .artifact("symbols_csv", &args.symbols_out)
+        .artifact("correlations_csv", &args.correlations_out)
+        .finish(args.report_out.as_deref())?;
+
+    Ok(())
+}
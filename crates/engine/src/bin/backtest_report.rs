@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+use serde_json::Value;
+
+use backtest::config::parse_args;
+use backtest::html::{HtmlData, HtmlSections, render};
+use backtest::report::JsonReport;
+
+/// HTML отчёт по артефактам завершённого прогона: JSON отчёт (`--report-out` прогона),
+/// equity и сделки. Для run'ов до появления HTML отчёта и для пересборки после правок шаблона.
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    /// JSON отчёт прогона
+    #[arg(long)]
+    source_report: String,
+    /// Equity CSV; по умолчанию — артефакт `equity_csv` отчёта
+    #[arg(long)]
+    equity: Option<String>,
+    /// Сделки CSV; по умолчанию — `round_trips_csv`, `fills_csv` или `trades_csv` отчёта
+    #[arg(long)]
+    trades: Option<String>,
+    /// По умолчанию `<source_report>.html`
+    #[arg(long)]
+    html_out: Option<String>,
+    /// JSON отчёт: пути, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metrics {
+    source_kind: String,
+    equity_points: usize,
+    trades: usize,
+}
+
+fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    let text = std::fs::read_to_string(&args.source_report)
+        .with_context(|| format!("read {} failed", args.source_report))?;
+    let source: Value = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a JSON report", args.source_report))?;
+    let artifact = |kind: &str| source["artifacts"][kind].as_str().map(|s| s.to_string());
+
+    let equity = args
+        .equity
+        .clone()
+        .or_else(|| artifact("equity_csv"))
+        .context("no equity CSV: set --equity or use a report with equity_csv artifact")?;
+    let trades = args.trades.clone().or_else(|| {
+        ["round_trips_csv", "fills_csv", "trades_csv"]
+            .into_iter()
+            .find_map(artifact)
+    });
+    let data = HtmlData::from_csv(&equity, trades.as_deref())?;
+
+    let kind = source["kind"].as_str().unwrap_or("backtest").to_string();
+    let html = render(
+        &HtmlSections {
+            kind: &kind,
+            config: &source["config"],
+            metrics: &source["metrics"],
+            costs: &source["costs"],
+            monthly: &source["monthly"],
+        },
+        &data,
+    );
+    let html_out = args.html_out.clone().unwrap_or_else(|| {
+        Path::new(&args.source_report)
+            .with_extension("html")
+            .to_string_lossy()
+            .into_owned()
+    });
+    if let Some(parent) = Path::new(&html_out).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&html_out, html).with_context(|| format!("write {} failed", html_out))?;
+
+    println!("report generated");
+    println!(
+        "source: kind={} equity_points={} trades={}",
+        kind,
+        data.equity.len(),
+        data.trades.len()
+    );
+    JsonReport::new(
+        "backtest_report",
+        &args,
+        Metrics {
+            source_kind: kind.clone(),
+            equity_points: data.equity.len(),
+            trades: data.trades.len(),
+        },
+    )
+    .artifact("report_html", &html_out)
+    .finish(args.report_out.as_deref())?;
+    Ok(())
+}
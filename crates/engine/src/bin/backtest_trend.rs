@@ -1,30 +1,19 @@
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, TimeZone, Utc};
-use clap::{Parser, ValueEnum};
-
-use bybit::rest::{BybitRest, download_range};
-use core::types::{Money, Price, Qty};
-use engine::feed::CandleFeed;
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::breakdown::{breakdown_summary, monthly_breakdown};
+use backtest::config::parse_args;
+use backtest::data::{Venue, date_range_ms, load_candles, parse_interval_ms};
+use backtest::html::HtmlData;
+use backtest::montecarlo::{McMode, McParams, McSummary, McTrade, simulate};
+use backtest::quality::{QualityGate, QualityMode};
+use backtest::report::{JsonReport, underwater_rows, write_csv};
+use backtest::synthetic::{SyntheticModel, SyntheticParams, synthetic_candles};
+use backtest::trend::{EntryGate, TrendParams, TrendReport, TrendRunConfig, run_trend};
 use execution::sim::ExecutionModel;
-use policy::trend_policy::{
-    TrendAction, TrendDecisionReason, TrendMode, TrendPolicyInput, TrendPolicyParams,
-    trend_policy_decision,
-};
-use state_machine::trend_cause::TrendCause;
-use state_machine::trend_state::TrendState;
-use state_machine::trend_transition::trend_transition;
-use structure::bos::{BosParams, BosState, BosTracker};
-use structure::pullback::{PullbackParams, PullbackTracker};
-use structure::structure::{StructureParams, detect_structure};
-
-#[derive(Debug, Copy, Clone, ValueEnum)]
-enum EntryGate {
-    Trend,
-    TrendBos,
-    TrendBosPullback,
-}
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 struct Args {
     #[arg(long)]
     symbol: String,
@@ -34,10 +23,33 @@ struct Args {
     start: String,
     #[arg(long)]
     end: String,
-    #[arg(long, default_value = "data/backtest_trend.csv")]
-    cache: String,
+    /// Кэш свечей, CSV или `.parquet` (feature `parquet`);
+    /// по умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    cache: Option<String>,
     #[arg(long, default_value_t = false)]
     refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Синтетические свечи вместо биржевых: gbm, heston, regime (зерно — `--seed`)
+    #[arg(long, value_enum)]
+    synthetic: Option<SyntheticModel>,
+    /// Начальная цена синтетической серии
+    #[arg(long, default_value_t = 2000.0)]
+    synthetic_price: f64,
+    /// Годовой drift лог-цены синтетической серии
+    #[arg(long, default_value_t = 0.0)]
+    synthetic_drift: f64,
+    /// Годовая волатильность синтетической серии
+    #[arg(long, default_value_t = 0.6)]
+    synthetic_vol: f64,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
 
     #[arg(long, default_value_t = 20)]
     ema_fast: usize,
@@ -65,152 +77,50 @@ struct Args {
     force_close_at_end: bool,
     #[arg(long, default_value = "data/backtest_trend_equity.csv")]
     equity_out: String,
+    /// Underwater-кривая: глубина просадки и время под пиком equity
+    #[arg(long, default_value = "data/backtest_trend_underwater.csv")]
+    underwater_out: String,
+    /// PnL, ROI и число сделок по месяцам (UTC)
+    #[arg(long, default_value = "data/backtest_trend_monthly.csv")]
+    monthly_out: String,
     #[arg(long, default_value = "data/backtest_trend_trades.csv")]
     trades_out: String,
+    /// Закрытые сделки: вход/выход, причина, PnL, баров в позиции, MAE/MFE
+    #[arg(long, default_value = "data/backtest_trend_round_trips.csv")]
+    round_trips_out: String,
+    /// Monte Carlo по закрытым сделкам: прогонов пересборки (0 — выключено)
+    #[arg(long, default_value_t = 0)]
+    mc_runs: usize,
+    /// Monte Carlo: bootstrap — сделки с возвращением, shuffle — только их порядок
+    #[arg(long, value_enum, default_value_t = McMode::Bootstrap)]
+    mc_mode: McMode,
+    /// Monte Carlo: случайное доп. проскальзывание сделки, от 0 до N bps её оборота
+    #[arg(long, default_value_t = 0.0)]
+    mc_slippage_bps: f64,
+    /// Monte Carlo: ROI, просадка и PF каждого прогона
+    #[arg(long, default_value = "data/backtest_trend_mc.csv")]
+    mc_out: String,
+    /// Зерно ГСЧ стохастических частей прогона, пишется в JSON отчёт
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// JSON отчёт: конфиг, метрики, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct CandleRow {
-    ts: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-#[derive(serde::Serialize)]
-struct EquityRow {
-    ts: i64,
-    close: f64,
+#[derive(Serialize)]
+struct TrendMetrics {
     state: String,
-    quote: f64,
-    base: f64,
-    equity: f64,
-    drawdown_pct: f64,
-}
-
-#[derive(serde::Serialize)]
-struct TradeRow {
-    ts: i64,
-    side: String,
-    reason: String,
-    qty: f64,
-    mid_price: f64,
-    fill_price: f64,
-    quote_delta: f64,
-    trade_pnl: Option<f64>,
-}
-
-struct EmaCalc {
-    alpha: f64,
-    value: Option<f64>,
-}
-
-impl EmaCalc {
-    fn new(period: usize) -> Self {
-        let p = period.max(1) as f64;
-        Self {
-            alpha: 2.0 / (p + 1.0),
-            value: None,
-        }
-    }
-
-    fn update(&mut self, x: f64) -> f64 {
-        match self.value {
-            Some(v) => {
-                let next = self.alpha * x + (1.0 - self.alpha) * v;
-                self.value = Some(next);
-                next
-            }
-            None => {
-                self.value = Some(x);
-                x
-            }
-        }
-    }
-}
-
-fn date_to_ms(date: &str) -> Result<i64> {
-    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .with_context(|| format!("bad date: {}", date))?;
-    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
-    Ok(dt.timestamp_millis())
-}
-
-fn read_cache(path: &str) -> Result<Vec<structure::candle::Candle>> {
-    let mut rdr = csv::Reader::from_path(path)?;
-    let mut out = Vec::new();
-
-    for r in rdr.deserialize::<CandleRow>() {
-        let row = r?;
-        out.push(structure::candle::Candle {
-            ts: core::types::TimestampMs(row.ts),
-            open: Price(row.open),
-            high: Price(row.high),
-            low: Price(row.low),
-            close: Price(row.close),
-            volume: Qty(row.volume),
-        });
-    }
-
-    Ok(out)
-}
-
-fn write_cache(path: &str, candles: &[structure::candle::Candle]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let mut wtr = csv::Writer::from_path(path)?;
-    for c in candles {
-        wtr.serialize(CandleRow {
-            ts: c.ts.0,
-            open: c.open.0,
-            high: c.high.0,
-            low: c.low.0,
-            close: c.close.0,
-            volume: c.volume.0,
-        })?;
-    }
-    wtr.flush()?;
-    Ok(())
-}
-
-fn trend_mode_from_state(state: TrendState) -> TrendMode {
-    match state {
-        TrendState::Flat => TrendMode::Flat,
-        TrendState::Long => TrendMode::Long,
-    }
-}
-
-fn write_equity_csv(path: &str, rows: &[EquityRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
-    }
-    wtr.flush()?;
-    Ok(())
-}
-
-fn write_trades_csv(path: &str, rows: &[TradeRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
-    }
-    wtr.flush()?;
-    Ok(())
+    #[serde(flatten)]
+    report: TrendReport,
+    /// Интервалы ROI/просадки/PF по Monte Carlo (`--mc-runs`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mc: Option<McSummary>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let args: Args = parse_args()?;
     if args.ema_fast >= args.ema_slow {
         anyhow::bail!("ema_fast must be < ema_slow");
     }
@@ -218,299 +128,67 @@ async fn main() -> Result<()> {
         anyhow::bail!("initial_quote must be > 0");
     }
 
-    let start_ms = date_to_ms(&args.start)?;
-    let end_ms = date_to_ms(&args.end)? + 24 * 60 * 60 * 1000 - 1;
-
-    let candles = if !args.refresh && std::path::Path::new(&args.cache).exists() {
-        read_cache(&args.cache).context("read cache failed")?
-    } else {
-        let api = BybitRest::new();
-        let data = download_range(&api, &args.symbol, &args.interval, start_ms, end_ms)
-            .await
-            .context("download range failed")?;
-        write_cache(&args.cache, &data).context("write cache failed")?;
-        data
+    let mc_params = McParams {
+        runs: args.mc_runs,
+        mode: args.mc_mode,
+        slippage_bps: args.mc_slippage_bps,
+        seed: args.seed,
     };
-
-    if candles.len() < args.ema_slow + 5 {
-        anyhow::bail!("not enough candles: {}", candles.len());
+    if args.mc_runs > 0 {
+        mc_params.validate()?;
     }
 
-    let mut feed = CandleFeed::new(args.ema_slow * 5);
-    let mut ema_fast = EmaCalc::new(args.ema_fast);
-    let mut ema_slow = EmaCalc::new(args.ema_slow);
-
-    let mut trend_state = TrendState::Flat;
-    let mut quote = Money(args.initial_quote);
-    let mut base = Qty(0.0);
-    let mut entry_price: Option<Price> = None;
-    let mut entry_cost_quote: Option<f64> = None;
-    let mut bos = BosTracker::new();
-    let mut pullback = PullbackTracker::new();
-    let bos_params = BosParams {
-        confirm_candles: 2,
-        epsilon_frac: 0.1,
-    };
-    let pullback_params = PullbackParams {
-        epsilon_frac: 0.1,
-        retrace_frac: 0.4,
-    };
-    let structure_params = StructureParams {
-        pivot_k: 1,
-        min_atr_frac: 0.1,
-    };
-
-    let exec = ExecutionModel {
-        fee_bps: args.fee_bps,
-        spread_bps: args.spread_bps,
-        slippage_bps: args.slippage_bps,
-    };
-    let mut trades = 0usize;
-    let mut stop_exits = 0usize;
-    let mut closed_trades = 0usize;
-    let mut winning_trades = 0usize;
-    let mut losing_trades = 0usize;
-    let mut gross_profit = 0.0_f64;
-    let mut gross_loss = 0.0_f64;
-
-    let mut max_equity = quote.0;
-    let mut max_drawdown = 0.0_f64;
-    let mut equity_rows: Vec<EquityRow> = Vec::new();
-    let mut trade_rows: Vec<TradeRow> = Vec::new();
-    let mut last_ts: Option<i64> = None;
-    let mut bars_since_exit: usize = usize::MAX / 2;
-
-    for c in candles {
-        last_ts = Some(c.ts.0);
-        bars_since_exit = bars_since_exit.saturating_add(1);
-        feed.push(c);
-        let fast = ema_fast.update(c.close.0);
-        let slow = ema_slow.update(c.close.0);
-
-        let Some(atr) = feed.atr() else {
-            continue;
-        };
-
-        let ms = detect_structure(&feed.candles, structure_params);
-        bos.on_candle_close(&c, &ms, atr, bos_params);
-        if bos.state == BosState::Confirmed {
-            pullback.on_candle_close(&c, &bos, atr, pullback_params);
-        } else {
-            pullback.reset();
-        }
-
-        let mut decision = trend_policy_decision(
-            trend_mode_from_state(trend_state),
-            TrendPolicyInput {
-                close: c.close,
-                atr,
-                ema_fast: Price(fast),
-                ema_slow: Price(slow),
-                position_qty: base,
-                entry_price,
-            },
-            TrendPolicyParams {
-                atr_stop_mult: args.atr_stop_mult,
-            },
-        );
-
-        if decision.action == TrendAction::EnterLong {
-            let bos_gate_ok = match args.entry_gate {
-                EntryGate::Trend => true,
-                EntryGate::TrendBos => bos.state == BosState::Confirmed,
-                EntryGate::TrendBosPullback => {
-                    bos.state == BosState::Confirmed && pullback.triggered
-                }
-            };
-            let trend_gap_bps = if c.close.0 > 0.0 {
-                ((fast - slow) / c.close.0) * 10_000.0
-            } else {
-                0.0
-            };
-            let trend_gap_ok = trend_gap_bps >= args.min_trend_gap_bps.max(0.0);
-            let cooldown_ok = bars_since_exit >= args.cooldown_bars;
-            let atr_pct = if c.close.0 > 0.0 {
-                100.0 * atr.0 / c.close.0
-            } else {
-                0.0
-            };
-            let atr_ok = atr_pct <= args.max_atr_pct.max(0.0);
-            let gate_ok = bos_gate_ok && trend_gap_ok && cooldown_ok && atr_ok;
-
-            if !gate_ok {
-                decision = match trend_mode_from_state(trend_state) {
-                    TrendMode::Flat => policy::trend_policy::TrendPolicyDecision {
-                        next_mode: TrendMode::Flat,
-                        action: TrendAction::HoldFlat,
-                        reason: TrendDecisionReason::NoSignal,
-                    },
-                    TrendMode::Long => policy::trend_policy::TrendPolicyDecision {
-                        next_mode: TrendMode::Long,
-                        action: TrendAction::HoldLong,
-                        reason: TrendDecisionReason::NoSignal,
-                    },
-                };
-            }
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let synthetic = SyntheticParams::for_model(
+        args.synthetic,
+        args.synthetic_price,
+        args.synthetic_drift,
+        args.synthetic_vol,
+    )?;
+    let candles = match synthetic {
+        Some(p) => synthetic_candles(&p, parse_interval_ms(&args.interval)?, range, args.seed),
+        None => {
+            load_candles(
+                args.exchange,
+                &args.symbol,
+                &args.interval,
+                range,
+                args.cache.as_deref(),
+                args.refresh,
+                gate,
+            )
+            .await?
         }
+    };
 
-        match decision.action {
-            TrendAction::EnterLong => {
-                if quote.0 > 0.0 {
-                    let qty = exec.buy_qty_for_quote(quote.0, c.close);
-                    if qty.0 > 0.0 {
-                        let fill_price = exec.buy_fill_price(c.close);
-                        let cost = exec.buy_cost(qty, c.close);
-                        quote = Money((quote.0 - cost).max(0.0));
-                        base = Qty(base.0 + qty.0);
-                        entry_price = Some(c.close);
-                        entry_cost_quote = Some(cost);
-                        trade_rows.push(TradeRow {
-                            ts: c.ts.0,
-                            side: "BUY".to_string(),
-                            reason: format!("{:?}", decision.reason),
-                            qty: qty.0,
-                            mid_price: c.close.0,
-                            fill_price: fill_price.0,
-                            quote_delta: -cost,
-                            trade_pnl: None,
-                        });
-                        trades += 1;
-                    }
-                }
-
-                if let Ok(next) = trend_transition(trend_state, TrendCause::EntrySignal) {
-                    trend_state = next;
-                }
-            }
-            TrendAction::ExitLong => {
-                if base.0 > 0.0 {
-                    let fill_price = exec.sell_fill_price(c.close);
-                    let proceeds = exec.sell_proceeds(base, c.close);
-                    let mut trade_pnl_out: Option<f64> = None;
-                    if let Some(cost) = entry_cost_quote {
-                        let trade_pnl = proceeds - cost;
-                        trade_pnl_out = Some(trade_pnl);
-                        closed_trades += 1;
-                        if trade_pnl > 0.0 {
-                            winning_trades += 1;
-                            gross_profit += trade_pnl;
-                        } else if trade_pnl < 0.0 {
-                            losing_trades += 1;
-                            gross_loss += -trade_pnl;
-                        }
-                    }
-                    quote = Money(quote.0 + proceeds);
-                    let exit_qty = base;
-                    base = Qty(0.0);
-                    entry_price = None;
-                    entry_cost_quote = None;
-                    bars_since_exit = 0;
-                    trade_rows.push(TradeRow {
-                        ts: c.ts.0,
-                        side: "SELL".to_string(),
-                        reason: format!("{:?}", decision.reason),
-                        qty: exit_qty.0,
-                        mid_price: c.close.0,
-                        fill_price: fill_price.0,
-                        quote_delta: proceeds,
-                        trade_pnl: trade_pnl_out,
-                    });
-                    trades += 1;
-                }
-
-                let cause = match decision.reason {
-                    TrendDecisionReason::AtrStopHit => {
-                        stop_exits += 1;
-                        TrendCause::StopLossHit
-                    }
-                    TrendDecisionReason::InvalidLongOnlyInvariant => TrendCause::ForceFlat,
-                    _ => TrendCause::ExitSignal,
-                };
-
-                if let Ok(next) = trend_transition(trend_state, cause) {
-                    trend_state = next;
-                }
-            }
-            TrendAction::HoldFlat | TrendAction::HoldLong => {}
-        }
-
-        let equity = quote.0 + base.0 * c.close.0;
-        max_equity = max_equity.max(equity);
-        if max_equity > 0.0 {
-            let dd = (max_equity - equity) / max_equity;
-            max_drawdown = max_drawdown.max(dd);
-            equity_rows.push(EquityRow {
-                ts: c.ts.0,
-                close: c.close.0,
-                state: format!("{:?}", trend_state),
-                quote: quote.0,
-                base: base.0,
-                equity,
-                drawdown_pct: dd * 100.0,
-            });
-        }
-    }
-
-    if args.force_close_at_end && base.0 > 0.0 {
-        let final_mark = feed.mid().unwrap_or(Price(0.0));
-        let final_ts = last_ts.unwrap_or(0);
-        let fill_price = exec.sell_fill_price(final_mark);
-        let proceeds = exec.sell_proceeds(base, final_mark);
-        let mut trade_pnl_out: Option<f64> = None;
-        if let Some(cost) = entry_cost_quote {
-            let trade_pnl = proceeds - cost;
-            trade_pnl_out = Some(trade_pnl);
-            closed_trades += 1;
-            if trade_pnl > 0.0 {
-                winning_trades += 1;
-                gross_profit += trade_pnl;
-            } else if trade_pnl < 0.0 {
-                losing_trades += 1;
-                gross_loss += -trade_pnl;
-            }
-        }
-        quote = Money(quote.0 + proceeds);
-        let exit_qty = base;
-        base = Qty(0.0);
-        trades += 1;
-        trade_rows.push(TradeRow {
-            ts: final_ts,
-            side: "SELL".to_string(),
-            reason: "ForceCloseAtEnd".to_string(),
-            qty: exit_qty.0,
-            mid_price: final_mark.0,
-            fill_price: fill_price.0,
-            quote_delta: proceeds,
-            trade_pnl: trade_pnl_out,
-        });
-        if let Ok(next) = trend_transition(trend_state, TrendCause::ForceFlat) {
-            trend_state = next;
-        }
+    if candles.len() < args.ema_slow + 5 {
+        anyhow::bail!("not enough candles: {}", candles.len());
     }
 
-    let final_mark = feed.mid().unwrap_or(Price(0.0));
-    let final_equity = quote.0 + base.0 * final_mark.0;
-    let pnl = final_equity - args.initial_quote;
-    let roi_pct = if args.initial_quote > 0.0 {
-        100.0 * pnl / args.initial_quote
-    } else {
-        0.0
-    };
-    let win_rate_pct = if closed_trades > 0 {
-        100.0 * (winning_trades as f64) / (closed_trades as f64)
-    } else {
-        0.0
+    let params = TrendParams {
+        ema_fast: args.ema_fast,
+        ema_slow: args.ema_slow,
+        atr_stop_mult: args.atr_stop_mult,
+        entry_gate: args.entry_gate,
+        min_trend_gap_bps: args.min_trend_gap_bps,
+        cooldown_bars: args.cooldown_bars,
+        max_atr_pct: args.max_atr_pct,
     };
-    let avg_win = if winning_trades > 0 {
-        gross_profit / (winning_trades as f64)
-    } else {
-        0.0
-    };
-    let avg_loss = if losing_trades > 0 {
-        gross_loss / (losing_trades as f64)
-    } else {
-        0.0
+    let cfg = TrendRunConfig {
+        initial_quote: args.initial_quote,
+        exec: ExecutionModel {
+            fee_bps: args.fee_bps,
+            spread_bps: args.spread_bps,
+            slippage_bps: args.slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        record: true,
     };
+    let run = run_trend(&candles, &params, cfg);
+    let r = run.report;
+    let p = r.perf;
+    let costs = r.costs;
 
     println!("Trend backtest finished");
     println!(
@@ -525,33 +203,99 @@ async fn main() -> Result<()> {
         "filters: min_trend_gap_bps={:.2} cooldown_bars={} max_atr_pct={:.2}",
         args.min_trend_gap_bps, args.cooldown_bars, args.max_atr_pct
     );
-    println!("state={:?} trades={} stop_exits={}", trend_state, trades, stop_exits);
+    println!(
+        "state={:?} trades={} stop_exits={}",
+        r.final_state, r.trades, r.stop_exits
+    );
     println!(
         "final_quote={:.4} final_base={:.8} final_equity={:.4}",
-        quote.0, base.0, final_equity
+        r.final_quote, r.final_base, r.final_equity
+    );
+    println!(
+        "pnl={:.4} roi={:.2}% max_drawdown={:.2}%",
+        p.pnl, p.roi_pct, p.max_drawdown_pct
     );
-    println!("pnl={:.4} roi={:.2}% max_drawdown={:.2}%", pnl, roi_pct, max_drawdown * 100.0);
-    if gross_loss > 0.0 {
-        println!(
-            "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor={:.4}",
-            closed_trades,
-            win_rate_pct,
-            avg_win,
-            avg_loss,
-            gross_profit / gross_loss
-        );
-    } else {
-        println!(
-            "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor=INF",
-            closed_trades, win_rate_pct, avg_win, avg_loss
-        );
-    }
-    write_equity_csv(&args.equity_out, &equity_rows).context("write equity csv failed")?;
-    write_trades_csv(&args.trades_out, &trade_rows).context("write trades csv failed")?;
     println!(
-        "artifacts: equity_csv={} trades_csv={}",
-        args.equity_out, args.trades_out
+        "drawdown: max_drawdown_duration={:.2}h time_to_recovery={}",
+        p.max_drawdown_duration_h,
+        p.recovery_label()
     );
+    println!(
+        "risk: sharpe={:.3} sortino={:.3} calmar={:.3} exposure={:.2}% avg_trade_duration={:.2}h turnover={:.2}x",
+        p.sharpe, p.sortino, p.calmar, p.exposure_pct, p.avg_trade_duration_h, p.turnover
+    );
+    println!(
+        "closed_trades={} win_rate={:.2}% avg_win={:.4} avg_loss={:.4} profit_factor={}",
+        p.closed_trades,
+        p.win_rate_pct,
+        p.avg_win,
+        p.avg_loss,
+        p.profit_factor_label()
+    );
+    println!(
+        "costs: taker_fees={:.4} spread_slippage={:.4} total_costs={:.4}",
+        costs.taker_fees, costs.spread_slippage, costs.total
+    );
+    write_csv(&args.equity_out, &run.equity_rows).context("write equity csv failed")?;
+    write_csv(
+        &args.underwater_out,
+        underwater_rows(run.equity_rows.iter().map(|e| (e.ts, e.drawdown_pct))),
+    )
+    .context("write underwater csv failed")?;
+    let monthly = monthly_breakdown(
+        r.final_equity - p.pnl,
+        &run.equity_rows
+            .iter()
+            .map(|e| (e.ts, e.equity))
+            .collect::<Vec<_>>(),
+        r.final_equity,
+        &run.round_trips
+            .iter()
+            .map(|t| t.exit_ts)
+            .collect::<Vec<_>>(),
+    );
+    write_csv(&args.monthly_out, &monthly).context("write monthly csv failed")?;
+    println!("monthly: {}", breakdown_summary(&monthly));
+    write_csv(&args.trades_out, &run.trade_rows).context("write trades csv failed")?;
+    write_csv(&args.round_trips_out, &run.round_trips).context("write round trips csv failed")?;
+    let mc_trades = McTrade::from_round_trips(&run.round_trips);
+    let mc = if args.mc_runs > 0 && !mc_trades.is_empty() {
+        let rows = simulate(&mc_trades, args.initial_quote, &mc_params);
+        write_csv(&args.mc_out, &rows).context("write monte carlo csv failed")?;
+        Some(McSummary::new(&rows, mc_trades.len()))
+    } else {
+        None
+    };
+    match &mc {
+        Some(s) => println!("mc: {}", s.label()),
+        None if args.mc_runs > 0 => println!("mc: skipped, no closed trades"),
+        None => {}
+    }
+    let has_mc = mc.is_some();
+    let metrics = TrendMetrics {
+        state: format!("{:?}", r.final_state),
+        report: r,
+        mc,
+    };
+    let mut report = JsonReport::new("backtest_trend", &args, metrics)
+        .with_seed(args.seed)
+        .with_monthly(monthly)
+        .with_costs(costs)
+        .with_html(HtmlData::new(
+            run.equity_rows
+                .iter()
+                .map(|e| (e.ts, e.equity, e.drawdown_pct)),
+            &run.round_trips,
+        )?)
+        .artifact("equity_csv", &args.equity_out)
+        .artifact("underwater_csv", &args.underwater_out)
+        .artifact("monthly_csv", &args.monthly_out)
+        .artifact("trades_csv", &args.trades_out)
+        .artifact("round_trips_csv", &args.round_trips_out);
+    if has_mc {
+        report = report.artifact("mc_csv", &args.mc_out);
+    }
+    report.finish(args.report_out.as_deref())?;
 
     Ok(())
 }
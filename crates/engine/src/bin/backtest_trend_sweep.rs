@@ -1,32 +1,34 @@
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, TimeZone, Utc};
-use clap::{Parser, ValueEnum};
-
-use bybit::rest::{BybitRest, download_range};
-use core::types::{Money, Price, Qty};
-use engine::feed::CandleFeed;
-use execution::sim::ExecutionModel;
-use policy::trend_policy::{
-    TrendAction, TrendDecisionReason, TrendMode, TrendPolicyInput, TrendPolicyParams,
-    trend_policy_decision,
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::checkpoint::Checkpoint;
+use backtest::config::parse_args;
+use backtest::data::{
+    Venue, date_range_ms, explicit_cache, load_candles, oos_start_ms, parse_num_list,
+    parse_symbols, split_at_ms,
 };
-use state_machine::trend_cause::TrendCause;
-use state_machine::trend_state::TrendState;
-use state_machine::trend_transition::trend_transition;
-use structure::bos::{BosParams, BosState, BosTracker};
-use structure::pullback::{PullbackParams, PullbackTracker};
-use structure::structure::{StructureParams, detect_structure};
-
-#[derive(Debug, Copy, Clone, ValueEnum)]
-enum EntryGate {
-    Trend,
-    TrendBos,
-    TrendBosPullback,
-}
+use backtest::parallel::{par_map, pool};
+use backtest::progress::Progress;
+use backtest::quality::{QualityGate, QualityMode};
+use backtest::report::{CsvStream, JsonReport, SymbolRow, write_csv};
+use backtest::search::{Dim, Search, SearchMode};
+use backtest::stability::{StabilitySummary, neighborhood};
+use backtest::stats::{Performance, RankBy};
+use backtest::table::write_table;
+use backtest::topn::TopN;
+use backtest::trend::{
+    EntryGate, TrendParams, TrendReport, TrendRunConfig, parse_gate_list, run_trend,
+};
+use backtest::walkforward::{OosSummary, WalkForward, WfConfigRow, WfResult, WfWindow, slice_ms};
+use execution::sim::ExecutionModel;
+use structure::candle::Candle;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 struct Args {
-    #[arg(long)]
+    /// Символ или список (`--symbols BTCUSDT,ETHUSDT`): конфиг прогоняется на каждом,
+    /// ранжирование по средним метрикам; кэш — `<cache>_<SYMBOL>.csv`
+    #[arg(long, alias = "symbols")]
     symbol: String,
     #[arg(long, default_value = "60")]
     interval: String,
@@ -34,10 +36,36 @@ struct Args {
     start: String,
     #[arg(long)]
     end: String,
-    #[arg(long, default_value = "data/backtest_trend.csv")]
-    cache: String,
+    /// Кэш свечей, CSV или `.parquet` (feature `parquet`);
+    /// по умолчанию — локальное хранилище свечей `CANDLE_DB` (`data/candles.sqlite`)
+    #[arg(long)]
+    cache: Option<String>,
     #[arg(long, default_value_t = false)]
     refresh: bool,
+    /// Биржа свечей: bybit | binance
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Проверка свечей перед прогоном: strict — ошибка с отчётом, repair — перекачать, off
+    #[arg(long, value_enum, default_value_t = QualityMode::Strict)]
+    data_quality: QualityMode,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 0.5)]
+    max_missing_pct: f64,
+
+    /// grid — все комбинации списков; random/tpe — `--samples` точек,
+    /// числовые списки можно задавать диапазоном `lo..hi`
+    #[arg(long, value_enum, default_value_t = SearchMode::Grid)]
+    search: SearchMode,
+    #[arg(long, default_value_t = 200)]
+    samples: usize,
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Потоков для прогонов сетки и walk-forward, 0 — по числу ядер
+    #[arg(long, default_value_t = 0)]
+    jobs: usize,
+    /// Обновлять прогресс каждые N посчитанных конфигов
+    #[arg(long, default_value_t = 10)]
+    progress_every: usize,
 
     #[arg(long, default_value = "20")]
     ema_fast_list: String,
@@ -65,23 +93,77 @@ struct Args {
     #[arg(long, default_value_t = true)]
     force_close_at_end: bool,
 
+    /// Доля конца периода под out-of-sample: ранжирование по началу,
+    /// OOS метрики top_n — в summary
+    #[arg(long, default_value_t = 0.0)]
+    oos_split: f64,
+    /// Walk-forward: дней обучения в окне; вместе с `--wf-test-days` лучший конфиг сетки
+    /// выбирается заново на каждом окне, итог — out-of-sample метрики (только `--search grid`)
+    #[arg(long)]
+    wf_train_days: Option<u32>,
+    /// Walk-forward: дней проверки сразу после обучения
+    #[arg(long)]
+    wf_test_days: Option<u32>,
+    /// Walk-forward: сдвиг окна, дней (по умолчанию — `--wf-test-days`)
+    #[arg(long)]
+    wf_step_days: Option<u32>,
+    /// Walk-forward: выбранный конфиг и его метрики по каждому окну
+    #[arg(long, default_value = "data/backtest_trend_sweep_wf_windows.csv")]
+    wf_windows_out: String,
+    /// Walk-forward: out-of-sample метрики каждого конфига по всем test окнам
+    #[arg(long, default_value = "data/backtest_trend_sweep_wf_configs.csv")]
+    wf_configs_out: String,
+
     #[arg(long, default_value_t = 10)]
     top_n: usize,
+    #[arg(long, value_enum, default_value_t = RankBy::Roi)]
+    rank_by: RankBy,
     #[arg(long, default_value = "data/backtest_trend_sweep_summary.csv")]
     summary_out: String,
+    /// Все протестированные конфигурации (не только top_n) — для анализа без перезапуска.
+    /// Здесь и в summary/per-symbol/stability: `.parquet` — Parquet (feature `parquet`)
+    #[arg(long, default_value = "data/backtest_trend_sweep_results.csv")]
+    results_out: String,
+    /// Писать каждый конфиг в `--results-out` (только CSV) по ходу sweep'а, в памяти держать
+    /// лишь лучшие (top_n/stability/artifacts) — для сеток на миллионы конфигов; rank там 0
+    #[arg(long, default_value_t = false)]
+    stream_results: bool,
+    /// Метрики top_n конфигов по каждому символу
+    #[arg(long, default_value = "data/backtest_trend_sweep_symbols.csv")]
+    per_symbol_out: String,
+    /// Посчитанные конфиги дописываются сюда по ходу sweep'а
+    #[arg(long, default_value = "data/backtest_trend_sweep_checkpoint.jsonl")]
+    checkpoint: String,
+    #[arg(long, default_value_t = 50)]
+    checkpoint_every: usize,
+    /// Не пересчитывать конфиги из `--checkpoint` (тот же прогон: данные, диапазон, издержки)
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+    /// Equity лучшего конфига (при нескольких символах — по первому), формат MM backtest'ов
+    #[arg(long, default_value = "data/backtest_trend_sweep_equity.csv")]
+    equity_out: String,
+    /// Перезапустить top-K конфигов с записью equity/trades CSV (артефакты rankN_*)
+    #[arg(long, default_value_t = 3)]
+    top_artifacts: usize,
+    #[arg(long, default_value = "data/backtest_trend_sweep_top")]
+    top_artifacts_dir: String,
+    /// Для top-K конфигов прогнать соседей ± шаг по каждому параметру
+    #[arg(long, default_value_t = 3)]
+    stability_top: usize,
+    #[arg(long, default_value = "data/backtest_trend_sweep_stability.csv")]
+    stability_out: String,
+    /// Конфиг хрупкий, если худший сосед теряет больше этой доли ROI
+    #[arg(long, default_value_t = 0.5)]
+    fragile_roi_drop: f64,
+    /// JSON отчёт: конфиг, метрики лучшего прогона, издержки, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+    /// Без progress bar: прогресс строками `progress:` в stdout (так запускает worker)
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct CandleRow {
-    ts: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-#[derive(serde::Serialize)]
+#[derive(Serialize)]
 struct SummaryRow {
     rank: usize,
     ema_fast: usize,
@@ -98,523 +180,757 @@ struct SummaryRow {
     max_drawdown_pct: f64,
     pnl: f64,
     roi_pct: f64,
+    sharpe: f64,
+    sortino: f64,
+    calmar: f64,
+    exposure_pct: f64,
+    avg_trade_duration_h: f64,
+    turnover: f64,
+    /// ROI на худшем символе
+    min_symbol_roi_pct: f64,
+    oos_roi_pct: Option<f64>,
+    oos_max_drawdown_pct: Option<f64>,
+    oos_sharpe: Option<f64>,
+    oos_profit_factor: Option<f64>,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct SweepConfig {
-    ema_fast: usize,
-    ema_slow: usize,
-    entry_gate: EntryGate,
-    min_trend_gap_bps: f64,
-    cooldown_bars: usize,
-    max_atr_pct: f64,
+/// Метрики sweep'а для JSON отчёта: метрики лучшего прогона на верхнем уровне
+#[derive(Serialize)]
+struct SweepMetrics<'a> {
+    tested: usize,
+    top_saved: usize,
+    #[serde(flatten)]
+    best: Option<&'a TrendReport>,
+    best_config: Option<&'a SummaryRow>,
+    per_symbol: &'a [SymbolRow],
+    stability: &'a [StabilitySummary],
+    /// Метрики лучшего конфига на out-of-sample
+    oos: Option<&'a Performance>,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct BacktestReport {
-    trades: usize,
-    closed_trades: usize,
-    stop_exits: usize,
-    win_rate_pct: f64,
-    profit_factor: f64,
-    max_drawdown_pct: f64,
-    pnl: f64,
-    roi_pct: f64,
+/// Метрики walk-forward для JSON отчёта: OOS выбранных конфигов подряд на верхнем уровне
+#[derive(Serialize)]
+struct WalkForwardMetrics<'a> {
+    configs: usize,
+    #[serde(flatten)]
+    oos: &'a OosSummary,
+    windows: &'a [WfWindow],
+    best_configs: &'a [WfConfigRow],
 }
 
-struct EmaCalc {
-    alpha: f64,
-    value: Option<f64>,
+/// Символ с in-sample и out-of-sample свечами
+struct Market {
+    symbol: String,
+    candles: Vec<Candle>,
+    oos_candles: Vec<Candle>,
 }
 
-impl EmaCalc {
-    fn new(period: usize) -> Self {
-        let p = period.max(1) as f64;
-        Self {
-            alpha: 2.0 / (p + 1.0),
-            value: None,
-        }
-    }
+fn symbol_key(symbol: &str, cfg: &TrendParams) -> String {
+    format!("{} {:?}", symbol, cfg)
+}
 
-    fn update(&mut self, x: f64) -> f64 {
-        match self.value {
-            Some(v) => {
-                let next = self.alpha * x + (1.0 - self.alpha) * v;
-                self.value = Some(next);
-                next
-            }
-            None => {
-                self.value = Some(x);
-                x
-            }
-        }
+/// Конфиг на каждом символе (прогоны — через чекпоинт); отчёт — среднее
+/// Лучший конфиг в progress bar
+fn best_label(cfg: &TrendParams) -> String {
+    format!(
+        "gate={:?} ema={}/{} gap_bps={:.2} cooldown={} max_atr_pct={:.2}",
+        cfg.entry_gate,
+        cfg.ema_fast,
+        cfg.ema_slow,
+        cfg.min_trend_gap_bps,
+        cfg.cooldown_bars,
+        cfg.max_atr_pct
+    )
+}
+
+/// Строка results/summary; `oos` — метрики конфига на out-of-sample
+fn summary_row(
+    rank: usize,
+    cfg: &TrendParams,
+    rep: &TrendReport,
+    min_symbol_roi_pct: f64,
+    oos: Option<&Performance>,
+) -> SummaryRow {
+    SummaryRow {
+        rank,
+        ema_fast: cfg.ema_fast,
+        ema_slow: cfg.ema_slow,
+        entry_gate: format!("{:?}", cfg.entry_gate),
+        min_trend_gap_bps: cfg.min_trend_gap_bps,
+        cooldown_bars: cfg.cooldown_bars,
+        max_atr_pct: cfg.max_atr_pct,
+        trades: rep.trades,
+        closed_trades: rep.perf.closed_trades,
+        stop_exits: rep.stop_exits,
+        win_rate_pct: rep.perf.win_rate_pct,
+        profit_factor: rep.perf.profit_factor,
+        max_drawdown_pct: rep.perf.max_drawdown_pct,
+        pnl: rep.perf.pnl,
+        roi_pct: rep.perf.roi_pct,
+        sharpe: rep.perf.sharpe,
+        sortino: rep.perf.sortino,
+        calmar: rep.perf.calmar,
+        exposure_pct: rep.perf.exposure_pct,
+        avg_trade_duration_h: rep.perf.avg_trade_duration_h,
+        turnover: rep.perf.turnover,
+        min_symbol_roi_pct,
+        oos_roi_pct: oos.map(|p| p.roi_pct),
+        oos_max_drawdown_pct: oos.map(|p| p.max_drawdown_pct),
+        oos_sharpe: oos.map(|p| p.sharpe),
+        oos_profit_factor: oos.map(|p| p.profit_factor),
     }
 }
 
-fn date_to_ms(date: &str) -> Result<i64> {
-    let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .with_context(|| format!("bad date: {}", date))?;
-    let dt = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
-    Ok(dt.timestamp_millis())
+/// ROI конфига на худшем символе (прогоны — из чекпоинта)
+fn min_symbol_roi(
+    checkpoint: &Checkpoint<TrendReport>,
+    markets: &[Market],
+    cfg: &TrendParams,
+) -> f64 {
+    markets
+        .iter()
+        .filter_map(|m| checkpoint.get(&symbol_key(&m.symbol, cfg)))
+        .map(|r| r.perf.roi_pct)
+        .fold(f64::INFINITY, f64::min)
 }
 
-fn read_cache(path: &str) -> Result<Vec<structure::candle::Candle>> {
-    let mut rdr = csv::Reader::from_path(path)?;
-    let mut out = Vec::new();
+/// Посчитанные конфиги: все в памяти или, с `--stream-results`, строки сразу в results CSV,
+/// а в памяти — только лучшие `keep`
+struct Collected {
+    top: TopN<(TrendParams, TrendReport)>,
+    stream: Option<CsvStream>,
+    rank_by: RankBy,
+    tested: usize,
+}
 
-    for r in rdr.deserialize::<CandleRow>() {
-        let row = r?;
-        out.push(structure::candle::Candle {
-            ts: core::types::TimestampMs(row.ts),
-            open: Price(row.open),
-            high: Price(row.high),
-            low: Price(row.low),
-            close: Price(row.close),
-            volume: Qty(row.volume),
-        });
+impl Collected {
+    fn new(args: &Args) -> Result<Self> {
+        let stream = if args.stream_results {
+            Some(CsvStream::create(&args.results_out)?)
+        } else {
+            None
+        };
+        let keep = match stream {
+            Some(_) => args.top_n.max(args.stability_top).max(args.top_artifacts),
+            None => usize::MAX,
+        };
+        Ok(Self {
+            top: TopN::new(keep),
+            stream,
+            rank_by: args.rank_by,
+            tested: 0,
+        })
     }
 
-    Ok(out)
-}
+    fn push(
+        &mut self,
+        checkpoint: &mut Checkpoint<TrendReport>,
+        markets: &[Market],
+        cfg: TrendParams,
+        rep: TrendReport,
+    ) -> Result<()> {
+        self.tested += 1;
+        if let Some(stream) = &mut self.stream {
+            let min_roi = min_symbol_roi(checkpoint, markets, &cfg);
+            stream.write(&summary_row(0, &cfg, &rep, min_roi, None))?;
+        }
+        let by = self.rank_by;
+        let evicted = self
+            .top
+            .push((cfg, rep), |a, b| a.1.perf.rank_cmp(&b.1.perf, by));
+        // прогоны вытесненных по символам остаются только в файле чекпоинта
+        for (cfg, _) in evicted {
+            for m in markets {
+                checkpoint.forget(&symbol_key(&m.symbol, &cfg));
+            }
+        }
+        Ok(())
+    }
 
-fn write_cache(path: &str, candles: &[structure::candle::Candle]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
+    /// Сколько конфигов посчитано и лучшие из них по убыванию
+    fn finish(mut self) -> Result<(usize, Vec<(TrendParams, TrendReport)>)> {
+        if let Some(stream) = &mut self.stream {
+            stream.flush()?;
+        }
+        let by = self.rank_by;
+        let best = self
+            .top
+            .into_sorted(|a, b| a.1.perf.rank_cmp(&b.1.perf, by));
+        Ok((self.tested, best))
     }
+}
 
-    let mut wtr = csv::Writer::from_path(path)?;
-    for c in candles {
-        wtr.serialize(CandleRow {
-            ts: c.ts.0,
-            open: c.open.0,
-            high: c.high.0,
-            low: c.low.0,
-            close: c.close.0,
-            volume: c.volume.0,
-        })?;
+fn evaluate(
+    checkpoint: &mut Checkpoint<TrendReport>,
+    markets: &[Market],
+    cfg: &TrendParams,
+    run_cfg: TrendRunConfig,
+) -> Result<TrendReport> {
+    let mut reports = Vec::with_capacity(markets.len());
+    for m in markets {
+        reports.push(checkpoint.get_or_run(symbol_key(&m.symbol, cfg), || {
+            run_trend(&m.candles, cfg, run_cfg).report
+        })?);
     }
-    wtr.flush()?;
-    Ok(())
+    Ok(TrendReport::mean(&reports))
 }
 
-fn write_summary(path: &str, rows: &[SummaryRow]) -> Result<()> {
-    if let Some(parent) = std::path::Path::new(path).parent() {
-        std::fs::create_dir_all(parent)?;
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    if args.initial_quote <= 0.0 {
+        anyhow::bail!("initial_quote must be > 0");
     }
 
-    let mut wtr = csv::Writer::from_path(path)?;
-    for r in rows {
-        wtr.serialize(r)?;
+    let entry_gate_list = parse_gate_list(&args.entry_gate_list)?;
+    let grid = match args.search {
+        SearchMode::Grid => grid_configs(&args, &entry_gate_list)?,
+        _ => Vec::new(),
+    };
+    let dims = search_dims(&args, entry_gate_list.len())?;
+    let wf = WalkForward::from_days(args.wf_train_days, args.wf_test_days, args.wf_step_days)?;
+    if wf.is_some() && (args.search != SearchMode::Grid || args.oos_split > 0.0) {
+        anyhow::bail!("walk-forward needs --search grid and no --oos-split");
     }
-    wtr.flush()?;
-    Ok(())
-}
 
-fn parse_num_list<T>(s: &str, name: &str) -> Result<Vec<T>>
-where
-    T: std::str::FromStr,
-    <T as std::str::FromStr>::Err: std::fmt::Display,
-{
-    let mut out = Vec::new();
-    for raw in s.split(',') {
-        let v = raw.trim();
-        if v.is_empty() {
-            continue;
+    let range = date_range_ms(&args.start, &args.end)?;
+    let gate = QualityGate::new(args.data_quality, args.max_missing_pct)?;
+    let symbols = parse_symbols(&args.symbol)?;
+    let multi = symbols.len() > 1;
+    let mut markets = Vec::with_capacity(symbols.len());
+    let mut oos_starts = Vec::new();
+    for symbol in symbols {
+        let cache = explicit_cache(args.cache.as_deref(), multi, &symbol);
+        let all_candles = load_candles(
+            args.exchange,
+            &symbol,
+            &args.interval,
+            range,
+            cache.as_deref(),
+            args.refresh,
+            gate,
+        )
+        .await?;
+        let oos_start = oos_start_ms(&all_candles, args.oos_split)?;
+        let (candles, oos_candles) = split_at_ms(&all_candles, oos_start.unwrap_or(i64::MAX));
+        if candles.len() < 120 {
+            anyhow::bail!("not enough candles for {}: {}", symbol, candles.len());
+        }
+        if oos_start.is_some() && oos_candles.len() < 120 {
+            anyhow::bail!(
+                "not enough out-of-sample candles for {}: {}",
+                symbol,
+                oos_candles.len()
+            );
         }
-        let parsed = v
-            .parse::<T>()
-            .map_err(|e| anyhow::anyhow!("bad value in {}: '{}' ({})", name, v, e))?;
-        out.push(parsed);
+        oos_starts.extend(oos_start);
+        markets.push(Market {
+            symbol,
+            candles: candles.to_vec(),
+            oos_candles: oos_candles.to_vec(),
+        });
     }
-    if out.is_empty() {
-        anyhow::bail!("{} cannot be empty", name);
+    let with_oos = !oos_starts.is_empty();
+
+    let run_cfg = TrendRunConfig {
+        initial_quote: args.initial_quote,
+        exec: ExecutionModel {
+            fee_bps: args.fee_bps,
+            spread_bps: args.spread_bps,
+            slippage_bps: args.slippage_bps,
+        },
+        force_close_at_end: args.force_close_at_end,
+        record: false,
+    };
+    if let Some(wf) = wf {
+        return walk_forward(&args, wf, range, &markets, &grid, run_cfg);
     }
-    Ok(out)
-}
 
-fn parse_gate_list(s: &str) -> Result<Vec<EntryGate>> {
-    let mut out = Vec::new();
-    for raw in s.split(',') {
-        let v = raw.trim().to_ascii_lowercase();
-        let gate = match v.as_str() {
-            "trend" => EntryGate::Trend,
-            "trend-bos" => EntryGate::TrendBos,
-            "trend-bos-pullback" => EntryGate::TrendBosPullback,
-            _ => anyhow::bail!("bad entry gate: {}", raw.trim()),
-        };
-        out.push(gate);
+    let mut run_key = format!(
+        "{} {} {}..{} {:?}",
+        args.symbol, args.interval, args.start, args.end, run_cfg
+    );
+    for ts in &oos_starts {
+        run_key.push_str(&format!(" is<{}", ts));
     }
-    if out.is_empty() {
-        anyhow::bail!("entry_gate_list cannot be empty");
+    let mut checkpoint = Checkpoint::open(
+        &args.checkpoint,
+        &run_key,
+        args.resume,
+        args.checkpoint_every,
+    )?;
+    if checkpoint.resumed() > 0 {
+        println!(
+            "checkpoint: resumed={} path={}",
+            checkpoint.resumed(),
+            args.checkpoint
+        );
     }
-    Ok(out)
-}
 
-fn trend_mode_from_state(state: TrendState) -> TrendMode {
-    match state {
-        TrendState::Flat => TrendMode::Flat,
-        TrendState::Long => TrendMode::Long,
+    let mut collected = Collected::new(&args)?;
+    if args.search == SearchMode::Grid {
+        let pool = pool(args.jobs)?;
+        let mut progress = Progress::new("sweep", "configs", grid.len(), args.quiet);
+        // пачками: прогоны пачки параллельно, посчитанное сразу уходит в чекпоинт
+        let batch = args.checkpoint_every.max(pool.current_num_threads());
+        for batch in grid.chunks(batch) {
+            let done = collected.tested;
+            let fresh = par_map(
+                &pool,
+                batch,
+                args.progress_every,
+                |cfg| {
+                    markets
+                        .iter()
+                        .filter_map(|m| {
+                            let key = symbol_key(&m.symbol, cfg);
+                            let todo = checkpoint.get(&key).is_none();
+                            todo.then(|| (key, run_trend(&m.candles, cfg, run_cfg).report))
+                        })
+                        .collect::<Vec<_>>()
+                },
+                |n| progress.set(done + n),
+            );
+            for (key, rep) in fresh.into_iter().flatten() {
+                checkpoint.insert(key, rep)?;
+            }
+            for cfg in batch {
+                let rep = evaluate(&mut checkpoint, &markets, cfg, run_cfg)?;
+                progress.offer_best(&rep.perf, args.rank_by, || best_label(cfg));
+                collected.push(&mut checkpoint, &markets, *cfg, rep)?;
+            }
+        }
+    } else {
+        let mut progress = Progress::new("sweep", "configs", args.samples, args.quiet);
+        let search = Search::new(args.search, dims.clone(), args.seed);
+        let sampled = search.run(
+            args.samples,
+            |p| {
+                let cfg = params_at(&args, &entry_gate_list, p);
+                (cfg.ema_fast < cfg.ema_slow).then_some(cfg)
+            },
+            |cfg| {
+                let report = evaluate(&mut checkpoint, &markets, cfg, run_cfg)?;
+                let score = report.perf.metric(args.rank_by);
+                progress.offer_best(&report.perf, args.rank_by, || best_label(cfg));
+                progress.tick();
+                Ok((report, score))
+            },
+        )?;
+        progress.finish();
+        for (cfg, report) in sampled {
+            collected.push(&mut checkpoint, &markets, cfg, report)?;
+        }
     }
-}
+    let (tested, results) = collected.finish()?;
+
+    let mut stability_rows = Vec::new();
+    let mut stability = Vec::new();
+    for (idx, (cfg, rep)) in results.iter().take(args.stability_top).enumerate() {
+        let (rows, summary) = neighborhood(
+            idx + 1,
+            &DIM_NAMES,
+            &dims,
+            &values_of(cfg, &entry_gate_list),
+            &rep.perf,
+            args.fragile_roi_drop,
+            |p| {
+                let cfg = params_at(&args, &entry_gate_list, p);
+                if cfg.ema_fast >= cfg.ema_slow {
+                    return Ok(None);
+                }
+                Ok(Some(
+                    evaluate(&mut checkpoint, &markets, &cfg, run_cfg)?.perf,
+                ))
+            },
+        )?;
+        stability_rows.extend(rows);
+        stability.push(summary);
+    }
+    checkpoint.flush()?;
 
-fn run_backtest(
-    candles: &[structure::candle::Candle],
-    cfg: SweepConfig,
-    atr_stop_mult: f64,
-    exec: ExecutionModel,
-    initial_quote: f64,
-    force_close_at_end: bool,
-) -> BacktestReport {
-    let mut feed = CandleFeed::new(cfg.ema_slow * 5);
-    let mut ema_fast = EmaCalc::new(cfg.ema_fast);
-    let mut ema_slow = EmaCalc::new(cfg.ema_slow);
-
-    let mut trend_state = TrendState::Flat;
-    let mut quote = Money(initial_quote);
-    let mut base = Qty(0.0);
-    let mut entry_price: Option<Price> = None;
-    let mut entry_cost_quote: Option<f64> = None;
-
-    let mut bos = BosTracker::new();
-    let mut pullback = PullbackTracker::new();
-    let bos_params = BosParams {
-        confirm_candles: 2,
-        epsilon_frac: 0.1,
-    };
-    let pullback_params = PullbackParams {
-        epsilon_frac: 0.1,
-        retrace_frac: 0.4,
-    };
-    let structure_params = StructureParams {
-        pivot_k: 1,
-        min_atr_frac: 0.1,
+    let take_n = args.top_n.min(results.len());
+    let oos: Vec<Performance> = if with_oos {
+        results
+            .iter()
+            .take(take_n)
+            .map(|(cfg, _)| {
+                let perf: Vec<Performance> = markets
+                    .iter()
+                    .map(|m| run_trend(&m.oos_candles, cfg, run_cfg).report.perf)
+                    .collect();
+                Performance::mean(&perf)
+            })
+            .collect()
+    } else {
+        Vec::new()
     };
+    let mut rows = Vec::with_capacity(results.len());
+    let mut symbol_rows = Vec::new();
+    for (idx, (cfg, rep)) in results.iter().enumerate() {
+        let min_symbol_roi_pct = min_symbol_roi(&checkpoint, &markets, cfg);
+        if idx < take_n {
+            for m in &markets {
+                if let Some(r) = checkpoint.get(&symbol_key(&m.symbol, cfg)) {
+                    symbol_rows.push(SymbolRow::new(idx + 1, &m.symbol, &r.perf));
+                }
+            }
+        }
+        rows.push(summary_row(
+            idx + 1,
+            cfg,
+            rep,
+            min_symbol_roi_pct,
+            oos.get(idx),
+        ));
+    }
 
-    let mut trades = 0usize;
-    let mut stop_exits = 0usize;
-    let mut closed_trades = 0usize;
-    let mut winning_trades = 0usize;
-    let mut gross_profit = 0.0_f64;
-    let mut gross_loss = 0.0_f64;
-    let mut max_equity = quote.0;
-    let mut max_drawdown = 0.0_f64;
-    let mut bars_since_exit: usize = usize::MAX / 2;
-
-    for c in candles.iter().copied() {
-        bars_since_exit = bars_since_exit.saturating_add(1);
-        feed.push(c);
-        let fast = ema_fast.update(c.close.0);
-        let slow = ema_slow.update(c.close.0);
-
-        let Some(atr) = feed.atr() else {
-            continue;
-        };
-
-        let ms = detect_structure(&feed.candles, structure_params);
-        bos.on_candle_close(&c, &ms, atr, bos_params);
-        if bos.state == BosState::Confirmed {
-            pullback.on_candle_close(&c, &bos, atr, pullback_params);
-        } else {
-            pullback.reset();
+    if !args.stream_results {
+        write_table(&args.results_out, &rows).context("write results failed")?;
+    }
+    rows.truncate(take_n);
+    write_table(&args.summary_out, &rows).context("write summary failed")?;
+    write_table(&args.per_symbol_out, &symbol_rows).context("write per-symbol failed")?;
+    println!(
+        "Sweep done: tested={} top_saved={} symbols={} summary={} results={}",
+        tested,
+        rows.len(),
+        markets.len(),
+        args.summary_out,
+        args.results_out
+    );
+    if let Some(best) = rows.first() {
+        println!(
+            "Best: rank={} gate={} ema={}/{} gap_bps={:.2} cooldown={} max_atr_pct={:.2} roi={:.2}% sharpe={:.3} pf={:.4} dd={:.2}%",
+            best.rank,
+            best.entry_gate,
+            best.ema_fast,
+            best.ema_slow,
+            best.min_trend_gap_bps,
+            best.cooldown_bars,
+            best.max_atr_pct,
+            best.roi_pct,
+            best.sharpe,
+            best.profit_factor,
+            best.max_drawdown_pct
+        );
+    }
+    let best_symbols: Vec<SymbolRow> = symbol_rows
+        .iter()
+        .filter(|r| r.rank == 1)
+        .cloned()
+        .collect();
+    if multi {
+        for r in &best_symbols {
+            println!(
+                "symbol: {} roi={:.2}% sharpe={:.3} dd={:.2}%",
+                r.symbol, r.roi_pct, r.sharpe, r.max_drawdown_pct
+            );
         }
+    }
+    if let (Some(ts), Some(p)) = (oos_starts.first(), oos.first()) {
+        println!(
+            "oos: from_ts={} candles={} roi={:.2}% sharpe={:.3} pf={} dd={:.2}%",
+            ts,
+            markets[0].oos_candles.len(),
+            p.roi_pct,
+            p.sharpe,
+            p.profit_factor_label(),
+            p.max_drawdown_pct
+        );
+    }
+    for st in &stability {
+        println!(
+            "stability: rank={} neighbors={} base_roi={:.2}% mean_roi={:.2}% worst_roi={:.2}% worst_param={} fragile={}",
+            st.rank,
+            st.neighbors,
+            st.base_roi_pct,
+            st.mean_roi_pct,
+            st.worst_roi_pct,
+            st.worst_param.unwrap_or("-"),
+            st.fragile
+        );
+    }
 
-        let mut decision = trend_policy_decision(
-            trend_mode_from_state(trend_state),
-            TrendPolicyInput {
-                close: c.close,
-                atr,
-                ema_fast: Price(fast),
-                ema_slow: Price(slow),
-                position_qty: base,
-                entry_price,
+    let best = results.first().map(|(_, rep)| rep);
+    let metrics = SweepMetrics {
+        tested,
+        top_saved: rows.len(),
+        best,
+        best_config: rows.first(),
+        per_symbol: &best_symbols,
+        stability: &stability,
+        oos: oos.first(),
+    };
+    let mut report = JsonReport::new("backtest_trend_sweep", &args, metrics)
+        .with_seed(args.seed)
+        .artifact("summary_csv", &args.summary_out)
+        .artifact("results_csv", &args.results_out)
+        .artifact("per_symbol_csv", &args.per_symbol_out);
+    if !stability.is_empty() {
+        write_table(&args.stability_out, &stability_rows).context("write stability failed")?;
+        report = report.artifact("stability_csv", &args.stability_out);
+    }
+
+    if let (Some((cfg, _)), Some(m)) = (results.first(), markets.first()) {
+        let run = run_trend(
+            &m.candles,
+            cfg,
+            TrendRunConfig {
+                record: true,
+                ..run_cfg
             },
-            TrendPolicyParams { atr_stop_mult },
         );
+        write_csv(&args.equity_out, &run.equity_rows).context("write best equity csv failed")?;
+        report = report.artifact("equity_csv", &args.equity_out);
+    }
 
-        if decision.action == TrendAction::EnterLong {
-            let bos_gate_ok = match cfg.entry_gate {
-                EntryGate::Trend => true,
-                EntryGate::TrendBos => bos.state == BosState::Confirmed,
-                EntryGate::TrendBosPullback => bos.state == BosState::Confirmed && pullback.triggered,
-            };
-            let trend_gap_bps = if c.close.0 > 0.0 {
-                ((fast - slow) / c.close.0) * 10_000.0
+    // при нескольких символах — `rankN_<SYMBOL>_*`
+    let dir = args.top_artifacts_dir.trim_end_matches('/');
+    for (idx, (cfg, _)) in results.iter().take(args.top_artifacts).enumerate() {
+        let rank = idx + 1;
+        for m in &markets {
+            let tag = if multi {
+                format!("rank{rank}_{}", m.symbol)
             } else {
-                0.0
+                format!("rank{rank}")
             };
-            let trend_gap_ok = trend_gap_bps >= cfg.min_trend_gap_bps.max(0.0);
-            let cooldown_ok = bars_since_exit >= cfg.cooldown_bars;
-            let atr_pct = if c.close.0 > 0.0 {
-                100.0 * atr.0 / c.close.0
-            } else {
-                0.0
-            };
-            let atr_ok = atr_pct <= cfg.max_atr_pct.max(0.0);
-            let gate_ok = bos_gate_ok && trend_gap_ok && cooldown_ok && atr_ok;
-
-            if !gate_ok {
-                decision = match trend_mode_from_state(trend_state) {
-                    TrendMode::Flat => policy::trend_policy::TrendPolicyDecision {
-                        next_mode: TrendMode::Flat,
-                        action: TrendAction::HoldFlat,
-                        reason: TrendDecisionReason::NoSignal,
-                    },
-                    TrendMode::Long => policy::trend_policy::TrendPolicyDecision {
-                        next_mode: TrendMode::Long,
-                        action: TrendAction::HoldLong,
-                        reason: TrendDecisionReason::NoSignal,
-                    },
-                };
-            }
+            let run = run_trend(
+                &m.candles,
+                cfg,
+                TrendRunConfig {
+                    record: true,
+                    ..run_cfg
+                },
+            );
+            let equity_out = format!("{dir}/{tag}_equity.csv");
+            let trades_out = format!("{dir}/{tag}_trades.csv");
+            let round_trips_out = format!("{dir}/{tag}_round_trips.csv");
+            write_csv(&equity_out, &run.equity_rows).context("write top equity csv failed")?;
+            write_csv(&trades_out, &run.trade_rows).context("write top trades csv failed")?;
+            write_csv(&round_trips_out, &run.round_trips)
+                .context("write top round trips csv failed")?;
+            report = report
+                .artifact(&format!("{tag}_equity_csv"), &equity_out)
+                .artifact(&format!("{tag}_trades_csv"), &trades_out)
+                .artifact(&format!("{tag}_round_trips_csv"), &round_trips_out);
         }
+    }
+    if let Some(best) = best {
+        report = report.with_costs(best.costs);
+    }
+    report.finish(args.report_out.as_deref())?;
 
-        match decision.action {
-            TrendAction::EnterLong => {
-                if quote.0 > 0.0 {
-                    let qty = exec.buy_qty_for_quote(quote.0, c.close);
-                    if qty.0 > 0.0 {
-                        let cost = exec.buy_cost(qty, c.close);
-                        quote = Money((quote.0 - cost).max(0.0));
-                        base = Qty(base.0 + qty.0);
-                        entry_price = Some(c.close);
-                        entry_cost_quote = Some(cost);
-                        trades += 1;
-                    }
-                }
-                if let Ok(next) = trend_transition(trend_state, TrendCause::EntrySignal) {
-                    trend_state = next;
-                }
-            }
-            TrendAction::ExitLong => {
-                if base.0 > 0.0 {
-                    let proceeds = exec.sell_proceeds(base, c.close);
-                    if let Some(cost) = entry_cost_quote {
-                        let trade_pnl = proceeds - cost;
-                        closed_trades += 1;
-                        if trade_pnl > 0.0 {
-                            winning_trades += 1;
-                            gross_profit += trade_pnl;
-                        } else if trade_pnl < 0.0 {
-                            gross_loss += -trade_pnl;
-                        }
-                    }
+    Ok(())
+}
 
-                    quote = Money(quote.0 + proceeds);
-                    base = Qty(0.0);
-                    entry_price = None;
-                    entry_cost_quote = None;
-                    bars_since_exit = 0;
-                    trades += 1;
-                }
-                let cause = match decision.reason {
-                    TrendDecisionReason::AtrStopHit => {
-                        stop_exits += 1;
-                        TrendCause::StopLossHit
-                    }
-                    TrendDecisionReason::InvalidLongOnlyInvariant => TrendCause::ForceFlat,
-                    _ => TrendCause::ExitSignal,
-                };
-                if let Ok(next) = trend_transition(trend_state, cause) {
-                    trend_state = next;
-                }
+/// Каждый конфиг сетки на обучении и проверке каждого окна (среднее по символам)
+fn walk_forward(
+    args: &Args,
+    wf: WalkForward,
+    range: (i64, i64),
+    markets: &[Market],
+    grid: &[TrendParams],
+    run_cfg: TrendRunConfig,
+) -> Result<()> {
+    let windows = wf.windows(range)?;
+    for w in &windows {
+        for m in markets {
+            let (train, test) = (slice_ms(&m.candles, w.train), slice_ms(&m.candles, w.test));
+            if train.len() < 120 || test.len() < 20 {
+                anyhow::bail!(
+                    "not enough candles in walk-forward window {} for {}: train={} test={}",
+                    w.index,
+                    m.symbol,
+                    train.len(),
+                    test.len()
+                );
             }
-            TrendAction::HoldFlat | TrendAction::HoldLong => {}
-        }
-
-        let equity = quote.0 + base.0 * c.close.0;
-        max_equity = max_equity.max(equity);
-        if max_equity > 0.0 {
-            let dd = (max_equity - equity) / max_equity;
-            max_drawdown = max_drawdown.max(dd);
         }
     }
+    let run = |period: (i64, i64), cfg: &TrendParams| {
+        let perf: Vec<Performance> = markets
+            .iter()
+            .map(|m| {
+                run_trend(slice_ms(&m.candles, period), cfg, run_cfg)
+                    .report
+                    .perf
+            })
+            .collect();
+        Performance::mean(&perf)
+    };
 
-    if force_close_at_end && base.0 > 0.0 {
-        let final_mark = feed.mid().unwrap_or(Price(0.0));
-        let proceeds = exec.sell_proceeds(base, final_mark);
-        if let Some(cost) = entry_cost_quote {
-            let trade_pnl = proceeds - cost;
-            closed_trades += 1;
-            if trade_pnl > 0.0 {
-                winning_trades += 1;
-                gross_profit += trade_pnl;
-            } else if trade_pnl < 0.0 {
-                gross_loss += -trade_pnl;
-            }
+    let mut progress = Progress::new(
+        "walk-forward",
+        "configs",
+        windows.len() * grid.len(),
+        args.quiet,
+    );
+    let pool = pool(args.jobs)?;
+    let mut train = Vec::with_capacity(windows.len());
+    let mut test = Vec::with_capacity(windows.len());
+    for (idx, w) in windows.iter().enumerate() {
+        let done = idx * grid.len();
+        let perfs = par_map(
+            &pool,
+            grid,
+            args.progress_every,
+            |cfg| (run(w.train, cfg), run(w.test, cfg)),
+            |n| progress.set(done + n),
+        );
+        for (cfg, (perf, _)) in grid.iter().zip(&perfs) {
+            progress.offer_best(perf, args.rank_by, || best_label(cfg));
         }
-        quote = Money(quote.0 + proceeds);
-        base = Qty(0.0);
-        trades += 1;
-        let _ = trend_transition(trend_state, TrendCause::ForceFlat);
+        let (train_w, test_w): (Vec<_>, Vec<_>) = perfs.into_iter().unzip();
+        train.push(train_w);
+        test.push(test_w);
+    }
+    progress.finish();
+
+    let labels: Vec<String> = grid.iter().map(best_label).collect();
+    let result = WfResult::new(&windows, &labels, &train, &test, args.rank_by);
+    write_table(&args.wf_windows_out, &result.window_rows)
+        .context("write walk-forward windows failed")?;
+    write_table(&args.wf_configs_out, &result.config_rows)
+        .context("write walk-forward configs failed")?;
+    println!(
+        "Walk-forward done: windows={} configs={} windows_csv={} configs_csv={}",
+        windows.len(),
+        grid.len(),
+        args.wf_windows_out,
+        args.wf_configs_out
+    );
+    for r in &result.window_rows {
+        println!(
+            "wf_window: {} test_from_ts={} train_roi={:.2}% oos_roi={:.2}% oos_dd={:.2}% {}",
+            r.window,
+            r.test_start_ms,
+            r.train_roi_pct,
+            r.test_roi_pct,
+            r.test_max_drawdown_pct,
+            r.config
+        );
+    }
+    let oos = &result.chained;
+    println!(
+        "wf_oos: roi={:.2}% mean_roi={:.2}% worst_dd={:.2}% positive={}/{}",
+        oos.compound_roi_pct,
+        oos.mean_roi_pct,
+        oos.worst_drawdown_pct,
+        oos.positive_windows,
+        oos.windows
+    );
+    if let Some(best) = result.config_rows.first() {
+        println!(
+            "wf_best_config: oos_roi={:.2}% worst_dd={:.2}% selected={}/{} {}",
+            best.compound_oos_roi_pct,
+            best.worst_oos_drawdown_pct,
+            best.selected,
+            best.windows,
+            best.config
+        );
     }
 
-    let final_mark = feed.mid().unwrap_or(Price(0.0));
-    let final_equity = quote.0 + base.0 * final_mark.0;
-    let pnl = final_equity - initial_quote;
-    let roi_pct = if initial_quote > 0.0 {
-        100.0 * pnl / initial_quote
-    } else {
-        0.0
-    };
-    let win_rate_pct = if closed_trades > 0 {
-        100.0 * (winning_trades as f64) / (closed_trades as f64)
-    } else {
-        0.0
-    };
-    let profit_factor = if gross_loss > 0.0 {
-        gross_profit / gross_loss
-    } else if gross_profit > 0.0 {
-        f64::INFINITY
-    } else {
-        0.0
+    let take_n = args.top_n.min(result.config_rows.len());
+    let metrics = WalkForwardMetrics {
+        configs: grid.len(),
+        oos,
+        windows: &windows,
+        best_configs: &result.config_rows[..take_n],
     };
-
-    BacktestReport {
-        trades,
-        closed_trades,
-        stop_exits,
-        win_rate_pct,
-        profit_factor,
-        max_drawdown_pct: max_drawdown * 100.0,
-        pnl,
-        roi_pct,
-    }
+    JsonReport::new("backtest_trend_sweep", args, metrics)
+        .artifact("wf_windows_csv", &args.wf_windows_out)
+        .artifact("wf_configs_csv", &args.wf_configs_out)
+        .finish(args.report_out.as_deref())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    if args.initial_quote <= 0.0 {
-        anyhow::bail!("initial_quote must be > 0");
-    }
-
+fn grid_configs(args: &Args, entry_gate_list: &[EntryGate]) -> Result<Vec<TrendParams>> {
     let ema_fast_list: Vec<usize> = parse_num_list(&args.ema_fast_list, "ema_fast_list")?;
     let ema_slow_list: Vec<usize> = parse_num_list(&args.ema_slow_list, "ema_slow_list")?;
-    let entry_gate_list = parse_gate_list(&args.entry_gate_list)?;
     let min_trend_gap_bps_list: Vec<f64> =
         parse_num_list(&args.min_trend_gap_bps_list, "min_trend_gap_bps_list")?;
     let cooldown_bars_list: Vec<usize> =
         parse_num_list(&args.cooldown_bars_list, "cooldown_bars_list")?;
     let max_atr_pct_list: Vec<f64> = parse_num_list(&args.max_atr_pct_list, "max_atr_pct_list")?;
 
-    let start_ms = date_to_ms(&args.start)?;
-    let end_ms = date_to_ms(&args.end)? + 24 * 60 * 60 * 1000 - 1;
-
-    let candles = if !args.refresh && std::path::Path::new(&args.cache).exists() {
-        read_cache(&args.cache).context("read cache failed")?
-    } else {
-        let api = BybitRest::new();
-        let data = download_range(&api, &args.symbol, &args.interval, start_ms, end_ms)
-            .await
-            .context("download range failed")?;
-        write_cache(&args.cache, &data).context("write cache failed")?;
-        data
-    };
-
-    if candles.len() < 120 {
-        anyhow::bail!("not enough candles: {}", candles.len());
-    }
-
-    let exec = ExecutionModel {
-        fee_bps: args.fee_bps,
-        spread_bps: args.spread_bps,
-        slippage_bps: args.slippage_bps,
-    };
-
-    let mut results: Vec<(SweepConfig, BacktestReport)> = Vec::new();
+    let mut out = Vec::new();
     for &ema_fast in &ema_fast_list {
         for &ema_slow in &ema_slow_list {
             if ema_fast >= ema_slow {
                 continue;
             }
-            for &entry_gate in &entry_gate_list {
+            for &entry_gate in entry_gate_list {
                 for &min_trend_gap_bps in &min_trend_gap_bps_list {
                     for &cooldown_bars in &cooldown_bars_list {
                         for &max_atr_pct in &max_atr_pct_list {
-                            let cfg = SweepConfig {
+                            out.push(TrendParams {
                                 ema_fast,
                                 ema_slow,
+                                atr_stop_mult: args.atr_stop_mult,
                                 entry_gate,
                                 min_trend_gap_bps,
                                 cooldown_bars,
                                 max_atr_pct,
-                            };
-                            let report = run_backtest(
-                                &candles,
-                                cfg,
-                                args.atr_stop_mult,
-                                exec,
-                                args.initial_quote,
-                                args.force_close_at_end,
-                            );
-                            results.push((cfg, report));
+                            });
                         }
                     }
                 }
             }
         }
     }
+    Ok(out)
+}
 
-    results.sort_by(|a, b| {
-        b.1.roi_pct
-            .partial_cmp(&a.1.roi_pct)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then(
-                a.1.max_drawdown_pct
-                    .partial_cmp(&b.1.max_drawdown_pct)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-            )
-            .then(
-                b.1.profit_factor
-                    .partial_cmp(&a.1.profit_factor)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-            )
-    });
-
-    let take_n = args.top_n.min(results.len());
-    let mut rows = Vec::with_capacity(take_n);
-    for (idx, (cfg, rep)) in results.iter().take(take_n).enumerate() {
-        rows.push(SummaryRow {
-            rank: idx + 1,
-            ema_fast: cfg.ema_fast,
-            ema_slow: cfg.ema_slow,
-            entry_gate: format!("{:?}", cfg.entry_gate),
-            min_trend_gap_bps: cfg.min_trend_gap_bps,
-            cooldown_bars: cfg.cooldown_bars,
-            max_atr_pct: cfg.max_atr_pct,
-            trades: rep.trades,
-            closed_trades: rep.closed_trades,
-            stop_exits: rep.stop_exits,
-            win_rate_pct: rep.win_rate_pct,
-            profit_factor: rep.profit_factor,
-            max_drawdown_pct: rep.max_drawdown_pct,
-            pnl: rep.pnl,
-            roi_pct: rep.roi_pct,
-        });
-    }
+/// Имена измерений [`search_dims`] для отчёта стабильности
+const DIM_NAMES: [&str; 6] = [
+    "ema_fast",
+    "ema_slow",
+    "entry_gate",
+    "min_trend_gap_bps",
+    "cooldown_bars",
+    "max_atr_pct",
+];
+
+/// Порядок измерений — как в [`params_at`]; gate кодируется индексом в списке
+fn search_dims(args: &Args, gates: usize) -> Result<Vec<Dim>> {
+    Ok(vec![
+        Dim::parse(&args.ema_fast_list, "ema_fast_list", true)?,
+        Dim::parse(&args.ema_slow_list, "ema_slow_list", true)?,
+        Dim::Values((0..gates).map(|i| i as f64).collect()),
+        Dim::parse(
+            &args.min_trend_gap_bps_list,
+            "min_trend_gap_bps_list",
+            false,
+        )?,
+        Dim::parse(&args.cooldown_bars_list, "cooldown_bars_list", true)?,
+        Dim::parse(&args.max_atr_pct_list, "max_atr_pct_list", false)?,
+    ])
+}
 
-    write_summary(&args.summary_out, &rows).context("write summary failed")?;
-    println!(
-        "Sweep done: tested={} top_saved={} summary={}",
-        results.len(),
-        rows.len(),
-        args.summary_out
-    );
-    if let Some(best) = rows.first() {
-        println!(
-            "Best: rank={} gate={} ema={}/{} gap_bps={:.2} cooldown={} max_atr_pct={:.2} roi={:.2}% pf={:.4} dd={:.2}%",
-            best.rank,
-            best.entry_gate,
-            best.ema_fast,
-            best.ema_slow,
-            best.min_trend_gap_bps,
-            best.cooldown_bars,
-            best.max_atr_pct,
-            best.roi_pct,
-            best.profit_factor,
-            best.max_drawdown_pct
-        );
+fn params_at(args: &Args, entry_gate_list: &[EntryGate], p: &[f64]) -> TrendParams {
+    TrendParams {
+        ema_fast: p[0] as usize,
+        ema_slow: p[1] as usize,
+        atr_stop_mult: args.atr_stop_mult,
+        entry_gate: entry_gate_list[p[2] as usize],
+        min_trend_gap_bps: p[3],
+        cooldown_bars: p[4] as usize,
+        max_atr_pct: p[5],
     }
+}
 
-    Ok(())
+/// Обратное к [`params_at`]
+fn values_of(cfg: &TrendParams, entry_gate_list: &[EntryGate]) -> Vec<f64> {
+    let gate = entry_gate_list
+        .iter()
+        .position(|g| *g == cfg.entry_gate)
+        .unwrap_or(0);
+    vec![
+        cfg.ema_fast as f64,
+        cfg.ema_slow as f64,
+        gate as f64,
+        cfg.min_trend_gap_bps,
+        cfg.cooldown_bars as f64,
+        cfg.max_atr_pct,
+    ]
 }
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+
+use backtest::config::parse_args;
+use backtest::data::{
+    CANDLE_STORE_ENV, StoreLoad, Venue, date_range_ms, load_from_local, load_from_store,
+    open_local_store, parse_interval_ms, parse_num_list, parse_symbols,
+};
+use backtest::quality::{QualityReport, check_candles};
+use backtest::report::{JsonReport, write_csv};
+use data::store::CandleStore as LocalStore;
+use storage::postgres::CandleStore;
+
+/// Предзагрузка свечей в общее хранилище (`CANDLE_STORE_URL`, без него — в локальное
+/// `CANDLE_DB`) с проверкой качества: тяжёлые загрузки идут отдельным run'ом,
+/// sweep'ы ссылаются на него через `depends_on`.
+#[derive(Parser, Debug, Serialize)]
+struct Args {
+    /// Символы через запятую
+    #[arg(long, alias = "symbol")]
+    symbols: String,
+    /// Интервалы в минутах через запятую (HTF и LTF MTF backtest'ов)
+    #[arg(long, default_value = "5,1")]
+    intervals: String,
+    #[arg(long)]
+    start: String,
+    #[arg(long)]
+    end: String,
+    /// Перекачать весь диапазон, а не только недостающие куски
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+    /// Биржа свечей: bybit | binance (binance — только в локальное хранилище)
+    #[arg(long, value_enum, default_value_t = Venue::Bybit)]
+    exchange: Venue,
+    /// Допустимая доля пропущенных свечей (пропуски биржи), %
+    #[arg(long, default_value_t = 1.0)]
+    max_missing_pct: f64,
+
+    /// Качество каждой серии symbol × interval
+    #[arg(long, default_value = "data/data_download_quality.csv")]
+    quality_out: String,
+    /// JSON отчёт: конфиг, качество серий с пропусками, артефакты
+    #[arg(long)]
+    report_out: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SeriesRow {
+    symbol: String,
+    interval: String,
+    downloaded_ranges: usize,
+    candles: usize,
+    expected: usize,
+    missing: usize,
+    missing_pct: f64,
+    gaps: usize,
+    duplicates: usize,
+    unsorted: usize,
+    zero_prices: usize,
+    invalid_ohlc: usize,
+    ok: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Series {
+    symbol: String,
+    interval: String,
+    downloaded_ranges: usize,
+    ok: bool,
+    quality: QualityReport,
+}
+
+#[derive(Debug, Serialize)]
+struct Metrics<'a> {
+    series_total: usize,
+    series_failed: usize,
+    candles: usize,
+    downloaded_ranges: usize,
+    series: &'a [Series],
+}
+
+/// Куда докачиваются свечи
+enum Store {
+    Shared(CandleStore),
+    Local(LocalStore),
+}
+
+impl Store {
+    async fn load(
+        &self,
+        args: &Args,
+        symbol: &str,
+        interval: &str,
+        range: (i64, i64),
+    ) -> Result<StoreLoad> {
+        match self {
+            Store::Shared(s) => load_from_store(s, symbol, interval, range, args.refresh).await,
+            Store::Local(s) => {
+                load_from_local(s, args.exchange, symbol, interval, range, args.refresh).await
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = parse_args()?;
+    let symbols = parse_symbols(&args.symbols)?;
+    let intervals = parse_num_list::<String>(&args.intervals, "intervals")?;
+    for interval in &intervals {
+        parse_interval_ms(interval)?;
+    }
+    if !(0.0..=100.0).contains(&args.max_missing_pct) {
+        anyhow::bail!("max_missing_pct must be in [0, 100]");
+    }
+    let range = date_range_ms(&args.start, &args.end)?;
+
+    let url = std::env::var(CANDLE_STORE_ENV)
+        .ok()
+        .filter(|v| !v.is_empty());
+    let store = match url {
+        Some(url) if args.exchange == Venue::Bybit => {
+            Store::Shared(CandleStore::connect(&url).await?)
+        }
+        _ => Store::Local(open_local_store().await?),
+    };
+
+    let mut series = Vec::new();
+    for symbol in &symbols {
+        for interval in &intervals {
+            let load = store.load(&args, symbol, interval, range).await?;
+            let quality = check_candles(&load.candles, parse_interval_ms(interval)?, range);
+            let ok = !quality.has_errors() && quality.missing_pct() <= args.max_missing_pct;
+            println!(
+                "series: symbol={} interval={}m downloaded_ranges={} ok={} {}",
+                symbol,
+                interval,
+                load.downloaded_ranges,
+                ok,
+                quality.summary()
+            );
+            for g in quality.gaps.iter().take(5) {
+                println!(
+                    "gap: symbol={} interval={}m from_ts={} to_ts={} missing={}",
+                    symbol, interval, g.from_ts, g.to_ts, g.missing
+                );
+            }
+            series.push(Series {
+                symbol: symbol.clone(),
+                interval: interval.clone(),
+                downloaded_ranges: load.downloaded_ranges,
+                ok,
+                quality,
+            });
+        }
+    }
+
+    let rows: Vec<SeriesRow> = series
+        .iter()
+        .map(|s| SeriesRow {
+            symbol: s.symbol.clone(),
+            interval: s.interval.clone(),
+            downloaded_ranges: s.downloaded_ranges,
+            candles: s.quality.candles,
+            expected: s.quality.expected,
+            missing: s.quality.missing,
+            missing_pct: s.quality.missing_pct(),
+            gaps: s.quality.gaps.len(),
+            duplicates: s.quality.duplicates,
+            unsorted: s.quality.unsorted,
+            zero_prices: s.quality.zero_prices,
+            invalid_ohlc: s.quality.invalid_ohlc,
+            ok: s.ok,
+        })
+        .collect();
+    write_csv(&args.quality_out, &rows).context("write quality csv failed")?;
+
+    let series_failed = series.iter().filter(|s| !s.ok).count();
+    let candles: usize = series.iter().map(|s| s.quality.candles).sum();
+    let downloaded_ranges: usize = series.iter().map(|s| s.downloaded_ranges).sum();
+    println!("Data download finished");
+    println!(
+        "series_total={} series_failed={} candles={} downloaded_ranges={}",
+        series.len(),
+        series_failed,
+        candles,
+        downloaded_ranges
+    );
+    JsonReport::new(
+        "data_download",
+        &args,
+        Metrics {
+            series_total: series.len(),
+            series_failed,
+            candles,
+            downloaded_ranges,
+            series: &series,
+        },
+    )
+    .artifact("quality_csv", &args.quality_out)
+    .finish(args.report_out.as_deref())?;
+
+    if series_failed > 0 {
+        anyhow::bail!(
+            "{} of {} series failed validation",
+            series_failed,
+            series.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use bybit::ws::MarketEvent;
+use core::types::{Price, Qty, TimestampMs};
+use structure::candle::Candle;
+
+/// Собирает OHLCV свечи произвольного интервала из сделок.
+///
+/// `ts` свечи — начало интервала (как у Bybit kline). Свеча закрывается,
+/// когда приходит сделка из следующего интервала или `flush` видит,
+/// что интервал истёк. Интервалы без сделок (при `fill_gaps`) —
+/// плоские свечи по последнему close с нулевым объёмом.
+#[derive(Debug, Clone)]
+pub struct CandleBuilder {
+    pub interval_ms: i64,
+    pub fill_gaps: bool,
+    current: Option<Candle>,
+    /// ts и close последней закрытой свечи
+    last_closed: Option<(i64, Price)>,
+}
+
+impl CandleBuilder {
+    pub fn new(interval_ms: i64, fill_gaps: bool) -> Self {
+        Self {
+            interval_ms: interval_ms.max(1),
+            fill_gaps,
+            current: None,
+            last_closed: None,
+        }
+    }
+
+    fn bucket(&self, ts: i64) -> i64 {
+        ts - ts.rem_euclid(self.interval_ms)
+    }
+
+    /// Текущая (ещё не закрытая) свеча
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    /// Учитывает сделку, возвращает закрытые свечи (в порядке времени).
+    /// Сделки из уже закрытых интервалов игнорируются.
+    pub fn on_trade(&mut self, ts: TimestampMs, price: Price, qty: Qty) -> Vec<Candle> {
+        let bucket = self.bucket(ts.0);
+        let mut closed = Vec::new();
+
+        match self.current.as_mut() {
+            Some(c) if bucket < c.ts.0 => return closed,
+            None if self.last_closed.is_some_and(|(ts, _)| bucket <= ts) => return closed,
+            Some(c) if bucket == c.ts.0 => {
+                c.high = Price(c.high.0.max(price.0));
+                c.low = Price(c.low.0.min(price.0));
+                c.close = price;
+                c.volume = Qty(c.volume.0 + qty.0);
+                return closed;
+            }
+            _ => {}
+        }
+
+        closed.extend(self.close_until(bucket));
+        self.current = Some(Candle {
+            ts: TimestampMs(bucket),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: qty,
+        });
+        closed
+    }
+
+    /// Закрывает свечи, интервал которых закончился к `now`
+    pub fn flush(&mut self, now: TimestampMs) -> Vec<Candle> {
+        let bucket = self.bucket(now.0);
+        match self.current {
+            Some(c) if c.ts.0 >= bucket => Vec::new(),
+            _ => self.close_until(bucket),
+        }
+    }
+
+    /// Закрывает текущую свечу и (при fill_gaps) пустые интервалы до `bucket`
+    fn close_until(&mut self, bucket: i64) -> Vec<Candle> {
+        let mut closed = Vec::new();
+        if let Some(c) = self.current.take() {
+            self.last_closed = Some((c.ts.0, c.close));
+            closed.push(c);
+        }
+
+        if !self.fill_gaps {
+            return closed;
+        }
+        if let Some((last_ts, close)) = self.last_closed {
+            let mut ts = last_ts + self.interval_ms;
+            while ts < bucket {
+                closed.push(Candle {
+                    ts: TimestampMs(ts),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: Qty(0.0),
+                });
+                self.last_closed = Some((ts, close));
+                ts += self.interval_ms;
+            }
+        }
+
+        closed
+    }
+}
+
+/// HTF + LTF свечи из потока сделок вместо kline-топиков биржи.
+/// Готовые свечи отдаются как `MarketEvent::Candle` с интервалом в минутах,
+/// чтобы цикл engine обрабатывал их так же, как биржевые.
+#[derive(Debug, Clone)]
+pub struct LocalCandles {
+    pub htf: CandleBuilder,
+    pub ltf: CandleBuilder,
+}
+
+impl LocalCandles {
+    pub fn new(htf_interval_ms: i64, ltf_interval_ms: i64, fill_gaps: bool) -> Self {
+        Self {
+            htf: CandleBuilder::new(htf_interval_ms, fill_gaps),
+            ltf: CandleBuilder::new(ltf_interval_ms, fill_gaps),
+        }
+    }
+
+    fn events(b: &CandleBuilder, candles: Vec<Candle>) -> impl Iterator<Item = MarketEvent> {
+        let interval = (b.interval_ms / 60_000).to_string();
+        candles.into_iter().map(move |candle| MarketEvent::Candle {
+            interval: interval.clone(),
+            candle,
+        })
+    }
+
+    /// Биржевые свечи отбрасываются, сделки превращаются в свечи
+    /// (и сами проходят дальше), остальное — как есть.
+    pub fn on_event(&mut self, ev: MarketEvent) -> Vec<MarketEvent> {
+        match ev {
+            MarketEvent::Candle { .. } => Vec::new(),
+            MarketEvent::Trade { ts, price, qty } => {
+                let ltf = self.ltf.on_trade(ts, price, qty);
+                let mut out: Vec<MarketEvent> = Self::events(&self.ltf, ltf).collect();
+                let htf = self.htf.on_trade(ts, price, qty);
+                out.extend(Self::events(&self.htf, htf));
+                out.push(ev);
+                out
+            }
+            other => vec![other],
+        }
+    }
+
+    pub fn flush(&mut self, now: TimestampMs) -> Vec<MarketEvent> {
+        let ltf = self.ltf.flush(now);
+        let mut out: Vec<MarketEvent> = Self::events(&self.ltf, ltf).collect();
+        let htf = self.htf.flush(now);
+        out.extend(Self::events(&self.htf, htf));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(b: &mut CandleBuilder, ts: i64, price: f64) -> Vec<Candle> {
+        b.on_trade(TimestampMs(ts), Price(price), Qty(1.0))
+    }
+
+    #[test]
+    fn builds_ohlcv_and_closes_on_next_interval() {
+        let mut b = CandleBuilder::new(60_000, false);
+        assert!(trade(&mut b, 1_000, 100.0).is_empty());
+        assert!(trade(&mut b, 2_000, 105.0).is_empty());
+        assert!(trade(&mut b, 3_000, 98.0).is_empty());
+        assert!(trade(&mut b, 59_999, 101.0).is_empty());
+
+        let closed = trade(&mut b, 60_000, 102.0);
+        assert_eq!(closed.len(), 1);
+        let c = closed[0];
+        assert_eq!(c.ts, TimestampMs(0));
+        assert_eq!(
+            (c.open, c.high, c.low, c.close),
+            (Price(100.0), Price(105.0), Price(98.0), Price(101.0))
+        );
+        assert_eq!(c.volume, Qty(4.0));
+
+        // опоздавшая сделка из закрытого интервала
+        assert!(trade(&mut b, 30_000, 1.0).is_empty());
+        assert_eq!(b.current().unwrap().low, Price(102.0));
+    }
+
+    #[test]
+    fn flush_closes_expired_interval_and_fills_gaps() {
+        let mut b = CandleBuilder::new(60_000, true);
+        trade(&mut b, 10_000, 100.0);
+
+        assert!(b.flush(TimestampMs(59_000)).is_empty());
+
+        let closed = b.flush(TimestampMs(185_000));
+        assert_eq!(closed.len(), 3);
+        assert_eq!(closed[2].ts, TimestampMs(120_000));
+        assert_eq!(closed[2].close, Price(100.0));
+        assert_eq!(closed[2].volume, Qty(0.0));
+        assert!(b.current().is_none());
+
+        // после flush пропуск продолжает заполняться до новой сделки
+        let closed = trade(&mut b, 250_000, 110.0);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].ts, TimestampMs(180_000));
+        assert!(trade(&mut b, 170_000, 1.0).is_empty());
+    }
+}
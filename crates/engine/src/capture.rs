@@ -0,0 +1,317 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+
+use bybit::ws::MarketEvent;
+use core::types::{Price, Qty, TimestampMs};
+use structure::candle::Candle;
+
+use crate::clock::{Clock, ManualClock};
+
+/// Одна строка capture-файла (JSON lines).
+///
+/// `recv_ts` — время получения события engine'ом, по нему replay
+/// восстанавливает темп. `warmup` — свечи из REST-догрузки: при replay
+/// они идут в warm-up, а не в основной цикл.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    pub recv_ts: i64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warmup: bool,
+    #[serde(flatten)]
+    pub event: CapturedEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CapturedEvent {
+    Candle {
+        interval: String,
+        candle: CandleRow,
+    },
+    /// Файлы, записанные до настраиваемых интервалов (только чтение)
+    Candle5m(CandleRow),
+    Candle1m(CandleRow),
+    Ticker {
+        mid: f64,
+    },
+    BookTop {
+        bid: f64,
+        ask: f64,
+    },
+    Trade {
+        ts: i64,
+        price: f64,
+        qty: f64,
+    },
+    Reconnected {
+        attempt: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CandleRow {
+    pub ts: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl From<&Candle> for CandleRow {
+    fn from(c: &Candle) -> Self {
+        Self {
+            ts: c.ts.0,
+            open: c.open.0,
+            high: c.high.0,
+            low: c.low.0,
+            close: c.close.0,
+            volume: c.volume.0,
+        }
+    }
+}
+
+impl From<CandleRow> for Candle {
+    fn from(r: CandleRow) -> Self {
+        Candle {
+            ts: TimestampMs(r.ts),
+            open: Price(r.open),
+            high: Price(r.high),
+            low: Price(r.low),
+            close: Price(r.close),
+            volume: Qty(r.volume),
+        }
+    }
+}
+
+impl From<&MarketEvent> for CapturedEvent {
+    fn from(ev: &MarketEvent) -> Self {
+        match ev {
+            MarketEvent::Candle { interval, candle } => CapturedEvent::Candle {
+                interval: interval.clone(),
+                candle: candle.into(),
+            },
+            MarketEvent::Ticker { mid } => CapturedEvent::Ticker { mid: mid.0 },
+            MarketEvent::BookTop { bid, ask } => CapturedEvent::BookTop {
+                bid: bid.0,
+                ask: ask.0,
+            },
+            MarketEvent::Trade { ts, price, qty } => CapturedEvent::Trade {
+                ts: ts.0,
+                price: price.0,
+                qty: qty.0,
+            },
+            MarketEvent::Reconnected { attempt } => {
+                CapturedEvent::Reconnected { attempt: *attempt }
+            }
+        }
+    }
+}
+
+impl From<CapturedEvent> for MarketEvent {
+    fn from(ev: CapturedEvent) -> Self {
+        match ev {
+            CapturedEvent::Candle { interval, candle } => MarketEvent::Candle {
+                interval,
+                candle: candle.into(),
+            },
+            CapturedEvent::Candle5m(r) => MarketEvent::Candle {
+                interval: "5".into(),
+                candle: r.into(),
+            },
+            CapturedEvent::Candle1m(r) => MarketEvent::Candle {
+                interval: "1".into(),
+                candle: r.into(),
+            },
+            CapturedEvent::Ticker { mid } => MarketEvent::Ticker { mid: Price(mid) },
+            CapturedEvent::BookTop { bid, ask } => MarketEvent::BookTop {
+                bid: Price(bid),
+                ask: Price(ask),
+            },
+            CapturedEvent::Trade { ts, price, qty } => MarketEvent::Trade {
+                ts: TimestampMs(ts),
+                price: Price(price),
+                qty: Qty(qty),
+            },
+            CapturedEvent::Reconnected { attempt } => MarketEvent::Reconnected { attempt },
+        }
+    }
+}
+
+/// Пишет market data в `<dir>/<symbol>-<YYYYmmdd-HHMMSS>.jsonl`
+pub struct CaptureWriter {
+    path: String,
+    out: BufWriter<File>,
+}
+
+impl CaptureWriter {
+    pub fn create(dir: &str, symbol: &str, now: TimestampMs) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let stamp = chrono::DateTime::from_timestamp_millis(now.0)
+            .context("invalid capture timestamp")?
+            .format("%Y%m%d-%H%M%S");
+        let path = Path::new(dir)
+            .join(format!("{}-{}.jsonl", symbol, stamp))
+            .to_string_lossy()
+            .into_owned();
+        let f = File::create(&path).with_context(|| format!("failed to create {}", path))?;
+        Ok(Self {
+            path,
+            out: BufWriter::new(f),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn write(&mut self, recv_ts: TimestampMs, ev: &MarketEvent) -> Result<()> {
+        self.write_record(&CaptureRecord {
+            recv_ts: recv_ts.0,
+            warmup: false,
+            event: ev.into(),
+        })
+    }
+
+    /// Свечи warm-up из REST
+    pub fn write_warmup(&mut self, recv_ts: TimestampMs, ev: &MarketEvent) -> Result<()> {
+        self.write_record(&CaptureRecord {
+            recv_ts: recv_ts.0,
+            warmup: true,
+            event: ev.into(),
+        })
+    }
+
+    fn write_record(&mut self, r: &CaptureRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.out, r)?;
+        self.out.write_all(b"\n")?;
+        // capture нужен как раз когда процесс падает — не держим в буфере
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+pub fn read_capture(path: &str) -> Result<Vec<CaptureRecord>> {
+    let f = File::open(path).with_context(|| format!("failed to open capture {}", path))?;
+    let mut out = Vec::new();
+    for (i, line) in BufReader::new(f).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let r: CaptureRecord = serde_json::from_str(&line)
+            .with_context(|| format!("bad capture line {} in {}", i + 1, path))?;
+        out.push(r);
+    }
+    Ok(out)
+}
+
+/// Отдаёт записанные события в канал engine'а вместо WS.
+///
+/// Время engine (`engine_clock`) выставляется в `recv_ts` каждого события,
+/// темп задаёт `pacer`: пауза = разница `recv_ts` / `speed`.
+/// `speed <= 0` — без пауз, так быстро как engine успевает.
+pub async fn replay(
+    records: Vec<CaptureRecord>,
+    tx: Sender<MarketEvent>,
+    engine_clock: ManualClock,
+    pacer: &dyn Clock,
+    speed: f64,
+) {
+    let mut prev_ts: Option<i64> = None;
+
+    for r in records {
+        if r.warmup {
+            continue;
+        }
+
+        if let Some(prev) = prev_ts {
+            let gap_ms = (r.recv_ts - prev).max(0);
+            if speed > 0.0 && gap_ms > 0 {
+                pacer
+                    .sleep(Duration::from_millis((gap_ms as f64 / speed) as u64))
+                    .await;
+            }
+        }
+        prev_ts = Some(r.recv_ts);
+
+        engine_clock.set(TimestampMs(r.recv_ts));
+        if tx.send(r.event.into()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(interval: &str, ts: i64, close: f64) -> MarketEvent {
+        MarketEvent::Candle {
+            interval: interval.into(),
+            candle: Candle {
+                ts: TimestampMs(ts),
+                open: Price(close),
+                high: Price(close),
+                low: Price(close),
+                close: Price(close),
+                volume: Qty(1.0),
+            },
+        }
+    }
+
+    #[test]
+    fn capture_round_trips_and_replays_in_order() {
+        let dir = std::env::temp_dir().join(format!("mmbot-capture-{}", std::process::id()));
+        let dir_s = dir.to_str().unwrap();
+
+        let mut w = CaptureWriter::create(dir_s, "ETHUSDT", TimestampMs(0)).unwrap();
+        assert!(w.path().ends_with("ETHUSDT-19700101-000000.jsonl"));
+        w.write_warmup(TimestampMs(5), &candle("15", 0, 99.0))
+            .unwrap();
+        w.write(TimestampMs(10), &MarketEvent::Ticker { mid: Price(100.0) })
+            .unwrap();
+        w.write(TimestampMs(20), &candle("3", 0, 101.0)).unwrap();
+        let path = w.path().to_string();
+        drop(w);
+
+        let records = read_capture(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].warmup);
+        assert_eq!(records[1].event, CapturedEvent::Ticker { mid: 100.0 });
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let engine_clock = ManualClock::new(TimestampMs(0));
+        let pacer = ManualClock::new(TimestampMs(0));
+        rt.block_on(replay(records, tx, engine_clock.clone(), &pacer, 0.0));
+
+        assert!(matches!(rx.try_recv(), Ok(MarketEvent::Ticker { .. })));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(MarketEvent::Candle { interval, .. }) if interval == "3"
+        ));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(engine_clock.now(), TimestampMs(20));
+
+        // старый формат: свеча 5m без interval
+        let old: CaptureRecord = serde_json::from_str(
+            r#"{"recv_ts":1,"type":"candle5m","ts":0,"open":1,"high":1,"low":1,"close":1,"volume":1}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            MarketEvent::from(old.event),
+            MarketEvent::Candle { interval, .. } if interval == "5"
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use core::types::TimestampMs;
+use tokio::sync::watch;
+
+pub type SleepFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Источник времени для engine.
+///
+/// Live-цикл работает от системных часов, а тесты и replay —
+/// от `ManualClock`, который двигается только явно.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> TimestampMs;
+    fn sleep(&self, d: Duration) -> SleepFuture;
+}
+
+/// Системные часы + tokio sleep
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> TimestampMs {
+        TimestampMs(chrono::Utc::now().timestamp_millis())
+    }
+
+    fn sleep(&self, d: Duration) -> SleepFuture {
+        Box::pin(tokio::time::sleep(d))
+    }
+}
+
+/// Ручные часы: время двигается только через `set` / `advance`.
+/// `sleep` завершается, когда часы дошли до дедлайна — как tokio
+/// с остановленным временем, но управляемо снаружи (тесты, replay).
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now_ms: Arc<watch::Sender<i64>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(TimestampMs(0))
+    }
+}
+
+impl ManualClock {
+    pub fn new(start: TimestampMs) -> Self {
+        let (tx, _rx) = watch::channel(start.0);
+        Self {
+            now_ms: Arc::new(tx),
+        }
+    }
+
+    pub fn set(&self, ts: TimestampMs) {
+        self.now_ms.send_replace(ts.0);
+    }
+
+    pub fn advance(&self, d: Duration) {
+        self.now_ms.send_modify(|t| *t += d.as_millis() as i64);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> TimestampMs {
+        TimestampMs(*self.now_ms.borrow())
+    }
+
+    fn sleep(&self, d: Duration) -> SleepFuture {
+        let deadline = self.now().0 + d.as_millis() as i64;
+        let mut rx = self.now_ms.subscribe();
+        Box::pin(async move {
+            let _ = rx.wait_for(|t| *t >= deadline).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_sleep_waits_for_advance() {
+        let clock = ManualClock::new(TimestampMs(1_000));
+        let shared = clock.clone();
+        assert_eq!(clock.now(), TimestampMs(1_000));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let sleep = clock.sleep(Duration::from_secs(2));
+            let waiter = tokio::spawn(sleep);
+
+            shared.advance(Duration::from_secs(1));
+            tokio::task::yield_now().await;
+            assert!(!waiter.is_finished());
+
+            shared.advance(Duration::from_secs(1));
+            waiter.await.unwrap();
+        });
+        assert_eq!(clock.now(), TimestampMs(3_000));
+
+        shared.set(TimestampMs(10));
+        assert_eq!(clock.now(), TimestampMs(10));
+    }
+}
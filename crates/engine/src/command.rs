@@ -0,0 +1,195 @@
+use core::types::{Price, Qty, TimestampMs};
+use mm::grid::Side;
+
+use crate::control::{AccountView, ControlCommand, ControlStatus, OrderView};
+use crate::event::EngineEvent;
+use crate::feed_quality::FeedMetrics;
+use crate::session::Session;
+use crate::tick::{clear_halt, tick};
+use crate::venue::Venue;
+
+impl Session {
+    /// Команда оператора из control API
+    pub async fn on_command(&mut self, cmd: ControlCommand, ts: TimestampMs) -> Vec<EngineEvent> {
+        let mut events = vec![EngineEvent::Log(format!("control: {:?}", cmd))];
+
+        match cmd {
+            ControlCommand::Pause | ControlCommand::Flatten => {
+                self.paused = true;
+                events.extend(self.cancel_grid().await);
+                if cmd == ControlCommand::Flatten {
+                    // до первой свечи нет ни цены, ни открытого учёта
+                    match self.last_mid.filter(|_| self.equity.is_some()) {
+                        Some(mark) => events.extend(self.flatten(ts, mark).await),
+                        None => {
+                            self.pending_flatten = true;
+                            events.push(EngineEvent::Alert {
+                                source: "control".into(),
+                                message: "flatten deferred: no price yet, base is sold on the first candle".into(),
+                            });
+                        }
+                    }
+                }
+            }
+            ControlCommand::Resume => {
+                self.paused = false;
+                self.pending_flatten = false;
+            }
+            ControlCommand::Unhalt => {
+                events.extend(clear_halt(&mut self.ctx, self.equity.as_mut()))
+            }
+            ControlCommand::Recenter => {
+                self.ctx.anchor.recenter();
+                // перестраиваем сетку сразу, не дожидаясь свечи
+                if let (Some(mut input), Some(mid), true, false) =
+                    (self.last_input, self.last_mid, self.run_mm, self.paused)
+                {
+                    input.ts = ts;
+                    input.mid = mid;
+                    input.inv = self.venue.inventory();
+                    let tick_events = tick(&mut self.ctx, input);
+                    events.extend(tick_events.iter().cloned());
+                    events.extend(self.execute(&tick_events, ts, mid).await);
+                }
+            }
+        }
+        events
+    }
+
+    /// Что видит оператор: под-счета, открытые ордера, equity по последней цене
+    pub fn control_status(
+        &self,
+        ts: TimestampMs,
+        symbol: &str,
+        mode: &str,
+        feed: FeedMetrics,
+    ) -> ControlStatus {
+        let view = |side: Side, price: Price, qty: Qty| OrderView {
+            side: format!("{:?}", side),
+            price: price.0,
+            qty: qty.0,
+        };
+        let (accounts, open_orders) = match &self.venue {
+            Venue::Paper(p) => (
+                [("mm", &p.mm), ("trend", &p.trend)]
+                    .into_iter()
+                    .filter_map(|(strategy, broker)| {
+                        broker.as_ref().map(|b| AccountView {
+                            strategy: strategy.into(),
+                            quote: b.ledger.quote,
+                            base: b.ledger.base,
+                        })
+                    })
+                    .collect(),
+                p.mm.as_ref()
+                    .map(|b| b.resting_orders())
+                    .unwrap_or(&self.ctx.desired)
+                    .iter()
+                    .map(|o| view(o.side, o.price, o.qty))
+                    .collect(),
+            ),
+            Venue::Live(m) => (
+                vec![AccountView {
+                    strategy: "mm".into(),
+                    quote: m.wallet.quote.0,
+                    base: m.wallet.base.0,
+                }],
+                m.orders
+                    .open_orders()
+                    .iter()
+                    .map(|o| view(o.side, o.price, o.qty))
+                    .collect(),
+            ),
+            Venue::Observe { .. } => (
+                Vec::new(),
+                self.ctx
+                    .desired
+                    .iter()
+                    .map(|o| view(o.side, o.price, o.qty))
+                    .collect(),
+            ),
+        };
+        ControlStatus {
+            ts_ms: ts.0,
+            symbol: symbol.to_string(),
+            mode: mode.to_string(),
+            state: format!("{:?}", self.ctx.state),
+            paused: self.paused,
+            last_mid: self.last_mid.map(|p| p.0),
+            equity: self
+                .equity
+                .as_ref()
+                .zip(self.last_mid)
+                .map(|(t, mid)| t.equity(mid).0),
+            accounts,
+            feed,
+            open_orders,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::tests::{block_on, candle, paper_session};
+    use crate::tick::tests::input;
+
+    #[test]
+    fn pause_cancels_grid_and_resume_clears_pause() {
+        let mut s = paper_session();
+        block_on(s.on_htf_candle(&candle(0, 100.0)));
+        block_on(s.run_tick(input(5.0, 500.0), TimestampMs(0), Price(100.0), Price(1.0)));
+
+        let events = block_on(s.on_command(ControlCommand::Pause, TimestampMs(1)));
+        assert!(s.paused);
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::OrdersCancelled { count } if *count > 0))
+        );
+        assert!(s.venue.mm_broker().unwrap().resting_orders().is_empty());
+
+        block_on(s.on_command(ControlCommand::Resume, TimestampMs(2)));
+        assert!(!s.paused);
+    }
+
+    #[test]
+    fn flatten_sells_base_at_last_mid() {
+        let mut s = paper_session();
+        block_on(s.on_htf_candle(&candle(0, 100.0)));
+
+        let events = block_on(s.on_command(ControlCommand::Flatten, TimestampMs(1)));
+        assert!(s.paused);
+        assert!(events.iter().any(|e| matches!(e, EngineEvent::Fill { .. })));
+        assert_eq!(s.venue.inventory().base, Qty(0.0));
+    }
+
+    #[test]
+    fn flatten_before_first_candle_is_deferred_with_alert() {
+        let mut s = paper_session();
+
+        let events = block_on(s.on_command(ControlCommand::Flatten, TimestampMs(0)));
+        assert!(s.paused && s.pending_flatten);
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::Alert { source, .. } if source == "control"))
+        );
+
+        let events = block_on(s.on_htf_candle(&candle(1, 100.0)));
+        assert!(!s.pending_flatten);
+        assert!(events.iter().any(|e| matches!(e, EngineEvent::Fill { .. })));
+        assert_eq!(s.venue.inventory().base, Qty(0.0));
+    }
+
+    #[test]
+    fn resume_cancels_deferred_flatten() {
+        let mut s = paper_session();
+        block_on(s.on_command(ControlCommand::Flatten, TimestampMs(0)));
+        block_on(s.on_command(ControlCommand::Resume, TimestampMs(1)));
+
+        let events = block_on(s.on_htf_candle(&candle(2, 100.0)));
+        assert!(!events.iter().any(|e| matches!(e, EngineEvent::Fill { .. })));
+        assert_eq!(s.venue.inventory().base, Qty(5.0));
+    }
+}
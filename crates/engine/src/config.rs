@@ -0,0 +1,283 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use core::types::{Bps, Money, Qty, Ratio};
+
+use crate::tick::EngineCtx;
+
+/// Параметры engine, которые можно менять на лету (hot reload).
+///
+/// Файл — JSON-объект с любым подмножеством полей: отсутствующие
+/// поля сохраняют текущее значение, `null` выключает risk-лимит.
+/// Новые значения применяются на следующем тике.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineConfig {
+    pub grid_levels: usize,
+    pub grid_step_bps: f64,
+    pub base_quote_per_order: f64,
+    pub max_size_mult: f64,
+    pub min_base_qty: f64,
+
+    /// Полосы inventory — общие для policy и сетки
+    pub soft_min: f64,
+    pub soft_max: f64,
+    pub hard_min: f64,
+    pub hard_max: f64,
+
+    pub max_position_notional: Option<f64>,
+    pub max_daily_loss: Option<f64>,
+    pub max_orders_per_min: Option<usize>,
+    pub max_drawdown: Option<f64>,
+}
+
+/// Одно изменённое поле
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl EngineConfig {
+    /// Текущие значения из контекста engine
+    pub fn of(ctx: &EngineCtx) -> Self {
+        let limits = ctx.risk.limits;
+        Self {
+            grid_levels: ctx.grid.levels,
+            grid_step_bps: ctx.grid.step.0,
+            base_quote_per_order: ctx.grid.base_quote_per_order.0,
+            max_size_mult: ctx.grid.max_size_mult,
+            min_base_qty: ctx.grid.min_base_qty.0,
+            soft_min: ctx.mm_policy.soft_min.0,
+            soft_max: ctx.mm_policy.soft_max.0,
+            hard_min: ctx.mm_policy.hard_min.0,
+            hard_max: ctx.mm_policy.hard_max.0,
+            max_position_notional: limits.max_position_notional.map(|m| m.0),
+            max_daily_loss: limits.max_daily_loss.map(|m| m.0),
+            max_orders_per_min: limits.max_orders_per_min,
+            max_drawdown: limits.max_drawdown.map(|m| m.0),
+        }
+    }
+
+    /// Читает файл поверх `base` и валидирует результат
+    pub fn load(path: &str, base: &EngineConfig) -> Result<Self> {
+        let body = std::fs::read(path).with_context(|| format!("failed to read {}", path))?;
+        let patch: Value =
+            serde_json::from_slice(&body).with_context(|| format!("invalid JSON in {}", path))?;
+        let Value::Object(patch) = patch else {
+            bail!("config {} must be a JSON object", path);
+        };
+
+        let mut merged = serde_json::to_value(base)?;
+        let fields = merged.as_object_mut().expect("config serializes to object");
+        for (k, v) in patch {
+            if !fields.contains_key(&k) {
+                bail!("unknown config field: {}", k);
+            }
+            fields.insert(k, v);
+        }
+
+        let cfg: EngineConfig =
+            serde_json::from_value(merged).with_context(|| format!("invalid config {}", path))?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=50).contains(&self.grid_levels) {
+            bail!("grid_levels must be in 1..=50");
+        }
+        if self.grid_step_bps <= 0.0 {
+            bail!("grid_step_bps must be > 0");
+        }
+        if self.base_quote_per_order <= 0.0 {
+            bail!("base_quote_per_order must be > 0");
+        }
+        if self.max_size_mult < 1.0 {
+            bail!("max_size_mult must be >= 1");
+        }
+        if self.min_base_qty < 0.0 {
+            bail!("min_base_qty must be >= 0");
+        }
+        let bands_ok = 0.0 <= self.hard_min
+            && self.hard_min <= self.soft_min
+            && self.soft_min < self.soft_max
+            && self.soft_max <= self.hard_max
+            && self.hard_max <= 1.0;
+        if !bands_ok {
+            bail!("bands must satisfy 0 <= hard_min <= soft_min < soft_max <= hard_max <= 1");
+        }
+        for (name, v) in [
+            ("max_position_notional", self.max_position_notional),
+            ("max_daily_loss", self.max_daily_loss),
+            ("max_drawdown", self.max_drawdown),
+        ] {
+            if v.is_some_and(|v| v <= 0.0) {
+                bail!("{} must be > 0", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Поля, значения которых отличаются в `new`
+    pub fn diff(&self, new: &EngineConfig) -> Vec<ConfigChange> {
+        let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+            (serde_json::to_value(self), serde_json::to_value(new))
+        else {
+            return Vec::new();
+        };
+
+        old.into_iter()
+            .filter_map(|(field, old)| {
+                let new = new.get(&field)?.clone();
+                (old != new).then_some(ConfigChange { field, old, new })
+            })
+            .collect()
+    }
+
+    pub fn apply(&self, ctx: &mut EngineCtx) {
+        ctx.grid.levels = self.grid_levels;
+        ctx.grid.step = Bps(self.grid_step_bps);
+        ctx.grid.base_quote_per_order = Money(self.base_quote_per_order);
+        ctx.grid.max_size_mult = self.max_size_mult;
+        ctx.grid.min_base_qty = Qty(self.min_base_qty);
+
+        ctx.grid.soft_min = Ratio(self.soft_min);
+        ctx.grid.soft_max = Ratio(self.soft_max);
+        ctx.grid.hard_min = Ratio(self.hard_min);
+        ctx.grid.hard_max = Ratio(self.hard_max);
+        ctx.mm_policy.soft_min = Ratio(self.soft_min);
+        ctx.mm_policy.soft_max = Ratio(self.soft_max);
+        ctx.mm_policy.hard_min = Ratio(self.hard_min);
+        ctx.mm_policy.hard_max = Ratio(self.hard_max);
+
+        let limits = &mut ctx.risk.limits;
+        limits.max_position_notional = self.max_position_notional.map(Money);
+        limits.max_daily_loss = self.max_daily_loss.map(Money);
+        limits.max_orders_per_min = self.max_orders_per_min;
+        limits.max_drawdown = self.max_drawdown.map(Money);
+    }
+}
+
+/// Следит за mtime файла конфигурации
+#[derive(Debug, Clone)]
+pub struct ConfigWatcher {
+    pub path: String,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            modified: modified(path),
+        }
+    }
+
+    /// `true`, если файл изменился с прошлой проверки
+    pub fn changed(&mut self) -> bool {
+        let m = modified(&self.path);
+        if m != self.modified {
+            self.modified = m;
+            return m.is_some();
+        }
+        false
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    Path::new(path).metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Команда на перечитывание конфига: SIGHUP (`kill -HUP <pid>`)
+pub struct ReloadSignal {
+    #[cfg(unix)]
+    hup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ReloadSignal {
+    pub fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+
+            let hup = match signal(SignalKind::hangup()) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    eprintln!("failed to install SIGHUP handler: {}", e);
+                    None
+                }
+            };
+            Self { hup }
+        }
+
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(hup) = self.hup.as_mut() {
+            hup.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+impl Default for ReloadSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> EngineConfig {
+        EngineConfig {
+            grid_levels: 5,
+            grid_step_bps: 12.0,
+            base_quote_per_order: 25.0,
+            max_size_mult: 2.0,
+            min_base_qty: 0.0001,
+            soft_min: 0.40,
+            soft_max: 0.60,
+            hard_min: 0.35,
+            hard_max: 0.65,
+            max_position_notional: Some(500.0),
+            max_daily_loss: None,
+            max_orders_per_min: None,
+            max_drawdown: None,
+        }
+    }
+
+    #[test]
+    fn partial_file_overrides_only_listed_fields() {
+        let path = std::env::temp_dir().join(format!("mmbot-config-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        std::fs::write(path, r#"{"grid_levels": 6, "max_position_notional": null}"#).unwrap();
+        let cfg = EngineConfig::load(path, &base()).unwrap();
+        let changes = base().diff(&cfg);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "grid_levels");
+        assert_eq!(changes[0].new, serde_json::json!(6));
+        assert_eq!(cfg.max_position_notional, None);
+        assert_eq!(cfg.grid_step_bps, 12.0);
+
+        std::fs::write(path, r#"{"soft_min": 0.7}"#).unwrap();
+        assert!(EngineConfig::load(path, &base()).is_err());
+        std::fs::write(path, r#"{"levels": 3}"#).unwrap();
+        assert!(EngineConfig::load(path, &base()).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{Next, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::{mpsc, watch};
+
+use crate::feed_quality::FeedMetrics;
+
+/// Переменная окружения с токеном control API
+pub const CONTROL_TOKEN_ENV: &str = "ENGINE_CONTROL_TOKEN";
+
+/// Ручные команды оператора. Выполняются главным циклом engine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Снять сетку и не тикать, пока не придёт `Resume`
+    Pause,
+    Resume,
+    /// Снять сетку, продать весь base по рынку и встать на паузу.
+    /// До первой свечи цены нет — продажа откладывается до неё (`Resume` отменяет)
+    Flatten,
+    /// Сбросить якорь и перестроить сетку вокруг текущего mid
+    Recenter,
+    /// Снять аварийную остановку (Halted) и kill switch риска
+    Unhalt,
+}
+
+/// Что видит оператор: публикуется главным циклом после каждой пачки событий
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ControlStatus {
+    pub ts_ms: i64,
+    pub symbol: String,
+    pub mode: String,
+    pub state: String,
+    pub paused: bool,
+    pub last_mid: Option<f64>,
+    pub equity: Option<f64>,
+    pub accounts: Vec<AccountView>,
+    pub feed: FeedMetrics,
+    pub open_orders: Vec<OrderView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountView {
+    pub strategy: String,
+    pub quote: f64,
+    pub base: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderView {
+    pub side: String,
+    pub price: f64,
+    pub qty: f64,
+}
+
+/// Сторона engine: публикует статус, читает команды
+pub struct ControlHandle {
+    pub status: watch::Sender<ControlStatus>,
+    pub commands: mpsc::UnboundedReceiver<ControlCommand>,
+}
+
+#[derive(Clone)]
+struct ControlState {
+    token: Arc<str>,
+    status: watch::Receiver<ControlStatus>,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+}
+
+/// Поднимает HTTP control API на `addr` в фоне.
+///
+/// Все маршруты, кроме `/health`, требуют `Authorization: Bearer <token>`.
+pub async fn serve(addr: SocketAddr, token: String) -> Result<(ControlHandle, SocketAddr)> {
+    anyhow::ensure!(!token.is_empty(), "control API token must not be empty");
+
+    let (status_tx, status_rx) = watch::channel(ControlStatus::default());
+    let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+    let app = router(ControlState {
+        token: token.into(),
+        status: status_rx,
+        commands: cmd_tx,
+    });
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind control API on {}", addr))?;
+    let local = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            eprintln!("control API stopped: {}", e);
+        }
+    });
+
+    Ok((
+        ControlHandle {
+            status: status_tx,
+            commands: cmd_rx,
+        },
+        local,
+    ))
+}
+
+fn router(state: ControlState) -> Router {
+    let protected = Router::new()
+        .route("/state", get(get_state))
+        .route("/orders", get(get_orders))
+        .route("/inventory", get(get_inventory))
+        .route(
+            "/pause",
+            post(|s: State<ControlState>| command(s, ControlCommand::Pause)),
+        )
+        .route(
+            "/resume",
+            post(|s: State<ControlState>| command(s, ControlCommand::Resume)),
+        )
+        .route(
+            "/flatten",
+            post(|s: State<ControlState>| command(s, ControlCommand::Flatten)),
+        )
+        .route(
+            "/recenter",
+            post(|s: State<ControlState>| command(s, ControlCommand::Recenter)),
+        )
+        .route(
+            "/unhalt",
+            post(|s: State<ControlState>| command(s, ControlCommand::Unhalt)),
+        )
+        .layer(from_fn_with_state(state.clone(), auth));
+
+    Router::new()
+        .route("/health", get(|| async { Json(json!({"ok": true})) }))
+        .merge(protected)
+        .with_state(state)
+}
+
+async fn auth(State(state): State<ControlState>, req: Request, next: Next) -> Response {
+    let ok = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|t| t == &*state.token);
+    if !ok {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "invalid or missing token"})),
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+async fn get_state(State(state): State<ControlState>) -> Json<ControlStatus> {
+    Json(state.status.borrow().clone())
+}
+
+async fn get_orders(State(state): State<ControlState>) -> Json<Vec<OrderView>> {
+    Json(state.status.borrow().open_orders.clone())
+}
+
+async fn get_inventory(State(state): State<ControlState>) -> impl IntoResponse {
+    let s = state.status.borrow();
+    Json(json!({
+        "accounts": s.accounts,
+        "last_mid": s.last_mid,
+        "equity": s.equity,
+    }))
+}
+
+async fn command(State(state): State<ControlState>, cmd: ControlCommand) -> impl IntoResponse {
+    match state.commands.send(cmd) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(json!({"queued": cmd}))),
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({"error": "engine loop is not running"})),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn request(addr: SocketAddr, method: &str, path: &str, token: Option<&str>) -> String {
+        let mut s = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let auth = token
+            .map(|t| format!("Authorization: Bearer {}\r\n", t))
+            .unwrap_or_default();
+        let req = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+            method, path, auth
+        );
+        s.write_all(req.as_bytes()).await.unwrap();
+        let mut out = String::new();
+        s.read_to_string(&mut out).await.unwrap();
+        out
+    }
+
+    #[test]
+    fn commands_require_token_and_reach_engine() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (mut handle, addr) = serve("127.0.0.1:0".parse().unwrap(), "secret".into())
+                .await
+                .unwrap();
+            handle.status.send_modify(|s| s.state = "MMNormal".into());
+
+            let r = request(addr, "POST", "/pause", None).await;
+            assert!(r.starts_with("HTTP/1.1 401"));
+            let r = request(addr, "POST", "/pause", Some("wrong")).await;
+            assert!(r.starts_with("HTTP/1.1 401"));
+            assert!(handle.commands.try_recv().is_err());
+
+            let r = request(addr, "POST", "/pause", Some("secret")).await;
+            assert!(r.starts_with("HTTP/1.1 202"));
+            assert_eq!(handle.commands.recv().await, Some(ControlCommand::Pause));
+            let r = request(addr, "POST", "/unhalt", Some("secret")).await;
+            assert!(r.contains("\"queued\":\"unhalt\""));
+            assert_eq!(handle.commands.recv().await, Some(ControlCommand::Unhalt));
+
+            let r = request(addr, "GET", "/state", Some("secret")).await;
+            assert!(r.contains("\"state\":\"MMNormal\""));
+            let r = request(addr, "GET", "/health", None).await;
+            assert!(r.starts_with("HTTP/1.1 200"));
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use core::types::{Money, Price, Qty, TimestampMs};
+use execution::accounting::Ledger;
+use mm::grid::Side;
+
+use crate::event::EngineEvent;
+
+/// Живой учёт equity и PnL по событиям engine.
+///
+/// Не зависит от того, кто исполняет ордера (paper или биржа): ведёт
+/// собственный `Ledger` только по `Fill`-событиям, unrealized считает
+/// по mark (mid). Снимки `EquitySnapshot` идут в sink'и, break-even
+/// и просадка — в risk.
+#[derive(Debug, Clone)]
+pub struct EquityTracker {
+    ledger: Ledger,
+    peak_equity: f64,
+    last_mark: Option<Price>,
+}
+
+impl EquityTracker {
+    /// Стартовый inventory оценивается по первому mark
+    pub fn new(initial_quote: f64, initial_base: f64, mark: Price) -> Self {
+        let ledger = Ledger::new(initial_quote, initial_base, mark.0);
+        Self {
+            peak_equity: ledger.equity(mark.0),
+            ledger,
+            last_mark: Some(mark),
+        }
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    pub fn last_mark(&self) -> Option<Price> {
+        self.last_mark
+    }
+
+    /// Учитывает `Fill` из пачки событий, остальное пропускает
+    pub fn on_events(&mut self, events: &[EngineEvent]) {
+        for e in events {
+            if let EngineEvent::Fill {
+                side,
+                price,
+                qty,
+                fee,
+                ..
+            } = e
+            {
+                match side {
+                    Side::Buy => self.ledger.buy(qty.0, price.0, fee.0),
+                    Side::Sell => {
+                        self.ledger.sell(qty.0, price.0, fee.0);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn equity(&self, mark: Price) -> Money {
+        Money(self.ledger.equity(mark.0))
+    }
+
+    /// Цена, при которой продажа всего base выходит в ноль (с комиссиями входа)
+    pub fn break_even(&self) -> Option<Price> {
+        self.ledger.avg_cost().map(Price)
+    }
+
+    /// Пик equity — по последнему mark: просадка после снятия остановки считается от него
+    pub fn reset_peak(&mut self) {
+        if let Some(mark) = self.last_mark {
+            self.peak_equity = self.ledger.equity(mark.0);
+        }
+    }
+
+    /// Снимок по mark; обновляет пик equity для просадки
+    pub fn snapshot(&mut self, ts: TimestampMs, mark: Price) -> EngineEvent {
+        let equity = self.ledger.equity(mark.0);
+        self.peak_equity = self.peak_equity.max(equity);
+        self.last_mark = Some(mark);
+
+        EngineEvent::EquitySnapshot {
+            ts,
+            quote: Money(self.ledger.quote),
+            base: Qty(self.ledger.base),
+            mark,
+            equity: Money(equity),
+            realized_pnl: Money(self.ledger.realized_pnl),
+            unrealized_pnl: Money(self.ledger.unrealized_pnl(mark.0)),
+            fees: Money(self.ledger.fees_paid),
+            break_even: self.break_even(),
+            drawdown: Money(self.peak_equity - equity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(side: Side, price: f64, qty: f64, fee: f64) -> EngineEvent {
+        EngineEvent::Fill {
+            ts: TimestampMs(0),
+            side,
+            price: Price(price),
+            qty: Qty(qty),
+            fee: Money(fee),
+            realized_pnl: None,
+        }
+    }
+
+    #[test]
+    fn snapshot_splits_realized_and_unrealized_and_tracks_drawdown() {
+        let mut t = EquityTracker::new(1000.0, 0.0, Price(100.0));
+        t.on_events(&[
+            fill(Side::Buy, 100.0, 2.0, 1.0),
+            EngineEvent::Log("ignored".into()),
+            fill(Side::Sell, 110.0, 1.0, 0.0),
+        ]);
+        assert_eq!(t.break_even(), Some(Price(100.5)));
+
+        let EngineEvent::EquitySnapshot {
+            equity,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            drawdown,
+            ..
+        } = t.snapshot(TimestampMs(1), Price(120.0))
+        else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(realized_pnl, Money(9.5));
+        assert_eq!(unrealized_pnl, Money(19.5));
+        assert_eq!(fees, Money(1.0));
+        assert_eq!(equity, Money(1029.0));
+        assert_eq!(drawdown, Money(0.0));
+
+        let EngineEvent::EquitySnapshot { drawdown, .. } = t.snapshot(TimestampMs(2), Price(100.0))
+        else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(drawdown, Money(20.0));
+    }
+}
@@ -1,7 +1,13 @@
+use core::types::{Money, Price, Qty, TimestampMs};
+use mm::grid::{DesiredOrder, Side};
+use mm::rebalance::RebalanceDecision;
 use policy::mm_policy::{MmDecisionReason, MmMode};
+use policy::trend_policy::{TrendAction, TrendDecisionReason};
 use state_machine::cause::TransitionCause;
 use state_machine::state::BotState;
 
+use crate::config::ConfigChange;
+
 #[derive(Debug, Clone)]
 pub enum EngineEvent {
     Transition {
@@ -13,5 +19,68 @@ pub enum EngineEvent {
         mode: MmMode,
         reason: MmDecisionReason,
     },
+    /// Решение тренд-стратегии на закрытии HTF свечи
+    TrendDecision {
+        action: TrendAction,
+        reason: TrendDecisionReason,
+    },
+    /// Желаемая сетка лимиток после тика (пустая = снять все)
+    DesiredGrid(Vec<DesiredOrder>),
+    /// Рыночная сделка, чтобы привести inventory к целевой доле
+    RebalanceIntent(RebalanceDecision),
+    /// Исполнение ордера (paper или live)
+    Fill {
+        ts: TimestampMs,
+        side: Side,
+        price: Price,
+        qty: Qty,
+        fee: Money,
+        realized_pnl: Option<Money>,
+    },
+    /// Снимок портфеля и PnL по mark-цене (см. `EquityTracker`)
+    EquitySnapshot {
+        ts: TimestampMs,
+        quote: Money,
+        base: Qty,
+        mark: Price,
+        equity: Money,
+        realized_pnl: Money,
+        unrealized_pnl: Money,
+        fees: Money,
+        /// Средняя цена base с комиссиями; `None`, если base нет
+        break_even: Option<Price>,
+        /// Просадка от пика equity
+        drawdown: Money,
+    },
+    /// Тревога (risk, watchdog...) — требует внимания человека
+    Alert {
+        source: String,
+        message: String,
+    },
+    /// Сняты все открытые ордера
+    OrdersCancelled {
+        count: usize,
+    },
+    /// Refresh сетки отправлен batch'ами; `deferred` не влезли в rate limit
+    OrdersSubmitted {
+        cancels: usize,
+        places: usize,
+        batches: usize,
+        deferred: usize,
+    },
+    /// Live: итог отправленных batch'ей; `failed` — ордера с ошибкой
+    OrdersSynced {
+        placed: usize,
+        cancelled: usize,
+        failed: usize,
+    },
+    /// Применён новый конфиг (hot reload), список изменённых полей
+    ConfigChanged {
+        changes: Vec<ConfigChange>,
+    },
+    /// Engine завершает работу (последнее событие сессии)
+    Shutdown {
+        reason: String,
+    },
     Log(String),
 }
@@ -1,4 +1,4 @@
-use core::types::Price;
+use core::types::{Price, TimestampMs};
 
 use structure::atr::atr;
 use structure::candle::Candle;
@@ -34,4 +34,50 @@ impl CandleFeed {
     pub fn mid(&self) -> Option<Price> {
         self.candles.last().map(|c| c.close)
     }
+
+    /// Свечи перестали приходить: следующая закрытая свеча
+    /// (`ts` — время открытия) должна была прийти больше `grace_ms` назад.
+    /// Пустой feed не считается устаревшим — он ещё не начался.
+    pub fn is_stale(&self, interval_ms: i64, now: TimestampMs, grace_ms: i64) -> bool {
+        match self.candles.last() {
+            Some(c) => now.0 > c.ts.0 + 2 * interval_ms + grace_ms,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use core::types::Qty;
+    use std::time::Duration;
+
+    fn candle(ts: i64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(1.0),
+            high: Price(1.0),
+            low: Price(1.0),
+            close: Price(1.0),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn feed_goes_stale_when_next_candle_is_late() {
+        let step = 60_000;
+        let clock = ManualClock::new(TimestampMs(0));
+        let mut feed = CandleFeed::new(10);
+        assert!(!feed.is_stale(step, clock.now(), 5_000));
+
+        // свеча [0, 60s) закрылась, следующая закроется в 120s
+        clock.set(TimestampMs(step));
+        feed.push(candle(0));
+        clock.advance(Duration::from_secs(64));
+        assert!(!feed.is_stale(step, clock.now(), 5_000));
+
+        clock.advance(Duration::from_secs(2));
+        assert!(feed.is_stale(step, clock.now(), 5_000));
+    }
 }
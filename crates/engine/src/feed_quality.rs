@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use core::types::TimestampMs;
+use policy::mm_policy::DataQuality;
+
+/// Окно, за которое считаются максимумы задержки и пауз
+const WINDOW_MS: i64 = 5 * 60_000;
+const HOUR_MS: i64 = 60 * 60_000;
+
+/// Пороги деградации market data
+#[derive(Debug, Copy, Clone)]
+pub struct FeedQualityParams {
+    /// Задержка закрытой свечи (получение − время закрытия)
+    pub degraded_latency_ms: i64,
+    pub bad_latency_ms: i64,
+    /// Пауза между сообщениями WS
+    pub degraded_gap_ms: i64,
+    pub bad_gap_ms: i64,
+    /// Реконнектов за час, после которых данные считаются негодными
+    pub bad_reconnects_per_hour: usize,
+}
+
+impl Default for FeedQualityParams {
+    fn default() -> Self {
+        Self {
+            degraded_latency_ms: 3_000,
+            bad_latency_ms: 15_000,
+            degraded_gap_ms: 10_000,
+            bad_gap_ms: 30_000,
+            bad_reconnects_per_hour: 5,
+        }
+    }
+}
+
+/// Метрики feed'а для heartbeat / control API
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FeedMetrics {
+    pub quality: String,
+    pub last_latency_ms: Option<i64>,
+    /// Максимумы за последние 5 минут (пауза — включая текущую)
+    pub max_latency_ms: i64,
+    pub max_gap_ms: i64,
+    pub reconnects: u64,
+    pub reconnects_last_hour: usize,
+}
+
+/// Следит за задержкой свечей, паузами между сообщениями и реконнектами WS
+#[derive(Debug, Clone)]
+pub struct FeedMonitor {
+    pub params: FeedQualityParams,
+    last_msg_ms: Option<i64>,
+    last_latency_ms: Option<i64>,
+    /// (время, значение) за окно
+    latencies: VecDeque<(i64, i64)>,
+    gaps: VecDeque<(i64, i64)>,
+    reconnect_times: VecDeque<i64>,
+    reconnects: u64,
+}
+
+impl FeedMonitor {
+    pub fn new(params: FeedQualityParams) -> Self {
+        Self {
+            params,
+            last_msg_ms: None,
+            last_latency_ms: None,
+            latencies: VecDeque::new(),
+            gaps: VecDeque::new(),
+            reconnect_times: VecDeque::new(),
+            reconnects: 0,
+        }
+    }
+
+    /// Любое сообщение из WS
+    pub fn on_message(&mut self, now: TimestampMs) {
+        if let Some(last) = self.last_msg_ms {
+            self.gaps.push_back((now.0, (now.0 - last).max(0)));
+        }
+        self.last_msg_ms = Some(now.0);
+        self.prune(now);
+    }
+
+    /// Закрытая свеча: `open_ts` — время открытия, `interval_ms` — длина
+    pub fn on_candle(&mut self, now: TimestampMs, open_ts: TimestampMs, interval_ms: i64) {
+        let latency = (now.0 - (open_ts.0 + interval_ms)).max(0);
+        self.last_latency_ms = Some(latency);
+        self.latencies.push_back((now.0, latency));
+        self.prune(now);
+    }
+
+    pub fn on_reconnect(&mut self, now: TimestampMs) {
+        self.reconnects += 1;
+        self.reconnect_times.push_back(now.0);
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: TimestampMs) {
+        while self
+            .latencies
+            .front()
+            .is_some_and(|(t, _)| now.0 - t > WINDOW_MS)
+        {
+            self.latencies.pop_front();
+        }
+        while self
+            .gaps
+            .front()
+            .is_some_and(|(t, _)| now.0 - t > WINDOW_MS)
+        {
+            self.gaps.pop_front();
+        }
+        while self
+            .reconnect_times
+            .front()
+            .is_some_and(|t| now.0 - t > HOUR_MS)
+        {
+            self.reconnect_times.pop_front();
+        }
+    }
+
+    fn max_latency_ms(&self) -> i64 {
+        self.latencies.iter().map(|(_, l)| *l).max().unwrap_or(0)
+    }
+
+    fn max_gap_ms(&self, now: TimestampMs) -> i64 {
+        // текущая тишина тоже пауза, иначе оборванный feed выглядит здоровым
+        let current = self.last_msg_ms.map(|t| (now.0 - t).max(0)).unwrap_or(0);
+        self.gaps.iter().map(|(_, g)| *g).fold(current, i64::max)
+    }
+
+    pub fn quality(&self, now: TimestampMs) -> DataQuality {
+        let p = self.params;
+        let latency = self.max_latency_ms();
+        let gap = self.max_gap_ms(now);
+        let recent_reconnect = self
+            .reconnect_times
+            .back()
+            .is_some_and(|t| now.0 - t <= WINDOW_MS);
+
+        if latency > p.bad_latency_ms
+            || gap > p.bad_gap_ms
+            || self.reconnect_times.len() >= p.bad_reconnects_per_hour
+        {
+            DataQuality::Bad
+        } else if latency > p.degraded_latency_ms || gap > p.degraded_gap_ms || recent_reconnect {
+            DataQuality::Degraded
+        } else {
+            DataQuality::Good
+        }
+    }
+
+    pub fn metrics(&self, now: TimestampMs) -> FeedMetrics {
+        FeedMetrics {
+            quality: format!("{:?}", self.quality(now)),
+            last_latency_ms: self.last_latency_ms,
+            max_latency_ms: self.max_latency_ms(),
+            max_gap_ms: self.max_gap_ms(now),
+            reconnects: self.reconnects,
+            reconnects_last_hour: self.reconnect_times.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quality_degrades_on_latency_gaps_and_reconnects_then_recovers() {
+        let mut m = FeedMonitor::new(FeedQualityParams::default());
+        m.on_message(TimestampMs(0));
+        m.on_candle(TimestampMs(60_500), TimestampMs(0), 60_000);
+        m.on_message(TimestampMs(1_000));
+        assert_eq!(m.quality(TimestampMs(2_000)), DataQuality::Good);
+
+        // 12с тишины — Degraded, 40с — Bad
+        assert_eq!(m.quality(TimestampMs(13_000)), DataQuality::Degraded);
+        assert_eq!(m.quality(TimestampMs(41_000)), DataQuality::Bad);
+
+        m.on_message(TimestampMs(41_000));
+        m.on_candle(TimestampMs(125_000), TimestampMs(60_000), 60_000);
+        assert_eq!(m.metrics(TimestampMs(125_000)).last_latency_ms, Some(5_000));
+
+        // ровный поток: через окно старые паузы и задержки забываются
+        let mut t = 41_000;
+        while t < 125_000 + WINDOW_MS + 5_000 {
+            t += 5_000;
+            m.on_message(TimestampMs(t));
+        }
+        assert_eq!(m.quality(TimestampMs(t)), DataQuality::Good);
+
+        m.on_reconnect(TimestampMs(t));
+        assert_eq!(m.quality(TimestampMs(t)), DataQuality::Degraded);
+        for i in 1..5 {
+            m.on_reconnect(TimestampMs(t + i));
+        }
+        assert_eq!(m.quality(TimestampMs(t + 5)), DataQuality::Bad);
+        assert_eq!(m.metrics(TimestampMs(t + 5)).reconnects, 5);
+    }
+}
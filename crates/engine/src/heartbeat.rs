@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::feed_quality::FeedMetrics;
+
+/// Префикс ключа heartbeat в Redis: `mmbot:heartbeat:<symbol>`
+pub const HEARTBEAT_KEY_PREFIX: &str = "mmbot:heartbeat:";
+
+/// Снимок "я жив" от engine
+#[derive(Debug, Clone, Serialize)]
+pub struct Heartbeat {
+    pub ts_ms: i64,
+    pub symbol: String,
+    pub mode: String,
+    pub state: String,
+    pub last_htf_ts: Option<i64>,
+    pub last_ltf_ts: Option<i64>,
+    pub equity: Option<f64>,
+    pub htf_stale: bool,
+    pub ltf_stale: bool,
+    /// Задержки, паузы и реконнекты market data
+    pub feed: FeedMetrics,
+}
+
+/// Куда писать heartbeat: Postgres (по run_id) и/или Redis (с TTL).
+/// Запись не должна тормозить цикл engine — `publish` уходит в фон.
+#[derive(Clone, Default)]
+pub struct HeartbeatStore {
+    pg: Option<(PgPool, Uuid)>,
+    redis: Option<redis::aio::MultiplexedConnection>,
+    ttl_secs: u64,
+}
+
+impl HeartbeatStore {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            ttl_secs,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_pg(mut self, pg: PgPool, run_id: Uuid) -> Self {
+        self.pg = Some((pg, run_id));
+        self
+    }
+
+    pub async fn with_redis(mut self, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context("redis connection failed")?;
+        self.redis = Some(conn);
+        Ok(self)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.pg.is_some() || self.redis.is_some()
+    }
+
+    pub fn publish(&self, hb: Heartbeat) {
+        if !self.is_enabled() {
+            return;
+        }
+        let store = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = store.write(&hb).await {
+                eprintln!("heartbeat write failed: {:#}", e);
+            }
+        });
+    }
+
+    pub async fn write(&self, hb: &Heartbeat) -> Result<()> {
+        let payload = serde_json::to_value(hb)?;
+
+        if let Some((pg, run_id)) = &self.pg {
+            sqlx::query(
+                r#"
+                INSERT INTO engine_heartbeats (run_id, ts, state, last_candle_ts, equity, payload)
+                VALUES ($1, NOW(), $2, $3, $4, $5)
+                ON CONFLICT (run_id)
+                DO UPDATE SET ts = NOW(), state = EXCLUDED.state,
+                    last_candle_ts = EXCLUDED.last_candle_ts,
+                    equity = EXCLUDED.equity, payload = EXCLUDED.payload
+                "#,
+            )
+            .bind(run_id)
+            .bind(&hb.state)
+            .bind(hb.last_htf_ts)
+            .bind(hb.equity)
+            .bind(&payload)
+            .execute(pg)
+            .await
+            .context("heartbeat upsert failed")?;
+        }
+
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            let _: () = redis::cmd("SET")
+                .arg(format!("{}{}", HEARTBEAT_KEY_PREFIX, hb.symbol))
+                .arg(payload.to_string())
+                .arg("EX")
+                .arg(self.ttl_secs.max(1))
+                .query_async(&mut conn)
+                .await
+                .context("heartbeat redis SET failed")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Отметка "цикл engine жив": главный цикл бьёт её на каждой итерации,
+/// watchdog в отдельной задаче проверяет, что она не застыла.
+#[derive(Debug, Clone, Default)]
+pub struct LoopBeat(Arc<AtomicI64>);
+
+impl LoopBeat {
+    pub fn beat(&self, now_ms: i64) {
+        self.0.store(now_ms, Ordering::Relaxed);
+    }
+
+    pub fn last(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Сколько цикл не отмечался (0, если ещё не стартовал)
+    pub fn stalled_for(&self, now_ms: i64) -> i64 {
+        let last = self.last();
+        if last == 0 { 0 } else { (now_ms - last).max(0) }
+    }
+}
+
+/// Watchdog главного цикла. Сам цикл о своём зависании сообщить не может,
+/// поэтому пишем в stderr (worker складывает stderr в run_events).
+pub async fn watch_loop(beat: LoopBeat, clock: Arc<dyn Clock>, every: Duration, stall_ms: i64) {
+    let mut alerted = false;
+    loop {
+        clock.sleep(every).await;
+
+        let stalled = beat.stalled_for(clock.now().0);
+        if stalled > stall_ms && !alerted {
+            eprintln!("ALERT [watchdog]: main loop stalled for {}ms", stalled);
+            alerted = true;
+        } else if stalled <= stall_ms && alerted {
+            eprintln!("watchdog: main loop recovered");
+            alerted = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_beat_reports_stall_only_after_start() {
+        let beat = LoopBeat::default();
+        assert_eq!(beat.stalled_for(10_000), 0);
+
+        beat.beat(10_000);
+        let shared = beat.clone();
+        assert_eq!(shared.stalled_for(10_500), 500);
+        assert_eq!(shared.stalled_for(9_000), 0);
+    }
+}
@@ -1,7 +1,30 @@
+pub mod allocator;
+pub mod anchor;
+pub mod candle_builder;
+pub mod capture;
+pub mod clock;
+pub mod command;
+pub mod config;
+pub mod control;
 pub mod context;
 pub mod driver;
 pub mod engine;
+pub mod equity;
 pub mod event;
 pub mod feed;
+pub mod feed_quality;
+pub mod heartbeat;
+pub mod live;
+pub mod orders;
+pub mod paper;
+pub mod pg_sink;
+pub mod reconcile;
+pub mod risk;
+pub mod session;
+pub mod shutdown;
 pub mod sink;
+pub mod snapshot;
 pub mod tick;
+pub mod trend;
+pub mod venue;
+pub mod warmup;
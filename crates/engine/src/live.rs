@@ -0,0 +1,483 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::sync::mpsc;
+
+use bybit::private::{BATCH_LIMIT, BybitPrivate, MAINNET_URL, TESTNET_URL, WalletBalance};
+use bybit::rest::InstrumentInfo;
+use bybit::ws::{AccountEvent, AccountStream, PRIVATE_WS_MAINNET, PRIVATE_WS_TESTNET};
+use core::types::{Price, Qty};
+use execution::accounting::Ledger;
+use execution::live::{OrderManager, SyncReport, TrackedFill};
+use execution::orders::LiveOrder;
+use execution::traits::OrderGateway;
+use mm::grid::{DesiredOrder, Inventory, Side};
+
+use crate::clock::{Clock, SystemClock};
+use crate::event::EngineEvent;
+use crate::orders::{OrderBatch, OrderPlanner, RateLimiter};
+use crate::reconcile::OpenOrder;
+
+/// Запросов/с на ордера в live без `--order-rate-limit` (запас от лимита Bybit spot)
+pub const LIVE_ORDER_RATE_LIMIT: f64 = 5.0;
+/// Live flatten: сколько раз и как часто ждать fill рыночной продажи
+const FLATTEN_POLL_ATTEMPTS: usize = 5;
+const FLATTEN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Live-исполнение: ордера на бирже, правила инструмента, балансы на старте
+/// и последние из private WS
+pub struct LiveAccount<G = BybitPrivate> {
+    pub orders: OrderManager<G>,
+    pub planner: OrderPlanner,
+    pub instrument: InstrumentInfo,
+    pub start: WalletBalance,
+    pub wallet: WalletBalance,
+}
+
+/// Подключённый live-аккаунт: открытые ордера символа на старте своими становятся
+/// только после сверки, события private WS — в `account_events`
+pub struct LiveStart {
+    pub account: LiveAccount,
+    pub startup_orders: Vec<LiveOrder>,
+    pub account_events: mpsc::Receiver<AccountEvent>,
+    pub url: &'static str,
+}
+
+/// Читает правила инструмента, балансы и открытые ордера символа и подписывается
+/// на private WS. Ключи — `BYBIT_API_KEY` / `BYBIT_API_SECRET`.
+pub async fn connect(
+    symbol: &str,
+    testnet: bool,
+    order_rate_limit: Option<f64>,
+    order_burst: usize,
+) -> Result<LiveStart> {
+    let url = if testnet { TESTNET_URL } else { MAINNET_URL };
+    let gateway = BybitPrivate::from_env(url, symbol)?;
+    let instrument = gateway
+        .instrument_info()
+        .await
+        .context("failed to load instrument info")?;
+    let start = gateway
+        .wallet_balance(&instrument.base_coin, &instrument.quote_coin)
+        .await
+        .context("failed to load wallet balance")?;
+    let ws_url = if testnet {
+        PRIVATE_WS_TESTNET
+    } else {
+        PRIVATE_WS_MAINNET
+    };
+    let stream = AccountStream::new(ws_url, gateway.clone(), &instrument);
+    let (tx, account_events) = mpsc::channel::<AccountEvent>(1024);
+    tokio::spawn(stream.run(tx));
+
+    let ledger = Ledger::new(start.quote.0, start.base.0, 0.0);
+    let orders = OrderManager::new(gateway, ledger, SystemClock.now());
+    let startup_orders = orders
+        .exchange_orders()
+        .await
+        .context("failed to load open orders")?;
+    let planner = OrderPlanner::new(RateLimiter::new(
+        order_rate_limit.unwrap_or(LIVE_ORDER_RATE_LIMIT),
+        order_burst,
+    ));
+    Ok(LiveStart {
+        account: LiveAccount {
+            orders,
+            planner,
+            instrument,
+            wallet: start.clone(),
+            start,
+        },
+        startup_orders,
+        account_events,
+        url,
+    })
+}
+
+impl<G: OrderGateway> LiveAccount<G> {
+    /// Inventory для тика — реальные балансы аккаунта
+    pub fn inventory(&self) -> Inventory {
+        Inventory {
+            base: self.wallet.base,
+            quote: self.wallet.quote,
+        }
+    }
+
+    /// Желаемая сетка тика уходит на биржу через планировщик, как в paper:
+    /// diff с открытыми ордерами, batch'и в пределах rate limit. Цены и объёмы
+    /// приводятся к тику и шагу, уровни меньше минимума отбрасываются.
+    pub async fn apply_tick(&mut self, events: &[EngineEvent], mid: Price) -> Vec<EngineEvent> {
+        let mut out = Vec::new();
+        for e in events {
+            match e {
+                EngineEvent::DesiredGrid(orders) => {
+                    let orders: Vec<DesiredOrder> = orders
+                        .iter()
+                        .filter_map(|o| self.instrument.normalize(o))
+                        .collect();
+                    let open: Vec<OpenOrder> = self
+                        .orders
+                        .open_orders()
+                        .iter()
+                        .map(|o| OpenOrder {
+                            id: o.order_id.clone(),
+                            side: o.side,
+                            price: o.price,
+                            qty: o.qty,
+                        })
+                        .collect();
+                    // лимиты биржи — по её часам, а не по времени свечи
+                    let plan = self.planner.plan(SystemClock.now(), &open, &orders, mid);
+                    out.extend(self.submit(plan.batches, plan.deferred).await);
+                }
+                EngineEvent::RebalanceIntent(_) => out.push(EngineEvent::Log(
+                    "live: rebalance skipped, market orders are not supported".into(),
+                )),
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// Отправляет batch'и плана; если биржа сообщила об исчерпанном лимите,
+    /// остаток откладывается до следующего тика
+    async fn submit(&mut self, batches: Vec<OrderBatch>, deferred: usize) -> Vec<EngineEvent> {
+        let (mut cancels, mut places, mut sent, mut deferred) = (0, 0, 0, deferred);
+        let mut report = SyncReport::default();
+        let mut batches = batches.into_iter();
+        for b in batches.by_ref() {
+            sent += 1;
+            match b {
+                OrderBatch::Cancel(v) => {
+                    cancels += v.len();
+                    let ids: Vec<String> = v.into_iter().map(|o| o.id).collect();
+                    report.merge(self.orders.cancel_batch(&ids).await);
+                }
+                OrderBatch::Place(v) => {
+                    places += v.len();
+                    report.merge(self.orders.place_batch(&v).await);
+                }
+            }
+            if let Some(status) = self.orders.gateway().limit_status() {
+                self.planner.limiter.on_limit_status(status);
+                if status.remaining == 0 {
+                    break;
+                }
+            }
+        }
+        deferred += batches.map(|b| b.len()).sum::<usize>();
+        if !report.errors.is_empty() {
+            // локальная картина могла разойтись с биржей
+            if let Err(e) = self.orders.refresh().await {
+                report.errors.push(format!("refresh open orders: {:#}", e));
+            }
+        }
+
+        let mut out = Vec::new();
+        if sent > 0 || deferred > 0 {
+            out.push(EngineEvent::OrdersSubmitted {
+                cancels,
+                places,
+                batches: sent,
+                deferred,
+            });
+        }
+        out.extend(sync_events(report));
+        out
+    }
+
+    /// Снимает все ордера на бирже
+    pub async fn cancel_all(&mut self) -> Vec<EngineEvent> {
+        let report = self.orders.cancel_all(BATCH_LIMIT).await;
+        let mut out = vec![EngineEvent::OrdersCancelled {
+            count: report.cancelled,
+        }];
+        out.extend(report.errors.into_iter().map(|message| EngineEvent::Alert {
+            source: "exchange".into(),
+            message,
+        }));
+        out
+    }
+
+    /// Flatten: продаёт base по рынку (сетка уже снята) и ждёт его fill'ы.
+    /// Продаётся не больше учтённого engine и не больше, чем есть на счёте.
+    pub async fn flatten(&mut self, mark: Price) -> Vec<EngineEvent> {
+        let order = DesiredOrder {
+            side: Side::Sell,
+            price: mark,
+            qty: Qty(self.orders.ledger.base.min(self.wallet.base.0)),
+        };
+        let Some(order) = self.instrument.normalize(&order) else {
+            return vec![EngineEvent::Log(format!(
+                "live: flatten skipped, base {} is below exchange minimum",
+                order.qty.0
+            ))];
+        };
+        let order_id = match self.orders.market_sell(order.qty).await {
+            Ok(id) => id,
+            Err(e) => {
+                return vec![EngineEvent::Alert {
+                    source: "exchange".into(),
+                    message: format!("flatten failed: {:#}", e),
+                }];
+            }
+        };
+
+        // рыночный ордер исполняется сразу, но в истории исполнений появляется не мгновенно
+        let mut out = Vec::new();
+        let mut sold = 0.0;
+        for _ in 0..FLATTEN_POLL_ATTEMPTS {
+            tokio::time::sleep(FLATTEN_POLL_INTERVAL).await;
+            match self.orders.poll_fills().await {
+                Ok(fills) => {
+                    for f in fills {
+                        if f.fill.order_id == order_id {
+                            sold += f.fill.qty.0;
+                        }
+                        out.push(fill_event(f));
+                    }
+                }
+                Err(e) => out.push(EngineEvent::Alert {
+                    source: "exchange".into(),
+                    message: format!("poll fills failed: {:#}", e),
+                }),
+            }
+            if sold >= order.qty.0 * (1.0 - 1e-9) {
+                return out;
+            }
+        }
+        out.push(EngineEvent::Alert {
+            source: "live".into(),
+            message: format!(
+                "flatten order {}: filled {} of {} so far",
+                order_id, sold, order.qty.0
+            ),
+        });
+        out
+    }
+
+    /// Новые fill'ы своих ордеров с биржи
+    pub async fn poll_fills(&mut self) -> Vec<EngineEvent> {
+        match self.orders.poll_fills().await {
+            Ok(fills) => fills.into_iter().map(fill_event).collect(),
+            Err(e) => vec![EngineEvent::Alert {
+                source: "exchange".into(),
+                message: format!("poll fills failed: {:#}", e),
+            }],
+        }
+    }
+
+    /// Событие private WS. Fill'ы до первой цены (`ready == false`) не учитываются —
+    /// их подберёт REST-опрос, он же догоняет push'и, потерянные за время обрыва.
+    pub async fn on_account_event(&mut self, ev: AccountEvent, ready: bool) -> Vec<EngineEvent> {
+        match ev {
+            AccountEvent::Fill(f) if ready => {
+                self.orders.on_fill(f).map(fill_event).into_iter().collect()
+            }
+            AccountEvent::Fill(_) => Vec::new(),
+            AccountEvent::Order { order, open } => {
+                self.orders.on_order_update(order, open);
+                Vec::new()
+            }
+            AccountEvent::Wallet(w) => {
+                self.wallet = w;
+                Vec::new()
+            }
+            AccountEvent::Reconnected { attempt } => {
+                let mut out = vec![EngineEvent::Alert {
+                    source: "account".into(),
+                    message: format!("private WS reconnected (attempt {})", attempt),
+                }];
+                if ready {
+                    out.extend(self.poll_fills().await);
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Итог batch'ей; ошибки отдельных ордеров — Alert, engine продолжает работу
+fn sync_events(report: SyncReport) -> Vec<EngineEvent> {
+    let mut out = Vec::new();
+    if report.placed + report.cancelled + report.errors.len() > 0 {
+        out.push(EngineEvent::OrdersSynced {
+            placed: report.placed,
+            cancelled: report.cancelled,
+            failed: report.errors.len(),
+        });
+    }
+    out.extend(report.errors.into_iter().map(|message| EngineEvent::Alert {
+        source: "exchange".into(),
+        message,
+    }));
+    out
+}
+
+fn fill_event(f: TrackedFill) -> EngineEvent {
+    EngineEvent::Fill {
+        ts: f.fill.ts,
+        side: f.fill.side,
+        price: f.fill.price,
+        qty: f.fill.net_qty(),
+        fee: f.fill.fee,
+        realized_pnl: f.realized_pnl,
+    }
+}
+
+/// Следующее событие private WS; без live — никогда
+pub async fn next_account_event(
+    rx: Option<&mut mpsc::Receiver<AccountEvent>>,
+) -> Option<AccountEvent> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Money, TimestampMs};
+    use execution::orders::{Fill, LimitStatus, NewOrder};
+    use std::sync::Mutex;
+
+    use crate::session::tests::block_on;
+
+    /// Биржа в памяти: ордера выставляются и снимаются без ошибок
+    #[derive(Default)]
+    struct FakeGateway {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl OrderGateway for FakeGateway {
+        async fn open_orders(&self) -> Result<Vec<LiveOrder>> {
+            Ok(Vec::new())
+        }
+
+        async fn place(&self, order: &NewOrder) -> Result<String> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(format!("place {:?} {}", order.side, order.price.0));
+            Ok(format!("o{}", calls.len()))
+        }
+
+        async fn cancel(&self, order_id: &str) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("cancel {}", order_id));
+            Ok(())
+        }
+
+        async fn market_sell(&self, _link_id: &str, qty: Qty) -> Result<String> {
+            self.calls.lock().unwrap().push(format!("sell {}", qty.0));
+            Ok("m".into())
+        }
+
+        async fn place_batch(&self, orders: &[NewOrder]) -> Result<Vec<Result<String>>> {
+            let mut out = Vec::new();
+            for o in orders {
+                out.push(self.place(o).await);
+            }
+            Ok(out)
+        }
+
+        async fn cancel_batch(&self, order_ids: &[String]) -> Result<Vec<Result<()>>> {
+            let mut out = Vec::new();
+            for id in order_ids {
+                out.push(self.cancel(id).await);
+            }
+            Ok(out)
+        }
+
+        async fn fills_since(&self, _since: TimestampMs) -> Result<Vec<Fill>> {
+            Ok(Vec::new())
+        }
+
+        fn limit_status(&self) -> Option<LimitStatus> {
+            None
+        }
+    }
+
+    fn wallet(base: f64, quote: f64) -> WalletBalance {
+        WalletBalance {
+            total_equity: Money(quote),
+            base: Qty(base),
+            base_locked: Qty(0.0),
+            quote: Money(quote),
+            quote_locked: Money(0.0),
+        }
+    }
+
+    fn account() -> LiveAccount<FakeGateway> {
+        let start = wallet(1.0, 1000.0);
+        LiveAccount {
+            orders: OrderManager::new(
+                FakeGateway::default(),
+                Ledger::new(1000.0, 1.0, 100.0),
+                TimestampMs(0),
+            ),
+            planner: OrderPlanner::new(RateLimiter::new(100.0, 10)),
+            instrument: InstrumentInfo {
+                symbol: "ETHUSDT".into(),
+                base_coin: "ETH".into(),
+                quote_coin: "USDT".into(),
+                tick_size: Price(0.01),
+                qty_step: Qty(0.0001),
+                min_qty: Qty(0.0001),
+                min_notional: Money(1.0),
+            },
+            wallet: start.clone(),
+            start,
+        }
+    }
+
+    #[test]
+    fn desired_grid_is_placed_and_cancelled() {
+        let mut m = account();
+        let grid = EngineEvent::DesiredGrid(vec![
+            DesiredOrder {
+                side: Side::Buy,
+                price: Price(99.0),
+                qty: Qty(0.1),
+            },
+            DesiredOrder {
+                side: Side::Sell,
+                price: Price(101.0),
+                qty: Qty(0.1),
+            },
+        ]);
+        let out = block_on(m.apply_tick(&[grid], Price(100.0)));
+        assert!(
+            out.iter()
+                .any(|e| matches!(e, EngineEvent::OrdersSubmitted { places: 2, .. }))
+        );
+        assert_eq!(m.orders.open_orders().len(), 2);
+
+        let out = block_on(m.cancel_all());
+        assert!(matches!(out[0], EngineEvent::OrdersCancelled { count: 2 }));
+        assert!(m.orders.open_orders().is_empty());
+    }
+
+    #[test]
+    fn wallet_push_updates_inventory_and_early_fill_is_ignored() {
+        let mut m = account();
+        let out = block_on(m.on_account_event(AccountEvent::Wallet(wallet(2.0, 800.0)), false));
+        assert!(out.is_empty());
+        assert_eq!(m.inventory().base, Qty(2.0));
+        assert_eq!(m.inventory().quote, Money(800.0));
+
+        let fill = Fill {
+            exec_id: "e1".into(),
+            order_id: "o1".into(),
+            ts: TimestampMs(1),
+            side: Side::Buy,
+            price: Price(99.0),
+            qty: Qty(0.1),
+            fee: Money(0.01),
+            base_fee: Qty(0.0),
+        };
+        let out = block_on(m.on_account_event(AccountEvent::Fill(fill), false));
+        assert!(out.is_empty());
+    }
+}
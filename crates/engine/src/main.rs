@@ -1,29 +1,354 @@
-mod event;
-mod feed;
-mod sink;
-mod tick;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context;
+use clap::{CommandFactory, Parser, ValueEnum};
 use tokio::sync::mpsc;
 
-use bybit::ws::{MarketEvent, run_ws};
+use bybit::ws::{MarketEvent, WsConfig, WsTopic, run_ws};
 
-use core::types::{Bps, Money, Qty, Ratio};
+use core::types::{Bps, Money, Price, Qty, Ratio, TimestampMs};
+
+use exchange::bybit::BybitExchange;
+use exchange::traits::Exchange;
+
+use execution::sim::ExecutionModel;
 
 use state_machine::state::BotState;
 
-use mm::grid::{GridParams, Inventory};
+use mm::grid::{GridParams, Inventory, LotFilters};
+use mm::rebalance::RebalanceParams;
 
-use policy::mm_policy::MmPolicyParams;
+use policy::mm_policy::{DataQuality, MmPolicyParams};
 
 use structure::bos::BosParams;
+use structure::ltf::{LtfParams, LtfSignal, LtfTracker};
 use structure::pullback::PullbackParams;
 use structure::structure::{StructureParams, detect_structure};
 
-use feed::CandleFeed;
-use tick::{EngineCtx, TickInput, tick};
+use engine::allocator::StrategyKind;
+use engine::anchor::{AnchorMode, AnchorState};
+use engine::candle_builder::LocalCandles;
+use engine::capture::{CaptureWriter, read_capture, replay};
+use engine::clock::{Clock, ManualClock, SystemClock};
+use engine::config::{ConfigWatcher, EngineConfig, ReloadSignal};
+use engine::control::{CONTROL_TOKEN_ENV, ControlCommand, ControlHandle, serve};
+use engine::event::EngineEvent;
+use engine::feed::CandleFeed;
+use engine::feed_quality::{FeedMetrics, FeedMonitor, FeedQualityParams};
+use engine::heartbeat::{Heartbeat, HeartbeatStore, LoopBeat, watch_loop};
+use engine::live::{self, next_account_event};
+use engine::orders::{OrderPlanner, RateLimiter};
+use engine::pg_sink::PgEventSink;
+use engine::reconcile::{AccountState, OpenOrder, ReconcilePolicy, reconcile};
+use engine::risk::{RiskLimits, RiskManager};
+use engine::session::Session;
+use engine::shutdown::wait_for_signal;
+use engine::sink::{EventSink, FanoutSink, JsonLinesSink, TextSink};
+use engine::snapshot::EngineSnapshot;
+use engine::tick::{EngineCtx, LtfSignals, TickInput};
+use engine::trend::{TrendParams, TrendStrategy};
+use engine::venue::{PaperVenue, Venue};
+use engine::warmup::{fetch_history, interval_ms};
+
+
+/// Как часто проверять, что свечи ещё приходят
+const STALE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Допуск на задержку закрытой свечи от биржи
+const STALE_GRACE_MS: i64 = 15_000;
+/// Как часто локальный builder закрывает истёкшие свечи без новых сделок
+const CANDLE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum RunMode {
+    /// Только решения, без ордеров
+    Observe,
+    /// Живые данные + симулированное исполнение
+    Paper,
+    /// Живые данные + ордера на Bybit (ключи — BYBIT_API_KEY / BYBIT_API_SECRET)
+    Live,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SinkKind {
+    /// Человекочитаемые строки в stdout
+    Text,
+    /// JSON lines в stdout
+    Jsonl,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum CandleSource {
+    /// kline-топики Bybit (только 5m/1m)
+    Exchange,
+    /// Свечи собираются локально из потока сделок
+    Local,
+}
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(long, value_enum, default_value_t = RunMode::Observe)]
+    mode: RunMode,
+    /// --mode live: ордера на testnet Bybit
+    #[arg(long, default_value_t = false)]
+    testnet: bool,
+
+    #[arg(long, default_value_t = 1000.0)]
+    initial_quote: f64,
+    #[arg(long, default_value_t = 0.0)]
+    initial_base: f64,
+
+    #[arg(long, default_value_t = 10.0)]
+    fee_bps: f64,
+    #[arg(long, default_value_t = 8.0)]
+    spread_bps: f64,
+    #[arg(long, default_value_t = 2.0)]
+    slippage_bps: f64,
+
+    #[arg(long, value_enum, default_value_t = SinkKind::Text)]
+    sink: SinkKind,
+    /// Дополнительно писать события JSON lines в файл
+    #[arg(long)]
+    events_out: Option<String>,
+
+    /// Писать события в Postgres (DATABASE_URL) как run в orchestrator-схеме
+    #[arg(long, default_value_t = false)]
+    persist_pg: bool,
+    /// Существующий run (создан API), иначе создаётся новый
+    #[arg(long)]
+    run_id: Option<uuid::Uuid>,
+    #[arg(long, default_value = "live engine ETHUSDT")]
+    run_name: String,
+    /// Спотовая пара Bybit
+    #[arg(long, default_value = "ETHUSDT")]
+    symbol: String,
+
+    #[arg(long, value_enum, default_value_t = CandleSource::Exchange)]
+    candle_source: CandleSource,
+    /// HTF интервал в минутах
+    #[arg(long, default_value = "5")]
+    htf_interval: String,
+    /// LTF интервал в минутах
+    #[arg(long, default_value = "1")]
+    ltf_interval: String,
+    /// Локальные свечи: интервалы без сделок закрывать плоской свечой
+    #[arg(long, default_value_t = false)]
+    fill_gaps: bool,
+
+    /// Не подгружать историю через REST перед стартом
+    #[arg(long, default_value_t = false)]
+    no_warmup: bool,
+
+    /// При остановке продать весь base по рынку (после снятия ордеров)
+    #[arg(long, default_value_t = false)]
+    flatten_on_shutdown: bool,
+    /// Куда сохранить состояние engine при остановке
+    #[arg(long, default_value = "data/engine_state.json")]
+    state_file: String,
+    /// JSON с параметрами сетки/полос/risk; перечитывается при изменении и по SIGHUP
+    #[arg(long)]
+    config: Option<String>,
+    /// Как часто проверять изменение `--config`
+    #[arg(long, default_value_t = 2)]
+    config_poll_secs: u64,
+    /// Сверка биржи с сохранённым состоянием при старте
+    #[arg(long, value_enum, default_value_t = ReconcilePolicy::Adopt)]
+    reconcile: ReconcilePolicy,
+    /// Относительный допуск расхождения балансов
+    #[arg(long, default_value_t = 0.001)]
+    reconcile_tolerance: f64,
+
+    /// Адрес control API (например 127.0.0.1:9100); токен — --control-token или ENGINE_CONTROL_TOKEN
+    #[arg(long)]
+    control_addr: Option<String>,
+    #[arg(long)]
+    control_token: Option<String>,
+
+    /// Стратегии в процессе (через запятую): mm, trend
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "mm")]
+    strategies: Vec<StrategyKind>,
+    /// Доля стартового quote под тренд-стратегию (если запущены обе)
+    #[arg(long, default_value_t = 0.3)]
+    trend_share: f64,
+    #[arg(long, default_value_t = 20)]
+    trend_ema_fast: usize,
+    #[arg(long, default_value_t = 100)]
+    trend_ema_slow: usize,
+    #[arg(long, default_value_t = 2.5)]
+    trend_atr_stop_mult: f64,
+
+    /// Якорь сетки
+    #[arg(long, value_enum, default_value_t = AnchorMode::Mid)]
+    anchor_mode: AnchorMode,
+    /// Окно VWAP (HTF свечей) для --anchor-mode vwap
+    #[arg(long, default_value_t = 20)]
+    vwap_window: usize,
+
+    /// Как часто писать heartbeat (Postgres при --persist-pg, Redis при --heartbeat-redis)
+    #[arg(long, default_value_t = 5)]
+    heartbeat_secs: u64,
+    /// Писать heartbeat в Redis (REDIS_URL), ключ mmbot:heartbeat:<symbol>
+    #[arg(long, default_value_t = false)]
+    heartbeat_redis: bool,
+    /// Через сколько секунд без итераций главный цикл считается зависшим
+    #[arg(long, default_value_t = 30)]
+    loop_stall_secs: u64,
+    /// Снимать сетку, пока HTF свечи не приходят
+    #[arg(long, default_value_t = false)]
+    halt_on_stall: bool,
+    /// Задержка закрытой свечи, после которой MM уходит в Defensive / Disabled (мс)
+    #[arg(long, default_value_t = 3_000)]
+    feed_degraded_latency_ms: i64,
+    #[arg(long, default_value_t = 15_000)]
+    feed_bad_latency_ms: i64,
+    /// Пауза между сообщениями WS, после которой MM уходит в Defensive / Disabled (мс)
+    #[arg(long, default_value_t = 10_000)]
+    feed_degraded_gap_ms: i64,
+    #[arg(long, default_value_t = 30_000)]
+    feed_bad_gap_ms: i64,
+    /// Реконнектов WS за час, после которых MM выключается
+    #[arg(long, default_value_t = 5)]
+    feed_bad_reconnects_per_hour: usize,
+
+    /// Лимит стоимости позиции base (в quote)
+    #[arg(long)]
+    max_position_notional: Option<f64>,
+    /// Лимит реализованного убытка за UTC-сутки (в quote)
+    #[arg(long)]
+    max_daily_loss: Option<f64>,
+    #[arg(long)]
+    max_orders_per_min: Option<usize>,
+    /// Rate limit batch-запросов ордеров (запросов/с): сетка обновляется
+    /// diff'ом, отмены раньше выставлений, ближние уровни раньше дальних.
+    /// В live планировщик включён всегда (по умолчанию `LIVE_ORDER_RATE_LIMIT`)
+    #[arg(long)]
+    order_rate_limit: Option<f64>,
+    /// Сколько запросов можно отправить пачкой сверх rate limit
+    #[arg(long, default_value_t = 5)]
+    order_burst: usize,
+    /// Лимит просадки equity от пика (в quote)
+    #[arg(long)]
+    max_drawdown: Option<f64>,
+    /// Если файл существует — аварийная остановка (kill switch)
+    #[arg(long)]
+    kill_switch_file: Option<String>,
+
+    /// Записывать все market data события в `<dir>/<symbol>-<время>.jsonl`
+    #[arg(long)]
+    capture_dir: Option<String>,
+    /// Прогнать capture-файл через engine вместо WS
+    #[arg(long)]
+    replay: Option<String>,
+    /// Скорость replay (1 = реальное время, 0 = без пауз)
+    #[arg(long, default_value_t = 1.0)]
+    replay_speed: f64,
+}
+
+/// Локальные sink'и + Postgres (его нужно явно закрыть в конце сессии)
+struct Sinks {
+    local: FanoutSink,
+    pg: Option<PgEventSink>,
+}
+
+impl EventSink for Sinks {
+    fn consume(&mut self, events: &[EngineEvent]) -> anyhow::Result<()> {
+        self.local.consume(events)?;
+        if let Some(pg) = self.pg.as_mut() {
+            pg.consume(events)?;
+        }
+        Ok(())
+    }
+}
+
+/// Смена качества feed'а: Alert при деградации, Log при восстановлении
+fn feed_quality_event(quality: DataQuality, m: &FeedMetrics) -> EngineEvent {
+    let msg = format!(
+        "feed quality: {} (latency={:?}ms max_latency={}ms max_gap={}ms reconnects/h={})",
+        m.quality, m.last_latency_ms, m.max_latency_ms, m.max_gap_ms, m.reconnects_last_hour
+    );
+    if quality == DataQuality::Good {
+        EngineEvent::Log(msg)
+    } else {
+        EngineEvent::Alert {
+            source: "feed".into(),
+            message: msg,
+        }
+    }
+}
+
+async fn next_command(control: Option<&mut ControlHandle>) -> Option<ControlCommand> {
+    match control {
+        Some(c) => c.commands.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Перечитывает `--config`; валидный конфиг ждёт следующего тика
+fn reload_config(
+    args: &Args,
+    current: &EngineConfig,
+    pending: &mut Option<EngineConfig>,
+) -> EngineEvent {
+    let Some(path) = &args.config else {
+        return EngineEvent::Log("config reload: no --config".into());
+    };
+    match EngineConfig::load(path, current) {
+        Ok(cfg) => {
+            *pending = Some(cfg);
+            EngineEvent::Log(format!("config reload queued: {}", path))
+        }
+        Err(e) => EngineEvent::Alert {
+            source: "config".into(),
+            message: format!("config reload rejected: {:#}", e),
+        },
+    }
+}
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
+    let argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    backtest::capabilities::exit_if_requested(&Args::command(), &argv);
+    let args = Args::parse_from(argv);
+
+    let mut local = FanoutSink::new();
+    match args.sink {
+        SinkKind::Text => local.push(Box::new(TextSink)),
+        SinkKind::Jsonl => local.push(Box::new(JsonLinesSink::stdout(&args.symbol))),
+    }
+    if let Some(path) = &args.events_out {
+        local.push(Box::new(JsonLinesSink::append_to(path, &args.symbol)?));
+    }
+
+    let pg = if args.persist_pg {
+        let database_url =
+            std::env::var("DATABASE_URL").context("DATABASE_URL is required for --persist-pg")?;
+        let pool = sqlx::PgPool::connect(&database_url).await?;
+        sqlx::migrate!("../../migrations").run(&pool).await?;
+        let kind = match args.mode {
+            RunMode::Observe | RunMode::Live => "live",
+            RunMode::Paper => "paper",
+        };
+        let pg = PgEventSink::start(pool, args.run_id, &args.run_name, kind).await?;
+        eprintln!("persisting events to run {}", pg.run_id());
+        Some(pg)
+    } else {
+        None
+    };
+
+    // heartbeat: Postgres (если пишем run) и/или Redis
+    let mut heartbeat = HeartbeatStore::new(args.heartbeat_secs * 3);
+    if let Some(pg) = &pg {
+        heartbeat = heartbeat.with_pg(pg.pool().clone(), pg.run_id());
+    }
+    if args.heartbeat_redis {
+        let redis_url =
+            std::env::var("REDIS_URL").context("REDIS_URL is required for --heartbeat-redis")?;
+        heartbeat = heartbeat.with_redis(&redis_url).await?;
+    }
+
+    let mut sink = Sinks { local, pg };
+
     // --- configs ---
     let mm_policy = MmPolicyParams {
         soft_min: Ratio(0.40),
@@ -42,6 +367,14 @@ async fn main() {
         hard_min: Ratio(0.35),
         hard_max: Ratio(0.65),
         min_base_qty: Qty(0.0001),
+        filters: LotFilters::default(),
+    };
+
+    let rebalance = RebalanceParams {
+        target_base_ratio: Ratio(0.50),
+        tolerance: Ratio(0.02),
+        fee_rate: Ratio(args.fee_bps / 10_000.0),
+        min_quote_trade: Money(5.0),
     };
 
     let bos_params = BosParams {
@@ -58,78 +391,747 @@ async fn main() {
         BotState::IdleUSDT,
         mm_policy,
         grid,
+        rebalance,
         bos_params,
         pullback_params,
     );
+    ctx.anchor = AnchorState::new(args.anchor_mode, args.vwap_window);
+    ctx.risk = RiskManager::new(RiskLimits {
+        max_position_notional: args.max_position_notional.map(Money),
+        max_daily_loss: args.max_daily_loss.map(Money),
+        max_orders_per_min: args.max_orders_per_min,
+        max_drawdown: args.max_drawdown.map(Money),
+    });
+
+    // hot reload: файл поверх CLI, изменения применяются на следующем тике
+    let mut config = EngineConfig::of(&ctx);
+    let mut pending_config: Option<EngineConfig> = None;
+    let mut config_watcher = args.config.as_deref().map(ConfigWatcher::new);
+    if let Some(path) = &args.config {
+        config = EngineConfig::load(path, &config)?;
+        config.apply(&mut ctx);
+    }
 
     // HTF candle feed
     let mut feed = CandleFeed::new(50);
 
+    // LTF candle feed + свой детектор слома/восстановления
+    let mut ltf_feed = CandleFeed::new(120);
+    let mut ltf = LtfTracker::new();
+    let ltf_params = LtfParams {
+        epsilon_frac: 0.1,
+        recover_candles: 2,
+    };
+
     // structure params
     let structure_params = StructureParams {
         pivot_k: 1,
         min_atr_frac: 0.1,
     };
 
-    // inventory пока мок (потом из Bybit REST/account WS)
-    let inv = Inventory {
-        base: Qty(0.0),
-        quote: Money(1000.0),
+    // batch'и ордеров с приоритетом и rate limit (иначе сетка заменяется целиком)
+    if args.order_rate_limit.is_some_and(|r| r <= 0.0) {
+        anyhow::bail!("--order-rate-limit must be > 0");
+    }
+
+    // multi-strategy: MM и/или тренд на одном символе, у каждой свой под-счёт
+    if args.strategies.is_empty() {
+        anyhow::bail!("--strategies must not be empty");
+    }
+    let run_mm = args.strategies.contains(&StrategyKind::Mm);
+    let trend = args.strategies.contains(&StrategyKind::Trend).then(|| {
+        TrendStrategy::new(TrendParams {
+            ema_fast: args.trend_ema_fast,
+            ema_slow: args.trend_ema_slow,
+            atr_stop_mult: args.trend_atr_stop_mult,
+        })
+    });
+    let exec = ExecutionModel {
+        fee_bps: args.fee_bps,
+        spread_bps: args.spread_bps,
+        slippage_bps: args.slippage_bps,
+    };
+
+    // live: балансы и открытые ордера символа читаются с биржи при старте,
+    // дальше учёт по fill'ам (стартовый base оценивается по первой цене);
+    // ордера, fill'ы и балансы приходят push'ем из private WS
+    let mut account_rx = None;
+    // открытые ордера символа на старте: своими становятся только после сверки
+    let mut startup_orders = Vec::new();
+    let venue = match args.mode {
+        // observe: ордеров нет, inventory — стартовые балансы из CLI
+        RunMode::Observe => Venue::Observe {
+            inventory: Inventory {
+                base: Qty(args.initial_base),
+                quote: Money(args.initial_quote),
+            },
+        },
+        // paper: виртуальный портфель, cost basis base считаем по первой цене
+        RunMode::Paper => Venue::Paper(Box::new(PaperVenue::new(
+            exec,
+            args.strategies.clone(),
+            args.initial_quote,
+            args.initial_base,
+            args.trend_share,
+            args.order_rate_limit
+                .map(|r| OrderPlanner::new(RateLimiter::new(r, args.order_burst))),
+        ))),
+        RunMode::Live => {
+            if args.replay.is_some() {
+                anyhow::bail!("--replay cannot be combined with --mode live");
+            }
+            if trend.is_some() {
+                anyhow::bail!("--mode live supports only --strategies mm");
+            }
+            let start = live::connect(
+                &args.symbol,
+                args.testnet,
+                args.order_rate_limit,
+                args.order_burst,
+            )
+            .await?;
+            let m = &start.account;
+            sink.consume(&[EngineEvent::Log(format!(
+                "live: {} on {}: {} {} / {} {}, {} open orders",
+                args.symbol,
+                start.url,
+                m.start.base.0,
+                m.instrument.base_coin,
+                m.start.quote,
+                m.instrument.quote_coin,
+                start.startup_orders.len()
+            ))])?;
+            account_rx = Some(start.account_events);
+            startup_orders = start.startup_orders;
+            Venue::Live(Box::new(start.account))
+        }
     };
+    let mut session = Session::new(ctx, venue, trend, run_mm);
+
+    // тик и шаг объёма инструмента: сетка сразу в точности биржи;
+    // в replay сеть не трогаем — сетка без округления
+    let filters = match (&session.venue, &args.replay) {
+        (Venue::Live(m), _) => Some(m.instrument.filters()),
+        (_, None) => match BybitExchange::public().instrument(&args.symbol).await {
+            Ok(i) => Some(i.filters),
+            Err(e) => {
+                eprintln!("instrument info unavailable, grid is not rounded: {:#}", e);
+                None
+            }
+        },
+        (_, Some(_)) => None,
+    };
+    if let Some(f) = filters {
+        session.ctx.grid.filters = f;
+    }
 
-    // --- ws ---
+    // --- reconciliation ---
+    // в paper "биржа" — стартовые балансы из CLI, в live — аккаунт Bybit
+    if args.mode != RunMode::Observe {
+        let persisted = match EngineSnapshot::load(&args.state_file) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("failed to load state: {:#}", e);
+                None
+            }
+        };
+        let (quote, base) = match &session.venue {
+            Venue::Live(m) => (m.start.quote.0, m.start.base.0),
+            _ => (args.initial_quote, args.initial_base),
+        };
+        let exchange = AccountState {
+            quote,
+            base,
+            open_orders: startup_orders
+                .iter()
+                .map(|o| OpenOrder {
+                    id: o.order_id.clone(),
+                    side: o.side,
+                    price: o.price,
+                    qty: o.qty,
+                })
+                .collect(),
+        };
+
+        match reconcile(
+            persisted.as_ref(),
+            exchange,
+            args.reconcile,
+            args.reconcile_tolerance,
+        ) {
+            Ok(r) => {
+                let mut events: Vec<EngineEvent> = r
+                    .discrepancies
+                    .iter()
+                    .map(|d| EngineEvent::Alert {
+                        source: "reconcile".into(),
+                        message: d.to_string(),
+                    })
+                    .collect();
+                for id in &r.cancel {
+                    events.push(EngineEvent::Log(format!("reconcile: cancel orphan order {}", id)));
+                    if let Venue::Live(m) = &mut session.venue {
+                        if let Err(e) = m.orders.cancel(id).await {
+                            events.push(EngineEvent::Alert {
+                                source: "reconcile".into(),
+                                message: format!("cancel {} failed: {:#}", id, e),
+                            });
+                        }
+                    }
+                }
+                // принятые сверкой ордера дальше ведутся как свои
+                if let Venue::Live(m) = &mut session.venue {
+                    let kept = &r.account.open_orders;
+                    m.orders.adopt(
+                        startup_orders
+                            .drain(..)
+                            .filter(|o| kept.iter().any(|k| k.id == o.order_id)),
+                    );
+                }
+                events.push(EngineEvent::Log(format!(
+                    "reconcile ({:?}): persisted={} discrepancies={} quote={} base={}",
+                    args.reconcile,
+                    persisted.is_some(),
+                    r.discrepancies.len(),
+                    r.account.quote,
+                    r.account.base
+                )));
+                sink.consume(&events)?;
+            }
+            Err(discrepancies) => {
+                let events: Vec<EngineEvent> = discrepancies
+                    .iter()
+                    .map(|d| EngineEvent::Alert {
+                        source: "reconcile".into(),
+                        message: d.to_string(),
+                    })
+                    .collect();
+                sink.consume(&events)?;
+                let reason = format!(
+                    "reconciliation failed: {} discrepancies",
+                    discrepancies.len()
+                );
+                sink.consume(&[EngineEvent::Shutdown {
+                    reason: reason.clone(),
+                }])?;
+                if let Some(pg) = sink.pg.take() {
+                    pg.finish(Some(&reason)).await?;
+                }
+                anyhow::bail!(reason);
+            }
+        }
+    }
+
+    // replay: события из capture-файла вместо WS, время engine — из записей
+    let replay_src = match &args.replay {
+        Some(path) => {
+            let records = read_capture(path)?;
+            let start = records.first().map(|r| r.recv_ts).unwrap_or(0);
+            Some((records, ManualClock::new(TimestampMs(start))))
+        }
+        None => None,
+    };
+    let clock: Arc<dyn Clock> = match &replay_src {
+        Some((_, c)) => Arc::new(c.clone()),
+        None => Arc::new(SystemClock),
+    };
+    let htf_interval_ms = interval_ms(&args.htf_interval)?;
+    let ltf_interval_ms = interval_ms(&args.ltf_interval)?;
+
+    // свечи из сделок вместо kline-топиков биржи
+    let mut local = match args.candle_source {
+        CandleSource::Exchange => None,
+        CandleSource::Local => Some(LocalCandles::new(
+            htf_interval_ms,
+            ltf_interval_ms,
+            args.fill_gaps,
+        )),
+    };
+    let mut candle_flush = clock.sleep(CANDLE_FLUSH_INTERVAL);
+
+    let mut capture = match &args.capture_dir {
+        Some(dir) => {
+            let w = CaptureWriter::create(dir, &args.symbol, clock.now())?;
+            eprintln!("capturing market data to {}", w.path());
+            // stdout может быть JSONL-потоком событий; worker читает `artifacts:` и из stderr
+            eprintln!("artifacts: capture={}", w.path());
+            Some(w)
+        }
+        None => None,
+    };
+
+    sink.consume(&[EngineEvent::Log(format!("engine mode: {:?}", args.mode))])?;
+
+    // --- market data ---
     let (tx, mut rx) = mpsc::channel::<MarketEvent>(2048);
+    let mut htf_history = Vec::new();
+    let mut ltf_history = Vec::new();
+
+    if let Some((records, engine_clock)) = replay_src {
+        for r in records.iter().filter(|r| r.warmup) {
+            match MarketEvent::from(r.event.clone()) {
+                MarketEvent::Candle { interval, candle } if interval == args.htf_interval => {
+                    htf_history.push(candle)
+                }
+                MarketEvent::Candle { interval, candle } if interval == args.ltf_interval => {
+                    ltf_history.push(candle)
+                }
+                MarketEvent::Candle { .. }
+                | MarketEvent::Ticker { .. }
+                | MarketEvent::BookTop { .. }
+                | MarketEvent::Trade { .. }
+                | MarketEvent::Reconnected { .. } => {}
+            }
+        }
+
+        let speed = args.replay_speed;
+        tokio::spawn(async move {
+            replay(records, tx, engine_clock, &SystemClock, speed).await;
+        });
+    } else {
+        let ws_config = WsConfig {
+            symbol: args.symbol.clone(),
+            kline_intervals: match args.candle_source {
+                CandleSource::Exchange => {
+                    vec![args.htf_interval.clone(), args.ltf_interval.clone()]
+                }
+                CandleSource::Local => Vec::new(),
+            },
+            topics: vec![WsTopic::Ticker, WsTopic::Trades, WsTopic::OrderBook],
+        };
+        tokio::spawn(async move {
+            run_ws(ws_config, tx).await;
+        });
+
+        // WS уже подписан и буферизует свечи в канал; историю догружаем до "сейчас",
+        // а свечи из WS, которые уже пришли через REST, отбрасываем по ts.
+        if !args.no_warmup {
+            let api = BybitExchange::public();
+            let now_ms = clock.now().0;
+
+            match fetch_history(&api, &args.symbol, &args.htf_interval, feed.window, now_ms).await {
+                Ok(history) => htf_history = history,
+                Err(e) => eprintln!("warm-up HTF failed: {:#}", e),
+            }
+            let ltf = fetch_history(&api, &args.symbol, &args.ltf_interval, ltf_feed.window, now_ms);
+            match ltf.await {
+                Ok(history) => ltf_history = history,
+                Err(e) => eprintln!("warm-up LTF failed: {:#}", e),
+            }
+
+            if let Some(w) = capture.as_mut() {
+                for c in &htf_history {
+                    let ev = MarketEvent::Candle {
+                        interval: args.htf_interval.clone(),
+                        candle: *c,
+                    };
+                    w.write_warmup(clock.now(), &ev)?;
+                }
+                for c in &ltf_history {
+                    let ev = MarketEvent::Candle {
+                        interval: args.ltf_interval.clone(),
+                        candle: *c,
+                    };
+                    w.write_warmup(clock.now(), &ev)?;
+                }
+            }
+        }
+    }
+
+    // --- warm-up ---
+    let mut last_htf_ts = i64::MIN;
+    let mut last_ltf_ts = i64::MIN;
 
-    tokio::spawn(async move {
-        run_ws(tx).await;
+    if !htf_history.is_empty() {
+        for c in htf_history {
+            last_htf_ts = c.ts.0;
+            feed.push(c);
+            session.ctx.anchor.on_candle_close(&c);
+            if let Some(t) = session.trend.as_mut() {
+                t.warm_up(c.close);
+            }
+
+            let Some(atr) = feed.atr() else {
+                continue;
+            };
+            let ms = detect_structure(&feed.candles, structure_params);
+            session.ctx.bos.on_candle_close(&c, &ms, atr, session.ctx.bos_params);
+            session.ctx.pullback
+                .on_candle_close(&c, &session.ctx.bos, atr, session.ctx.pullback_params);
+        }
+        sink.consume(&[EngineEvent::Log(format!(
+            "warm-up HTF: candles={} bos={:?} pullback={}",
+            feed.candles.len(),
+            session.ctx.bos.state,
+            session.ctx.pullback.triggered
+        ))])?;
+    }
+
+    if !ltf_history.is_empty() {
+        for c in ltf_history {
+            last_ltf_ts = c.ts.0;
+            ltf_feed.push(c);
+
+            let Some(atr) = ltf_feed.atr() else {
+                continue;
+            };
+            let ms = detect_structure(&ltf_feed.candles, structure_params);
+            ltf.on_candle_close(&c, &ms, atr, ltf_params);
+        }
+        sink.consume(&[EngineEvent::Log(format!(
+            "warm-up LTF: candles={} broken={}",
+            ltf_feed.candles.len(),
+            ltf.broken
+        ))])?;
+    }
+    // слом LTF из истории держится, восстановление уходит в тик только живое
+    let mut ltf_signals = LtfSignals::new(ltf.broken);
+
+    // mid книги — якорь сетки вместо close свечи, пока WS не оборвался
+    let mut book_mid: Option<Price> = None;
+
+    let shutdown = wait_for_signal();
+    tokio::pin!(shutdown);
+
+    let mut stale_check = clock.sleep(STALE_CHECK_INTERVAL);
+    let mut htf_stale = false;
+    let mut ltf_stale = false;
+
+    // качество feed'а: задержка свечей, паузы, реконнекты -> режим policy
+    let mut feed_monitor = FeedMonitor::new(FeedQualityParams {
+        degraded_latency_ms: args.feed_degraded_latency_ms,
+        bad_latency_ms: args.feed_bad_latency_ms,
+        degraded_gap_ms: args.feed_degraded_gap_ms,
+        bad_gap_ms: args.feed_bad_gap_ms,
+        bad_reconnects_per_hour: args.feed_bad_reconnects_per_hour,
     });
+    let mut feed_quality = DataQuality::Good;
+
+    let heartbeat_interval = Duration::from_secs(args.heartbeat_secs.max(1));
+    let mut heartbeat_due = clock.sleep(heartbeat_interval);
+
+    // watchdog главного цикла (отдельная задача)
+    let loop_beat = LoopBeat::default();
+    tokio::spawn(watch_loop(
+        loop_beat.clone(),
+        clock.clone(),
+        STALE_CHECK_INTERVAL,
+        args.loop_stall_secs as i64 * 1000,
+    ));
+
+    // control API: состояние и ручные команды оператора
+    let mut control = match &args.control_addr {
+        Some(addr) => {
+            let token = args
+                .control_token
+                .clone()
+                .or_else(|| std::env::var(CONTROL_TOKEN_ENV).ok())
+                .with_context(|| format!("--control-token or {} is required", CONTROL_TOKEN_ENV))?;
+            let addr = addr.parse().context("invalid --control-addr")?;
+            let (handle, local) = serve(addr, token).await?;
+            sink.consume(&[EngineEvent::Log(format!("control API on {}", local))])?;
+            Some(handle)
+        }
+        None => None,
+    };
+
+    let config_poll_interval = Duration::from_secs(args.config_poll_secs.max(1));
+    let mut config_poll = clock.sleep(config_poll_interval);
+    let mut reload_signal = ReloadSignal::new();
 
     // --- event loop ---
-    while let Some(ev) = rx.recv().await {
-        match ev {
-            MarketEvent::Candle5m(candle) => {
-                feed.push(candle);
-
-                let (Some(atr), Some(mid)) = (feed.atr(), feed.mid()) else {
-                    continue;
-                };
-
-                // структура на окне
-                let ms = detect_structure(&feed.candles, structure_params);
-
-                println!(
-                    "HTF close={} last_high={:?} last_low={:?} bos={:?} pullback={}",
-                    mid.0,
-                    ms.last_high.map(|p| p.0),
-                    ms.last_low.map(|p| p.0),
-                    ctx.bos.state,
-                    ctx.pullback.triggered
-                );
+    let reason = loop {
+        loop_beat.beat(clock.now().0);
+
+        // raw = пришло из WS/replay; иначе — свечи, закрытые локальным builder'ом по таймеру
+        let (incoming, raw) = tokio::select! {
+            ev = rx.recv() => match ev {
+                Some(ev) => (vec![ev], true),
+                None => break "market data stream closed".to_string(),
+            },
+            _ = &mut candle_flush, if local.is_some() => {
+                candle_flush = clock.sleep(CANDLE_FLUSH_INTERVAL);
+                let built = local.as_mut().map(|l| l.flush(clock.now())).unwrap_or_default();
+                (built, false)
+            }
+            sig = &mut shutdown => break format!("signal {}", sig),
+            Some(cmd) = next_command(control.as_mut()) => {
+                let events = session.on_command(cmd, clock.now()).await;
+                sink.consume(&events)?;
+                (Vec::new(), false)
+            }
+            Some(ev) = next_account_event(account_rx.as_mut()) => {
+                let events = session.on_account_event(ev).await;
+                sink.consume(&events)?;
+                continue;
+            }
+            _ = &mut config_poll, if config_watcher.is_some() => {
+                config_poll = clock.sleep(config_poll_interval);
+                if config_watcher.as_mut().is_some_and(|w| w.changed()) {
+                    sink.consume(&[reload_config(&args, &config, &mut pending_config)])?;
+                }
+                continue;
+            }
+            _ = reload_signal.recv() => {
+                if args.config.is_some() {
+                    sink.consume(&[reload_config(&args, &config, &mut pending_config)])?;
+                }
+                continue;
+            }
+            _ = &mut stale_check => {
+                stale_check = clock.sleep(STALE_CHECK_INTERVAL);
+
+                // пишем только смену статуса, чтобы не спамить каждые 10с
+                let now = clock.now();
+                let htf = feed.is_stale(htf_interval_ms, now, STALE_GRACE_MS);
+                let ltf = ltf_feed.is_stale(ltf_interval_ms, now, STALE_GRACE_MS);
+                if htf != htf_stale || ltf != ltf_stale {
+                    htf_stale = htf;
+                    ltf_stale = ltf;
 
-                // обновить BOS
-                let last = feed.candles.last().unwrap();
-                ctx.bos.on_candle_close(last, &ms, atr, ctx.bos_params);
+                    let msg = format!("feed staleness: htf_stale={} ltf_stale={}", htf, ltf);
+                    let mut events = vec![if htf || ltf {
+                        EngineEvent::Alert {
+                            source: "watchdog".into(),
+                            message: msg,
+                        }
+                    } else {
+                        EngineEvent::Log(msg)
+                    }];
 
-                // обновить Pullback
-                ctx.pullback
-                    .on_candle_close(last, &ctx.bos, atr, ctx.pullback_params);
+                    // без свежих свечей сетка устаревает — снимаем до следующего тика
+                    if htf && args.halt_on_stall {
+                        events.extend(session.cancel_grid().await);
+                    }
+                    sink.consume(&events)?;
+                }
 
-                // тик engine
-                let input = TickInput {
-                    mid,
-                    atr,
-                    inv,
-                    ltf_broken_down: false,
-                    ltf_recovered: false,
-                };
+                let quality = feed_monitor.quality(now);
+                if quality != feed_quality {
+                    feed_quality = quality;
+                    let mut events = vec![feed_quality_event(quality, &feed_monitor.metrics(now))];
 
-                let events = tick(&mut ctx, input);
-                sink::consume(events);
+                    // policy всё равно выключит MM на следующем тике, сетку снимаем сразу
+                    if quality == DataQuality::Bad {
+                        events.extend(session.cancel_grid().await);
+                    }
+                    sink.consume(&events)?;
+                }
+                continue;
+            }
+            _ = &mut heartbeat_due => {
+                heartbeat_due = clock.sleep(heartbeat_interval);
+
+                let now = clock.now();
+                let events = session.on_heartbeat(now).await;
+                sink.consume(&events)?;
+
+                heartbeat.publish(Heartbeat {
+                    ts_ms: clock.now().0,
+                    symbol: args.symbol.clone(),
+                    mode: format!("{:?}", args.mode),
+                    state: format!("{:?}", session.ctx.state),
+                    last_htf_ts: (last_htf_ts != i64::MIN).then_some(last_htf_ts),
+                    last_ltf_ts: (last_ltf_ts != i64::MIN).then_some(last_ltf_ts),
+                    equity: session
+                        .equity
+                        .as_ref()
+                        .zip(session.last_mid)
+                        .map(|(t, mid)| t.equity(mid).0),
+                    htf_stale,
+                    ltf_stale,
+                    feed: feed_monitor.metrics(now),
+                });
+                continue;
+            }
+        };
+
+        if raw {
+            let now = clock.now();
+            for ev in &incoming {
+                feed_monitor.on_message(now);
+                if let MarketEvent::Reconnected { attempt } = ev {
+                    feed_monitor.on_reconnect(now);
+                    sink.consume(&[EngineEvent::Alert {
+                        source: "feed".into(),
+                        message: format!("WS reconnected (attempt {})", attempt),
+                    }])?;
+                }
+            }
+            if let Some(w) = capture.as_mut() {
+                for ev in &incoming {
+                    w.write(clock.now(), ev)?;
+                }
             }
+        }
+
+        let batch: Vec<MarketEvent> = match local.as_mut() {
+            Some(l) if raw => incoming.into_iter().flat_map(|ev| l.on_event(ev)).collect(),
+            _ => incoming,
+        };
+
+        for ev in batch {
+            match ev {
+                MarketEvent::Candle { interval, candle } if interval == args.htf_interval => {
+                    if candle.ts.0 <= last_htf_ts {
+                        continue;
+                    }
+                    last_htf_ts = candle.ts.0;
+                    feed_monitor.on_candle(clock.now(), candle.ts, htf_interval_ms);
+
+                    // лимитки, выставленные прошлым тиком, проверяем на этой свече
+                    let fills = session.on_htf_candle(&candle).await;
+                    sink.consume(&fills)?;
+
+                    feed.push(candle);
+                    session.ctx.anchor.on_candle_close(&candle);
+
+                    let (Some(atr), Some(close)) = (feed.atr(), feed.mid()) else {
+                        continue;
+                    };
+                    let mid = book_mid.unwrap_or(close);
+
+                    // структура на окне
+                    let ms = detect_structure(&feed.candles, structure_params);
+
+                    let ctx = &mut session.ctx;
+                    sink.consume(&[EngineEvent::Log(format!(
+                        "HTF close={} last_high={:?} last_low={:?} bos={:?} pullback={}",
+                        close.0,
+                        ms.last_high.map(|p| p.0),
+                        ms.last_low.map(|p| p.0),
+                        ctx.bos.state,
+                        ctx.pullback.triggered
+                    ))])?;
+
+                    // обновить BOS
+                    let last = feed.candles.last().unwrap();
+                    ctx.bos.on_candle_close(last, &ms, atr, ctx.bos_params);
+
+                    // обновить Pullback
+                    ctx.pullback
+                        .on_candle_close(last, &ctx.bos, atr, ctx.pullback_params);
 
-            MarketEvent::Ticker { mid: _ } => {
-                // пока игнорируем, mid берём из close свечи
+                    // тик engine
+                    // kill switch: достаточно создать файл
+                    if let Some(path) = &args.kill_switch_file {
+                        if Path::new(path).exists() && !ctx.risk.kill_switch() {
+                            ctx.risk.engage_kill_switch();
+                        }
+                    }
+
+                    if let Some(new) = pending_config.take() {
+                        let changes = config.diff(&new);
+                        new.apply(ctx);
+                        config = new;
+                        if !changes.is_empty() {
+                            sink.consume(&[EngineEvent::ConfigChanged { changes }])?;
+                        }
+                    }
+
+                    let (ltf_broken_down, ltf_recovered) = ltf_signals.take();
+                    let input = TickInput {
+                        ts: clock.now(),
+                        mid,
+                        atr,
+                        inv: session.venue.inventory(),
+                        ltf_broken_down,
+                        ltf_recovered,
+                        data_quality: feed_monitor.quality(clock.now()),
+                    };
+                    let events = session.run_tick(input, candle.ts, mid, atr).await;
+                    sink.consume(&events)?;
+                }
+
+                MarketEvent::Candle { interval, candle } if interval == args.ltf_interval => {
+                    if candle.ts.0 <= last_ltf_ts {
+                        continue;
+                    }
+                    last_ltf_ts = candle.ts.0;
+                    feed_monitor.on_candle(clock.now(), candle.ts, ltf_interval_ms);
+
+                    ltf_feed.push(candle);
+
+                    let Some(atr) = ltf_feed.atr() else {
+                        continue;
+                    };
+
+                    let ms = detect_structure(&ltf_feed.candles, structure_params);
+                    let signal = ltf.on_candle_close(&candle, &ms, atr, ltf_params);
+                    ltf_signals.on_signal(signal);
+
+                    if signal != LtfSignal::None {
+                        sink.consume(&[EngineEvent::Log(format!(
+                            "LTF {:?}: close={} level={:?}",
+                            signal,
+                            candle.close.0,
+                            ltf.level.map(|p| p.0)
+                        ))])?;
+                    }
+                }
+
+                // mid для решений берём из книги или close свечи, но лимитки paper-режима
+                // исполняем по тикеру, чтобы не ждать закрытия свечи
+                MarketEvent::Ticker { mid } => {
+                    let events = session.on_price(clock.now(), mid);
+                    sink.consume(&events)?;
+                }
+
+                MarketEvent::BookTop { bid, ask } => {
+                    book_mid = Some(Price((bid.0 + ask.0) / 2.0));
+                    let events = session.on_book(clock.now(), bid, ask);
+                    sink.consume(&events)?;
+                }
+
+                // книга до нового snapshot'а неизвестна
+                MarketEvent::Reconnected { .. } => {
+                    book_mid = None;
+                    session.clear_book();
+                }
+
+                MarketEvent::Candle { .. } => {}
+
+                // сделка прошла по цене — лимитки paper-режима, которые она задела
+                MarketEvent::Trade { price, .. } => {
+                    let events = session.on_price(clock.now(), price);
+                    sink.consume(&events)?;
+                }
             }
         }
+
+        if let Some(c) = &control {
+            let mode = format!("{:?}", args.mode);
+            let status = session.control_status(
+                clock.now(),
+                &args.symbol,
+                &mode,
+                feed_monitor.metrics(clock.now()),
+            );
+            c.status.send_replace(status);
+        }
+    };
+
+    // --- shutdown ---
+    // снимаем сетку, по желанию закрываем позицию, сохраняем состояние
+    let ts = clock.now();
+    let mut events = session.shutdown(ts, args.flatten_on_shutdown).await;
+
+    let mode = format!("{:?}", args.mode);
+    let snapshot = session.snapshot(ts, &args.symbol, &mode, &reason);
+    match snapshot.save(&args.state_file) {
+        Ok(()) => events.push(EngineEvent::Log(format!(
+            "state saved: {}",
+            args.state_file
+        ))),
+        Err(e) => eprintln!("failed to save state: {:#}", e),
     }
+
+    events.push(EngineEvent::Shutdown { reason });
+    sink.consume(&events)?;
+
+    if let Some(pg) = sink.pg.take() {
+        pg.finish(None).await?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,291 @@
+use bybit::private::BATCH_LIMIT;
+use core::types::{Price, TimestampMs};
+use execution::orders::LimitStatus;
+use mm::grid::DesiredOrder;
+
+use crate::reconcile::OpenOrder;
+
+/// Относительный допуск, в котором стоящий ордер считается совпавшим с желаемым
+const MATCH_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone)]
+pub enum OrderAction {
+    Cancel(OpenOrder),
+    Place(DesiredOrder),
+}
+
+impl OrderAction {
+    fn price(&self) -> Price {
+        match self {
+            OrderAction::Cancel(o) => o.price,
+            OrderAction::Place(o) => o.price,
+        }
+    }
+}
+
+/// Что нужно снять и что выставить, чтобы на бирже стояла `desired`.
+///
+/// Совпавшие (сторона, цена, qty) ордера не трогаются — refresh
+/// неизменной сетки не тратит лимит запросов.
+pub fn diff_orders(open: &[OpenOrder], desired: &[DesiredOrder]) -> Vec<OrderAction> {
+    let mut kept = vec![false; open.len()];
+    let mut actions = Vec::new();
+
+    for d in desired {
+        let hit = open
+            .iter()
+            .enumerate()
+            .position(|(i, o)| !kept[i] && same_order(o, d));
+        match hit {
+            Some(i) => kept[i] = true,
+            None => actions.push(OrderAction::Place(*d)),
+        }
+    }
+    for (o, kept) in open.iter().zip(kept) {
+        if !kept {
+            actions.push(OrderAction::Cancel(o.clone()));
+        }
+    }
+    actions
+}
+
+fn same_order(o: &OpenOrder, d: &DesiredOrder) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() <= MATCH_TOLERANCE * a.abs().max(b.abs());
+    o.side == d.side && close(o.price.0, d.price.0) && close(o.qty.0, d.qty.0)
+}
+
+/// Приоритет отправки: сначала отмены (освобождают баланс и убирают
+/// устаревшие цены), затем выставления; внутри — от mid наружу.
+pub fn prioritize(actions: &mut [OrderAction], mid: Price) {
+    actions.sort_by(|a, b| {
+        let key = |x: &OrderAction| {
+            (
+                matches!(x, OrderAction::Place(_)),
+                (x.price().0 - mid.0).abs(),
+            )
+        };
+        let (ka, kb) = (key(a), key(b));
+        ka.0.cmp(&kb.0).then(ka.1.total_cmp(&kb.1))
+    });
+}
+
+/// Один batch-запрос к бирже
+#[derive(Debug, Clone)]
+pub enum OrderBatch {
+    Cancel(Vec<OpenOrder>),
+    Place(Vec<DesiredOrder>),
+}
+
+impl OrderBatch {
+    pub fn len(&self) -> usize {
+        match self {
+            OrderBatch::Cancel(v) => v.len(),
+            OrderBatch::Place(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Режет упорядоченные действия на batch'и по `max_batch`,
+/// не смешивая отмены и выставления в одном запросе.
+pub fn batch(actions: Vec<OrderAction>, max_batch: usize) -> Vec<OrderBatch> {
+    let max_batch = max_batch.max(1);
+    let mut out: Vec<OrderBatch> = Vec::new();
+    for a in actions {
+        match (out.last_mut(), a) {
+            (Some(OrderBatch::Cancel(v)), OrderAction::Cancel(o)) if v.len() < max_batch => {
+                v.push(o)
+            }
+            (Some(OrderBatch::Place(v)), OrderAction::Place(o)) if v.len() < max_batch => v.push(o),
+            (_, OrderAction::Cancel(o)) => out.push(OrderBatch::Cancel(vec![o])),
+            (_, OrderAction::Place(o)) => out.push(OrderBatch::Place(vec![o])),
+        }
+    }
+    out
+}
+
+/// Token bucket на запросы к бирже.
+///
+/// Дополнительно слушает лимиты из ответов Bybit
+/// (`X-Bapi-Limit-Status` / `X-Bapi-Limit-Reset-Timestamp`): при нуле
+/// оставшихся запросов не отправляет ничего до сброса окна.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    tokens: f64,
+    last_ms: Option<i64>,
+    blocked_until_ms: i64,
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: f64, burst: usize) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            per_sec: per_sec.max(0.0),
+            burst,
+            tokens: burst,
+            last_ms: None,
+            blocked_until_ms: i64::MIN,
+        }
+    }
+
+    fn refill(&mut self, now: TimestampMs) {
+        if let Some(last) = self.last_ms {
+            let dt = (now.0 - last).max(0) as f64 / 1000.0;
+            self.tokens = (self.tokens + dt * self.per_sec).min(self.burst);
+        }
+        self.last_ms = Some(now.0);
+    }
+
+    /// Забирает токен на один запрос, `false` — запрос надо отложить
+    pub fn try_acquire(&mut self, now: TimestampMs) -> bool {
+        self.refill(now);
+        if now.0 < self.blocked_until_ms || self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Лимит, который сообщила биржа в ответе
+    pub fn on_limit_status(&mut self, status: LimitStatus) {
+        if status.remaining == 0 {
+            self.blocked_until_ms = self.blocked_until_ms.max(status.reset_at.0);
+        }
+    }
+}
+
+/// Итог одного refresh'а сетки
+#[derive(Debug, Clone, Default)]
+pub struct OrderPlan {
+    /// Batch'и, на которые хватило лимита, в порядке отправки
+    pub batches: Vec<OrderBatch>,
+    /// Действия, не влезшие в лимит (внешние уровни) — уйдут на следующем refresh
+    pub deferred: usize,
+}
+
+impl OrderPlan {
+    pub fn cancels(&self) -> usize {
+        self.count(|b| matches!(b, OrderBatch::Cancel(_)))
+    }
+
+    pub fn places(&self) -> usize {
+        self.count(|b| matches!(b, OrderBatch::Place(_)))
+    }
+
+    fn count(&self, f: impl Fn(&OrderBatch) -> bool) -> usize {
+        self.batches.iter().filter(|b| f(b)).map(|b| b.len()).sum()
+    }
+}
+
+/// Превращает новую желаемую сетку в batch-запросы в пределах rate limit
+#[derive(Debug, Clone)]
+pub struct OrderPlanner {
+    pub limiter: RateLimiter,
+    pub max_batch: usize,
+}
+
+impl OrderPlanner {
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self {
+            limiter,
+            max_batch: BATCH_LIMIT,
+        }
+    }
+
+    pub fn plan(
+        &mut self,
+        now: TimestampMs,
+        open: &[OpenOrder],
+        desired: &[DesiredOrder],
+        mid: Price,
+    ) -> OrderPlan {
+        let mut actions = diff_orders(open, desired);
+        prioritize(&mut actions, mid);
+
+        let mut plan = OrderPlan::default();
+        for b in batch(actions, self.max_batch) {
+            // порядок приоритетный: после первого отказа дальше не пробуем
+            if plan.deferred == 0 && self.limiter.try_acquire(now) {
+                plan.batches.push(b);
+            } else {
+                plan.deferred += b.len();
+            }
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::Qty;
+    use mm::grid::Side;
+
+    fn desired(side: Side, price: f64) -> DesiredOrder {
+        DesiredOrder {
+            side,
+            price: Price(price),
+            qty: Qty(0.1),
+        }
+    }
+
+    fn open(id: &str, side: Side, price: f64) -> OpenOrder {
+        OpenOrder {
+            id: id.into(),
+            side,
+            price: Price(price),
+            qty: Qty(0.1),
+        }
+    }
+
+    #[test]
+    fn diff_keeps_matching_orders_and_prioritizes_cancels_then_inner_levels() {
+        let open = [open("a", Side::Buy, 99.0), open("b", Side::Buy, 97.0)];
+        let want = [
+            desired(Side::Buy, 99.0),
+            desired(Side::Buy, 98.0),
+            desired(Side::Sell, 103.0),
+            desired(Side::Sell, 101.0),
+        ];
+        let mut actions = diff_orders(&open, &want);
+        assert_eq!(actions.len(), 4);
+
+        prioritize(&mut actions, Price(100.0));
+        assert!(matches!(&actions[0], OrderAction::Cancel(o) if o.id == "b"));
+        let places: Vec<f64> = actions[1..].iter().map(|a| a.price().0).collect();
+        assert_eq!(places, vec![101.0, 98.0, 103.0]);
+    }
+
+    #[test]
+    fn planner_batches_and_defers_outer_levels_over_rate_limit() {
+        let want: Vec<_> = (1..=25)
+            .map(|i| desired(Side::Buy, 100.0 - i as f64))
+            .collect();
+        let mut planner = OrderPlanner::new(RateLimiter::new(1.0, 2));
+
+        let plan = planner.plan(TimestampMs(0), &[], &want, Price(100.0));
+        assert_eq!(plan.batches.len(), 2);
+        assert_eq!(plan.places(), 20);
+        assert_eq!(plan.deferred, 5);
+        let OrderBatch::Place(first) = &plan.batches[0] else {
+            panic!("expected place batch");
+        };
+        assert_eq!(first[0].price, Price(99.0));
+
+        // через секунду — один токен на оставшиеся
+        let plan = planner.plan(TimestampMs(1000), &[], &want[20..], Price(100.0));
+        assert_eq!((plan.places(), plan.deferred), (5, 0));
+
+        planner.limiter.on_limit_status(LimitStatus {
+            remaining: 0,
+            reset_at: TimestampMs(5000),
+        });
+        let plan = planner.plan(TimestampMs(4000), &[], &want[..1], Price(100.0));
+        assert_eq!(plan.deferred, 1);
+    }
+}
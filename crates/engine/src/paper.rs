@@ -0,0 +1,388 @@
+use core::types::{Money, Price, Qty, TimestampMs};
+
+use execution::accounting::Ledger;
+use execution::sim::ExecutionModel;
+use mm::grid::{DesiredOrder, Inventory, Side};
+use mm::rebalance::RebalanceDecision;
+
+use crate::event::EngineEvent;
+use crate::orders::OrderBatch;
+use crate::reconcile::OpenOrder;
+
+/// Виртуальный портфель для paper-режима.
+///
+/// Market data — живая, а исполнение желаемых ордеров симулируется
+/// через `ExecutionModel`: лимитки исполняются по своей цене с комиссией,
+/// когда цена рынка до них доходит. При известной книге рыночные сделки
+/// идут по её bid/ask, без неё — по mid ± spread модели.
+#[derive(Debug, Clone)]
+pub struct PaperBroker {
+    pub exec: ExecutionModel,
+    pub ledger: Ledger,
+    /// Ордера, выставленные последним тиком
+    resting: Vec<DesiredOrder>,
+    /// Последние (bid, ask) книги
+    book: Option<(Price, Price)>,
+}
+
+impl PaperBroker {
+    pub fn new(exec: ExecutionModel, initial_quote: f64, initial_base: f64, mark: Price) -> Self {
+        Self {
+            exec,
+            ledger: Ledger::new(initial_quote, initial_base, mark.0),
+            resting: Vec::new(),
+            book: None,
+        }
+    }
+
+    pub fn inventory(&self) -> Inventory {
+        Inventory {
+            base: Qty(self.ledger.base),
+            quote: Money(self.ledger.quote),
+        }
+    }
+
+    pub fn equity(&self, mark: Price) -> Money {
+        Money(self.ledger.equity(mark.0))
+    }
+
+    /// Заменяет набор лимиток на новую желаемую сетку
+    pub fn set_orders(&mut self, orders: &[DesiredOrder]) {
+        self.resting = orders.to_vec();
+    }
+
+    pub fn resting_orders(&self) -> &[DesiredOrder] {
+        &self.resting
+    }
+
+    /// Стоящие лимитки как ордера биржи; id — позиция в списке
+    pub fn open_orders(&self) -> Vec<OpenOrder> {
+        self.resting
+            .iter()
+            .enumerate()
+            .map(|(i, o)| OpenOrder {
+                id: format!("paper-{}", i),
+                side: o.side,
+                price: o.price,
+                qty: o.qty,
+            })
+            .collect()
+    }
+
+    /// Применяет отправленные batch'и (id — из `open_orders`)
+    pub fn apply_batches(&mut self, batches: &[OrderBatch]) {
+        let mut cancelled = vec![false; self.resting.len()];
+        let mut placed = Vec::new();
+        for b in batches {
+            match b {
+                OrderBatch::Cancel(orders) => {
+                    for o in orders {
+                        let idx =
+                            o.id.strip_prefix("paper-")
+                                .and_then(|i| i.parse::<usize>().ok());
+                        if let Some(c) = idx.and_then(|i| cancelled.get_mut(i)) {
+                            *c = true;
+                        }
+                    }
+                }
+                OrderBatch::Place(orders) => placed.extend_from_slice(orders),
+            }
+        }
+        let mut cancelled = cancelled.into_iter();
+        self.resting.retain(|_| !cancelled.next().unwrap_or(false));
+        self.resting.extend(placed);
+    }
+
+    /// Прогоняет диапазон цен [low, high] через лимитки.
+    /// Для свечи это high/low, для тикера — low == high == mid.
+    pub fn on_price_range(&mut self, ts: TimestampMs, low: Price, high: Price) -> Vec<EngineEvent> {
+        let mut events = Vec::new();
+        let mut still_resting = Vec::with_capacity(self.resting.len());
+
+        for o in std::mem::take(&mut self.resting) {
+            let filled = match o.side {
+                Side::Buy if low.0 <= o.price.0 => self.fill_buy(ts, o),
+                Side::Sell if high.0 >= o.price.0 => self.fill_sell(ts, o),
+                _ => None,
+            };
+
+            match filled {
+                Some(ev) => events.push(ev),
+                None => still_resting.push(o),
+            }
+        }
+
+        self.resting = still_resting;
+        events
+    }
+
+    /// Новые лучшие цены книги: лимитка на покупку исполняется, когда ask
+    /// опустился до её цены, на продажу — когда bid поднялся до её цены
+    pub fn on_book(&mut self, ts: TimestampMs, bid: Price, ask: Price) -> Vec<EngineEvent> {
+        self.set_book(bid, ask);
+        self.on_price_range(ts, ask, bid)
+    }
+
+    /// Лучшие цены для рыночных сделок, без проверки лимиток
+    pub fn set_book(&mut self, bid: Price, ask: Price) {
+        self.book = Some((bid, ask));
+    }
+
+    /// Книга устарела (обрыв WS) — рыночные сделки снова по mid
+    pub fn clear_book(&mut self) {
+        self.book = None;
+    }
+
+    /// Снимает все лимитки, возвращает сколько было снято
+    pub fn cancel_all(&mut self) -> usize {
+        let n = self.resting.len();
+        self.resting.clear();
+        n
+    }
+
+    /// Продаёт весь base по рынку (через spread/slippage/fee `ExecutionModel`)
+    pub fn flatten(&mut self, ts: TimestampMs, mark: Price) -> Option<EngineEvent> {
+        self.market_sell(ts, mark, Qty(self.ledger.base))
+    }
+
+    /// Исполняет intent ребаланса по рынку
+    pub fn rebalance(
+        &mut self,
+        ts: TimestampMs,
+        mark: Price,
+        d: RebalanceDecision,
+    ) -> Option<EngineEvent> {
+        match d {
+            RebalanceDecision::BuyBase(qty) => self.market_buy(ts, mark, qty),
+            RebalanceDecision::SellBase(qty) => self.market_sell(ts, mark, qty),
+            RebalanceDecision::Noop => None,
+        }
+    }
+
+    pub fn market_buy(&mut self, ts: TimestampMs, mark: Price, qty: Qty) -> Option<EngineEvent> {
+        if qty.0 <= 0.0 || mark.0 <= 0.0 {
+            return None;
+        }
+
+        let fill_price = match self.book {
+            Some((_, ask)) => self.exec.buy_fill_price_at_ask(ask),
+            None => self.exec.buy_fill_price(mark),
+        };
+        let fee = self.exec.fee_quote(qty.0 * fill_price.0);
+        if qty.0 * fill_price.0 + fee > self.ledger.quote {
+            return None;
+        }
+        self.ledger.buy(qty.0, fill_price.0, fee);
+
+        Some(EngineEvent::Fill {
+            ts,
+            side: Side::Buy,
+            price: fill_price,
+            qty,
+            fee: Money(fee),
+            realized_pnl: None,
+        })
+    }
+
+    pub fn market_sell(&mut self, ts: TimestampMs, mark: Price, qty: Qty) -> Option<EngineEvent> {
+        let qty = Qty(qty.0.min(self.ledger.base));
+        if qty.0 <= 0.0 || mark.0 <= 0.0 {
+            return None;
+        }
+
+        let fill_price = match self.book {
+            Some((bid, _)) => self.exec.sell_fill_price_at_bid(bid),
+            None => self.exec.sell_fill_price(mark),
+        };
+        let fee = self.exec.fee_quote(qty.0 * fill_price.0);
+        let realized = self.ledger.sell(qty.0, fill_price.0, fee);
+
+        Some(EngineEvent::Fill {
+            ts,
+            side: Side::Sell,
+            price: fill_price,
+            qty,
+            fee: Money(fee),
+            realized_pnl: Some(Money(realized)),
+        })
+    }
+
+    fn fill_buy(&mut self, ts: TimestampMs, o: DesiredOrder) -> Option<EngineEvent> {
+        let gross = o.qty.0 * o.price.0;
+        let fee = self.exec.fee_quote(gross);
+        if o.qty.0 <= 0.0 || gross + fee > self.ledger.quote {
+            return None;
+        }
+        self.ledger.buy(o.qty.0, o.price.0, fee);
+
+        Some(EngineEvent::Fill {
+            ts,
+            side: Side::Buy,
+            price: o.price,
+            qty: o.qty,
+            fee: Money(fee),
+            realized_pnl: None,
+        })
+    }
+
+    fn fill_sell(&mut self, ts: TimestampMs, o: DesiredOrder) -> Option<EngineEvent> {
+        let qty = o.qty.0.min(self.ledger.base);
+        if qty <= 0.0 {
+            return None;
+        }
+
+        let fee = self.exec.fee_quote(qty * o.price.0);
+        let realized = self.ledger.sell(qty, o.price.0, fee);
+
+        Some(EngineEvent::Fill {
+            ts,
+            side: Side::Sell,
+            price: o.price,
+            qty: Qty(qty),
+            fee: Money(fee),
+            realized_pnl: Some(Money(realized)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec() -> ExecutionModel {
+        ExecutionModel {
+            fee_bps: 10.0,
+            spread_bps: 0.0,
+            slippage_bps: 0.0,
+        }
+    }
+
+    fn order(side: Side, price: f64, qty: f64) -> DesiredOrder {
+        DesiredOrder {
+            side,
+            price: Price(price),
+            qty: Qty(qty),
+        }
+    }
+
+    #[test]
+    fn buy_fills_only_when_price_reaches_limit() {
+        let mut b = PaperBroker::new(exec(), 1000.0, 0.0, Price(100.0));
+        b.set_orders(&[order(Side::Buy, 99.0, 1.0)]);
+
+        let ev = b.on_price_range(TimestampMs(1), Price(99.5), Price(100.5));
+        assert!(ev.is_empty());
+        assert_eq!(b.resting_orders().len(), 1);
+
+        let ev = b.on_price_range(TimestampMs(2), Price(98.5), Price(100.0));
+        assert_eq!(ev.len(), 1);
+        assert!(b.resting_orders().is_empty());
+        assert!((b.ledger.base - 1.0).abs() < 1e-12);
+        assert!(b.ledger.quote < 1000.0 - 99.0);
+    }
+
+    #[test]
+    fn apply_batches_cancels_by_id_and_keeps_untouched_orders() {
+        let mut b = PaperBroker::new(exec(), 1000.0, 1.0, Price(100.0));
+        b.set_orders(&[order(Side::Buy, 99.0, 1.0), order(Side::Sell, 101.0, 1.0)]);
+
+        let open = b.open_orders();
+        b.apply_batches(&[
+            OrderBatch::Cancel(vec![open[0].clone()]),
+            OrderBatch::Place(vec![order(Side::Buy, 98.0, 1.0)]),
+        ]);
+        let prices: Vec<f64> = b.resting_orders().iter().map(|o| o.price.0).collect();
+        assert_eq!(prices, vec![101.0, 98.0]);
+    }
+
+    #[test]
+    fn sell_realizes_pnl_against_cost_basis() {
+        let mut b = PaperBroker::new(exec(), 0.0, 1.0, Price(100.0));
+        b.set_orders(&[order(Side::Sell, 110.0, 1.0)]);
+
+        let ev = b.on_price_range(TimestampMs(1), Price(109.0), Price(111.0));
+        assert_eq!(ev.len(), 1);
+        assert_eq!(b.ledger.base, 0.0);
+        assert!(b.ledger.realized_pnl > 9.0 && b.ledger.realized_pnl < 10.0);
+    }
+
+    #[test]
+    fn flatten_sells_all_base_and_clears_cost_basis() {
+        let mut b = PaperBroker::new(exec(), 0.0, 2.0, Price(100.0));
+        b.set_orders(&[order(Side::Sell, 120.0, 1.0)]);
+
+        assert_eq!(b.cancel_all(), 1);
+        let ev = b.flatten(TimestampMs(1), Price(100.0));
+        assert!(matches!(
+            ev,
+            Some(EngineEvent::Fill {
+                side: Side::Sell,
+                ..
+            })
+        ));
+        assert_eq!(b.ledger.base, 0.0);
+        assert_eq!(b.ledger.cost_basis_quote, 0.0);
+        assert!(b.ledger.quote > 199.0 && b.ledger.quote < 200.0);
+        assert!(b.flatten(TimestampMs(2), Price(100.0)).is_none());
+    }
+
+    #[test]
+    fn rebalance_buy_moves_quote_into_base() {
+        let mut b = PaperBroker::new(exec(), 1000.0, 0.0, Price(100.0));
+
+        let ev = b.rebalance(
+            TimestampMs(1),
+            Price(100.0),
+            RebalanceDecision::BuyBase(Qty(5.0)),
+        );
+        assert!(matches!(
+            ev,
+            Some(EngineEvent::Fill {
+                side: Side::Buy,
+                ..
+            })
+        ));
+        assert_eq!(b.ledger.base, 5.0);
+        assert!(b.ledger.quote < 500.0 && b.ledger.quote > 499.0);
+        assert_eq!(b.ledger.cost_basis_quote, 1000.0 - b.ledger.quote);
+    }
+
+    #[test]
+    fn book_crossing_fills_limits_and_market_orders_use_bid_ask() {
+        let mut b = PaperBroker::new(exec(), 1000.0, 1.0, Price(100.0));
+        b.set_orders(&[order(Side::Buy, 99.0, 1.0), order(Side::Sell, 101.0, 1.0)]);
+
+        assert!(
+            b.on_book(TimestampMs(1), Price(99.5), Price(100.5))
+                .is_empty()
+        );
+        let ev = b.on_book(TimestampMs(2), Price(98.5), Price(99.0));
+        assert_eq!(ev.len(), 1);
+        assert_eq!(b.resting_orders()[0].side, Side::Sell);
+
+        let Some(EngineEvent::Fill { price, .. }) =
+            b.market_sell(TimestampMs(3), Price(100.0), Qty(1.0))
+        else {
+            panic!("expected fill");
+        };
+        assert_eq!(price, Price(98.5));
+
+        b.clear_book();
+        let Some(EngineEvent::Fill { price, .. }) =
+            b.market_buy(TimestampMs(4), Price(100.0), Qty(1.0))
+        else {
+            panic!("expected fill");
+        };
+        assert_eq!(price, Price(100.0));
+    }
+
+    #[test]
+    fn buy_is_skipped_when_quote_is_insufficient() {
+        let mut b = PaperBroker::new(exec(), 50.0, 0.0, Price(100.0));
+        b.set_orders(&[order(Side::Buy, 100.0, 1.0)]);
+
+        let ev = b.on_price_range(TimestampMs(1), Price(90.0), Price(100.0));
+        assert!(ev.is_empty());
+        assert_eq!(b.ledger.quote, 50.0);
+    }
+}
@@ -0,0 +1,355 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::event::EngineEvent;
+use crate::sink::{EventSink, format_text};
+
+/// Sink в Postgres схему orchestrator'а (runs / run_events / run_metrics).
+///
+/// Live-сессия выглядит в dashboard как обычный run: события идут в
+/// `run_events`, последний снимок портфеля и счётчики — в `run_metrics`.
+/// Запись идёт в фоне, чтобы цикл engine не ждал БД.
+pub struct PgEventSink {
+    run_id: Uuid,
+    pg: PgPool,
+    tx: Option<mpsc::UnboundedSender<Vec<EngineEvent>>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl PgEventSink {
+    /// Привязывается к существующему run (создан API/worker'ом)
+    /// или создаёт новый со статусом `running`.
+    pub async fn start(pg: PgPool, run_id: Option<Uuid>, name: &str, kind: &str) -> Result<Self> {
+        let run_id = match run_id {
+            Some(id) => {
+                sqlx::query(
+                    r#"
+                    UPDATE runs
+                    SET status = 'running', started_at = COALESCE(started_at, NOW())
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(id)
+                .execute(&pg)
+                .await
+                .context("failed to mark live run as running")?;
+                id
+            }
+            None => {
+                let id = Uuid::new_v4();
+                sqlx::query(
+                    r#"
+                    INSERT INTO runs (id, name, kind, status, created_at, started_at)
+                    VALUES ($1, $2, $3, 'running', NOW(), NOW())
+                    "#,
+                )
+                .bind(id)
+                .bind(name)
+                .bind(kind)
+                .execute(&pg)
+                .await
+                .context("failed to create live run")?;
+                id
+            }
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let writer = tokio::spawn(write_loop(pg.clone(), run_id, rx));
+
+        Ok(Self {
+            run_id,
+            pg,
+            tx: Some(tx),
+            writer: Some(writer),
+        })
+    }
+
+    pub fn run_id(&self) -> Uuid {
+        self.run_id
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pg
+    }
+
+    /// Дописывает очередь и закрывает run со статусом `completed` / `failed`
+    pub async fn finish(mut self, error: Option<&str>) -> Result<()> {
+        self.tx.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.await;
+        }
+
+        let status = if error.is_some() {
+            "failed"
+        } else {
+            "completed"
+        };
+        sqlx::query(
+            r#"
+            UPDATE runs
+            SET status = $2, ended_at = NOW(), exit_code = $3, error = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(self.run_id)
+        .bind(status)
+        .bind(if error.is_some() { 1 } else { 0 })
+        .bind(error)
+        .execute(&self.pg)
+        .await?;
+        Ok(())
+    }
+}
+
+impl EventSink for PgEventSink {
+    fn consume(&mut self, events: &[EngineEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let Some(tx) = &self.tx else {
+            anyhow::bail!("pg sink is closed");
+        };
+        tx.send(events.to_vec())
+            .map_err(|_| anyhow::anyhow!("pg sink writer stopped"))
+    }
+}
+
+async fn write_loop(pg: PgPool, run_id: Uuid, mut rx: mpsc::UnboundedReceiver<Vec<EngineEvent>>) {
+    let mut metrics = RunMetrics::default();
+
+    while let Some(batch) = rx.recv().await {
+        let mut metrics_dirty = false;
+
+        for e in &batch {
+            metrics_dirty |= metrics.apply(e);
+            let (level, message) = event_row(e);
+            if let Err(e) = append_event(&pg, run_id, level, &message).await {
+                eprintln!("pg sink: append event failed: {:#}", e);
+            }
+        }
+
+        if metrics_dirty {
+            if let Err(e) = upsert_metrics(&pg, run_id, &metrics.payload).await {
+                eprintln!("pg sink: upsert metrics failed: {:#}", e);
+            }
+        }
+    }
+}
+
+/// `payload` строки `run_metrics`: счётчики и последний снимок портфеля
+#[derive(Default)]
+struct RunMetrics {
+    payload: serde_json::Map<String, serde_json::Value>,
+    fills: u64,
+    transitions: u64,
+    alerts: u64,
+    realized_pnl: f64,
+}
+
+impl RunMetrics {
+    /// Учитывает событие; `true` — `payload` изменился
+    fn apply(&mut self, e: &EngineEvent) -> bool {
+        let metrics = &mut self.payload;
+        match e {
+            EngineEvent::Fill {
+                realized_pnl: pnl, ..
+            } => {
+                self.fills += 1;
+                self.realized_pnl += pnl.map(|p| p.0).unwrap_or(0.0);
+                metrics.insert("fills".into(), json!(self.fills));
+                metrics.insert("realized_pnl".into(), json!(self.realized_pnl));
+            }
+            EngineEvent::EquitySnapshot {
+                ts,
+                quote,
+                base,
+                mark,
+                equity,
+                unrealized_pnl,
+                drawdown,
+                ..
+            } => {
+                metrics.insert("last_ts".into(), json!(ts.0));
+                metrics.insert("final_quote".into(), json!(quote.0));
+                metrics.insert("final_base".into(), json!(base.0));
+                metrics.insert("mark".into(), json!(mark.0));
+                metrics.insert("final_equity".into(), json!(equity.0));
+                metrics.insert("unrealized_pnl".into(), json!(unrealized_pnl.0));
+                let max_dd = metrics
+                    .get("max_drawdown")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.0)
+                    .max(drawdown.0);
+                metrics.insert("max_drawdown".into(), json!(max_dd));
+            }
+            EngineEvent::Transition { to, .. } => {
+                self.transitions += 1;
+                metrics.insert("state".into(), json!(format!("{:?}", to)));
+                metrics.insert("transitions".into(), json!(self.transitions));
+            }
+            EngineEvent::Shutdown { reason } => {
+                metrics.insert("shutdown_reason".into(), json!(reason));
+            }
+            EngineEvent::Alert { .. } => {
+                self.alerts += 1;
+                metrics.insert("alerts".into(), json!(self.alerts));
+            }
+            EngineEvent::DesiredGrid(orders) => {
+                metrics.insert("open_orders".into(), json!(orders.len()));
+            }
+            EngineEvent::PolicyDecision { .. }
+            | EngineEvent::TrendDecision { .. }
+            | EngineEvent::RebalanceIntent(_)
+            | EngineEvent::OrdersCancelled { .. }
+            | EngineEvent::OrdersSubmitted { .. }
+            | EngineEvent::OrdersSynced { .. }
+            | EngineEvent::ConfigChanged { .. }
+            | EngineEvent::Log(_) => return false,
+        }
+        true
+    }
+}
+
+/// (level, message) строки `run_events`
+fn event_row(e: &EngineEvent) -> (&'static str, String) {
+    let level = match e {
+        EngineEvent::Alert { .. } => "warn",
+        _ => "info",
+    };
+    (level, format_text(e))
+}
+
+async fn append_event(pg: &PgPool, run_id: Uuid, level: &str, message: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO run_events (run_id, ts, level, message)
+        VALUES ($1, NOW(), $2, $3)
+        "#,
+    )
+    .bind(run_id)
+    .bind(level)
+    .bind(message)
+    .execute(pg)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_metrics(
+    pg: &PgPool,
+    run_id: Uuid,
+    metrics: &serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO run_metrics (run_id, payload, updated_at)
+        VALUES ($1, $2, NOW())
+        ON CONFLICT (run_id)
+        DO UPDATE SET payload = EXCLUDED.payload, updated_at = NOW()
+        "#,
+    )
+    .bind(run_id)
+    .bind(serde_json::Value::Object(metrics.clone()))
+    .execute(pg)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::types::{Money, Price, Qty, TimestampMs};
+    use mm::grid::Side;
+    use state_machine::cause::TransitionCause;
+    use state_machine::state::BotState;
+
+    use super::*;
+
+    fn fill(pnl: Option<f64>) -> EngineEvent {
+        EngineEvent::Fill {
+            ts: TimestampMs(1),
+            side: Side::Sell,
+            price: Price(2000.0),
+            qty: Qty(0.1),
+            fee: Money(0.2),
+            realized_pnl: pnl.map(Money),
+        }
+    }
+
+    fn snapshot(ts: i64, equity: f64, drawdown: f64) -> EngineEvent {
+        EngineEvent::EquitySnapshot {
+            ts: TimestampMs(ts),
+            quote: Money(500.0),
+            base: Qty(0.25),
+            mark: Price(2000.0),
+            equity: Money(equity),
+            realized_pnl: Money(3.0),
+            unrealized_pnl: Money(-1.5),
+            fees: Money(0.4),
+            break_even: None,
+            drawdown: Money(drawdown),
+        }
+    }
+
+    #[test]
+    fn events_fold_into_run_metrics_payload() {
+        let mut m = RunMetrics::default();
+        let events = [
+            fill(Some(2.0)),
+            fill(None),
+            fill(Some(1.0)),
+            snapshot(10, 1000.0, 12.0),
+            snapshot(20, 1010.0, 4.0),
+            EngineEvent::Transition {
+                from: BotState::IdleUSDT,
+                cause: TransitionCause::BosConfirmed,
+                to: BotState::MMNormal,
+            },
+            EngineEvent::Alert {
+                source: "risk".into(),
+                message: "halt".into(),
+            },
+            EngineEvent::Shutdown {
+                reason: "ctrl-c".into(),
+            },
+        ];
+        assert!(events.iter().all(|e| m.apply(e)));
+        assert!(!m.apply(&EngineEvent::Log("noise".into())));
+
+        assert_eq!(
+            serde_json::Value::Object(m.payload),
+            json!({
+                "fills": 3,
+                "realized_pnl": 3.0,
+                "last_ts": 20,
+                "final_quote": 500.0,
+                "final_base": 0.25,
+                "mark": 2000.0,
+                "final_equity": 1010.0,
+                "unrealized_pnl": -1.5,
+                "max_drawdown": 12.0,
+                "state": "MMNormal",
+                "transitions": 1,
+                "alerts": 1,
+                "shutdown_reason": "ctrl-c",
+            })
+        );
+    }
+
+    #[test]
+    fn alerts_are_warn_rows_and_the_rest_info() {
+        let alert = EngineEvent::Alert {
+            source: "control".into(),
+            message: "flatten deferred".into(),
+        };
+        assert_eq!(
+            event_row(&alert),
+            ("warn", "ALERT [control]: flatten deferred".to_string())
+        );
+        let (level, message) = event_row(&fill(Some(1.0)));
+        assert_eq!(level, "info");
+        assert_eq!(message, format_text(&fill(Some(1.0))));
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use core::types::{Price, Qty};
+use mm::grid::Side;
+
+use crate::snapshot::EngineSnapshot;
+
+/// Что делать, если состояние биржи расходится с сохранённым
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ValueEnum)]
+pub enum ReconcilePolicy {
+    /// Принять состояние биржи как есть (ордера тоже)
+    #[default]
+    Adopt,
+    /// Принять балансы, снять ордера, о которых engine не знает
+    CancelOrphans,
+    /// Не стартовать при любом расхождении
+    Refuse,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrder {
+    pub id: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+}
+
+/// Балансы и открытые ордера на бирже на момент старта
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountState {
+    pub quote: f64,
+    pub base: f64,
+    pub open_orders: Vec<OpenOrder>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Discrepancy {
+    Balance {
+        asset: &'static str,
+        persisted: f64,
+        exchange: f64,
+    },
+    /// Ордер на бирже, которого нет в сохранённом состоянии
+    /// (при остановке engine снимает свои ордера, неснятые попадают в snapshot)
+    OrphanOrder(OpenOrder),
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::Balance {
+                asset,
+                persisted,
+                exchange,
+            } => write!(
+                f,
+                "{} balance: persisted={} exchange={}",
+                asset, persisted, exchange
+            ),
+            Discrepancy::OrphanOrder(o) => write!(
+                f,
+                "orphan order {}: {:?} {} @ {}",
+                o.id, o.side, o.qty.0, o.price
+            ),
+        }
+    }
+}
+
+/// Итог сверки: с каким состоянием стартуем и какие ордера снять
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconciliation {
+    pub discrepancies: Vec<Discrepancy>,
+    pub account: AccountState,
+    pub cancel: Vec<String>,
+}
+
+/// Сравнивает биржу с сохранённым snapshot'ом.
+///
+/// Балансы сравниваются с относительным допуском `tolerance`: с live-учётом,
+/// если он есть в snapshot'е, иначе с суммой paper под-счетов.
+/// Свои ордера из live snapshot'а не считаются чужими.
+/// Без snapshot'а (первый запуск) сверять не с чем — биржа принимается.
+/// `Err` — политика `Refuse` и есть расхождения.
+pub fn reconcile(
+    persisted: Option<&EngineSnapshot>,
+    exchange: AccountState,
+    policy: ReconcilePolicy,
+    tolerance: f64,
+) -> Result<Reconciliation, Vec<Discrepancy>> {
+    let mut discrepancies = Vec::new();
+
+    let balances = persisted.and_then(|s| match &s.live {
+        Some(l) => Some((l.quote, l.base)),
+        // на бирже один счёт: под-счета стратегий складываются
+        None => s.paper.as_ref().map(|_| {
+            s.paper
+                .iter()
+                .chain(s.trend.iter())
+                .fold((0.0, 0.0), |(q, b), p| (q + p.quote, b + p.base))
+        }),
+    });
+    if let Some((quote, base)) = balances {
+        for (asset, persisted, exchange) in [
+            ("quote", quote, exchange.quote),
+            ("base", base, exchange.base),
+        ] {
+            let diff = (persisted - exchange).abs();
+            if diff > 1e-12 && diff > tolerance * persisted.abs().max(exchange.abs()) {
+                discrepancies.push(Discrepancy::Balance {
+                    asset,
+                    persisted,
+                    exchange,
+                });
+            }
+        }
+    }
+    if let Some(s) = persisted {
+        let own = s
+            .live
+            .as_ref()
+            .map(|l| l.orders.as_slice())
+            .unwrap_or_default();
+        discrepancies.extend(
+            exchange
+                .open_orders
+                .iter()
+                .filter(|o| !own.contains(&o.id))
+                .cloned()
+                .map(Discrepancy::OrphanOrder),
+        );
+    }
+
+    if policy == ReconcilePolicy::Refuse && !discrepancies.is_empty() {
+        return Err(discrepancies);
+    }
+
+    let mut account = exchange;
+    let mut cancel = Vec::new();
+    if policy == ReconcilePolicy::CancelOrphans {
+        for d in &discrepancies {
+            if let Discrepancy::OrphanOrder(o) = d {
+                cancel.push(o.id.clone());
+            }
+        }
+        account.open_orders.retain(|o| !cancel.contains(&o.id));
+    }
+
+    Ok(Reconciliation {
+        discrepancies,
+        account,
+        cancel,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{LiveSnapshot, PaperSnapshot};
+
+    fn persisted(quote: f64, base: f64) -> EngineSnapshot {
+        EngineSnapshot {
+            saved_at_ms: 0,
+            symbol: "ETHUSDT".into(),
+            mode: "Paper".into(),
+            reason: "SIGTERM".into(),
+            state: "MMNormal".into(),
+            bos_state: "None".into(),
+            pullback_triggered: false,
+            paper: Some(PaperSnapshot {
+                quote,
+                base,
+                cost_basis_quote: 0.0,
+                realized_pnl: 0.0,
+            }),
+            trend: None,
+            live: None,
+        }
+    }
+
+    fn exchange() -> AccountState {
+        AccountState {
+            quote: 1000.0,
+            base: 0.5,
+            open_orders: vec![OpenOrder {
+                id: "o1".into(),
+                side: Side::Buy,
+                price: Price(2000.0),
+                qty: Qty(0.1),
+            }],
+        }
+    }
+
+    #[test]
+    fn balances_within_tolerance_match_and_orphans_are_cancelled() {
+        let snap = persisted(1000.5, 0.5);
+        let r = reconcile(
+            Some(&snap),
+            exchange(),
+            ReconcilePolicy::CancelOrphans,
+            0.001,
+        )
+        .unwrap();
+
+        assert_eq!(r.discrepancies.len(), 1);
+        assert!(matches!(r.discrepancies[0], Discrepancy::OrphanOrder(_)));
+        assert_eq!(r.cancel, vec!["o1".to_string()]);
+        assert!(r.account.open_orders.is_empty());
+    }
+
+    #[test]
+    fn refuse_rejects_any_discrepancy_and_adopt_keeps_exchange() {
+        let snap = persisted(1000.0, 1.0);
+        let err = reconcile(Some(&snap), exchange(), ReconcilePolicy::Refuse, 0.001).unwrap_err();
+        assert!(
+            err.iter()
+                .any(|d| matches!(d, Discrepancy::Balance { asset: "base", .. }))
+        );
+
+        let r = reconcile(Some(&snap), exchange(), ReconcilePolicy::Adopt, 0.001).unwrap();
+        assert_eq!(r.account, exchange());
+        assert!(r.cancel.is_empty());
+
+        // первый запуск: сверять не с чем
+        let r = reconcile(None, exchange(), ReconcilePolicy::Refuse, 0.001).unwrap();
+        assert!(r.discrepancies.is_empty());
+    }
+
+    #[test]
+    fn live_snapshot_keeps_own_orders_and_checks_ledger() {
+        let mut snap = persisted(0.0, 0.0);
+        snap.live = Some(LiveSnapshot {
+            quote: 1000.0,
+            base: 0.4,
+            cost_basis_quote: 0.0,
+            realized_pnl: 0.0,
+            orders: vec!["o1".into()],
+        });
+        let r = reconcile(
+            Some(&snap),
+            exchange(),
+            ReconcilePolicy::CancelOrphans,
+            0.001,
+        )
+        .unwrap();
+
+        assert_eq!(r.discrepancies.len(), 1);
+        assert!(matches!(
+            r.discrepancies[0],
+            Discrepancy::Balance { asset: "base", .. }
+        ));
+        assert!(r.cancel.is_empty());
+        assert_eq!(r.account.open_orders, exchange().open_orders);
+    }
+}
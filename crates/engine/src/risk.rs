@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use core::types::{Money, Price, Qty, TimestampMs};
+use mm::grid::{DesiredOrder, Inventory, Side};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const MINUTE_MS: i64 = 60 * 1000;
+
+/// Лимиты риска. `None` — лимит выключен.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RiskLimits {
+    /// Максимальная стоимость base (по mid), включая buy-ордера сетки
+    pub max_position_notional: Option<Money>,
+    /// Максимальный реализованный убыток за UTC-сутки
+    pub max_daily_loss: Option<Money>,
+    /// Максимум отправленных на выставление ордеров за скользящую минуту
+    pub max_orders_per_min: Option<usize>,
+    /// Максимальная просадка equity от пика (realized + unrealized)
+    pub max_drawdown: Option<Money>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    KillSwitch,
+    PositionNotional { notional: Money, limit: Money },
+    DailyLoss { loss: Money, limit: Money },
+    OrderRate { orders: usize, limit: usize },
+    Drawdown { drawdown: Money, limit: Money },
+}
+
+impl RiskViolation {
+    /// Аварийная остановка; частота ордеров только придерживает сетку до конца окна
+    pub fn halts(&self) -> bool {
+        !matches!(self, RiskViolation::OrderRate { .. })
+    }
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::KillSwitch => write!(f, "kill switch engaged"),
+            RiskViolation::PositionNotional { notional, limit } => {
+                write!(f, "position notional {} > limit {}", notional, limit)
+            }
+            RiskViolation::DailyLoss { loss, limit } => {
+                write!(f, "daily realized loss {} > limit {}", loss, limit)
+            }
+            RiskViolation::OrderRate { orders, limit } => {
+                write!(f, "order rate {}/min > limit {}/min", orders, limit)
+            }
+            RiskViolation::Drawdown { drawdown, limit } => {
+                write!(f, "equity drawdown {} > limit {}", drawdown, limit)
+            }
+        }
+    }
+}
+
+/// Проверки риска перед выставлением ордеров.
+///
+/// Живёт в `EngineCtx`; `tick()` вызывает `check` до того, как отдать
+/// желаемую сетку наружу. Нарушение с `halts()` = аварийная остановка.
+#[derive(Debug, Clone, Default)]
+pub struct RiskManager {
+    pub limits: RiskLimits,
+    kill_switch: bool,
+    /// UTC-сутки (ts / DAY_MS), к которым относится `day_realized`
+    day: i64,
+    day_realized: f64,
+    /// Время отправки ордеров за последнюю минуту (`on_orders_submitted`)
+    order_times: VecDeque<i64>,
+    /// Просадка из последнего `EquitySnapshot`
+    drawdown: f64,
+    /// base других стратегий процесса — лимит позиции общий
+    external_base: f64,
+}
+
+impl RiskManager {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    pub fn engage_kill_switch(&mut self) {
+        self.kill_switch = true;
+    }
+
+    /// Ручное снятие остановки: kill switch, а убыток за сутки и просадка считаются
+    /// заново — иначе тот же лимит снова остановил бы engine на следующем тике
+    pub fn release(&mut self) {
+        self.kill_switch = false;
+        self.day_realized = 0.0;
+        self.drawdown = 0.0;
+    }
+
+    pub fn kill_switch(&self) -> bool {
+        self.kill_switch
+    }
+
+    /// Реализованный PnL за текущие сутки
+    pub fn daily_realized(&self) -> Money {
+        Money(self.day_realized)
+    }
+
+    pub fn on_fill(&mut self, ts: TimestampMs, realized_pnl: Option<Money>) {
+        self.roll_day(ts);
+        if let Some(p) = realized_pnl {
+            self.day_realized += p.0;
+        }
+    }
+
+    /// base, который держат другие стратегии (учитывается в лимите позиции)
+    pub fn set_external_base(&mut self, base: Qty) {
+        self.external_base = base.0.max(0.0);
+    }
+
+    pub fn on_equity(&mut self, drawdown: Money) {
+        self.drawdown = drawdown.0;
+    }
+
+    /// Ордера, которые планировщик реально отправил на биржу (или в paper)
+    pub fn on_orders_submitted(&mut self, ts: TimestampMs, n: usize) {
+        self.order_times.extend(std::iter::repeat_n(ts.0, n));
+    }
+
+    /// Проверяет текущую позицию и новую сетку.
+    /// Лимит частоты — по уже отправленным ордерам за минуту.
+    pub fn check(
+        &mut self,
+        ts: TimestampMs,
+        inv: Inventory,
+        mid: Price,
+        orders: &[DesiredOrder],
+    ) -> Result<(), RiskViolation> {
+        self.roll_day(ts);
+
+        if self.kill_switch {
+            return Err(RiskViolation::KillSwitch);
+        }
+
+        if let Some(limit) = self.limits.max_daily_loss {
+            let loss = -self.day_realized;
+            if loss > limit.0 {
+                return Err(RiskViolation::DailyLoss {
+                    loss: Money(loss),
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = self.limits.max_drawdown {
+            if self.drawdown > limit.0 {
+                return Err(RiskViolation::Drawdown {
+                    drawdown: Money(self.drawdown),
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = self.limits.max_position_notional {
+            // худший случай: исполнились все buy-ордера сетки
+            let pending_buy: f64 = orders
+                .iter()
+                .filter(|o| o.side == Side::Buy)
+                .map(|o| o.qty.0)
+                .sum();
+            let notional = (inv.base.0 + self.external_base + pending_buy) * mid.0;
+            if notional > limit.0 {
+                return Err(RiskViolation::PositionNotional {
+                    notional: Money(notional),
+                    limit,
+                });
+            }
+        }
+
+        while let Some(&t) = self.order_times.front() {
+            if t > ts.0 - MINUTE_MS {
+                break;
+            }
+            self.order_times.pop_front();
+        }
+        if let Some(limit) = self.limits.max_orders_per_min {
+            let n = self.order_times.len();
+            if n >= limit {
+                return Err(RiskViolation::OrderRate { orders: n, limit });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn roll_day(&mut self, ts: TimestampMs) {
+        let day = ts.0.div_euclid(DAY_MS);
+        if day != self.day {
+            self.day = day;
+            self.day_realized = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inv(base: f64) -> Inventory {
+        Inventory {
+            base: Qty(base),
+            quote: Money(1000.0),
+        }
+    }
+
+    fn buy(qty: f64) -> DesiredOrder {
+        DesiredOrder {
+            side: Side::Buy,
+            price: Price(100.0),
+            qty: Qty(qty),
+        }
+    }
+
+    #[test]
+    fn notional_limit_counts_pending_buys() {
+        let mut r = RiskManager::new(RiskLimits {
+            max_position_notional: Some(Money(500.0)),
+            ..RiskLimits::default()
+        });
+
+        assert!(r.check(TimestampMs(0), inv(4.0), Price(100.0), &[]).is_ok());
+        assert!(matches!(
+            r.check(TimestampMs(0), inv(4.0), Price(100.0), &[buy(2.0)]),
+            Err(RiskViolation::PositionNotional { .. })
+        ));
+
+        // позиция другой стратегии съедает общий лимит
+        r.set_external_base(Qty(2.0));
+        assert!(
+            r.check(TimestampMs(0), inv(4.0), Price(100.0), &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn daily_loss_resets_on_new_utc_day() {
+        let mut r = RiskManager::new(RiskLimits {
+            max_daily_loss: Some(Money(10.0)),
+            ..RiskLimits::default()
+        });
+
+        r.on_fill(TimestampMs(1_000), Some(Money(-15.0)));
+        assert!(matches!(
+            r.check(TimestampMs(2_000), inv(0.0), Price(100.0), &[]),
+            Err(RiskViolation::DailyLoss { .. })
+        ));
+        assert!(
+            r.check(TimestampMs(DAY_MS + 1), inv(0.0), Price(100.0), &[])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn drawdown_limit_uses_last_equity_snapshot() {
+        let mut r = RiskManager::new(RiskLimits {
+            max_drawdown: Some(Money(20.0)),
+            ..RiskLimits::default()
+        });
+
+        r.on_equity(Money(15.0));
+        assert!(r.check(TimestampMs(0), inv(0.0), Price(100.0), &[]).is_ok());
+        r.on_equity(Money(25.0));
+        assert!(matches!(
+            r.check(TimestampMs(0), inv(0.0), Price(100.0), &[]),
+            Err(RiskViolation::Drawdown { .. })
+        ));
+    }
+
+    #[test]
+    fn order_rate_uses_sliding_minute_and_kill_switch_wins() {
+        let mut r = RiskManager::new(RiskLimits {
+            max_orders_per_min: Some(3),
+            ..RiskLimits::default()
+        });
+        let orders = [buy(0.1), buy(0.1)];
+
+        // считается только отправленное, не вся желаемая сетка
+        assert!(
+            r.check(TimestampMs(0), inv(0.0), Price(100.0), &orders)
+                .is_ok()
+        );
+        r.on_orders_submitted(TimestampMs(0), 2);
+        r.on_orders_submitted(TimestampMs(10_000), 1);
+        let err = r
+            .check(TimestampMs(30_000), inv(0.0), Price(100.0), &orders)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RiskViolation::OrderRate {
+                orders: 3,
+                limit: 3
+            }
+        );
+        assert!(!err.halts());
+        assert!(
+            r.check(TimestampMs(60_000), inv(0.0), Price(100.0), &orders)
+                .is_ok()
+        );
+
+        r.engage_kill_switch();
+        assert_eq!(
+            r.check(TimestampMs(200_000), inv(0.0), Price(100.0), &[]),
+            Err(RiskViolation::KillSwitch)
+        );
+        assert!(RiskViolation::KillSwitch.halts());
+    }
+}
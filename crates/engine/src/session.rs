@@ -0,0 +1,506 @@
+use bybit::ws::AccountEvent;
+use core::types::{Price, Qty, TimestampMs};
+use mm::grid::{DesiredOrder, Side};
+use policy::trend_policy::TrendAction;
+use state_machine::state::BotState;
+use structure::candle::Candle;
+
+use crate::equity::EquityTracker;
+use crate::event::EngineEvent;
+use crate::snapshot::{EngineSnapshot, LiveSnapshot, PaperSnapshot};
+use crate::tick::{EngineCtx, TickInput, tick};
+use crate::trend::TrendStrategy;
+use crate::venue::Venue;
+
+/// Состояние торговой сессии engine: решения (`ctx`, тренд), исполнение
+/// (`venue`) и учёт equity. Главный цикл только раздаёт ей события.
+pub struct Session {
+    pub ctx: EngineCtx,
+    pub venue: Venue,
+    /// equity/PnL по fill'ам, стартовый inventory оцениваем по первой цене
+    pub equity: Option<EquityTracker>,
+    pub trend: Option<TrendStrategy>,
+    pub run_mm: bool,
+    /// Пауза оператора: тики MM и тренда не выставляют ордера
+    pub paused: bool,
+    /// Последняя известная цена — для flatten и снимков equity
+    pub last_mid: Option<Price>,
+    /// Вход последнего тика — для немедленного recenter
+    pub last_input: Option<TickInput>,
+    /// Flatten пришёл до первой свечи: выполняется, как только счёт открыт
+    pub pending_flatten: bool,
+}
+
+impl Session {
+    pub fn new(ctx: EngineCtx, venue: Venue, trend: Option<TrendStrategy>, run_mm: bool) -> Self {
+        Self {
+            ctx,
+            venue,
+            equity: None,
+            trend,
+            run_mm,
+            paused: false,
+            last_mid: None,
+            last_input: None,
+            pending_flatten: false,
+        }
+    }
+
+    /// Закрытая HTF свеча до тика: под-счета и учёт equity открываются по первой
+    /// цене, лимитки прошлого тика проверяются на диапазоне свечи, отложенный
+    /// flatten выполняется по её close
+    pub async fn on_htf_candle(&mut self, candle: &Candle) -> Vec<EngineEvent> {
+        self.last_mid = Some(candle.close);
+        if let Venue::Paper(p) = &mut self.venue {
+            p.open(candle.close);
+        }
+        if self.equity.is_none() {
+            let (quote, base) = self.venue.start_balances(candle.close);
+            self.equity = Some(EquityTracker::new(quote, base, candle.close));
+        }
+
+        let fills = match &mut self.venue {
+            Venue::Paper(p) => {
+                p.mm.as_mut()
+                    .map(|b| b.on_price_range(candle.ts, candle.low, candle.high))
+                    .unwrap_or_default()
+            }
+            Venue::Live(m) => m.poll_fills().await,
+            Venue::Observe { .. } => Vec::new(),
+        };
+        self.record_fills(&fills);
+
+        let mut events = fills;
+        if std::mem::take(&mut self.pending_flatten) {
+            events.extend(self.flatten(candle.ts, candle.close).await);
+        }
+        events
+    }
+
+    /// Тик MM и тренда на закрытии HTF свечи, исполнение и снимок equity
+    pub async fn run_tick(
+        &mut self,
+        input: TickInput,
+        ts: TimestampMs,
+        mid: Price,
+        atr: Price,
+    ) -> Vec<EngineEvent> {
+        // лимит позиции общий: base тренда учитывается в риске MM
+        let (mm_base, trend_base) = self.venue.paper_base();
+        self.ctx.risk.set_external_base(trend_base);
+        self.last_input = Some(input);
+        let mut events = if self.run_mm && !self.paused {
+            tick(&mut self.ctx, input)
+        } else {
+            Vec::new()
+        };
+        let executed = self.execute(&events, ts, mid).await;
+        events.extend(executed);
+
+        if let Some(t) = self.trend.as_mut().filter(|_| !self.paused) {
+            if let Some(d) = t.on_candle(mid, atr, trend_base) {
+                events.push(EngineEvent::TrendDecision {
+                    action: d.action,
+                    reason: d.reason,
+                });
+
+                let fill = match (d.action, self.venue.trend_broker()) {
+                    (TrendAction::EnterLong, Some(tb)) => {
+                        let qty = tb.exec.buy_qty_for_quote(tb.ledger.quote, mid);
+                        let entry = DesiredOrder {
+                            side: Side::Buy,
+                            price: mid,
+                            qty,
+                        };
+                        self.ctx.risk.set_external_base(mm_base);
+                        let check = if self.ctx.state == BotState::Halted {
+                            Err("engine halted".to_string())
+                        } else {
+                            self.ctx
+                                .risk
+                                .check(ts, tb.inventory(), mid, &[entry])
+                                .map_err(|v| v.to_string())
+                        };
+                        match check {
+                            Ok(()) => tb.market_buy(ts, mid, qty),
+                            Err(why) => {
+                                events.push(EngineEvent::Alert {
+                                    source: "risk".into(),
+                                    message: format!("trend entry blocked: {}", why),
+                                });
+                                None
+                            }
+                        }
+                    }
+                    (TrendAction::ExitLong, Some(tb)) => {
+                        tb.market_sell(ts, mid, Qty(tb.ledger.base))
+                    }
+                    _ => None,
+                };
+
+                if let Some(f) = fill {
+                    if d.action == TrendAction::EnterLong {
+                        t.entry_price = Some(mid);
+                    }
+                    let fills = [f];
+                    self.record_trend_fills(&fills);
+                    events.extend(fills);
+                }
+            }
+        }
+        events.extend(self.equity_snapshot(ts, mid));
+        events
+    }
+
+    /// Исполняет intent'ы тика: paper — на под-счёте MM, live — на бирже
+    pub async fn execute(
+        &mut self,
+        events: &[EngineEvent],
+        ts: TimestampMs,
+        mid: Price,
+    ) -> Vec<EngineEvent> {
+        let out = match &mut self.venue {
+            Venue::Paper(p) => p.apply_tick(events, ts, mid),
+            Venue::Live(m) => m.apply_tick(events, mid).await,
+            Venue::Observe { .. } => Vec::new(),
+        };
+        self.record_fills(&out);
+        self.record_submitted(ts, &out);
+        out
+    }
+
+    /// Снимает сетку MM до следующего тика
+    pub async fn cancel_grid(&mut self) -> Vec<EngineEvent> {
+        let events = match &mut self.venue {
+            Venue::Paper(p) => {
+                p.mm.as_mut()
+                    .map(|b| {
+                        vec![EngineEvent::OrdersCancelled {
+                            count: b.cancel_all(),
+                        }]
+                    })
+                    .unwrap_or_default()
+            }
+            Venue::Live(m) => m.cancel_all().await,
+            Venue::Observe { .. } => Vec::new(),
+        };
+        self.ctx.desired.clear();
+        events
+    }
+
+    /// Продаёт base всех под-счетов по `mark` (сетка уже снята) и снимает equity
+    pub async fn flatten(&mut self, ts: TimestampMs, mark: Price) -> Vec<EngineEvent> {
+        let (fills, trend_fills): (Vec<_>, Vec<_>) = match &mut self.venue {
+            Venue::Paper(p) => (
+                p.mm.as_mut()
+                    .and_then(|b| b.flatten(ts, mark))
+                    .into_iter()
+                    .collect(),
+                p.trend
+                    .as_mut()
+                    .and_then(|b| b.flatten(ts, mark))
+                    .into_iter()
+                    .collect(),
+            ),
+            Venue::Live(m) => (m.flatten(mark).await, Vec::new()),
+            Venue::Observe { .. } => (Vec::new(), Vec::new()),
+        };
+        self.record_fills(&fills);
+        self.record_trend_fills(&trend_fills);
+
+        let mut events = fills;
+        events.extend(trend_fills);
+        events.extend(self.equity_snapshot(ts, mark));
+        events
+    }
+
+    /// Fill'ы live между свечами (учёт начинается с первой цены) и снимок equity
+    pub async fn on_heartbeat(&mut self, now: TimestampMs) -> Vec<EngineEvent> {
+        let mut events = match &mut self.venue {
+            Venue::Live(m) if self.equity.is_some() => m.poll_fills().await,
+            _ => Vec::new(),
+        };
+        self.record_fills(&events);
+        if let Some(mid) = self.last_mid {
+            events.extend(self.equity_snapshot(now, mid));
+        }
+        events
+    }
+
+    /// Событие private WS аккаунта
+    pub async fn on_account_event(&mut self, ev: AccountEvent) -> Vec<EngineEvent> {
+        let ready = self.equity.is_some();
+        let events = match &mut self.venue {
+            Venue::Live(m) => m.on_account_event(ev, ready).await,
+            _ => Vec::new(),
+        };
+        self.record_fills(&events);
+        events
+    }
+
+    /// Цена сделки или тикера: лимитки paper-режима, которые она задела,
+    /// исполняются сразу, не дожидаясь закрытия свечи
+    pub fn on_price(&mut self, ts: TimestampMs, price: Price) -> Vec<EngineEvent> {
+        self.last_mid = Some(price);
+        let fills = self
+            .venue
+            .mm_broker()
+            .map(|b| b.on_price_range(ts, price, price))
+            .unwrap_or_default();
+        self.after_paper_fills(ts, price, fills)
+    }
+
+    /// Лучшие bid/ask книги
+    pub fn on_book(&mut self, ts: TimestampMs, bid: Price, ask: Price) -> Vec<EngineEvent> {
+        let mid = Price((bid.0 + ask.0) / 2.0);
+        self.last_mid = Some(mid);
+
+        // у тренда нет лимиток, только рыночные сделки
+        if let Some(b) = self.venue.trend_broker() {
+            b.set_book(bid, ask);
+        }
+        let fills = self
+            .venue
+            .mm_broker()
+            .map(|b| b.on_book(ts, bid, ask))
+            .unwrap_or_default();
+        self.after_paper_fills(ts, mid, fills)
+    }
+
+    /// Книга до нового snapshot'а неизвестна
+    pub fn clear_book(&mut self) {
+        if let Venue::Paper(p) = &mut self.venue {
+            for b in p.mm.iter_mut().chain(p.trend.iter_mut()) {
+                b.clear_book();
+            }
+        }
+    }
+
+    fn after_paper_fills(
+        &mut self,
+        ts: TimestampMs,
+        mark: Price,
+        mut fills: Vec<EngineEvent>,
+    ) -> Vec<EngineEvent> {
+        if fills.is_empty() {
+            return fills;
+        }
+        self.record_fills(&fills);
+        fills.extend(self.equity_snapshot(ts, mark));
+        fills
+    }
+
+    /// Остановка: снимает сетку, по желанию закрывает позицию
+    pub async fn shutdown(&mut self, ts: TimestampMs, flatten: bool) -> Vec<EngineEvent> {
+        let mut events = self.cancel_grid().await;
+        if let Venue::Live(m) = &mut self.venue {
+            if self.equity.is_some() {
+                let fills = m.poll_fills().await;
+                self.record_fills(&fills);
+                events.extend(fills);
+            }
+        }
+        match self.last_mid {
+            Some(mark) if flatten => events.extend(self.flatten(ts, mark).await),
+            Some(mark) => events.extend(self.equity_snapshot(ts, mark)),
+            None if flatten && matches!(self.venue, Venue::Live(_)) => {
+                events.push(EngineEvent::Alert {
+                    source: "live".into(),
+                    message: "flatten on shutdown skipped: no price yet, base kept".into(),
+                })
+            }
+            None => {}
+        }
+        events
+    }
+
+    /// Состояние для `--state-file`
+    pub fn snapshot(
+        &self,
+        ts: TimestampMs,
+        symbol: &str,
+        mode: &str,
+        reason: &str,
+    ) -> EngineSnapshot {
+        let (paper, trend) = match &self.venue {
+            Venue::Paper(p) => (
+                p.mm.as_ref().map(PaperSnapshot::of),
+                p.trend.as_ref().map(PaperSnapshot::of),
+            ),
+            _ => (None, None),
+        };
+        EngineSnapshot {
+            saved_at_ms: ts.0,
+            symbol: symbol.to_string(),
+            mode: mode.to_string(),
+            reason: reason.to_string(),
+            state: format!("{:?}", self.ctx.state),
+            bos_state: format!("{:?}", self.ctx.bos.state),
+            pullback_triggered: self.ctx.pullback.triggered,
+            paper,
+            trend,
+            live: match &self.venue {
+                Venue::Live(m) => Some(LiveSnapshot::of(&m.orders)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Fill'ы влияют на дневной лимит риска, якорь LastFill и учёт equity
+    fn record_fills(&mut self, events: &[EngineEvent]) {
+        if let Some(t) = self.equity.as_mut() {
+            t.on_events(events);
+        }
+        for e in events {
+            if let EngineEvent::Fill {
+                ts,
+                price,
+                realized_pnl,
+                ..
+            } = e
+            {
+                self.ctx.risk.on_fill(*ts, *realized_pnl);
+                self.ctx.anchor.on_fill(*price);
+            }
+        }
+    }
+
+    /// Fill'ы тренд-стратегии: общий риск и equity, но не якорь сетки MM
+    fn record_trend_fills(&mut self, events: &[EngineEvent]) {
+        if let Some(t) = self.equity.as_mut() {
+            t.on_events(events);
+        }
+        for e in events {
+            if let EngineEvent::Fill {
+                ts, realized_pnl, ..
+            } = e
+            {
+                self.ctx.risk.on_fill(*ts, *realized_pnl);
+            }
+        }
+    }
+
+    /// Отправленные планировщиком выставления идут в лимит частоты ордеров
+    fn record_submitted(&mut self, ts: TimestampMs, events: &[EngineEvent]) {
+        for e in events {
+            if let EngineEvent::OrdersSubmitted { places, .. } = e {
+                self.ctx.risk.on_orders_submitted(ts, *places);
+            }
+        }
+    }
+
+    /// Снимок equity по mark; просадка сразу уходит в risk
+    pub fn equity_snapshot(&mut self, ts: TimestampMs, mark: Price) -> Option<EngineEvent> {
+        let ev = self.equity.as_mut()?.snapshot(ts, mark);
+        if let EngineEvent::EquitySnapshot { drawdown, .. } = &ev {
+            self.ctx.risk.on_equity(*drawdown);
+        }
+        Some(ev)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::allocator::StrategyKind;
+    use crate::tick::tests::{ctx, input};
+    use crate::venue::PaperVenue;
+    use execution::sim::ExecutionModel;
+
+    pub(crate) fn paper_session() -> Session {
+        let exec = ExecutionModel {
+            fee_bps: 10.0,
+            spread_bps: 0.0,
+            slippage_bps: 0.0,
+        };
+        let venue = PaperVenue::new(exec, vec![StrategyKind::Mm], 500.0, 5.0, 0.3, None);
+        Session::new(
+            ctx(BotState::MMNormal),
+            Venue::Paper(Box::new(venue)),
+            None,
+            true,
+        )
+    }
+
+    pub(crate) fn candle(ts: i64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(close),
+            high: Price(close),
+            low: Price(close),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    pub(crate) fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn first_candle_opens_paper_account_and_equity() {
+        let mut s = paper_session();
+        let fills = block_on(s.on_htf_candle(&candle(0, 100.0)));
+        assert!(fills.is_empty());
+        assert_eq!(s.last_mid, Some(Price(100.0)));
+        assert!(s.equity.is_some());
+        assert_eq!(s.venue.inventory().base, Qty(5.0));
+    }
+
+    #[test]
+    fn tick_places_grid_on_paper_account() {
+        let mut s = paper_session();
+        block_on(s.on_htf_candle(&candle(0, 100.0)));
+        let events =
+            block_on(s.run_tick(input(5.0, 500.0), TimestampMs(0), Price(100.0), Price(1.0)));
+
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::DesiredGrid(_)))
+        );
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::EquitySnapshot { .. }))
+        );
+        let resting = s.venue.mm_broker().unwrap().resting_orders().len();
+        assert!(resting > 0);
+        assert_eq!(s.last_input.map(|i| i.mid), Some(Price(100.0)));
+    }
+
+    #[test]
+    fn paused_session_does_not_tick() {
+        let mut s = paper_session();
+        block_on(s.on_htf_candle(&candle(0, 100.0)));
+        s.paused = true;
+        let events =
+            block_on(s.run_tick(input(5.0, 500.0), TimestampMs(0), Price(100.0), Price(1.0)));
+
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::DesiredGrid(_)))
+        );
+        assert!(s.venue.mm_broker().unwrap().resting_orders().is_empty());
+    }
+
+    #[test]
+    fn shutdown_with_flatten_sells_paper_base() {
+        let mut s = paper_session();
+        block_on(s.on_htf_candle(&candle(0, 100.0)));
+        let events = block_on(s.shutdown(TimestampMs(1), true));
+
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::OrdersCancelled { .. }))
+        );
+        assert!(events.iter().any(|e| matches!(e, EngineEvent::Fill { .. })));
+        assert_eq!(s.venue.inventory().base, Qty(0.0));
+    }
+}
@@ -0,0 +1,28 @@
+/// Ждёт SIGINT (Ctrl+C) или SIGTERM (docker stop / worker) и
+/// возвращает имя сигнала.
+pub async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut term = match signal(SignalKind::terminate()) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return "SIGINT";
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = term.recv() => "SIGTERM",
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
@@ -1,17 +1,373 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use mm::rebalance::RebalanceDecision;
+
 use crate::event::EngineEvent;
 
-pub fn consume(events: Vec<EngineEvent>) {
-    for e in events {
-        match e {
-            EngineEvent::Transition { from, cause, to } => {
-                println!("Transition: {:?} --({:?})-> {:?}", from, cause, to);
-            }
-            EngineEvent::PolicyDecision { mode, reason } => {
-                println!("Policy: {:?} ({:?})", mode, reason);
-            }
-            EngineEvent::Log(msg) => {
-                println!("Log: {}", msg);
+/// Потребитель событий engine (логи, файлы, БД, телега...)
+pub trait EventSink {
+    fn consume(&mut self, events: &[EngineEvent]) -> Result<()>;
+}
+
+/// Человекочитаемый вывод в stdout (как было раньше)
+#[derive(Debug, Default)]
+pub struct TextSink;
+
+impl EventSink for TextSink {
+    fn consume(&mut self, events: &[EngineEvent]) -> Result<()> {
+        for e in events {
+            println!("{}", format_text(e));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn format_text(e: &EngineEvent) -> String {
+    match e {
+        EngineEvent::Transition { from, cause, to } => {
+            format!("Transition: {:?} --({:?})-> {:?}", from, cause, to)
+        }
+        EngineEvent::PolicyDecision { mode, reason } => {
+            format!("Policy: {:?} ({:?})", mode, reason)
+        }
+        EngineEvent::TrendDecision { action, reason } => {
+            format!("Trend: {:?} ({:?})", action, reason)
+        }
+        EngineEvent::DesiredGrid(orders) => format!(
+            "DesiredGrid: {} orders [{}]",
+            orders.len(),
+            orders
+                .iter()
+                .map(|o| format!("{:?} {}x{:.6}", o.side, o.price, o.qty.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        EngineEvent::RebalanceIntent(d) => format!("RebalanceIntent: {:?}", d),
+        EngineEvent::Fill {
+            ts,
+            side,
+            price,
+            qty,
+            fee,
+            realized_pnl,
+        } => format!(
+            "Fill: ts={} side={:?} price={} qty={:.8} fee={} realized_pnl={}",
+            ts.0,
+            side,
+            price,
+            qty.0,
+            fee,
+            realized_pnl
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "-".into())
+        ),
+        EngineEvent::EquitySnapshot {
+            ts,
+            quote,
+            base,
+            mark,
+            equity,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            break_even,
+            drawdown,
+        } => format!(
+            "Equity: ts={} quote={} base={:.8} mark={} equity={} realized={} unrealized={} fees={} break_even={} drawdown={}",
+            ts.0,
+            quote,
+            base.0,
+            mark,
+            equity,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            break_even
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "-".into()),
+            drawdown
+        ),
+        EngineEvent::Alert { source, message } => format!("ALERT [{}]: {}", source, message),
+        EngineEvent::OrdersCancelled { count } => format!("OrdersCancelled: count={}", count),
+        EngineEvent::OrdersSubmitted {
+            cancels,
+            places,
+            batches,
+            deferred,
+        } => format!(
+            "OrdersSubmitted: cancels={} places={} batches={} deferred={}",
+            cancels, places, batches, deferred
+        ),
+        EngineEvent::OrdersSynced {
+            placed,
+            cancelled,
+            failed,
+        } => format!(
+            "OrdersSynced: placed={} cancelled={} failed={}",
+            placed, cancelled, failed
+        ),
+        EngineEvent::ConfigChanged { changes } => format!(
+            "ConfigChanged: {}",
+            changes
+                .iter()
+                .map(|c| format!("{} {} -> {}", c.field, c.old, c.new))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        EngineEvent::Shutdown { reason } => format!("Shutdown: {}", reason),
+        EngineEvent::Log(msg) => format!("Log: {}", msg),
+    }
+}
+
+/// JSON lines: одна строка = одно событие, с временем записи и тегом символа.
+/// Пишет в файл или в stdout — формат удобен для worker'а и лог-пайплайнов.
+pub struct JsonLinesSink<W: Write> {
+    out: W,
+    symbol: String,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W, symbol: impl Into<String>) -> Self {
+        Self {
+            out,
+            symbol: symbol.into(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl JsonLinesSink<BufWriter<File>> {
+    /// Открывает файл на дозапись (создаёт директорию при необходимости)
+    pub fn append_to(path: &str, symbol: impl Into<String>) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let f = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(f), symbol))
+    }
+}
+
+impl JsonLinesSink<io::Stdout> {
+    pub fn stdout(symbol: impl Into<String>) -> Self {
+        Self::new(io::stdout(), symbol)
+    }
+}
+
+impl<W: Write> EventSink for JsonLinesSink<W> {
+    fn consume(&mut self, events: &[EngineEvent]) -> Result<()> {
+        let ts_ms = chrono::Utc::now().timestamp_millis();
+        for e in events {
+            let mut v = event_json(e);
+            if let Value::Object(map) = &mut v {
+                map.insert("ts_ms".into(), json!(ts_ms));
+                map.insert("symbol".into(), json!(self.symbol));
             }
+            serde_json::to_writer(&mut self.out, &v)?;
+            self.out.write_all(b"\n")?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Событие → JSON-объект с полем `type`
+pub fn event_json(e: &EngineEvent) -> Value {
+    match e {
+        EngineEvent::Transition { from, cause, to } => json!({
+            "type": "transition",
+            "from": format!("{:?}", from),
+            "cause": format!("{:?}", cause),
+            "to": format!("{:?}", to),
+        }),
+        EngineEvent::PolicyDecision { mode, reason } => json!({
+            "type": "policy_decision",
+            "mode": format!("{:?}", mode),
+            "reason": format!("{:?}", reason),
+        }),
+        EngineEvent::TrendDecision { action, reason } => json!({
+            "type": "trend_decision",
+            "action": format!("{:?}", action),
+            "reason": format!("{:?}", reason),
+        }),
+        EngineEvent::DesiredGrid(orders) => json!({
+            "type": "desired_grid",
+            "orders": orders
+                .iter()
+                .map(|o| json!({
+                    "side": format!("{:?}", o.side),
+                    "price": o.price.0,
+                    "qty": o.qty.0,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        EngineEvent::RebalanceIntent(d) => {
+            let (side, qty) = match d {
+                RebalanceDecision::BuyBase(q) => ("Buy", q.0),
+                RebalanceDecision::SellBase(q) => ("Sell", q.0),
+                RebalanceDecision::Noop => ("None", 0.0),
+            };
+            json!({
+                "type": "rebalance_intent",
+                "side": side,
+                "qty": qty,
+            })
         }
+        EngineEvent::Fill {
+            ts,
+            side,
+            price,
+            qty,
+            fee,
+            realized_pnl,
+        } => json!({
+            "type": "fill",
+            "event_ts": ts.0,
+            "side": format!("{:?}", side),
+            "price": price.0,
+            "qty": qty.0,
+            "fee": fee.0,
+            "realized_pnl": realized_pnl.map(|p| p.0),
+        }),
+        EngineEvent::EquitySnapshot {
+            ts,
+            quote,
+            base,
+            mark,
+            equity,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            break_even,
+            drawdown,
+        } => json!({
+            "type": "equity",
+            "event_ts": ts.0,
+            "quote": quote.0,
+            "base": base.0,
+            "mark": mark.0,
+            "equity": equity.0,
+            "realized_pnl": realized_pnl.0,
+            "unrealized_pnl": unrealized_pnl.0,
+            "fees": fees.0,
+            "break_even": break_even.map(|p| p.0),
+            "drawdown": drawdown.0,
+        }),
+        EngineEvent::Alert { source, message } => json!({
+            "type": "alert",
+            "source": source,
+            "message": message,
+        }),
+        EngineEvent::OrdersCancelled { count } => json!({
+            "type": "orders_cancelled",
+            "count": count,
+        }),
+        EngineEvent::OrdersSubmitted {
+            cancels,
+            places,
+            batches,
+            deferred,
+        } => json!({
+            "type": "orders_submitted",
+            "cancels": cancels,
+            "places": places,
+            "batches": batches,
+            "deferred": deferred,
+        }),
+        EngineEvent::OrdersSynced {
+            placed,
+            cancelled,
+            failed,
+        } => json!({
+            "type": "orders_synced",
+            "placed": placed,
+            "cancelled": cancelled,
+            "failed": failed,
+        }),
+        EngineEvent::ConfigChanged { changes } => json!({
+            "type": "config_changed",
+            "changes": changes,
+        }),
+        EngineEvent::Shutdown { reason } => json!({
+            "type": "shutdown",
+            "reason": reason,
+        }),
+        EngineEvent::Log(msg) => json!({
+            "type": "log",
+            "message": msg,
+        }),
+    }
+}
+
+/// Раздаёт события нескольким sink'ам
+#[derive(Default)]
+pub struct FanoutSink {
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl FanoutSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, sink: Box<dyn EventSink>) {
+        self.sinks.push(sink);
+    }
+}
+
+impl EventSink for FanoutSink {
+    fn consume(&mut self, events: &[EngineEvent]) -> Result<()> {
+        for s in &mut self.sinks {
+            s.consume(events)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Money, Price, Qty, TimestampMs};
+
+    #[test]
+    fn json_lines_are_tagged_with_symbol_and_time() {
+        let mut sink = JsonLinesSink::new(Vec::new(), "ETHUSDT");
+        sink.consume(&[
+            EngineEvent::Log("hello".into()),
+            EngineEvent::EquitySnapshot {
+                ts: TimestampMs(42),
+                quote: Money(10.0),
+                base: Qty(1.0),
+                mark: Price(100.0),
+                equity: Money(110.0),
+                realized_pnl: Money(0.0),
+                unrealized_pnl: Money(5.0),
+                fees: Money(0.1),
+                break_even: Some(Price(95.0)),
+                drawdown: Money(0.0),
+            },
+        ])
+        .unwrap();
+
+        let out = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<Value> = out
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "log");
+        assert_eq!(lines[0]["symbol"], "ETHUSDT");
+        assert!(lines[0]["ts_ms"].as_i64().unwrap() > 0);
+        assert_eq!(lines[1]["type"], "equity");
+        assert_eq!(lines[1]["event_ts"], 42);
+        assert_eq!(lines[1]["break_even"], 95.0);
     }
 }
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use execution::live::OrderManager;
+use execution::traits::OrderGateway;
+use serde::{Deserialize, Serialize};
+
+use crate::paper::PaperBroker;
+
+/// Состояние engine на момент остановки.
+///
+/// Пишется в JSON при shutdown, чтобы следующий запуск мог сверить
+/// позицию и понять, в каком состоянии бот остановился.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub saved_at_ms: i64,
+    pub symbol: String,
+    pub mode: String,
+    pub reason: String,
+    /// `BotState` в виде `{:?}`
+    pub state: String,
+    pub bos_state: String,
+    pub pullback_triggered: bool,
+    pub paper: Option<PaperSnapshot>,
+    /// Под-счёт тренд-стратегии (если она была запущена)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trend: Option<PaperSnapshot>,
+    /// Live: учёт по fill'ам и свои ордера, оставшиеся на бирже
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live: Option<LiveSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaperSnapshot {
+    pub quote: f64,
+    pub base: f64,
+    pub cost_basis_quote: f64,
+    pub realized_pnl: f64,
+}
+
+impl PaperSnapshot {
+    pub fn of(broker: &PaperBroker) -> Self {
+        Self {
+            quote: broker.ledger.quote,
+            base: broker.ledger.base,
+            cost_basis_quote: broker.ledger.cost_basis_quote,
+            realized_pnl: broker.ledger.realized_pnl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveSnapshot {
+    pub quote: f64,
+    pub base: f64,
+    pub cost_basis_quote: f64,
+    pub realized_pnl: f64,
+    /// id своих открытых ордеров (не снялись при остановке)
+    pub orders: Vec<String>,
+}
+
+impl LiveSnapshot {
+    pub fn of<G: OrderGateway>(orders: &OrderManager<G>) -> Self {
+        Self {
+            quote: orders.ledger.quote,
+            base: orders.ledger.base,
+            cost_basis_quote: orders.ledger.cost_basis_quote,
+            realized_pnl: orders.ledger.realized_pnl,
+            orders: orders
+                .open_orders()
+                .iter()
+                .map(|o| o.order_id.clone())
+                .collect(),
+        }
+    }
+}
+
+impl EngineSnapshot {
+    /// Пишет через временный файл, чтобы не оставить обрезанный JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = format!("{}.tmp", path);
+        let body = serde_json::to_vec_pretty(self)?;
+        std::fs::write(&tmp, body).with_context(|| format!("failed to write {}", tmp))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("failed to rename to {}", path))?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Option<Self>> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let body = std::fs::read(path).with_context(|| format!("failed to read {}", path))?;
+        let snap = serde_json::from_slice(&body)
+            .with_context(|| format!("invalid engine snapshot: {}", path))?;
+        Ok(Some(snap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trips_through_file() {
+        let dir = std::env::temp_dir().join(format!("mmbot-snap-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let path = path.to_str().unwrap();
+
+        let snap = EngineSnapshot {
+            saved_at_ms: 1,
+            symbol: "ETHUSDT".into(),
+            mode: "Paper".into(),
+            reason: "SIGTERM".into(),
+            state: "MMNormal".into(),
+            bos_state: "Confirmed".into(),
+            pullback_triggered: true,
+            paper: Some(PaperSnapshot {
+                quote: 500.0,
+                base: 0.25,
+                cost_basis_quote: 490.0,
+                realized_pnl: 3.5,
+            }),
+            trend: None,
+            live: Some(LiveSnapshot {
+                quote: 1000.0,
+                base: 0.5,
+                cost_basis_quote: 995.0,
+                realized_pnl: 0.0,
+                orders: vec!["o1".into()],
+            }),
+        };
+        snap.save(path).unwrap();
+
+        assert_eq!(EngineSnapshot::load(path).unwrap(), Some(snap));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(EngineSnapshot::load(path).unwrap(), None);
+    }
+}
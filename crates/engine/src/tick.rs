@@ -1,18 +1,25 @@
-use core::types::Price;
+use core::types::{Price, TimestampMs};
 
 use state_machine::cause::TransitionCause;
 use state_machine::state::BotState;
 use state_machine::transition::transition;
 
 use structure::bos::{BosParams, BosTracker};
+use structure::ltf::LtfSignal;
 use structure::pullback::{PullbackParams, PullbackTracker};
 
-use mm::grid::GridParams;
+use mm::grid::{DesiredOrder, GridParams, Side};
 use mm::grid::{Inventory, base_ratio, build_grid};
+use mm::rebalance::{Portfolio, RebalanceDecision, RebalanceParams, rebalance_decision};
 
-use policy::mm_policy::{MmMode, MmPolicyParams, mm_policy_decision};
+use policy::mm_policy::{
+    DataQuality, MmMode, MmPolicyParams, apply_data_quality, mm_policy_decision,
+};
 
+use crate::anchor::AnchorState;
+use crate::equity::EquityTracker;
 use crate::event::EngineEvent;
+use crate::risk::RiskManager;
 
 /// Engine runtime context (живёт между тиками)
 pub struct EngineCtx {
@@ -22,9 +29,19 @@ pub struct EngineCtx {
     pub bos: BosTracker,
     pub pullback: PullbackTracker,
 
+    // желаемая сетка последнего тика (пусто, если MM выключен)
+    pub desired: Vec<DesiredOrder>,
+
+    // якорь сетки (mid / BOS level / last fill / VWAP)
+    pub anchor: AnchorState,
+
+    // лимиты риска, проверяются до выдачи сетки наружу
+    pub risk: RiskManager,
+
     // config
     pub mm_policy: MmPolicyParams,
     pub grid: GridParams,
+    pub rebalance: RebalanceParams,
     pub bos_params: BosParams,
     pub pullback_params: PullbackParams,
 }
@@ -34,6 +51,7 @@ impl EngineCtx {
         state: BotState,
         mm_policy: MmPolicyParams,
         grid: GridParams,
+        rebalance: RebalanceParams,
         bos_params: BosParams,
         pullback_params: PullbackParams,
     ) -> Self {
@@ -41,8 +59,12 @@ impl EngineCtx {
             state,
             bos: BosTracker::new(),
             pullback: PullbackTracker::new(),
+            desired: Vec::new(),
+            anchor: AnchorState::default(),
+            risk: RiskManager::default(),
             mm_policy,
             grid,
+            rebalance,
             bos_params,
             pullback_params,
         }
@@ -52,11 +74,52 @@ impl EngineCtx {
 /// Вход тик-данных (пока мок)
 #[derive(Debug, Copy, Clone)]
 pub struct TickInput {
+    pub ts: TimestampMs,
     pub mid: Price,
     pub atr: Price,
     pub inv: Inventory,
     pub ltf_broken_down: bool,
     pub ltf_recovered: bool,
+    /// Качество market data: при деградации policy понижает режим
+    pub data_quality: DataQuality,
+}
+
+/// LTF сигналы для HTF тика. LTF свечи закрываются чаще: слом держится,
+/// пока структура не восстановится, а восстановление — событие и уходит
+/// ровно в один тик после перехода broken -> not broken
+#[derive(Debug, Default, Copy, Clone)]
+pub struct LtfSignals {
+    broken: bool,
+    recovered: bool,
+}
+
+impl LtfSignals {
+    /// `broken` — состояние LTF после прогрева истории
+    pub fn new(broken: bool) -> Self {
+        Self {
+            broken,
+            recovered: false,
+        }
+    }
+
+    pub fn on_signal(&mut self, signal: LtfSignal) {
+        match signal {
+            LtfSignal::BrokenDown => {
+                self.broken = true;
+                self.recovered = false;
+            }
+            LtfSignal::Recovered => {
+                self.broken = false;
+                self.recovered = true;
+            }
+            LtfSignal::None => {}
+        }
+    }
+
+    /// (`ltf_broken_down`, `ltf_recovered`) для ближайшего тика
+    pub fn take(&mut self) -> (bool, bool) {
+        (self.broken, std::mem::take(&mut self.recovered))
+    }
 }
 
 /// Один тик мышления.
@@ -67,6 +130,12 @@ pub fn tick(ctx: &mut EngineCtx, input: TickInput) -> Vec<EngineEvent> {
     let _ = input.atr;
 
     let mut events = Vec::new();
+    let prev = std::mem::take(&mut ctx.desired);
+
+    // после аварийной остановки ничего не делаем до ручного снятия
+    if ctx.state == BotState::Halted {
+        return events;
+    }
 
     // --- 2) policy decision ---
     let r = match base_ratio(input.inv, input.mid) {
@@ -77,7 +146,10 @@ pub fn tick(ctx: &mut EngineCtx, input: TickInput) -> Vec<EngineEvent> {
         }
     };
 
-    let decision = mm_policy_decision(ctx.bos.state, &ctx.pullback, r, ctx.mm_policy);
+    let decision = apply_data_quality(
+        mm_policy_decision(ctx.bos.state, &ctx.pullback, r, ctx.mm_policy),
+        input.data_quality,
+    );
 
     events.push(EngineEvent::PolicyDecision {
         mode: decision.mode,
@@ -134,16 +206,36 @@ pub fn tick(ctx: &mut EngineCtx, input: TickInput) -> Vec<EngineEvent> {
         }
     }
 
-    // --- 4) build desired grid when MM is allowed ---
+    // --- 4) rebalance intent: довести inventory до целевой доли ---
+    let mut intent = None;
+    if ctx.state == BotState::Rebalancing {
+        let p = Portfolio {
+            base: input.inv.base,
+            quote: input.inv.quote,
+        };
+        match rebalance_decision(p, input.mid, ctx.rebalance) {
+            Some(RebalanceDecision::Noop) => {
+                if let Ok(next) = transition(ctx.state, TransitionCause::RebalanceDone) {
+                    events.push(EngineEvent::Transition {
+                        from: ctx.state,
+                        cause: TransitionCause::RebalanceDone,
+                        to: next,
+                    });
+                    ctx.state = next;
+                }
+            }
+            Some(d) => intent = Some(d),
+            None => events.push(EngineEvent::Log("rebalance not possible".into())),
+        }
+    }
+
+    // --- 5) build desired grid when MM is allowed ---
+    let mut orders = Vec::new();
     if matches!(decision.mode, MmMode::Normal | MmMode::Defensive) {
-        // anchor пока = mid (позже будет BOS level / last fill / VWAP)
-        let anchor = input.mid;
-
-        if let Some(orders) = build_grid(anchor, input.mid, input.inv, ctx.grid) {
-            events.push(EngineEvent::Log(format!(
-                "desired_orders: {}",
-                orders.len()
-            )));
+        let anchor = ctx.anchor.update(input.mid, &ctx.bos);
+
+        if let Some(grid) = build_grid(anchor, input.mid, input.inv, ctx.grid) {
+            orders = grid;
         } else {
             events.push(EngineEvent::Log(
                 "grid disabled by hard band or invalid inputs".into(),
@@ -151,5 +243,298 @@ pub fn tick(ctx: &mut EngineCtx, input: TickInput) -> Vec<EngineEvent> {
         }
     }
 
+    // --- 6) risk: до того, как сетка уйдёт наружу ---
+    // рыночную сделку ребаланса проверяем как ещё один ордер по mid
+    let mut checked = orders.clone();
+    match intent {
+        Some(RebalanceDecision::BuyBase(qty)) => checked.push(DesiredOrder {
+            side: Side::Buy,
+            price: input.mid,
+            qty,
+        }),
+        Some(RebalanceDecision::SellBase(qty)) => checked.push(DesiredOrder {
+            side: Side::Sell,
+            price: input.mid,
+            qty,
+        }),
+        _ => {}
+    }
+    if let Err(violation) = ctx.risk.check(input.ts, input.inv, input.mid, &checked) {
+        events.push(EngineEvent::Alert {
+            source: "risk".into(),
+            message: violation.to_string(),
+        });
+        // частота ордеров: стоящая сетка остаётся, новая — после окна
+        if !violation.halts() {
+            ctx.desired = prev;
+            return events;
+        }
+        if let Ok(next) = transition(ctx.state, TransitionCause::EmergencyHalt) {
+            events.push(EngineEvent::Transition {
+                from: ctx.state,
+                cause: TransitionCause::EmergencyHalt,
+                to: next,
+            });
+            ctx.state = next;
+        }
+        events.push(EngineEvent::DesiredGrid(Vec::new()));
+        return events;
+    }
+
+    if let Some(d) = intent {
+        events.push(EngineEvent::RebalanceIntent(d));
+    }
+    events.push(EngineEvent::DesiredGrid(orders.clone()));
+    ctx.desired = orders;
+
+    events
+}
+
+/// Ручное снятие аварийной остановки: состояние `Halted` -> `IdleUSDT`, kill switch,
+/// суточный убыток риска и пик просадки `equity`. Сетка вернётся на следующем тике.
+pub fn clear_halt(ctx: &mut EngineCtx, equity: Option<&mut EquityTracker>) -> Vec<EngineEvent> {
+    let mut events = Vec::new();
+    ctx.risk.release();
+    if let Some(equity) = equity {
+        equity.reset_peak();
+    }
+    if let Ok(next) = transition(ctx.state, TransitionCause::HaltCleared) {
+        events.push(EngineEvent::Transition {
+            from: ctx.state,
+            cause: TransitionCause::HaltCleared,
+            to: next,
+        });
+        ctx.state = next;
+    }
     events
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::risk::RiskLimits;
+    use core::types::{Bps, Money, Qty, Ratio};
+    use mm::grid::LotFilters;
+    use structure::bos::BosState;
+
+    pub(crate) fn ctx(state: BotState) -> EngineCtx {
+        let mut ctx = EngineCtx::new(
+            state,
+            MmPolicyParams {
+                soft_min: Ratio(0.40),
+                soft_max: Ratio(0.60),
+                hard_min: Ratio(0.35),
+                hard_max: Ratio(0.65),
+            },
+            GridParams {
+                levels: 3,
+                step: Bps(10.0),
+                base_quote_per_order: Money(25.0),
+                max_size_mult: 2.0,
+                soft_min: Ratio(0.40),
+                soft_max: Ratio(0.60),
+                hard_min: Ratio(0.35),
+                hard_max: Ratio(0.65),
+                min_base_qty: Qty(0.0001),
+                filters: LotFilters::default(),
+            },
+            RebalanceParams {
+                target_base_ratio: Ratio(0.50),
+                tolerance: Ratio(0.02),
+                fee_rate: Ratio(0.001),
+                min_quote_trade: Money(5.0),
+            },
+            BosParams {
+                confirm_candles: 2,
+                epsilon_frac: 0.1,
+            },
+            PullbackParams {
+                epsilon_frac: 0.1,
+                retrace_frac: 0.4,
+            },
+        );
+        ctx.bos.state = BosState::Confirmed;
+        ctx.pullback.triggered = true;
+        ctx
+    }
+
+    pub(crate) fn input(base: f64, quote: f64) -> TickInput {
+        TickInput {
+            ts: TimestampMs(0),
+            mid: Price(100.0),
+            atr: Price(1.0),
+            inv: Inventory {
+                base: Qty(base),
+                quote: Money(quote),
+            },
+            ltf_broken_down: false,
+            ltf_recovered: false,
+            data_quality: DataQuality::Good,
+        }
+    }
+
+    #[test]
+    fn mm_tick_emits_desired_grid() {
+        let mut ctx = ctx(BotState::MMNormal);
+        let events = tick(&mut ctx, input(5.0, 500.0));
+
+        let grid = events.iter().find_map(|e| match e {
+            EngineEvent::DesiredGrid(orders) => Some(orders.clone()),
+            _ => None,
+        });
+        assert_eq!(grid.map(|g| g.len()), Some(ctx.desired.len()));
+        assert!(!ctx.desired.is_empty());
+    }
+
+    #[test]
+    fn order_rate_holds_grid_and_halt_is_cleared_manually() {
+        let mut ctx = ctx(BotState::MMNormal);
+        ctx.risk = RiskManager::new(RiskLimits {
+            max_orders_per_min: Some(2),
+            ..RiskLimits::default()
+        });
+        tick(&mut ctx, input(5.0, 500.0));
+        let grid: Vec<f64> = ctx.desired.iter().map(|o| o.price.0).collect();
+        ctx.risk.on_orders_submitted(TimestampMs(0), 2);
+
+        // лимит частоты: сетка не обновляется, но и не снимается
+        let events = tick(&mut ctx, input(5.0, 500.0));
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::DesiredGrid(_)))
+        );
+        assert_eq!(
+            ctx.desired.iter().map(|o| o.price.0).collect::<Vec<_>>(),
+            grid
+        );
+        assert_eq!(ctx.state, BotState::MMNormal);
+
+        ctx.risk.engage_kill_switch();
+        tick(&mut ctx, input(5.0, 500.0));
+        assert_eq!(ctx.state, BotState::Halted);
+        assert!(tick(&mut ctx, input(5.0, 500.0)).is_empty());
+
+        let events = clear_halt(&mut ctx, None);
+        assert_eq!(events.len(), 1);
+        assert_eq!(ctx.state, BotState::IdleUSDT);
+        assert!(!ctx.risk.kill_switch());
+    }
+
+    #[test]
+    fn drawdown_halt_cleared_by_unhalt_keeps_running() {
+        let mut ctx = ctx(BotState::MMNormal);
+        ctx.risk = RiskManager::new(RiskLimits {
+            max_drawdown: Some(Money(100.0)),
+            ..RiskLimits::default()
+        });
+        let mut equity = EquityTracker::new(500.0, 5.0, Price(100.0));
+        let feed_drawdown = |ctx: &mut EngineCtx, equity: &mut EquityTracker| {
+            if let EngineEvent::EquitySnapshot { drawdown, .. } =
+                equity.snapshot(TimestampMs(0), Price(50.0))
+            {
+                ctx.risk.on_equity(drawdown);
+            }
+        };
+
+        // equity 1000 -> 750: просадка 250 > 100
+        feed_drawdown(&mut ctx, &mut equity);
+        tick(&mut ctx, input(5.0, 500.0));
+        assert_eq!(ctx.state, BotState::Halted);
+
+        clear_halt(&mut ctx, Some(&mut equity));
+        feed_drawdown(&mut ctx, &mut equity);
+        for _ in 0..3 {
+            let events = tick(&mut ctx, input(5.0, 500.0));
+            assert_ne!(ctx.state, BotState::Halted);
+            assert!(
+                !events
+                    .iter()
+                    .any(|e| matches!(e, EngineEvent::Alert { .. }))
+            );
+        }
+    }
+
+    #[test]
+    fn bad_market_data_disables_grid() {
+        let mut ctx = ctx(BotState::MMNormal);
+        let mut inp = input(5.0, 500.0);
+        inp.data_quality = DataQuality::Bad;
+        let events = tick(&mut ctx, inp);
+
+        assert!(events.iter().any(|e| matches!(
+            e,
+            EngineEvent::PolicyDecision {
+                mode: MmMode::Disabled,
+                ..
+            }
+        )));
+        assert!(ctx.desired.is_empty());
+    }
+
+    #[test]
+    fn rebalancing_emits_intent_until_inventory_is_balanced() {
+        let mut ctx = ctx(BotState::Rebalancing);
+        ctx.mm_policy.hard_min = Ratio(0.0);
+        ctx.mm_policy.soft_min = Ratio(0.0);
+
+        let events = tick(&mut ctx, input(1.0, 900.0));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            EngineEvent::RebalanceIntent(RebalanceDecision::BuyBase(_))
+        )));
+        assert_eq!(ctx.state, BotState::Rebalancing);
+
+        let events = tick(&mut ctx, input(5.0, 500.0));
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, EngineEvent::RebalanceIntent(_)))
+        );
+        assert_eq!(ctx.state, BotState::MMNormal);
+    }
+
+    #[test]
+    fn ltf_recovery_reaches_only_the_next_tick() {
+        let mut ctx = ctx(BotState::MMNormal);
+        let mut ltf = LtfSignals::default();
+        let ltf_input = |ltf: &mut LtfSignals| {
+            let (broken, recovered) = ltf.take();
+            TickInput {
+                ltf_broken_down: broken,
+                ltf_recovered: recovered,
+                ..input(5.0, 500.0)
+            }
+        };
+        let recovered = |events: &[EngineEvent]| {
+            events.iter().any(|e| {
+                matches!(
+                    e,
+                    EngineEvent::Transition {
+                        cause: TransitionCause::LtfStructureRecovered,
+                        ..
+                    }
+                )
+            })
+        };
+
+        ltf.on_signal(LtfSignal::BrokenDown);
+        tick(&mut ctx, ltf_input(&mut ltf));
+        assert_eq!(ctx.state, BotState::MMDefensive);
+
+        // слом держится, пока нет восстановления
+        ltf.on_signal(LtfSignal::None);
+        tick(&mut ctx, ltf_input(&mut ltf));
+        assert_eq!(ctx.state, BotState::MMDefensive);
+
+        ltf.on_signal(LtfSignal::Recovered);
+        let events = tick(&mut ctx, ltf_input(&mut ltf));
+        assert!(recovered(&events));
+        assert_eq!(ctx.state, BotState::MMNormal);
+
+        // следующий тик без нового восстановления его не повторяет
+        let events = tick(&mut ctx, ltf_input(&mut ltf));
+        assert!(!recovered(&events));
+        assert_eq!(ltf.take(), (false, false));
+    }
+}
@@ -0,0 +1,160 @@
+use core::types::{Price, Qty};
+use policy::trend_policy::{
+    TrendAction, TrendDecisionReason, TrendMode, TrendPolicyDecision, TrendPolicyInput,
+    TrendPolicyParams, trend_policy_decision,
+};
+use state_machine::trend_cause::TrendCause;
+use state_machine::trend_state::TrendState;
+use state_machine::trend_transition::trend_transition;
+
+/// Параметры тренд-стратегии в engine (как у backtest_trend)
+#[derive(Debug, Copy, Clone)]
+pub struct TrendParams {
+    pub ema_fast: usize,
+    pub ema_slow: usize,
+    pub atr_stop_mult: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    fn new(period: usize) -> Self {
+        Self {
+            alpha: 2.0 / (period.max(1) as f64 + 1.0),
+            value: None,
+        }
+    }
+
+    fn update(&mut self, x: f64) -> f64 {
+        let next = match self.value {
+            Some(v) => self.alpha * x + (1.0 - self.alpha) * v,
+            None => x,
+        };
+        self.value = Some(next);
+        next
+    }
+}
+
+/// Long-only тренд по EMA fast/slow с ATR-стопом, на HTF свечах.
+///
+/// Сама не торгует: отдаёт решение policy, исполнение (и проверку
+/// риска) делает runtime на своём под-счёте.
+#[derive(Debug, Clone)]
+pub struct TrendStrategy {
+    pub params: TrendParams,
+    pub state: TrendState,
+    pub entry_price: Option<Price>,
+    ema_fast: Ema,
+    ema_slow: Ema,
+    /// Сколько свечей прошло через EMA (до ema_slow решений нет)
+    bars: usize,
+}
+
+impl TrendStrategy {
+    pub fn new(params: TrendParams) -> Self {
+        Self {
+            params,
+            state: TrendState::Flat,
+            entry_price: None,
+            ema_fast: Ema::new(params.ema_fast),
+            ema_slow: Ema::new(params.ema_slow),
+            bars: 0,
+        }
+    }
+
+    /// Только прогрев EMA (история при старте)
+    pub fn warm_up(&mut self, close: Price) {
+        self.ema_fast.update(close.0);
+        self.ema_slow.update(close.0);
+        self.bars += 1;
+    }
+
+    /// Решение на закрытии свечи. `position` — base под-счёта стратегии.
+    /// `None`, пока EMA не прогреты.
+    pub fn on_candle(
+        &mut self,
+        close: Price,
+        atr: Price,
+        position: Qty,
+    ) -> Option<TrendPolicyDecision> {
+        let fast = self.ema_fast.update(close.0);
+        let slow = self.ema_slow.update(close.0);
+        self.bars += 1;
+        if self.bars < self.params.ema_slow {
+            return None;
+        }
+
+        let mode = match self.state {
+            TrendState::Flat => TrendMode::Flat,
+            TrendState::Long => TrendMode::Long,
+        };
+        let d = trend_policy_decision(
+            mode,
+            TrendPolicyInput {
+                close,
+                atr,
+                ema_fast: Price(fast),
+                ema_slow: Price(slow),
+                position_qty: position,
+                entry_price: self.entry_price,
+            },
+            TrendPolicyParams {
+                atr_stop_mult: self.params.atr_stop_mult,
+            },
+        );
+
+        let cause = match (d.action, d.reason) {
+            (TrendAction::EnterLong, _) => Some(TrendCause::EntrySignal),
+            (TrendAction::ExitLong, TrendDecisionReason::AtrStopHit) => {
+                Some(TrendCause::StopLossHit)
+            }
+            (TrendAction::ExitLong, _) => Some(TrendCause::ExitSignal),
+            _ => None,
+        };
+        self.state = match cause.map(|c| trend_transition(self.state, c)) {
+            Some(Ok(next)) => next,
+            // policy нормализует режим под фактическую позицию
+            _ => match d.next_mode {
+                TrendMode::Flat => TrendState::Flat,
+                TrendMode::Long => TrendState::Long,
+            },
+        };
+        if self.state == TrendState::Flat {
+            self.entry_price = None;
+        }
+
+        Some(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enters_on_uptrend_after_warm_up_and_exits_on_stop() {
+        let mut s = TrendStrategy::new(TrendParams {
+            ema_fast: 2,
+            ema_slow: 4,
+            atr_stop_mult: 1.0,
+        });
+        for _ in 0..2 {
+            s.warm_up(Price(100.0));
+        }
+        assert_eq!(s.on_candle(Price(101.0), Price(1.0), Qty(0.0)), None);
+
+        let d = s.on_candle(Price(102.0), Price(1.0), Qty(0.0)).unwrap();
+        assert_eq!(d.action, TrendAction::EnterLong);
+        assert_eq!(s.state, TrendState::Long);
+        s.entry_price = Some(Price(102.0));
+
+        let d = s.on_candle(Price(100.5), Price(1.0), Qty(1.0)).unwrap();
+        assert_eq!(d.reason, TrendDecisionReason::AtrStopHit);
+        assert_eq!(s.state, TrendState::Flat);
+        assert_eq!(s.entry_price, None);
+    }
+}
@@ -0,0 +1,175 @@
+use core::types::{Money, Price, Qty, TimestampMs};
+
+use execution::accounting::Ledger;
+use execution::sim::ExecutionModel;
+use mm::grid::Inventory;
+
+use crate::allocator::{StrategyKind, allocate};
+use crate::event::EngineEvent;
+use crate::live::LiveAccount;
+use crate::orders::OrderPlanner;
+use crate::paper::PaperBroker;
+
+/// Где исполняются решения тика — режим engine
+pub enum Venue {
+    /// Только решения, без ордеров; inventory — стартовые балансы из CLI
+    Observe { inventory: Inventory },
+    /// Живые данные + симулированное исполнение
+    Paper(Box<PaperVenue>),
+    /// Ордера на бирже
+    Live(Box<LiveAccount>),
+}
+
+/// Paper: у каждой стратегии свой виртуальный под-счёт, создаются по первой цене
+pub struct PaperVenue {
+    pub exec: ExecutionModel,
+    pub strategies: Vec<StrategyKind>,
+    pub initial_quote: f64,
+    pub initial_base: f64,
+    pub trend_share: f64,
+    pub mm: Option<PaperBroker>,
+    pub trend: Option<PaperBroker>,
+    /// batch'и ордеров с приоритетом и rate limit (иначе сетка заменяется целиком)
+    pub planner: Option<OrderPlanner>,
+}
+
+impl PaperVenue {
+    pub fn new(
+        exec: ExecutionModel,
+        strategies: Vec<StrategyKind>,
+        initial_quote: f64,
+        initial_base: f64,
+        trend_share: f64,
+        planner: Option<OrderPlanner>,
+    ) -> Self {
+        Self {
+            exec,
+            strategies,
+            initial_quote,
+            initial_base,
+            trend_share,
+            mm: None,
+            trend: None,
+            planner,
+        }
+    }
+
+    /// Делит стартовый капитал между под-счетами; cost basis base — по `mark`
+    pub fn open(&mut self, mark: Price) {
+        if self.mm.is_some() {
+            return;
+        }
+        for a in allocate(
+            &self.strategies,
+            self.initial_quote,
+            self.initial_base,
+            self.trend_share,
+        ) {
+            let broker = PaperBroker::new(self.exec, a.quote, a.base, mark);
+            match a.strategy {
+                StrategyKind::Mm => self.mm = Some(broker),
+                StrategyKind::Trend => self.trend = Some(broker),
+            }
+        }
+        // без MM пустой под-счёт, чтобы paper-режим оставался включён
+        if self.mm.is_none() {
+            self.mm = Some(PaperBroker::new(self.exec, 0.0, 0.0, mark));
+        }
+    }
+
+    /// Исполняет intent'ы тика на под-счёте MM, возвращает fill'ы
+    ///
+    /// С планировщиком сетка обновляется как на бирже: diff с текущими ордерами,
+    /// batch'и в пределах rate limit, остальное — на следующем тике.
+    pub fn apply_tick(
+        &mut self,
+        events: &[EngineEvent],
+        ts: TimestampMs,
+        mid: Price,
+    ) -> Vec<EngineEvent> {
+        let mut fills = Vec::new();
+        let Some(broker) = self.mm.as_mut() else {
+            return fills;
+        };
+        for e in events {
+            match e {
+                EngineEvent::DesiredGrid(orders) => match self.planner.as_mut() {
+                    Some(p) => {
+                        let plan = p.plan(ts, &broker.open_orders(), orders, mid);
+                        broker.apply_batches(&plan.batches);
+                        if !plan.batches.is_empty() || plan.deferred > 0 {
+                            fills.push(EngineEvent::OrdersSubmitted {
+                                cancels: plan.cancels(),
+                                places: plan.places(),
+                                batches: plan.batches.len(),
+                                deferred: plan.deferred,
+                            });
+                        }
+                    }
+                    None => broker.set_orders(orders),
+                },
+                EngineEvent::RebalanceIntent(d) => fills.extend(broker.rebalance(ts, mid, *d)),
+                _ => {}
+            }
+        }
+        fills
+    }
+}
+
+impl Venue {
+    /// Стартовые (quote, base) для учёта equity. В live учёт fill'ов
+    /// начинается с этой же цены.
+    pub fn start_balances(&mut self, mark: Price) -> (f64, f64) {
+        match self {
+            Venue::Observe { inventory } => (inventory.quote.0, inventory.base.0),
+            Venue::Paper(p) => (p.initial_quote, p.initial_base),
+            Venue::Live(m) => {
+                let (quote, base) = (m.start.quote.0, m.start.base.0);
+                m.orders.ledger = Ledger::new(quote, base, mark.0);
+                (quote, base)
+            }
+        }
+    }
+
+    /// Inventory для тика MM
+    pub fn inventory(&self) -> Inventory {
+        match self {
+            Venue::Observe { inventory } => *inventory,
+            Venue::Paper(p) => p.mm.as_ref().map_or(
+                Inventory {
+                    base: Qty(p.initial_base),
+                    quote: Money(p.initial_quote),
+                },
+                |b| b.inventory(),
+            ),
+            Venue::Live(m) => m.inventory(),
+        }
+    }
+
+    /// Под-счёт MM в paper
+    pub fn mm_broker(&mut self) -> Option<&mut PaperBroker> {
+        match self {
+            Venue::Paper(p) => p.mm.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Под-счёт тренда в paper
+    pub fn trend_broker(&mut self) -> Option<&mut PaperBroker> {
+        match self {
+            Venue::Paper(p) => p.trend.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// base под-счетов (MM, тренд): у каждой стратегии свой лимит позиции
+    pub fn paper_base(&self) -> (Qty, Qty) {
+        match self {
+            Venue::Paper(p) => (
+                Qty(p.mm.as_ref().map_or(0.0, |b| b.ledger.base)),
+                Qty(p.trend.as_ref().map_or(0.0, |b| b.ledger.base)),
+            ),
+            _ => (Qty(0.0), Qty(0.0)),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+
+use exchange::traits::Exchange;
+use structure::candle::Candle;
+
+/// Интервал Bybit ("1", "5", ...) → миллисекунды
+pub fn interval_ms(interval: &str) -> Result<i64> {
+    let mins: i64 = interval
+        .parse()
+        .with_context(|| format!("interval must be numeric minutes, got {}", interval))?;
+    if mins <= 0 {
+        anyhow::bail!("interval must be > 0");
+    }
+    Ok(mins * 60 * 1000)
+}
+
+/// REST отдаёт и текущую (ещё не закрытую) свечу — её выбрасываем,
+/// иначе структура считалась бы по неполным данным.
+pub fn drop_unclosed(candles: &mut Vec<Candle>, interval_ms: i64, now_ms: i64) {
+    candles.retain(|c| c.ts.0 + interval_ms <= now_ms);
+}
+
+/// Загружает последние `count` закрытых свечей до `now_ms`,
+/// чтобы ATR/структура/BOS были валидны сразу после старта.
+pub async fn fetch_history(
+    api: &impl Exchange,
+    symbol: &str,
+    interval: &str,
+    count: usize,
+    now_ms: i64,
+) -> Result<Vec<Candle>> {
+    let step = interval_ms(interval)?;
+    // +1 свеча запаса под текущую незакрытую
+    let start_ms = now_ms - step * (count as i64 + 1);
+
+    let mut candles = api
+        .candles(symbol, interval, start_ms, now_ms)
+        .await
+        .with_context(|| {
+            format!("warm-up download failed: {} {} {}", api.name(), symbol, interval)
+        })?;
+    drop_unclosed(&mut candles, step, now_ms);
+
+    if candles.len() > count {
+        let excess = candles.len() - count;
+        candles.drain(0..excess);
+    }
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Price, Qty, TimestampMs};
+    use exchange::types::{Balances, BookTop, Instrument};
+    use execution::orders::NewOrder;
+
+    /// Свечи каждую минуту с 0; без ключей
+    struct Minutes;
+
+    impl Exchange for Minutes {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn candles(
+            &self,
+            _symbol: &str,
+            _interval: &str,
+            start_ms: i64,
+            end_ms: i64,
+        ) -> Result<Vec<Candle>> {
+            Ok((start_ms.max(0) / 60_000..=end_ms / 60_000)
+                .map(|i| candle(i * 60_000))
+                .collect())
+        }
+
+        async fn book_top(&self, _symbol: &str) -> Result<Option<BookTop>> {
+            Ok(None)
+        }
+
+        async fn instrument(&self, _symbol: &str) -> Result<Instrument> {
+            anyhow::bail!("not supported")
+        }
+
+        async fn balances(&self, _instrument: &Instrument) -> Result<Balances> {
+            anyhow::bail!("not supported")
+        }
+
+        async fn place_order(&self, _symbol: &str, _order: &NewOrder) -> Result<String> {
+            anyhow::bail!("not supported")
+        }
+
+        async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<()> {
+            anyhow::bail!("not supported")
+        }
+    }
+
+    fn candle(ts: i64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(1.0),
+            high: Price(1.0),
+            low: Price(1.0),
+            close: Price(1.0),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn drops_candle_that_is_still_forming() {
+        let step = 60_000;
+        let mut candles = vec![candle(0), candle(step), candle(2 * step)];
+        drop_unclosed(&mut candles, step, 2 * step + 30_000);
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles.last().unwrap().ts.0, step);
+    }
+
+    #[test]
+    fn fetch_history_keeps_last_closed_candles() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let now = 10 * 60_000 + 30_000;
+        let candles = rt
+            .block_on(fetch_history(&Minutes, "ETHUSDT", "1", 3, now))
+            .unwrap();
+        let ts: Vec<i64> = candles.iter().map(|c| c.ts.0 / 60_000).collect();
+        assert_eq!(ts, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn rejects_non_numeric_interval() {
+        assert!(interval_ms("D").is_err());
+        assert_eq!(interval_ms("5").unwrap(), 300_000);
+    }
+}
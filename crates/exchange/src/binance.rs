@@ -0,0 +1,459 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use tokio_tungstenite::tungstenite::Message;
+
+use ::bybit::ws::{MarketEvent, WsConfig, WsTopic};
+use core::types::{Money, Price, Qty, TimestampMs};
+use execution::orders::NewOrder;
+use mm::grid::LotFilters;
+use structure::candle::Candle;
+
+use crate::traits::Exchange;
+use crate::types::{Balances, BookTop, Instrument};
+
+const REST_BASE: &str = "https://api.binance.com";
+const WS_BASE: &str = "wss://stream.binance.com:9443/stream";
+/// Максимум свечей на страницу `/api/v3/klines`
+const KLINES_LIMIT: usize = 1000;
+/// Пауза между страницами: лимит по весу — 6000 в минуту, klines весит 2
+const PAGE_PAUSE: Duration = Duration::from_millis(100);
+
+/// Интервал в минутах (как у Bybit: "1", "60", "D") → формат Binance ("1m", "1h", "1d")
+pub fn binance_interval(interval: &str) -> Result<&'static str> {
+    Ok(match interval {
+        "1" => "1m",
+        "3" => "3m",
+        "5" => "5m",
+        "15" => "15m",
+        "30" => "30m",
+        "60" => "1h",
+        "120" => "2h",
+        "240" => "4h",
+        "360" => "6h",
+        "720" => "12h",
+        "D" | "1440" => "1d",
+        "W" => "1w",
+        "M" => "1M",
+        other => bail!("interval {} is not supported by binance", other),
+    })
+}
+
+/// Обратно: интервал kline-потока Binance → минуты; `None` — неизвестный
+fn minutes_interval(interval: &str) -> Option<&'static str> {
+    Some(match interval {
+        "1m" => "1",
+        "3m" => "3",
+        "5m" => "5",
+        "15m" => "15",
+        "30m" => "30",
+        "60m" | "1h" => "60",
+        "2h" => "120",
+        "4h" => "240",
+        "6h" => "360",
+        "12h" => "720",
+        "1d" => "D",
+        "1w" => "W",
+        "1M" => "M",
+        _ => return None,
+    })
+}
+
+/// Строка `/api/v3/klines`: `[openTime, "open", "high", "low", "close", "volume", closeTime, ...]`
+fn kline_row(row: &[Value]) -> Result<Candle> {
+    let num = |i: usize| -> Result<f64> {
+        row.get(i)
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse().ok())
+            .with_context(|| format!("bad kline field {}", i))
+    };
+    let ts = row
+        .first()
+        .and_then(Value::as_i64)
+        .context("bad kline open time")?;
+    Ok(Candle {
+        ts: TimestampMs(ts),
+        open: Price(num(1)?),
+        high: Price(num(2)?),
+        low: Price(num(3)?),
+        close: Price(num(4)?),
+        volume: Qty(num(5)?),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    code: i64,
+    msg: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawBookTicker {
+    bid_price: String,
+    ask_price: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawExchangeInfo {
+    symbols: Vec<RawSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSymbol {
+    symbol: String,
+    base_asset: String,
+    quote_asset: String,
+    filters: Vec<Value>,
+}
+
+impl RawSymbol {
+    /// PRICE_FILTER, LOT_SIZE и NOTIONAL (у старых пар — MIN_NOTIONAL)
+    fn filters(&self) -> LotFilters {
+        let mut out = LotFilters::default();
+        for f in &self.filters {
+            let field = |name: &str| {
+                f.get(name)
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .unwrap_or(0.0)
+            };
+            match f.get("filterType").and_then(Value::as_str) {
+                Some("PRICE_FILTER") => out.tick_size = Price(field("tickSize")),
+                Some("LOT_SIZE") => {
+                    out.qty_step = Qty(field("stepSize"));
+                    out.min_qty = Qty(field("minQty"));
+                }
+                Some("NOTIONAL" | "MIN_NOTIONAL") => out.min_notional = Money(field("minNotional")),
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+/// Binance spot, только market data: ключи и ордера пока не поддержаны
+#[derive(Clone)]
+pub struct BinanceExchange {
+    client: reqwest::Client,
+    base: String,
+}
+
+impl Default for BinanceExchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinanceExchange {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base: REST_BASE.to_string(),
+        }
+    }
+
+    /// GET публичного эндпоинта; ошибка Binance (`{"code","msg"}`) — в тексте ошибки
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base, path))
+            .query(query)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            match serde_json::from_str::<ApiError>(&body) {
+                Ok(e) => bail!("{} failed: {} {} (HTTP {})", path, e.code, e.msg, status),
+                Err(_) => bail!("{} failed: HTTP {}", path, status),
+            }
+        }
+        serde_json::from_str(&body).with_context(|| format!("{}: unexpected response", path))
+    }
+}
+
+impl Exchange for BinanceExchange {
+    fn name(&self) -> &str {
+        "binance"
+    }
+
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Candle>> {
+        let binance = binance_interval(interval)?;
+        let mut all: Vec<Candle> = Vec::new();
+        let mut cursor = start_ms;
+        while cursor <= end_ms {
+            let rows: Vec<Vec<Value>> = self
+                .get(
+                    "/api/v3/klines",
+                    &[
+                        ("symbol", symbol.to_ascii_uppercase()),
+                        ("interval", binance.to_string()),
+                        ("startTime", cursor.to_string()),
+                        ("endTime", end_ms.to_string()),
+                        ("limit", KLINES_LIMIT.to_string()),
+                    ],
+                )
+                .await?;
+            let page = rows
+                .iter()
+                .map(|r| kline_row(r))
+                .collect::<Result<Vec<_>>>()?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = last.ts.0 + 1;
+            let full = page.len() == KLINES_LIMIT;
+            all.extend(page);
+            if !full {
+                break;
+            }
+            tokio::time::sleep(PAGE_PAUSE).await;
+        }
+        Ok(all)
+    }
+
+    async fn book_top(&self, symbol: &str) -> Result<Option<BookTop>> {
+        let t: RawBookTicker = self
+            .get(
+                "/api/v3/ticker/bookTicker",
+                &[("symbol", symbol.to_ascii_uppercase())],
+            )
+            .await?;
+        let (bid, ask) = (t.bid_price.parse::<f64>()?, t.ask_price.parse::<f64>()?);
+        Ok((bid > 0.0 && ask > 0.0).then_some(BookTop {
+            bid: Price(bid),
+            ask: Price(ask),
+        }))
+    }
+
+    async fn instrument(&self, symbol: &str) -> Result<Instrument> {
+        let info: RawExchangeInfo = self
+            .get(
+                "/api/v3/exchangeInfo",
+                &[("symbol", symbol.to_ascii_uppercase())],
+            )
+            .await?;
+        let Some(s) = info.symbols.into_iter().next() else {
+            bail!("binance instrument {} not found", symbol);
+        };
+        Ok(Instrument {
+            filters: s.filters(),
+            symbol: s.symbol,
+            base_coin: s.base_asset,
+            quote_coin: s.quote_asset,
+        })
+    }
+
+    async fn balances(&self, _instrument: &Instrument) -> Result<Balances> {
+        bail!("binance adapter is market data only")
+    }
+
+    async fn place_order(&self, _symbol: &str, _order: &NewOrder) -> Result<String> {
+        bail!("binance adapter is market data only")
+    }
+
+    async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<()> {
+        bail!("binance adapter is market data only")
+    }
+}
+
+/// Имена потоков combined stream для подписки `config`.
+/// `OrderBook` — `bookTicker` (лучшие цены без локальной книги)
+fn stream_names(config: &WsConfig) -> Result<Vec<String>> {
+    let symbol = config.symbol.to_ascii_lowercase();
+    let mut out = Vec::new();
+    for i in &config.kline_intervals {
+        out.push(format!("{}@kline_{}", symbol, binance_interval(i)?));
+    }
+    for t in &config.topics {
+        out.push(match t {
+            WsTopic::Ticker => format!("{}@miniTicker", symbol),
+            WsTopic::Trades => format!("{}@trade", symbol),
+            WsTopic::OrderBook => format!("{}@bookTicker", symbol),
+        });
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    stream: String,
+    data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawKline {
+    t: i64,
+    i: String,
+    o: String,
+    h: String,
+    l: String,
+    c: String,
+    v: String,
+    /// Свеча закрыта
+    x: bool,
+}
+
+/// Событие из сообщения combined stream; `None` — незакрытая свеча или чужой поток
+fn parse_stream_message(text: &str) -> Option<MarketEvent> {
+    let msg: StreamMessage = serde_json::from_str(text).ok()?;
+    let (_, kind) = msg.stream.split_once('@')?;
+    let str_num = |v: &Value, name: &str| v.get(name)?.as_str()?.parse::<f64>().ok();
+    if kind.starts_with("kline_") {
+        let k: RawKline = serde_json::from_value(msg.data.get("k")?.clone()).ok()?;
+        if !k.x {
+            return None; // только закрытые свечи
+        }
+        let candle = Candle {
+            ts: TimestampMs(k.t),
+            open: Price(k.o.parse().ok()?),
+            high: Price(k.h.parse().ok()?),
+            low: Price(k.l.parse().ok()?),
+            close: Price(k.c.parse().ok()?),
+            volume: Qty(k.v.parse().ok()?),
+        };
+        let interval = minutes_interval(&k.i)?.to_string();
+        return Some(MarketEvent::Candle { interval, candle });
+    }
+    match kind {
+        "miniTicker" => Some(MarketEvent::Ticker {
+            mid: Price(str_num(&msg.data, "c")?),
+        }),
+        "trade" => Some(MarketEvent::Trade {
+            ts: TimestampMs(msg.data.get("T")?.as_i64()?),
+            price: Price(str_num(&msg.data, "p")?),
+            qty: Qty(str_num(&msg.data, "q")?),
+        }),
+        "bookTicker" => {
+            let (bid, ask) = (str_num(&msg.data, "b")?, str_num(&msg.data, "a")?);
+            (bid > 0.0 && bid < ask).then_some(MarketEvent::BookTop {
+                bid: Price(bid),
+                ask: Price(ask),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Пауза перед переподключением: 1с, 2с, 4с... до 30с
+fn reconnect_backoff(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.min(5)).min(Duration::from_secs(30))
+}
+
+/// Публичный WS Binance с теми же событиями, что `bybit::ws::run_ws`:
+/// интервалы свечей в событиях — в минутах, как в `config`.
+/// Переподключается после обрыва (Binance рвёт соединение раз в сутки);
+/// завершается, когда получатель `tx` закрыт или подписка невалидна.
+pub async fn run_ws(config: WsConfig, tx: Sender<MarketEvent>) {
+    let streams = match stream_names(&config) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("binance WS: {:#}", e);
+            return;
+        }
+    };
+    let url = format!("{}?streams={}", WS_BASE, streams.join("/"));
+
+    let mut reconnects = 0u64;
+    let mut failures = 0u32;
+    loop {
+        match session(&url, &tx).await {
+            Ok(()) => failures = 0,
+            Err(e) => {
+                eprintln!("binance WS session failed: {:#}", e);
+                failures += 1;
+            }
+        }
+        if tx.is_closed() {
+            return;
+        }
+
+        tokio::time::sleep(reconnect_backoff(failures)).await;
+        reconnects += 1;
+        let ev = MarketEvent::Reconnected {
+            attempt: reconnects,
+        };
+        if tx.send(ev).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Одно соединение: подписка задана в URL, ping'и биржи tungstenite отвечает сам
+async fn session(url: &str, tx: &Sender<MarketEvent>) -> Result<()> {
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await?;
+    while let Some(msg) = ws.next().await {
+        let Message::Text(text) = msg? else { continue };
+        if let Some(ev) = parse_stream_message(&text) {
+            if tx.send(ev).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_klines_intervals_and_streams() {
+        assert_eq!(binance_interval("5").unwrap(), "5m");
+        assert_eq!(binance_interval("240").unwrap(), "4h");
+        assert_eq!(minutes_interval(binance_interval("D").unwrap()), Some("D"));
+        assert!(binance_interval("7").is_err());
+
+        let rows: Vec<Vec<Value>> = serde_json::from_str(
+            r#"[[1700000000000,"2000.1","2010","1995.5","2005","12.5",1700000299999,"25000",10,"6","12000","0"]]"#,
+        )
+        .unwrap();
+        let c = kline_row(&rows[0]).unwrap();
+        assert_eq!(c.ts, TimestampMs(1_700_000_000_000));
+        assert_eq!(
+            (c.open, c.close, c.volume),
+            (Price(2000.1), Price(2005.0), Qty(12.5))
+        );
+
+        let config = WsConfig {
+            symbol: "ETHUSDT".to_string(),
+            kline_intervals: vec!["60".to_string()],
+            topics: vec![WsTopic::OrderBook],
+        };
+        assert_eq!(
+            stream_names(&config).unwrap(),
+            vec!["ethusdt@kline_1h", "ethusdt@bookTicker"]
+        );
+
+        let closed = r#"{"stream":"ethusdt@kline_1h","data":{"e":"kline","k":{"t":1700000000000,"i":"1h","o":"1","h":"3","l":"0.5","c":"2","v":"10","x":true}}}"#;
+        match parse_stream_message(closed) {
+            Some(MarketEvent::Candle { interval, candle }) => {
+                assert_eq!(interval, "60");
+                assert_eq!(candle.high, Price(3.0));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_stream_message(&closed.replace("true", "false")).is_none());
+
+        let top = r#"{"stream":"ethusdt@bookTicker","data":{"u":1,"s":"ETHUSDT","b":"100.5","B":"1","a":"101","A":"2"}}"#;
+        match parse_stream_message(top) {
+            Some(MarketEvent::BookTop { bid, ask }) => {
+                assert_eq!((bid, ask), (Price(100.5), Price(101.0)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{Result, bail};
+use execution::orders::NewOrder;
+use execution::traits::OrderGateway;
+use structure::candle::Candle;
+
+use ::bybit::private::BybitPrivate;
+use ::bybit::rest::{BybitRest, download_range};
+
+use crate::traits::Exchange;
+use crate::types::{Balances, BookTop, Instrument};
+
+/// Bybit spot: публичный REST и, с ключами, signed-клиент одного символа
+#[derive(Clone)]
+pub struct BybitExchange {
+    rest: BybitRest,
+    private: Option<BybitPrivate>,
+}
+
+impl BybitExchange {
+    /// Только market data
+    pub fn public() -> Self {
+        Self {
+            rest: BybitRest::new(),
+            private: None,
+        }
+    }
+
+    /// Market data и ордера/балансы символа `private`
+    pub fn with_private(private: BybitPrivate) -> Self {
+        Self {
+            rest: BybitRest::new(),
+            private: Some(private),
+        }
+    }
+
+    fn private_for(&self, symbol: &str) -> Result<&BybitPrivate> {
+        match &self.private {
+            Some(p) if p.symbol() == symbol => Ok(p),
+            Some(p) => bail!("bybit client is bound to {}, not {}", p.symbol(), symbol),
+            None => bail!("bybit client has no api keys"),
+        }
+    }
+}
+
+impl Exchange for BybitExchange {
+    fn name(&self) -> &str {
+        "bybit"
+    }
+
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Candle>> {
+        download_range(&self.rest, symbol, interval, start_ms, end_ms).await
+    }
+
+    async fn book_top(&self, symbol: &str) -> Result<Option<BookTop>> {
+        let top = self.rest.get_orderbook_top(symbol).await?;
+        Ok(top.map(|(bid, ask)| BookTop { bid, ask }))
+    }
+
+    async fn instrument(&self, symbol: &str) -> Result<Instrument> {
+        let info = self.rest.get_instrument_info(symbol).await?;
+        Ok(Instrument {
+            filters: info.filters(),
+            symbol: info.symbol,
+            base_coin: info.base_coin,
+            quote_coin: info.quote_coin,
+        })
+    }
+
+    async fn balances(&self, instrument: &Instrument) -> Result<Balances> {
+        let w = self
+            .private_for(&instrument.symbol)?
+            .wallet_balance(&instrument.base_coin, &instrument.quote_coin)
+            .await?;
+        Ok(Balances {
+            base: w.base,
+            quote: w.quote,
+        })
+    }
+
+    async fn place_order(&self, symbol: &str, order: &NewOrder) -> Result<String> {
+        self.private_for(symbol)?.place(order).await
+    }
+
+    async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()> {
+        self.private_for(symbol)?.cancel(order_id).await
+    }
+}
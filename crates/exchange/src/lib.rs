@@ -0,0 +1,7 @@
+//! Биржа за трейтом [`traits::Exchange`]: engine и backtest'ы работают с ним,
+//! а не с API конкретной биржи. Адаптеры — по модулю на биржу.
+
+pub mod binance;
+pub mod bybit;
+pub mod traits;
+pub mod types;
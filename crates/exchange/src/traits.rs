@@ -0,0 +1,43 @@
+use std::future::Future;
+
+use anyhow::Result;
+use execution::orders::NewOrder;
+use structure::candle::Candle;
+
+use crate::types::{Balances, BookTop, Instrument};
+
+/// Market data и исполнение одной биржи (spot).
+///
+/// Интервалы свечей — в минутах строкой ("1", "5", "60"); адаптер сам
+/// переводит их в формат биржи. Методы с ключами возвращают ошибку,
+/// если адаптер создан без них.
+pub trait Exchange {
+    /// Короткое имя для логов и метрик (`bybit`)
+    fn name(&self) -> &str;
+
+    /// Свечи `[start, end]` по возрастанию времени, включая текущую незакрытую
+    fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> impl Future<Output = Result<Vec<Candle>>> + Send;
+
+    /// `None` — одна из сторон книги пуста
+    fn book_top(&self, symbol: &str) -> impl Future<Output = Result<Option<BookTop>>> + Send;
+
+    fn instrument(&self, symbol: &str) -> impl Future<Output = Result<Instrument>> + Send;
+
+    fn balances(&self, instrument: &Instrument) -> impl Future<Output = Result<Balances>> + Send;
+
+    /// Выставляет лимитку, возвращает id ордера биржи
+    fn place_order(
+        &self,
+        symbol: &str,
+        order: &NewOrder,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    fn cancel_order(&self, symbol: &str, order_id: &str)
+    -> impl Future<Output = Result<()>> + Send;
+}
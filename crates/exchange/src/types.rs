@@ -0,0 +1,31 @@
+use core::types::{Money, Price, Qty};
+use mm::grid::LotFilters;
+
+/// Лучшие цены книги
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BookTop {
+    pub bid: Price,
+    pub ask: Price,
+}
+
+impl BookTop {
+    pub fn mid(&self) -> Price {
+        Price((self.bid.0 + self.ask.0) / 2.0)
+    }
+}
+
+/// Балансы пары на счёте, включая заблокированное в ордерах
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Balances {
+    pub base: Qty,
+    pub quote: Money,
+}
+
+/// Spot-инструмент: монеты пары и правила лимиток
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub symbol: String,
+    pub base_coin: String,
+    pub quote_coin: String,
+    pub filters: LotFilters,
+}
@@ -0,0 +1,111 @@
+/// Учёт позиции по средней себестоимости (average cost).
+///
+/// Общий для paper-брокера и трекера equity: комиссия покупки входит
+/// в себестоимость, комиссия продажи уменьшает выручку.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Ledger {
+    pub quote: f64,
+    pub base: f64,
+    /// Себестоимость текущего base (в quote, с комиссиями)
+    pub cost_basis_quote: f64,
+    pub realized_pnl: f64,
+    pub fees_paid: f64,
+}
+
+impl Ledger {
+    /// Стартовый base считаем купленным по `mark`
+    pub fn new(quote: f64, base: f64, mark: f64) -> Self {
+        Self {
+            quote: quote.max(0.0),
+            base: base.max(0.0),
+            cost_basis_quote: base.max(0.0) * mark,
+            ..Self::default()
+        }
+    }
+
+    /// Покупка `qty` по `price`, комиссия `fee` в quote
+    pub fn buy(&mut self, qty: f64, price: f64, fee: f64) {
+        if qty <= 0.0 {
+            return;
+        }
+        let cost = qty * price + fee;
+        self.quote -= cost;
+        self.base += qty;
+        self.cost_basis_quote += cost;
+        self.fees_paid += fee;
+    }
+
+    /// Продажа (не больше, чем есть base). Возвращает реализованный PnL.
+    pub fn sell(&mut self, qty: f64, price: f64, fee: f64) -> f64 {
+        let qty = qty.min(self.base);
+        if qty <= 0.0 {
+            return 0.0;
+        }
+
+        let removed_cost = self.avg_cost().unwrap_or(0.0) * qty;
+        let proceeds = qty * price - fee;
+        let realized = proceeds - removed_cost;
+
+        self.quote += proceeds;
+        self.base -= qty;
+        self.cost_basis_quote = (self.cost_basis_quote - removed_cost).max(0.0);
+        if self.base <= 1e-12 {
+            self.base = 0.0;
+            self.cost_basis_quote = 0.0;
+        }
+        self.realized_pnl += realized;
+        self.fees_paid += fee;
+        realized
+    }
+
+    /// Средняя цена base с учётом комиссий (она же break-even без выходной комиссии)
+    pub fn avg_cost(&self) -> Option<f64> {
+        if self.base > 0.0 {
+            Some(self.cost_basis_quote / self.base)
+        } else {
+            None
+        }
+    }
+
+    pub fn equity(&self, mark: f64) -> f64 {
+        self.quote + self.base * mark
+    }
+
+    /// Нереализованный PnL по mark (без учёта комиссии на выход)
+    pub fn unrealized_pnl(&self, mark: f64) -> f64 {
+        self.base * mark - self.cost_basis_quote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_cost_realizes_against_fee_inclusive_basis() {
+        let mut l = Ledger::new(1000.0, 0.0, 100.0);
+        l.buy(1.0, 100.0, 1.0);
+        l.buy(1.0, 110.0, 1.0);
+        assert_eq!(l.avg_cost(), Some(106.0));
+
+        let realized = l.sell(1.0, 120.0, 1.0);
+        assert_eq!(realized, 13.0);
+        assert_eq!(l.base, 1.0);
+        assert_eq!(l.cost_basis_quote, 106.0);
+        assert_eq!(l.fees_paid, 3.0);
+        assert_eq!(l.unrealized_pnl(120.0), 14.0);
+        assert_eq!(l.equity(120.0), 1000.0 - 212.0 + 119.0 + 120.0);
+    }
+
+    #[test]
+    fn sell_is_capped_by_base_and_resets_basis() {
+        let mut l = Ledger::new(0.0, 1.0, 100.0);
+        let realized = l.sell(5.0, 100.0, 0.0);
+
+        assert_eq!(realized, 0.0);
+        assert_eq!(l.base, 0.0);
+        assert_eq!(l.cost_basis_quote, 0.0);
+        assert_eq!(l.avg_cost(), None);
+        assert_eq!(l.quote, 100.0);
+    }
+}
@@ -1 +1,5 @@
+pub mod accounting;
+pub mod live;
+pub mod orders;
 pub mod sim;
+pub mod traits;
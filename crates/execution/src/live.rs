@@ -0,0 +1,503 @@
+//! Живое исполнение: держит на бирже сетку, которую строит `build_grid`,
+//! и ведёт учёт по реальным fill'ам.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use core::types::{Money, Qty, TimestampMs};
+use mm::grid::{DesiredOrder, Inventory, Side};
+
+use crate::accounting::Ledger;
+use crate::orders::{Fill, LiveOrder, NewOrder};
+use crate::traits::OrderGateway;
+
+/// Относительный допуск: ордер с остатком меньше него считается исполненным
+const FILL_TOLERANCE: f64 = 1e-9;
+/// Сколько последних `exec_id` помнить, чтобы не учесть fill дважды
+const SEEN_FILLS: usize = 1024;
+/// Сколько закрытых своих ордеров помнить: их fill'ы могут прийти после закрытия
+const CLOSED_ORDERS: usize = 256;
+
+/// Итог отправленных batch'ей: ошибки отдельных ордеров не прерывают остальные
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub placed: usize,
+    pub cancelled: usize,
+    pub errors: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn merge(&mut self, other: SyncReport) {
+        self.placed += other.placed;
+        self.cancelled += other.cancelled;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Fill с реализованным PnL по учёту менеджера (`None` для покупок)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedFill {
+    pub fill: Fill,
+    pub realized_pnl: Option<Money>,
+}
+
+/// Ордера engine на бирже: batch'и выставлений и отмен через `OrderGateway`
+/// (что отправить, решает планировщик engine) и учёт fill'ов в `Ledger`.
+///
+/// Свои ордера — выставленные в этой сессии (`orderLinkId` с префиксом сессии)
+/// и принятые после сверки на старте (`adopt`); чужие и ручные не трогаются.
+pub struct OrderManager<G> {
+    gateway: G,
+    open: Vec<LiveOrder>,
+    /// Открытые свои ордера: только их fill'ы идут в учёт
+    own: HashSet<String>,
+    /// Недавно закрытые свои ордера — для поздних fill'ов
+    closed: VecDeque<String>,
+    pub ledger: Ledger,
+    fills_cursor: TimestampMs,
+    seen_fills: VecDeque<String>,
+    /// Сумма учтённых fill'ов по открытым ордерам: push ордера и push fill'а
+    /// приходят в любом порядке, исполненный объём — максимум из двух
+    fill_totals: HashMap<String, f64>,
+    link_prefix: String,
+    next_link: u64,
+}
+
+impl<G: OrderGateway> OrderManager<G> {
+    /// Fill'ы учитываются начиная с `now`
+    pub fn new(gateway: G, ledger: Ledger, now: TimestampMs) -> Self {
+        Self {
+            gateway,
+            open: Vec::new(),
+            own: HashSet::new(),
+            closed: VecDeque::new(),
+            ledger,
+            fills_cursor: now,
+            seen_fills: VecDeque::new(),
+            fill_totals: HashMap::new(),
+            link_prefix: format!("mm{}", now.0),
+            next_link: 0,
+        }
+    }
+
+    pub fn gateway(&self) -> &G {
+        &self.gateway
+    }
+
+    pub fn open_orders(&self) -> &[LiveOrder] {
+        &self.open
+    }
+
+    pub fn inventory(&self) -> Inventory {
+        Inventory {
+            base: Qty(self.ledger.base),
+            quote: Money(self.ledger.quote),
+        }
+    }
+
+    /// Все открытые ордера символа на бирже, свои и чужие — для сверки на старте
+    pub async fn exchange_orders(&self) -> Result<Vec<LiveOrder>> {
+        self.gateway.open_orders().await
+    }
+
+    /// Принимает ордера как свои (после сверки на старте)
+    pub fn adopt(&mut self, orders: impl IntoIterator<Item = LiveOrder>) {
+        for o in orders {
+            if self.own.insert(o.order_id.clone()) {
+                self.open.push(o);
+            }
+        }
+    }
+
+    /// Перечитывает свои открытые ордера с биржи, возвращает их число.
+    /// Чужие не подхватываются, свои, которых больше нет на бирже, забываются.
+    pub async fn refresh(&mut self) -> Result<usize> {
+        let orders = self.gateway.open_orders().await?;
+        self.open = orders
+            .into_iter()
+            .filter(|o| self.own.contains(&o.order_id) || o.link_id.starts_with(&self.link_prefix))
+            .collect();
+        self.own
+            .extend(self.open.iter().map(|o| o.order_id.clone()));
+        let gone: Vec<String> = self
+            .own
+            .iter()
+            .filter(|id| !self.open.iter().any(|o| &o.order_id == *id))
+            .cloned()
+            .collect();
+        for id in gone {
+            self.forget(&id);
+        }
+        self.drop_filled();
+        Ok(self.open.len())
+    }
+
+    /// Выставляет ордера одним batch-запросом
+    pub async fn place_batch(&mut self, desired: &[DesiredOrder]) -> SyncReport {
+        let orders: Vec<NewOrder> = desired
+            .iter()
+            .map(|d| NewOrder {
+                link_id: self.next_link_id(),
+                side: d.side,
+                price: d.price,
+                qty: d.qty,
+            })
+            .collect();
+        let mut report = SyncReport::default();
+        let results = match self.gateway.place_batch(&orders).await {
+            Ok(r) => r,
+            Err(e) => {
+                report
+                    .errors
+                    .push(format!("place batch of {}: {:#}", orders.len(), e));
+                return report;
+            }
+        };
+        for (order, r) in orders.into_iter().zip(results) {
+            match r {
+                Ok(order_id) => {
+                    self.own.insert(order_id.clone());
+                    self.open.push(LiveOrder {
+                        order_id,
+                        link_id: order.link_id,
+                        side: order.side,
+                        price: order.price,
+                        qty: order.qty,
+                        filled: Qty(0.0),
+                    });
+                    report.placed += 1;
+                }
+                Err(e) => report.errors.push(format!(
+                    "place {:?} {}@{}: {:#}",
+                    order.side, order.qty.0, order.price.0, e
+                )),
+            }
+        }
+        report
+    }
+
+    /// Снимает ордера одним batch-запросом
+    pub async fn cancel_batch(&mut self, order_ids: &[String]) -> SyncReport {
+        let mut report = SyncReport::default();
+        let results = match self.gateway.cancel_batch(order_ids).await {
+            Ok(r) => r,
+            Err(e) => {
+                report
+                    .errors
+                    .push(format!("cancel batch of {}: {:#}", order_ids.len(), e));
+                return report;
+            }
+        };
+        for (id, r) in order_ids.iter().zip(results) {
+            match r {
+                Ok(()) => {
+                    self.forget(id);
+                    report.cancelled += 1;
+                }
+                Err(e) => report.errors.push(format!("cancel {}: {:#}", id, e)),
+            }
+        }
+        self.drop_filled();
+        report
+    }
+
+    /// Снимает один ордер (например, чужой — при сверке на старте)
+    pub async fn cancel(&mut self, order_id: &str) -> Result<()> {
+        self.gateway.cancel(order_id).await?;
+        self.forget(order_id);
+        Ok(())
+    }
+
+    /// Продаёт `qty` base по рынку; fill'ы придут опросом или push'ем
+    pub async fn market_sell(&mut self, qty: Qty) -> Result<String> {
+        let link_id = self.next_link_id();
+        let order_id = self.gateway.market_sell(&link_id, qty).await?;
+        self.push_closed(order_id.clone());
+        Ok(order_id)
+    }
+
+    /// Снимает все открытые ордера batch'ами по `max_batch`, без rate limit:
+    /// вызывается на паузе и при остановке. После ошибок открытые ордера
+    /// перечитываются — локальная картина могла разойтись с биржей.
+    pub async fn cancel_all(&mut self, max_batch: usize) -> SyncReport {
+        let ids: Vec<String> = self.open.iter().map(|o| o.order_id.clone()).collect();
+        let mut report = SyncReport::default();
+        for chunk in ids.chunks(max_batch.max(1)) {
+            report.merge(self.cancel_batch(chunk).await);
+        }
+        if !report.errors.is_empty() {
+            if let Err(e) = self.refresh().await {
+                report.errors.push(format!("refresh open orders: {:#}", e));
+            }
+        }
+        report
+    }
+
+    /// Новые fill'ы своих ордеров с биржи (см. `on_fill`)
+    pub async fn poll_fills(&mut self) -> Result<Vec<TrackedFill>> {
+        let fills = self.gateway.fills_since(self.fills_cursor).await?;
+        let mut out = Vec::new();
+        for f in fills {
+            // курсор включительный: fill'ы на границе приходят повторно
+            self.fills_cursor = self.fills_cursor.max(f.ts);
+            out.extend(self.on_fill(f));
+        }
+        Ok(out)
+    }
+
+    /// Fill из опроса или push-потока: учёт в `Ledger`, исполненный объём у открытого
+    /// ордера; полностью исполненные убираются. `None` — чужой или уже учтённый.
+    ///
+    /// Курсор опроса не двигает: пропущенные push'ем fill'ы подберёт `poll_fills`.
+    pub fn on_fill(&mut self, f: Fill) -> Option<TrackedFill> {
+        let own = self.own.contains(&f.order_id) || self.closed.contains(&f.order_id);
+        if !own || self.seen_fills.contains(&f.exec_id) {
+            return None;
+        }
+        if self.seen_fills.len() >= SEEN_FILLS {
+            self.seen_fills.pop_front();
+        }
+        self.seen_fills.push_back(f.exec_id.clone());
+
+        let realized_pnl = match f.side {
+            Side::Buy => {
+                self.ledger.buy(f.net_qty().0, f.price.0, f.fee.0);
+                None
+            }
+            Side::Sell => Some(Money(self.ledger.sell(f.net_qty().0, f.price.0, f.fee.0))),
+        };
+        let total = self.fill_totals.entry(f.order_id.clone()).or_default();
+        *total += f.qty.0;
+        let total = *total;
+        if let Some(o) = self.open.iter_mut().find(|o| o.order_id == f.order_id) {
+            o.filled = Qty(o.filled.0.max(total));
+        }
+        self.drop_filled();
+        Some(TrackedFill {
+            fill: f,
+            realized_pnl,
+        })
+    }
+
+    /// Состояние своего ордера из push-потока; `open == false` — снят или исполнен
+    pub fn on_order_update(&mut self, order: LiveOrder, open: bool) {
+        if !self.own.contains(&order.order_id) {
+            return;
+        }
+        if !open {
+            self.forget(&order.order_id);
+            return;
+        }
+        let counted = self.fill_totals.get(&order.order_id).copied();
+        if let Some(o) = self.open.iter_mut().find(|o| o.order_id == order.order_id) {
+            o.price = order.price;
+            o.qty = order.qty;
+            o.filled = Qty(order.filled.0.max(counted.unwrap_or(0.0)));
+        }
+        self.drop_filled();
+    }
+
+    fn drop_filled(&mut self) {
+        let filled: Vec<String> = self
+            .open
+            .iter()
+            .filter(|o| o.filled.0 >= o.qty.0 * (1.0 - FILL_TOLERANCE))
+            .map(|o| o.order_id.clone())
+            .collect();
+        for id in filled {
+            self.forget(&id);
+        }
+    }
+
+    /// Ордер закрыт: из открытых — в недавно закрытые
+    fn forget(&mut self, order_id: &str) {
+        self.open.retain(|o| o.order_id != order_id);
+        self.fill_totals.remove(order_id);
+        if self.own.remove(order_id) {
+            self.push_closed(order_id.to_string());
+        }
+    }
+
+    fn push_closed(&mut self, order_id: String) {
+        if self.closed.len() >= CLOSED_ORDERS {
+            self.closed.pop_front();
+        }
+        self.closed.push_back(order_id);
+    }
+
+    fn next_link_id(&mut self) -> String {
+        self.next_link += 1;
+        format!("{}-{}", self.link_prefix, self.next_link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::orders::LimitStatus;
+    use core::types::Price;
+
+    #[derive(Default)]
+    struct MockGateway {
+        calls: Mutex<Vec<String>>,
+        fills: Mutex<Vec<Fill>>,
+    }
+
+    impl OrderGateway for MockGateway {
+        async fn open_orders(&self) -> Result<Vec<LiveOrder>> {
+            Ok(vec![order("a", Side::Buy, 99.0, 1.0)])
+        }
+
+        async fn place(&self, order: &NewOrder) -> Result<String> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(format!("place {}", order.price.0));
+            Ok(format!("new{}", calls.len()))
+        }
+
+        async fn cancel(&self, order_id: &str) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("cancel {}", order_id));
+            Ok(())
+        }
+
+        async fn market_sell(&self, _link_id: &str, qty: Qty) -> Result<String> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(format!("sell {}", qty.0));
+            Ok(format!("new{}", calls.len()))
+        }
+
+        async fn place_batch(&self, orders: &[NewOrder]) -> Result<Vec<Result<String>>> {
+            let mut out = Vec::new();
+            for o in orders {
+                out.push(self.place(o).await);
+            }
+            Ok(out)
+        }
+
+        async fn cancel_batch(&self, order_ids: &[String]) -> Result<Vec<Result<()>>> {
+            let mut out = Vec::new();
+            for id in order_ids {
+                out.push(self.cancel(id).await);
+            }
+            Ok(out)
+        }
+
+        fn limit_status(&self) -> Option<LimitStatus> {
+            None
+        }
+
+        async fn fills_since(&self, since: TimestampMs) -> Result<Vec<Fill>> {
+            let fills = self.fills.lock().unwrap();
+            Ok(fills.iter().filter(|f| f.ts >= since).cloned().collect())
+        }
+    }
+
+    fn order(id: &str, side: Side, price: f64, qty: f64) -> LiveOrder {
+        LiveOrder {
+            order_id: id.into(),
+            link_id: String::new(),
+            side,
+            price: Price(price),
+            qty: Qty(qty),
+            filled: Qty(0.0),
+        }
+    }
+
+    fn desired(side: Side, price: f64, qty: f64) -> DesiredOrder {
+        DesiredOrder {
+            side,
+            price: Price(price),
+            qty: Qty(qty),
+        }
+    }
+
+    fn fill(exec_id: &str, order_id: &str, ts: i64, side: Side, price: f64) -> Fill {
+        Fill {
+            exec_id: exec_id.into(),
+            order_id: order_id.into(),
+            ts: TimestampMs(ts),
+            side,
+            price: Price(price),
+            qty: Qty(1.0),
+            fee: Money(0.1),
+            base_fee: Qty(0.0),
+        }
+    }
+
+    #[test]
+    fn batches_track_own_orders_and_fills() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(batch_scenario());
+    }
+
+    async fn batch_scenario() {
+        let mut m = OrderManager::new(
+            MockGateway::default(),
+            Ledger::new(1000.0, 1.0, 100.0),
+            TimestampMs(0),
+        );
+        // чужой ордер на бирже не подхватывается
+        assert_eq!(m.refresh().await.unwrap(), 0);
+        m.adopt([
+            order("b", Side::Sell, 101.5, 1.0),
+            order("c", Side::Sell, 102.0, 1.0),
+        ]);
+
+        let report = m.cancel_batch(&["c".to_string()]).await;
+        assert_eq!(report.cancelled, 1);
+        let report = m.place_batch(&[desired(Side::Buy, 98.0, 1.0)]).await;
+        assert_eq!(
+            *m.gateway.calls.lock().unwrap(),
+            vec!["cancel c", "place 98"]
+        );
+        assert_eq!(report.placed, 1);
+        assert!(report.errors.is_empty());
+        assert_eq!(m.open.len(), 2);
+        assert!(m.open.iter().any(|o| o.link_id.starts_with("mm0-")));
+        assert!(!m.own.contains("c"));
+
+        // fill чужого ордера не учитывается, повтор на границе курсора — тоже
+        m.gateway.fills.lock().unwrap().extend([
+            fill("e1", "b", 10, Side::Sell, 101.5),
+            fill("e2", "manual", 11, Side::Buy, 100.0),
+        ]);
+        let fills = m.poll_fills().await.unwrap();
+        assert_eq!(fills.len(), 1);
+        let realized = fills[0].realized_pnl.unwrap().0;
+        assert!((realized - 1.4).abs() < 1e-9);
+        assert!(m.poll_fills().await.unwrap().is_empty());
+        assert_eq!(m.ledger.base, 0.0);
+        assert!(m.open_orders().iter().all(|o| o.order_id != "b"));
+        // тот же fill из push-потока уже учтён
+        assert!(m.on_fill(fill("e1", "b", 10, Side::Sell, 101.5)).is_none());
+
+        // push ордера раньше push'а fill'а: объём не считается дважды
+        let mut partial = m.open_orders()[0].clone();
+        partial.filled = Qty(0.5);
+        m.on_order_update(partial.clone(), true);
+        m.on_fill(Fill {
+            qty: Qty(0.5),
+            ..fill("e3", &partial.order_id, 12, partial.side, partial.price.0)
+        });
+        assert_eq!(m.open_orders()[0].filled, Qty(0.5));
+        m.on_order_update(partial.clone(), false);
+        assert!(
+            m.open_orders()
+                .iter()
+                .all(|o| o.order_id != partial.order_id)
+        );
+
+        // fill рыночной продажи (flatten) учитывается, хотя ордер не стоит в книге
+        let sold = m.market_sell(Qty(1.0)).await.unwrap();
+        assert!(
+            m.on_fill(fill("e4", &sold, 13, Side::Sell, 100.0))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use core::types::{Money, Price, Qty, TimestampMs};
+use mm::grid::Side;
+
+/// Лимитка, стоящая на бирже
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveOrder {
+    pub order_id: String,
+    /// Наш `orderLinkId`; пустой у ордеров, выставленных не engine
+    pub link_id: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+    /// Уже исполнено из `qty`
+    pub filled: Qty,
+}
+
+/// Новая post-only лимитка
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewOrder {
+    pub link_id: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+}
+
+/// Одна сделка по ордеру; комиссия уже пересчитана в quote
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub exec_id: String,
+    pub order_id: String,
+    pub ts: TimestampMs,
+    pub side: Side,
+    pub price: Price,
+    /// Исполненный объём ордера
+    pub qty: Qty,
+    pub fee: Money,
+    /// Комиссия, удержанная в base (spot покупка); в `fee` она тоже есть, по цене fill'а
+    pub base_fee: Qty,
+}
+
+impl Fill {
+    /// Изменение base на счёте: покупка приносит `qty` без удержанной комиссии,
+    /// продажа забирает `qty` вместе с ней
+    pub fn net_qty(&self) -> Qty {
+        match self.side {
+            Side::Buy => Qty(self.qty.0 - self.base_fee.0),
+            Side::Sell => Qty(self.qty.0 + self.base_fee.0),
+        }
+    }
+}
+
+/// Остаток лимита запросов из ответа биржи
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LimitStatus {
+    pub remaining: u32,
+    /// Когда окно лимита сбрасывается
+    pub reset_at: TimestampMs,
+}
@@ -12,6 +12,11 @@ impl ExecutionModel {
         (bps.max(0.0)) / 10_000.0
     }
 
+    /// Комиссия за исполнение на сумму `notional` (в quote)
+    pub fn fee_quote(self, notional: f64) -> f64 {
+        notional.max(0.0) * Self::bps_to_ratio(self.fee_bps)
+    }
+
     pub fn buy_fill_price(self, mid: Price) -> Price {
         let half_spread = Self::bps_to_ratio(self.spread_bps) / 2.0;
         let slippage = Self::bps_to_ratio(self.slippage_bps);
@@ -24,6 +29,16 @@ impl ExecutionModel {
         Price(mid.0 * (1.0 - half_spread - slippage))
     }
 
+    /// Покупка по рынку при известной книге: ask + slippage, spread уже в ask
+    pub fn buy_fill_price_at_ask(self, ask: Price) -> Price {
+        Price(ask.0 * (1.0 + Self::bps_to_ratio(self.slippage_bps)))
+    }
+
+    /// Продажа по рынку при известной книге: bid - slippage
+    pub fn sell_fill_price_at_bid(self, bid: Price) -> Price {
+        Price(bid.0 * (1.0 - Self::bps_to_ratio(self.slippage_bps)))
+    }
+
     pub fn buy_qty_for_quote(self, quote_budget: f64, mid: Price) -> Qty {
         if quote_budget <= 0.0 || mid.0 <= 0.0 {
             return Qty(0.0);
@@ -0,0 +1,41 @@
+use std::future::Future;
+
+use anyhow::Result;
+use core::types::{Qty, TimestampMs};
+
+use crate::orders::{Fill, LimitStatus, LiveOrder, NewOrder};
+
+/// Доступ к ордерам одного символа на бирже (signed REST).
+///
+/// Ошибка — запрос не прошёл (сеть, отказ биржи); `OrderManager`
+/// продолжает с остальными действиями и сообщает о ней наверх.
+pub trait OrderGateway {
+    fn open_orders(&self) -> impl Future<Output = Result<Vec<LiveOrder>>> + Send;
+
+    /// Выставляет лимитку, возвращает `orderId` биржи
+    fn place(&self, order: &NewOrder) -> impl Future<Output = Result<String>> + Send;
+
+    fn cancel(&self, order_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Продаёт `qty` base по рынку (flatten), возвращает `orderId` биржи
+    fn market_sell(&self, link_id: &str, qty: Qty) -> impl Future<Output = Result<String>> + Send;
+
+    /// Выставляет лимитки одним запросом. Внешняя ошибка — запрос не прошёл целиком,
+    /// внутренние — по каждой лимитке в том же порядке (`orderId` при успехе)
+    fn place_batch(
+        &self,
+        orders: &[NewOrder],
+    ) -> impl Future<Output = Result<Vec<Result<String>>>> + Send;
+
+    /// Снимает ордера одним запросом; результат — как у `place_batch`
+    fn cancel_batch(
+        &self,
+        order_ids: &[String],
+    ) -> impl Future<Output = Result<Vec<Result<()>>>> + Send;
+
+    /// Исполнения начиная с `since` включительно, по возрастанию времени
+    fn fills_since(&self, since: TimestampMs) -> impl Future<Output = Result<Vec<Fill>>> + Send;
+
+    /// Лимит запросов из последнего ответа; `None` — биржа его не сообщала
+    fn limit_status(&self) -> Option<LimitStatus>;
+}
@@ -38,6 +38,70 @@ pub struct GridParams {
 
     /// Минимальный размер в базовой валюте (exchange limits)
     pub min_base_qty: Qty,
+
+    /// Тик цены, шаг и минимумы объёма инструмента
+    pub filters: LotFilters,
+}
+
+/// Правила биржи для цены и объёма лимитки; нули — без ограничения
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LotFilters {
+    pub tick_size: Price,
+    pub qty_step: Qty,
+    pub min_qty: Qty,
+    pub min_notional: Money,
+}
+
+impl Default for LotFilters {
+    fn default() -> Self {
+        Self {
+            tick_size: Price(0.0),
+            qty_step: Qty(0.0),
+            min_qty: Qty(0.0),
+            min_notional: Money(0.0),
+        }
+    }
+}
+
+impl LotFilters {
+    /// Ордер по правилам биржи: цена к тику в сторону от рынка (buy вниз, sell вверх),
+    /// объём вниз к шагу. `None` — меньше минимального объёма или суммы.
+    pub fn normalize(&self, order: &DesiredOrder) -> Option<DesiredOrder> {
+        let price = match order.side {
+            Side::Buy => floor_to(order.price.0, self.tick_size.0),
+            Side::Sell => ceil_to(order.price.0, self.tick_size.0),
+        };
+        let qty = floor_to(order.qty.0, self.qty_step.0);
+        if price <= 0.0 || qty <= 0.0 || qty < self.min_qty.0 || qty * price < self.min_notional.0 {
+            return None;
+        }
+        Some(DesiredOrder {
+            side: order.side,
+            price: Price(price),
+            qty: Qty(qty),
+        })
+    }
+}
+
+fn floor_to(x: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return x;
+    }
+    round_to((x / step + 1e-9).floor() * step, step)
+}
+
+fn ceil_to(x: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return x;
+    }
+    round_to((x / step - 1e-9).ceil() * step, step)
+}
+
+/// Убирает хвост float (`0.30000000000000004`) по числу знаков шага
+fn round_to(x: f64, step: f64) -> f64 {
+    let decimals = (-step.log10()).ceil().max(0.0) as i32;
+    let k = 10f64.powi(decimals);
+    (x * k).round() / k
 }
 
 /// Контекст сетки: что сейчас у нас в портфеле
@@ -137,26 +201,26 @@ pub fn build_grid(
         } else {
             0.0
         };
-        let buy_qty = Qty(desired_buy_qty.min(max_buy_qty_by_quote).max(0.0));
-        let sell_qty = Qty(desired_sell_qty.min(remaining_base).max(0.0));
+        let buy = params.filters.normalize(&DesiredOrder {
+            side: Side::Buy,
+            price: buy_price,
+            qty: Qty(desired_buy_qty.min(max_buy_qty_by_quote).max(0.0)),
+        });
+        let sell = params.filters.normalize(&DesiredOrder {
+            side: Side::Sell,
+            price: sell_price,
+            qty: Qty(desired_sell_qty.min(remaining_base).max(0.0)),
+        });
 
         // фильтр минимального количества (биржевые лимиты)
-        if buy_qty.0 >= params.min_base_qty.0 {
-            remaining_quote -= buy_qty.0 * buy_price.0;
-            out.push(DesiredOrder {
-                side: Side::Buy,
-                price: buy_price,
-                qty: buy_qty,
-            });
+        if let Some(o) = buy.filter(|o| o.qty.0 >= params.min_base_qty.0) {
+            remaining_quote -= o.qty.0 * o.price.0;
+            out.push(o);
         }
 
-        if sell_qty.0 >= params.min_base_qty.0 {
-            remaining_base -= sell_qty.0;
-            out.push(DesiredOrder {
-                side: Side::Sell,
-                price: sell_price,
-                qty: sell_qty,
-            });
+        if let Some(o) = sell.filter(|o| o.qty.0 >= params.min_base_qty.0) {
+            remaining_base -= o.qty.0;
+            out.push(o);
         }
     }
 
@@ -178,6 +242,7 @@ mod tests {
             hard_min: Ratio(0.35),
             hard_max: Ratio(0.65),
             min_base_qty: Qty(0.0001),
+            filters: LotFilters::default(),
         }
     }
 
@@ -290,4 +355,39 @@ mod tests {
 
         assert!(total_buy_qty > total_sell_qty);
     }
+
+    #[test]
+    fn rounds_orders_to_exchange_filters() {
+        let inv = Inventory {
+            base: Qty(1.0),
+            quote: Money(1000.0),
+        };
+        let mut p = params();
+        p.filters = LotFilters {
+            tick_size: Price(0.01),
+            qty_step: Qty(0.0001),
+            min_qty: Qty(0.0001),
+            min_notional: Money(60.0),
+        };
+        // 50 USDT на ордер меньше min_notional — сетки нет
+        assert!(
+            build_grid(Price(1000.0), Price(1000.0), inv, p)
+                .unwrap()
+                .is_empty()
+        );
+
+        p.base_quote_per_order = Money(77.0);
+        let orders = build_grid(Price(1000.0), Price(1000.0), inv, p).unwrap();
+        assert!(!orders.is_empty());
+        for o in &orders {
+            let ticks = o.price.0 / 0.01;
+            let steps = o.qty.0 / 0.0001;
+            assert!((ticks - ticks.round()).abs() < 1e-6, "{:?}", o);
+            assert!((steps - steps.round()).abs() < 1e-6, "{:?}", o);
+            assert!(o.qty.0 * o.price.0 >= 60.0);
+        }
+        // buy 1000/1.001 = 999.000999.. округляется вниз, sell 1001 остаётся
+        let first_buy = orders.iter().find(|o| o.side == Side::Buy).unwrap();
+        assert_eq!(first_buy.price, Price(999.0));
+    }
 }
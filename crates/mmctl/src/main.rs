@@ -0,0 +1,295 @@
+mod spec;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use orchestrator_core::client::ApiClient;
+use orchestrator_core::metrics::MetricsPayload;
+use orchestrator_core::models::{ArtifactRecord, EventLevel, RunEventRecord, RunRecord, RunStatus};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Клиент orchestrator API: запуск run'ов из TOML, список и tail событий, метрики, артефакты
+#[derive(Parser, Debug)]
+#[command(name = "mmctl", version)]
+struct Cli {
+    /// Адрес API
+    #[arg(long, env = "MMCTL_API_URL", default_value = "http://localhost:8080")]
+    api: String,
+    /// Ключ в `X-Api-Key`: в audit_log виден отпечаток
+    #[arg(long, env = "MMCTL_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Поставить run в очередь по TOML описанию (поля `POST /runs`)
+    Submit {
+        file: PathBuf,
+        /// Перекрывает `experiment_id` файла
+        #[arg(long)]
+        experiment: Option<Uuid>,
+        /// После постановки — как `watch`
+        #[arg(long)]
+        watch: bool,
+        /// Перекрывает `project` файла; по умолчанию — проект ключа
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Последние run'ы
+    List {
+        #[arg(long)]
+        experiment: Option<Uuid>,
+        #[arg(long)]
+        project: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// События run'а по мере появления, до завершения; код выхода 1, если run не completed
+    Watch {
+        run_id: Uuid,
+        /// Минимальный уровень: debug, info, warn, error
+        #[arg(long)]
+        level: Option<String>,
+    },
+    /// Метрики run'а
+    Metrics {
+        run_id: Uuid,
+        /// Весь payload как есть
+        #[arg(long)]
+        json: bool,
+    },
+    /// Артефакты run'а
+    Artifacts { run_id: Uuid },
+    /// Скачать артефакты из S3/MinIO по presigned ссылкам API
+    Download {
+        run_id: Uuid,
+        /// Только эти артефакты (`equity_csv`, `report_json`, ...); по умолчанию — все
+        #[arg(long = "name")]
+        names: Vec<String>,
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+    },
+    /// Отменить run
+    Cancel { run_id: Uuid },
+}
+
+/// Пауза между опросами событий и статуса в `watch`
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Сколько последних событий показать в начале `watch`
+const WATCH_BACKLOG: u32 = 50;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = ApiClient::new(&cli.api, cli.api_key)?;
+
+    match cli.command {
+        Command::Submit {
+            file,
+            experiment,
+            watch,
+            project,
+        } => {
+            let mut req = spec::load_run_request(&file)?;
+            if experiment.is_some() {
+                req.experiment_id = experiment;
+            }
+            if project.is_some() {
+                req.project = project;
+            }
+            let run: RunRecord = client.post("/runs", &req).await?;
+            println!("{} queued ({})", run.id, label(&run.kind));
+            if watch {
+                watch_run(&client, run.id, None).await?;
+            }
+        }
+        Command::List {
+            experiment,
+            project,
+            limit,
+        } => {
+            let mut path = format!("/runs?limit={}", limit);
+            if let Some(id) = experiment {
+                path.push_str(&format!("&experiment_id={}", id));
+            }
+            if let Some(project) = &project {
+                path.push_str(&format!("&project={}", project));
+            }
+            let runs: Vec<RunRecord> = client.get(&path).await?;
+            for r in &runs {
+                println!(
+                    "{}  {:<9}  {:<22}  {}  {:<12}  {}",
+                    r.id,
+                    label(&r.status),
+                    label(&r.kind),
+                    r.created_at.format("%Y-%m-%d %H:%M"),
+                    r.project,
+                    r.name
+                );
+            }
+        }
+        Command::Watch { run_id, level } => {
+            if let Some(level) = &level {
+                if EventLevel::parse(level).is_none() {
+                    bail!("level must be debug, info, warn or error");
+                }
+            }
+            watch_run(&client, run_id, level.as_deref()).await?;
+        }
+        Command::Metrics { run_id, json } => {
+            let res: Value = client.get(&format!("/runs/{}/metrics", run_id)).await?;
+            let payload = MetricsPayload::from_value(res["payload"].clone());
+            if json {
+                println!("{}", serde_json::to_string_pretty(&payload)?);
+            } else {
+                print_metrics(&payload)?;
+            }
+        }
+        Command::Artifacts { run_id } => {
+            let artifacts: Vec<ArtifactRecord> =
+                client.get(&format!("/runs/{}/artifacts", run_id)).await?;
+            for a in &artifacts {
+                println!(
+                    "{:>6}  {:<24}  {:<13}  {:>10}  {}  {}",
+                    a.id,
+                    a.name,
+                    a.kind.as_str(),
+                    a.size_bytes.map_or("-".to_string(), |s| s.to_string()),
+                    if a.download_url.is_some() { "s3" } else { "--" },
+                    a.path
+                );
+            }
+        }
+        Command::Download { run_id, names, out } => {
+            download_artifacts(&client, run_id, &names, &out).await?;
+        }
+        Command::Cancel { run_id } => {
+            let res: Value = client
+                .post(&format!("/runs/{}/cancel", run_id), &Value::Null)
+                .await?;
+            println!("{} {}", run_id, res["status"].as_str().unwrap_or("?"));
+        }
+    }
+    Ok(())
+}
+
+/// Tail событий: сначала последние `WATCH_BACKLOG`, дальше — новые по `after_id`;
+/// после финального статуса ещё один опрос, чтобы не потерять последние строки
+async fn watch_run(client: &ApiClient, run_id: Uuid, level: Option<&str>) -> Result<()> {
+    let level_query = level.map_or(String::new(), |l| format!("&level={}", l));
+    let mut after_id: Option<i64> = None;
+    let mut finished: Option<RunRecord> = None;
+    loop {
+        let path = match after_id {
+            None => format!(
+                "/runs/{}/events?limit={}{}",
+                run_id, WATCH_BACKLOG, level_query
+            ),
+            Some(id) => format!(
+                "/runs/{}/events?after_id={}&limit=2000{}",
+                run_id, id, level_query
+            ),
+        };
+        let mut events: Vec<RunEventRecord> = client.get(&path).await?;
+        if after_id.is_none() {
+            // без after_id API отдаёт от новых к старым
+            events.reverse();
+        }
+        for e in &events {
+            println!(
+                "{} {:<5} {}",
+                e.ts.format("%H:%M:%S"),
+                e.level.as_str().to_uppercase(),
+                e.message
+            );
+        }
+        after_id = events.last().map(|e| e.id).or(after_id).or(Some(0));
+
+        if let Some(run) = finished {
+            return match run.status {
+                RunStatus::Completed => Ok(()),
+                status => bail!(
+                    "run {} {}{}",
+                    run_id,
+                    label(&status),
+                    run.error.map_or(String::new(), |e| format!(": {}", e))
+                ),
+            };
+        }
+        let run: RunRecord = client.get(&format!("/runs/{}", run_id)).await?;
+        if matches!(run.status, RunStatus::Queued | RunStatus::Running) {
+            tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+        } else {
+            finished = Some(run);
+        }
+    }
+}
+
+fn print_metrics(payload: &MetricsPayload) -> Result<()> {
+    let core = serde_json::to_value(&payload.core)?;
+    if let Some(map) = core.as_object() {
+        for (key, value) in map.iter().filter(|(_, v)| !v.is_null()) {
+            println!("{:<14} {}", key, value);
+        }
+    }
+    if let Some(percent) = payload
+        .progress
+        .as_ref()
+        .and_then(|p| p.get("percent"))
+        .and_then(Value::as_f64)
+    {
+        println!("{:<14} {:.1}%", "progress", percent);
+    }
+    Ok(())
+}
+
+async fn download_artifacts(
+    client: &ApiClient,
+    run_id: Uuid,
+    names: &[String],
+    out: &std::path::Path,
+) -> Result<()> {
+    let artifacts: Vec<ArtifactRecord> = client.get(&format!("/runs/{}/artifacts", run_id)).await?;
+    let selected: Vec<&ArtifactRecord> = artifacts
+        .iter()
+        .filter(|a| names.is_empty() || names.contains(&a.name))
+        .collect();
+    if selected.is_empty() {
+        bail!("no matching artifacts for run {}", run_id);
+    }
+    std::fs::create_dir_all(out).with_context(|| format!("create {} failed", out.display()))?;
+
+    let mut downloaded = 0;
+    for a in selected {
+        let (Some(url), Some(key)) = (&a.download_url, &a.object_key) else {
+            eprintln!(
+                "skip {}: not in object storage (worker path {})",
+                a.name, a.path
+            );
+            continue;
+        };
+        let file_name = key.rsplit('/').next().unwrap_or(&a.name);
+        let target = out.join(file_name);
+        let bytes = client.download(url, &target).await?;
+        println!("{} -> {} ({} bytes)", a.name, target.display(), bytes);
+        downloaded += 1;
+    }
+    if downloaded == 0 {
+        bail!("none of the artifacts are in object storage; set ARTIFACT_S3_BUCKET for worker");
+    }
+    Ok(())
+}
+
+/// Имя варианта как в API (`backtest_trend`, `completed`)
+fn label<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
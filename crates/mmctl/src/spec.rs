@@ -0,0 +1,89 @@
+//! Описание run'а в TOML для `mmctl submit`: те же поля, что у `POST /runs`, `params` —
+//! таблица типизированных параметров `kind`.
+//!
+//! ```toml
+//! name = "trend ETH 2024"
+//! kind = "backtest_trend"
+//! cli_args = ["--interval", "15"]
+//!
+//! [params]
+//! symbol = "ETHUSDT"
+//! start = 2024-01-01
+//! end = 2024-06-30
+//! ```
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use orchestrator_core::models::CreateRunRequest;
+use serde_json::Value;
+
+pub fn load_run_request(path: &Path) -> Result<CreateRunRequest> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("read {} failed", path.display()))?;
+    parse_run_request(&text).with_context(|| format!("invalid run spec {}", path.display()))
+}
+
+pub fn parse_run_request(text: &str) -> Result<CreateRunRequest> {
+    let spec: toml::Value = toml::from_str(text)?;
+    Ok(serde_json::from_value(toml_to_json(spec))?)
+}
+
+/// Даты TOML (`start = 2024-01-01`) — строками, как их ждут `params` API
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_core::models::RunKind;
+    use serde_json::json;
+
+    #[test]
+    fn spec_maps_to_create_run_request() {
+        let req = parse_run_request(
+            r#"
+            name = "trend ETH"
+            kind = "backtest_trend"
+            cli_args = ["--interval", "15"]
+
+            [params]
+            symbol = "ETHUSDT"
+            start = 2024-01-01
+            end = 2024-06-30
+            fee_bps = 5.5
+            force_close_at_end = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(req.kind, RunKind::BacktestTrend);
+        assert_eq!(req.cli_args, vec!["--interval", "15"]);
+        assert!(req.depends_on.is_empty() && req.experiment_id.is_none());
+        assert_eq!(
+            req.params,
+            Some(json!({
+                "symbol": "ETHUSDT",
+                "start": "2024-01-01",
+                "end": "2024-06-30",
+                "fee_bps": 5.5,
+                "force_close_at_end": true,
+            }))
+        );
+
+        assert!(parse_run_request("name = \"x\"\nkind = \"nope\"").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Состояние TUI: run'ы, очередь, события и equity выбранного run'а; обновляется опросом API.
+
+use std::collections::VecDeque;
+
+use orchestrator_core::client::{ApiClient, ApiError};
+use orchestrator_core::metrics::{CoreMetrics, MetricsPayload};
+use orchestrator_core::models::{QueueStreamRecord, RunEventRecord, RunRecord};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Сколько событий выбранного run'а держать на экране
+const MAX_EVENTS: usize = 500;
+/// Сколько последних событий подгрузить при выборе run'а
+const EVENTS_BACKLOG: usize = 200;
+const RUNS_LIMIT: usize = 100;
+
+#[derive(Default)]
+pub struct App {
+    pub runs: Vec<RunRecord>,
+    pub selected: usize,
+    pub queue: Vec<QueueStreamRecord>,
+    /// Очередь недоступна (Redis), остальное работает
+    pub queue_error: Option<String>,
+    pub events: VecDeque<RunEventRecord>,
+    /// Run, чьи события в `events`; сменился выбор — история грузится заново
+    events_run: Option<Uuid>,
+    pub equity: Vec<f64>,
+    pub core: Option<CoreMetrics>,
+    /// Ошибка последнего обновления или время успешного
+    pub status: String,
+}
+
+impl App {
+    pub fn selected_run(&self) -> Option<&RunRecord> {
+        self.runs.get(self.selected)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.runs.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub async fn refresh(&mut self, client: &ApiClient) {
+        self.status = match self.try_refresh(client).await {
+            Ok(()) => format!("updated {}", chrono::Local::now().format("%H:%M:%S")),
+            Err(e) => format!("error: {}", e),
+        };
+    }
+
+    async fn try_refresh(&mut self, client: &ApiClient) -> Result<(), ApiError> {
+        let selected_id = self.selected_run().map(|r| r.id);
+        self.runs = client.get(&format!("/runs?limit={}", RUNS_LIMIT)).await?;
+        // выбор держится за run, а не за строку: новые run'ы сдвигают список
+        self.selected = selected_id
+            .and_then(|id| self.runs.iter().position(|r| r.id == id))
+            .unwrap_or(0)
+            .min(self.runs.len().saturating_sub(1));
+        match client.get("/queue").await {
+            Ok(queue) => {
+                self.queue = queue;
+                self.queue_error = None;
+            }
+            Err(e) => self.queue_error = Some(e.to_string()),
+        }
+
+        let Some(run_id) = self.selected_run().map(|r| r.id) else {
+            self.events.clear();
+            self.events_run = None;
+            self.equity.clear();
+            self.core = None;
+            return Ok(());
+        };
+        if self.events_run != Some(run_id) {
+            let mut backlog: Vec<RunEventRecord> = client
+                .get(&format!("/runs/{}/events?limit={}", run_id, EVENTS_BACKLOG))
+                .await?;
+            // без after_id API отдаёт от новых к старым
+            backlog.reverse();
+            self.events.clear();
+            self.events_run = Some(run_id);
+            self.push_events(backlog);
+        } else {
+            let after_id = self.events.back().map_or(0, |e| e.id);
+            let new: Vec<RunEventRecord> = client
+                .get(&format!(
+                    "/runs/{}/events?after_id={}&limit={}",
+                    run_id, after_id, MAX_EVENTS
+                ))
+                .await?;
+            self.push_events(new);
+        }
+
+        let metrics: Value = client.get(&format!("/runs/{}/metrics", run_id)).await?;
+        let payload = MetricsPayload::from_value(metrics["payload"].clone());
+        self.equity = payload.charts.equity.iter().map(|p| p.equity).collect();
+        self.core = Some(payload.core);
+        Ok(())
+    }
+
+    fn push_events(&mut self, events: Vec<RunEventRecord>) {
+        self.events.extend(events);
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Equity под ширину sparkline: последнее значение каждого из `width` отрезков,
+/// шкала min..max → 0..100 (ровная линия — посередине)
+pub fn sparkline_points(values: &[f64], width: usize) -> Vec<u64> {
+    if values.is_empty() || width == 0 {
+        return Vec::new();
+    }
+    let buckets = width.min(values.len());
+    let sampled: Vec<f64> = (1..=buckets)
+        .map(|i| values[i * values.len() / buckets - 1])
+        .collect();
+    let min = sampled.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = sampled.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    sampled
+        .iter()
+        .map(|v| {
+            if max > min {
+                ((v - min) / (max - min) * 100.0).round() as u64
+            } else {
+                50
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_downsamples_and_scales() {
+        let values: Vec<f64> = (0..10).map(|i| 1000.0 + i as f64 * 10.0).collect();
+        assert_eq!(sparkline_points(&values, 5), vec![0, 25, 50, 75, 100]);
+        assert_eq!(sparkline_points(&values[..3], 80), vec![0, 50, 100]);
+        assert_eq!(sparkline_points(&[5.0, 5.0], 10), vec![50, 50]);
+        assert!(sparkline_points(&[], 10).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+mod app;
+mod ui;
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::Parser;
+use orchestrator_core::client::ApiClient;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+use app::App;
+
+/// Терминальный дашборд orchestrator'а: очередь, run'ы, события и equity выбранного run'а.
+/// Для работы по SSH, где веб UI недоступен
+#[derive(Parser, Debug)]
+#[command(name = "mmtui", version)]
+struct Cli {
+    /// Адрес API
+    #[arg(long, env = "MMCTL_API_URL", default_value = "http://localhost:8080")]
+    api: String,
+    #[arg(long, env = "MMCTL_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+}
+
+/// Как часто опрашивать API
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Сколько ждать клавишу, прежде чем проверить, не пора ли обновиться
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = ApiClient::new(&cli.api, cli.api_key)?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &client).await;
+    ratatui::restore();
+    result
+}
+
+async fn run(terminal: &mut DefaultTerminal, client: &ApiClient) -> Result<()> {
+    let mut app = App::default();
+    let mut last_refresh: Option<Instant> = None;
+    loop {
+        if last_refresh.is_none_or(|t| t.elapsed() >= REFRESH_INTERVAL) {
+            app.refresh(client).await;
+            last_refresh = Some(Instant::now());
+        }
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+
+        if !event::poll(INPUT_POLL)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.select_next();
+                // события и equity нового run'а — сразу, не ждать следующего опроса
+                last_refresh = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.select_prev();
+                last_refresh = None;
+            }
+            KeyCode::Char('r') => last_refresh = None,
+            _ => {}
+        }
+    }
+}
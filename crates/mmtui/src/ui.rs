@@ -0,0 +1,172 @@
+//! Отрисовка: run'ы и очередь сверху, equity выбранного run'а, его события, подсказка снизу.
+
+use orchestrator_core::models::{EventLevel, RunStatus};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Block, Borders, List, ListItem, Paragraph, Row, Sparkline, Table, TableState,
+};
+
+use crate::app::{App, sparkline_points};
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [top, equity, events, footer] = Layout::vertical([
+        Constraint::Percentage(40),
+        Constraint::Length(7),
+        Constraint::Min(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [runs, queue] =
+        Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(top);
+
+    draw_runs(frame, app, runs);
+    draw_queue(frame, app, queue);
+    draw_equity(frame, app, equity);
+    draw_events(frame, app, events);
+    frame.render_widget(
+        Paragraph::new(format!("q quit  ↑/↓ select  r refresh  |  {}", app.status))
+            .style(Style::default().fg(Color::DarkGray)),
+        footer,
+    );
+}
+
+fn draw_runs(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let rows = app.runs.iter().map(|r| {
+        Row::new(vec![
+            label(&r.status).to_string(),
+            label(&r.kind).to_string(),
+            r.created_at.format("%m-%d %H:%M").to_string(),
+            r.name.clone(),
+        ])
+        .style(Style::default().fg(status_color(r.status)))
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(24),
+            Constraint::Length(12),
+            Constraint::Min(10),
+        ],
+    )
+    .header(Row::new(vec!["status", "kind", "created", "name"]).style(bold()))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(Block::default().borders(Borders::ALL).title("Runs"));
+    let mut state = TableState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_queue(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Queue");
+    if let Some(e) = &app.queue_error {
+        frame.render_widget(
+            Paragraph::new(format!("unavailable: {}", e)).block(block),
+            area,
+        );
+        return;
+    }
+    let mut items: Vec<ListItem> = Vec::new();
+    for s in &app.queue {
+        let waiting = s.length.saturating_sub(s.pending.len() as u64);
+        items.push(ListItem::new(Line::from(format!(
+            "{}  waiting {}  running {}",
+            s.stream.rsplit(':').next().unwrap_or(&s.stream),
+            waiting,
+            s.pending.len()
+        ))));
+        for p in &s.pending {
+            items.push(ListItem::new(format!(
+                "  {} @ {} idle {}s",
+                p.run_id
+                    .map_or("?".to_string(), |id| id.to_string()[..8].to_string()),
+                p.consumer,
+                p.idle_ms / 1000
+            )));
+        }
+    }
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+fn draw_equity(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let mut title = "Equity".to_string();
+    if let Some(run) = app.selected_run() {
+        title.push_str(&format!(" — {}", run.name));
+    }
+    if let Some(core) = &app.core {
+        if let Some(roi) = core.roi {
+            title.push_str(&format!("  roi {:.2}%", roi));
+        }
+        if let Some(dd) = core.max_drawdown {
+            title.push_str(&format!("  max dd {:.2}%", dd));
+        }
+    }
+    if let (Some(first), Some(last)) = (app.equity.first(), app.equity.last()) {
+        title.push_str(&format!("  {:.2} → {:.2}", first, last));
+    }
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let points = sparkline_points(&app.equity, block.inner(area).width as usize);
+    frame.render_widget(
+        Sparkline::default()
+            .block(block)
+            .data(&points)
+            .max(100)
+            .style(Style::default().fg(Color::Cyan)),
+        area,
+    );
+}
+
+fn draw_events(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let visible = area.height.saturating_sub(2) as usize;
+    // последние строки внизу, как в tail -f
+    let items: Vec<ListItem> = app
+        .events
+        .iter()
+        .skip(app.events.len().saturating_sub(visible))
+        .map(|e| {
+            ListItem::new(format!(
+                "{} {:<5} {}",
+                e.ts.format("%H:%M:%S"),
+                e.level.as_str().to_uppercase(),
+                e.message
+            ))
+            .style(Style::default().fg(level_color(e.level)))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL).title("Events")),
+        area,
+    );
+}
+
+fn label<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn status_color(status: RunStatus) -> Color {
+    match status {
+        RunStatus::Queued => Color::Gray,
+        RunStatus::Running => Color::Yellow,
+        RunStatus::Completed => Color::Green,
+        RunStatus::Failed => Color::Red,
+        RunStatus::Cancelled => Color::DarkGray,
+    }
+}
+
+fn level_color(level: EventLevel) -> Color {
+    match level {
+        EventLevel::Debug => Color::DarkGray,
+        EventLevel::Info => Color::Reset,
+        EventLevel::Warn => Color::Yellow,
+        EventLevel::Error => Color::Red,
+    }
+}
+
+fn bold() -> Style {
+    Style::default().add_modifier(Modifier::BOLD)
+}
@@ -0,0 +1,157 @@
+//! Артефакты run'ов в S3/MinIO: worker загружает файлы по завершении run'а, в БД — ключ
+//! объекта, API отдаёт presigned URL на скачивание. Включается `ARTIFACT_S3_BUCKET`;
+//! без него артефакты остаются только на диске worker'а.
+//!
+//! Доступ и endpoint — стандартные переменные `object_store`: `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY`, `AWS_REGION`, для MinIO — `AWS_ENDPOINT` и `AWS_ALLOW_HTTP=true`.
+//! Время жизни ссылок — `ARTIFACT_URL_TTL_SECS` (по умолчанию час).
+
+use std::{fmt, path::Path as FsPath, time::Duration};
+
+use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::path::Path;
+use object_store::signer::Signer;
+use object_store::{ObjectStore, WriteMultipart};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+/// Кусок чтения файла при загрузке: большие capture идут multipart'ом, не целиком в память
+const UPLOAD_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+const DEFAULT_URL_TTL_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactStoreError(pub String);
+
+impl fmt::Display for ArtifactStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ArtifactStoreError {}
+
+impl From<object_store::Error> for ArtifactStoreError {
+    fn from(e: object_store::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for ArtifactStoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    s3: AmazonS3,
+    prefix: String,
+    url_ttl: Duration,
+}
+
+impl ArtifactStore {
+    /// `None` — `ARTIFACT_S3_BUCKET` не задан; `ARTIFACT_S3_PREFIX` — префикс ключей
+    /// (по умолчанию `runs`)
+    pub fn from_env() -> Result<Option<Self>, ArtifactStoreError> {
+        let Some(bucket) = std::env::var("ARTIFACT_S3_BUCKET")
+            .ok()
+            .filter(|b| !b.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let s3 = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket.trim())
+            .build()?;
+        let prefix = std::env::var("ARTIFACT_S3_PREFIX").unwrap_or_else(|_| "runs".to_string());
+        let ttl_secs = match std::env::var("ARTIFACT_URL_TTL_SECS") {
+            Ok(raw) => raw.trim().parse::<u64>().map_err(|_| {
+                ArtifactStoreError(format!("invalid ARTIFACT_URL_TTL_SECS: {}", raw))
+            })?,
+            Err(_) => DEFAULT_URL_TTL_SECS,
+        };
+        Ok(Some(Self::new(s3, &prefix, Duration::from_secs(ttl_secs))))
+    }
+
+    pub fn new(s3: AmazonS3, prefix: &str, url_ttl: Duration) -> Self {
+        Self {
+            s3,
+            prefix: prefix.trim_matches('/').to_string(),
+            url_ttl,
+        }
+    }
+
+    /// `<prefix>/<run_id>/<name>/<имя файла>`: имя артефакта в ключе — файлы с одинаковым
+    /// именем в разных каталогах не перезаписывают друг друга
+    pub fn object_key(&self, run_id: Uuid, name: &str, path: &FsPath) -> String {
+        let file = path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.to_string());
+        if self.prefix.is_empty() {
+            format!("{}/{}/{}", run_id, name, file)
+        } else {
+            format!("{}/{}/{}/{}", self.prefix, run_id, name, file)
+        }
+    }
+
+    pub async fn upload(&self, key: &str, path: &FsPath) -> Result<(), ArtifactStoreError> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let upload = self.s3.put_multipart(&Path::from(key)).await?;
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, UPLOAD_CHUNK_BYTES);
+        let mut buf = vec![0u8; UPLOAD_CHUNK_BYTES];
+        loop {
+            let n = match file.read(&mut buf).await {
+                Ok(n) => n,
+                Err(e) => {
+                    let _ = writer.abort().await;
+                    return Err(e.into());
+                }
+            };
+            if n == 0 {
+                break;
+            }
+            writer.wait_for_capacity(4).await?;
+            writer.write(&buf[..n]);
+        }
+        writer.finish().await?;
+        Ok(())
+    }
+
+    /// Presigned GET: ссылка на скачивание без ключей доступа, живёт `ARTIFACT_URL_TTL_SECS`
+    pub async fn download_url(&self, key: &str) -> Result<String, ArtifactStoreError> {
+        let url = self
+            .s3
+            .signed_url(http::Method::GET, &Path::from(key), self.url_ttl)
+            .await?;
+        Ok(url.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_key_keeps_artifact_name_and_file_name() {
+        let s3 = AmazonS3Builder::new()
+            .with_bucket_name("artifacts")
+            .with_region("us-east-1")
+            .build()
+            .unwrap();
+        let run_id = Uuid::nil();
+        let store = ArtifactStore::new(s3.clone(), "/runs/", Duration::from_secs(60));
+        assert_eq!(
+            store.object_key(
+                run_id,
+                "equity_csv",
+                FsPath::new("/app/data/out/equity.csv")
+            ),
+            format!("runs/{}/equity_csv/equity.csv", run_id)
+        );
+        let bare = ArtifactStore::new(s3, "", Duration::from_secs(60));
+        assert_eq!(
+            bare.object_key(run_id, "run_log", FsPath::new("")),
+            format!("{}/run_log/run_log", run_id)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Ответ engine бинаря на `--capabilities` и проверка аргументов run'а по нему:
+//! несовместимый бинарь отклоняется до запуска, а не падает на неизвестном флаге.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub const CAPABILITIES_FLAG: &str = "--capabilities";
+/// Версия протокола вывода бинарей, которую понимает worker
+pub const ENGINE_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilitiesError(pub String);
+
+impl fmt::Display for CapabilitiesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CapabilitiesError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineCapabilities {
+    pub binary: String,
+    pub protocol_version: u32,
+    pub strategy_version: String,
+    /// Длинные флаги без `--`
+    pub flags: Vec<String>,
+}
+
+impl EngineCapabilities {
+    /// Версия протокола и все `--флаги` из `cli_args`
+    pub fn check(&self, cli_args: &[String]) -> Result<(), CapabilitiesError> {
+        if self.protocol_version != ENGINE_PROTOCOL_VERSION {
+            return Err(CapabilitiesError(format!(
+                "{} speaks protocol {}, worker expects {}",
+                self.binary, self.protocol_version, ENGINE_PROTOCOL_VERSION
+            )));
+        }
+        let mut unknown: Vec<&str> = cli_args
+            .iter()
+            .filter_map(|a| a.strip_prefix("--"))
+            .map(|a| a.split_once('=').map_or(a, |(name, _)| name))
+            .filter(|name| !self.flags.iter().any(|f| f == name))
+            .collect();
+        unknown.dedup();
+        if !unknown.is_empty() {
+            return Err(CapabilitiesError(format!(
+                "{} (strategy {}) does not support --{}",
+                self.binary,
+                self.strategy_version,
+                unknown.join(", --")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(protocol_version: u32) -> EngineCapabilities {
+        EngineCapabilities {
+            binary: "backtest_mm".to_string(),
+            protocol_version,
+            strategy_version: "0.1.0".to_string(),
+            flags: vec!["symbol".to_string(), "maker-fee-bps".to_string()],
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_protocol() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(
+            caps(1)
+                .check(&args(&["--symbol", "ETHUSDT", "--maker-fee-bps=-1.5"]))
+                .is_ok()
+        );
+        let err = caps(1)
+            .check(&args(&["--symbol", "X", "--levels=3", "--quiet"]))
+            .unwrap_err();
+        assert_eq!(
+            err.0,
+            "backtest_mm (strategy 0.1.0) does not support --levels, --quiet"
+        );
+        assert!(caps(2).check(&[]).unwrap_err().0.contains("protocol 2"));
+    }
+}
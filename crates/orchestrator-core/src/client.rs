@@ -0,0 +1,112 @@
+//! HTTP клиент orchestrator API для `mmctl` и `mmtui`: JSON запросы с ключом в `X-Api-Key`
+//! (попадает в audit_log отпечатком), ошибки API — с телом ответа.
+
+use std::{fmt, path::Path};
+
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError(pub String);
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(e: std::io::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base: String,
+    api_key: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base: &str, api_key: Option<String>) -> Result<Self, ApiError> {
+        let http = reqwest::Client::builder()
+            .user_agent(concat!("mmbot-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            http,
+            base: base.trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        let res = self.send(self.http.get(self.url(path))).await?;
+        Ok(res.json().await?)
+    }
+
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ApiError> {
+        let res = self.send(self.http.post(self.url(path)).json(body)).await?;
+        Ok(res.json().await?)
+    }
+
+    /// Presigned URL артефакта — в файл по кускам (capture и fills бывают большими);
+    /// без ключа API: ссылка уже подписана и ведёт в S3/MinIO
+    pub async fn download(&self, url: &str, out: &Path) -> Result<u64, ApiError> {
+        let mut res = check(self.http.get(url).send().await?).await?;
+        let mut file = tokio::fs::File::create(out)
+            .await
+            .map_err(|e| ApiError(format!("create {} failed: {}", out.display(), e)))?;
+        let mut written = 0u64;
+        while let Some(chunk) = res.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(written)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    async fn send(&self, req: RequestBuilder) -> Result<Response, ApiError> {
+        let req = match &self.api_key {
+            Some(key) => req.header("x-api-key", key),
+            None => req,
+        };
+        let res = req
+            .send()
+            .await
+            .map_err(|e| ApiError(format!("API {} unreachable: {}", self.base, e)))?;
+        check(res).await
+    }
+}
+
+async fn check(res: Response) -> Result<Response, ApiError> {
+    let status = res.status();
+    if !status.is_success() {
+        let host = res.url().host_str().unwrap_or_default().to_string();
+        let text = res.text().await.unwrap_or_default();
+        return Err(ApiError(format!("{} {}: {}", host, status.as_u16(), text)));
+    }
+    Ok(res)
+}
@@ -1 +1,10 @@
+#[cfg(feature = "artifact-store")]
+pub mod artifact_store;
+pub mod capabilities;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod metrics;
 pub mod models;
+pub mod params;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
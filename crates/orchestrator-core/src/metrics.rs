@@ -0,0 +1,178 @@
+//! Payload `run_metrics`: версия схемы, основные метрики, снимки графиков
+//! и остальные ключи отчёта/stdout в `extras`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const METRICS_SCHEMA_VERSION: u32 = 1;
+
+/// Метрики, общие для backtest'ов; `None` — бинарь их не считает
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoreMetrics {
+    pub pnl: Option<f64>,
+    /// %
+    pub roi: Option<f64>,
+    /// %
+    pub max_drawdown: Option<f64>,
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    pub calmar: Option<f64>,
+    pub profit_factor: Option<f64>,
+    /// %
+    pub win_rate: Option<f64>,
+    pub closed_trades: Option<u64>,
+    pub final_equity: Option<f64>,
+    /// %
+    pub exposure: Option<f64>,
+    pub total_costs: Option<f64>,
+}
+
+impl CoreMetrics {
+    /// Забирает метрики из плоских ключей отчёта/stdout (`roi`, `max_drawdown`, ...);
+    /// `total_costs` — ещё и из `costs.total`
+    fn take_from(map: &mut Map<String, Value>) -> Self {
+        let mut num = |key: &str| map.remove(key).and_then(|v| v.as_f64());
+        let mut core = Self {
+            pnl: num("pnl"),
+            roi: num("roi"),
+            max_drawdown: num("max_drawdown"),
+            sharpe: num("sharpe"),
+            sortino: num("sortino"),
+            calmar: num("calmar"),
+            profit_factor: num("profit_factor"),
+            win_rate: num("win_rate"),
+            closed_trades: num("closed_trades").map(|v| v as u64),
+            final_equity: num("final_equity"),
+            exposure: num("exposure"),
+            total_costs: num("total_costs"),
+        };
+        if core.total_costs.is_none() {
+            core.total_costs = map
+                .get("costs")
+                .and_then(|c| c.get("total"))
+                .and_then(Value::as_f64);
+        }
+        core
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquityPoint {
+    pub ts: i64,
+    pub equity: f64,
+    pub close: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradePoint {
+    pub ts: i64,
+    pub side: String,
+    pub price: f64,
+    pub qty: Option<f64>,
+    pub pnl: Option<f64>,
+}
+
+/// Прореженные equity и сделки из CSV-артефактов для графиков UI
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartSnapshots {
+    pub equity: Vec<EquityPoint>,
+    pub trades: Vec<TradePoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsPayload {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub core: CoreMetrics,
+    #[serde(default)]
+    pub charts: ChartSnapshots,
+    /// Последняя строка `progress:` (`percent`, `done`, `total`, `unit`, `eta_s`)
+    #[serde(default)]
+    pub progress: Option<Map<String, Value>>,
+    /// Издержки по видам (`costs` JSON отчёта)
+    #[serde(default)]
+    pub costs: Option<Value>,
+    /// Помесячная разбивка
+    #[serde(default)]
+    pub monthly: Option<Value>,
+    /// Остальные метрики бинаря как есть
+    #[serde(default)]
+    pub extras: Map<String, Value>,
+}
+
+impl MetricsPayload {
+    /// Из плоских метрик отчёта или stdout: известные ключи — в поля, остальное — в `extras`
+    pub fn from_flat(mut map: Map<String, Value>) -> Self {
+        let core = CoreMetrics::take_from(&mut map);
+        let mut take = |key: &str| map.remove(key).filter(|v| !v.is_null());
+        let charts = ChartSnapshots {
+            equity: take("chart_equity")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+            trades: take("chart_trades")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+        };
+        let progress = match take("progress") {
+            Some(Value::Object(p)) => Some(p),
+            _ => None,
+        };
+        let costs = take("costs");
+        let monthly = take("monthly");
+        Self {
+            schema_version: METRICS_SCHEMA_VERSION,
+            core,
+            charts,
+            progress,
+            costs,
+            monthly,
+            extras: map,
+        }
+    }
+
+    /// Payload из `run_metrics`; записи до версионирования (плоский map) переводятся в схему
+    pub fn from_value(value: Value) -> Self {
+        match value {
+            Value::Object(map) if map.contains_key("schema_version") => {
+                serde_json::from_value(Value::Object(map.clone()))
+                    .unwrap_or_else(|_| Self::from_flat(map))
+            }
+            Value::Object(map) => Self::from_flat(map),
+            _ => Self::from_flat(Map::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_flat_payload_maps_to_schema() {
+        let legacy = json!({
+            "roi": 1.25,
+            "max_drawdown": 3.5,
+            "closed_trades": 12.0,
+            "buy_fills": 40,
+            "costs": {"total": 7.5, "maker_fees": 5.0},
+            "progress": {"percent": 50.0},
+            "chart_equity": [{"ts": 1, "equity": 1000.0, "close": null}],
+        });
+        let p = MetricsPayload::from_value(legacy);
+        assert_eq!(p.schema_version, METRICS_SCHEMA_VERSION);
+        assert_eq!(p.core.roi, Some(1.25));
+        assert_eq!(p.core.closed_trades, Some(12));
+        assert_eq!(p.core.total_costs, Some(7.5));
+        assert_eq!(p.core.sharpe, None);
+        assert_eq!(p.charts.equity.len(), 1);
+        assert_eq!(p.extras.get("buy_fills"), Some(&json!(40)));
+        assert!(!p.extras.contains_key("roi") && !p.extras.contains_key("costs"));
+        assert!(p.progress.is_some());
+
+        let stored = serde_json::to_value(&p).unwrap();
+        assert_eq!(MetricsPayload::from_value(stored), p);
+    }
+}
@@ -1,8 +1,48 @@
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-pub const RUN_QUEUE_KEY: &str = "mmbot:run_queue";
+use crate::metrics::MetricsPayload;
+
+/// Очереди run'ов — Redis Streams по приоритету; запись — поле `RUN_STREAM_FIELD` с id run'а
+pub const RUN_STREAM_KEY: &str = "mmbot:run_stream";
+pub const RUN_STREAM_HIGH_KEY: &str = "mmbot:run_stream:high";
+pub const RUN_STREAM_LOW_KEY: &str = "mmbot:run_stream:low";
+/// В порядке приоритета: worker берёт новую запись из первого непустого
+pub const RUN_STREAM_KEYS: [&str; 3] = [RUN_STREAM_HIGH_KEY, RUN_STREAM_KEY, RUN_STREAM_LOW_KEY];
+/// Consumer group worker'ов: запись подтверждается (XACK) после завершения run'а,
+/// неподтверждённые записи упавшего worker'а забирают другие
+pub const RUN_STREAM_GROUP: &str = "workers";
+pub const RUN_STREAM_FIELD: &str = "run_id";
+/// Списки LPUSH/BRPOP до перехода на streams (в порядке приоритета); worker при старте
+/// переносит оставшиеся в них id в streams
+pub const LEGACY_RUN_QUEUE_KEYS: [&str; 3] = [
+    "mmbot:run_queue:high",
+    "mmbot:run_queue",
+    "mmbot:run_queue:low",
+];
+/// Канал Postgres NOTIFY со сменой статуса и метрик run'а (`RunUpdate` в JSON):
+/// шлёт worker (и API при отмене queued run'а), API раздаёт SSE подписчикам
+pub const RUN_UPDATES_CHANNEL: &str = "mmbot_run_updates";
+/// Флаг отмены running run'а: API ставит, worker опрашивает
+pub const RUN_CANCEL_KEY_PREFIX: &str = "mmbot:run_cancel:";
+
+pub fn run_cancel_key(run_id: Uuid) -> String {
+    format!("{}{}", RUN_CANCEL_KEY_PREFIX, run_id)
+}
+
+/// Проект run'ов и экспериментов, созданных без `project`
+pub const DEFAULT_PROJECT: &str = "default";
+
+/// Имя проекта: 1–64 символа `a-z`, `0-9`, `_`, `-`
+pub fn is_valid_project_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-')
+}
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -12,6 +52,36 @@ pub enum RunKind {
     BacktestMm,
     BacktestMmMtf,
     BacktestMmMtfSweep,
+    BacktestMmMtfGa,
+    BacktestMmMtfCosts,
+    BacktestMmMtfStress,
+    BacktestMmPortfolio,
+    /// Сравнение артефактов двух прогонов
+    BacktestDiff,
+    /// Предзагрузка и проверка свечей в общем хранилище
+    DataDownload,
+    /// HTML отчёт по артефактам завершённого run'а
+    ReportGeneration,
+    Live,
+    Paper,
+}
+
+/// Приоритет очереди run'а
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunPriority {
+    High,
+    Normal,
+    Low,
+}
+
+impl RunPriority {
+    pub fn stream_key(self) -> &'static str {
+        match self {
+            Self::High => RUN_STREAM_HIGH_KEY,
+            Self::Normal => RUN_STREAM_KEY,
+            Self::Low => RUN_STREAM_LOW_KEY,
+        }
+    }
 }
 
 impl RunKind {
@@ -22,6 +92,75 @@ impl RunKind {
             Self::BacktestMm => "backtest_mm",
             Self::BacktestMmMtf => "backtest_mm_mtf",
             Self::BacktestMmMtfSweep => "backtest_mm_mtf_sweep",
+            Self::BacktestMmMtfGa => "backtest_mm_mtf_ga",
+            Self::BacktestMmMtfCosts => "backtest_mm_mtf_costs",
+            Self::BacktestMmMtfStress => "backtest_mm_mtf_stress",
+            Self::BacktestMmPortfolio => "backtest_mm_portfolio",
+            Self::BacktestDiff => "backtest_diff",
+            Self::DataDownload => "data_download",
+            Self::ReportGeneration => "backtest_report",
+            Self::Live | Self::Paper => "engine",
+        }
+    }
+
+    /// Процесс не завершается сам — только по отмене (SIGTERM); worker запускает такие
+    /// run'ы отдельной задачей параллельно очереди, а не в общем последовательном цикле
+    pub fn is_long_running(self) -> bool {
+        matches!(self, Self::Live | Self::Paper)
+    }
+
+    /// live/paper и данные, от которых ждут другие run'ы, — вперёд (live/paper сразу уходят
+    /// в свою задачу worker'а и не держат очередь); переборы параметров — после одиночных прогонов
+    pub fn priority(self) -> RunPriority {
+        match self {
+            Self::Live | Self::Paper | Self::DataDownload => RunPriority::High,
+            Self::BacktestTrendSweep
+            | Self::BacktestMmMtfSweep
+            | Self::BacktestMmMtfGa
+            | Self::BacktestMmMtfCosts
+            | Self::BacktestMmMtfStress => RunPriority::Low,
+            _ => RunPriority::Normal,
+        }
+    }
+
+    /// Предел времени выполнения, после него worker останавливает процесс
+    /// и помечает run failed; `None` — без предела (live/paper)
+    pub fn default_timeout(self) -> Option<Duration> {
+        const MIN: u64 = 60;
+        let mins = match self {
+            Self::Live | Self::Paper => return None,
+            Self::BacktestDiff | Self::ReportGeneration => 10,
+            Self::BacktestTrend | Self::BacktestMm | Self::BacktestMmMtf => 60,
+            Self::DataDownload | Self::BacktestMmPortfolio => 2 * 60,
+            Self::BacktestTrendSweep
+            | Self::BacktestMmMtfSweep
+            | Self::BacktestMmMtfGa
+            | Self::BacktestMmMtfCosts
+            | Self::BacktestMmMtfStress => 24 * 60,
+        };
+        Some(Duration::from_secs(mins * MIN))
+    }
+
+    /// Бинарь печатает прогресс (`--quiet` — строками `progress:` вместо progress bar)
+    pub fn reports_progress(self) -> bool {
+        matches!(
+            self,
+            Self::BacktestTrendSweep
+                | Self::BacktestMmMtfSweep
+                | Self::BacktestMmMtfGa
+                | Self::BacktestMmMtfCosts
+                | Self::BacktestMmMtfStress
+        )
+    }
+
+    /// `--mode` engine для long-running run'ов.
+    /// Live по умолчанию наблюдает: реальные ордера — только при явном
+    /// `--mode live` в cli_args (и ключах Bybit в окружении worker'а).
+    pub fn engine_mode(self) -> Option<&'static str> {
+        match self {
+            Self::Live => Some("observe"),
+            Self::Paper => Some("paper"),
+            _ => None,
         }
     }
 }
@@ -33,13 +172,62 @@ pub enum RunStatus {
     Running,
     Completed,
     Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunUpdateKind {
+    Status,
+    Metrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunUpdate {
+    pub run_id: Uuid,
+    pub kind: RunUpdateKind,
+    /// Новый статус для `status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<RunStatus>,
+}
+
+impl RunUpdate {
+    pub fn status(run_id: Uuid, status: RunStatus) -> Self {
+        Self {
+            run_id,
+            kind: RunUpdateKind::Status,
+            status: Some(status),
+        }
+    }
+
+    pub fn metrics(run_id: Uuid) -> Self {
+        Self {
+            run_id,
+            kind: RunUpdateKind::Metrics,
+            status: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRunRequest {
     pub name: String,
     pub kind: RunKind,
+    /// Флаги бинаря как есть; после `params`, поэтому перекрывают их
+    #[serde(default)]
     pub cli_args: Vec<String>,
+    /// Типизированные параметры `kind` (`params::TrendBacktestParams`, ...),
+    /// проверяются при создании run'а
+    #[serde(default)]
+    pub params: Option<serde_json::Value>,
+    /// Run'ы, которые должны успешно завершиться до старта (например, `data_download`)
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    #[serde(default)]
+    pub experiment_id: Option<Uuid>,
+    /// `None` — проект ключа API, для ключа администратора — `DEFAULT_PROJECT`
+    #[serde(default)]
+    pub project: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +241,190 @@ pub struct RunRecord {
     pub ended_at: Option<DateTime<Utc>>,
     pub exit_code: Option<i32>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    #[serde(default)]
+    pub experiment_id: Option<Uuid>,
+    /// События и артефакты перенесены в архив (`ARCHIVE_DIR` worker'а)
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(default = "default_project")]
+    pub project: String,
+}
+
+fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
+/// Группа run'ов одного исследования (walk-forward, sweep по символам)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "default_project")]
+    pub project: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateExperimentRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Как в `CreateRunRequest`
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// Пространство имён run'ов, экспериментов и ключей API одной команды или стратегии
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRecord {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProjectRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Ключ API без самого ключа: в базе только его sha256
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: i64,
+    /// `None` — ключ администратора: все проекты, очередь, журнал, ключи
+    pub project: Option<String>,
+    pub label: Option<String>,
+    /// Как `actor` в `audit_log`
+    pub actor: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Ответ на создание ключа: `key` показывается один раз
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub record: ApiKeyRecord,
+}
+
+/// Тип артефакта run'а; имя из строки `artifacts:` бинаря (`equity_csv`,
+/// `top1_fills_csv`, ...) хранится рядом
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    EquityCsv,
+    /// Исполнения: fills MM, trades и round trips trend
+    FillsCsv,
+    /// Таблицы переборов и сценариев: summary, results, per-symbol, stability, ...
+    SweepSummary,
+    /// JSON/HTML отчёт и конфиг прогона
+    Report,
+    Log,
+    /// Запись рыночных данных engine для replay
+    Capture,
+    /// Прочие CSV (underwater, monthly, diff, ...)
+    Table,
+    Other,
+}
+
+impl ArtifactKind {
+    pub fn from_name(name: &str) -> Self {
+        let has = |s: &str| name.contains(s);
+        if has("capture") {
+            Self::Capture
+        } else if name == "log" || name.ends_with("_log") {
+            Self::Log
+        } else if name.starts_with("report_") || name == "config_json" {
+            Self::Report
+        } else if has("diff") {
+            Self::Table
+        } else if has("equity") {
+            Self::EquityCsv
+        } else if has("fills") || has("trades") || has("round_trips") {
+            Self::FillsCsv
+        } else if [
+            "summary",
+            "results",
+            "per_symbol",
+            "stability",
+            "generations",
+            "table",
+            "breakeven",
+        ]
+        .iter()
+        .any(|s| has(s))
+        {
+            Self::SweepSummary
+        } else if name.ends_with("_csv") {
+            Self::Table
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::EquityCsv => "equity_csv",
+            Self::FillsCsv => "fills_csv",
+            Self::SweepSummary => "sweep_summary",
+            Self::Report => "report",
+            Self::Log => "log",
+            Self::Capture => "capture",
+            Self::Table => "table",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [
+            Self::EquityCsv,
+            Self::FillsCsv,
+            Self::SweepSummary,
+            Self::Report,
+            Self::Log,
+            Self::Capture,
+            Self::Table,
+            Self::Other,
+        ]
+        .into_iter()
+        .find(|k| k.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactRecord {
+    pub id: i64,
+    pub run_id: Uuid,
+    pub kind: ArtifactKind,
+    pub name: String,
+    pub path: String,
+    /// Метаданные на момент записи worker'ом; `None` — файла не было
+    pub size_bytes: Option<i64>,
+    /// Строки данных CSV без заголовка
+    pub row_count: Option<i64>,
+    /// `sha256:<hex>`
+    pub checksum: Option<String>,
+    /// Ключ в S3/MinIO; `None` — файл только на диске worker'а
+    #[serde(default)]
+    pub object_key: Option<String>,
+    /// Presigned URL на скачивание, если артефакт загружен в хранилище
+    #[serde(default)]
+    pub download_url: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +432,120 @@ pub struct RunEventRecord {
     pub id: i64,
     pub run_id: Uuid,
     pub ts: DateTime<Utc>,
-    pub level: String,
+    pub level: EventLevel,
     pub message: String,
+    /// Разобранная строка протокола engine (`event`: `progress`, `artifacts`, `metrics`)
+    /// или событие worker'а (`status`, `cancel`, `timeout`); `{}` — просто текст
+    #[serde(default)]
+    pub fields: serde_json::Value,
+}
+
+/// Поток очереди: записей всего (ждущие + взятые) и неподтверждённые записи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueStreamRecord {
+    pub stream: String,
+    pub length: u64,
+    pub pending: Vec<PendingRunRecord>,
+}
+
+/// Запись, взятая worker'ом и ещё не подтверждённая: run идёт или worker упал
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRunRecord {
+    pub entry_id: String,
+    pub run_id: Option<Uuid>,
+    pub consumer: String,
+    /// С последней доставки или heartbeat'а worker'а
+    pub idle_ms: u64,
+    pub deliveries: u64,
+}
+
+/// Изменяющий вызов API из `audit_log`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub ts: DateTime<Utc>,
+    /// Отпечаток ключа API (`key:<hex>`); `None` — запрос без ключа
+    pub actor: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Шаблон маршрута (`/runs/{id}/cancel`)
+    pub route: Option<String>,
+    pub status: i32,
+    pub body: Option<serde_json::Value>,
+}
+
+/// Снимок `run_metrics` из истории (без графиков)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshotRecord {
+    pub id: i64,
+    pub run_id: Uuid,
+    pub ts: DateTime<Utc>,
+    /// Записан по завершении run'а
+    pub is_final: bool,
+    pub payload: MetricsPayload,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum EventLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl EventLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Self::Debug, Self::Info, Self::Warn, Self::Error]
+            .into_iter()
+            .find(|l| l.as_str() == s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_names_map_to_kinds() {
+        let cases = [
+            ("equity_csv", ArtifactKind::EquityCsv),
+            ("top1_equity_csv", ArtifactKind::EquityCsv),
+            ("fills_csv", ArtifactKind::FillsCsv),
+            ("round_trips_csv", ArtifactKind::FillsCsv),
+            ("summary_csv", ArtifactKind::SweepSummary),
+            ("table_csv", ArtifactKind::SweepSummary),
+            ("report_json", ArtifactKind::Report),
+            ("report_html", ArtifactKind::Report),
+            ("config_json", ArtifactKind::Report),
+            ("equity_diff_csv", ArtifactKind::Table),
+            ("underwater_csv", ArtifactKind::Table),
+            ("capture", ArtifactKind::Capture),
+            ("log", ArtifactKind::Log),
+            ("something", ArtifactKind::Other),
+        ];
+        for (name, kind) in cases {
+            assert_eq!(ArtifactKind::from_name(name), kind, "{}", name);
+            assert_eq!(ArtifactKind::parse(kind.as_str()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn project_names_are_slugs() {
+        for name in ["default", "mm-eth", "team_2"] {
+            assert!(is_valid_project_name(name), "{}", name);
+        }
+        for name in ["", "Default", "mm eth", "a/b", &"x".repeat(65)] {
+            assert!(!is_valid_project_name(name), "{}", name);
+        }
+    }
 }
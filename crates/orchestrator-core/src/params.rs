@@ -0,0 +1,646 @@
+//! Типизированные параметры run'ов: контракт между API/worker и бинарями engine.
+//! Имена полей — флаги бинаря (`maker_fee_bps` -> `--maker-fee-bps`),
+//! незаданные `Option` не передаются, и действует default бинаря.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::RunKind;
+
+/// Параметры не прошли проверку схемы
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsError(pub String);
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+/// Опция run'а в `params`, а не флаг бинаря: `true` — worker шлёт в Telegram/Slack
+/// сообщение о завершении или падении run'а
+pub const NOTIFY_PARAM: &str = "notify";
+
+fn invalid<T>(msg: impl Into<String>) -> Result<T, ParamsError> {
+    Err(ParamsError(msg.into()))
+}
+
+pub trait RunParams: Serialize {
+    fn validate(&self) -> Result<(), ParamsError>;
+
+    /// Флаги бинаря: `true` — флаг без значения, списки — через запятую
+    fn to_cli_args(&self) -> Vec<String> {
+        let Ok(Value::Object(fields)) = serde_json::to_value(self) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for (key, value) in fields {
+            let flag = format!("--{}", key.replace('_', "-"));
+            let value = match value {
+                Value::Null | Value::Bool(false) => continue,
+                Value::Bool(true) => {
+                    out.push(flag);
+                    continue;
+                }
+                Value::String(s) => s,
+                Value::Array(items) => items
+                    .iter()
+                    .map(|v| match v {
+                        Value::String(s) => s.clone(),
+                        v => v.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+                v => v.to_string(),
+            };
+            out.push(flag);
+            out.push(value);
+        }
+        out
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarketKind {
+    Spot,
+    Perp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FillMode {
+    Touch,
+    Strict,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntrabarPath {
+    Sorted,
+    Ohlc,
+    Olhc,
+    WorstCase,
+    BestCase,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryGate {
+    Trend,
+    TrendBos,
+    TrendBosPullback,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchMode {
+    Grid,
+    Random,
+    Tpe,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RankBy {
+    Roi,
+    Sharpe,
+    Sortino,
+    Calmar,
+}
+
+fn check_symbol(symbol: &str) -> Result<(), ParamsError> {
+    if symbol.is_empty()
+        || !symbol
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return invalid(format!("symbol must be like ETHUSDT, got {:?}", symbol));
+    }
+    Ok(())
+}
+
+fn check_range(start: NaiveDate, end: NaiveDate) -> Result<(), ParamsError> {
+    if start > end {
+        return invalid("start must not be after end");
+    }
+    Ok(())
+}
+
+fn check_positive(name: &str, v: Option<f64>) -> Result<(), ParamsError> {
+    match v {
+        Some(x) if x.is_nan() || x <= 0.0 => invalid(format!("{} must be > 0", name)),
+        _ => Ok(()),
+    }
+}
+
+fn check_non_negative(name: &str, v: Option<f64>) -> Result<(), ParamsError> {
+    match v {
+        Some(x) if x.is_nan() || x < 0.0 => invalid(format!("{} must be >= 0", name)),
+        _ => Ok(()),
+    }
+}
+
+fn check_frac(name: &str, v: Option<f64>) -> Result<(), ParamsError> {
+    match v {
+        Some(x) if !(0.0..=1.0).contains(&x) => invalid(format!("{} must be in [0, 1]", name)),
+        _ => Ok(()),
+    }
+}
+
+fn check_list(name: &str, v: &Option<Vec<f64>>) -> Result<(), ParamsError> {
+    match v {
+        Some(items) if items.is_empty() => invalid(format!("{} must not be empty", name)),
+        Some(items) if items.iter().any(|x| !x.is_finite()) => {
+            invalid(format!("{} must contain finite numbers", name))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Зоны инвентаря MM: `hard_min <= soft_min <= soft_max <= hard_max`
+fn check_bands(
+    soft: (Option<f64>, Option<f64>),
+    hard: (Option<f64>, Option<f64>),
+) -> Result<(), ParamsError> {
+    for (name, v) in [
+        ("soft_min", soft.0),
+        ("soft_max", soft.1),
+        ("hard_min", hard.0),
+        ("hard_max", hard.1),
+    ] {
+        check_frac(name, v)?;
+    }
+    let ordered = |lo: Option<f64>, hi: Option<f64>| match (lo, hi) {
+        (Some(lo), Some(hi)) => lo <= hi,
+        _ => true,
+    };
+    if !ordered(soft.0, soft.1) || !ordered(hard.0, soft.0) || !ordered(soft.1, hard.1) {
+        return invalid("bands must satisfy hard_min <= soft_min <= soft_max <= hard_max");
+    }
+    Ok(())
+}
+
+/// `backtest_trend`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrendBacktestParams {
+    pub symbol: String,
+    /// Минуты
+    #[serde(default)]
+    pub interval: Option<u32>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    #[serde(default)]
+    pub ema_fast: Option<u32>,
+    #[serde(default)]
+    pub ema_slow: Option<u32>,
+    #[serde(default)]
+    pub atr_stop_mult: Option<f64>,
+    #[serde(default)]
+    pub fee_bps: Option<f64>,
+    #[serde(default)]
+    pub spread_bps: Option<f64>,
+    #[serde(default)]
+    pub slippage_bps: Option<f64>,
+    #[serde(default)]
+    pub initial_quote: Option<f64>,
+    #[serde(default)]
+    pub entry_gate: Option<EntryGate>,
+    #[serde(default)]
+    pub force_close_at_end: bool,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl RunParams for TrendBacktestParams {
+    fn validate(&self) -> Result<(), ParamsError> {
+        check_symbol(&self.symbol)?;
+        check_range(self.start, self.end)?;
+        if self.interval == Some(0) {
+            return invalid("interval must be > 0");
+        }
+        if let (Some(fast), Some(slow)) = (self.ema_fast, self.ema_slow) {
+            if fast == 0 || fast >= slow {
+                return invalid("ema_fast must be in [1, ema_slow)");
+            }
+        }
+        check_positive("atr_stop_mult", self.atr_stop_mult)?;
+        check_positive("initial_quote", self.initial_quote)?;
+        for (name, v) in [
+            ("fee_bps", self.fee_bps),
+            ("spread_bps", self.spread_bps),
+            ("slippage_bps", self.slippage_bps),
+        ] {
+            check_non_negative(name, v)?;
+        }
+        Ok(())
+    }
+}
+
+/// `backtest_mm`: одна сетка на одном таймфрейме
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MmBacktestParams {
+    pub symbol: String,
+    #[serde(default)]
+    pub interval: Option<u32>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    #[serde(default)]
+    pub initial_quote: Option<f64>,
+    #[serde(default)]
+    pub initial_base: Option<f64>,
+    #[serde(default)]
+    pub initial_base_ratio: Option<f64>,
+    #[serde(default)]
+    pub market: Option<MarketKind>,
+    #[serde(default)]
+    pub leverage: Option<f64>,
+    #[serde(default)]
+    pub levels: Option<u32>,
+    #[serde(default)]
+    pub step_bps: Option<f64>,
+    #[serde(default)]
+    pub base_quote_per_order: Option<f64>,
+    #[serde(default)]
+    pub max_size_mult: Option<f64>,
+    #[serde(default)]
+    pub soft_min: Option<f64>,
+    #[serde(default)]
+    pub soft_max: Option<f64>,
+    #[serde(default)]
+    pub hard_min: Option<f64>,
+    #[serde(default)]
+    pub hard_max: Option<f64>,
+    #[serde(default)]
+    pub maker_fee_bps: Option<f64>,
+    #[serde(default)]
+    pub force_close_at_end: bool,
+    #[serde(default)]
+    pub intrabar_path: Option<IntrabarPath>,
+    #[serde(default)]
+    pub fill_mode: Option<FillMode>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl RunParams for MmBacktestParams {
+    fn validate(&self) -> Result<(), ParamsError> {
+        check_symbol(&self.symbol)?;
+        check_range(self.start, self.end)?;
+        if self.interval == Some(0) || self.levels == Some(0) {
+            return invalid("interval and levels must be > 0");
+        }
+        check_positive("initial_quote", self.initial_quote)?;
+        check_non_negative("initial_base", self.initial_base)?;
+        check_frac("initial_base_ratio", self.initial_base_ratio)?;
+        check_positive("leverage", self.leverage)?;
+        check_positive("step_bps", self.step_bps)?;
+        check_positive("base_quote_per_order", self.base_quote_per_order)?;
+        check_positive("max_size_mult", self.max_size_mult)?;
+        check_bands(
+            (self.soft_min, self.soft_max),
+            (self.hard_min, self.hard_max),
+        )
+    }
+}
+
+/// `backtest_mm_mtf`: HTF решения, LTF исполнение
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MmMtfBacktestParams {
+    pub symbol: String,
+    #[serde(default)]
+    pub htf_interval: Option<u32>,
+    #[serde(default)]
+    pub ltf_interval: Option<u32>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    #[serde(default)]
+    pub initial_quote: Option<f64>,
+    #[serde(default)]
+    pub initial_base: Option<f64>,
+    #[serde(default)]
+    pub initial_base_ratio: Option<f64>,
+    #[serde(default)]
+    pub market: Option<MarketKind>,
+    #[serde(default)]
+    pub leverage: Option<f64>,
+    #[serde(default)]
+    pub levels: Option<u32>,
+    #[serde(default)]
+    pub step_bps: Option<f64>,
+    #[serde(default)]
+    pub base_quote_per_order: Option<f64>,
+    #[serde(default)]
+    pub max_size_mult: Option<f64>,
+    #[serde(default)]
+    pub soft_min: Option<f64>,
+    #[serde(default)]
+    pub soft_max: Option<f64>,
+    #[serde(default)]
+    pub hard_min: Option<f64>,
+    #[serde(default)]
+    pub hard_max: Option<f64>,
+    #[serde(default)]
+    pub maker_fee_bps: Option<f64>,
+    #[serde(default)]
+    pub defensive_step_mult: Option<f64>,
+    #[serde(default)]
+    pub defensive_size_mult: Option<f64>,
+    #[serde(default)]
+    pub force_close_at_end: bool,
+    #[serde(default)]
+    pub bootstrap_rebalance: bool,
+    #[serde(default)]
+    pub bootstrap_target_ratio: Option<f64>,
+    #[serde(default)]
+    pub intrabar_path: Option<IntrabarPath>,
+    #[serde(default)]
+    pub fill_mode: Option<FillMode>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn check_mtf_intervals(htf: Option<u32>, ltf: Option<u32>) -> Result<(), ParamsError> {
+    if htf == Some(0) || ltf == Some(0) {
+        return invalid("htf_interval and ltf_interval must be > 0");
+    }
+    if let (Some(htf), Some(ltf)) = (htf, ltf) {
+        if htf % ltf != 0 || htf == ltf {
+            return invalid("htf_interval must be a multiple of ltf_interval");
+        }
+    }
+    Ok(())
+}
+
+impl RunParams for MmMtfBacktestParams {
+    fn validate(&self) -> Result<(), ParamsError> {
+        check_symbol(&self.symbol)?;
+        check_range(self.start, self.end)?;
+        check_mtf_intervals(self.htf_interval, self.ltf_interval)?;
+        if self.levels == Some(0) {
+            return invalid("levels must be > 0");
+        }
+        check_positive("initial_quote", self.initial_quote)?;
+        check_non_negative("initial_base", self.initial_base)?;
+        check_frac("initial_base_ratio", self.initial_base_ratio)?;
+        check_frac("bootstrap_target_ratio", self.bootstrap_target_ratio)?;
+        check_positive("leverage", self.leverage)?;
+        check_positive("step_bps", self.step_bps)?;
+        check_positive("base_quote_per_order", self.base_quote_per_order)?;
+        check_positive("max_size_mult", self.max_size_mult)?;
+        check_positive("defensive_step_mult", self.defensive_step_mult)?;
+        check_positive("defensive_size_mult", self.defensive_size_mult)?;
+        check_bands(
+            (self.soft_min, self.soft_max),
+            (self.hard_min, self.hard_max),
+        )
+    }
+}
+
+/// `backtest_mm_mtf_sweep`: списки значений — оси перебора
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MmMtfSweepParams {
+    pub symbol: String,
+    #[serde(default)]
+    pub htf_interval: Option<u32>,
+    #[serde(default)]
+    pub ltf_interval: Option<u32>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    #[serde(default)]
+    pub initial_quote: Option<f64>,
+    #[serde(default)]
+    pub initial_base: Option<f64>,
+    #[serde(default)]
+    pub initial_base_ratio: Option<f64>,
+    #[serde(default)]
+    pub market: Option<MarketKind>,
+    #[serde(default)]
+    pub search: Option<SearchMode>,
+    #[serde(default)]
+    pub samples: Option<u32>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub levels_list: Option<Vec<u32>>,
+    #[serde(default)]
+    pub step_bps_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub base_quote_per_order_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub max_size_mult_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub soft_min_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub soft_max_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub hard_min_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub hard_max_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub maker_fee_bps_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub defensive_step_mult_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub defensive_size_mult_list: Option<Vec<f64>>,
+    #[serde(default)]
+    pub force_close_fee_bps: Option<f64>,
+    #[serde(default)]
+    pub force_close_spread_bps: Option<f64>,
+    #[serde(default)]
+    pub force_close_slippage_bps: Option<f64>,
+    #[serde(default)]
+    pub force_close_at_end: bool,
+    #[serde(default)]
+    pub bootstrap_rebalance: bool,
+    #[serde(default)]
+    pub bootstrap_target_ratio: Option<f64>,
+    #[serde(default)]
+    pub top_n: Option<u32>,
+    #[serde(default)]
+    pub rank_by: Option<RankBy>,
+    #[serde(default)]
+    pub summary_out: Option<String>,
+}
+
+impl RunParams for MmMtfSweepParams {
+    fn validate(&self) -> Result<(), ParamsError> {
+        check_symbol(&self.symbol)?;
+        check_range(self.start, self.end)?;
+        check_mtf_intervals(self.htf_interval, self.ltf_interval)?;
+        check_positive("initial_quote", self.initial_quote)?;
+        check_non_negative("initial_base", self.initial_base)?;
+        check_frac("initial_base_ratio", self.initial_base_ratio)?;
+        check_frac("bootstrap_target_ratio", self.bootstrap_target_ratio)?;
+        for (name, v) in [
+            ("step_bps_list", &self.step_bps_list),
+            ("base_quote_per_order_list", &self.base_quote_per_order_list),
+            ("max_size_mult_list", &self.max_size_mult_list),
+            ("soft_min_list", &self.soft_min_list),
+            ("soft_max_list", &self.soft_max_list),
+            ("hard_min_list", &self.hard_min_list),
+            ("hard_max_list", &self.hard_max_list),
+            ("maker_fee_bps_list", &self.maker_fee_bps_list),
+            ("defensive_step_mult_list", &self.defensive_step_mult_list),
+            ("defensive_size_mult_list", &self.defensive_size_mult_list),
+        ] {
+            check_list(name, v)?;
+        }
+        if self
+            .levels_list
+            .as_ref()
+            .is_some_and(|l| l.is_empty() || l.contains(&0))
+        {
+            return invalid("levels_list must be non-empty, levels > 0");
+        }
+        for (name, v) in [
+            ("force_close_fee_bps", self.force_close_fee_bps),
+            ("force_close_spread_bps", self.force_close_spread_bps),
+            ("force_close_slippage_bps", self.force_close_slippage_bps),
+        ] {
+            check_non_negative(name, v)?;
+        }
+        if self.samples == Some(0) || self.top_n == Some(0) {
+            return invalid("samples and top_n must be > 0");
+        }
+        Ok(())
+    }
+}
+
+/// `data_download`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DataDownloadParams {
+    pub symbols: Vec<String>,
+    pub intervals: Vec<u32>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    #[serde(default)]
+    pub refresh: bool,
+    #[serde(default)]
+    pub max_missing_pct: Option<f64>,
+}
+
+impl RunParams for DataDownloadParams {
+    fn validate(&self) -> Result<(), ParamsError> {
+        if self.symbols.is_empty() || self.intervals.is_empty() {
+            return invalid("symbols and intervals must not be empty");
+        }
+        for s in &self.symbols {
+            check_symbol(s)?;
+        }
+        if self.intervals.contains(&0) {
+            return invalid("intervals must be > 0");
+        }
+        check_range(self.start, self.end)?;
+        check_non_negative("max_missing_pct", self.max_missing_pct)
+    }
+}
+
+fn parse<T: RunParams + for<'de> Deserialize<'de>>(params: &Value) -> Result<T, ParamsError> {
+    let typed: T =
+        serde_json::from_value(params.clone()).map_err(|e| ParamsError(e.to_string()))?;
+    typed.validate()?;
+    Ok(typed)
+}
+
+/// Проверенные параметры `kind` в флаги его бинаря
+pub fn typed_cli_args(kind: RunKind, params: &Value) -> Result<Vec<String>, ParamsError> {
+    // опции run'а — не параметры бинаря; `{"notify": true}` годится и для run'а на cli_args
+    let mut params = params.clone();
+    if let Value::Object(fields) = &mut params {
+        if let Some(notify) = fields.remove(NOTIFY_PARAM) {
+            if !notify.is_boolean() {
+                return invalid("notify must be true or false");
+            }
+            if fields.is_empty() {
+                return Ok(Vec::new());
+            }
+        }
+    }
+    let params = &params;
+    match kind {
+        RunKind::BacktestTrend => Ok(parse::<TrendBacktestParams>(params)?.to_cli_args()),
+        RunKind::BacktestMm => Ok(parse::<MmBacktestParams>(params)?.to_cli_args()),
+        RunKind::BacktestMmMtf => Ok(parse::<MmMtfBacktestParams>(params)?.to_cli_args()),
+        RunKind::BacktestMmMtfSweep => Ok(parse::<MmMtfSweepParams>(params)?.to_cli_args()),
+        RunKind::DataDownload => Ok(parse::<DataDownloadParams>(params)?.to_cli_args()),
+        _ => invalid(format!(
+            "typed params are not supported for {}, use cli_args",
+            kind.engine_bin()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn typed_params_render_binary_flags() {
+        let sweep = json!({
+            "symbol": "ETHUSDT",
+            "htf_interval": 15,
+            "ltf_interval": 5,
+            "start": "2026-01-01",
+            "end": "2026-01-31",
+            "levels_list": [3, 5],
+            "maker_fee_bps_list": [2.5],
+            "bootstrap_rebalance": true,
+            "force_close_at_end": false,
+            "rank_by": "sharpe",
+        });
+        let args = typed_cli_args(RunKind::BacktestMmMtfSweep, &sweep).unwrap();
+        let pairs: Vec<&str> = args.iter().map(String::as_str).collect();
+        assert!(pairs.windows(2).any(|w| w == ["--levels-list", "3,5"]));
+        assert!(
+            pairs
+                .windows(2)
+                .any(|w| w == ["--maker-fee-bps-list", "2.5"])
+        );
+        assert!(pairs.windows(2).any(|w| w == ["--start", "2026-01-01"]));
+        assert!(pairs.windows(2).any(|w| w == ["--rank-by", "sharpe"]));
+        assert!(pairs.contains(&"--bootstrap-rebalance"));
+        assert!(!pairs.contains(&"--force-close-at-end"));
+        assert!(!pairs.contains(&"--top-n"));
+
+        let trend = json!({"symbol": "BTCUSDT", "start": "2026-01-01", "end": "2026-02-01",
+            "entry_gate": "trend-bos", "ema_fast": 10, "ema_slow": 50});
+        let args = typed_cli_args(RunKind::BacktestTrend, &trend).unwrap();
+        assert!(args.windows(2).any(|w| w == ["--entry-gate", "trend-bos"]));
+
+        let bad = [
+            json!({"symbol": "ETHUSDT", "start": "2026-02-01", "end": "2026-01-01"}),
+            json!({"symbol": "ETHUSDT", "start": "2026-13-01", "end": "2026-01-31"}),
+            json!({"symbol": "ETHUSDT", "start": "2026-01-01", "end": "2026-01-31", "levls": 5}),
+            json!({"symbol": "ETHUSDT", "start": "2026-01-01", "end": "2026-01-31",
+                "htf_interval": 15, "ltf_interval": 4}),
+            json!({"symbol": "ETHUSDT", "start": "2026-01-01", "end": "2026-01-31",
+                "soft_min": 0.5, "hard_min": 0.6}),
+        ];
+        for p in &bad {
+            assert!(typed_cli_args(RunKind::BacktestMmMtf, p).is_err(), "{}", p);
+        }
+        assert!(typed_cli_args(RunKind::Live, &json!({})).is_err());
+
+        let notify = json!({"symbol": "BTCUSDT", "start": "2026-01-01", "end": "2026-02-01",
+            "notify": true});
+        let args = typed_cli_args(RunKind::BacktestTrend, &notify).unwrap();
+        assert!(!args.iter().any(|a| a == "--notify"));
+        assert_eq!(
+            typed_cli_args(RunKind::Live, &json!({"notify": true})),
+            Ok(Vec::new())
+        );
+        assert!(typed_cli_args(RunKind::BacktestTrend, &json!({"notify": "yes"})).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Логи и трассировка api/worker: fmt в stdout, а при заданном `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! (или `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) — ещё и спаны в OTLP по HTTP/protobuf.
+//! Спаны несут `run_id`, по нему связываются enqueue в api и claim/spawn/persist в worker.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Держит OTLP provider; при drop выгружает оставшиеся спаны
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// `default_filter` — если не задан `RUST_LOG`; `service_name` — `service.name` в OTLP,
+/// переопределяется `OTEL_SERVICE_NAME`
+pub fn init(service_name: &'static str, default_filter: &str) -> TelemetryGuard {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let otlp_enabled = [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .iter()
+    .any(|k| std::env::var_os(k).is_some_and(|v| !v.is_empty()));
+    let provider = otlp_enabled.then(|| build_provider(service_name));
+    let tracer = match &provider {
+        Some(Ok(p)) => Some(p.tracer(service_name)),
+        _ => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracer.map(|t| tracing_opentelemetry::layer().with_tracer(t)))
+        .init();
+
+    let provider = match provider {
+        Some(Ok(p)) => Some(p),
+        Some(Err(e)) => {
+            tracing::warn!("OTLP exporter disabled: {}", e);
+            None
+        }
+        None => None,
+    };
+    TelemetryGuard { provider }
+}
+
+fn build_provider(
+    service_name: &'static str,
+) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+    let exporter = SpanExporter::builder().with_http().build()?;
+    let mut resource = Resource::builder();
+    if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name(service_name);
+    }
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build())
+}
@@ -19,9 +19,22 @@ pub enum MmDecisionReason {
     InventoryOutsideSoftBand,
     InventoryOutsideHardBand,
     LtfStructureBroken,
+    /// Market data приходит с задержками / дырами
+    DataDegraded,
+    /// Market data непригодна для торговли
+    DataBad,
     Ok,
 }
 
+/// Качество market data (задержка свечей, паузы между сообщениями, реконнекты WS)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DataQuality {
+    #[default]
+    Good,
+    Degraded,
+    Bad,
+}
+
 /// Параметры policy
 #[derive(Debug, Copy, Clone)]
 pub struct MmPolicyParams {
@@ -85,3 +98,43 @@ pub fn mm_policy_decision(
         reason: MmDecisionReason::Ok,
     }
 }
+
+/// Понижает режим по качеству данных: Degraded — не выше Defensive,
+/// Bad — Disabled. Решения строже исходного не ослабляются.
+pub fn apply_data_quality(decision: MmPolicyDecision, quality: DataQuality) -> MmPolicyDecision {
+    match (quality, decision.mode) {
+        (DataQuality::Bad, MmMode::Normal | MmMode::Defensive) => MmPolicyDecision {
+            mode: MmMode::Disabled,
+            reason: MmDecisionReason::DataBad,
+        },
+        (DataQuality::Degraded, MmMode::Normal) => MmPolicyDecision {
+            mode: MmMode::Defensive,
+            reason: MmDecisionReason::DataDegraded,
+        },
+        _ => decision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(mode: MmMode) -> MmPolicyDecision {
+        MmPolicyDecision {
+            mode,
+            reason: MmDecisionReason::Ok,
+        }
+    }
+
+    #[test]
+    fn data_quality_only_tightens_mode() {
+        let d = apply_data_quality(decision(MmMode::Normal), DataQuality::Degraded);
+        assert_eq!(d.mode, MmMode::Defensive);
+        let d = apply_data_quality(decision(MmMode::Defensive), DataQuality::Bad);
+        assert_eq!(d.mode, MmMode::Disabled);
+        let d = apply_data_quality(decision(MmMode::Disabled), DataQuality::Degraded);
+        assert_eq!(d.mode, MmMode::Disabled);
+        let d = apply_data_quality(decision(MmMode::Normal), DataQuality::Good);
+        assert_eq!(d.mode, MmMode::Normal);
+    }
+}
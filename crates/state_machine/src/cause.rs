@@ -21,4 +21,8 @@ pub enum TransitionCause {
 
     // Exit lifecycle
     ExitDone,
+
+    // Risk
+    EmergencyHalt,
+    HaltCleared,
 }
@@ -7,4 +7,6 @@ pub enum BotState {
     MMNormal,
     MMDefensive,
     Exiting,
+    /// Аварийная остановка (risk / kill switch), ордеров нет
+    Halted,
 }
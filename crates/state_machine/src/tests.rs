@@ -26,3 +26,25 @@ fn illegal_transition_from_idle() {
 fn cannot_skip_bos_confirmation() {
     assert!(transition(BotState::IdleUSDT, TransitionCause::PullbackDetected).is_err());
 }
+
+#[test]
+fn emergency_halt_from_any_state_and_manual_clear() {
+    for s in [
+        BotState::IdleUSDT,
+        BotState::BosConfirmed,
+        BotState::MMNormal,
+        BotState::Exiting,
+    ] {
+        assert_eq!(
+            transition(s, TransitionCause::EmergencyHalt),
+            Ok(BotState::Halted)
+        );
+    }
+
+    assert!(transition(BotState::Halted, TransitionCause::EmergencyHalt).is_err());
+    assert!(transition(BotState::Halted, TransitionCause::HtfBosUpDetected).is_err());
+    assert_eq!(
+        transition(BotState::Halted, TransitionCause::HaltCleared),
+        Ok(BotState::IdleUSDT)
+    );
+}
@@ -43,6 +43,11 @@ pub fn transition(state: BotState, cause: TransitionCause) -> Result<BotState, T
         // --- Exiting --------------------------------------------------------
         (BotState::Exiting, TransitionCause::ExitDone) => BotState::IdleUSDT,
 
+        // --- Risk -----------------------------------------------------------
+        // halt разрешён из любого состояния, выход — только вручную в Idle
+        (BotState::Halted, TransitionCause::HaltCleared) => BotState::IdleUSDT,
+        (s, TransitionCause::EmergencyHalt) if s != BotState::Halted => BotState::Halted,
+
         // --- Illegal --------------------------------------------------------
         _ => return Err(TransitionError::IllegalTransition { from: state, cause }),
     };
@@ -0,0 +1,4 @@
+// sqlx::migrate! встраивает миграции при компиляции: пересобирать при новых файлах
+fn main() {
+    println!("cargo:rerun-if-changed=../../migrations");
+}
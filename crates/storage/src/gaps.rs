@@ -0,0 +1,156 @@
+use structure::candle::Candle;
+
+/// Пропуски серии: диапазоны `[from, to]` в мс, где у слотов интервала нет свечи.
+/// `from` — открытие первой пропущенной свечи, `to` — конец последней.
+/// Свечи — по возрастанию ts; дубли и свечи вне `range` не мешают.
+pub fn find_gaps(
+    candles: &[Candle],
+    interval_ms: i64,
+    (start_ms, end_ms): (i64, i64),
+) -> Vec<(i64, i64)> {
+    let mut next = start_ms.div_euclid(interval_ms) * interval_ms;
+    if next < start_ms {
+        next += interval_ms;
+    }
+    let last_slot = end_ms.div_euclid(interval_ms) * interval_ms;
+    let mut out = Vec::new();
+    for c in candles {
+        let ts = c.ts.0;
+        if ts < next || ts > last_slot {
+            continue;
+        }
+        if ts > next {
+            out.push((next, ts - 1));
+        }
+        next = ts + interval_ms;
+    }
+    if next <= last_slot {
+        out.push((next, last_slot + interval_ms - 1));
+    }
+    out
+}
+
+/// Окна докачки: соседние пропуски склеиваются, пока окно не длиннее
+/// `max_candles` свечей, — мелкие дыры качаются одним запросом
+pub fn backfill_windows(
+    gaps: &[(i64, i64)],
+    interval_ms: i64,
+    max_candles: i64,
+) -> Vec<(i64, i64)> {
+    let max_len = interval_ms * max_candles;
+    let mut out: Vec<(i64, i64)> = Vec::new();
+    for &(from, to) in gaps {
+        match out.last_mut() {
+            Some(w) if to - w.0 < max_len => w.1 = to,
+            _ => out.push((from, to)),
+        }
+    }
+    out
+}
+
+/// Итог проверки серии перед прогоном
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GapRepair {
+    /// Пропуски до докачки (без уже известных пропусков биржи)
+    pub detected: Vec<(i64, i64)>,
+    /// Пропуски, которые докачка закрыла полностью
+    pub repaired: Vec<(i64, i64)>,
+    /// Что осталось пустым и после докачки — свечей нет у самой биржи
+    pub unrepaired: Vec<(i64, i64)>,
+}
+
+impl GapRepair {
+    /// `gaps=.. repaired=.. unrepaired=..` для строки лога
+    pub fn summary(&self) -> String {
+        format!(
+            "gaps={} repaired={} unrepaired={}",
+            self.detected.len(),
+            self.repaired.len(),
+            self.unrepaired.len()
+        )
+    }
+}
+
+/// `gaps` без диапазонов `known` (пропуски биржи, подтверждённые раньше)
+pub fn subtract_known(gaps: &[(i64, i64)], known: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    gaps.iter()
+        .copied()
+        .filter(|&(from, to)| !known.iter().any(|&(s, e)| s <= from && e >= to))
+        .collect()
+}
+
+/// Непокрытые куски запроса; диапазоны включительные, соседние (`end + 1 == start`) склеиваются
+pub fn missing_ranges(
+    mut covered: Vec<(i64, i64)>,
+    (start_ms, end_ms): (i64, i64),
+) -> Vec<(i64, i64)> {
+    covered.sort_unstable();
+    let mut out = Vec::new();
+    let mut cursor = start_ms;
+    for (s, e) in covered {
+        if cursor > end_ms {
+            break;
+        }
+        if s > cursor {
+            out.push((cursor, (s - 1).min(end_ms)));
+        }
+        cursor = cursor.max(e.saturating_add(1));
+    }
+    if cursor <= end_ms {
+        out.push((cursor, end_ms));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Price, Qty, TimestampMs};
+
+    fn candle(ts: i64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(1.0),
+            high: Price(1.0),
+            low: Price(1.0),
+            close: Price(1.0),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn finds_gaps_and_merges_windows() {
+        let m = 60_000;
+        // слоты 0..10, нет 2, 3, 7 и хвоста 9
+        let candles: Vec<Candle> = [0, 1, 4, 5, 6, 8].iter().map(|i| candle(i * m)).collect();
+        let gaps = find_gaps(&candles, m, (0, 10 * m - 1));
+        assert_eq!(
+            gaps,
+            vec![(2 * m, 4 * m - 1), (7 * m, 8 * m - 1), (9 * m, 10 * m - 1)]
+        );
+        assert!(find_gaps(&candles, m, (4 * m, 7 * m - 1)).is_empty());
+        assert_eq!(find_gaps(&[], m, (30_000, 2 * m - 1)), vec![(m, 2 * m - 1)]);
+
+        assert_eq!(
+            backfill_windows(&gaps, m, 6),
+            vec![(2 * m, 8 * m - 1), (9 * m, 10 * m - 1)]
+        );
+        assert_eq!(backfill_windows(&gaps, m, 100), vec![(2 * m, 10 * m - 1)]);
+
+        assert_eq!(
+            subtract_known(&gaps, &[(7 * m, 8 * m - 1)]),
+            vec![(2 * m, 4 * m - 1), (9 * m, 10 * m - 1)]
+        );
+    }
+
+    #[test]
+    fn finds_gaps_between_downloaded_ranges() {
+        assert_eq!(missing_ranges(vec![], (0, 99)), vec![(0, 99)]);
+        assert_eq!(missing_ranges(vec![(0, 99)], (10, 20)), vec![]);
+        assert_eq!(
+            missing_ranges(vec![(30, 49), (0, 9), (10, 19), (45, 60)], (0, 99)),
+            vec![(20, 29), (61, 99)]
+        );
+        assert_eq!(missing_ranges(vec![(50, 200)], (0, 99)), vec![(0, 49)]);
+    }
+}
@@ -1 +1,6 @@
+//! Общее хранилище данных между процессами (Postgres) и общая для всех
+//! хранилищ свечей логика диапазонов и пропусков.
 
+pub mod gaps;
+pub mod postgres;
+pub mod series;
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use core::types::{Price, Qty, TimestampMs};
+use structure::candle::Candle;
+
+use crate::series::CandleSeries;
+
+/// Свечи, скачанные с биржи, общие для backtest'ов и worker'а:
+/// `candles` по (symbol, interval, ts), `candle_ranges` — какие диапазоны уже скачаны,
+/// `candle_gaps` — пропуски, которых нет и у биржи
+#[derive(Clone)]
+pub struct CandleStore {
+    pool: PgPool,
+}
+
+impl CandleStore {
+    /// Подключение с применением миграций (стандартный `DATABASE_URL` схемы orchestrator)
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = PgPool::connect(url)
+            .await
+            .context("candle store connection failed")?;
+        sqlx::migrate!("../../migrations").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    /// Транзакция с advisory lock на (symbol, interval): параллельные прогоны
+    /// ждут, пока первый докачает данные, вместо повторной загрузки
+    pub async fn begin(&self, symbol: &str, interval_min: i32) -> Result<CandleSession> {
+        let symbol = symbol.to_ascii_uppercase();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("candles:{}:{}", symbol, interval_min))
+            .execute(&mut *tx)
+            .await
+            .context("candle store lock failed")?;
+        Ok(CandleSession {
+            tx,
+            symbol,
+            interval_min,
+        })
+    }
+}
+
+/// Серия одного символа и интервала под блокировкой (`CandleSeries`);
+/// без `commit` изменения откатываются
+pub struct CandleSession {
+    tx: Transaction<'static, Postgres>,
+    symbol: String,
+    interval_min: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbCandle {
+    ts: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+impl CandleSession {
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await.context("candle store commit failed")
+    }
+}
+
+impl CandleSeries for CandleSession {
+    async fn ranges(&mut self, range: (i64, i64)) -> Result<Vec<(i64, i64)>> {
+        sqlx::query_as(
+            r#"
+            SELECT start_ms, end_ms
+            FROM candle_ranges
+            WHERE symbol = $1 AND interval_min = $2 AND end_ms >= $3 AND start_ms <= $4
+            "#,
+        )
+        .bind(&self.symbol)
+        .bind(self.interval_min)
+        .bind(range.0)
+        .bind(range.1)
+        .fetch_all(&mut *self.tx)
+        .await
+        .context("candle ranges query failed")
+    }
+
+    async fn save(&mut self, range: (i64, i64), candles: &[Candle]) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO candles (symbol, interval_min, ts, open, high, low, close, volume)
+            SELECT $1, $2, * FROM UNNEST($3::BIGINT[], $4::FLOAT8[], $5::FLOAT8[],
+                $6::FLOAT8[], $7::FLOAT8[], $8::FLOAT8[])
+            ON CONFLICT (symbol, interval_min, ts)
+            DO UPDATE SET open = EXCLUDED.open, high = EXCLUDED.high, low = EXCLUDED.low,
+                close = EXCLUDED.close, volume = EXCLUDED.volume
+            "#,
+        )
+        .bind(&self.symbol)
+        .bind(self.interval_min)
+        .bind(candles.iter().map(|c| c.ts.0).collect::<Vec<_>>())
+        .bind(candles.iter().map(|c| c.open.0).collect::<Vec<_>>())
+        .bind(candles.iter().map(|c| c.high.0).collect::<Vec<_>>())
+        .bind(candles.iter().map(|c| c.low.0).collect::<Vec<_>>())
+        .bind(candles.iter().map(|c| c.close.0).collect::<Vec<_>>())
+        .bind(candles.iter().map(|c| c.volume.0).collect::<Vec<_>>())
+        .execute(&mut *self.tx)
+        .await
+        .context("candles upsert failed")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO candle_ranges (symbol, interval_min, start_ms, end_ms)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&self.symbol)
+        .bind(self.interval_min)
+        .bind(range.0)
+        .bind(range.1)
+        .execute(&mut *self.tx)
+        .await
+        .context("candle range insert failed")?;
+        Ok(())
+    }
+
+    async fn load(&mut self, (start_ms, end_ms): (i64, i64)) -> Result<Vec<Candle>> {
+        let rows: Vec<DbCandle> = sqlx::query_as(
+            r#"
+            SELECT ts, open, high, low, close, volume
+            FROM candles
+            WHERE symbol = $1 AND interval_min = $2 AND ts BETWEEN $3 AND $4
+            ORDER BY ts
+            "#,
+        )
+        .bind(&self.symbol)
+        .bind(self.interval_min)
+        .bind(start_ms)
+        .bind(end_ms)
+        .fetch_all(&mut *self.tx)
+        .await
+        .context("candles query failed")?;
+        Ok(rows
+            .into_iter()
+            .map(|r| Candle {
+                ts: TimestampMs(r.ts),
+                open: Price(r.open),
+                high: Price(r.high),
+                low: Price(r.low),
+                close: Price(r.close),
+                volume: Qty(r.volume),
+            })
+            .collect())
+    }
+
+    async fn known_gaps(&mut self, range: (i64, i64)) -> Result<Vec<(i64, i64)>> {
+        sqlx::query_as(
+            r#"
+            SELECT start_ms, end_ms
+            FROM candle_gaps
+            WHERE symbol = $1 AND interval_min = $2 AND end_ms >= $3 AND start_ms <= $4
+            "#,
+        )
+        .bind(&self.symbol)
+        .bind(self.interval_min)
+        .bind(range.0)
+        .bind(range.1)
+        .fetch_all(&mut *self.tx)
+        .await
+        .context("candle gaps query failed")
+    }
+
+    async fn add_known_gaps(&mut self, gaps: &[(i64, i64)]) -> Result<()> {
+        for &(start_ms, end_ms) in gaps {
+            sqlx::query(
+                r#"
+                INSERT INTO candle_gaps (symbol, interval_min, start_ms, end_ms)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(&self.symbol)
+            .bind(self.interval_min)
+            .bind(start_ms)
+            .bind(end_ms)
+            .execute(&mut *self.tx)
+            .await
+            .context("candle gap insert failed")?;
+        }
+        Ok(())
+    }
+}
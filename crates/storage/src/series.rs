@@ -0,0 +1,42 @@
+use std::future::Future;
+
+use anyhow::Result;
+use structure::candle::Candle;
+
+use crate::gaps::missing_ranges;
+
+/// Одна серия свечей (символ × интервал) в хранилище — общем Postgres или
+/// локальном SQLite: скачанные диапазоны, сами свечи и подтверждённые пропуски
+/// биржи. Докачка поверх неё одна для обоих хранилищ.
+pub trait CandleSeries: Send {
+    /// Скачанные диапазоны, пересекающие `range`
+    fn ranges(&mut self, range: (i64, i64))
+    -> impl Future<Output = Result<Vec<(i64, i64)>>> + Send;
+
+    /// Upsert свечей и отметка `range` как скачанного
+    fn save(
+        &mut self,
+        range: (i64, i64),
+        candles: &[Candle],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Свечи `[start, end]` по возрастанию ts
+    fn load(&mut self, range: (i64, i64)) -> impl Future<Output = Result<Vec<Candle>>> + Send;
+
+    /// Известные пропуски биржи, пересекающие `range`
+    fn known_gaps(
+        &mut self,
+        range: (i64, i64),
+    ) -> impl Future<Output = Result<Vec<(i64, i64)>>> + Send;
+
+    /// Отмечает пропуски как пропуски биржи: дальше они не докачиваются
+    fn add_known_gaps(&mut self, gaps: &[(i64, i64)]) -> impl Future<Output = Result<()>> + Send;
+
+    /// Части `range`, которых ещё нет в скачанных диапазонах
+    fn missing(
+        &mut self,
+        range: (i64, i64),
+    ) -> impl Future<Output = Result<Vec<(i64, i64)>>> + Send {
+        async move { Ok(missing_ranges(self.ranges(range).await?, range)) }
+    }
+}
@@ -25,6 +25,12 @@ pub struct BosParams {
     pub epsilon_frac: f64,
 }
 
+impl Default for BosTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BosTracker {
     pub fn new() -> Self {
         Self {
@@ -16,6 +16,9 @@ pub enum Timeframe {
     Min1,
     Min5,
     Min15,
+    Min30,
+    Hour1,
+    Hour4,
 }
 
 impl Timeframe {
@@ -24,6 +27,92 @@ impl Timeframe {
             Timeframe::Min1 => 60_000,
             Timeframe::Min5 => 5 * 60_000,
             Timeframe::Min15 => 15 * 60_000,
+            Timeframe::Min30 => 30 * 60_000,
+            Timeframe::Hour1 => 60 * 60_000,
+            Timeframe::Hour4 => 240 * 60_000,
         }
     }
 }
+
+/// Свечи таймфрейма `tf` из более мелких (5m/15m/1h из 1m)
+pub fn resample(candles: &[Candle], tf: Timeframe) -> Vec<Candle> {
+    resample_ms(candles, tf.as_millis())
+}
+
+/// Свечи интервала `interval_ms` из более мелких: окна от эпохи (как у бирж),
+/// open — первой свечи окна, close — последней, high/low — экстремумы, объём — сумма.
+/// Свечи — по возрастанию ts; окно с пропусками собирается из того, что есть.
+pub fn resample_ms(candles: &[Candle], interval_ms: i64) -> Vec<Candle> {
+    let mut out: Vec<Candle> = Vec::new();
+    for c in candles {
+        let ts = c.ts.0 - c.ts.0.rem_euclid(interval_ms);
+        match out.last_mut() {
+            Some(last) if last.ts.0 == ts => {
+                last.high = Price(last.high.0.max(c.high.0));
+                last.low = Price(last.low.0.min(c.low.0));
+                last.close = c.close;
+                last.volume = Qty(last.volume.0 + c.volume.0);
+            }
+            _ => out.push(Candle {
+                ts: TimestampMs(ts),
+                ..*c
+            }),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(ts: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(ts),
+            open: Price(open),
+            high: Price(high),
+            low: Price(low),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    #[test]
+    fn resamples_minutes_into_epoch_aligned_windows() {
+        let m = 60_000;
+        // начинается с середины 5m окна, минута 7 пропущена
+        let ltf = [
+            candle(3 * m, 10.0, 11.0, 9.0, 10.5),
+            candle(4 * m, 10.5, 12.0, 10.0, 11.0),
+            candle(5 * m, 11.0, 11.5, 10.8, 11.2),
+            candle(6 * m, 11.2, 11.3, 8.0, 9.0),
+            candle(8 * m, 9.0, 9.5, 8.5, 9.4),
+            candle(9 * m, 9.4, 9.9, 9.1, 9.8),
+            candle(10 * m, 9.8, 10.0, 9.7, 9.9),
+        ];
+        let htf = resample(&ltf, Timeframe::Min5);
+        assert_eq!(htf.len(), 3);
+        let first = htf[0];
+        assert_eq!(
+            (first.ts, first.open, first.close),
+            (TimestampMs(0), Price(10.0), Price(11.0))
+        );
+        assert_eq!(
+            (first.high, first.low, first.volume),
+            (Price(12.0), Price(9.0), Qty(2.0))
+        );
+        let second = htf[1];
+        assert_eq!(second.ts, TimestampMs(5 * m));
+        assert_eq!(
+            (second.open, second.high, second.low),
+            (Price(11.0), Price(11.5), Price(8.0))
+        );
+        assert_eq!((second.close, second.volume), (Price(9.8), Qty(4.0)));
+        assert_eq!(htf[2].ts, TimestampMs(10 * m));
+
+        let hour = resample(&ltf, Timeframe::Hour1);
+        assert_eq!(hour.len(), 1);
+        assert_eq!((hour[0].open, hour[0].close), (Price(10.0), Price(9.9)));
+        assert_eq!(resample_ms(&ltf, m).len(), ltf.len());
+    }
+}
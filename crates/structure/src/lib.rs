@@ -1,6 +1,7 @@
 pub mod atr;
 pub mod bos;
 pub mod candle;
+pub mod ltf;
 pub mod pivot;
 pub mod pullback;
 pub mod structure;
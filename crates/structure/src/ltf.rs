@@ -0,0 +1,164 @@
+use core::types::Price;
+
+use crate::candle::Candle;
+use crate::structure::MarketStructure;
+
+/// Параметры LTF-детектора (слом структуры вниз / восстановление)
+#[derive(Debug, Copy, Clone)]
+pub struct LtfParams {
+    /// Запас за уровнем в долях ATR (например 0.1)
+    pub epsilon_frac: f64,
+    /// Сколько закрытий выше сломанного уровня нужно для восстановления
+    pub recover_candles: usize,
+}
+
+/// Сигнал LTF на закрытой свече
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LtfSignal {
+    None,
+    BrokenDown,
+    Recovered,
+}
+
+/// LTF sidecar: BOS вниз по last_low и восстановление над ним
+#[derive(Debug, Copy, Clone)]
+pub struct LtfTracker {
+    pub broken: bool,
+    pub level: Option<Price>,
+    pub closes_above: usize,
+}
+
+impl Default for LtfTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LtfTracker {
+    pub fn new() -> Self {
+        Self {
+            broken: false,
+            level: None,
+            closes_above: 0,
+        }
+    }
+
+    pub fn on_candle_close(
+        &mut self,
+        candle: &Candle,
+        structure: &MarketStructure,
+        atr: Price,
+        params: LtfParams,
+    ) -> LtfSignal {
+        let epsilon = atr.0 * params.epsilon_frac;
+
+        if !self.broken {
+            let Some(low) = structure.last_low else {
+                return LtfSignal::None;
+            };
+
+            if candle.close.0 < low.0 - epsilon {
+                self.broken = true;
+                self.level = Some(low);
+                self.closes_above = 0;
+                return LtfSignal::BrokenDown;
+            }
+
+            return LtfSignal::None;
+        }
+
+        let level = self.level.expect("level must exist");
+
+        if candle.close.0 > level.0 + epsilon {
+            self.closes_above += 1;
+        } else {
+            // восстановление должно идти подряд
+            self.closes_above = 0;
+        }
+
+        if self.closes_above >= params.recover_candles.max(1) {
+            self.reset();
+            return LtfSignal::Recovered;
+        }
+
+        LtfSignal::None
+    }
+
+    pub fn reset(&mut self) {
+        self.broken = false;
+        self.level = None;
+        self.closes_above = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::{Qty, TimestampMs};
+
+    fn candle(close: f64) -> Candle {
+        Candle {
+            ts: TimestampMs(0),
+            open: Price(close),
+            high: Price(close),
+            low: Price(close),
+            close: Price(close),
+            volume: Qty(1.0),
+        }
+    }
+
+    fn structure(low: f64) -> MarketStructure {
+        MarketStructure {
+            last_high: None,
+            last_low: Some(Price(low)),
+        }
+    }
+
+    fn params() -> LtfParams {
+        LtfParams {
+            epsilon_frac: 0.1,
+            recover_candles: 2,
+        }
+    }
+
+    #[test]
+    fn breaks_down_below_last_low() {
+        let mut t = LtfTracker::new();
+        let s = structure(100.0);
+        assert_eq!(
+            t.on_candle_close(&candle(99.95), &s, Price(1.0), params()),
+            LtfSignal::None
+        );
+        assert_eq!(
+            t.on_candle_close(&candle(99.5), &s, Price(1.0), params()),
+            LtfSignal::BrokenDown
+        );
+        assert!(t.broken);
+    }
+
+    #[test]
+    fn recovers_after_consecutive_closes_above_level() {
+        let mut t = LtfTracker::new();
+        let s = structure(100.0);
+        t.on_candle_close(&candle(99.0), &s, Price(1.0), params());
+
+        assert_eq!(
+            t.on_candle_close(&candle(100.5), &s, Price(1.0), params()),
+            LtfSignal::None
+        );
+        // серия прервалась
+        assert_eq!(
+            t.on_candle_close(&candle(99.8), &s, Price(1.0), params()),
+            LtfSignal::None
+        );
+        assert_eq!(
+            t.on_candle_close(&candle(100.5), &s, Price(1.0), params()),
+            LtfSignal::None
+        );
+        assert_eq!(
+            t.on_candle_close(&candle(100.6), &s, Price(1.0), params()),
+            LtfSignal::Recovered
+        );
+        assert!(!t.broken);
+    }
+}
@@ -18,6 +18,12 @@ pub struct PullbackTracker {
     pub triggered: bool,
 }
 
+impl Default for PullbackTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PullbackTracker {
     pub fn new() -> Self {
         Self {
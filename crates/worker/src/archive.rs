@@ -0,0 +1,220 @@
+//! Архивация старых run'ов: события — в `events.jsonl.gz`, файлы артефактов — в `.gz`
+//! в `<ARCHIVE_DIR>/<run_id>/`; строки `run_events` удаляются, пути артефактов переписываются
+//! на архив, run помечается `archived_at`. Включается `ARCHIVE_AFTER_DAYS`; несколько
+//! worker'ов делят run'ы через `FOR UPDATE SKIP LOCKED`.
+
+use std::{
+    env,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use flate2::{Compression, write::GzEncoder};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::resolve_artifact_path;
+
+/// Как часто искать run'ы старше порога
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Сколько run'ов архивировать за один проход: остальные — в следующий
+const ARCHIVE_BATCH: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct ArchivePolicy {
+    /// Завершённые раньше стольких дней назад уходят в архив
+    pub after_days: u32,
+    /// Относительно `WORKSPACE_ROOT`, если не абсолютный
+    pub dir: String,
+}
+
+impl ArchivePolicy {
+    /// `None` — `ARCHIVE_AFTER_DAYS` не задан, архивация выключена
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(raw) = env::var("ARCHIVE_AFTER_DAYS") else {
+            return Ok(None);
+        };
+        let after_days: u32 = raw
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid ARCHIVE_AFTER_DAYS: {}", raw))?;
+        let dir = env::var("ARCHIVE_DIR").unwrap_or_else(|_| "data/archive".to_string());
+        Ok(Some(Self {
+            after_days,
+            dir: dir.trim_end_matches('/').to_string(),
+        }))
+    }
+}
+
+pub fn spawn(pg: PgPool, policy: ArchivePolicy, workspace_root: String) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(ARCHIVE_INTERVAL);
+        loop {
+            tick.tick().await;
+            match archive_batch(&pg, &policy, &workspace_root).await {
+                Ok(0) => {}
+                Ok(n) => info!("archived {} runs older than {} days", n, policy.after_days),
+                Err(e) => warn!("run archival failed: {:#}", e),
+            }
+        }
+    });
+}
+
+async fn archive_batch(pg: &PgPool, policy: &ArchivePolicy, workspace_root: &str) -> Result<usize> {
+    let mut archived = 0;
+    while archived < ARCHIVE_BATCH && archive_next(pg, policy, workspace_root).await? {
+        archived += 1;
+    }
+    Ok(archived)
+}
+
+/// Один run под блокировкой строки; исходные файлы удаляются только после commit'а
+async fn archive_next(pg: &PgPool, policy: &ArchivePolicy, workspace_root: &str) -> Result<bool> {
+    let mut tx = pg.begin().await?;
+    let run_id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id
+        FROM runs
+        WHERE archived_at IS NULL
+            AND status IN ('completed', 'failed', 'cancelled')
+            AND ended_at < NOW() - make_interval(days => $1)
+        ORDER BY ended_at ASC
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(policy.after_days as i32)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(run_id) = run_id else {
+        return Ok(false);
+    };
+
+    let events: Vec<serde_json::Value> = sqlx::query_scalar(
+        r#"
+        SELECT jsonb_build_object(
+            'id', id, 'ts', ts, 'level', level, 'message', message, 'fields', fields
+        )
+        FROM run_events
+        WHERE run_id = $1
+        ORDER BY id ASC
+        "#,
+    )
+    .bind(run_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let artifacts: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, path FROM run_artifacts WHERE run_id = $1 ORDER BY id ASC")
+            .bind(run_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let run_dir = format!("{}/{}", policy.dir, run_id);
+    let archived = {
+        let root = workspace_root.to_string();
+        let run_dir = run_dir.clone();
+        tokio::task::spawn_blocking(move || write_archive(&root, &run_dir, &events, &artifacts))
+            .await??
+    };
+
+    for a in &archived {
+        sqlx::query("UPDATE run_artifacts SET path = $2 WHERE id = $1")
+            .bind(a.id)
+            .bind(&a.path)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("DELETE FROM run_events WHERE run_id = $1")
+        .bind(run_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE runs SET archived_at = NOW() WHERE id = $1")
+        .bind(run_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    for a in &archived {
+        if let Err(e) = std::fs::remove_file(&a.original) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!(
+                    "archived artifact {} not removed: {}",
+                    a.original.display(),
+                    e
+                );
+            }
+        }
+        // каталог run'а (`data/runs/<id>`) пропадает, когда в нём ничего не осталось
+        if let Some(parent) = a.original.parent() {
+            let _ = std::fs::remove_dir(parent);
+        }
+    }
+    info!("run {} archived to {}", run_id, run_dir);
+    Ok(true)
+}
+
+struct ArchivedArtifact {
+    id: i64,
+    /// Новый путь в `run_artifacts`: в том же виде, что `ARCHIVE_DIR`
+    path: String,
+    original: PathBuf,
+}
+
+/// Пишет `events.jsonl.gz` и сжатые копии существующих файлов артефактов; отсутствующие
+/// файлы пропускаются, их строки остаются как есть
+fn write_archive(
+    workspace_root: &str,
+    run_dir: &str,
+    events: &[serde_json::Value],
+    artifacts: &[(i64, String)],
+) -> Result<Vec<ArchivedArtifact>> {
+    let dir = resolve_artifact_path(workspace_root, run_dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("create {} failed", dir.display()))?;
+
+    let mut out = gzip_writer(&dir.join("events.jsonl.gz"))?;
+    for event in events {
+        serde_json::to_writer(&mut out, event)?;
+        out.write_all(b"\n")?;
+    }
+    out.into_inner()
+        .map_err(|e| e.into_error())?
+        .finish()?
+        .sync_all()?;
+
+    let mut archived = Vec::new();
+    for (id, raw) in artifacts {
+        let original = resolve_artifact_path(workspace_root, raw);
+        if !original.is_file() {
+            continue;
+        }
+        let file_name = original
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_else(|| id.to_string());
+        // id в имени: у артефактов из разных каталогов могут совпадать имена файлов
+        let name = format!("{}_{}.gz", id, file_name);
+        let mut out = gzip_writer(&dir.join(&name))?;
+        let mut src =
+            File::open(&original).with_context(|| format!("open {} failed", original.display()))?;
+        std::io::copy(&mut src, &mut out)?;
+        out.into_inner()
+            .map_err(|e| e.into_error())?
+            .finish()?
+            .sync_all()?;
+        archived.push(ArchivedArtifact {
+            id: *id,
+            path: format!("{}/{}", run_dir, name),
+            original,
+        });
+    }
+    Ok(archived)
+}
+
+fn gzip_writer(path: &Path) -> Result<BufWriter<GzEncoder<File>>> {
+    let file = File::create(path).with_context(|| format!("create {} failed", path.display()))?;
+    Ok(BufWriter::new(GzEncoder::new(file, Compression::default())))
+}
@@ -0,0 +1,71 @@
+//! Проверка engine бинаря перед запуском run'а: `--capabilities` бинаря (кэш до смены
+//! файла) сверяется с аргументами run'а.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use orchestrator_core::capabilities::{CAPABILITIES_FLAG, EngineCapabilities};
+use tokio::process::Command;
+
+/// Бинарь отвечает сразу, не читая данных
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct EngineCheck {
+    /// По пути бинаря: время изменения файла и его ответ
+    cache: Mutex<HashMap<PathBuf, (SystemTime, EngineCapabilities)>>,
+}
+
+impl EngineCheck {
+    /// `Err` — понятная причина, почему run не запустится этим бинарём
+    pub async fn verify(
+        &self,
+        bin: &str,
+        cli_args: &[String],
+    ) -> Result<EngineCapabilities, String> {
+        let caps = self.capabilities(Path::new(bin)).await?;
+        caps.check(cli_args).map_err(|e| e.to_string())?;
+        Ok(caps)
+    }
+
+    async fn capabilities(&self, bin: &Path) -> Result<EngineCapabilities, String> {
+        let modified = std::fs::metadata(bin)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("engine binary {} unavailable: {}", bin.display(), e))?;
+        if let Some((at, caps)) = self.cache.lock().unwrap().get(bin) {
+            if *at == modified {
+                return Ok(caps.clone());
+            }
+        }
+
+        let output = tokio::time::timeout(
+            PROBE_TIMEOUT,
+            Command::new(bin)
+                .arg(CAPABILITIES_FLAG)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| format!("{} {} timed out", bin.display(), CAPABILITIES_FLAG))?
+        .map_err(|e| format!("{} {} failed: {}", bin.display(), CAPABILITIES_FLAG, e))?;
+        let caps: EngineCapabilities = output
+            .status
+            .success()
+            .then(|| serde_json::from_slice(&output.stdout).ok())
+            .flatten()
+            .ok_or_else(|| {
+                format!(
+                    "{} does not report {}; rebuild engine binaries",
+                    bin.display(),
+                    CAPABILITIES_FLAG
+                )
+            })?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(bin.to_path_buf(), (modified, caps.clone()));
+        Ok(caps)
+    }
+}
@@ -1,36 +1,55 @@
+mod archive;
+mod engine_check;
+mod notify;
+mod queue;
+
 use std::{
+    collections::VecDeque,
     env,
     path::PathBuf,
     process::Stdio,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
-use orchestrator_core::models::{RUN_QUEUE_KEY, RunKind};
+use orchestrator_core::models::{
+    ArtifactKind, EventLevel, RUN_UPDATES_CHANNEL, RunKind, RunStatus, RunUpdate, run_cancel_key,
+};
+use orchestrator_core::artifact_store::ArtifactStore;
+use orchestrator_core::metrics::{ChartSnapshots, EquityPoint, MetricsPayload, TradePoint};
+use orchestrator_core::params::typed_cli_args;
+use orchestrator_core::telemetry;
+use redis::AsyncCommands;
+use redis::aio::MultiplexedConnection;
+use serde_json::json;
 use sqlx::PgPool;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Command,
+    task::JoinSet,
 };
-use tracing::{error, info};
+use tracing::{Instrument, Span, error, field, info, info_span, warn};
 use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "worker=info".into()),
-        )
-        .init();
+    let _telemetry = telemetry::init("worker", "worker=info");
 
     let database_url = env::var("DATABASE_URL").context("DATABASE_URL is required")?;
     let redis_url = env::var("REDIS_URL").context("REDIS_URL is required")?;
     let workspace_root = env::var("WORKSPACE_ROOT").unwrap_or_else(|_| "/app".to_string());
     let engine_bin_dir = env::var("ENGINE_BIN_DIR").unwrap_or_else(|_| "/usr/local/bin".to_string());
+    let artifact_store = ArtifactStore::from_env().context("artifact store config failed")?;
+    let archive_policy = archive::ArchivePolicy::from_env()?;
+    let notifier = notify::Notifier::from_env();
 
     let pg = PgPool::connect(&database_url).await?;
     sqlx::migrate!("../../migrations").run(&pg).await?;
+    if let Some(policy) = archive_policy {
+        info!("archiving runs older than {} days to {}", policy.after_days, policy.dir);
+        archive::spawn(pg.clone(), policy, workspace_root.clone());
+    }
 
     let redis = redis::Client::open(redis_url)?;
     let mut conn = redis
@@ -38,35 +57,224 @@ async fn main() -> Result<()> {
         .await
         .context("redis connection failed")?;
 
-    info!("worker started");
+    // имя в consumer group; стабильный WORKER_ID позволяет после рестарта сразу доделать свои run'ы
+    let consumer = env::var("WORKER_ID").unwrap_or_else(|_| {
+        format!(
+            "{}-{}",
+            env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string()),
+            std::process::id()
+        )
+    });
+    queue::ensure_groups(&mut conn).await?;
+    let moved = queue::migrate_legacy_lists(&mut conn).await?;
+    if moved > 0 {
+        info!("moved {} queued runs from legacy lists to streams", moved);
+    }
+    requeue_orphans(&pg, &mut conn).await?;
+    let mut entries: VecDeque<queue::QueueEntry> =
+        queue::own_pending(&mut conn, &consumer).await?.into();
+    let mut last_reclaim: Option<Instant> = None;
+
+    info!(
+        "worker {} started, artifact store {}, notifications {}",
+        consumer,
+        if artifact_store.is_some() { "s3" } else { "disabled" },
+        notifier.as_ref().map_or("disabled".to_string(), |n| n.targets().join("+"))
+    );
+    let worker = Arc::new(WorkerEnv {
+        consumer,
+        workspace_root,
+        engine_bin_dir,
+        artifact_store,
+        notifier,
+        engine_check: engine_check::EngineCheck::default(),
+    });
+    let consumer = worker.consumer.as_str();
+    // идущие live/paper run'ы: каждый в своей задаче, последовательный цикл их не ждёт
+    let mut long_runs = JoinSet::new();
 
     loop {
-        let resp: (String, String) = redis::cmd("BRPOP")
-            .arg(RUN_QUEUE_KEY)
-            .arg(0)
-            .query_async(&mut conn)
-            .await
-            .context("queue pop failed")?;
-
-        let run_id: Uuid = match resp.1.parse() {
-            Ok(v) => v,
+        while let Some(done) = long_runs.try_join_next() {
+            if let Err(e) = done {
+                error!("live/paper run task failed: {}", e);
+            }
+        }
+        if entries.is_empty() && last_reclaim.is_none_or(|t| t.elapsed() >= RECLAIM_INTERVAL) {
+            entries.extend(queue::reclaim_stale(&mut conn, consumer).await?);
+            if let Err(e) = requeue_orphans(&pg, &mut conn).await {
+                error!("requeue of lost queued runs failed: {}", e);
+            }
+            last_reclaim = Some(Instant::now());
+        }
+        if entries.is_empty() {
+            let new = queue::read_new(&mut conn, consumer)
+                .await
+                .context("queue read failed")?;
+            entries.extend(new);
+        }
+        let Some(entry) = entries.pop_front() else {
+            continue;
+        };
+        // перехваченную запись не запускаем и не подтверждаем: run идёт у другого worker'а
+        match queue::still_owned(&mut conn, &entry, consumer).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!(
+                    "queue entry {} in {} was claimed by another worker, skip",
+                    entry.id, entry.stream
+                );
+                continue;
+            }
             Err(e) => {
-                error!("invalid run id in queue '{}': {}", resp.1, e);
+                error!("ownership check of queue entry {} failed: {}", entry.id, e);
                 continue;
             }
-        };
+        }
+
+
+        // live/paper не завершаются сами: в очереди за ними backtest'ы ждали бы без конца
+        if is_long_running_entry(&pg, &entry).await {
+            let (pg, mut conn, worker) = (pg.clone(), conn.clone(), worker.clone());
+            long_runs.spawn(async move { handle_entry(&pg, &mut conn, &entry, &worker).await });
+            continue;
+        }
+        handle_entry(&pg, &mut conn, &entry, &worker).await;
+    }
+}
+
+/// Run записи — live/paper; ошибки чтения оставляют запись последовательному циклу,
+/// там `process_run` их и покажет
+async fn is_long_running_entry(pg: &PgPool, entry: &queue::QueueEntry) -> bool {
+    let Some(run_id) = entry.run_id else {
+        return false;
+    };
+    let kind: Option<String> = sqlx::query_scalar("SELECT kind FROM runs WHERE id = $1")
+        .bind(run_id)
+        .fetch_optional(pg)
+        .await
+        .ok()
+        .flatten();
+    kind.and_then(|k| parse_run_kind(&k).ok())
+        .is_some_and(RunKind::is_long_running)
+}
 
-        if let Err(e) = process_run(&pg, run_id, &workspace_root, &engine_bin_dir).await {
+/// Выполняет run записи, шлёт уведомление и подтверждает запись
+async fn handle_entry(
+    pg: &PgPool,
+    conn: &mut MultiplexedConnection,
+    entry: &queue::QueueEntry,
+    worker: &WorkerEnv,
+) {
+    if let Some(run_id) = entry.run_id {
+        let processed = process_run(pg, conn, entry, run_id, worker).await;
+        if let Err(e) = processed {
             error!("run {} failed: {}", run_id, e);
-            let _ = mark_failed(&pg, run_id, None, &format!("{}", e)).await;
+            let _ = mark_failed(pg, run_id, None, &format!("{}", e)).await;
         }
+        if let Some(notifier) = &worker.notifier {
+            notifier.run_finished(pg, run_id).await;
+        }
+    } else {
+        error!("queue entry {} in {} has no valid run id", entry.id, entry.stream);
+    }
+    // at-least-once: подтверждение только после завершения run'а
+    if let Err(e) = queue::ack(conn, entry).await {
+        error!("ack of queue entry {} failed: {}", entry.id, e);
     }
 }
 
-async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin_dir: &str) -> Result<()> {
+/// Настройки worker'а, общие для всех run'ов
+struct WorkerEnv {
+    consumer: String,
+    workspace_root: String,
+    engine_bin_dir: String,
+    /// `None` — артефакты остаются только на диске
+    artifact_store: Option<ArtifactStore>,
+    /// `None` — Telegram и Slack не настроены
+    notifier: Option<notify::Notifier>,
+    engine_check: engine_check::EngineCheck,
+}
+
+/// Как часто искать записи упавших worker'ов и потерянные queued run'ы
+const RECLAIM_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Queued run'ы без записи в очереди (XADD в api не прошёл после коммита) — снова в stream;
+/// если два worker'а поставят один run дважды, второй увидит не queued статус и пропустит его
+async fn requeue_orphans(pg: &PgPool, conn: &mut MultiplexedConnection) -> Result<usize> {
+    let queued: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, kind FROM runs WHERE status = 'queued' AND created_at < NOW() - make_interval(secs => $1)",
+    )
+    .bind(queue::ORPHAN_AFTER.as_secs_f64())
+    .fetch_all(pg)
+    .await?;
+    if queued.is_empty() {
+        return Ok(0);
+    }
+    let in_streams = queue::run_ids_in_streams(conn).await?;
+    let lost = queue::orphans(&queued, &in_streams);
+    for (run_id, kind) in &lost {
+        let stream = parse_run_kind(kind)?.priority().stream_key();
+        queue::enqueue(conn, stream, *run_id).await?;
+        warn!("queued run {} had no queue entry, requeued to {}", run_id, stream);
+    }
+    Ok(lost.len())
+}
+
+/// Как часто проверять флаг отмены
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Сколько ждать штатного завершения после SIGTERM, потом SIGKILL
+const CANCEL_GRACE: Duration = Duration::from_secs(30);
+/// Пауза перед возвратом в очередь run'а с незавершёнными зависимостями
+const DEPENDENCY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+enum DependenciesState {
+    Ready,
+    Waiting,
+    /// Первая зависимость, которая не завершится успешно, и её статус
+    Failed(Uuid, String),
+}
+
+async fn dependencies_state(pg: &PgPool, depends_on: &[Uuid]) -> Result<DependenciesState> {
+    if depends_on.is_empty() {
+        return Ok(DependenciesState::Ready);
+    }
+    let rows: Vec<(Uuid, String)> = sqlx::query_as("SELECT id, status FROM runs WHERE id = ANY($1)")
+        .bind(depends_on)
+        .fetch_all(pg)
+        .await?;
+    let mut waiting = false;
+    for dep in depends_on {
+        match rows.iter().find(|(id, _)| id == dep).map(|(_, s)| s.as_str()) {
+            Some("completed") => {}
+            Some("queued") | Some("running") => waiting = true,
+            Some(status) => return Ok(DependenciesState::Failed(*dep, status.to_string())),
+            None => return Ok(DependenciesState::Failed(*dep, "missing".to_string())),
+        }
+    }
+    Ok(if waiting {
+        DependenciesState::Waiting
+    } else {
+        DependenciesState::Ready
+    })
+}
+
+#[tracing::instrument(
+    name = "run",
+    skip_all,
+    fields(run_id = %run_id, kind = field::Empty, queue_wait_ms = field::Empty)
+)]
+async fn process_run(
+    pg: &PgPool,
+    redis: &mut MultiplexedConnection,
+    entry: &queue::QueueEntry,
+    run_id: Uuid,
+    worker: &WorkerEnv,
+) -> Result<()> {
+    let consumer = worker.consumer.as_str();
+    let workspace_root = worker.workspace_root.as_str();
     let row = sqlx::query_as::<_, DbRunAndParams>(
         r#"
-        SELECT r.id, r.kind, p.cli_args
+        SELECT r.id, r.kind, r.status, r.created_at, r.depends_on, p.cli_args, p.params
         FROM runs r
         JOIN run_params p ON p.run_id = r.id
         WHERE r.id = $1
@@ -74,16 +282,119 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
     )
     .bind(run_id)
     .fetch_optional(pg)
+    .instrument(info_span!("claim", run_id = %run_id))
     .await?;
 
     let Some(row) = row else {
         anyhow::bail!("run {} not found", run_id);
     };
 
+    // запись упавшего worker'а: run прерван посередине и запускается заново
+    if entry.recovered && row.status == "running" {
+        // engine live/paper мог пережить worker и ещё торговать: второй на том же аккаунте
+        // не запускаем, перезапуск — вручную
+        if parse_run_kind(&row.kind)?.is_long_running() {
+            mark_failed(
+                pg,
+                run_id,
+                None,
+                "worker lost: live/paper run is not restarted automatically, restart it manually",
+            )
+            .await?;
+            return Ok(());
+        }
+        sqlx::query("UPDATE runs SET status = 'queued' WHERE id = $1 AND status = 'running'")
+            .bind(run_id)
+            .execute(pg)
+            .await?;
+        notify_update(pg, RunUpdate::status(run_id, RunStatus::Queued)).await;
+        append_event(
+            pg,
+            run_id,
+            EventLevel::Warn,
+            "worker lost: restarting run",
+            json!({"event": "status", "status": "queued", "reason": "worker_lost"}),
+        )
+        .await?;
+    } else if row.status != "queued" {
+        // отменён, пока стоял в очереди, или уже завершён, но запись не успели подтвердить
+        info!("skip run {} with status {}", run_id, row.status);
+        return Ok(());
+    }
+
     let run_kind = parse_run_kind(&row.kind)?;
-    let cli_args: Vec<String> = serde_json::from_value(row.cli_args)
+    // от постановки в очередь (с учётом возвратов из-за зависимостей) до взятия worker'ом
+    let queue_wait = chrono::Utc::now() - row.created_at;
+    Span::current()
+        .record("kind", row.kind.as_str())
+        .record("queue_wait_ms", queue_wait.num_milliseconds());
+
+    // зависимости (например, data_download) ещё идут — в конец очереди; упали — падает и этот
+    match dependencies_state(pg, &row.depends_on).await? {
+        DependenciesState::Ready => {}
+        DependenciesState::Waiting => {
+            tokio::time::sleep(DEPENDENCY_POLL_INTERVAL).await;
+            queue::enqueue(redis, run_kind.priority().stream_key(), run_id)
+                .await
+                .context("requeue failed")?;
+            return Ok(());
+        }
+        DependenciesState::Failed(dep, status) => {
+            mark_failed(pg, run_id, None, &format!("dependency {} is {}", dep, status)).await?;
+            return Ok(());
+        }
+    }
+
+    let mut cli_args: Vec<String> = serde_json::from_value(row.cli_args)
         .context("failed to decode cli_args for run")?;
 
+    // типизированные параметры — первыми: флаги из cli_args их перекрывают
+    if let Some(params) = &row.params {
+        match typed_cli_args(run_kind, params) {
+            Ok(args) => {
+                cli_args.splice(0..0, args);
+            }
+            Err(e) => {
+                mark_failed(pg, run_id, None, &format!("invalid params: {}", e)).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    // live/paper — тот же бинарь engine, режим задаёт kind (если не указан явно)
+    if let Some(mode) = run_kind.engine_mode() {
+        if !cli_args.iter().any(|a| a == "--mode" || a.starts_with("--mode=")) {
+            cli_args.splice(0..0, ["--mode".to_string(), mode.to_string()]);
+        }
+    }
+
+    // backtest'ы пишут JSON отчёт, метрики берутся из него, а не из stdout
+    if !run_kind.is_long_running()
+        && !cli_args.iter().any(|a| a == "--report-out" || a.starts_with("--report-out="))
+    {
+        cli_args.push("--report-out".to_string());
+        cli_args.push(format!("data/runs/{}/report.json", run_id));
+    }
+
+    // progress bar только мешает в логах: прогресс идёт строками `progress:`
+    if run_kind.reports_progress() && !cli_args.iter().any(|a| a == "--quiet") {
+        cli_args.push("--quiet".to_string());
+    }
+
+    let engine_bin_path = format!(
+        "{}/{}",
+        worker.engine_bin_dir.trim_end_matches('/'),
+        run_kind.engine_bin()
+    );
+    // несовместимый бинарь — сразу failed, а не падение на неизвестном флаге посреди пачки
+    let caps = match worker.engine_check.verify(&engine_bin_path, &cli_args).await {
+        Ok(caps) => caps,
+        Err(e) => {
+            mark_failed(pg, run_id, None, &format!("incompatible engine: {}", e)).await?;
+            return Ok(());
+        }
+    };
+
     sqlx::query(
         r#"
         UPDATE runs
@@ -94,18 +405,53 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
     .bind(run_id)
     .execute(pg)
     .await?;
+    notify_update(pg, RunUpdate::status(run_id, RunStatus::Running)).await;
 
-    append_event(pg, run_id, "info", "started worker execution").await?;
+    append_event(
+        pg,
+        run_id,
+        EventLevel::Info,
+        "started worker execution",
+        json!({
+            "event": "status",
+            "status": "running",
+            "protocol_version": caps.protocol_version,
+            "strategy_version": caps.strategy_version,
+        }),
+    )
+    .await?;
 
-    let engine_bin_path = format!("{}/{}", engine_bin_dir.trim_end_matches('/'), run_kind.engine_bin());
     let mut cmd = Command::new(&engine_bin_path);
     cmd.args(&cli_args)
         .current_dir(workspace_root)
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // своя группа процессов: сигналы worker'а и терминала не доходят до engine напрямую,
+    // SIGTERM/SIGKILL уходят всей группе; на Linux engine получает SIGTERM, если worker умер
+    #[cfg(unix)]
+    {
+        cmd.process_group(0);
+        #[cfg(target_os = "linux")]
+        // SAFETY: в pre_exec только prctl(2), он async-signal-safe
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    // свечи backtest'ов — через общий Postgres-кэш, чтобы параллельные прогоны не качали их заново
+    if env::var_os("CANDLE_STORE_URL").is_none() {
+        if let Ok(url) = env::var("DATABASE_URL") {
+            cmd.env("CANDLE_STORE_URL", url);
+        }
+    }
 
-    let mut child = cmd
-        .spawn()
+    let mut child = info_span!("spawn", run_id = %run_id, bin = %engine_bin_path)
+        .in_scope(|| cmd.spawn())
         .with_context(|| format!("failed to spawn backtest process: {}", engine_bin_path))?;
     let stdout = child.stdout.take().context("stdout unavailable")?;
     let stderr = child.stderr.take().context("stderr unavailable")?;
@@ -114,15 +460,71 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
     let mut err_reader = BufReader::new(stderr).lines();
     let mut metrics = serde_json::Map::<String, serde_json::Value>::new();
     let mut artifacts: Vec<ArtifactEntry> = Vec::new();
+    // stdout и stderr процесса целиком — артефакт `log`, события в БД его не заменяют
+    let log_path = format!("data/runs/{}/run.log", run_id);
+    let mut log = open_run_log(&resolve_artifact_path(workspace_root, &log_path));
+    if log.is_some() {
+        artifacts.push(ArtifactEntry {
+            name: "log".to_string(),
+            path: log_path,
+        });
+    }
     let mut last_progress_persist = Instant::now();
+    let mut cancel_check = tokio::time::interval(CANCEL_POLL_INTERVAL);
+    let mut heartbeat = tokio::time::interval(queue::HEARTBEAT_INTERVAL);
+    let mut cancelling = false;
+    let mut kill_at: Option<tokio::time::Instant> = None;
+    let timeout = run_kind.default_timeout();
+    let deadline = timeout.map(|t| tokio::time::Instant::now() + t);
+    let mut timed_out = false;
 
     loop {
         tokio::select! {
+            _ = heartbeat.tick() => {
+                if let Err(e) = queue::heartbeat(redis, entry, consumer).await {
+                    warn!("queue heartbeat for run {} failed: {}", run_id, e);
+                }
+            }
+            _ = cancel_check.tick(), if !cancelling => {
+                let requested: bool = redis.exists(run_cancel_key(run_id)).await.unwrap_or(false);
+                if requested {
+                    append_event(pg, run_id, EventLevel::Info, "cancel: sending SIGTERM", json!({"event": "cancel"})).await?;
+                    terminate(&mut child);
+                    cancelling = true;
+                    kill_at = Some(tokio::time::Instant::now() + CANCEL_GRACE);
+                }
+            }
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() && !cancelling => {
+                let secs = timeout.map_or(0, |t| t.as_secs());
+                append_event(
+                    pg,
+                    run_id,
+                    EventLevel::Error,
+                    &format!("timeout: {}s exceeded, sending SIGTERM", secs),
+                    json!({"event": "timeout", "timeout_s": secs}),
+                ).await?;
+                terminate(&mut child);
+                cancelling = true;
+                timed_out = true;
+                kill_at = Some(tokio::time::Instant::now() + CANCEL_GRACE);
+            }
+            _ = tokio::time::sleep_until(kill_at.unwrap_or_else(tokio::time::Instant::now)), if kill_at.is_some() => {
+                append_event(
+                    pg,
+                    run_id,
+                    EventLevel::Error,
+                    "cancel: grace period expired, killing process",
+                    json!({"event": "kill"}),
+                ).await?;
+                signal_group(&mut child, libc::SIGKILL);
+                kill_at = None;
+            }
             out = out_reader.next_line() => {
                 match out {
                     Ok(Some(line)) => {
+                        write_log(&mut log, &line);
                         collect_results_from_line(&line, &mut metrics, &mut artifacts);
-                        append_event(pg, run_id, "info", &line).await?;
+                        append_line_event(pg, run_id, &line, EventLevel::Info).await?;
                         persist_progress_if_due(
                             pg,
                             run_id,
@@ -134,15 +536,16 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
                     }
                     Ok(None) => {}
                     Err(e) => {
-                        append_event(pg, run_id, "error", &format!("stdout read error: {}", e)).await?;
+                        append_event(pg, run_id, EventLevel::Error, &format!("stdout read error: {}", e), json!({})).await?;
                     }
                 }
             }
             err = err_reader.next_line() => {
                 match err {
                     Ok(Some(line)) => {
+                        write_log(&mut log, &line);
                         collect_results_from_line(&line, &mut metrics, &mut artifacts);
-                        append_event(pg, run_id, "error", &line).await?;
+                        append_line_event(pg, run_id, &line, EventLevel::Error).await?;
                         persist_progress_if_due(
                             pg,
                             run_id,
@@ -154,7 +557,7 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
                     }
                     Ok(None) => {}
                     Err(e) => {
-                        append_event(pg, run_id, "error", &format!("stderr read error: {}", e)).await?;
+                        append_event(pg, run_id, EventLevel::Error, &format!("stderr read error: {}", e), json!({})).await?;
                     }
                 }
             }
@@ -165,16 +568,47 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
                 // Process may exit before we consume buffered stdout/stderr lines.
                 // Drain remaining output so metrics/artifacts are not lost.
                 while let Ok(Some(line)) = out_reader.next_line().await {
+                    write_log(&mut log, &line);
                     collect_results_from_line(&line, &mut metrics, &mut artifacts);
-                    append_event(pg, run_id, "info", &line).await?;
+                    append_line_event(pg, run_id, &line, EventLevel::Info).await?;
                 }
                 while let Ok(Some(line)) = err_reader.next_line().await {
+                    write_log(&mut log, &line);
                     collect_results_from_line(&line, &mut metrics, &mut artifacts);
-                    append_event(pg, run_id, "error", &line).await?;
+                    append_line_event(pg, run_id, &line, EventLevel::Error).await?;
                 }
 
-                if status.success() {
-                    persist_results(pg, run_id, workspace_root, &metrics, &artifacts).await?;
+                if timed_out {
+                    persist_results(pg, run_id, workspace_root, &metrics, &artifacts, true).await?;
+                    upload_artifacts(pg, worker, run_id, &artifacts).await;
+                    let secs = timeout.map_or(0, |t| t.as_secs());
+                    mark_failed(pg, run_id, Some(code), &format!("timed out after {}s", secs)).await?;
+                } else if cancelling {
+                    persist_results(pg, run_id, workspace_root, &metrics, &artifacts, true).await?;
+                    upload_artifacts(pg, worker, run_id, &artifacts).await;
+                    sqlx::query(
+                        r#"
+                        UPDATE runs
+                        SET status = 'cancelled', ended_at = NOW(), exit_code = $2
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(run_id)
+                    .bind(code)
+                    .execute(pg)
+                    .await?;
+                    notify_update(pg, RunUpdate::status(run_id, RunStatus::Cancelled)).await;
+                    let _: Result<(), _> = redis.del(run_cancel_key(run_id)).await;
+                    append_event(
+                        pg,
+                        run_id,
+                        EventLevel::Info,
+                        "run cancelled",
+                        json!({"event": "status", "status": "cancelled", "exit_code": code}),
+                    ).await?;
+                } else if status.success() {
+                    persist_results(pg, run_id, workspace_root, &metrics, &artifacts, true).await?;
+                    upload_artifacts(pg, worker, run_id, &artifacts).await;
                     sqlx::query(
                         r#"
                         UPDATE runs
@@ -186,7 +620,14 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
                     .bind(code)
                     .execute(pg)
                     .await?;
-                    append_event(pg, run_id, "info", "run completed").await?;
+                    notify_update(pg, RunUpdate::status(run_id, RunStatus::Completed)).await;
+                    append_event(
+                        pg,
+                        run_id,
+                        EventLevel::Info,
+                        "run completed",
+                        json!({"event": "status", "status": "completed", "exit_code": code}),
+                    ).await?;
                 } else {
                     mark_failed(pg, run_id, Some(code), "engine process exited with failure").await?;
                 }
@@ -198,6 +639,41 @@ async fn process_run(pg: &PgPool, run_id: Uuid, workspace_root: &str, engine_bin
     Ok(())
 }
 
+fn open_run_log(path: &PathBuf) -> Option<std::fs::File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok()?;
+    }
+    std::fs::File::create(path).ok()
+}
+
+fn write_log(log: &mut Option<std::fs::File>, line: &str) {
+    use std::io::Write;
+
+    if let Some(f) = log.as_mut() {
+        if writeln!(f, "{}", line).is_err() {
+            *log = None;
+        }
+    }
+}
+
+/// SIGTERM: engine снимает ордера, сохраняет состояние и выходит сам
+fn terminate(child: &mut tokio::process::Child) {
+    signal_group(child, libc::SIGTERM);
+}
+
+/// Сигнал группе процессов engine (`process_group(0)` при запуске: pgid = pid)
+fn signal_group(child: &mut tokio::process::Child, sig: libc::c_int) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: обычный kill(2) по группе дочернего процесса
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), sig);
+        }
+        return;
+    }
+    let _ = child.start_kill();
+}
+
 const LIVE_PERSIST_INTERVAL: Duration = Duration::from_secs(2);
 
 async fn persist_progress_if_due(
@@ -212,31 +688,74 @@ async fn persist_progress_if_due(
         return Ok(());
     }
 
-    persist_results(pg, run_id, workspace_root, metrics, artifacts).await?;
+    persist_results(pg, run_id, workspace_root, metrics, artifacts, false).await?;
     *last_persist = Instant::now();
     Ok(())
 }
 
 #[derive(Debug, Clone)]
 struct ArtifactEntry {
-    kind: String,
+    /// Имя из строки `artifacts:` (`equity_csv`, `top1_fills_csv`, ...)
+    name: String,
     path: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-struct EquityPoint {
-    ts: i64,
-    equity: f64,
-    close: Option<f64>,
+impl ArtifactEntry {
+    fn kind(&self) -> ArtifactKind {
+        ArtifactKind::from_name(&self.name)
+    }
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-struct TradePoint {
-    ts: i64,
-    side: String,
-    price: f64,
-    qty: Option<f64>,
-    pnl: Option<f64>,
+/// Размер, строки CSV и sha256 файла артефакта
+#[derive(Debug, Default)]
+struct ArtifactMeta {
+    size_bytes: Option<i64>,
+    row_count: Option<i64>,
+    checksum: Option<String>,
+}
+
+/// `full = false` — только размер: промежуточные сохранения идут каждые пару секунд,
+/// а capture live run'а растёт без конца
+fn artifact_meta(path: &PathBuf, full: bool) -> ArtifactMeta {
+    let Ok(md) = std::fs::metadata(path) else {
+        return ArtifactMeta::default();
+    };
+    let mut meta = ArtifactMeta {
+        size_bytes: Some(md.len() as i64),
+        ..ArtifactMeta::default()
+    };
+    if !full {
+        return meta;
+    }
+    if path.extension().is_some_and(|e| e == "csv") {
+        meta.row_count = csv::Reader::from_path(path)
+            .ok()
+            .map(|mut r| r.records().filter(|r| r.is_ok()).count() as i64);
+    }
+    meta.checksum = file_sha256(path).ok();
+    meta
+}
+
+fn file_sha256(path: &PathBuf) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(format!("sha256:{}", hex))
 }
 
 fn collect_results_from_line(
@@ -247,15 +766,28 @@ fn collect_results_from_line(
     if let Some(rest) = line.strip_prefix("artifacts:") {
         for token in rest.split_whitespace() {
             if let Some((k, v)) = token.split_once('=') {
-                let kind = k.trim().to_string();
+                let name = k.trim().to_string();
                 let path = v.trim().trim_end_matches(',').to_string();
-                if !kind.is_empty() && !path.is_empty() {
-                    artifacts.push(ArtifactEntry { kind, path });
+                if !name.is_empty() && !path.is_empty() {
+                    artifacts.retain(|a| a.name != name);
+                    artifacts.push(ArtifactEntry { name, path });
                 }
             }
         }
     }
 
+    if let Some(rest) = line.strip_prefix("progress:") {
+        let mut progress = serde_json::Map::new();
+        collect_tokens(rest, &mut progress);
+        metrics.insert("progress".to_string(), serde_json::Value::Object(progress));
+        return;
+    }
+
+    collect_tokens(line, metrics);
+}
+
+/// `key=value` токены строки; числа (в т.ч. с `%`) сохраняются как числа
+fn collect_tokens(line: &str, metrics: &mut serde_json::Map<String, serde_json::Value>) {
     for token in line
         .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
         .filter(|s| !s.is_empty())
@@ -278,18 +810,34 @@ fn collect_results_from_line(
     }
 }
 
+#[tracing::instrument(
+    name = "persist",
+    skip_all,
+    fields(run_id = %run_id, is_final = full_meta)
+)]
 async fn persist_results(
     pg: &PgPool,
     run_id: Uuid,
     workspace_root: &str,
     metrics: &serde_json::Map<String, serde_json::Value>,
     artifacts: &[ArtifactEntry],
+    full_meta: bool,
 ) -> Result<()> {
-    let mut payload_map = metrics.clone();
-    append_chart_snapshots(workspace_root, artifacts, &mut payload_map);
+    let mut payload_map =
+        load_report_metrics(workspace_root, artifacts).unwrap_or_else(|| metrics.clone());
+    // прогресс из stdout остаётся и рядом с метриками отчёта
+    if let Some(progress) = metrics.get("progress") {
+        payload_map
+            .entry("progress")
+            .or_insert_with(|| progress.clone());
+    }
 
     if !payload_map.is_empty() {
-        let payload = serde_json::Value::Object(payload_map);
+        let mut payload = MetricsPayload::from_flat(payload_map);
+        // в историю — без графиков: они пересчитываются из CSV и есть в последнем payload
+        let snapshot = serde_json::to_value(&payload)?;
+        payload.charts = chart_snapshots(workspace_root, artifacts);
+        let payload = serde_json::to_value(&payload)?;
         sqlx::query(
             r#"
             INSERT INTO run_metrics (run_id, payload, updated_at)
@@ -302,6 +850,8 @@ async fn persist_results(
         .bind(payload)
         .execute(pg)
         .await?;
+        append_metrics_snapshot(pg, run_id, snapshot, full_meta).await?;
+        notify_update(pg, RunUpdate::metrics(run_id)).await;
     }
 
     if !artifacts.is_empty() {
@@ -311,15 +861,20 @@ async fn persist_results(
             .await?;
 
         for a in artifacts {
+            let meta = artifact_meta(&resolve_artifact_path(workspace_root, &a.path), full_meta);
             sqlx::query(
                 r#"
-                INSERT INTO run_artifacts (run_id, kind, path, created_at)
-                VALUES ($1, $2, $3, NOW())
+                INSERT INTO run_artifacts (run_id, kind, name, path, size_bytes, row_count, checksum, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
                 "#,
             )
             .bind(run_id)
-            .bind(&a.kind)
+            .bind(a.kind().as_str())
+            .bind(&a.name)
             .bind(&a.path)
+            .bind(meta.size_bytes)
+            .bind(meta.row_count)
+            .bind(meta.checksum)
             .execute(pg)
             .await?;
         }
@@ -328,28 +883,124 @@ async fn persist_results(
     Ok(())
 }
 
-fn append_chart_snapshots(
-    workspace_root: &str,
+/// Финальные артефакты — в S3/MinIO, ключи — в `run_artifacts.object_key`; ошибка загрузки
+/// не валит run: артефакт остаётся доступен по пути на диске
+#[tracing::instrument(name = "upload_artifacts", skip_all, fields(run_id = %run_id))]
+async fn upload_artifacts(
+    pg: &PgPool,
+    worker: &WorkerEnv,
+    run_id: Uuid,
     artifacts: &[ArtifactEntry],
-    payload: &mut serde_json::Map<String, serde_json::Value>,
 ) {
-    let equity_artifact = artifacts.iter().find(|a| a.kind.contains("equity"));
+    let Some(store) = &worker.artifact_store else {
+        return;
+    };
+    for a in artifacts {
+        let path = resolve_artifact_path(&worker.workspace_root, &a.path);
+        if !path.is_file() {
+            continue;
+        }
+        let key = store.object_key(run_id, &a.name, &path);
+        if let Err(e) = store.upload(&key, &path).await {
+            warn!("upload of artifact {} for run {} failed: {}", a.name, run_id, e);
+            continue;
+        }
+        let updated = sqlx::query(
+            "UPDATE run_artifacts SET object_key = $3 WHERE run_id = $1 AND name = $2",
+        )
+        .bind(run_id)
+        .bind(&a.name)
+        .bind(&key)
+        .execute(pg)
+        .await;
+        if let Err(e) = updated {
+            warn!("object key of artifact {} for run {} not saved: {}", a.name, run_id, e);
+        }
+    }
+}
+
+const SNAPSHOT_INTERVAL_SECS: f64 = 30.0;
+
+/// Снимок в `run_metrics_snapshots`: промежуточные — не чаще `SNAPSHOT_INTERVAL_SECS`,
+/// повтор последнего payload не пишется
+async fn append_metrics_snapshot(
+    pg: &PgPool,
+    run_id: Uuid,
+    payload: serde_json::Value,
+    is_final: bool,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO run_metrics_snapshots (run_id, ts, is_final, payload)
+        SELECT $1, NOW(), $3, $2
+        WHERE NOT EXISTS (
+            SELECT 1
+            FROM (
+                SELECT ts, payload
+                FROM run_metrics_snapshots
+                WHERE run_id = $1
+                ORDER BY id DESC
+                LIMIT 1
+            ) last
+            WHERE last.payload = $2
+                OR (NOT $3 AND last.ts > NOW() - make_interval(secs => $4))
+        )
+        "#,
+    )
+    .bind(run_id)
+    .bind(payload)
+    .bind(is_final)
+    .bind(SNAPSHOT_INTERVAL_SECS)
+    .execute(pg)
+    .await?;
+    Ok(())
+}
+
+/// Метрики, издержки и помесячная разбивка из артефакта `report_json`; `None` — отчёта нет,
+/// остаются значения, наскрапленные из stdout (live/paper)
+fn load_report_metrics(
+    workspace_root: &str,
+    artifacts: &[ArtifactEntry],
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    let a = artifacts.iter().find(|a| a.name == "report_json")?;
+    let path = resolve_artifact_path(workspace_root, &a.path);
+    let raw = std::fs::read_to_string(&path).ok()?;
+    let mut report: serde_json::Value = serde_json::from_str(&raw).ok()?;
+
+    let mut out = match report.get_mut("metrics").map(serde_json::Value::take) {
+        Some(serde_json::Value::Object(m)) => m,
+        _ => return None,
+    };
+    for key in ["costs", "monthly"] {
+        if let Some(value) = report.get_mut(key).map(serde_json::Value::take) {
+            if !value.is_null() {
+                out.insert(key.to_string(), value);
+            }
+        }
+    }
+    Some(out)
+}
+
+fn chart_snapshots(workspace_root: &str, artifacts: &[ArtifactEntry]) -> ChartSnapshots {
+    let mut charts = ChartSnapshots::default();
+    let equity_artifact = artifacts.iter().find(|a| a.kind() == ArtifactKind::EquityCsv);
     if let Some(a) = equity_artifact {
         let path = resolve_artifact_path(workspace_root, &a.path);
         if let Ok(points) = read_equity_points(&path, 800) {
-            payload.insert("chart_equity".to_string(), serde_json::json!(points));
+            charts.equity = points;
         }
     }
 
     let trade_artifact = artifacts
         .iter()
-        .find(|a| a.kind.contains("fills") || a.kind.contains("trades"));
+        .find(|a| a.kind() == ArtifactKind::FillsCsv && !a.name.contains("round_trips"));
     if let Some(a) = trade_artifact {
         let path = resolve_artifact_path(workspace_root, &a.path);
         if let Ok(points) = read_trade_points(&path, 1200) {
-            payload.insert("chart_trades".to_string(), serde_json::json!(points));
+            charts.trades = points;
         }
     }
+    charts
 }
 
 fn resolve_artifact_path(workspace_root: &str, raw: &str) -> PathBuf {
@@ -462,21 +1113,91 @@ fn read_trade_points(path: &PathBuf, max_points: usize) -> Result<Vec<TradePoint
     Ok(sample_evenly(&points, max_points))
 }
 
-async fn append_event(pg: &PgPool, run_id: Uuid, level: &str, message: &str) -> Result<()> {
+async fn append_event(
+    pg: &PgPool,
+    run_id: Uuid,
+    level: EventLevel,
+    message: &str,
+    fields: serde_json::Value,
+) -> Result<()> {
     sqlx::query(
         r#"
-        INSERT INTO run_events (run_id, ts, level, message)
-        VALUES ($1, NOW(), $2, $3)
+        INSERT INTO run_events (run_id, ts, level, message, fields)
+        VALUES ($1, NOW(), $2, $3, $4)
         "#,
     )
     .bind(run_id)
-    .bind(level)
+    .bind(level.as_str())
     .bind(message)
+    .bind(fields)
     .execute(pg)
     .await?;
     Ok(())
 }
 
+/// NOTIFY для SSE подписчиков API; ошибка не роняет run — дашборд догонит опросом
+async fn notify_update(pg: &PgPool, update: RunUpdate) {
+    let Ok(payload) = serde_json::to_string(&update) else {
+        return;
+    };
+    let sent = sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(RUN_UPDATES_CHANNEL)
+        .bind(payload)
+        .execute(pg)
+        .await;
+    if let Err(e) = sent {
+        warn!("notify for run {} failed: {}", update.run_id, e);
+    }
+}
+
+async fn append_line_event(
+    pg: &PgPool,
+    run_id: Uuid,
+    line: &str,
+    default_level: EventLevel,
+) -> Result<()> {
+    let (level, fields) = line_event(line, default_level);
+    append_event(pg, run_id, level, line, fields).await
+}
+
+/// Событие по строке процесса: `progress:`, `artifacts:` и `key=value` метрики — в `fields`
+/// с `event` = `progress` / `artifacts` / `metrics`; у логов tracing (`WARN`, `ERROR`, ...)
+/// уровень берётся из строки, а поля помечаются `event` = `log`
+fn line_event(line: &str, default_level: EventLevel) -> (EventLevel, serde_json::Value) {
+    let mut fields = serde_json::Map::new();
+    let (level, event) = if let Some(rest) = line.strip_prefix("progress:") {
+        collect_tokens(rest, &mut fields);
+        (EventLevel::Info, "progress")
+    } else if let Some(rest) = line.strip_prefix("artifacts:") {
+        collect_tokens(rest, &mut fields);
+        (EventLevel::Info, "artifacts")
+    } else if let Some(level) = tracing_level(line) {
+        collect_tokens(line, &mut fields);
+        (level, "log")
+    } else {
+        collect_tokens(line, &mut fields);
+        (default_level, "metrics")
+    };
+    if !fields.is_empty() {
+        fields.insert("event".to_string(), json!(event));
+    }
+    (level, serde_json::Value::Object(fields))
+}
+
+/// Уровень строки лога tracing (`<время>  WARN engine: ...`), в т.ч. с ANSI-цветами
+fn tracing_level(line: &str) -> Option<EventLevel> {
+    line.split_whitespace().take(3).find_map(|token| {
+        let word: String = token.chars().filter(|c| c.is_ascii_uppercase()).collect();
+        match word.as_str() {
+            "ERROR" => Some(EventLevel::Error),
+            "WARN" => Some(EventLevel::Warn),
+            "INFO" => Some(EventLevel::Info),
+            "DEBUG" | "TRACE" => Some(EventLevel::Debug),
+            _ => None,
+        }
+    })
+}
+
 async fn mark_failed(pg: &PgPool, run_id: Uuid, code: Option<i32>, error: &str) -> Result<()> {
     sqlx::query(
         r#"
@@ -490,7 +1211,15 @@ async fn mark_failed(pg: &PgPool, run_id: Uuid, code: Option<i32>, error: &str)
     .bind(error)
     .execute(pg)
     .await?;
-    append_event(pg, run_id, "error", error).await?;
+    notify_update(pg, RunUpdate::status(run_id, RunStatus::Failed)).await;
+    append_event(
+        pg,
+        run_id,
+        EventLevel::Error,
+        error,
+        json!({"event": "status", "status": "failed", "exit_code": code}),
+    )
+    .await?;
     Ok(())
 }
 
@@ -499,7 +1228,11 @@ struct DbRunAndParams {
     #[allow(dead_code)]
     id: Uuid,
     kind: String,
+    status: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    depends_on: Vec<Uuid>,
     cli_args: serde_json::Value,
+    params: Option<serde_json::Value>,
 }
 
 fn parse_run_kind(s: &str) -> Result<RunKind> {
@@ -509,6 +1242,15 @@ fn parse_run_kind(s: &str) -> Result<RunKind> {
         "backtest_mm" => Ok(RunKind::BacktestMm),
         "backtest_mm_mtf" => Ok(RunKind::BacktestMmMtf),
         "backtest_mm_mtf_sweep" => Ok(RunKind::BacktestMmMtfSweep),
+        "backtest_mm_mtf_ga" => Ok(RunKind::BacktestMmMtfGa),
+        "backtest_mm_mtf_costs" => Ok(RunKind::BacktestMmMtfCosts),
+        "backtest_mm_mtf_stress" => Ok(RunKind::BacktestMmMtfStress),
+        "backtest_mm_portfolio" => Ok(RunKind::BacktestMmPortfolio),
+        "backtest_diff" => Ok(RunKind::BacktestDiff),
+        "data_download" => Ok(RunKind::DataDownload),
+        "report_generation" => Ok(RunKind::ReportGeneration),
+        "live" => Ok(RunKind::Live),
+        "paper" => Ok(RunKind::Paper),
         _ => anyhow::bail!("unknown run kind: {}", s),
     }
 }
@@ -0,0 +1,176 @@
+//! Сообщения о завершении run'ов в Telegram и Slack: только для run'ов с `params.notify`,
+//! не больше одного раза (`runs.notified_at`), с основными метриками и ссылкой на run в веб UI.
+//! Ошибки доставки только логируются — run от них не зависит.
+
+use std::{env, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use orchestrator_core::metrics::{CoreMetrics, MetricsPayload};
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Telegram или Slack не ответили — не держать очередь
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Notifier {
+    http: reqwest::Client,
+    /// Токен бота и чат
+    telegram: Option<(String, String)>,
+    slack_webhook: Option<String>,
+    /// База веб UI: ссылка на run — `<WEB_UI_URL>/runs/<id>`
+    web_ui_url: Option<String>,
+}
+
+impl Notifier {
+    /// `None` — не задан ни Telegram (`TELEGRAM_BOT_TOKEN` + `TELEGRAM_CHAT_ID`),
+    /// ни Slack (`SLACK_WEBHOOK_URL`)
+    pub fn from_env() -> Option<Self> {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let telegram = var("TELEGRAM_BOT_TOKEN").zip(var("TELEGRAM_CHAT_ID"));
+        let slack_webhook = var("SLACK_WEBHOOK_URL");
+        if telegram.is_none() && slack_webhook.is_none() {
+            return None;
+        }
+        Some(Self {
+            http: reqwest::Client::builder()
+                .timeout(SEND_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            telegram,
+            slack_webhook,
+            web_ui_url: var("WEB_UI_URL").map(|u| u.trim_end_matches('/').to_string()),
+        })
+    }
+
+    pub fn targets(&self) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        if self.telegram.is_some() {
+            out.push("telegram");
+        }
+        if self.slack_webhook.is_some() {
+            out.push("slack");
+        }
+        out
+    }
+
+    /// После обработки записи очереди: run завершён или упал и просил уведомление —
+    /// отметить и отправить; остальные (ждут зависимостей, отменены, уже уведомлены) — мимо
+    pub async fn run_finished(&self, pg: &PgPool, run_id: Uuid) {
+        if let Err(e) = self.try_run_finished(pg, run_id).await {
+            warn!("notification for run {} failed: {}", run_id, e);
+        }
+    }
+
+    async fn try_run_finished(&self, pg: &PgPool, run_id: Uuid) -> Result<()> {
+        let row = sqlx::query_as::<_, DbFinishedRun>(
+            r#"
+            UPDATE runs r
+            SET notified_at = NOW()
+            FROM run_params p
+            WHERE r.id = $1
+                AND p.run_id = r.id
+                AND r.notified_at IS NULL
+                AND r.status IN ('completed', 'failed')
+                AND COALESCE((p.params->>'notify')::boolean, FALSE)
+            RETURNING r.name, r.kind, r.status, r.error, r.started_at, r.ended_at
+            "#,
+        )
+        .bind(run_id)
+        .fetch_optional(pg)
+        .await?;
+        let Some(run) = row else {
+            return Ok(());
+        };
+        let payload: Option<serde_json::Value> =
+            sqlx::query_scalar("SELECT payload FROM run_metrics WHERE run_id = $1")
+                .bind(run_id)
+                .fetch_optional(pg)
+                .await?;
+        let core = payload.map(|p| MetricsPayload::from_value(p).core);
+        let text = self.message(run_id, &run, core.as_ref());
+
+        if let Some((token, chat_id)) = &self.telegram {
+            let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+            let body = json!({"chat_id": chat_id, "text": text, "disable_web_page_preview": true});
+            self.send("telegram", &url, &body).await;
+        }
+        if let Some(webhook) = &self.slack_webhook {
+            self.send("slack", webhook, &json!({"text": text})).await;
+        }
+        Ok(())
+    }
+
+    /// Без URL в ошибке: в нём токен бота / секрет webhook'а
+    async fn send(&self, target: &str, url: &str, body: &serde_json::Value) {
+        let sent = self
+            .http
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = sent {
+            warn!("{} notification failed: {}", target, e.without_url());
+        }
+    }
+
+    fn message(&self, run_id: Uuid, run: &DbFinishedRun, core: Option<&CoreMetrics>) -> String {
+        let mark = if run.status == "completed" {
+            "✅"
+        } else {
+            "❌"
+        };
+        let mut lines = vec![format!(
+            "{} {} {} ({})",
+            mark, run.name, run.status, run.kind
+        )];
+        if let Some(error) = &run.error {
+            lines.push(format!("error: {}", error));
+        }
+        if let Some(core) = core {
+            let mut parts = Vec::new();
+            let mut push = |label: &str, value: Option<f64>, suffix: &str| {
+                if let Some(v) = value {
+                    parts.push(format!("{} {:.2}{}", label, v, suffix));
+                }
+            };
+            push("pnl", core.pnl, "");
+            push("roi", core.roi, "%");
+            push("max dd", core.max_drawdown, "%");
+            push("sharpe", core.sharpe, "");
+            if let Some(trades) = core.closed_trades {
+                parts.push(format!("trades {}", trades));
+            }
+            if !parts.is_empty() {
+                lines.push(parts.join(" · "));
+            }
+        }
+        if let (Some(started), Some(ended)) = (run.started_at, run.ended_at) {
+            let secs = (ended - started).num_seconds().max(0);
+            lines.push(format!(
+                "duration {}h {:02}m {:02}s",
+                secs / 3600,
+                secs % 3600 / 60,
+                secs % 60
+            ));
+        }
+        lines.push(match &self.web_ui_url {
+            Some(base) => format!("{}/runs/{}", base, run_id),
+            None => format!("run {}", run_id),
+        });
+        lines.join("\n")
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DbFinishedRun {
+    name: String,
+    kind: String,
+    status: String,
+    error: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
+}
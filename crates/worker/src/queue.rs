@@ -0,0 +1,305 @@
+//! Очередь run'ов на Redis Streams: чтение группой `RUN_STREAM_GROUP` по приоритету,
+//! подтверждение после завершения run'а, heartbeat идущего run'а и перехват записей,
+//! которые упавший worker так и не подтвердил.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use orchestrator_core::models::{
+    LEGACY_RUN_QUEUE_KEYS, RUN_STREAM_FIELD, RUN_STREAM_GROUP, RUN_STREAM_KEYS,
+};
+use redis::AsyncCommands;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{
+    StreamAutoClaimOptions, StreamAutoClaimReply, StreamClaimOptions, StreamId,
+    StreamPendingCountReply, StreamRangeReply, StreamReadOptions, StreamReadReply,
+};
+use uuid::Uuid;
+
+/// Сколько ждать новых записей за один XREADGROUP: между ожиданиями worker проверяет
+/// чужие зависшие записи
+const READ_BLOCK: Duration = Duration::from_secs(5);
+/// Как часто идущий run обновляет свою запись, чтобы её не сочли брошенной
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+/// Запись без heartbeat'а дольше — worker упал, её забирает другой
+const STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+/// Run в статусе queued дольше без записи в stream'ах потерян: api закоммитил его,
+/// но XADD не прошёл (Redis недоступен, api упал между ними)
+pub const ORPHAN_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct QueueEntry {
+    pub stream: &'static str,
+    pub id: String,
+    /// `None` — запись без id run'а (удалена или чужая): только подтвердить
+    pub run_id: Option<Uuid>,
+    /// Уже доставлялась другому (или прошлому запуску этого) worker'у и не подтверждена
+    pub recovered: bool,
+}
+
+impl QueueEntry {
+    fn new(stream: &'static str, entry: &StreamId, recovered: bool) -> Self {
+        Self {
+            stream,
+            id: entry.id.clone(),
+            run_id: entry
+                .get::<String>(RUN_STREAM_FIELD)
+                .and_then(|s| s.parse().ok()),
+            recovered,
+        }
+    }
+}
+
+fn stream_key(key: &str) -> Option<&'static str> {
+    RUN_STREAM_KEYS.into_iter().find(|k| *k == key)
+}
+
+/// Группа читает с начала stream'а: записи, добавленные до её создания, не теряются
+pub async fn ensure_groups(conn: &mut MultiplexedConnection) -> Result<()> {
+    for key in RUN_STREAM_KEYS {
+        let created: redis::RedisResult<()> = conn
+            .xgroup_create_mkstream(key, RUN_STREAM_GROUP, "0")
+            .await;
+        if let Err(e) = created {
+            if e.code() != Some("BUSYGROUP") {
+                return Err(e).with_context(|| format!("XGROUP CREATE {} failed", key));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Id из списков LPUSH/BRPOP прежней версии — в streams того же приоритета
+pub async fn migrate_legacy_lists(conn: &mut MultiplexedConnection) -> Result<usize> {
+    let mut moved = 0;
+    for (legacy, stream) in LEGACY_RUN_QUEUE_KEYS.into_iter().zip(RUN_STREAM_KEYS) {
+        while let Some(raw) = conn.rpop::<_, Option<String>>(legacy, None).await? {
+            match raw.parse::<Uuid>() {
+                Ok(run_id) => {
+                    enqueue(conn, stream, run_id).await?;
+                    moved += 1;
+                }
+                Err(e) => tracing::error!("invalid run id in {}: '{}': {}", legacy, raw, e),
+            }
+        }
+    }
+    Ok(moved)
+}
+
+pub async fn enqueue(conn: &mut MultiplexedConnection, stream: &str, run_id: Uuid) -> Result<()> {
+    conn.xadd::<_, _, _, _, String>(stream, "*", &[(RUN_STREAM_FIELD, run_id.to_string())])
+        .await
+        .with_context(|| format!("XADD {} failed", stream))?;
+    Ok(())
+}
+
+/// Свои неподтверждённые записи: worker с тем же `WORKER_ID` перезапустился посреди run'а
+pub async fn own_pending(
+    conn: &mut MultiplexedConnection,
+    consumer: &str,
+) -> Result<Vec<QueueEntry>> {
+    let opts = StreamReadOptions::default().group(RUN_STREAM_GROUP, consumer);
+    let ids = ["0"; RUN_STREAM_KEYS.len()];
+    let reply: StreamReadReply = conn.xread_options(&RUN_STREAM_KEYS, &ids, &opts).await?;
+    Ok(entries(reply, true))
+}
+
+/// Одна запись другого worker'а без heartbeat'а дольше `STALE_AFTER`, по приоритету.
+/// По одной: run'ы идут по очереди, а heartbeat есть только у идущего —
+/// взятые про запас записи снова сочли бы брошенными.
+pub async fn reclaim_stale(
+    conn: &mut MultiplexedConnection,
+    consumer: &str,
+) -> Result<Vec<QueueEntry>> {
+    let mut out = Vec::new();
+    for stream in RUN_STREAM_KEYS {
+        let reply: StreamAutoClaimReply = conn
+            .xautoclaim_options(
+                stream,
+                RUN_STREAM_GROUP,
+                consumer,
+                STALE_AFTER.as_millis() as usize,
+                "0-0",
+                StreamAutoClaimOptions::default().count(1),
+            )
+            .await?;
+        out.extend(
+            reply
+                .claimed
+                .iter()
+                .map(|e| QueueEntry::new(stream, e, true)),
+        );
+        if !out.is_empty() {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// Новые записи: без ожидания по приоритету, а если пусто — ждать во всех сразу
+/// (тогда может прийти по записи из нескольких stream'ов — они идут в порядке приоритета)
+pub async fn read_new(conn: &mut MultiplexedConnection, consumer: &str) -> Result<Vec<QueueEntry>> {
+    let opts = StreamReadOptions::default()
+        .group(RUN_STREAM_GROUP, consumer)
+        .count(1);
+    for stream in RUN_STREAM_KEYS {
+        let reply: StreamReadReply = conn.xread_options(&[stream], &[">"], &opts).await?;
+        let found = entries(reply, false);
+        if !found.is_empty() {
+            return Ok(found);
+        }
+    }
+
+    let opts = opts.block(READ_BLOCK.as_millis() as usize);
+    let ids = [">"; RUN_STREAM_KEYS.len()];
+    let reply: Option<StreamReadReply> = conn.xread_options(&RUN_STREAM_KEYS, &ids, &opts).await?;
+    Ok(reply.map(|r| entries(r, false)).unwrap_or_default())
+}
+
+/// Запись всё ещё за `consumer`: пока она ждала в локальной очереди без heartbeat'а,
+/// её мог перехватить другой worker (тогда run уже у него)
+pub async fn still_owned(
+    conn: &mut MultiplexedConnection,
+    entry: &QueueEntry,
+    consumer: &str,
+) -> Result<bool> {
+    let reply: StreamPendingCountReply = conn
+        .xpending_count(entry.stream, RUN_STREAM_GROUP, &entry.id, &entry.id, 1)
+        .await?;
+    Ok(reply
+        .ids
+        .iter()
+        .any(|p| p.id == entry.id && p.consumer == consumer))
+}
+
+/// Id run'ов во всех stream'ах: ждущие и взятые, но не подтверждённые
+/// (подтверждённые записи удаляются)
+pub async fn run_ids_in_streams(conn: &mut MultiplexedConnection) -> Result<HashSet<Uuid>> {
+    let mut ids = HashSet::new();
+    for stream in RUN_STREAM_KEYS {
+        let reply: StreamRangeReply = conn
+            .xrange_all(stream)
+            .await
+            .with_context(|| format!("XRANGE {} failed", stream))?;
+        ids.extend(
+            reply
+                .ids
+                .iter()
+                .filter_map(|e| QueueEntry::new(stream, e, false).run_id),
+        );
+    }
+    Ok(ids)
+}
+
+/// Queued run'ы без записи ни в одном stream'е — их надо поставить заново
+pub fn orphans<T: Clone>(queued: &[(Uuid, T)], in_streams: &HashSet<Uuid>) -> Vec<(Uuid, T)> {
+    queued
+        .iter()
+        .filter(|(id, _)| !in_streams.contains(id))
+        .cloned()
+        .collect()
+}
+
+/// Сбрасывает время простоя записи идущего run'а
+pub async fn heartbeat(
+    conn: &mut MultiplexedConnection,
+    entry: &QueueEntry,
+    consumer: &str,
+) -> Result<()> {
+    let _: redis::Value = conn
+        .xclaim_options(
+            entry.stream,
+            RUN_STREAM_GROUP,
+            consumer,
+            0,
+            &[&entry.id],
+            StreamClaimOptions::default().with_justid(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Подтверждает и удаляет запись: длина stream'а — только ждущие и взятые run'ы
+pub async fn ack(conn: &mut MultiplexedConnection, entry: &QueueEntry) -> Result<()> {
+    conn.xack::<_, _, _, usize>(entry.stream, RUN_STREAM_GROUP, &[&entry.id])
+        .await?;
+    conn.xdel::<_, _, usize>(entry.stream, &[&entry.id]).await?;
+    Ok(())
+}
+
+fn entries(reply: StreamReadReply, recovered: bool) -> Vec<QueueEntry> {
+    let mut out: Vec<QueueEntry> = reply
+        .keys
+        .iter()
+        .filter_map(|k| Some((stream_key(&k.key)?, &k.ids)))
+        .flat_map(|(stream, ids)| {
+            ids.iter()
+                .map(move |e| QueueEntry::new(stream, e, recovered))
+        })
+        .collect();
+    out.sort_by_key(|e| RUN_STREAM_KEYS.iter().position(|k| *k == e.stream));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use redis::streams::StreamKey;
+
+    use super::*;
+
+    fn stream_entry(id: &str, run_id: &str) -> StreamId {
+        StreamId {
+            id: id.to_string(),
+            map: HashMap::from([(
+                RUN_STREAM_FIELD.to_string(),
+                redis::Value::BulkString(run_id.as_bytes().to_vec()),
+            )]),
+        }
+    }
+
+    #[test]
+    fn entry_without_valid_run_id_is_kept_for_ack() {
+        let run_id = Uuid::new_v4();
+        let ok = QueueEntry::new(
+            RUN_STREAM_KEYS[0],
+            &stream_entry("1-0", &run_id.to_string()),
+            false,
+        );
+        assert_eq!(ok.run_id, Some(run_id));
+        let bad = QueueEntry::new(RUN_STREAM_KEYS[0], &stream_entry("2-0", "not-a-uuid"), true);
+        assert_eq!(bad.run_id, None);
+        assert!(bad.recovered);
+        assert_eq!(bad.id, "2-0");
+    }
+
+    #[test]
+    fn entries_are_ordered_by_priority_and_unknown_streams_dropped() {
+        let key = |key: &str, id: &str| StreamKey {
+            key: key.to_string(),
+            ids: vec![stream_entry(id, &Uuid::new_v4().to_string())],
+        };
+        let reply = StreamReadReply {
+            keys: vec![
+                key(RUN_STREAM_KEYS[2], "3-0"),
+                key("other:stream", "9-0"),
+                key(RUN_STREAM_KEYS[0], "1-0"),
+                key(RUN_STREAM_KEYS[1], "2-0"),
+            ],
+        };
+        let got = entries(reply, false);
+        let streams: Vec<_> = got.iter().map(|e| e.stream).collect();
+        assert_eq!(streams, RUN_STREAM_KEYS.to_vec());
+    }
+
+    #[test]
+    fn queued_run_missing_from_streams_is_orphan() {
+        let (in_stream, lost) = (Uuid::new_v4(), Uuid::new_v4());
+        let queued = [(in_stream, "backtest_mm"), (lost, "live")];
+        let found = HashSet::from([in_stream]);
+        assert_eq!(orphans(&queued, &found), vec![(lost, "live")]);
+        assert!(orphans(&queued, &HashSet::from([in_stream, lost])).is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS engine_heartbeats (
+    run_id UUID PRIMARY KEY REFERENCES runs(id) ON DELETE CASCADE,
+    ts TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    state TEXT NOT NULL,
+    last_candle_ts BIGINT NULL,
+    equity DOUBLE PRECISION NULL,
+    payload JSONB NOT NULL
+);
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS candles (
+    symbol TEXT NOT NULL,
+    interval_min INTEGER NOT NULL,
+    ts BIGINT NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    volume DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (symbol, interval_min, ts)
+);
+
+-- скачанные с биржи диапазоны: пропуски свечей внутри них — пропуски биржи
+CREATE TABLE IF NOT EXISTS candle_ranges (
+    id BIGSERIAL PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    interval_min INTEGER NOT NULL,
+    start_ms BIGINT NOT NULL,
+    end_ms BIGINT NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_candle_ranges_symbol_interval ON candle_ranges(symbol, interval_min);
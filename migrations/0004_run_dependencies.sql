@@ -0,0 +1,2 @@
+-- run стартует только после успешного завершения этих run'ов
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS depends_on UUID[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+-- типизированные параметры run'а (orchestrator_core::params); worker превращает их в флаги бинаря
+ALTER TABLE run_params ADD COLUMN IF NOT EXISTS params JSONB;
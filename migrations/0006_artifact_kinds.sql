@@ -0,0 +1,25 @@
+-- kind — тип артефакта (orchestrator_core::models::ArtifactKind), name — имя из строки
+-- `artifacts:` бинаря; метаданные файла пишет worker
+ALTER TABLE run_artifacts ADD COLUMN IF NOT EXISTS name TEXT;
+ALTER TABLE run_artifacts ADD COLUMN IF NOT EXISTS size_bytes BIGINT;
+ALTER TABLE run_artifacts ADD COLUMN IF NOT EXISTS row_count BIGINT;
+ALTER TABLE run_artifacts ADD COLUMN IF NOT EXISTS checksum TEXT;
+
+UPDATE run_artifacts
+SET name = kind,
+    kind = CASE
+        WHEN kind LIKE '%capture%' THEN 'capture'
+        WHEN kind = 'log' OR kind LIKE '%\_log' THEN 'log'
+        WHEN kind LIKE 'report\_%' OR kind = 'config_json' THEN 'report'
+        WHEN kind LIKE '%diff%' THEN 'table'
+        WHEN kind LIKE '%equity%' THEN 'equity_csv'
+        WHEN kind LIKE '%fills%' OR kind LIKE '%trades%' OR kind LIKE '%round\_trips%' THEN 'fills_csv'
+        WHEN kind LIKE '%summary%' OR kind LIKE '%results%' OR kind LIKE '%per\_symbol%'
+            OR kind LIKE '%stability%' OR kind LIKE '%generations%' OR kind LIKE '%table%'
+            OR kind LIKE '%breakeven%' THEN 'sweep_summary'
+        WHEN kind LIKE '%\_csv' THEN 'table'
+        ELSE 'other'
+    END
+WHERE name IS NULL;
+
+ALTER TABLE run_artifacts ALTER COLUMN name SET NOT NULL;
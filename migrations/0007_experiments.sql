@@ -0,0 +1,10 @@
+-- группа run'ов одного исследования: walk-forward, мультисимвольный sweep, ...
+CREATE TABLE IF NOT EXISTS experiments (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS experiment_id UUID REFERENCES experiments(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_runs_experiment_id ON runs(experiment_id);
@@ -0,0 +1,10 @@
+-- уровень события — из фиксированного набора, разобранные поля строки — в JSONB
+ALTER TABLE run_events ADD COLUMN IF NOT EXISTS fields JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+UPDATE run_events SET level = 'warn' WHERE level = 'warning';
+UPDATE run_events SET level = 'info' WHERE level NOT IN ('debug', 'info', 'warn', 'error');
+
+ALTER TABLE run_events
+    ADD CONSTRAINT run_events_level_check CHECK (level IN ('debug', 'info', 'warn', 'error'));
+
+CREATE INDEX IF NOT EXISTS idx_run_events_fields ON run_events USING GIN (fields);
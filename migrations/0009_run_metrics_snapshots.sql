@@ -0,0 +1,10 @@
+-- история payload'ов run_metrics: run_metrics хранит последний, здесь — все по времени
+CREATE TABLE IF NOT EXISTS run_metrics_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    run_id UUID NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    ts TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    is_final BOOLEAN NOT NULL DEFAULT FALSE,
+    payload JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_metrics_snapshots_run_id_id ON run_metrics_snapshots(run_id, id);
@@ -0,0 +1,14 @@
+-- изменяющие вызовы API: кто, когда, что прислал и чем закончилось
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    ts TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    actor TEXT,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    route TEXT,
+    status INTEGER NOT NULL,
+    body JSONB
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_ts ON audit_log(ts DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor_ts ON audit_log(actor, ts DESC);
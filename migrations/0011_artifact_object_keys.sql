@@ -0,0 +1,2 @@
+-- ключ объекта в S3/MinIO; NULL — артефакт только на диске worker'а
+ALTER TABLE run_artifacts ADD COLUMN IF NOT EXISTS object_key TEXT;
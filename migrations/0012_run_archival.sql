@@ -0,0 +1,4 @@
+-- архивация старых run'ов: события и артефакты сжаты в архив, горячие таблицы не растут
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_runs_archive_candidates ON runs(ended_at) WHERE archived_at IS NULL;
@@ -0,0 +1,3 @@
+-- уведомление о завершении run'а (`params.notify`) уходит один раз, даже если запись очереди
+-- обработана повторно
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS notified_at TIMESTAMPTZ;
@@ -0,0 +1,25 @@
+-- проекты делят одну установку orchestrator'а: run'ы, эксперименты и ключи API привязаны
+-- к проекту; всё созданное до проектов — в 'default'
+CREATE TABLE IF NOT EXISTS projects (
+    name TEXT PRIMARY KEY,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO projects (name, description) VALUES ('default', NULL) ON CONFLICT DO NOTHING;
+
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS project TEXT NOT NULL DEFAULT 'default' REFERENCES projects(name);
+CREATE INDEX IF NOT EXISTS idx_runs_project_created_at ON runs(project, created_at DESC);
+
+ALTER TABLE experiments ADD COLUMN IF NOT EXISTS project TEXT NOT NULL DEFAULT 'default' REFERENCES projects(name);
+CREATE INDEX IF NOT EXISTS idx_experiments_project_created_at ON experiments(project, created_at DESC);
+
+-- ключ хранится как sha256 (hex); project NULL — ключ администратора, видит все проекты
+CREATE TABLE IF NOT EXISTS api_keys (
+    id BIGSERIAL PRIMARY KEY,
+    key_hash TEXT NOT NULL UNIQUE,
+    project TEXT REFERENCES projects(name),
+    label TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
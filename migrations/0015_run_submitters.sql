@@ -0,0 +1,5 @@
+-- кто поставил run (`key:<hex>` как в audit_log, NULL — без ключа): лимиты очереди на ключ
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS submitted_by TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_runs_queued_kind ON runs(kind) WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS idx_runs_queued_submitted_by ON runs(submitted_by) WHERE status = 'queued';
@@ -0,0 +1,10 @@
+-- пропуски, которых нет и у биржи: повторная докачка их не закрыла
+CREATE TABLE IF NOT EXISTS candle_gaps (
+    id BIGSERIAL PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    interval_min INTEGER NOT NULL,
+    start_ms BIGINT NOT NULL,
+    end_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_candle_gaps_symbol_interval ON candle_gaps(symbol, interval_min);